@@ -3,13 +3,394 @@
 //! Refer to the `examples` in the `ssz_rs` crate for a better idea on how to use this derive macro.
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote, quote_spanned};
-use syn::{parse_macro_input, spanned::Spanned, Data, DeriveInput, Fields, Generics, Ident};
+use syn::{
+    parenthesized, parse::ParseStream, parse_macro_input, spanned::Spanned, Attribute, Data,
+    DeriveInput, Expr, Field, Fields, FieldsNamed, GenericArgument, Generics, Ident, PathArguments,
+    Token, Type,
+};
 
 // NOTE: copied here from `ssz_rs` crate as it is unlikely to change
 // and can keep it out of the crate's public interface.
 const BYTES_PER_LENGTH_OFFSET: usize = 4;
 const BYTES_PER_CHUNK: usize = 32;
 
+// The SSZ type used to represent a field of a container.
+enum FieldKind<'a> {
+    // the type of the field itself
+    Native(&'a Type),
+    // a map encoded as a list of its entries with `#[ssz(as_list(max = N))]`
+    MapAsList(&'a Type, Expr),
+}
+
+impl<'a> From<&'a Field> for FieldKind<'a> {
+    fn from(field: &'a Field) -> Self {
+        Self::parse(field).expect("field attributes were already validated")
+    }
+}
+
+impl<'a> FieldKind<'a> {
+    // Parse the kind of `field` from its `#[ssz(as_list(max = N))]` attribute, if any.
+    fn parse(field: &'a Field) -> Result<Self, syn::Error> {
+        let Some(attr) = field.attrs.iter().find(|attr| attr.path.is_ident("ssz")) else {
+            return Ok(Self::Native(&field.ty))
+        };
+        let bound = attr.parse_args_with(|input: ParseStream| {
+            let kind: Ident = input.parse()?;
+            if kind != "as_list" {
+                return Err(syn::Error::new(kind.span(), "expected `as_list(max = N)`"))
+            }
+            let content;
+            parenthesized!(content in input);
+            let key: Ident = content.parse()?;
+            if key != "max" {
+                return Err(syn::Error::new(key.span(), "expected `max = N`"))
+            }
+            content.parse::<Token![=]>()?;
+            content.parse::<Expr>()
+        })?;
+        if parse_map_entry_types(&field.ty).is_none() {
+            return Err(syn::Error::new(
+                field.ty.span(),
+                "fields with `#[ssz(as_list(..))]` must have a map type like `BTreeMap<K, V>`",
+            ))
+        }
+        Ok(Self::MapAsList(&field.ty, bound))
+    }
+
+    fn is_variable_size(&self) -> TokenStream {
+        match self {
+            Self::Native(ty) => quote! { <#ty>::is_variable_size() },
+            Self::MapAsList(..) => quote! { true },
+        }
+    }
+
+    // the constant counterpart of `is_variable_size`
+    fn is_variable_size_const(&self) -> TokenStream {
+        match self {
+            Self::Native(ty) => quote! { <#ty as ssz_rs::SimpleSerialize>::IS_VARIABLE_SIZE },
+            Self::MapAsList(..) => quote! { true },
+        }
+    }
+
+    fn size_hint(&self) -> TokenStream {
+        match self {
+            Self::Native(ty) => quote! { <#ty>::size_hint() },
+            Self::MapAsList(..) => quote! { 0 },
+        }
+    }
+
+    fn serialize(&self, value: TokenStream, buffer: TokenStream) -> TokenStream {
+        match self {
+            Self::Native(..) => quote! { #value.serialize(#buffer) },
+            Self::MapAsList(_, bound) => {
+                quote! { ssz_rs::map::serialize::<_, _, { #bound }>(&#value, #buffer) }
+            }
+        }
+    }
+
+    fn deserialize(&self, encoding: TokenStream) -> TokenStream {
+        match self {
+            Self::Native(ty) => quote! { <#ty>::deserialize(#encoding) },
+            Self::MapAsList(_, bound) => {
+                quote! { ssz_rs::map::deserialize::<_, _, { #bound }>(#encoding) }
+            }
+        }
+    }
+
+    fn nesting_depth(&self) -> TokenStream {
+        match self {
+            Self::Native(ty) => quote! { <#ty as ssz_rs::SimpleSerialize>::nesting_depth() },
+            Self::MapAsList(ty, _) => {
+                let (key, value) = map_entry_types(ty);
+                quote! { ssz_rs::map::nesting_depth::<#key, #value>() }
+            }
+        }
+    }
+
+    // the constant counterpart of `nesting_depth`
+    fn nesting_depth_const(&self) -> TokenStream {
+        match self {
+            Self::Native(ty) => quote! { <#ty as ssz_rs::SimpleSerialize>::NESTING_DEPTH },
+            Self::MapAsList(ty, _) => {
+                let (key, value) = map_entry_types(ty);
+                quote! { ssz_rs::map::nesting_depth::<#key, #value>() }
+            }
+        }
+    }
+
+    // the maximum length of the field in the encoding of its container, including the offset
+    // to a variable-size field
+    fn max_size(&self) -> TokenStream {
+        match self {
+            Self::Native(ty) => quote! { ssz_rs::__internal::field_max_size::<#ty>() },
+            Self::MapAsList(ty, bound) => {
+                let (key, value) = map_entry_types(ty);
+                quote! {
+                    #BYTES_PER_LENGTH_OFFSET
+                        .saturating_add(ssz_rs::map::max_size::<#key, #value, { #bound }>())
+                }
+            }
+        }
+    }
+
+    fn generalized_index_from(&self, root: TokenStream, path: TokenStream) -> TokenStream {
+        match self {
+            Self::Native(ty) => quote! {
+                <#ty as ssz_rs::SimpleSerialize>::generalized_index_from(#root, #path)
+            },
+            Self::MapAsList(ty, bound) => {
+                let (key, value) = map_entry_types(ty);
+                quote! {
+                    ssz_rs::map::generalized_index_from::<#key, #value, { #bound }>(#root, #path)
+                }
+            }
+        }
+    }
+
+    fn heap_size(&self, value: TokenStream) -> TokenStream {
+        match self {
+            Self::Native(ty) => quote! { <#ty as ssz_rs::SimpleSerialize>::ssz_heap_size(&#value) },
+            Self::MapAsList(..) => quote! { ssz_rs::map::heap_size(&#value) },
+        }
+    }
+
+    fn hash_tree_root_from_bytes(&self, encoding: TokenStream) -> TokenStream {
+        match self {
+            Self::Native(ty) => quote! {
+                <#ty as ssz_rs::SimpleSerialize>::hash_tree_root_from_bytes(#encoding, context)
+            },
+            Self::MapAsList(ty, bound) => {
+                let (key, value) = map_entry_types(ty);
+                quote! {
+                    ssz_rs::map::hash_tree_root_from_bytes::<#key, #value, { #bound }, _>(
+                        #encoding,
+                        context,
+                    )
+                }
+            }
+        }
+    }
+
+    fn hash_tree_root(&self, value: TokenStream) -> TokenStream {
+        match self {
+            // NOTE: a default field takes the default root of its type rather than being hashed
+            Self::Native(ty) => quote! {
+                if <#ty as ssz_rs::SimpleSerialize>::is_default(&#value) {
+                    <#ty as ssz_rs::SimpleSerialize>::default_hash_tree_root_with(context)
+                } else {
+                    #value.hash_tree_root_with(context)
+                }
+            },
+            Self::MapAsList(_, bound) => quote! {
+                ssz_rs::map::hash_tree_root_with::<_, _, { #bound }, _>(&#value, context)
+            },
+        }
+    }
+
+    fn is_default(&self, value: TokenStream) -> TokenStream {
+        match self {
+            Self::Native(ty) => quote! { <#ty as ssz_rs::SimpleSerialize>::is_default(&#value) },
+            Self::MapAsList(..) => quote! { #value.is_empty() },
+        }
+    }
+
+    fn default_hash_tree_root(&self) -> TokenStream {
+        match self {
+            Self::Native(ty) => quote! {
+                <#ty as ssz_rs::SimpleSerialize>::default_hash_tree_root_with(context)
+            },
+            Self::MapAsList(ty, bound) => quote! {
+                ssz_rs::map::hash_tree_root_with::<_, _, { #bound }, _>(&<#ty>::default(), context)
+            },
+        }
+    }
+}
+
+// Return the key and value types of a map type like `BTreeMap<K, V>`, if `ty` is one.
+fn parse_map_entry_types(ty: &Type) -> Option<(&Type, &Type)> {
+    let Type::Path(path) = ty else { return None };
+    let Some(PathArguments::AngleBracketed(arguments)) =
+        path.path.segments.last().map(|segment| &segment.arguments)
+    else {
+        return None
+    };
+    let mut types = arguments.args.iter().filter_map(|arg| match arg {
+        GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    });
+    match (types.next(), types.next(), types.next()) {
+        (Some(key), Some(value), None) => Some((key, value)),
+        _ => None,
+    }
+}
+
+// Return the key and value types of the map type of a field validated by `FieldKind::parse`.
+fn map_entry_types(ty: &Type) -> (&Type, &Type) {
+    parse_map_entry_types(ty).expect("map types were already validated")
+}
+
+// The options of a container given by its attributes
+// `#[ssz(max_depth = N, builder, default_root)]`.
+#[derive(Default)]
+struct ContainerOptions {
+    // the maximum nesting depth of the container
+    max_depth: Option<Expr>,
+    // generate a builder of the container
+    builder: bool,
+    // implement `DefaultRoot` for the container
+    default_root: bool,
+}
+
+// Parse the options of a container from its `ssz` attributes.
+fn parse_container_options(attrs: &[Attribute]) -> Result<ContainerOptions, syn::Error> {
+    let mut options = ContainerOptions::default();
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("ssz")) {
+        attr.parse_args_with(|input: ParseStream| loop {
+            let key: Ident = input.parse()?;
+            if key == "max_depth" {
+                input.parse::<Token![=]>()?;
+                options.max_depth = Some(input.parse::<Expr>()?);
+            } else if key == "builder" {
+                options.builder = true;
+            } else if key == "default_root" {
+                options.default_root = true;
+            } else {
+                return Err(syn::Error::new(
+                    key.span(),
+                    "expected `max_depth = N`, `builder` or `default_root`",
+                ))
+            }
+            if input.is_empty() {
+                return Ok(())
+            }
+            input.parse::<Token![,]>()?;
+        })?;
+    }
+    Ok(options)
+}
+
+// Generate `#builder_name` with a setter for each field of the struct `name` and a `build`
+// method which checks that every field was given a valid value. Each field keeps the result of
+// its last setter call, so a valid value replaces an invalid one.
+fn derive_builder(input: &DeriveInput) -> Result<TokenStream, syn::Error> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new(
+                    input.ident.span(),
+                    "`#[ssz(builder)]` is only supported for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                "`#[ssz(builder)]` is only supported for structs with named fields",
+            ))
+        }
+    };
+    let name = &input.ident;
+    let vis = &input.vis;
+    let builder_name = format_ident!("{}Builder", name);
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let idents =
+        fields.iter().map(|f| f.ident.as_ref().expect("field is named")).collect::<Vec<_>>();
+    let types = fields.iter().map(|f| &f.ty).collect::<Vec<_>>();
+    let setters = fields.iter().map(|f| {
+        let ident = f.ident.as_ref().expect("field is named");
+        let ty = &f.ty;
+        let doc = format!(" Set the `{ident}` of the container to `value` converted into its type.");
+        quote_spanned! { f.span() =>
+            #[doc = #doc]
+            pub fn #ident<__SszValue: ::core::convert::TryInto<#ty>>(mut self, value: __SszValue) -> Self {
+                self.#ident = Some(value.try_into().map_err(|_| ()));
+                self
+            }
+        }
+    });
+    let doc = format!(" A builder of [`{name}`] setting one field at a time.");
+    Ok(quote! {
+        #[doc = #doc]
+        #vis struct #builder_name #impl_generics #where_clause {
+            #(#idents: Option<Result<#types, ()>>,)*
+        }
+
+        impl #impl_generics ::core::default::Default for #builder_name #ty_generics #where_clause {
+            fn default() -> Self {
+                Self { #(#idents: None,)* }
+            }
+        }
+
+        impl #impl_generics #builder_name #ty_generics #where_clause {
+            #(#setters)*
+
+            /// Return the container with the values given to the setters, or an error naming the
+            /// first field with no value or with a value which could not be converted.
+            pub fn build(self) -> Result<#name #ty_generics, ssz_rs::BuildError> {
+                Ok(#name {
+                    #(#idents: match self.#idents {
+                        Some(Ok(value)) => value,
+                        Some(Err(())) => return Err(ssz_rs::BuildError::Invalid(stringify!(#idents))),
+                        None => return Err(ssz_rs::BuildError::Missing(stringify!(#idents))),
+                    },)*
+                })
+            }
+        }
+
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Return a builder of the container with no fields set.
+            pub fn builder() -> #builder_name #ty_generics {
+                ::core::default::Default::default()
+            }
+        }
+    })
+}
+
+// Implement `DefaultRoot` for the struct of `input` by merkleizing the default roots of its
+// fields at compile time.
+fn derive_default_root(input: &DeriveInput) -> Result<TokenStream, syn::Error> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            Fields::Unnamed(fields) => &fields.unnamed,
+            Fields::Unit => unreachable!("data was already validated to exclude unit structs"),
+        },
+        _ => {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                "`#[ssz(default_root)]` is only supported for structs",
+            ))
+        }
+    };
+    let types = fields
+        .iter()
+        .map(|f| match FieldKind::from(f) {
+            FieldKind::Native(ty) => Ok(ty),
+            FieldKind::MapAsList(..) => Err(syn::Error::new(
+                f.span(),
+                "`#[ssz(default_root)]` is not supported with `as_list` fields",
+            )),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let mut where_clause = where_clause.cloned().unwrap_or_else(|| syn::WhereClause {
+        where_token: Default::default(),
+        predicates: Default::default(),
+    });
+    for ty in &types {
+        where_clause.predicates.push(syn::parse_quote! { #ty: ssz_rs::DefaultRoot });
+    }
+    Ok(quote! {
+        impl #impl_generics ssz_rs::DefaultRoot for #name #ty_generics #where_clause {
+            const DEFAULT_ROOT: ssz_rs::Node = ssz_rs::__internal::merkleize_roots([
+                #(<#types as ssz_rs::DefaultRoot>::DEFAULT_ROOT,)*
+            ]);
+        }
+    })
+}
+
 fn derive_container_set_by_index_impl(
     name: &Ident,
     data: &Data,
@@ -29,17 +410,17 @@ fn derive_container_set_by_index_impl(
             };
 
             let set_by_field = fields.iter().enumerate().map(|(i, f)| {
-                let field_type = &f.ty;
+                let deserialize = FieldKind::from(f).deserialize(quote! { encoding });
                 match &f.ident {
                     Some(field_name) => quote_spanned! { f.span() =>
                         #i => {
-                            let result = <#field_type>::deserialize(encoding)?;
+                            let result = #deserialize?;
                             self.#field_name = result;
                         },
                     },
                     None => quote_spanned! { f.span() =>
                         #i => {
-                            let result = <#field_type>::deserialize(encoding)?;
+                            let result = #deserialize?;
                             self.0 = result;
                         },
                     },
@@ -77,10 +458,12 @@ fn derive_serialize_impl(data: &Data) -> TokenStream {
                 Fields::Named(ref fields) => &fields.named,
                 // "tuple" struct
                 // only support the case with one unnamed field, to support "newtype" pattern
-                Fields::Unnamed(..) => {
+                Fields::Unnamed(ref fields) => {
+                    let serialize = FieldKind::from(&fields.unnamed[0])
+                        .serialize(quote! { self.0 }, quote! { buffer });
                     return quote! {
                         fn serialize(&self, buffer: &mut Vec<u8>) -> Result<usize, ssz_rs::SerializeError> {
-                                self.0.serialize(buffer)
+                                #serialize
                         }
                     }
                 }
@@ -89,38 +472,24 @@ fn derive_serialize_impl(data: &Data) -> TokenStream {
                 ),
             };
             let serialization_by_field = fields.iter().map(|f| {
-                let field_type = &f.ty;
-                match &f.ident {
-                    Some(field_name) => quote_spanned! { f.span() =>
-                        let mut element_buffer = Vec::with_capacity(<#field_type>::size_hint());
-                        self.#field_name.serialize(&mut element_buffer)?;
-
-                        let buffer_len = element_buffer.len();
-                        if <#field_type>::is_variable_size() {
-                            fixed.push(None);
-                            fixed_lengths_sum += #BYTES_PER_LENGTH_OFFSET;
-                            variable.push(element_buffer);
-                            variable_lengths.push(buffer_len);
-                        } else {
-                            fixed.push(Some(element_buffer));
-                            fixed_lengths_sum += buffer_len;
-                            variable_lengths.push(0)
-                        }
-                    },
-                    None => panic!("should have already returned an impl"),
+                let field_kind = FieldKind::from(f);
+                let size_hint = field_kind.size_hint();
+                let is_variable_size = field_kind.is_variable_size();
+                let field_name = f.ident.as_ref().expect("should have already returned an impl");
+                let serialize =
+                    field_kind.serialize(quote! { self.#field_name }, quote! { &mut encoding });
+                quote_spanned! { f.span() =>
+                    let mut encoding = Vec::with_capacity(#size_hint);
+                    #serialize?;
+                    parts.push(#is_variable_size, encoding);
                 }
             });
 
             quote! {
                 fn serialize(&self, buffer: &mut Vec<u8>) -> Result<usize, ssz_rs::SerializeError> {
-                    let mut fixed = vec![];
-                    let mut variable = vec![];
-                    let mut variable_lengths = vec![];
-                    let mut fixed_lengths_sum = 0;
-
+                    let mut parts = ssz_rs::__internal::CompositeParts::default();
                     #(#serialization_by_field)*
-
-                    ssz_rs::__internal::serialize_composite_from_components(fixed, variable, variable_lengths, fixed_lengths_sum, buffer)
+                    parts.serialize(buffer)
                 }
             }
         }
@@ -161,21 +530,22 @@ fn derive_serialize_impl(data: &Data) -> TokenStream {
     }
 }
 
-fn derive_deserialize_impl(data: &Data) -> TokenStream {
+fn derive_deserialize_impl(data: &Data, check_depth: &TokenStream) -> TokenStream {
     match data {
         Data::Struct(ref data) => {
-            let fields = match data.fields {
+            let layout = match data.fields {
                 // "regular" struct with 1+ fields
-                Fields::Named(ref fields) => &fields.named,
+                Fields::Named(ref fields) => field_layout(fields),
                 // "tuple" struct
                 // only support the case with one unnamed field, to support "newtype" pattern
                 Fields::Unnamed(ref fields) => {
-                    let f = &fields.unnamed[0];
-                    let field_type = &f.ty;
+                    let deserialize =
+                        FieldKind::from(&fields.unnamed[0]).deserialize(quote! { &encoding });
                     return quote! {
                         fn deserialize(encoding: &[u8]) -> Result<Self, ssz_rs::DeserializeError> {
+                            #check_depth
                             let mut container = Self::default();
-                            let result = <#field_type>::deserialize(&encoding)?;
+                            let result = #deserialize?;
                             container.0 = result;
 
                             Ok(container)
@@ -186,65 +556,16 @@ fn derive_deserialize_impl(data: &Data) -> TokenStream {
                     "this type of struct is currently not supported by this derive macro"
                 ),
             };
-            let deserialization_by_field = fields.iter().enumerate().map(|(i, f)| {
-                let field_type = &f.ty;
-                match &f.ident {
-                    Some(field_name) => quote_spanned! { f.span() =>
-                        let bytes_read = if <#field_type>::is_variable_size() {
-                            let end = start + #BYTES_PER_LENGTH_OFFSET;
-                            let next_offset = u32::deserialize(&encoding[start..end])?;
-                            offsets.push((#i, next_offset as usize));
-
-                            #BYTES_PER_LENGTH_OFFSET
-                        } else {
-                            let encoded_length = <#field_type>::size_hint();
-                            let end = start + encoded_length;
-                            let result = <#field_type>::deserialize(&encoding[start..end])?;
-                            container.#field_name = result;
-                            encoded_length
-                        };
-                        start += bytes_read;
-                    },
-                    None => panic!("should have already returned an impl"),
-                }
-            });
-
+            // NOTE: `field_ranges` checks the offsets so untrusted input can not index out of
+            // bounds
             quote! {
                 fn deserialize(encoding: &[u8]) -> Result<Self, ssz_rs::DeserializeError> {
-                    let mut start = 0;
-                    let mut offsets = vec![];
+                    #check_depth
+                    #layout
                     let mut container = Self::default();
-
-                    #(#deserialization_by_field)*
-
-                    let mut total_bytes_read = start;
-
-                    // NOTE: this value is not read
-                    let dummy_index = 0;
-                    offsets.push((dummy_index, encoding.len()));
-
-                    for span in offsets.windows(2) {
-                        let (index, start) = span[0];
-                        let (_, end) = span[1];
-
-                        container.__ssz_rs_set_by_index(index, &encoding[start..end])?;
-                        total_bytes_read += end - start;
+                    for (index, range) in ranges.iter().enumerate() {
+                        container.__ssz_rs_set_by_index(index, &encoding[range.clone()])?;
                     }
-
-                    if total_bytes_read > encoding.len() {
-                        return Err(ssz_rs::DeserializeError::ExpectedFurtherInput {
-                            provided: encoding.len(),
-                            expected: total_bytes_read,
-                        });
-                    }
-
-                    if total_bytes_read < encoding.len() {
-                        return Err(ssz_rs::DeserializeError::AdditionalInput {
-                            provided: encoding.len(),
-                            expected: total_bytes_read,
-                        });
-                    }
-
                     Ok(container)
                 }
             }
@@ -266,8 +587,15 @@ fn derive_deserialize_impl(data: &Data) -> TokenStream {
                             }
                         }
                         Fields::Unit => {
+                            // NOTE: the `None` variant has an empty body
                             quote_spanned! { variant.span() =>
-                                0 => Ok(Self::None),
+                                0 => match encoding.len() {
+                                    1 => Ok(Self::None),
+                                    provided => Err(ssz_rs::DeserializeError::AdditionalInput {
+                                        provided,
+                                        expected: 1,
+                                    }),
+                                },
                             }
                         }
                         _ => unreachable!(),
@@ -276,6 +604,7 @@ fn derive_deserialize_impl(data: &Data) -> TokenStream {
 
             quote! {
                 fn deserialize(encoding: &[u8]) -> Result<Self, ssz_rs::DeserializeError> {
+                    #check_depth
                     if encoding.is_empty() {
                         return Err(ssz_rs::DeserializeError::ExpectedFurtherInput {
                             provided: 0,
@@ -305,9 +634,9 @@ fn derive_variable_size_impl(data: &Data) -> TokenStream {
                 ),
             };
             let impl_by_field = fields.iter().map(|f| {
-                let field_type = &f.ty;
+                let is_variable_size = FieldKind::from(f).is_variable_size();
                 quote_spanned! { f.span() =>
-                    <#field_type>::is_variable_size()
+                    #is_variable_size
                 }
             });
 
@@ -333,9 +662,9 @@ fn derive_size_hint_impl(data: &Data) -> TokenStream {
                 ),
             };
             let impl_by_field = fields.iter().map(|f| {
-                let field_type = &f.ty;
+                let size_hint = FieldKind::from(f).size_hint();
                 quote_spanned! { f.span() =>
-                    <#field_type>::size_hint()
+                    #size_hint
                 }
             });
 
@@ -366,23 +695,46 @@ fn derive_merkleization_impl(data: &Data) -> TokenStream {
             };
             let field_count = fields.iter().len();
             let impl_by_field = fields.iter().enumerate().map(|(i, f)| match &f.ident {
-                Some(field_name) => quote_spanned! { f.span() =>
-                    let chunk = self.#field_name.hash_tree_root()?;
+                Some(field_name) => {
+                    let hash_tree_root =
+                        FieldKind::from(f).hash_tree_root(quote! { self.#field_name });
+                    quote_spanned! { f.span() =>
+                    let chunk = #hash_tree_root?;
                     let range = #i*#BYTES_PER_CHUNK..(#i+1)*#BYTES_PER_CHUNK;
                     chunks[range].copy_from_slice(chunk.as_ref());
-                },
-                None => quote_spanned! { f.span() =>
-                    let chunk = self.0.hash_tree_root()?;
+                    }
+                }
+                None => {
+                    let hash_tree_root = FieldKind::from(f).hash_tree_root(quote! { self.0 });
+                    quote_spanned! { f.span() =>
+                    let chunk = #hash_tree_root?;
                     let range = #i*#BYTES_PER_CHUNK..(#i+1)*#BYTES_PER_CHUNK;
                     chunks[range].copy_from_slice(chunk.as_ref());
-                },
+                    }
+                }
+            });
+            let root_by_field = fields.iter().map(|f| match &f.ident {
+                Some(field_name) => FieldKind::from(f).hash_tree_root(quote! { self.#field_name }),
+                None => FieldKind::from(f).hash_tree_root(quote! { self.0 }),
             });
             quote! {
-                fn hash_tree_root(&mut self) -> Result<ssz_rs::Node, ssz_rs::MerkleizationError> {
+                fn hash_tree_root_with<__SszHasher: ssz_rs::MerkleHasher>(
+                    &mut self,
+                    context: &ssz_rs::MerkleizationContext<__SszHasher>,
+                ) -> Result<ssz_rs::Node, ssz_rs::MerkleizationError> {
                     let mut chunks = vec![0u8; #field_count * #BYTES_PER_CHUNK];
                     #(#impl_by_field)*
-                    ssz_rs::__internal::merkleize(&chunks, None)
+                    ssz_rs::__internal::merkleize_with(&chunks, None, context)
                 }
+
+                fn leaves<__SszHasher: ssz_rs::MerkleHasher>(
+                    &mut self,
+                    context: &ssz_rs::MerkleizationContext<__SszHasher>,
+                ) -> Result<Vec<ssz_rs::Node>, ssz_rs::MerkleizationError> {
+                    Ok(vec![#(#root_by_field?),*])
+                }
+
+                const CHUNK_COUNT: usize = #field_count;
             }
         }
         Data::Enum(ref data) => {
@@ -393,8 +745,8 @@ fn derive_merkleization_impl(data: &Data) -> TokenStream {
                         quote_spanned! { variant.span() =>
                             Self::#variant_name(value) => {
                                 let selector = #i;
-                                let data_root  = value.hash_tree_root()?;
-                                Ok(ssz_rs::__internal::mix_in_selector(&data_root, selector))
+                                let data_root  = value.hash_tree_root_with(context)?;
+                                Ok(ssz_rs::__internal::mix_in_selector(&data_root, selector, context))
                             }
                         }
                     }
@@ -403,6 +755,7 @@ fn derive_merkleization_impl(data: &Data) -> TokenStream {
                             Self::None => Ok(ssz_rs::__internal::mix_in_selector(
                                 &ssz_rs::Node::default(),
                                 0,
+                                context,
                             )),
                         }
                     }
@@ -410,118 +763,758 @@ fn derive_merkleization_impl(data: &Data) -> TokenStream {
                 }
             });
             quote! {
-                fn hash_tree_root(&mut self) -> Result<ssz_rs::Node, ssz_rs::MerkleizationError> {
+                fn hash_tree_root_with<__SszHasher: ssz_rs::MerkleHasher>(
+                    &mut self,
+                    context: &ssz_rs::MerkleizationContext<__SszHasher>,
+                ) -> Result<ssz_rs::Node, ssz_rs::MerkleizationError> {
                     match self {
                             #(#hash_tree_root_by_variant)*
                     }
                 }
+
+                fn leaves<__SszHasher: ssz_rs::MerkleHasher>(
+                    &mut self,
+                    context: &ssz_rs::MerkleizationContext<__SszHasher>,
+                ) -> Result<Vec<ssz_rs::Node>, ssz_rs::MerkleizationError> {
+                    Ok(vec![ssz_rs::Union::variant_hash_tree_root_with(self, context)?])
+                }
+
+                const CHUNK_COUNT: usize = 1;
             }
         }
         Data::Union(..) => unreachable!("data was already validated to exclude union types"),
     }
 }
 
-fn is_valid_none_identifier(ident: &Ident) -> bool {
-    *ident == format_ident!("None")
+fn derive_nesting_depth_impl(data: &Data) -> TokenStream {
+    match data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let depth_by_field =
+                    fields.named.iter().map(|f| FieldKind::from(f).nesting_depth());
+                quote! {
+                    1 #(.max(1 + #depth_by_field))*
+                }
+            }
+            // a "newtype" has the SSZ type of its field
+            Fields::Unnamed(ref fields) => FieldKind::from(&fields.unnamed[0]).nesting_depth(),
+            _ => unreachable!(),
+        },
+        Data::Enum(ref data) => {
+            let depth_by_variant =
+                data.variants.iter().filter_map(|variant| match &variant.fields {
+                    Fields::Unnamed(inner) => {
+                        let variant_type = &inner.unnamed[0].ty;
+                        Some(quote! { <#variant_type as ssz_rs::SimpleSerialize>::nesting_depth() })
+                    }
+                    _ => None,
+                });
+            quote! {
+                1 #(.max(1 + #depth_by_variant))*
+            }
+        }
+        Data::Union(..) => unreachable!("data was already validated to exclude union types"),
+    }
 }
 
-// Refers to the validation state of proc macro's input
-enum ValidationState<'a> {
-    Unvalidated(&'a Data),
-    Validated(&'a Data),
+fn derive_max_size_impl(data: &Data) -> TokenStream {
+    match data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let size_by_field = fields.named.iter().map(|f| FieldKind::from(f).max_size());
+                quote! {
+                    0usize #(.saturating_add(#size_by_field))*
+                }
+            }
+            // a "newtype" has the SSZ type of its field
+            Fields::Unnamed(ref fields) => {
+                let ty = &fields.unnamed[0].ty;
+                quote! { <#ty as ssz_rs::SimpleSerialize>::MAX_SIZE }
+            }
+            _ => unreachable!(),
+        },
+        Data::Enum(ref data) => {
+            let size_by_variant =
+                data.variants.iter().filter_map(|variant| match &variant.fields {
+                    Fields::Unnamed(inner) => {
+                        let variant_type = &inner.unnamed[0].ty;
+                        Some(quote! { <#variant_type as ssz_rs::SimpleSerialize>::MAX_SIZE })
+                    }
+                    _ => None,
+                });
+            // the selector and the largest variant, in a const context without `Ord::max`
+            quote! {
+                {
+                    let mut max_size = 0usize;
+                    #(
+                        if #size_by_variant > max_size {
+                            max_size = #size_by_variant;
+                        }
+                    )*
+                    1usize.saturating_add(max_size)
+                }
+            }
+        }
+        Data::Union(..) => unreachable!("data was already validated to exclude union types"),
+    }
 }
 
-// Validates the incoming data follows the rules
-// for mapping the Rust term to something that can
-// implement the `SimpleSerialize` trait.
-fn validate_derive_data(data: ValidationState) -> ValidationState {
-    let data = match data {
-        ValidationState::Unvalidated(data) => data,
-        data @ ValidationState::Validated(..) => return data,
-    };
+fn derive_variable_size_const_impl(data: &Data) -> TokenStream {
+    match data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let is_variable_size_by_field =
+                    fields.named.iter().map(|f| FieldKind::from(f).is_variable_size_const());
+                quote! {
+                    false #(|| #is_variable_size_by_field)*
+                }
+            }
+            // a "newtype" has the SSZ type of its field
+            Fields::Unnamed(ref fields) => {
+                let ty = &fields.unnamed[0].ty;
+                quote! { <#ty as ssz_rs::SimpleSerialize>::IS_VARIABLE_SIZE }
+            }
+            _ => unreachable!(),
+        },
+        Data::Enum(..) => quote! { true },
+        Data::Union(..) => unreachable!("data was already validated to exclude union types"),
+    }
+}
 
+fn derive_nesting_depth_const_impl(data: &Data) -> TokenStream {
     match data {
         Data::Struct(ref data) => match data.fields {
             Fields::Named(ref fields) => {
-                if fields.named.is_empty() {
-                    panic!("ssz_rs containers with no fields are illegal")
+                let depth_by_field =
+                    fields.named.iter().map(|f| FieldKind::from(f).nesting_depth_const());
+                quote! {
+                    ssz_rs::__internal::composite_nesting_depth(&[#(#depth_by_field),*])
                 }
             }
-            Fields::Unnamed(ref fields) if fields.unnamed.len() == 1 => {}
-            _ => panic!("Structs with unit or multiple unnnamed fields are not supported"),
+            // a "newtype" has the SSZ type of its field
+            Fields::Unnamed(ref fields) => {
+                FieldKind::from(&fields.unnamed[0]).nesting_depth_const()
+            }
+            _ => unreachable!(),
         },
         Data::Enum(ref data) => {
-            if data.variants.is_empty() {
-                panic!("SSZ unions must have at least 1 variant; this enum has none");
+            let depth_by_variant =
+                data.variants.iter().filter_map(|variant| match &variant.fields {
+                    Fields::Unnamed(inner) => {
+                        let variant_type = &inner.unnamed[0].ty;
+                        Some(quote! { <#variant_type as ssz_rs::SimpleSerialize>::NESTING_DEPTH })
+                    }
+                    _ => None,
+                });
+            quote! {
+                ssz_rs::__internal::composite_nesting_depth(&[#(#depth_by_variant),*])
             }
+        }
+        Data::Union(..) => unreachable!("data was already validated to exclude union types"),
+    }
+}
 
-            if data.variants.len() > 127 {
-                panic!("SSZ unions cannot have more than 127 variants; this enum has more");
+fn derive_generalized_index_impl(data: &Data) -> TokenStream {
+    match data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let index_by_field = fields.named.iter().enumerate().map(|(i, f)| {
+                    let name = f.ident.as_ref().unwrap().to_string();
+                    let index = FieldKind::from(f).generalized_index_from(
+                        quote! { ssz_rs::__internal::child_generalized_index(root, depth, #i)? },
+                        quote! { rest },
+                    );
+                    quote_spanned! { f.span() => #name => #index, }
+                });
+                quote! {
+                    let (element, rest) = match path.split_first() {
+                        Some(split) => split,
+                        None => return Ok(root),
+                    };
+                    let depth = <Self as ssz_rs::Merkleized>::tree_depth();
+                    match element {
+                        ssz_rs::PathElement::Field(name) => match *name {
+                            #(#index_by_field)*
+                            _ => Err(ssz_rs::__internal::invalid_path_element::<Self>(element)),
+                        },
+                        _ => Err(ssz_rs::__internal::invalid_path_element::<Self>(element)),
+                    }
+                }
             }
-
-            let mut none_forbidden = false;
-            let mut already_has_none = false;
-            for (i, variant) in data.variants.iter().enumerate() {
-                match &variant.fields {
+            // a "newtype" has the SSZ type of its field
+            Fields::Unnamed(ref fields) => FieldKind::from(&fields.unnamed[0])
+                .generalized_index_from(quote! { root }, quote! { path }),
+            _ => unreachable!(),
+        },
+        Data::Enum(ref data) => {
+            let variant_count = data.variants.len();
+            let index_by_variant =
+                data.variants.iter().enumerate().map(|(i, variant)| match &variant.fields {
                     Fields::Unnamed(inner) => {
-                        if i == 0 {
-                            none_forbidden = true;
-                        }
-                        if inner.unnamed.len() != 1 {
-                            panic!("Enums can only have 1 type per variant");
-                        }
-                    }
-                    Fields::Unit => {
-                        if none_forbidden {
-                            panic!("only the first variant can be `None`");
-                        }
-                        if already_has_none {
-                            panic!("cannot duplicate a unit variant (as only `None` is allowed)");
-                        }
-                        if !is_valid_none_identifier(&variant.ident) {
-                            panic!("Variant identifier is invalid: must be `None`");
-                        }
-                        assert!(i == 0);
-                        if data.variants.len() < 2 {
-                            panic!(
-                                "SSZ unions must have more than 1 selector if the first is `None`"
-                            );
+                        let variant_type = &inner.unnamed[0].ty;
+                        quote_spanned! { variant.span() =>
+                            #i => <#variant_type as ssz_rs::SimpleSerialize>
+                                ::generalized_index_from(root, path),
                         }
-                        already_has_none = true;
                     }
-                    Fields::Named(..) => {
-                        panic!("Enums with named fields in variants are not supported");
-                    }
-                };
+                    Fields::Unit => quote_spanned! { variant.span() =>
+                        #i => match path.first() {
+                            None => Ok(root),
+                            Some(element) => {
+                                Err(ssz_rs::__internal::invalid_path_element::<Self>(element))
+                            }
+                        },
+                    },
+                    _ => unreachable!(),
+                });
+            quote! {
+                ssz_rs::__internal::union_generalized_index::<Self>(
+                    root,
+                    path,
+                    |selector, root, path| match selector {
+                        #(#index_by_variant)*
+                        index => Err(ssz_rs::PathError::IndexOutOfBounds {
+                            index,
+                            bound: #variant_count,
+                        }),
+                    },
+                )
             }
         }
-        Data::Union(..) => panic!("Rust unions cannot produce valid SSZ types"),
+        Data::Union(..) => unreachable!("data was already validated to exclude union types"),
     }
-
-    ValidationState::Validated(data)
 }
 
-#[proc_macro_derive(SimpleSerialize)]
-pub fn derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
-    let input = parse_macro_input!(input as DeriveInput);
-
-    let data = ValidationState::Unvalidated(&input.data);
+fn derive_hash_tree_root_from_bytes_impl(data: &Data, check_depth: &TokenStream) -> TokenStream {
+    let body = match data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let field_count = fields.named.len();
+                let root_by_field = fields.named.iter().enumerate().map(|(i, f)| {
+                    let root = FieldKind::from(f)
+                        .hash_tree_root_from_bytes(quote! { &encoding[ranges[#i].clone()] });
+                    quote_spanned! { f.span() =>
+                        let root = #root?;
+                        chunks[#i * #BYTES_PER_CHUNK..(#i + 1) * #BYTES_PER_CHUNK]
+                            .copy_from_slice(root.as_ref());
+                    }
+                });
+                let layout = field_layout(fields);
+                quote! {
+                    #check_depth
+                    #layout
+                    let mut chunks = vec![0u8; #field_count * #BYTES_PER_CHUNK];
+                    #(#root_by_field)*
+                    Ok(ssz_rs::__internal::merkleize_with(&chunks, None, context)?)
+                }
+            }
+            // a "newtype" has the SSZ type of its field
+            Fields::Unnamed(ref fields) => {
+                FieldKind::from(&fields.unnamed[0]).hash_tree_root_from_bytes(quote! { encoding })
+            }
+            _ => unreachable!(),
+        },
+        Data::Enum(ref data) => {
+            let root_by_variant = data.variants.iter().enumerate().map(|(i, variant)| {
+                // NOTE: this is "safe" as the number of legal variants fits into `u8`
+                let selector = i as u8;
+                match &variant.fields {
+                    Fields::Unnamed(inner) => {
+                        let variant_type = &inner.unnamed[0].ty;
+                        quote_spanned! { variant.span() =>
+                            #selector => {
+                                let data_root = <#variant_type as ssz_rs::SimpleSerialize>::hash_tree_root_from_bytes(
+                                    &encoding[1..],
+                                    context,
+                                )?;
+                                Ok(ssz_rs::__internal::mix_in_selector(&data_root, #i, context))
+                            }
+                        }
+                    }
+                    _ => quote_spanned! { variant.span() =>
+                        0 => match encoding.len() {
+                            1 => Ok(ssz_rs::__internal::mix_in_selector(
+                                &ssz_rs::Node::default(),
+                                0,
+                                context,
+                            )),
+                            provided => Err(ssz_rs::DeserializeError::AdditionalInput {
+                                provided,
+                                expected: 1,
+                            }
+                            .into()),
+                        },
+                    },
+                }
+            });
+            quote! {
+                #check_depth
+                if encoding.is_empty() {
+                    return Err(ssz_rs::DeserializeError::ExpectedFurtherInput {
+                        provided: 0,
+                        expected: 1,
+                    }
+                    .into());
+                }
+                match encoding[0] {
+                    #(#root_by_variant)*
+                    b => Err(ssz_rs::DeserializeError::InvalidByte(b).into()),
+                }
+            }
+        }
+        Data::Union(..) => unreachable!("data was already validated to exclude union types"),
+    };
+
+    quote! {
+        fn hash_tree_root_from_bytes<__SszHasher: ssz_rs::MerkleHasher>(
+            encoding: &[u8],
+            context: &ssz_rs::MerkleizationContext<__SszHasher>,
+        ) -> Result<ssz_rs::Node, ssz_rs::SimpleSerializeError> {
+            #body
+        }
+    }
+}
+
+// A container is default if all of its fields are, and its default root is computed from the
+// default roots of the types of its fields without constructing them. The root of a non-generic
+// container is memoized, as a `static` in a generic impl would be shared by all of its types.
+fn derive_default_root_impl(data: &Data, generics: &Generics) -> TokenStream {
+    let fields = match data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => &fields.named,
+            Fields::Unnamed(ref fields) => &fields.unnamed,
+            _ => unreachable!(),
+        },
+        // NOTE: the default variant of a union is chosen by its `Default` implementation
+        Data::Enum(..) => return quote! {},
+        Data::Union(..) => unreachable!("data was already validated to exclude union types"),
+    };
+    let field_count = fields.len();
+    let is_default_by_field = fields.iter().map(|f| match &f.ident {
+        Some(field_name) => FieldKind::from(f).is_default(quote! { self.#field_name }),
+        None => FieldKind::from(f).is_default(quote! { self.0 }),
+    });
+    let default_root_by_field = fields.iter().enumerate().map(|(i, f)| {
+        let default_root = FieldKind::from(f).default_hash_tree_root();
+        quote_spanned! { f.span() =>
+            let chunk = #default_root?;
+            let range = #i*#BYTES_PER_CHUNK..(#i+1)*#BYTES_PER_CHUNK;
+            chunks[range].copy_from_slice(chunk.as_ref());
+        }
+    });
+    let compute = quote! {
+        let mut chunks = vec![0u8; #field_count * #BYTES_PER_CHUNK];
+        #(#default_root_by_field)*
+        ssz_rs::__internal::merkleize_with(&chunks, None, context)
+    };
+    let body = if generics.params.is_empty() {
+        quote! {
+            static MEMO: ssz_rs::__internal::RootMemo = ssz_rs::__internal::RootMemo::new();
+            MEMO.get_or_try_init::<__SszHasher>(|| { #compute })
+        }
+    } else {
+        compute
+    };
+    quote! {
+        fn is_default(&self) -> bool {
+            true #(&& #is_default_by_field)*
+        }
+
+        fn default_hash_tree_root_with<__SszHasher: ssz_rs::MerkleHasher>(
+            context: &ssz_rs::MerkleizationContext<__SszHasher>,
+        ) -> Result<ssz_rs::Node, ssz_rs::MerkleizationError> {
+            #body
+        }
+    }
+}
+
+fn derive_heap_size_impl(data: &Data) -> TokenStream {
+    match data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let size_by_field = fields.named.iter().map(|f| {
+                    let field_name = &f.ident;
+                    FieldKind::from(f).heap_size(quote! { self.#field_name })
+                });
+                quote! {
+                    0 #(+ #size_by_field)*
+                }
+            }
+            Fields::Unnamed(ref fields) => {
+                FieldKind::from(&fields.unnamed[0]).heap_size(quote! { self.0 })
+            }
+            _ => unreachable!(),
+        },
+        Data::Enum(ref data) => {
+            let size_by_variant = data.variants.iter().map(|variant| {
+                let variant_name = &variant.ident;
+                match &variant.fields {
+                    Fields::Unnamed(inner) => {
+                        let variant_type = &inner.unnamed[0].ty;
+                        quote! {
+                            Self::#variant_name(value) => {
+                                <#variant_type as ssz_rs::SimpleSerialize>::ssz_heap_size(value)
+                            }
+                        }
+                    }
+                    _ => quote! { Self::None => 0, },
+                }
+            });
+            quote! {
+                match self {
+                    #(#size_by_variant)*
+                }
+            }
+        }
+        Data::Union(..) => unreachable!("data was already validated to exclude union types"),
+    }
+}
+
+fn field_infos(fields: &FieldsNamed) -> TokenStream {
+    let info_by_field = fields.named.iter().map(|f| {
+        let name = f.ident.as_ref().unwrap().to_string();
+        let ty = &f.ty;
+        let kind = FieldKind::from(f);
+        let is_variable_size = kind.is_variable_size();
+        let size_hint = kind.size_hint();
+        quote_spanned! { f.span() =>
+            ssz_rs::FieldInfo {
+                name: #name,
+                type_name: ::core::any::type_name::<#ty>(),
+                is_variable_size: #is_variable_size,
+                fixed_size: if #is_variable_size { 4 } else { #size_hint },
+            },
+        }
+    });
+    quote! { [#(#info_by_field)*] }
+}
+
+// Emit `ranges`, the range of the encoding of each field in `encoding`, with the layout of the
+// fields and the ranges in arrays on the stack so decoding does not allocate for them.
+fn field_layout(fields: &FieldsNamed) -> TokenStream {
+    let field_count = fields.named.len();
+    let infos = field_infos(fields);
+    quote! {
+        let fields: [ssz_rs::FieldInfo; #field_count] = #infos;
+        let mut ranges = [(); #field_count].map(|_| 0..0);
+        ssz_rs::__internal::field_ranges(&fields, encoding, &mut ranges)?;
+    }
+}
+
+fn derive_container_impl(data: &Data) -> Option<TokenStream> {
+    let fields = match data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => fields,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let infos = field_infos(fields);
+    let field_count = fields.named.len();
+    let root_by_field = fields.named.iter().enumerate().map(|(i, f)| {
+        let root = FieldKind::from(f).hash_tree_root_from_bytes(quote! { encoding });
+        quote_spanned! { f.span() => #i => #root, }
+    });
+    let type_id_by_field = fields.named.iter().enumerate().map(|(i, f)| {
+        let ty = &f.ty;
+        quote_spanned! { f.span() => #i => Some(::core::any::TypeId::of::<#ty>()), }
+    });
+    let visit_by_field = fields.named.iter().enumerate().map(|(i, f)| {
+        let field_name = f.ident.as_ref().unwrap();
+        let name = field_name.to_string();
+        let value = match FieldKind::from(f) {
+            FieldKind::Native(..) => quote! { &self.#field_name },
+            FieldKind::MapAsList(_, bound) => quote! {
+                &ssz_rs::map::MapAsList::<_, _, { #bound }>(&self.#field_name)
+            },
+        };
+        quote_spanned! { f.span() => visitor.visit_field(#i, #name, #value); }
+    });
+    Some(quote! {
+        fn fields() -> Vec<ssz_rs::FieldInfo> {
+            Vec::from(#infos)
+        }
+
+        fn field_type_id(index: usize) -> Option<::core::any::TypeId>
+        where
+            Self: 'static,
+        {
+            match index {
+                #(#type_id_by_field)*
+                _ => None,
+            }
+        }
+
+        fn visit_fields<__SszVisitor: ssz_rs::FieldVisitor>(&self, visitor: &mut __SszVisitor) {
+            #(#visit_by_field)*
+        }
+
+        fn field_hash_tree_root_from_bytes<__SszHasher: ssz_rs::MerkleHasher>(
+            index: usize,
+            encoding: &[u8],
+            context: &ssz_rs::MerkleizationContext<__SszHasher>,
+        ) -> Result<ssz_rs::Node, ssz_rs::SimpleSerializeError> {
+            match index {
+                #(#root_by_field)*
+                _ => Err(ssz_rs::InstanceError::bounded::<Self>(#field_count, index).into()),
+            }
+        }
+    })
+}
+
+fn derive_union_impl(data: &Data) -> Option<TokenStream> {
+    let variants = match data {
+        Data::Enum(ref data) => &data.variants,
+        _ => return None,
+    };
+    let selector_by_variant = variants.iter().enumerate().map(|(i, variant)| {
+        let i = i as u8;
+        let variant_name = &variant.ident;
+        match &variant.fields {
+            Fields::Unnamed(..) => {
+                quote_spanned! { variant.span() => Self::#variant_name(..) => #i, }
+            }
+            _ => quote_spanned! { variant.span() => Self::None => 0, },
+        }
+    });
+    let variant_count = variants.len();
+    let name_by_selector = variants.iter().enumerate().map(|(i, variant)| {
+        let i = i as u8;
+        let variant_name = variant.ident.to_string();
+        quote! { #i => Some(#variant_name), }
+    });
+    let root_by_variant = variants.iter().map(|variant| {
+        let variant_name = &variant.ident;
+        match &variant.fields {
+            Fields::Unnamed(..) => quote_spanned! { variant.span() =>
+                Self::#variant_name(value) => value.hash_tree_root_with(context),
+            },
+            _ => quote_spanned! { variant.span() => Self::None => Ok(ssz_rs::Node::default()), },
+        }
+    });
+    Some(quote! {
+        const VARIANT_COUNT: usize = #variant_count;
+
+        fn selector(&self) -> u8 {
+            match self {
+                #(#selector_by_variant)*
+            }
+        }
+
+        fn variant_name(selector: u8) -> Option<&'static str> {
+            match selector {
+                #(#name_by_selector)*
+                _ => None,
+            }
+        }
+
+        fn variant_hash_tree_root_with<__SszHasher: ssz_rs::MerkleHasher>(
+            &mut self,
+            context: &ssz_rs::MerkleizationContext<__SszHasher>,
+        ) -> Result<ssz_rs::Node, ssz_rs::MerkleizationError> {
+            match self {
+                #(#root_by_variant)*
+            }
+        }
+    })
+}
+
+fn is_valid_none_identifier(ident: &Ident) -> bool {
+    *ident == format_ident!("None")
+}
+
+// Refers to the validation state of proc macro's input
+enum ValidationState<'a> {
+    Unvalidated(&'a Data),
+    Validated(&'a Data),
+}
+
+// Validates the incoming data follows the rules
+// for mapping the Rust term to something that can
+// implement the `SimpleSerialize` trait, with errors
+// pointing at the offending part of the input named `ident`.
+fn validate_derive_data<'a>(
+    data: ValidationState<'a>,
+    ident: &Ident,
+) -> Result<ValidationState<'a>, syn::Error> {
+    let data = match data {
+        ValidationState::Unvalidated(data) => data,
+        data @ ValidationState::Validated(..) => return Ok(data),
+    };
+
+    if let Data::Struct(ref data) = data {
+        for field in data.fields.iter() {
+            FieldKind::parse(field)?;
+        }
+    }
+
+    match data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                if fields.named.is_empty() {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "ssz_rs containers with no fields are illegal",
+                    ))
+                }
+            }
+            Fields::Unnamed(ref fields) if fields.unnamed.len() == 1 => {}
+            Fields::Unnamed(ref fields) if !fields.unnamed.is_empty() => {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    "Structs with multiple unnamed fields are not supported",
+                ))
+            }
+            // NOTE: unit structs and tuple structs without fields are empty containers
+            _ => {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    "ssz_rs containers with no fields are illegal",
+                ))
+            }
+        },
+        Data::Enum(ref data) => {
+            if data.variants.is_empty() {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    "SSZ unions must have at least 1 variant; this enum has none",
+                ))
+            }
 
-    let data = &validate_derive_data(data);
+            if let Some(variant) = data.variants.iter().nth(127) {
+                return Err(syn::Error::new(
+                    variant.span(),
+                    "SSZ unions cannot have more than 127 variants; this enum has more",
+                ))
+            }
 
-    let data = match *data {
-        ValidationState::Validated(data) => data,
-        ValidationState::Unvalidated(..) => panic!("do not process unvalidated input"),
+            let mut already_has_none = false;
+            for (i, variant) in data.variants.iter().enumerate() {
+                match &variant.fields {
+                    Fields::Unnamed(inner) => {
+                        if inner.unnamed.len() != 1 {
+                            return Err(syn::Error::new(
+                                inner.span(),
+                                "Enums can only have 1 type per variant",
+                            ))
+                        }
+                    }
+                    Fields::Unit => {
+                        if already_has_none {
+                            return Err(syn::Error::new(
+                                variant.span(),
+                                "cannot duplicate a unit variant (as only `None` is allowed)",
+                            ))
+                        }
+                        if i != 0 {
+                            return Err(syn::Error::new(
+                                variant.span(),
+                                "only the first variant can be `None`",
+                            ))
+                        }
+                        if !is_valid_none_identifier(&variant.ident) {
+                            return Err(syn::Error::new(
+                                variant.ident.span(),
+                                "Variant identifier is invalid: must be `None`",
+                            ))
+                        }
+                        if data.variants.len() < 2 {
+                            return Err(syn::Error::new(
+                                variant.span(),
+                                "SSZ unions must have more than 1 selector if the first is `None`",
+                            ))
+                        }
+                        already_has_none = true;
+                    }
+                    Fields::Named(fields) => {
+                        return Err(syn::Error::new(
+                            fields.span(),
+                            "Enums with named fields in variants are not supported",
+                        ))
+                    }
+                };
+            }
+        }
+        Data::Union(..) => {
+            return Err(syn::Error::new(ident.span(), "Rust unions cannot produce valid SSZ types"))
+        }
+    }
+
+    Ok(ValidationState::Validated(data))
+}
+
+#[proc_macro_derive(SimpleSerialize, attributes(ssz))]
+pub fn derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    let data = ValidationState::Unvalidated(&input.data);
+    let data = match validate_derive_data(data, &input.ident) {
+        Ok(ValidationState::Validated(data)) => data,
+        Ok(ValidationState::Unvalidated(..)) => panic!("do not process unvalidated input"),
+        Err(err) => return err.to_compile_error().into(),
     };
 
     let name = &input.ident;
     let generics = &input.generics;
     let set_by_index_impl = derive_container_set_by_index_impl(name, data, generics);
     let serialize_impl = derive_serialize_impl(data);
-    let deserialize_impl = derive_deserialize_impl(data);
+    let options = match parse_container_options(&input.attrs) {
+        Ok(options) => options,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let builder = match options.builder.then(|| derive_builder(&input)).transpose() {
+        Ok(builder) => builder,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let default_root = match options.default_root.then(|| derive_default_root(&input)).transpose() {
+        Ok(default_root) => default_root,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    // NOTE: the nesting depth is fixed by the type, so a container exceeding its `max_depth` is
+    // rejected when compiling. Constants of generic types are only evaluated once the types
+    // are known, so decoding generic containers refers to the check.
+    let (max_depth_check, check_depth) = match options.max_depth {
+        Some(max_depth) if generics.params.is_empty() => (
+            Some(quote! {
+                const _: () = assert!(
+                    <#name as ssz_rs::SimpleSerialize>::NESTING_DEPTH <= #max_depth,
+                    "the nesting depth of the container exceeds its `max_depth`",
+                );
+            }),
+            quote! {},
+        ),
+        Some(max_depth) => {
+            let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+            (
+                Some(quote! {
+                    impl #impl_generics #name #ty_generics #where_clause {
+                        #[doc(hidden)]
+                        const __SSZ_MAX_DEPTH_CHECK: () = assert!(
+                            <Self as ssz_rs::SimpleSerialize>::NESTING_DEPTH <= #max_depth,
+                            "the nesting depth of the container exceeds its `max_depth`",
+                        );
+                    }
+                }),
+                quote! {
+                    #[allow(clippy::let_unit_value)]
+                    let () = Self::__SSZ_MAX_DEPTH_CHECK;
+                },
+            )
+        }
+        None => (None, quote! {}),
+    };
+    let deserialize_impl = derive_deserialize_impl(data, &check_depth);
     let is_variable_size_impl = derive_variable_size_impl(data);
     let size_hint_impl = derive_size_hint_impl(data);
     let merkleization_impl = derive_merkleization_impl(data);
+    let nesting_depth_impl = derive_nesting_depth_impl(data);
+    let nesting_depth_const_impl = derive_nesting_depth_const_impl(data);
+    let heap_size_impl = derive_heap_size_impl(data);
+    let default_root_impl = derive_default_root_impl(data, generics);
+    let max_size_impl = derive_max_size_impl(data);
+    let is_variable_size_const_impl = derive_variable_size_const_impl(data);
+    let generalized_index_impl = derive_generalized_index_impl(data);
+    let hash_tree_root_from_bytes_impl = derive_hash_tree_root_from_bytes_impl(data, &check_depth);
 
     let impl_impl = if generics.params.is_empty() {
         quote! { impl }
@@ -536,6 +1529,22 @@ pub fn derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
         quote! { #name #ty_generics }
     };
 
+    let container_impl = derive_container_impl(data).map(|container_impl| {
+        quote! {
+            #impl_impl ssz_rs::Container for #name_impl {
+                #container_impl
+            }
+        }
+    });
+
+    let union_impl = derive_union_impl(data).map(|union_impl| {
+        quote! {
+            #impl_impl ssz_rs::Union for #name_impl {
+                #union_impl
+            }
+        }
+    });
+
     let expansion = quote! {
         #set_by_index_impl
 
@@ -561,7 +1570,729 @@ pub fn derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
             #merkleization_impl
         }
 
-        #impl_impl ssz_rs::SimpleSerialize for #name_impl {}
+        #impl_impl ssz_rs::SimpleSerialize for #name_impl {
+            const MAX_SIZE: usize = #max_size_impl;
+
+            const IS_VARIABLE_SIZE: bool = #is_variable_size_const_impl;
+
+            const NESTING_DEPTH: usize = #nesting_depth_const_impl;
+
+            fn nesting_depth() -> usize {
+                #nesting_depth_impl
+            }
+
+            fn ssz_heap_size(&self) -> usize {
+                #heap_size_impl
+            }
+
+            fn generalized_index_from(
+                root: ssz_rs::GeneralizedIndex,
+                path: &[ssz_rs::PathElement<'_>],
+            ) -> Result<ssz_rs::GeneralizedIndex, ssz_rs::PathError> {
+                #generalized_index_impl
+            }
+
+            #hash_tree_root_from_bytes_impl
+
+            #default_root_impl
+        }
+
+        #container_impl
+
+        #union_impl
+
+        #builder
+
+        #default_root
+
+        #max_depth_check
+    };
+
+    proc_macro::TokenStream::from(expansion)
+}
+
+// Return `true` if the attributes include `#[repr(u8)]`.
+fn has_repr_u8(attrs: &[Attribute]) -> bool {
+    attrs.iter().filter(|attr| attr.path.is_ident("repr")).any(|attr| {
+        attr.parse_args_with(|input: ParseStream| {
+            let repr: Ident = input.parse()?;
+            Ok(repr == "u8")
+        })
+        .unwrap_or(false)
+    })
+}
+
+/// Derive `SimpleSerialize` for a fieldless `#[repr(u8)]` enum, encoded as the SSZ type
+/// `uint8` with the discriminant of the variant. Decoding an unknown discriminant is an error.
+///
+/// Conversions to and from `u8` are derived as well. As for any `SimpleSerialize` type, the
+/// enum must also implement `Default`.
+#[proc_macro_derive(SszEnum)]
+pub fn derive_ssz_enum(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+
+    let data = match &input.data {
+        Data::Enum(data) => data,
+        _ => {
+            return syn::Error::new(name.span(), "`SszEnum` can only be derived for enums")
+                .to_compile_error()
+                .into()
+        }
+    };
+    if data.variants.is_empty() {
+        return syn::Error::new(
+            name.span(),
+            "`SszEnum` requires at least 1 variant; this enum has none",
+        )
+        .to_compile_error()
+        .into()
+    }
+    if let Some(variant) =
+        data.variants.iter().find(|variant| !matches!(variant.fields, Fields::Unit))
+    {
+        return syn::Error::new(variant.span(), "`SszEnum` requires fieldless variants")
+            .to_compile_error()
+            .into()
+    }
+    if !has_repr_u8(&input.attrs) {
+        return syn::Error::new(name.span(), "`SszEnum` requires the enum to be `#[repr(u8)]`")
+            .to_compile_error()
+            .into()
+    }
+
+    let variants = data.variants.iter().map(|variant| &variant.ident).collect::<Vec<_>>();
+
+    let expansion = quote! {
+        impl From<#name> for u8 {
+            fn from(value: #name) -> Self {
+                match value {
+                    #(#name::#variants => #name::#variants as u8,)*
+                }
+            }
+        }
+
+        impl TryFrom<u8> for #name {
+            type Error = ssz_rs::DeserializeError;
+
+            fn try_from(value: u8) -> Result<Self, Self::Error> {
+                match value {
+                    #(b if b == Self::#variants as u8 => Ok(Self::#variants),)*
+                    b => Err(ssz_rs::DeserializeError::InvalidByte(b)),
+                }
+            }
+        }
+
+        impl ssz_rs::Sized for #name {
+            fn is_variable_size() -> bool {
+                false
+            }
+
+            fn size_hint() -> usize {
+                1
+            }
+        }
+
+        impl ssz_rs::Serialize for #name {
+            fn serialize(&self, buffer: &mut Vec<u8>) -> Result<usize, ssz_rs::SerializeError> {
+                let discriminant = match self {
+                    #(Self::#variants => Self::#variants as u8,)*
+                };
+                ssz_rs::Serialize::serialize(&discriminant, buffer)
+            }
+        }
+
+        impl ssz_rs::Deserialize for #name {
+            fn deserialize(encoding: &[u8]) -> Result<Self, ssz_rs::DeserializeError> {
+                Self::try_from(<u8 as ssz_rs::Deserialize>::deserialize(encoding)?)
+            }
+        }
+
+        impl ssz_rs::Merkleized for #name {
+            fn hash_tree_root_with<__SszHasher: ssz_rs::MerkleHasher>(
+                &mut self,
+                context: &ssz_rs::MerkleizationContext<__SszHasher>,
+            ) -> Result<ssz_rs::Node, ssz_rs::MerkleizationError> {
+                let mut discriminant = match self {
+                    #(Self::#variants => Self::#variants as u8,)*
+                };
+                ssz_rs::Merkleized::hash_tree_root_with(&mut discriminant, context)
+            }
+
+            const CHUNK_COUNT: usize = 1;
+        }
+
+        impl ssz_rs::SimpleSerialize for #name {
+            const MAX_SIZE: usize = 1;
+
+            const IS_VARIABLE_SIZE: bool = false;
+
+            const NESTING_DEPTH: usize = 0;
+
+            const PACKED_PER_CHUNK: usize = 32;
+
+            fn is_composite_type() -> bool {
+                false
+            }
+        }
+    };
+
+    proc_macro::TokenStream::from(expansion)
+}
+
+/// Derive `TestValue` for a container or union, generating each field (or the selected
+/// variant) from a seed mixed from the seed of the value.
+#[proc_macro_derive(TestValue, attributes(ssz))]
+pub fn derive_test_value(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    let data = ValidationState::Unvalidated(&input.data);
+    let data = match validate_derive_data(data, &input.ident) {
+        Ok(ValidationState::Validated(data)) => data,
+        Ok(ValidationState::Unvalidated(..)) => panic!("do not process unvalidated input"),
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    let body = match data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let value_by_field = fields.named.iter().enumerate().map(|(i, f)| {
+                    let field_name = &f.ident;
+                    let seed = quote! { ssz_rs::test_value::mix(seed, #i as u64) };
+                    let value = match FieldKind::from(f) {
+                        FieldKind::Native(ty) => {
+                            quote! { <#ty as ssz_rs::TestValue>::test_value(#seed) }
+                        }
+                        FieldKind::MapAsList(_, bound) => {
+                            quote! { ssz_rs::test_value::map::<_, _, { #bound }>(#seed) }
+                        }
+                    };
+                    quote_spanned! { f.span() => #field_name: #value, }
+                });
+                quote! {
+                    Self { #(#value_by_field)* }
+                }
+            }
+            Fields::Unnamed(ref fields) => {
+                let ty = &fields.unnamed[0].ty;
+                quote! {
+                    Self(<#ty as ssz_rs::TestValue>::test_value(seed))
+                }
+            }
+            _ => unreachable!(),
+        },
+        Data::Enum(ref data) => {
+            let variant_count = data.variants.len() as u64;
+            let value_by_variant = data.variants.iter().enumerate().map(|(i, variant)| {
+                let i = i as u64;
+                let variant_name = &variant.ident;
+                match &variant.fields {
+                    Fields::Unnamed(inner) => {
+                        let ty = &inner.unnamed[0].ty;
+                        quote_spanned! { variant.span() =>
+                            #i => Self::#variant_name(<#ty as ssz_rs::TestValue>::test_value(
+                                ssz_rs::test_value::mix(seed, 1),
+                            )),
+                        }
+                    }
+                    _ => quote_spanned! { variant.span() => #i => Self::None, },
+                }
+            });
+            quote! {
+                match ssz_rs::test_value::mix(seed, 0) % #variant_count {
+                    #(#value_by_variant)*
+                    _ => unreachable!(),
+                }
+            }
+        }
+        Data::Union(..) => unreachable!("data was already validated to exclude union types"),
+    };
+
+    let name = &input.ident;
+    let generics = &input.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let expansion = quote! {
+        impl #impl_generics ssz_rs::TestValue for #name #ty_generics #where_clause {
+            fn test_value(seed: u64) -> Self {
+                #body
+            }
+        }
+    };
+
+    proc_macro::TokenStream::from(expansion)
+}
+
+/// Derive `CanonicalText` for a container or union, writing the fields of a container
+/// in declaration order and a union as its selected variant.
+#[proc_macro_derive(CanonicalText, attributes(ssz))]
+pub fn derive_canonical_text(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    let data = ValidationState::Unvalidated(&input.data);
+    let data = match validate_derive_data(data, &input.ident) {
+        Ok(ValidationState::Validated(data)) => data,
+        Ok(ValidationState::Unvalidated(..)) => panic!("do not process unvalidated input"),
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    let (write_body, parse_body) = match data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let write_by_field = fields.named.iter().map(|f| {
+                    let field_name = f.ident.as_ref().unwrap();
+                    let name = field_name.to_string();
+                    let write = match FieldKind::from(f) {
+                        FieldKind::Native(ty) => quote! {
+                            <#ty as ssz_rs::CanonicalText>::write_text(&self.#field_name, writer);
+                        },
+                        FieldKind::MapAsList(..) => quote! {
+                            ssz_rs::text::write_map(&self.#field_name, writer);
+                        },
+                    };
+                    quote_spanned! { f.span() =>
+                        writer.field(#name);
+                        #write
+                        writer.write(",");
+                    }
+                });
+                let parse_by_field = fields.named.iter().map(|f| {
+                    let field_name = f.ident.as_ref().unwrap();
+                    let name = field_name.to_string();
+                    let parse = match FieldKind::from(f) {
+                        FieldKind::Native(ty) => quote! {
+                            <#ty as ssz_rs::CanonicalText>::parse_text(parser)?
+                        },
+                        FieldKind::MapAsList(_, bound) => quote! {
+                            ssz_rs::text::parse_map::<_, _, { #bound }>(parser)?
+                        },
+                    };
+                    quote_spanned! { f.span() =>
+                        #field_name: {
+                            parser.field(#name)?;
+                            let value = #parse;
+                            parser.expect(",")?;
+                            value
+                        },
+                    }
+                });
+                (
+                    quote! {
+                        writer.begin("{");
+                        #(#write_by_field)*
+                        writer.end("}");
+                    },
+                    quote! {
+                        parser.expect("{")?;
+                        let value = Self { #(#parse_by_field)* };
+                        parser.expect("}")?;
+                        Ok(value)
+                    },
+                )
+            }
+            Fields::Unnamed(ref fields) => {
+                let ty = &fields.unnamed[0].ty;
+                (
+                    quote! {
+                        <#ty as ssz_rs::CanonicalText>::write_text(&self.0, writer);
+                    },
+                    quote! {
+                        Ok(Self(<#ty as ssz_rs::CanonicalText>::parse_text(parser)?))
+                    },
+                )
+            }
+            _ => unreachable!(),
+        },
+        Data::Enum(ref data) => {
+            let write_by_variant = data.variants.iter().map(|variant| {
+                let variant_name = &variant.ident;
+                let name = variant_name.to_string();
+                match &variant.fields {
+                    Fields::Unnamed(inner) => {
+                        let ty = &inner.unnamed[0].ty;
+                        quote_spanned! { variant.span() =>
+                            Self::#variant_name(value) => {
+                                writer.write(concat!(#name, "("));
+                                <#ty as ssz_rs::CanonicalText>::write_text(value, writer);
+                                writer.write(")");
+                            }
+                        }
+                    }
+                    _ => quote_spanned! { variant.span() => Self::None => writer.write("None"), },
+                }
+            });
+            let parse_by_variant = data.variants.iter().map(|variant| {
+                let variant_name = &variant.ident;
+                let name = variant_name.to_string();
+                match &variant.fields {
+                    Fields::Unnamed(inner) => {
+                        let ty = &inner.unnamed[0].ty;
+                        quote_spanned! { variant.span() =>
+                            #name => {
+                                parser.expect("(")?;
+                                let value = <#ty as ssz_rs::CanonicalText>::parse_text(parser)?;
+                                parser.expect(")")?;
+                                Ok(Self::#variant_name(value))
+                            }
+                        }
+                    }
+                    _ => quote_spanned! { variant.span() => "None" => Ok(Self::None), },
+                }
+            });
+            (
+                quote! {
+                    match self {
+                        #(#write_by_variant)*
+                    }
+                },
+                quote! {
+                    let error = parser.error("a variant");
+                    match parser.word()? {
+                        #(#parse_by_variant)*
+                        _ => Err(error),
+                    }
+                },
+            )
+        }
+        Data::Union(..) => unreachable!("data was already validated to exclude union types"),
+    };
+
+    let name = &input.ident;
+    let generics = &input.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let expansion = quote! {
+        impl #impl_generics ssz_rs::CanonicalText for #name #ty_generics #where_clause {
+            fn write_text(&self, writer: &mut ssz_rs::text::TextWriter) {
+                #write_body
+            }
+
+            fn parse_text(
+                parser: &mut ssz_rs::text::TextParser<'_>,
+            ) -> Result<Self, ssz_rs::text::TextError> {
+                #parse_body
+            }
+        }
+    };
+
+    proc_macro::TokenStream::from(expansion)
+}
+
+/// Derive `Describe` for a container or union, describing each field (or variant) in
+/// declaration order.
+#[proc_macro_derive(Describe, attributes(ssz))]
+pub fn derive_describe(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    let data = ValidationState::Unvalidated(&input.data);
+    let data = match validate_derive_data(data, &input.ident) {
+        Ok(ValidationState::Validated(data)) => data,
+        Ok(ValidationState::Unvalidated(..)) => panic!("do not process unvalidated input"),
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    let body = match data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => {
+                let descriptor_by_field = fields.named.iter().map(|f| {
+                    let field_name = f.ident.as_ref().expect("named field").to_string();
+                    let descriptor = match FieldKind::from(f) {
+                        FieldKind::Native(ty) => {
+                            quote! { <#ty as ssz_rs::compat::Describe>::describe() }
+                        }
+                        FieldKind::MapAsList(ty, bound) => {
+                            let (key, value) = map_entry_types(ty);
+                            quote! { ssz_rs::compat::map::<#key, #value, { #bound }>() }
+                        }
+                    };
+                    quote_spanned! { f.span() => (#field_name.into(), #descriptor), }
+                });
+                quote! {
+                    ssz_rs::compat::TypeDescriptor::Container(vec![#(#descriptor_by_field)*])
+                }
+            }
+            Fields::Unnamed(ref fields) => {
+                let ty = &fields.unnamed[0].ty;
+                quote! {
+                    <#ty as ssz_rs::compat::Describe>::describe()
+                }
+            }
+            _ => unreachable!(),
+        },
+        Data::Enum(ref data) => {
+            let descriptor_by_variant = data.variants.iter().map(|variant| match &variant.fields {
+                Fields::Unnamed(inner) => {
+                    let ty = &inner.unnamed[0].ty;
+                    quote_spanned! { variant.span() =>
+                        Some(<#ty as ssz_rs::compat::Describe>::describe()),
+                    }
+                }
+                _ => quote_spanned! { variant.span() => None, },
+            });
+            quote! {
+                ssz_rs::compat::TypeDescriptor::Union(vec![#(#descriptor_by_variant)*])
+            }
+        }
+        Data::Union(..) => unreachable!("data was already validated to exclude union types"),
+    };
+
+    let name = &input.ident;
+    let generics = &input.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let expansion = quote! {
+        impl #impl_generics ssz_rs::compat::Describe for #name #ty_generics #where_clause {
+            fn describe() -> ssz_rs::compat::TypeDescriptor {
+                #body
+            }
+        }
+    };
+
+    proc_macro::TokenStream::from(expansion)
+}
+
+/// Derive `FromSszBridge` and `IntoSszBridge` for a container and the counterpart named by its
+/// `#[bridge(Type)]` attribute, converting the fields of the same names. The container is also
+/// converted from and into an `Option` of its counterpart, as for the nested messages of
+/// protobuf.
+#[proc_macro_derive(SszBridge, attributes(bridge))]
+pub fn derive_ssz_bridge(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+
+    let counterpart = match input.attrs.iter().find(|attr| attr.path.is_ident("bridge")) {
+        Some(attr) => match attr.parse_args::<syn::Path>() {
+            Ok(counterpart) => counterpart,
+            Err(err) => return err.to_compile_error().into(),
+        },
+        None => {
+            return syn::Error::new(name.span(), "expected a `#[bridge(Type)]` attribute")
+                .to_compile_error()
+                .into()
+        }
+    };
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return syn::Error::new(name.span(), "`SszBridge` requires named fields")
+                    .to_compile_error()
+                    .into()
+            }
+        },
+        _ => {
+            return syn::Error::new(name.span(), "`SszBridge` can only be derived for structs")
+                .to_compile_error()
+                .into()
+        }
+    };
+
+    let from_by_field = fields.iter().map(|f| {
+        let field_name = f.ident.as_ref().expect("named field");
+        let name = field_name.to_string();
+        quote_spanned! { f.span() =>
+            #field_name: ssz_rs::bridge::FromSszBridge::from_bridge(other.#field_name)
+                .map_err(|err| err.within(#name))?,
+        }
+    });
+    let into_by_field = fields.iter().map(|f| {
+        let field_name = f.ident.as_ref().expect("named field");
+        quote_spanned! { f.span() =>
+            #field_name: ssz_rs::bridge::IntoSszBridge::into_bridge(self.#field_name),
+        }
+    });
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let expansion = quote! {
+        impl #impl_generics ssz_rs::bridge::FromSszBridge<#counterpart> for #name #ty_generics
+            #where_clause
+        {
+            fn from_bridge(other: #counterpart) -> Result<Self, ssz_rs::bridge::BridgeError> {
+                Ok(Self { #(#from_by_field)* })
+            }
+        }
+
+        impl #impl_generics ssz_rs::bridge::IntoSszBridge<#counterpart> for #name #ty_generics
+            #where_clause
+        {
+            fn into_bridge(self) -> #counterpart {
+                #counterpart { #(#into_by_field)* }
+            }
+        }
+
+        impl #impl_generics ssz_rs::bridge::FromSszBridge<Option<#counterpart>>
+            for #name #ty_generics #where_clause
+        {
+            fn from_bridge(
+                other: Option<#counterpart>,
+            ) -> Result<Self, ssz_rs::bridge::BridgeError> {
+                match other {
+                    Some(other) => {
+                        <Self as ssz_rs::bridge::FromSszBridge<#counterpart>>::from_bridge(other)
+                    }
+                    None => Err(ssz_rs::bridge::BridgeError::new(
+                        ssz_rs::bridge::BridgeErrorKind::Missing,
+                    )),
+                }
+            }
+        }
+
+        impl #impl_generics ssz_rs::bridge::IntoSszBridge<Option<#counterpart>>
+            for #name #ty_generics #where_clause
+        {
+            fn into_bridge(self) -> Option<#counterpart> {
+                Some(<Self as ssz_rs::bridge::IntoSszBridge<#counterpart>>::into_bridge(self))
+            }
+        }
+    };
+
+    proc_macro::TokenStream::from(expansion)
+}
+
+// A container named by a `#[migrate(from = Type, removed = path)]` attribute, with the hook
+// checking the fields which were removed from it.
+struct MigrateSource {
+    ty: Type,
+    removed: Option<syn::Path>,
+}
+
+// The hook of a field of a container deriving `Migrate` given by `#[migrate(default)]` or
+// `#[migrate(with = path)]`.
+enum MigrateHook {
+    Default,
+    With(syn::Path),
+}
+
+fn parse_migrate_source(attr: &Attribute) -> syn::Result<MigrateSource> {
+    attr.parse_args_with(|input: ParseStream| {
+        let key: Ident = input.parse()?;
+        if key != "from" {
+            return Err(syn::Error::new(key.span(), "expected `from = Type`"))
+        }
+        input.parse::<Token![=]>()?;
+        let ty = input.parse::<Type>()?;
+        let mut removed = None;
+        if !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            let key: Ident = input.parse()?;
+            if key != "removed" {
+                return Err(syn::Error::new(key.span(), "expected `removed = path`"))
+            }
+            input.parse::<Token![=]>()?;
+            removed = Some(input.parse::<syn::Path>()?);
+        }
+        Ok(MigrateSource { ty, removed })
+    })
+}
+
+fn parse_migrate_hook(attr: &Attribute) -> syn::Result<MigrateHook> {
+    attr.parse_args_with(|input: ParseStream| {
+        let key: Ident = input.parse()?;
+        if key == "default" {
+            Ok(MigrateHook::Default)
+        } else if key == "with" {
+            input.parse::<Token![=]>()?;
+            Ok(MigrateHook::With(input.parse::<syn::Path>()?))
+        } else {
+            Err(syn::Error::new(key.span(), "expected `default` or `with = path`"))
+        }
+    })
+}
+
+/// Derive `Migrate` for a container from itself and from each container named by a
+/// `#[migrate(from = Type)]` attribute, migrating the fields of the same names. The fields
+/// missing from the other container are given by `#[migrate(default)]` or
+/// `#[migrate(with = path)]`, and a `removed = path` hook checks the other container before its
+/// removed fields are dropped.
+#[proc_macro_derive(Migrate, attributes(migrate))]
+pub fn derive_migrate(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+
+    let sources = match input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("migrate"))
+        .map(parse_migrate_source)
+        .collect::<syn::Result<Vec<_>>>()
+    {
+        Ok(sources) => sources,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return syn::Error::new(name.span(), "`Migrate` requires named fields")
+                    .to_compile_error()
+                    .into()
+            }
+        },
+        _ => {
+            return syn::Error::new(name.span(), "`Migrate` can only be derived for structs")
+                .to_compile_error()
+                .into()
+        }
+    };
+    let hooks = match fields
+        .iter()
+        .map(|f| f.attrs.iter().find(|attr| attr.path.is_ident("migrate")).map(parse_migrate_hook))
+        .map(Option::transpose)
+        .collect::<syn::Result<Vec<_>>>()
+    {
+        Ok(hooks) => hooks,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    // NOTE: the hooks borrow the other container, so they run before its fields are moved
+    let hook_by_field = fields.iter().zip(&hooks).filter_map(|(f, hook)| {
+        let field_name = f.ident.as_ref().expect("named field");
+        let name = field_name.to_string();
+        let value = match hook.as_ref()? {
+            MigrateHook::Default => quote! { ::core::default::Default::default() },
+            MigrateHook::With(path) => quote! { #path(&from).map_err(|err| err.within(#name))? },
+        };
+        Some(quote_spanned! { f.span() =>
+            let #field_name = #value;
+        })
+    });
+    let hook_by_field = hook_by_field.collect::<Vec<_>>();
+    let migrate_by_field = fields
+        .iter()
+        .zip(&hooks)
+        .map(|(f, hook)| {
+            let field_name = f.ident.as_ref().expect("named field");
+            let name = field_name.to_string();
+            match hook {
+                Some(..) => quote! { #field_name, },
+                None => quote_spanned! { f.span() =>
+                    #field_name: ssz_rs::migrate::Migrate::migrate(from.#field_name)
+                        .map_err(|err| err.within(#name))?,
+                },
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let impl_by_source = sources.iter().map(|MigrateSource { ty, removed }| {
+        let check_removed = removed.as_ref().map(|path| quote! { #path(&from)?; });
+        quote! {
+            impl #impl_generics ssz_rs::migrate::Migrate<#ty> for #name #ty_generics #where_clause {
+                fn migrate(from: #ty) -> Result<Self, ssz_rs::migrate::MigrateError> {
+                    #check_removed
+                    #(#hook_by_field)*
+                    Ok(Self { #(#migrate_by_field)* })
+                }
+            }
+        }
+    });
+    let expansion = quote! {
+        impl #impl_generics ssz_rs::migrate::Migrate<#name #ty_generics> for #name #ty_generics
+            #where_clause
+        {
+            fn migrate(from: Self) -> Result<Self, ssz_rs::migrate::MigrateError> {
+                Ok(from)
+            }
+        }
+
+        #(#impl_by_source)*
     };
 
     proc_macro::TokenStream::from(expansion)
@@ -39,6 +39,7 @@ fn generate() -> std::io::Result<()> {
 
         static CONTEXT: Context = Context {{
             zero_hashes: {data:?},
+            _hasher: PhantomData,
         }};",
     )
     .unwrap();
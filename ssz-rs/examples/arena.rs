@@ -0,0 +1,50 @@
+//! Compare decoding nested lists with and without an arena.
+//!
+//! Run with `cargo run --release --example arena --features arena`.
+use ssz_rs::{
+    arena::{Arena, ArenaList},
+    prelude::*,
+};
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+const ITERATIONS: usize = 200;
+
+type Balances = List<List<u64, 16>, 4096>;
+
+fn balances() -> Balances {
+    List::try_from(
+        (0..4096).map(|i| List::try_from(vec![i as u64; 4]).unwrap()).collect::<Vec<_>>(),
+    )
+    .unwrap()
+}
+
+fn time(mut f: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    start.elapsed() / ITERATIONS as u32
+}
+
+fn main() {
+    let encoding = serialize(&balances()).unwrap();
+
+    let baseline = time(|| {
+        black_box(Balances::deserialize(&encoding).unwrap());
+    });
+    println!("decode without an arena: {baseline:?}/iter");
+
+    // one region is reused across iterations
+    let mut arena = Arena::with_capacity(1 << 20).unwrap();
+    let in_arena = time(|| {
+        black_box(arena.deserialize::<ArenaList<ArenaList<u64, 16>, 4096>>(&encoding).unwrap());
+        arena.reset();
+    });
+    println!(
+        "decode in an arena: {in_arena:?}/iter ({:+.1}%)",
+        (in_arena.as_secs_f64() / baseline.as_secs_f64() - 1.0) * 100.0
+    );
+}
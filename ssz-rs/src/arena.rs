@@ -0,0 +1,327 @@
+//! Decoding into a caller-provided arena, so the many small allocations of decoding a large
+//! value come from one contiguous region which is freed at once.
+//!
+//! The elements of an [`ArenaList`] or an [`ArenaVector`] decoded by [`Arena::deserialize`] are
+//! bumped out of the region of the arena, which they borrow, so values decoded in an arena can
+//! not outlive it. Nothing in the region is dropped, which is why the types decoded in an arena
+//! are `Copy`. Implement [`DeserializeIn`] to decode other types, e.g. containers of arena lists,
+//! in an arena.
+//!
+//! Decoding fails with [`DeserializeError::ArenaExhausted`] once the region is used up.
+//! [`Arena::reset`] frees every value decoded in the arena, so the region can be reused.
+//!
+//! ```rust
+//! use ssz_rs::{
+//!     arena::{Arena, ArenaList},
+//!     prelude::*,
+//! };
+//!
+//! let values = List::<List<u8, 8>, 4>::try_from(vec![List::try_from(vec![1u8, 2]).unwrap(); 4])
+//!     .unwrap();
+//! let encoding = serialize(&values).unwrap();
+//!
+//! let arena = Arena::with_capacity(1 << 12).unwrap();
+//! let decoded: ArenaList<ArenaList<u8, 8>, 4> = arena.deserialize(&encoding).unwrap();
+//! assert_eq!(decoded.len(), 4);
+//! assert_eq!(decoded[0].to_list(), values[0]);
+//! assert!(arena.used() > 0);
+//! ```
+use crate::{
+    de::{variable_element_ranges, DeserializeError},
+    error::{InstanceError, TypeError},
+    lib::*,
+    list::List,
+    vector::Vector,
+    SimpleSerialize,
+};
+use std::{
+    alloc::{self, Layout},
+    cell::Cell,
+    mem::MaybeUninit,
+    ptr::NonNull,
+};
+
+// The alignment of the region. Larger alignments are served by aligning the address of each
+// allocation.
+const REGION_ALIGN: usize = 16;
+
+/// A region of memory to decode values into, see the [module documentation](self).
+#[derive(Debug)]
+pub struct Arena {
+    start: NonNull<u8>,
+    capacity: usize,
+    used: Cell<usize>,
+}
+
+impl Arena {
+    /// Allocate an arena with a region of `capacity` bytes.
+    ///
+    /// Returns `None` if the region can not be allocated.
+    pub fn with_capacity(capacity: usize) -> Option<Self> {
+        let layout = Layout::from_size_align(capacity.max(1), REGION_ALIGN).ok()?;
+        // SAFETY: `layout` has a non-zero size
+        let start = NonNull::new(unsafe { alloc::alloc(layout) })?;
+        Some(Self { start, capacity: layout.size(), used: Cell::new(0) })
+    }
+
+    /// Return the size of the region in bytes.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Return the number of bytes of the region used so far.
+    pub fn used(&self) -> usize {
+        self.used.get()
+    }
+
+    /// Free every value decoded in this arena at once, so its region can be used again.
+    pub fn reset(&mut self) {
+        self.used.set(0);
+    }
+
+    /// Decode a value of type `T` from `encoding` with its elements in this arena.
+    pub fn deserialize<'a, T: DeserializeIn<'a>>(
+        &'a self,
+        encoding: &[u8],
+    ) -> Result<T, DeserializeError> {
+        T::deserialize_in(encoding, self)
+    }
+
+    // Bump `layout` out of the region. The address is aligned, not the offset into the region,
+    // as the region is only aligned to `REGION_ALIGN`.
+    fn alloc(&self, layout: Layout) -> Option<NonNull<u8>> {
+        let start = self.start.as_ptr() as usize;
+        let next = start + self.used.get();
+        let address = next.checked_add(layout.align() - 1)? & !(layout.align() - 1);
+        let offset = address - start;
+        let end = offset.checked_add(layout.size())?;
+        if end > self.capacity {
+            return None
+        }
+        self.used.set(end);
+        // SAFETY: `offset` is within the region
+        Some(unsafe { NonNull::new_unchecked(self.start.as_ptr().add(offset)) })
+    }
+
+    // Bump room for `len` values of type `T` out of the region.
+    #[allow(clippy::mut_from_ref)]
+    fn alloc_slice<T>(&self, len: usize) -> Result<&mut [MaybeUninit<T>], DeserializeError> {
+        let exhausted = || DeserializeError::ArenaExhausted {
+            required: len.saturating_mul(mem::size_of::<T>()),
+            available: self.capacity - self.used(),
+        };
+        let layout = Layout::array::<T>(len).map_err(|_| exhausted())?;
+        let ptr = if layout.size() == 0 {
+            NonNull::dangling()
+        } else {
+            self.alloc(layout).ok_or_else(exhausted)?.cast()
+        };
+        // SAFETY: the memory is suitably aligned, in bounds of the region and handed out only
+        // once, and `MaybeUninit` needs no initialization
+        Ok(unsafe { slice::from_raw_parts_mut(ptr.as_ptr(), len) })
+    }
+}
+
+impl Drop for Arena {
+    fn drop(&mut self) {
+        let layout = Layout::from_size_align(self.capacity, REGION_ALIGN).expect("is valid layout");
+        // SAFETY: the region was allocated with this layout and the values decoded in the
+        // arena borrow it, so none of them is alive anymore
+        unsafe { alloc::dealloc(self.start.as_ptr(), layout) };
+    }
+}
+
+/// A value that can be decoded with its elements in an [`Arena`].
+///
+/// Values in an arena are never dropped, so they are `Copy`. Every `Copy` type implementing
+/// [`SimpleSerialize`], e.g. the unsigned integers, decodes in an arena as it does outside.
+pub trait DeserializeIn<'a>: crate::Sized + Copy {
+    /// Decode a value from `encoding` with its elements in `arena`.
+    fn deserialize_in(encoding: &[u8], arena: &'a Arena) -> Result<Self, DeserializeError>;
+}
+
+impl<'a, T: SimpleSerialize + Copy> DeserializeIn<'a> for T {
+    fn deserialize_in(encoding: &[u8], _: &'a Arena) -> Result<Self, DeserializeError> {
+        T::deserialize(encoding)
+    }
+}
+
+// Decode the elements of a homogeneous composite type from `encoding` into `arena`, after
+// `check` accepted their number.
+fn deserialize_elements<'a, T: DeserializeIn<'a>>(
+    encoding: &[u8],
+    arena: &'a Arena,
+    check: impl FnOnce(usize) -> Result<(), DeserializeError>,
+) -> Result<&'a [T], DeserializeError> {
+    let slots = if T::is_variable_size() {
+        let ranges = variable_element_ranges(encoding)?;
+        check(ranges.len())?;
+        let slots = arena.alloc_slice(ranges.len())?;
+        for (slot, range) in slots.iter_mut().zip(ranges) {
+            slot.write(T::deserialize_in(&encoding[range], arena)?);
+        }
+        slots
+    } else {
+        let size = T::size_hint();
+        // NOTE: elements without any encoding, e.g. `Vector<T, 0>`, are not legal SSZ types
+        if size == 0 {
+            return Err(TypeError::InvalidBound(size).into())
+        }
+        let remainder = encoding.len() % size;
+        if remainder != 0 {
+            return Err(DeserializeError::AdditionalInput {
+                provided: encoding.len(),
+                expected: encoding.len() - remainder,
+            })
+        }
+        check(encoding.len() / size)?;
+        let slots = arena.alloc_slice(encoding.len() / size)?;
+        for (slot, chunk) in slots.iter_mut().zip(encoding.chunks_exact(size)) {
+            slot.write(T::deserialize_in(chunk, arena)?);
+        }
+        slots
+    };
+    // SAFETY: every slot was initialized above
+    Ok(unsafe { &*(slots as *const [MaybeUninit<T>] as *const [T]) })
+}
+
+/// A `List<T, N>` decoded in an [`Arena`], whose elements are in the region of the arena.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ArenaList<'a, T, const N: usize>(&'a [T]);
+
+impl<'a, T, const N: usize> ArenaList<'a, T, N> {
+    pub fn as_slice(&self) -> &'a [T] {
+        self.0
+    }
+}
+
+impl<'a, T: SimpleSerialize + Clone, const N: usize> ArenaList<'a, T, N> {
+    /// Copy the elements into a `List`.
+    pub fn to_list(&self) -> List<T, N> {
+        List::try_from(self.0.to_vec())
+            .unwrap_or_else(|_| unreachable!("length was checked when decoding"))
+    }
+}
+
+impl<'a, T, const N: usize> Clone for ArenaList<'a, T, N> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T, const N: usize> Copy for ArenaList<'a, T, N> {}
+
+impl<'a, T, const N: usize> Deref for ArenaList<'a, T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+impl<'a, T, const N: usize> crate::Sized for ArenaList<'a, T, N> {
+    fn is_variable_size() -> bool {
+        true
+    }
+
+    fn size_hint() -> usize {
+        0
+    }
+}
+
+impl<'a, T: DeserializeIn<'a>, const N: usize> DeserializeIn<'a> for ArenaList<'a, T, N> {
+    fn deserialize_in(encoding: &[u8], arena: &'a Arena) -> Result<Self, DeserializeError> {
+        let elements = deserialize_elements(encoding, arena, |len| {
+            if len > N {
+                return Err(InstanceError::bounded::<Self>(N, len).into())
+            }
+            Ok(())
+        })?;
+        Ok(Self(elements))
+    }
+}
+
+/// A `Vector<T, N>` decoded in an [`Arena`], whose elements are in the region of the arena.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ArenaVector<'a, T, const N: usize>(&'a [T]);
+
+impl<'a, T, const N: usize> ArenaVector<'a, T, N> {
+    pub fn as_slice(&self) -> &'a [T] {
+        self.0
+    }
+}
+
+impl<'a, T: SimpleSerialize + Clone, const N: usize> ArenaVector<'a, T, N> {
+    /// Copy the elements into a `Vector`.
+    pub fn to_vector(&self) -> Vector<T, N> {
+        Vector::try_from(self.0.to_vec())
+            .unwrap_or_else(|_| unreachable!("length was checked when decoding"))
+    }
+}
+
+impl<'a, T, const N: usize> Clone for ArenaVector<'a, T, N> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T, const N: usize> Copy for ArenaVector<'a, T, N> {}
+
+impl<'a, T, const N: usize> Deref for ArenaVector<'a, T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+impl<'a, T: crate::Sized, const N: usize> crate::Sized for ArenaVector<'a, T, N> {
+    fn is_variable_size() -> bool {
+        T::is_variable_size()
+    }
+
+    fn size_hint() -> usize {
+        T::size_hint() * N
+    }
+}
+
+impl<'a, T: DeserializeIn<'a>, const N: usize> DeserializeIn<'a> for ArenaVector<'a, T, N> {
+    fn deserialize_in(encoding: &[u8], arena: &'a Arena) -> Result<Self, DeserializeError> {
+        if N == 0 {
+            return Err(TypeError::InvalidBound(N).into())
+        }
+        let elements = deserialize_elements(encoding, arena, |len| {
+            if len != N {
+                return Err(InstanceError::exact::<Self>(N, len).into())
+            }
+            Ok(())
+        })?;
+        Ok(Self(elements))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alloc_aligns_the_address() {
+        // the region is only aligned to `REGION_ALIGN`, so a larger alignment may not fit
+        let arena = Arena::with_capacity(64).unwrap();
+        for _ in 0..32 {
+            if let Some(ptr) = arena.alloc(Layout::from_size_align(8, 4096).unwrap()) {
+                assert_eq!(ptr.as_ptr() as usize % 4096, 0);
+            }
+        }
+        assert!(arena.used() <= arena.capacity());
+
+        let arena = Arena::with_capacity(1 << 14).unwrap();
+        let first = arena.alloc(Layout::from_size_align(1, 1).unwrap()).unwrap();
+        let second = arena.alloc(Layout::from_size_align(8, 4096).unwrap()).unwrap();
+        assert_eq!(second.as_ptr() as usize % 4096, 0);
+        assert!(second.as_ptr() > first.as_ptr());
+        let third = arena.alloc(Layout::from_size_align(8, 64).unwrap()).unwrap();
+        assert_eq!(third.as_ptr() as usize % 64, 0);
+        assert!(arena.used() <= arena.capacity());
+    }
+}
@@ -5,10 +5,14 @@
 //! can likely be simplified to a definition over `const N: usize`.
 use crate::{
     de::{deserialize_homogeneous_composite, Deserialize, DeserializeError},
-    error::{InstanceError, TypeError},
+    error::{Error, InstanceError, TypeError},
     lib::*,
-    merkleization::{merkleize, pack, MerkleizationError, Merkleized, Node, BYTES_PER_CHUNK},
-    ser::{serialize_composite, Serialize, SerializeError},
+    merkleization::{
+        chunks_to_nodes, elements_chunk_count_const, elements_chunks, elements_generalized_index,
+        elements_root_from_bytes, merkleize_with, Context, GeneralizedIndex, MerkleHasher,
+        MerkleizationError, Merkleized, Node, PathElement, PathError,
+    },
+    ser::{elements_max_size, serialize_composite, Serialize, SerializeError},
     SimpleSerialize, Sized,
 };
 
@@ -65,7 +69,7 @@ macro_rules! define_ssz_for_array_of_size {
                 }
                 let elements = deserialize_homogeneous_composite(encoding)?;
                 elements.try_into().map_err(|elements: Vec<T>| {
-                    InstanceError::Exact { required: $n, provided: elements.len() }.into()
+                    InstanceError::exact::<Self>($n, elements.len()).into()
                 })
             }
         }
@@ -74,20 +78,22 @@ macro_rules! define_ssz_for_array_of_size {
         where
             T: SimpleSerialize,
         {
-            fn hash_tree_root(&mut self) -> Result<Node, MerkleizationError> {
-                if T::is_composite_type() {
-                    let mut chunks = vec![0u8; self.len() * BYTES_PER_CHUNK];
-                    for (i, elem) in self.iter_mut().enumerate() {
-                        let chunk = elem.hash_tree_root()?;
-                        let range = i * BYTES_PER_CHUNK..(i + 1) * BYTES_PER_CHUNK;
-                        chunks[range].copy_from_slice(chunk.as_ref());
-                    }
-                    merkleize(&chunks, None)
-                } else {
-                    let chunks = pack(self)?;
-                    merkleize(&chunks, None)
-                }
+            fn hash_tree_root_with<H: MerkleHasher>(
+                &mut self,
+                context: &Context<H>,
+            ) -> Result<Node, MerkleizationError> {
+                let chunks = elements_chunks(self, context)?;
+                merkleize_with(&chunks, None, context)
             }
+
+            fn leaves<H: MerkleHasher>(
+                &mut self,
+                context: &Context<H>,
+            ) -> Result<Vec<Node>, MerkleizationError> {
+                Ok(chunks_to_nodes(&elements_chunks(self, context)?))
+            }
+
+            const CHUNK_COUNT: usize = elements_chunk_count_const::<T>($n);
         }
 
         impl<T> SimpleSerialize for [T; $n]
@@ -97,6 +103,38 @@ macro_rules! define_ssz_for_array_of_size {
             fn is_composite_type() -> bool {
                 T::is_composite_type()
             }
+
+            fn nesting_depth() -> usize {
+                1 + T::nesting_depth()
+            }
+
+            const MAX_SIZE: usize = elements_max_size::<T>($n);
+
+            const IS_VARIABLE_SIZE: bool = T::IS_VARIABLE_SIZE;
+
+            const NESTING_DEPTH: usize = T::NESTING_DEPTH.saturating_add(1);
+
+            fn generalized_index_from(
+                root: GeneralizedIndex,
+                path: &[PathElement<'_>],
+            ) -> Result<GeneralizedIndex, PathError> {
+                elements_generalized_index::<Self, T>(root, path, $n, Self::tree_depth(), false)
+            }
+
+            fn hash_tree_root_from_bytes<H: MerkleHasher>(
+                encoding: &[u8],
+                context: &Context<H>,
+            ) -> Result<Node, Error> {
+                let (root, len) = elements_root_from_bytes::<Self, T, H>(encoding, $n, context)?;
+                if len != $n {
+                    return Err(Error::Instance(InstanceError::exact::<Self>($n, len)))
+                }
+                Ok(root)
+            }
+
+            fn ssz_heap_size(&self) -> usize {
+                self.iter().map(T::ssz_heap_size).sum()
+            }
         }
     };
 }
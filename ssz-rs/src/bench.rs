@@ -0,0 +1,174 @@
+//! Measure the throughput of encoding, decoding and hashing a value.
+//!
+//! The measurements are meant to compare schema designs against each other on one machine,
+//! e.g. a container with a `List` of containers against one with a `List` of each field.
+//!
+//! ```rust
+//! use ssz_rs::prelude::*;
+//! use ssz_rs::bench::{measure, BenchConfig};
+//!
+//! let value = List::<u64, 1024>::try_from(vec![7u64; 512]).unwrap();
+//! let report = measure(&value, &BenchConfig::new().with_iterations(10)).unwrap();
+//! assert_eq!(report.encoded_len, 4096);
+//! assert_eq!(report.hash_tree_root.iterations, 10);
+//! println!("{report}");
+//! ```
+use crate::{error::Error, lib::*, SimpleSerialize};
+use std::{
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+/// The number of untimed and timed runs of each operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BenchConfig {
+    pub warmup_iterations: usize,
+    pub iterations: usize,
+}
+
+impl Default for BenchConfig {
+    fn default() -> Self {
+        Self { warmup_iterations: 10, iterations: 100 }
+    }
+}
+
+impl BenchConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_warmup_iterations(mut self, warmup_iterations: usize) -> Self {
+        self.warmup_iterations = warmup_iterations;
+        self
+    }
+
+    /// Time `iterations` runs of each operation, at least one.
+    pub fn with_iterations(mut self, iterations: usize) -> Self {
+        self.iterations = iterations.max(1);
+        self
+    }
+}
+
+/// The time taken by the timed runs of an operation over an encoding of `bytes` bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Measurement {
+    pub iterations: usize,
+    pub bytes: usize,
+    pub total: Duration,
+}
+
+impl Measurement {
+    /// Return the mean time of a run.
+    pub fn per_iteration(&self) -> Duration {
+        self.total / self.iterations as u32
+    }
+
+    /// Return the number of bytes of the encoding processed per second.
+    pub fn bytes_per_second(&self) -> f64 {
+        let seconds = self.total.as_secs_f64();
+        if seconds == 0.0 {
+            return f64::INFINITY
+        }
+        (self.bytes * self.iterations) as f64 / seconds
+    }
+}
+
+impl Display for Measurement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?}/iter, {:.1} MB/s",
+            self.per_iteration(),
+            self.bytes_per_second() / 1_000_000.0
+        )
+    }
+}
+
+/// The measurements of each operation on a value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    /// The length of the encoding of the value in bytes.
+    pub encoded_len: usize,
+    pub serialize: Measurement,
+    pub deserialize: Measurement,
+    pub hash_tree_root: Measurement,
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "encoded length: {} bytes", self.encoded_len)?;
+        writeln!(f, "serialize:      {}", self.serialize)?;
+        writeln!(f, "deserialize:    {}", self.deserialize)?;
+        write!(f, "hash_tree_root: {}", self.hash_tree_root)
+    }
+}
+
+// Time `iterations` runs of `run` after `warmup_iterations` untimed runs.
+fn time<F>(config: &BenchConfig, bytes: usize, mut run: F) -> Result<Measurement, Error>
+where
+    F: FnMut() -> Result<Duration, Error>,
+{
+    for _ in 0..config.warmup_iterations {
+        run()?;
+    }
+    let mut total = Duration::ZERO;
+    for _ in 0..config.iterations {
+        total += run()?;
+    }
+    Ok(Measurement { iterations: config.iterations, bytes, total })
+}
+
+/// Measure encoding, decoding and hashing `value`.
+///
+/// Each hash is computed on a freshly decoded copy of `value` (decoded outside the timed
+/// section) so that roots cached by a previous run are not reused.
+pub fn measure<T: SimpleSerialize>(value: &T, config: &BenchConfig) -> Result<Report, Error> {
+    let mut encoding = vec![];
+    value.serialize(&mut encoding)?;
+    let encoded_len = encoding.len();
+
+    let mut buffer = Vec::with_capacity(encoded_len);
+    let serialize = time(config, encoded_len, || {
+        buffer.clear();
+        let start = Instant::now();
+        black_box(value).serialize(&mut buffer)?;
+        Ok(start.elapsed())
+    })?;
+
+    let deserialize = time(config, encoded_len, || {
+        let start = Instant::now();
+        let value = T::deserialize(black_box(&encoding))?;
+        let elapsed = start.elapsed();
+        drop(black_box(value));
+        Ok(elapsed)
+    })?;
+
+    let hash_tree_root = time(config, encoded_len, || {
+        let mut value = T::deserialize(&encoding)?;
+        let start = Instant::now();
+        black_box(value.hash_tree_root()?);
+        Ok(start.elapsed())
+    })?;
+
+    Ok(Report { encoded_len, serialize, deserialize, hash_tree_root })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn test_measure() {
+        let value = List::<u16, 64>::try_from(vec![1u16; 40]).unwrap();
+        let config = BenchConfig::new().with_warmup_iterations(1).with_iterations(0);
+        let report = measure(&value, &config).unwrap();
+        assert_eq!(report.encoded_len, 80);
+        for measurement in [report.serialize, report.deserialize, report.hash_tree_root] {
+            assert_eq!(measurement.iterations, 1);
+            assert_eq!(measurement.bytes, 80);
+            assert!(measurement.bytes_per_second() > 0.0);
+        }
+        assert_eq!(report.to_string().lines().count(), 4);
+    }
+}
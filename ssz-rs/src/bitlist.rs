@@ -1,12 +1,16 @@
 use crate::{
-    de::{Deserialize, DeserializeError},
-    error::InstanceError,
+    de::{Deserialize, DeserializeError, DeserializeWithLength},
+    error::{Error, InstanceError, OutOfBounds},
     lib::*,
-    merkleization::{merkleize, mix_in_length, pack_bytes, MerkleizationError, Merkleized, Node},
+    merkleization::{
+        bits_generalized_index, chunks_for_bits, chunks_to_nodes, merkleize_with, mix_in_length,
+        pack_bytes, Context, GeneralizedIndex, MerkleHasher, MerkleizationError, Merkleized, Node,
+        PathElement, PathError,
+    },
     ser::{Serialize, SerializeError},
     SimpleSerialize, Sized,
 };
-use bitvec::prelude::{BitVec, Lsb0};
+use bitvec::prelude::{BitSlice, BitVec, Lsb0};
 
 // +1 for length bit
 fn byte_length(bound: usize) -> usize {
@@ -16,6 +20,7 @@ fn byte_length(bound: usize) -> usize {
 type BitlistInner = BitVec<u8, Lsb0>;
 
 /// A homogenous collection of a variable number of boolean values.
+/// NOTE: a `Bitlist` with a bound of `0` is legal and always empty.
 #[derive(PartialEq, Eq, Clone)]
 pub struct Bitlist<const N: usize>(BitlistInner);
 
@@ -67,11 +72,19 @@ impl<const N: usize> Default for Bitlist<N> {
 }
 
 impl<const N: usize> Bitlist<N> {
+    /// The maximum number of bits in this `Bitlist`.
+    pub const MAX_BITS: usize = N;
+
     /// Return the bit at `index`. `None` if index is out-of-bounds.
-    pub fn get(&mut self, index: usize) -> Option<bool> {
+    pub fn get(&self, index: usize) -> Option<bool> {
         self.0.get(index).map(|value| *value)
     }
 
+    /// Return the bit at `index`, or an error with the length if out of bounds.
+    pub fn try_at(&self, index: usize) -> Result<bool, OutOfBounds> {
+        self.get(index).ok_or(OutOfBounds { index, len: self.len() })
+    }
+
     /// Set the bit at `index` to `value`. Return the previous value
     /// or `None` if index is out-of-bounds.
     pub fn set(&mut self, index: usize, value: bool) -> Option<bool> {
@@ -82,6 +95,115 @@ impl<const N: usize> Bitlist<N> {
         })
     }
 
+    /// Append `value` to the end of the `Bitlist`.
+    /// Returns an error if the `Bitlist` is already at its limit `N`.
+    pub fn push(&mut self, value: bool) -> Result<(), Error> {
+        if self.len() >= N {
+            let provided = self.len() + 1;
+            return Err(Error::Instance(InstanceError::bounded::<Self>(N, provided)))
+        }
+        self.0.push(value);
+        Ok(())
+    }
+
+    /// Remove the last bit and return it, or `None` if the `Bitlist` is empty.
+    pub fn pop(&mut self) -> Option<bool> {
+        self.0.pop()
+    }
+
+    /// Resize the `Bitlist` to `len` bits, filling any new bits with `value`.
+    /// Returns an error if `len` exceeds the limit `N`.
+    pub fn resize(&mut self, len: usize, value: bool) -> Result<(), Error> {
+        if len > N {
+            return Err(Error::Instance(InstanceError::bounded::<Self>(N, len)))
+        }
+        self.0.resize(len, value);
+        Ok(())
+    }
+
+    /// Remove all bits from the `Bitlist`.
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    /// Return the packed bits as bytes, least significant bit first.
+    ///
+    /// Any bits in the last byte past `len` are not part of the value and should be ignored.
+    pub fn as_raw_bytes(&self) -> &[u8] {
+        self.0.as_raw_slice()
+    }
+
+    /// Construct a `Bitlist` of `len` bits from their packed representation,
+    /// least significant bit first (i.e. the inverse of `as_raw_bytes`).
+    ///
+    /// Any bits in the last byte past `len` must be zero.
+    pub fn from_raw_bytes(bytes: &[u8], len: usize) -> Result<Self, DeserializeError> {
+        if len > N {
+            return Err(InstanceError::bounded::<Self>(N, len).into())
+        }
+        let expected_length = (len + 7) / 8;
+        if bytes.len() < expected_length {
+            return Err(DeserializeError::ExpectedFurtherInput {
+                provided: bytes.len(),
+                expected: expected_length,
+            })
+        }
+        if bytes.len() > expected_length {
+            return Err(DeserializeError::AdditionalInput {
+                provided: bytes.len(),
+                expected: expected_length,
+            })
+        }
+        let remainder_count = len % 8;
+        if remainder_count != 0 {
+            let last_byte = bytes[expected_length - 1];
+            if last_byte >> remainder_count != 0 {
+                return Err(DeserializeError::InvalidByte(last_byte))
+            }
+        }
+        let mut result = BitlistInner::from_slice(bytes);
+        result.truncate(len);
+        Ok(Self(result))
+    }
+
+    /// Return the number of bits set.
+    pub fn count_ones(&self) -> usize {
+        self.words().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// Return `true` if any bit is set.
+    pub fn any(&self) -> bool {
+        self.words().any(|word| word != 0)
+    }
+
+    /// Return `true` if every bit is set, including when the `Bitlist` is empty.
+    pub fn all(&self) -> bool {
+        self.count_ones() == self.len()
+    }
+
+    /// Return the number of bits set in both `self` and `other`, e.g. to check that
+    /// aggregation bits are disjoint (`0`) or that `self` is a superset of `other`
+    /// (`other.count_ones()`). Bits past the end of the shorter `Bitlist` are not counted.
+    pub fn overlap_count(&self, other: &Self) -> usize {
+        self.words().zip(other.words()).map(|(a, b)| (a & b).count_ones() as usize).sum()
+    }
+
+    // Return the bits in 64-bit words, least significant bit first, with the bits past the
+    // end of the `Bitlist` cleared, so counting can process a word at a time.
+    fn words(&self) -> impl Iterator<Item = u64> + '_ {
+        let len = self.len();
+        let bytes = &self.as_raw_bytes()[..(len + 7) / 8];
+        bytes.chunks(8).enumerate().map(move |(i, chunk)| {
+            let mut word = [0u8; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            let word = u64::from_le_bytes(word);
+            match len - i * 64 {
+                remaining if remaining < 64 => word & ((1u64 << remaining) - 1),
+                _ => word,
+            }
+        })
+    }
+
     fn pack_bits(&self) -> Result<Vec<u8>, MerkleizationError> {
         let mut data = vec![];
         let _ = self.serialize_with_length(&mut data, false)?;
@@ -95,14 +217,20 @@ impl<const N: usize> Bitlist<N> {
         with_length_bit: bool,
     ) -> Result<usize, SerializeError> {
         if self.len() > N {
-            return Err(InstanceError::Bounded { bound: N, provided: self.len() }.into())
+            return Err(SerializeError::LimitExceeded { bound: N, provided: self.len() })
         }
         let start_len = buffer.len();
-        buffer.extend_from_slice(self.as_raw_slice());
+        buffer.extend_from_slice(self.0.as_raw_slice());
+
+        let element_count = self.len();
+        let marker_index = element_count % 8;
+        if marker_index != 0 {
+            // NOTE: clear any stale bits past the end of the `Bitlist`
+            let last = buffer.last_mut().expect("bitlist cannot be empty");
+            *last &= (1u8 << marker_index) - 1;
+        }
 
         if with_length_bit {
-            let element_count = self.len();
-            let marker_index = element_count % 8;
             if marker_index == 0 {
                 buffer.push(1u8);
             } else {
@@ -114,8 +242,10 @@ impl<const N: usize> Bitlist<N> {
     }
 }
 
+// NOTE: deref to a `BitSlice` rather than the inner `BitVec` so the bits
+// can change but the length can not, except through methods that enforce `N`.
 impl<const N: usize> Deref for Bitlist<N> {
-    type Target = BitlistInner;
+    type Target = BitSlice<u8, Lsb0>;
 
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -158,7 +288,11 @@ impl<const N: usize> Deserialize for Bitlist<N> {
             })
         }
 
-        let (last_byte, prefix) = encoding.split_last().unwrap();
+        let (last_byte, prefix) = encoding.split_last().expect("encoding is not empty");
+        // the last byte must contain the delimiting bit
+        if *last_byte == 0 {
+            return Err(DeserializeError::InvalidByte(*last_byte))
+        }
         let mut result = BitlistInner::from_slice(prefix);
         let last = BitlistInner::from_element(*last_byte);
         let high_bit_index = 8 - last.trailing_zeros();
@@ -172,21 +306,85 @@ impl<const N: usize> Deserialize for Bitlist<N> {
         }
         // TODO: this seems redundant...
         if result.len() > N {
-            return Err(InstanceError::Bounded { bound: N, provided: result.len() }.into())
+            return Err(InstanceError::bounded::<Self>(N, result.len()).into())
         }
         Ok(Self(result))
     }
 }
 
+impl<const N: usize> DeserializeWithLength for Bitlist<N> {
+    fn deserialize_with_length(encoding: &[u8], len: usize) -> Result<Self, DeserializeError> {
+        if len > N {
+            return Err(InstanceError::bounded::<Self>(N, len).into())
+        }
+        // the bits and the delimiting bit
+        let expected = len / 8 + 1;
+        if encoding.len() < expected {
+            return Err(DeserializeError::ExpectedFurtherInput {
+                provided: encoding.len(),
+                expected,
+            })
+        }
+        if encoding.len() > expected {
+            return Err(DeserializeError::AdditionalInput { provided: encoding.len(), expected })
+        }
+        let result = Self::deserialize(encoding)?;
+        if result.len() != len {
+            return Err(InstanceError::exact::<Self>(len, result.len()).into())
+        }
+        Ok(result)
+    }
+}
+
 impl<const N: usize> Merkleized for Bitlist<N> {
-    fn hash_tree_root(&mut self) -> Result<Node, MerkleizationError> {
+    fn hash_tree_root_with<H: MerkleHasher>(
+        &mut self,
+        context: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
         let chunks = self.pack_bits()?;
-        let data_root = merkleize(&chunks, Some((N + 255) / 256))?;
-        Ok(mix_in_length(&data_root, self.len()))
+        let data_root = merkleize_with(&chunks, Some(Self::CHUNK_COUNT), context)?;
+        Ok(mix_in_length(&data_root, self.len(), context))
+    }
+
+    fn leaves<H: MerkleHasher>(&mut self, _: &Context<H>) -> Result<Vec<Node>, MerkleizationError> {
+        Ok(chunks_to_nodes(&self.pack_bits()?))
+    }
+
+    const CHUNK_COUNT: usize = chunks_for_bits(N);
+
+    fn merkle_limit() -> Option<usize> {
+        Some(Self::chunk_count())
     }
 }
 
-impl<const N: usize> SimpleSerialize for Bitlist<N> {}
+impl<const N: usize> SimpleSerialize for Bitlist<N> {
+    // the bits and the delimiting bit
+    const MAX_SIZE: usize = N / 8 + 1;
+
+    const NESTING_DEPTH: usize = 1;
+
+    fn generalized_index_from(
+        root: GeneralizedIndex,
+        path: &[PathElement<'_>],
+    ) -> Result<GeneralizedIndex, PathError> {
+        bits_generalized_index::<Self>(root, path, N, Self::tree_depth(), true)
+    }
+
+    fn is_default(&self) -> bool {
+        self.is_empty()
+    }
+
+    fn default_hash_tree_root_with<H: MerkleHasher>(
+        context: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
+        let data_root = merkleize_with(&[], Some(Self::CHUNK_COUNT), context)?;
+        Ok(mix_in_length(&data_root, 0, context))
+    }
+
+    fn ssz_heap_size(&self) -> usize {
+        self.0.capacity() / 8
+    }
+}
 
 impl<const N: usize> TryFrom<&[u8]> for Bitlist<N> {
     type Error = DeserializeError;
@@ -205,7 +403,7 @@ impl<const N: usize> FromIterator<bool> for Bitlist<N> {
     {
         let mut result: Bitlist<N> = Default::default();
         for bit in iter.into_iter().take(N) {
-            result.push(bit);
+            result.0.push(bit);
         }
         result
     }
@@ -226,21 +424,21 @@ mod tests {
         assert_eq!(encoding, expected);
 
         let mut value: Bitlist<COUNT> = Bitlist::default();
-        value.push(false);
-        value.push(true);
+        value.push(false).unwrap();
+        value.push(true).unwrap();
         let encoding = serialize(&value).expect("can encode");
         let expected = [6u8];
         assert_eq!(encoding, expected);
 
         let mut value: Bitlist<COUNT> = Bitlist::default();
-        value.push(false);
-        value.push(false);
-        value.push(false);
-        value.push(true);
-        value.push(true);
-        value.push(false);
-        value.push(false);
-        value.push(false);
+        value.push(false).unwrap();
+        value.push(false).unwrap();
+        value.push(false).unwrap();
+        value.push(true).unwrap();
+        value.push(true).unwrap();
+        value.push(false).unwrap();
+        value.push(false).unwrap();
+        value.push(false).unwrap();
         assert!(!value.get(0).expect("test data correct"));
         assert!(value.get(3).expect("test data correct"));
         assert!(value.get(4).expect("test data correct"));
@@ -275,6 +473,118 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn test_raw_bytes() {
+        let value =
+            Bitlist::<COUNT>::from_iter([true, false, false, true, true, false, true, true, true]);
+        assert_eq!(value.as_raw_bytes(), [0b1101_1001u8, 0b1u8]);
+
+        let recovered = Bitlist::<COUNT>::from_raw_bytes(value.as_raw_bytes(), value.len())
+            .expect("can decode");
+        assert_eq!(recovered, value);
+
+        assert!(Bitlist::<COUNT>::from_raw_bytes(&[0b0000_0011u8], 1).is_err());
+        assert!(Bitlist::<COUNT>::from_raw_bytes(&[0u8, 0u8], 8).is_err());
+        assert!(Bitlist::<4>::from_raw_bytes(&[0u8], 5).is_err());
+    }
+
+    #[test]
+    fn test_serialize_clears_stale_bits_after_pop() {
+        // NOTE: `pop` leaves the removed bits in the last byte of the storage
+        let mut value = Bitlist::<COUNT>::from_iter([false, true, true]);
+        value.pop();
+        value.pop();
+        let encoding = serialize(&value).expect("can encode");
+        assert_eq!(encoding, [0b10u8]);
+
+        let mut expected = Bitlist::<COUNT>::from_iter([false]);
+        assert_eq!(value.hash_tree_root().unwrap(), expected.hash_tree_root().unwrap());
+    }
+
+    #[test]
+    fn push_bitlist_over_limit() {
+        let mut value = Bitlist::<2>::from_iter([true]);
+        value.push(false).unwrap();
+        assert!(value.push(true).is_err());
+        assert_eq!(value.len(), 2);
+
+        value.set(1, true);
+        assert_eq!(serialize(&value).unwrap(), [0b111u8]);
+    }
+
+    #[test]
+    fn resize_and_clear_bitlist() {
+        let mut value = Bitlist::<12>::default();
+        value.resize(10, true).unwrap();
+        assert_eq!(value.count_ones(), 10);
+        value.resize(4, false).unwrap();
+        assert_eq!(serialize(&value).unwrap(), [0b11111u8]);
+        assert!(value.resize(13, false).is_err());
+        assert_eq!(value.len(), 4);
+        assert_eq!(value.set(4, true), None);
+
+        value.clear();
+        assert!(value.is_empty());
+        assert_eq!(serialize(&value).unwrap(), [1u8]);
+    }
+
+    #[test]
+    fn test_bit_counting() {
+        let mut bits = Bitlist::<200>::from_iter((0..150).map(|i| i % 3 == 0 || i == 149));
+        let mut other = Bitlist::<200>::from_iter((0..130).map(|i| i % 2 == 0));
+        assert_eq!(bits.count_ones(), bits.iter().filter(|bit| **bit).count());
+        assert_eq!(bits.count_ones(), 51);
+        assert_eq!(bits.overlap_count(&other), (0..130).filter(|i| i % 6 == 0).count());
+        assert_eq!(other.overlap_count(&bits), bits.overlap_count(&other));
+        assert!(bits.any() && !bits.all());
+
+        // bits past the end are not counted, even if left set in the last byte
+        bits.pop();
+        other.resize(65, false).unwrap();
+        assert_eq!(bits.count_ones(), 50);
+        assert_eq!(other.count_ones(), 33);
+        assert_eq!(bits.overlap_count(&other), (0..65).filter(|i| i % 6 == 0).count());
+
+        let ones = Bitlist::<200>::from_iter([true; 70]);
+        assert!(ones.all() && ones.any());
+        assert_eq!(ones.overlap_count(&other), other.count_ones());
+        let empty = Bitlist::<200>::default();
+        assert!(empty.all() && !empty.any());
+        assert_eq!(empty.count_ones(), 0);
+        assert_eq!(empty.overlap_count(&ones), 0);
+    }
+
+    #[test]
+    fn test_bitlist_with_zero_bound() {
+        let mut value = Bitlist::<0>::default();
+        assert_eq!(serialize(&value).unwrap(), [1u8]);
+        assert_eq!(Bitlist::<0>::deserialize(&[1u8]).unwrap(), value);
+        assert!(Bitlist::<0>::deserialize(&[3u8]).is_err());
+        assert!(value.push(true).is_err());
+
+        let expected = mix_in_length(&Node::default(), 0, Context::global());
+        assert_eq!(value.hash_tree_root().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_capacity_consts() {
+        assert_eq!(Bitlist::<COUNT>::MAX_BITS, COUNT);
+        assert_eq!(Bitlist::<256>::CHUNK_COUNT, 1);
+        assert_eq!(Bitlist::<257>::CHUNK_COUNT, 2);
+        assert_eq!(crate::Bitvector::<257>::LENGTH, 257);
+        assert_eq!(crate::Bitvector::<257>::CHUNK_COUNT, 2);
+    }
+
+    #[test]
+    fn encode_bitlist_over_limit() {
+        let mut value = Bitlist::<2>::from_iter([true, false]);
+        assert!(value.push(true).is_err());
+        // NOTE: a value over the limit can no longer be built through the public API
+        value.0.push(true);
+        let result = serialize(&value);
+        assert!(matches!(result, Err(SerializeError::LimitExceeded { bound: 2, provided: 3 })));
+    }
+
     #[test]
     fn roundtrip_bitlist() {
         let input = Bitlist::<COUNT>::from_iter(vec![
@@ -1,14 +1,18 @@
 use crate::{
-    de::{Deserialize, DeserializeError},
-    error::TypeError,
+    de::{Deserialize, DeserializeError, DeserializeWithLength},
+    error::{InstanceError, OutOfBounds, TypeError},
     lib::*,
-    merkleization::{merkleize, pack_bytes, MerkleizationError, Merkleized, Node},
+    merkleization::{
+        bits_generalized_index, chunks_for_bits, chunks_to_nodes, merkleize_with, pack_bytes,
+        Context, GeneralizedIndex, MerkleHasher, MerkleizationError, Merkleized, Node, PathElement,
+        PathError,
+    },
     ser::{Serialize, SerializeError},
     SimpleSerialize, Sized,
 };
 use bitvec::{
     field::BitField,
-    prelude::{BitVec, Lsb0},
+    prelude::{BitSlice, BitVec, Lsb0},
 };
 
 fn byte_length(bound: usize) -> usize {
@@ -71,17 +75,25 @@ impl<const N: usize> fmt::Debug for Bitvector<N> {
 
 impl<const N: usize> Default for Bitvector<N> {
     fn default() -> Self {
-        assert!(N > 0);
+        assert!(N > 0, "a `Bitvector` of length `0` is illegal");
         Self(BitVec::repeat(false, N))
     }
 }
 
 impl<const N: usize> Bitvector<N> {
+    /// The number of bits in this `Bitvector`.
+    pub const LENGTH: usize = N;
+
     /// Return the bit at `index`. `None` if index is out-of-bounds.
-    pub fn get(&mut self, index: usize) -> Option<bool> {
+    pub fn get(&self, index: usize) -> Option<bool> {
         self.0.get(index).map(|value| *value)
     }
 
+    /// Return the bit at `index`, or an error with the length if out of bounds.
+    pub fn try_at(&self, index: usize) -> Result<bool, OutOfBounds> {
+        self.get(index).ok_or(OutOfBounds { index, len: self.len() })
+    }
+
     /// Set the bit at `index` to `value`. Return the previous value
     /// or `None` if index is out-of-bounds.
     pub fn set(&mut self, index: usize, value: bool) -> Option<bool> {
@@ -92,6 +104,24 @@ impl<const N: usize> Bitvector<N> {
         })
     }
 
+    /// Return the packed bits as bytes, least significant bit first.
+    ///
+    /// Any bits in the last byte past `N` are not part of the value and should be ignored.
+    pub fn as_raw_bytes(&self) -> &[u8] {
+        self.0.as_raw_slice()
+    }
+
+    /// Construct a `Bitvector` from the packed representation of its `len` bits,
+    /// least significant bit first (i.e. the inverse of `as_raw_bytes`).
+    ///
+    /// `len` must equal `N` and any bits in the last byte past `N` must be zero.
+    pub fn from_raw_bytes(bytes: &[u8], len: usize) -> Result<Self, DeserializeError> {
+        if len != N {
+            return Err(InstanceError::exact::<Self>(N, len).into())
+        }
+        Self::deserialize(bytes)
+    }
+
     fn pack_bits(&self) -> Result<Vec<u8>, MerkleizationError> {
         let mut data = vec![];
         let _ = self.serialize(&mut data)?;
@@ -100,8 +130,10 @@ impl<const N: usize> Bitvector<N> {
     }
 }
 
+// NOTE: deref to a `BitSlice` rather than the inner `BitVec` so the bits
+// can change but the length can not, except through methods that enforce `N`.
 impl<const N: usize> Deref for Bitvector<N> {
-    type Target = BitvectorInner;
+    type Target = BitSlice<u8, Lsb0>;
 
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -174,14 +206,55 @@ impl<const N: usize> Deserialize for Bitvector<N> {
     }
 }
 
+impl<const N: usize> DeserializeWithLength for Bitvector<N> {
+    fn deserialize_with_length(encoding: &[u8], len: usize) -> Result<Self, DeserializeError> {
+        if len != N {
+            return Err(InstanceError::exact::<Self>(N, len).into())
+        }
+        Self::deserialize(encoding)
+    }
+}
+
 impl<const N: usize> Merkleized for Bitvector<N> {
-    fn hash_tree_root(&mut self) -> Result<Node, MerkleizationError> {
+    fn hash_tree_root_with<H: MerkleHasher>(
+        &mut self,
+        context: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
         let chunks = self.pack_bits()?;
-        merkleize(&chunks, Some((N + 255) / 256))
+        merkleize_with(&chunks, Some(Self::CHUNK_COUNT), context)
     }
+
+    fn leaves<H: MerkleHasher>(&mut self, _: &Context<H>) -> Result<Vec<Node>, MerkleizationError> {
+        Ok(chunks_to_nodes(&self.pack_bits()?))
+    }
+
+    const CHUNK_COUNT: usize = chunks_for_bits(N);
 }
 
-impl<const N: usize> SimpleSerialize for Bitvector<N> {}
+impl<const N: usize> SimpleSerialize for Bitvector<N> {
+    const MAX_SIZE: usize = (N + 7) / 8;
+
+    const IS_VARIABLE_SIZE: bool = false;
+
+    const NESTING_DEPTH: usize = 1;
+
+    fn generalized_index_from(
+        root: GeneralizedIndex,
+        path: &[PathElement<'_>],
+    ) -> Result<GeneralizedIndex, PathError> {
+        bits_generalized_index::<Self>(root, path, N, Self::tree_depth(), false)
+    }
+
+    fn default_hash_tree_root_with<H: MerkleHasher>(
+        context: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
+        merkleize_with(&[], Some(Self::CHUNK_COUNT), context)
+    }
+
+    fn ssz_heap_size(&self) -> usize {
+        self.0.capacity() / 8
+    }
+}
 
 impl<const N: usize> TryFrom<&[u8]> for Bitvector<N> {
     type Error = DeserializeError;
@@ -256,6 +329,21 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn test_raw_bytes() {
+        let value = Bitvector::<COUNT>::from_iter([
+            false, false, false, true, true, false, false, false, true, false, false, false,
+        ]);
+        assert_eq!(value.as_raw_bytes(), [24u8, 1u8]);
+
+        let recovered =
+            Bitvector::<COUNT>::from_raw_bytes(value.as_raw_bytes(), COUNT).expect("can decode");
+        assert_eq!(recovered, value);
+
+        assert!(Bitvector::<COUNT>::from_raw_bytes(&[24u8, 1u8], COUNT - 1).is_err());
+        assert!(Bitvector::<COUNT>::from_raw_bytes(&[24u8, 0b1_0000u8], COUNT).is_err());
+    }
+
     #[test]
     fn roundtrip_bitvector() {
         let input = Bitvector::<COUNT>::from_iter(vec![
@@ -266,4 +354,13 @@ mod tests {
         let recovered = Bitvector::<COUNT>::deserialize(&buffer).expect("can decode");
         assert_eq!(input, recovered);
     }
+
+    #[test]
+    fn test_fallible_access() {
+        let mut value = Bitvector::<COUNT>::default();
+        value.set(3, true);
+        assert_eq!(value.get(3), Some(true));
+        assert_eq!(value.try_at(2), Ok(false));
+        assert_eq!(value.try_at(COUNT), Err(OutOfBounds { index: COUNT, len: COUNT }));
+    }
 }
@@ -1,7 +1,7 @@
 use crate::{
     de::{Deserialize, DeserializeError},
     lib::*,
-    merkleization::{MerkleizationError, Merkleized, Node},
+    merkleization::{Context, MerkleHasher, MerkleizationError, Merkleized, Node},
     ser::{Serialize, SerializeError},
     SimpleSerialize, Sized,
 };
@@ -39,19 +39,42 @@ impl Deserialize for bool {
 }
 
 impl Merkleized for bool {
-    fn hash_tree_root(&mut self) -> Result<Node, MerkleizationError> {
+    fn hash_tree_root_with<H: MerkleHasher>(
+        &mut self,
+        _: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
         let mut node = Node::default();
         if *self {
             node.as_mut()[0] = 1;
         }
         Ok(node)
     }
+
+    const CHUNK_COUNT: usize = 1;
 }
 
 impl SimpleSerialize for bool {
+    const MAX_SIZE: usize = 1;
+
+    const IS_VARIABLE_SIZE: bool = false;
+
+    const NESTING_DEPTH: usize = 0;
+
+    const PACKED_PER_CHUNK: usize = 32;
+
     fn is_composite_type() -> bool {
         false
     }
+
+    fn is_default(&self) -> bool {
+        !*self
+    }
+
+    fn default_hash_tree_root_with<H: MerkleHasher>(
+        _: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
+        Ok(Node::default())
+    }
 }
 
 #[cfg(test)]
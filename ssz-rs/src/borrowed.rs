@@ -0,0 +1,160 @@
+use crate::{
+    de::DeserializeError,
+    error::InstanceError,
+    lib::*,
+    list::List,
+    merkleization::{Node, BYTES_PER_CHUNK},
+    vector::Vector,
+};
+
+/// A value that can be decoded as a view borrowing from the encoding, without copying it.
+///
+/// This suits decoders that only read byte fields, e.g. to forward roots, where copying
+/// each field into an owned `Vector<u8, N>` or `List<u8, N>` is wasted work.
+///
+/// ```rust
+/// use ssz_rs::prelude::*;
+/// use ssz_rs::{ByteListRef, ByteVectorRef, DeserializeBorrowed};
+///
+/// let encoding = [7u8; 32];
+/// let root = ByteVectorRef::<32>::deserialize_borrowed(&encoding).unwrap();
+/// assert_eq!(Node::from(root), Node::try_from(encoding.as_ref()).unwrap());
+///
+/// let data = ByteListRef::<64>::deserialize_borrowed(&encoding[..5]).unwrap();
+/// assert_eq!(data.to_list(), List::<u8, 64>::try_from(vec![7u8; 5]).unwrap());
+/// assert!(ByteListRef::<4>::deserialize_borrowed(&encoding).is_err());
+/// ```
+pub trait DeserializeBorrowed<'a>: core::marker::Sized {
+    /// Decode a view of `encoding`, which must outlive the view.
+    fn deserialize_borrowed(encoding: &'a [u8]) -> Result<Self, DeserializeError>;
+}
+
+/// A borrowed view of the encoding of a `Vector<u8, N>`, e.g. a `Node` when `N` is 32.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ByteVectorRef<'a, const N: usize>(&'a [u8; N]);
+
+impl<'a, const N: usize> ByteVectorRef<'a, N> {
+    pub fn as_array(&self) -> &'a [u8; N] {
+        self.0
+    }
+
+    /// Copy the bytes into a `Vector`.
+    pub fn to_vector(&self) -> Vector<u8, N> {
+        Vector::from(*self.0)
+    }
+}
+
+impl<'a, const N: usize> Deref for ByteVectorRef<'a, N> {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+impl<'a, const N: usize> DeserializeBorrowed<'a> for ByteVectorRef<'a, N> {
+    fn deserialize_borrowed(encoding: &'a [u8]) -> Result<Self, DeserializeError> {
+        match <&[u8; N]>::try_from(encoding) {
+            Ok(bytes) => Ok(Self(bytes)),
+            Err(_) if encoding.len() < N => Err(DeserializeError::ExpectedFurtherInput {
+                provided: encoding.len(),
+                expected: N,
+            }),
+            Err(_) => {
+                Err(DeserializeError::AdditionalInput { provided: encoding.len(), expected: N })
+            }
+        }
+    }
+}
+
+impl From<ByteVectorRef<'_, BYTES_PER_CHUNK>> for Node {
+    fn from(bytes: ByteVectorRef<'_, BYTES_PER_CHUNK>) -> Self {
+        Node::try_from(bytes.0.as_ref()).expect("is one chunk")
+    }
+}
+
+/// A borrowed view of the encoding of a `List<u8, N>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ByteListRef<'a, const N: usize>(&'a [u8]);
+
+impl<'a, const N: usize> ByteListRef<'a, N> {
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.0
+    }
+
+    /// Copy the bytes into a `List`.
+    pub fn to_list(&self) -> List<u8, N> {
+        List::try_from(self.0.to_vec()).expect("length was checked when decoding")
+    }
+}
+
+impl<'a, const N: usize> Deref for ByteListRef<'a, N> {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+impl<'a, const N: usize> DeserializeBorrowed<'a> for ByteListRef<'a, N> {
+    fn deserialize_borrowed(encoding: &'a [u8]) -> Result<Self, DeserializeError> {
+        if encoding.len() > N {
+            return Err(InstanceError::bounded::<Self>(N, encoding.len()).into())
+        }
+        Ok(Self(encoding))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prelude::*, Container};
+
+    #[derive(Default, Debug, SimpleSerialize)]
+    struct Block {
+        slot: u64,
+        parent_root: Node,
+        graffiti: List<u8, 32>,
+    }
+
+    #[test]
+    fn test_borrow_fields_of_encoding() {
+        let block = Block {
+            slot: 3,
+            parent_root: Node::try_from([5u8; 32].as_ref()).unwrap(),
+            graffiti: List::try_from(b"hello".to_vec()).unwrap(),
+        };
+        let encoding = serialize(&block).unwrap();
+
+        let range = Block::field_range(&encoding, "parent_root").unwrap();
+        let parent_root = ByteVectorRef::<32>::deserialize_borrowed(&encoding[range]).unwrap();
+        assert_eq!(Node::from(parent_root), block.parent_root);
+        assert_eq!(parent_root.to_vector().as_slice(), [5u8; 32]);
+
+        let range = Block::field_range(&encoding, "graffiti").unwrap();
+        let graffiti = ByteListRef::<32>::deserialize_borrowed(&encoding[range]).unwrap();
+        assert_eq!(graffiti.as_bytes(), b"hello");
+        assert_eq!(graffiti.to_list(), block.graffiti);
+    }
+
+    #[test]
+    fn test_invalid_lengths() {
+        assert!(matches!(
+            ByteVectorRef::<4>::deserialize_borrowed(&[1, 2, 3]),
+            Err(DeserializeError::ExpectedFurtherInput { provided: 3, expected: 4 })
+        ));
+        assert!(matches!(
+            ByteVectorRef::<2>::deserialize_borrowed(&[1, 2, 3]),
+            Err(DeserializeError::AdditionalInput { provided: 3, expected: 2 })
+        ));
+        assert!(matches!(
+            ByteListRef::<2>::deserialize_borrowed(&[1, 2, 3]),
+            Err(DeserializeError::InvalidInstance(InstanceError::Bounded {
+                bound: 2,
+                provided: 3,
+                ..
+            }))
+        ));
+        assert!(ByteListRef::<2>::deserialize_borrowed(&[]).unwrap().is_empty());
+    }
+}
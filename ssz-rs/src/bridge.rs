@@ -0,0 +1,320 @@
+//! Conversions between SSZ types and their counterparts in other schemas, e.g. the structs
+//! generated by `prost` for protobuf messages, for services translating between their APIs and
+//! the SSZ wire types.
+//!
+//! [`FromSszBridge`] builds an SSZ value from its counterpart, checking the bounds of the SSZ
+//! types, and [`IntoSszBridge`] converts an SSZ value into its counterpart. Both are implemented
+//! for the conversions a protobuf schema needs:
+//!
+//! - `uint8` and `uint16` from the `uint32` of protobuf, other integers and booleans as is;
+//! - a `List` or a `Vector` from a `Vec` of the counterparts of its elements, so bytes are a
+//!   `Vec<u8>` as in `prost`;
+//! - a `Node`, `U256`, `Bitvector` or `Bitlist` from bytes of its SSZ encoding;
+//! - an `Option` from an `Option` of the counterpart.
+//!
+//! `#[derive(SszBridge)]` implements both for a container with a `#[bridge(Type)]` attribute
+//! naming its counterpart, which must have fields of the same names. As nested messages are
+//! optional in protobuf, the derive also converts the container from and into an `Option` of
+//! its counterpart, failing on a missing message.
+//!
+//! ```rust
+//! use ssz_rs::prelude::*;
+//! use ssz_rs::bridge::{FromSszBridge, IntoSszBridge};
+//!
+//! // the structs generated for the protobuf messages
+//! mod proto {
+//!     #[derive(Default, Clone)]
+//!     pub struct Checkpoint {
+//!         pub epoch: u64,
+//!         pub root: Vec<u8>,
+//!     }
+//!
+//!     #[derive(Default, Clone)]
+//!     pub struct Vote {
+//!         pub source: Option<Checkpoint>,
+//!         pub validators: Vec<u32>,
+//!     }
+//! }
+//!
+//! #[derive(Default, Debug, PartialEq, Eq, SimpleSerialize, SszBridge)]
+//! #[bridge(proto::Checkpoint)]
+//! struct Checkpoint {
+//!     epoch: u64,
+//!     root: Node,
+//! }
+//!
+//! #[derive(Default, Debug, PartialEq, Eq, SimpleSerialize, SszBridge)]
+//! #[bridge(proto::Vote)]
+//! struct Vote {
+//!     source: Checkpoint,
+//!     validators: List<u16, 4>,
+//! }
+//!
+//! let message = proto::Vote {
+//!     source: Some(proto::Checkpoint { epoch: 3, root: vec![0xaa; 32] }),
+//!     validators: vec![1, 2],
+//! };
+//! let vote = Vote::from_bridge(message.clone()).unwrap();
+//! assert_eq!(vote.validators.as_ref(), &[1, 2]);
+//! let message: proto::Vote = vote.into_bridge();
+//! assert_eq!(message.source.unwrap().root, vec![0xaa; 32]);
+//!
+//! let error = Vote::from_bridge(proto::Vote { source: None, validators: vec![1] });
+//! assert_eq!(error.unwrap_err().path, "source");
+//! ```
+use crate::{
+    bitlist::Bitlist,
+    bitvector::Bitvector,
+    de::Deserialize,
+    error::{convert_elements, Error, ErrorPath, InstanceError},
+    lib::*,
+    list::List,
+    merkleization::Node,
+    ser::Serialize,
+    uint::U256,
+    vector::Vector,
+    SimpleSerialize,
+};
+
+/// The reason a value could not be converted from its counterpart.
+#[derive(Debug)]
+pub enum BridgeErrorKind {
+    /// An optional message is missing.
+    Missing,
+    /// An integer does not fit in the SSZ type.
+    OutOfRange,
+    /// The value is not an instance of the SSZ type, e.g. a list beyond its bound.
+    Invalid(Error),
+}
+
+/// An error converting a value from its counterpart, with the path to the part which could not
+/// be converted, e.g. `source.root`.
+#[derive(Debug)]
+pub struct BridgeError {
+    pub path: String,
+    pub kind: BridgeErrorKind,
+}
+
+impl BridgeError {
+    pub fn new(kind: BridgeErrorKind) -> Self {
+        Self { path: String::new(), kind }
+    }
+
+    /// Prefix the path of the error with the field or index `segment` of the enclosing value.
+    pub fn within(self, segment: &str) -> Self {
+        ErrorPath::within(self, segment)
+    }
+}
+
+impl ErrorPath for BridgeError {
+    fn path_mut(&mut self) -> &mut String {
+        &mut self.path
+    }
+}
+
+impl From<Error> for BridgeError {
+    fn from(err: Error) -> Self {
+        Self::new(BridgeErrorKind::Invalid(err))
+    }
+}
+
+impl Display for BridgeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() { "the value" } else { &self.path };
+        match &self.kind {
+            BridgeErrorKind::Missing => write!(f, "missing message at {path}"),
+            BridgeErrorKind::OutOfRange => write!(f, "integer out of range at {path}"),
+            BridgeErrorKind::Invalid(err) => write!(f, "invalid value at {path}: {err}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BridgeError {}
+
+/// A type which can be built from its counterpart `P` in another schema.
+pub trait FromSszBridge<P>: core::marker::Sized {
+    fn from_bridge(other: P) -> Result<Self, BridgeError>;
+}
+
+/// A type which can be converted into its counterpart `P` in another schema.
+pub trait IntoSszBridge<P> {
+    fn into_bridge(self) -> P;
+}
+
+macro_rules! define_bridge_as_is {
+    ($($ty:ty),*) => {
+        $(
+            impl FromSszBridge<$ty> for $ty {
+                fn from_bridge(other: $ty) -> Result<Self, BridgeError> {
+                    Ok(other)
+                }
+            }
+
+            impl IntoSszBridge<$ty> for $ty {
+                fn into_bridge(self) -> $ty {
+                    self
+                }
+            }
+        )*
+    };
+}
+
+define_bridge_as_is!(u8, u16, u32, u64, u128, usize, bool);
+
+macro_rules! define_bridge_from_u32 {
+    ($($ty:ty),*) => {
+        $(
+            impl FromSszBridge<u32> for $ty {
+                fn from_bridge(other: u32) -> Result<Self, BridgeError> {
+                    <$ty>::try_from(other).map_err(|_| BridgeError::new(BridgeErrorKind::OutOfRange))
+                }
+            }
+
+            impl IntoSszBridge<u32> for $ty {
+                fn into_bridge(self) -> u32 {
+                    self.into()
+                }
+            }
+        )*
+    };
+}
+
+define_bridge_from_u32!(u8, u16);
+
+impl<T, P, const N: usize> FromSszBridge<Vec<P>> for List<T, N>
+where
+    T: SimpleSerialize + FromSszBridge<P>,
+{
+    fn from_bridge(other: Vec<P>) -> Result<Self, BridgeError> {
+        if other.len() > N {
+            let err = InstanceError::bounded::<Self>(N, other.len());
+            return Err(Error::Instance(err).into())
+        }
+        let elements = convert_elements(other, T::from_bridge)?;
+        Ok(Self::try_from(elements).map_err(|(_, err)| err)?)
+    }
+}
+
+impl<T, P, const N: usize> IntoSszBridge<Vec<P>> for List<T, N>
+where
+    T: SimpleSerialize + IntoSszBridge<P>,
+{
+    fn into_bridge(self) -> Vec<P> {
+        self.into_inner().into_iter().map(IntoSszBridge::into_bridge).collect()
+    }
+}
+
+impl<T, P, const N: usize> FromSszBridge<Vec<P>> for Vector<T, N>
+where
+    T: SimpleSerialize + FromSszBridge<P>,
+{
+    fn from_bridge(other: Vec<P>) -> Result<Self, BridgeError> {
+        if other.len() != N {
+            let err = InstanceError::exact::<Self>(N, other.len());
+            return Err(Error::Instance(err).into())
+        }
+        let elements = convert_elements(other, T::from_bridge)?;
+        Ok(Self::try_from(elements).map_err(|(_, err)| err)?)
+    }
+}
+
+impl<T, P, const N: usize> IntoSszBridge<Vec<P>> for Vector<T, N>
+where
+    T: SimpleSerialize + IntoSszBridge<P>,
+{
+    fn into_bridge(self) -> Vec<P> {
+        self.into_inner().into_iter().map(IntoSszBridge::into_bridge).collect()
+    }
+}
+
+macro_rules! define_bridge_as_encoding {
+    ($(<$(const $n:ident: usize),*> $ty:ty),*) => {
+        $(
+            impl<$(const $n: usize),*> FromSszBridge<Vec<u8>> for $ty {
+                fn from_bridge(other: Vec<u8>) -> Result<Self, BridgeError> {
+                    Ok(Self::deserialize(&other).map_err(Error::Deserialize)?)
+                }
+            }
+
+            impl<$(const $n: usize),*> IntoSszBridge<Vec<u8>> for $ty {
+                fn into_bridge(self) -> Vec<u8> {
+                    let mut buffer = vec![];
+                    self.serialize(&mut buffer).expect("can serialize a valid value");
+                    buffer
+                }
+            }
+        )*
+    };
+}
+
+define_bridge_as_encoding!(<> Node, <> U256, <const N: usize> Bitvector<N>, <const N: usize> Bitlist<N>);
+
+impl<T: FromSszBridge<P>, P> FromSszBridge<Option<P>> for Option<T> {
+    fn from_bridge(other: Option<P>) -> Result<Self, BridgeError> {
+        other.map(T::from_bridge).transpose()
+    }
+}
+
+impl<T: IntoSszBridge<P>, P> IntoSszBridge<Option<P>> for Option<T> {
+    fn into_bridge(self) -> Option<P> {
+        self.map(IntoSszBridge::into_bridge)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        prelude::*,
+        test_utils::{proto, Inner, Message},
+    };
+
+    fn message() -> proto::Message {
+        let inner = |a| proto::Inner { a, b: vec![0b101] };
+        proto::Message {
+            index: 7,
+            inners: vec![inner(1), inner(2)],
+            parent: None,
+            data: vec![1, 2, 3, 4],
+            balance: serialize(&U256::from(9u64)).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_bridge() {
+        let value = Message::from_bridge(message()).unwrap();
+        assert_eq!(value.index, 7);
+        assert_eq!(value.inners[1].b, Bitlist::<5>::from_iter([true, false]));
+        assert_eq!(value.balance, U256::from(9u64));
+        assert_eq!(IntoSszBridge::<proto::Message>::into_bridge(value), message());
+
+        let parent = Some(proto::Inner { a: 3, b: vec![1] });
+        let value = Message::from_bridge(proto::Message { parent: parent.clone(), ..message() });
+        let message: proto::Message = value.unwrap().into_bridge();
+        assert_eq!(message.parent, parent);
+    }
+
+    #[test]
+    fn test_bridge_errors() {
+        let check = |message: proto::Message, path: &str| {
+            let err = Message::from_bridge(message).unwrap_err();
+            assert_eq!(err.path, path, "{err}");
+        };
+        check(proto::Message { index: 256, ..message() }, "index");
+        check(proto::Message { data: vec![1, 2, 3], ..message() }, "data");
+        check(proto::Message { balance: vec![0; 31], ..message() }, "balance");
+        let mut invalid = message();
+        invalid.inners[1].b = vec![0];
+        check(invalid, "inners[1].b");
+        invalid = message();
+        invalid.inners[0].a = 256;
+        check(invalid, "inners[0].a");
+        invalid = message();
+        invalid.inners.push(proto::Inner::default());
+        check(invalid, "inners");
+
+        let err = Inner::from_bridge(None::<proto::Inner>).unwrap_err();
+        assert!(matches!(err.kind, BridgeErrorKind::Missing));
+    }
+}
@@ -0,0 +1,321 @@
+use crate::{list::List, vector::Vector};
+
+/// A fixed number of bytes, e.g. a BLS public key as a `ByteVector<48>`.
+pub type ByteVector<const N: usize> = Vector<u8, N>;
+
+/// A variable number of bytes up to a bound, e.g. the extra data of an execution payload as a
+/// `ByteList<32>`.
+pub type ByteList<const N: usize> = List<u8, N>;
+
+/// Define a newtype `$name` over a `ByteVector<$len>`, with the encoding and root of the
+/// `ByteVector`, written as `0x`-prefixed hex by `Display`, `Debug` and `serde`, and parsed
+/// with or without the prefix by `FromStr`.
+#[cfg_attr(
+    feature = "subtle",
+    doc = " With the `subtle` feature, it is also compared in constant time by \
+           [`ConstantTimeEq`](crate::ConstantTimeEq)."
+)]
+///
+/// Attributes and a visibility can precede the name, e.g. doc comments or more derives.
+///
+/// ```rust
+/// use ssz_rs::{define_bytes_type, prelude::*};
+///
+/// define_bytes_type!(
+///     /// A BLS signature.
+///     pub Signature,
+///     96
+/// );
+///
+/// let signature: Signature = format!("0x{}", "ab".repeat(96)).parse().unwrap();
+/// assert_eq!(signature.as_ref(), &[0xab; 96][..]);
+/// assert_eq!(signature.as_bytes().len(), Signature::LENGTH);
+/// assert_eq!(signature.to_string(), format!("0x{}", "ab".repeat(96)));
+/// assert_eq!(serialize(&signature).unwrap(), vec![0xab; 96]);
+/// assert_eq!(Signature::try_from(&[0xab; 96][..]).unwrap(), signature);
+/// assert!("0xabcd".parse::<Signature>().is_err());
+/// ```
+#[macro_export]
+macro_rules! define_bytes_type {
+    ($(#[$attr:meta])* $vis:vis $name:ident, $len:expr) => {
+        $(#[$attr])*
+        #[derive(Default, Clone, PartialEq, Eq)]
+        $vis struct $name(pub $crate::ByteVector<$len>);
+
+        // NOTE: the helpers may be unused by the crate defining the type
+        #[allow(dead_code)]
+        impl $name {
+            pub const LENGTH: usize = $len;
+
+            pub fn as_bytes(&self) -> &[u8] {
+                &self.0
+            }
+        }
+
+        impl ::core::convert::From<[u8; $len]> for $name {
+            fn from(bytes: [u8; $len]) -> Self {
+                Self(bytes.into())
+            }
+        }
+
+        impl ::core::convert::TryFrom<&[u8]> for $name {
+            type Error = $crate::DeserializeError;
+
+            fn try_from(bytes: &[u8]) -> ::core::result::Result<Self, Self::Error> {
+                <Self as $crate::Deserialize>::deserialize(bytes)
+            }
+        }
+
+        impl ::core::convert::AsRef<[u8]> for $name {
+            fn as_ref(&self) -> &[u8] {
+                &self.0
+            }
+        }
+
+        impl ::core::convert::AsMut<[u8]> for $name {
+            fn as_mut(&mut self) -> &mut [u8] {
+                self.0.as_mut_slice()
+            }
+        }
+
+        impl ::core::fmt::LowerHex for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                if f.alternate() {
+                    f.write_str("0x")?;
+                }
+                f.write_str(&$crate::hex::encode(self))
+            }
+        }
+
+        impl ::core::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                write!(f, "{:#x}", self)
+            }
+        }
+
+        impl ::core::fmt::Debug for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                write!(f, "{}({:#x})", stringify!($name), self)
+            }
+        }
+
+        impl ::core::str::FromStr for $name {
+            type Err = $crate::hex::HexError;
+
+            fn from_str(s: &str) -> ::core::result::Result<Self, Self::Err> {
+                $crate::hex::decode_to_array::<$len>(s).map(Self::from)
+            }
+        }
+
+        impl $crate::Sized for $name {
+            fn is_variable_size() -> bool {
+                false
+            }
+
+            fn size_hint() -> usize {
+                $len
+            }
+        }
+
+        impl $crate::Serialize for $name {
+            fn serialize(
+                &self,
+                buffer: &mut $crate::__internal::Vec<u8>,
+            ) -> ::core::result::Result<usize, $crate::SerializeError> {
+                $crate::Serialize::serialize(&self.0, buffer)
+            }
+        }
+
+        impl $crate::Deserialize for $name {
+            fn deserialize(encoding: &[u8]) -> ::core::result::Result<Self, $crate::DeserializeError> {
+                <$crate::ByteVector<$len> as $crate::Deserialize>::deserialize(encoding).map(Self)
+            }
+        }
+
+        impl $crate::Merkleized for $name {
+            fn hash_tree_root_with<H: $crate::MerkleHasher>(
+                &mut self,
+                context: &$crate::MerkleizationContext<H>,
+            ) -> ::core::result::Result<$crate::Node, $crate::MerkleizationError> {
+                $crate::Merkleized::hash_tree_root_with(&mut self.0, context)
+            }
+
+            fn leaves<H: $crate::MerkleHasher>(
+                &mut self,
+                context: &$crate::MerkleizationContext<H>,
+            ) -> ::core::result::Result<
+                $crate::__internal::Vec<$crate::Node>,
+                $crate::MerkleizationError,
+            > {
+                $crate::Merkleized::leaves(&mut self.0, context)
+            }
+
+            const CHUNK_COUNT: usize = <$crate::ByteVector<$len> as $crate::Merkleized>::CHUNK_COUNT;
+        }
+
+        impl $crate::SimpleSerialize for $name {
+            const MAX_SIZE: usize = $len;
+
+            const IS_VARIABLE_SIZE: bool = false;
+
+            const NESTING_DEPTH: usize = 1;
+
+            fn is_default(&self) -> bool {
+                $crate::SimpleSerialize::is_default(&self.0)
+            }
+
+            fn default_hash_tree_root_with<H: $crate::MerkleHasher>(
+                context: &$crate::MerkleizationContext<H>,
+            ) -> ::core::result::Result<$crate::Node, $crate::MerkleizationError> {
+                <$crate::ByteVector<$len> as $crate::SimpleSerialize>::default_hash_tree_root_with(
+                    context,
+                )
+            }
+
+            fn hash_tree_root_from_bytes<H: $crate::MerkleHasher>(
+                encoding: &[u8],
+                context: &$crate::MerkleizationContext<H>,
+            ) -> ::core::result::Result<$crate::Node, $crate::SimpleSerializeError> {
+                <$crate::ByteVector<$len> as $crate::SimpleSerialize>::hash_tree_root_from_bytes(
+                    encoding, context,
+                )
+            }
+
+            fn generalized_index_from(
+                root: $crate::GeneralizedIndex,
+                path: &[$crate::PathElement<'_>],
+            ) -> ::core::result::Result<$crate::GeneralizedIndex, $crate::PathError> {
+                <$crate::ByteVector<$len> as $crate::SimpleSerialize>::generalized_index_from(
+                    root, path,
+                )
+            }
+        }
+
+        $crate::__impl_bytes_type_serde!($name);
+        $crate::__impl_bytes_type_subtle!($name);
+    };
+}
+
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_bytes_type_serde {
+    ($name:ident) => {
+        impl $crate::__internal::serde::Serialize for $name {
+            fn serialize<S: $crate::__internal::serde::Serializer>(
+                &self,
+                serializer: S,
+            ) -> ::core::result::Result<S::Ok, S::Error> {
+                $crate::__internal::as_hex::serialize(self, serializer)
+            }
+        }
+
+        impl<'de> $crate::__internal::serde::Deserialize<'de> for $name {
+            fn deserialize<D: $crate::__internal::serde::Deserializer<'de>>(
+                deserializer: D,
+            ) -> ::core::result::Result<Self, D::Error> {
+                $crate::__internal::as_hex::deserialize(deserializer)
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "serde"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_bytes_type_serde {
+    ($name:ident) => {};
+}
+
+#[cfg(feature = "subtle")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_bytes_type_subtle {
+    ($name:ident) => {
+        impl $crate::__internal::subtle::ConstantTimeEq for $name {
+            fn ct_eq(&self, other: &Self) -> $crate::__internal::subtle::Choice {
+                $crate::__internal::subtle::ConstantTimeEq::ct_eq(&self.0, &other.0)
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "subtle"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __impl_bytes_type_subtle {
+    ($name:ident) => {};
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{merkleization::Context, prelude::*, ByteVector};
+
+    define_bytes_type!(
+        /// A BLS public key.
+        pub BlsPublicKey,
+        48
+    );
+
+    define_bytes_type!(Hash32, 32);
+
+    // the macro names every item by its path, so it works where `Vec` or `Result` name other
+    // items, e.g. in `no_std` crates
+    mod shadowed {
+        #![allow(dead_code)]
+        struct Vec;
+        type Result = ();
+
+        crate::define_bytes_type!(pub(super) Hash20, 20);
+    }
+
+    #[derive(Default, Debug, Clone, PartialEq, Eq, SimpleSerialize)]
+    struct Deposit {
+        pubkey: BlsPublicKey,
+        root: Hash32,
+        amount: u64,
+    }
+
+    #[test]
+    fn test_bytes_type() {
+        let mut key = BlsPublicKey::from([0x11; 48]);
+        let mut bytes = ByteVector::<48>::from([0x11; 48]);
+        assert_eq!(BlsPublicKey::LENGTH, 48);
+        assert_eq!(serialize(&key).unwrap(), serialize(&bytes).unwrap());
+        assert_eq!(key.hash_tree_root().unwrap(), bytes.hash_tree_root().unwrap());
+        assert_eq!(
+            BlsPublicKey::hash_tree_root_from_bytes(&[0x11; 48], Context::global()).unwrap(),
+            bytes.hash_tree_root().unwrap()
+        );
+        assert!(BlsPublicKey::try_from(&[0x11; 47][..]).is_err());
+
+        let text = format!("0x{}", "11".repeat(48));
+        assert_eq!(key.to_string(), text);
+        assert_eq!(format!("{key:?}"), format!("BlsPublicKey({text})"));
+        assert_eq!(text.parse::<BlsPublicKey>().unwrap(), key);
+        assert_eq!(text[2..].parse::<BlsPublicKey>().unwrap(), key);
+        assert!("0x11".parse::<BlsPublicKey>().is_err());
+
+        key.as_mut()[0] = 0;
+        assert_ne!(key.hash_tree_root().unwrap(), bytes.hash_tree_root().unwrap());
+
+        let mut hash = Hash32::from([0x22; 32]);
+        assert_eq!(hash.hash_tree_root().unwrap(), Node::try_from(hash.as_bytes()).unwrap());
+        let deposit = Deposit { pubkey: key, root: hash, amount: 32 };
+        let encoding = serialize(&deposit).unwrap();
+        assert_eq!(encoding.len(), 48 + 32 + 8);
+        assert_eq!(Deposit::deserialize(&encoding).unwrap(), deposit);
+
+        let address = shadowed::Hash20::from([0x33; 20]);
+        assert_eq!(serialize(&address).unwrap(), vec![0x33; 20]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_bytes_type_serde() {
+        let hash = Hash32::from([0xab; 32]);
+        let json = serde_json::to_string(&hash).unwrap();
+        assert_eq!(json, format!("\"0x{}\"", "ab".repeat(32)));
+        assert_eq!(serde_json::from_str::<Hash32>(&json).unwrap(), hash);
+    }
+}
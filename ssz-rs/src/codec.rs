@@ -0,0 +1,211 @@
+use crate::{
+    de::DeserializeError, error::TypeError, lib::*, metrics, ser::SerializeError, SimpleSerialize,
+};
+
+/// An `SszEncoder` encodes values into an output buffer it owns and reuses across calls.
+///
+/// Encoding many values with the same encoder amortizes the allocation of the output
+/// buffer, which only grows to fit the largest encoding seen so far. It does not hold scratch
+/// space for the offsets and parts of composite values, which their `Serialize` implementations
+/// still allocate on every call, so the savings are largest for values of basic types and of
+/// fixed-size or packed collections of them.
+///
+/// ```rust
+/// use ssz_rs::prelude::*;
+///
+/// let mut encoder = ssz_rs::SszEncoder::new();
+/// for value in [1u32, 2, 3] {
+///     let encoding = encoder.encode(&value).unwrap();
+///     assert_eq!(encoding, serialize(&value).unwrap());
+/// }
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct SszEncoder {
+    buffer: Vec<u8>,
+}
+
+impl SszEncoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an encoder with space for an encoding of `capacity` bytes without reallocating.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self { buffer: Vec::with_capacity(capacity) }
+    }
+
+    /// Return the number of bytes the encoder can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+
+    /// Encode `value`, replacing the previous encoding, and return the encoding.
+    pub fn encode<T: SimpleSerialize>(&mut self, value: &T) -> Result<&[u8], SerializeError> {
+        self.buffer.clear();
+        if let Err(err) = value.serialize(&mut self.buffer) {
+            self.buffer.clear();
+            return Err(err)
+        }
+        Ok(&self.buffer)
+    }
+
+    /// Release the memory held by the encoder beyond `capacity` bytes.
+    pub fn shrink_to(&mut self, capacity: usize) {
+        self.buffer.clear();
+        self.buffer.shrink_to(capacity);
+    }
+}
+
+/// An `SszDecoder` decodes values subject to limits configured once and reused across calls.
+///
+/// When reading messages from an `std::io::Read`, the decoder reuses an input buffer it owns
+/// so that decoding many messages amortizes its allocation.
+///
+/// ```rust
+/// use ssz_rs::prelude::*;
+///
+/// let decoder = ssz_rs::SszDecoder::new().with_max_len(8);
+/// let value: List<u8, 16> = decoder.decode(&[1, 2, 3]).unwrap();
+/// assert_eq!(value.as_ref(), [1, 2, 3]);
+/// assert!(decoder.decode::<List<u8, 16>>(&[0u8; 9]).is_err());
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct SszDecoder {
+    max_len: Option<usize>,
+    max_depth: Option<usize>,
+    #[cfg(feature = "std")]
+    buffer: Vec<u8>,
+}
+
+impl SszDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reject any encoding longer than `max_len` bytes before decoding it.
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = Some(max_len);
+        self
+    }
+
+    /// Reject any type nesting more than `max_depth` composite types before decoding.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    pub fn max_len(&self) -> Option<usize> {
+        self.max_len
+    }
+
+    pub fn max_depth(&self) -> Option<usize> {
+        self.max_depth
+    }
+
+    /// Decode a value of type `T` from `encoding`.
+    pub fn decode<T: SimpleSerialize>(&self, encoding: &[u8]) -> Result<T, DeserializeError> {
+        self.check::<T>(encoding.len())?;
+        metrics::record_decoded_bytes(encoding.len());
+        T::deserialize(encoding)
+    }
+
+    /// Read the remaining data of `reader` into the buffer of this decoder
+    /// and decode a value of type `T` from it.
+    ///
+    /// At most one byte more than the maximum length is read from `reader`.
+    #[cfg(feature = "std")]
+    pub fn decode_from_reader<T, R>(&mut self, mut reader: R) -> std::io::Result<T>
+    where
+        T: SimpleSerialize,
+        R: std::io::Read,
+    {
+        use std::io::{Error, ErrorKind, Read};
+
+        self.buffer.clear();
+        match self.max_len {
+            Some(max_len) => reader.take(max_len as u64 + 1).read_to_end(&mut self.buffer)?,
+            None => reader.read_to_end(&mut self.buffer)?,
+        };
+        let encoding = &self.buffer;
+        self.check::<T>(encoding.len())
+            .and_then(|_| {
+                metrics::record_decoded_bytes(encoding.len());
+                T::deserialize(encoding)
+            })
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err))
+    }
+
+    fn check<T: SimpleSerialize>(&self, len: usize) -> Result<(), DeserializeError> {
+        if let Some(max_depth) = self.max_depth {
+            let depth = T::nesting_depth();
+            if depth > max_depth {
+                return Err(TypeError::MaxDepthExceeded { depth, max_depth }.into())
+            }
+        }
+        match self.max_len {
+            Some(max_len) if len > max_len => Err(DeserializeError::InputTooLarge { len, max_len }),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{list::List, serialize};
+
+    #[test]
+    fn test_encoder_reuses_buffer() {
+        let mut encoder = SszEncoder::with_capacity(64);
+        let mut list = List::<u16, 8>::default();
+        for i in 0..8 {
+            list.push(i).unwrap();
+            let expected = serialize(&list).unwrap();
+            assert_eq!(encoder.encode(&list).unwrap(), expected);
+        }
+        assert_eq!(encoder.encode(&true).unwrap(), [1u8]);
+        assert_eq!(encoder.capacity(), 64);
+
+        encoder.shrink_to(0);
+        assert_eq!(encoder.capacity(), 0);
+    }
+
+    #[test]
+    fn test_decoder_limits() {
+        let list = List::<u16, 8>::try_from(vec![1u16, 2, 3]).unwrap();
+        let encoding = serialize(&list).unwrap();
+
+        let mut decoder = SszDecoder::new();
+        assert_eq!(decoder.decode::<List<u16, 8>>(&encoding).unwrap(), list);
+        decoder = decoder.with_max_len(4);
+        let result = decoder.decode::<List<u16, 8>>(&encoding);
+        assert!(matches!(result, Err(DeserializeError::InputTooLarge { len: 6, max_len: 4 })));
+
+        let decoder = SszDecoder::new().with_max_depth(1);
+        assert!(decoder.decode::<List<u16, 8>>(&encoding).is_ok());
+        let result = decoder.decode::<List<List<u16, 8>, 2>>(&[4, 0, 0, 0]);
+        assert!(matches!(
+            result,
+            Err(DeserializeError::InvalidType(TypeError::MaxDepthExceeded {
+                depth: 2,
+                max_depth: 1
+            }))
+        ));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_decoder_from_reader() {
+        let list = List::<u16, 8>::try_from(vec![1u16, 2, 3]).unwrap();
+        let encoding = serialize(&list).unwrap();
+
+        let mut decoder = SszDecoder::new().with_max_len(6);
+        let recovered: List<u16, 8> = decoder.decode_from_reader(encoding.as_slice()).unwrap();
+        assert_eq!(recovered, list);
+
+        let long = [encoding.as_slice(), &[0u8; 32]].concat();
+        let result = decoder.decode_from_reader::<List<u16, 8>, _>(long.as_slice());
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(decoder.buffer.len(), 7);
+    }
+}
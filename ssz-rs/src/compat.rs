@@ -0,0 +1,560 @@
+//! Check whether two versions of an SSZ type are compatible on the wire and in their roots,
+//! e.g. in the CI of a protocol to flag breaking changes to its types.
+//!
+//! The types are compared through their [`TypeDescriptor`], which `#[derive(Describe)]`
+//! provides for containers and unions.
+//!
+//! ```rust
+//! use ssz_rs::prelude::*;
+//! use ssz_rs::compat::{compare, ChangeKind};
+//!
+//! #[derive(Default, Debug, SimpleSerialize, Describe)]
+//! struct Registry {
+//!     epoch: u64,
+//!     balances: List<u64, 1024>,
+//! }
+//!
+//! #[derive(Default, Debug, SimpleSerialize, Describe)]
+//! struct RegistryV2 {
+//!     current_epoch: u64,
+//!     balances: List<u64, 4096>,
+//! }
+//!
+//! let report = compare(&Registry::describe(), &RegistryV2::describe());
+//! assert_eq!(report.changes.len(), 2);
+//! assert!(matches!(report.changes[1].kind, ChangeKind::LimitChanged { old: 1024, new: 4096, .. }));
+//! // every encoding of a `Registry` decodes as a `RegistryV2`...
+//! assert!(report.is_wire_compatible());
+//! // ... but the deeper tree of `balances` changes the roots
+//! assert!(!report.roots_coincide());
+//! println!("{report}");
+//! ```
+use crate::{
+    bitlist::Bitlist,
+    bitvector::Bitvector,
+    lib::*,
+    list::List,
+    map::MapEntry,
+    merkleization::{chunks_for_bits, chunks_for_list, depth_for_chunks, Node},
+    uint::U256,
+    utf8_list::Utf8List,
+    vector::Vector,
+    SimpleSerialize,
+};
+
+/// The SSZ type of a Rust type, without the names of the Rust types involved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeDescriptor {
+    /// An unsigned integer with the given number of bits.
+    Uint(usize),
+    Boolean,
+    /// `Vector[T, N]`
+    Vector(Box<TypeDescriptor>, usize),
+    /// `List[T, N]`
+    List(Box<TypeDescriptor>, usize),
+    Bitvector(usize),
+    Bitlist(usize),
+    /// The names and types of the fields in declaration order.
+    Container(Vec<(String, TypeDescriptor)>),
+    /// The types of the variants in order, with `None` for the `None` variant.
+    Union(Vec<Option<TypeDescriptor>>),
+}
+
+impl TypeDescriptor {
+    pub fn is_basic(&self) -> bool {
+        matches!(self, Self::Uint(..) | Self::Boolean)
+    }
+
+    /// Return `true` if the encodings of values of this type have different lengths.
+    pub fn is_variable_size(&self) -> bool {
+        match self {
+            Self::Uint(..) | Self::Boolean | Self::Bitvector(..) => false,
+            Self::List(..) | Self::Bitlist(..) | Self::Union(..) => true,
+            Self::Vector(element, _) => element.is_variable_size(),
+            Self::Container(fields) => fields.iter().any(|(_, ty)| ty.is_variable_size()),
+        }
+    }
+
+    /// Return the length of the encoding of a fixed-size type, or `0` for a variable-size type.
+    pub fn size_hint(&self) -> usize {
+        if self.is_variable_size() {
+            return 0
+        }
+        match self {
+            Self::Uint(bits) => bits / 8,
+            Self::Boolean => 1,
+            Self::Vector(element, length) => element.size_hint().saturating_mul(*length),
+            Self::Bitvector(length) => (length + 7) / 8,
+            Self::Container(fields) => fields.iter().map(|(_, ty)| ty.size_hint()).sum(),
+            Self::List(..) | Self::Bitlist(..) | Self::Union(..) => 0,
+        }
+    }
+
+    // Return the number of chunks of a list of at most `limit` elements of type `self`.
+    fn elements_chunk_count(&self, limit: usize) -> usize {
+        match self {
+            Self::Uint(bits) => chunks_for_list(limit, bits / 8),
+            Self::Boolean => chunks_for_list(limit, 1),
+            _ => limit,
+        }
+    }
+}
+
+impl Display for TypeDescriptor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Uint(bits) => write!(f, "uint{bits}"),
+            Self::Boolean => write!(f, "boolean"),
+            Self::Vector(element, length) => write!(f, "Vector[{element}, {length}]"),
+            Self::List(element, limit) => write!(f, "List[{element}, {limit}]"),
+            Self::Bitvector(length) => write!(f, "Bitvector[{length}]"),
+            Self::Bitlist(limit) => write!(f, "Bitlist[{limit}]"),
+            Self::Container(fields) => {
+                write!(f, "Container {{ ")?;
+                for (i, (name, ty)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{name}: {ty}")?;
+                }
+                write!(f, " }}")
+            }
+            Self::Union(variants) => {
+                write!(f, "Union[")?;
+                for (i, variant) in variants.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    match variant {
+                        Some(ty) => write!(f, "{ty}")?,
+                        None => write!(f, "None")?,
+                    }
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+/// A type with a known SSZ type.
+pub trait Describe {
+    fn describe() -> TypeDescriptor;
+}
+
+macro_rules! define_describe_for_uint {
+    ($($uint:ty),*) => {
+        $(
+            impl Describe for $uint {
+                fn describe() -> TypeDescriptor {
+                    TypeDescriptor::Uint(<$uint>::BITS as usize)
+                }
+            }
+        )*
+    };
+}
+
+define_describe_for_uint!(u8, u16, u32, u64, u128);
+
+// `usize` is encoded as a `uint64` on every target
+impl Describe for usize {
+    fn describe() -> TypeDescriptor {
+        u64::describe()
+    }
+}
+
+impl Describe for U256 {
+    fn describe() -> TypeDescriptor {
+        TypeDescriptor::Uint(256)
+    }
+}
+
+impl Describe for bool {
+    fn describe() -> TypeDescriptor {
+        TypeDescriptor::Boolean
+    }
+}
+
+impl Describe for Node {
+    fn describe() -> TypeDescriptor {
+        <[u8; 32]>::describe()
+    }
+}
+
+impl<T: Describe, const N: usize> Describe for [T; N] {
+    fn describe() -> TypeDescriptor {
+        TypeDescriptor::Vector(Box::new(T::describe()), N)
+    }
+}
+
+impl<T: SimpleSerialize + Describe, const N: usize> Describe for Vector<T, N> {
+    fn describe() -> TypeDescriptor {
+        TypeDescriptor::Vector(Box::new(T::describe()), N)
+    }
+}
+
+impl<T: SimpleSerialize + Describe, const N: usize> Describe for List<T, N> {
+    fn describe() -> TypeDescriptor {
+        TypeDescriptor::List(Box::new(T::describe()), N)
+    }
+}
+
+impl<const N: usize> Describe for Bitvector<N> {
+    fn describe() -> TypeDescriptor {
+        TypeDescriptor::Bitvector(N)
+    }
+}
+
+impl<const N: usize> Describe for Bitlist<N> {
+    fn describe() -> TypeDescriptor {
+        TypeDescriptor::Bitlist(N)
+    }
+}
+
+impl<const N: usize> Describe for Utf8List<N> {
+    fn describe() -> TypeDescriptor {
+        List::<u8, N>::describe()
+    }
+}
+
+impl<T: Describe> Describe for Option<T> {
+    fn describe() -> TypeDescriptor {
+        TypeDescriptor::Union(vec![None, Some(T::describe())])
+    }
+}
+
+impl<K, V> Describe for MapEntry<K, V>
+where
+    K: SimpleSerialize + Describe,
+    V: SimpleSerialize + Describe,
+{
+    fn describe() -> TypeDescriptor {
+        TypeDescriptor::Container(vec![
+            ("key".into(), K::describe()),
+            ("value".into(), V::describe()),
+        ])
+    }
+}
+
+/// Return the SSZ type of a map encoded as a list of at most `N` entries.
+pub fn map<K, V, const N: usize>() -> TypeDescriptor
+where
+    K: SimpleSerialize + Describe,
+    V: SimpleSerialize + Describe,
+{
+    List::<MapEntry<K, V>, N>::describe()
+}
+
+/// A difference between two versions of a type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChangeKind {
+    FieldRenamed {
+        old: String,
+        new: String,
+    },
+    FieldAdded(String),
+    FieldRemoved(String),
+    /// A variant was added at the given selector.
+    VariantAdded(usize),
+    /// The variant at the given selector was removed.
+    VariantRemoved(usize),
+    /// The length of a `Vector` or `Bitvector` changed.
+    LengthChanged {
+        old: usize,
+        new: usize,
+    },
+    /// The limit of a `List` or `Bitlist` changed, keeping the depth of its tree if `same_depth`.
+    LimitChanged {
+        old: usize,
+        new: usize,
+        same_depth: bool,
+    },
+    TypeChanged {
+        old: TypeDescriptor,
+        new: TypeDescriptor,
+    },
+}
+
+impl ChangeKind {
+    /// Return `true` if every encoding of the old type decodes as the new type.
+    pub fn is_wire_compatible(&self) -> bool {
+        match self {
+            Self::FieldRenamed { .. } | Self::VariantAdded(..) => true,
+            Self::LimitChanged { old, new, .. } => new >= old,
+            _ => false,
+        }
+    }
+
+    /// Return `true` if values of the old type have the same root as the new type.
+    pub fn preserves_root(&self) -> bool {
+        match self {
+            Self::FieldRenamed { .. } | Self::VariantAdded(..) => true,
+            Self::LimitChanged { same_depth, .. } => *same_depth,
+            _ => false,
+        }
+    }
+}
+
+impl Display for ChangeKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FieldRenamed { old, new } => write!(f, "field `{old}` renamed to `{new}`"),
+            Self::FieldAdded(name) => write!(f, "field `{name}` added"),
+            Self::FieldRemoved(name) => write!(f, "field `{name}` removed"),
+            Self::VariantAdded(selector) => write!(f, "variant {selector} added"),
+            Self::VariantRemoved(selector) => write!(f, "variant {selector} removed"),
+            Self::LengthChanged { old, new } => write!(f, "length changed from {old} to {new}"),
+            Self::LimitChanged { old, new, .. } => write!(f, "limit changed from {old} to {new}"),
+            Self::TypeChanged { old, new } => write!(f, "type changed from {old} to {new}"),
+        }
+    }
+}
+
+/// A difference at `path`, e.g. `.body.attestations[]` for the elements of the list
+/// `attestations` in the container `body` or `#1` for the variant 1 of a union.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub path: String,
+    pub kind: ChangeKind,
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() { "." } else { &self.path };
+        write!(f, "{path}: {}", self.kind)
+    }
+}
+
+/// The differences between two versions of a type, see [`compare`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    pub changes: Vec<Change>,
+}
+
+impl Report {
+    /// Return `true` if every encoding of the old type decodes as the new type.
+    pub fn is_wire_compatible(&self) -> bool {
+        self.changes.iter().all(|change| change.kind.is_wire_compatible())
+    }
+
+    /// Return `true` if values of the old type have the same root as the new type.
+    pub fn roots_coincide(&self) -> bool {
+        self.changes.iter().all(|change| change.kind.preserves_root())
+    }
+
+    /// Return the changes that are not wire compatible.
+    pub fn breaking_changes(&self) -> impl Iterator<Item = &Change> {
+        self.changes.iter().filter(|change| !change.kind.is_wire_compatible())
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "wire compatible: {}", self.is_wire_compatible())?;
+        write!(f, "roots coincide: {}", self.roots_coincide())?;
+        for change in &self.changes {
+            write!(f, "\n{change}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Compare the `old` and `new` versions of a type, matching fields and variants by position.
+pub fn compare(old: &TypeDescriptor, new: &TypeDescriptor) -> Report {
+    let mut report = Report::default();
+    compare_at(String::new(), old, new, &mut report.changes);
+    report
+}
+
+fn same_depth(old_chunks: usize, new_chunks: usize) -> bool {
+    depth_for_chunks(old_chunks) == depth_for_chunks(new_chunks)
+}
+
+fn compare_at(path: String, old: &TypeDescriptor, new: &TypeDescriptor, changes: &mut Vec<Change>) {
+    use TypeDescriptor::*;
+
+    if old == new {
+        return
+    }
+    match (old, new) {
+        (Vector(old_element, old_length), Vector(new_element, new_length)) => {
+            if old_length != new_length {
+                let kind = ChangeKind::LengthChanged { old: *old_length, new: *new_length };
+                changes.push(Change { path: path.clone(), kind });
+            }
+            compare_at(format!("{path}[]"), old_element, new_element, changes);
+        }
+        (List(old_element, old_limit), List(new_element, new_limit)) => {
+            if old_limit != new_limit {
+                let same_depth = same_depth(
+                    old_element.elements_chunk_count(*old_limit),
+                    new_element.elements_chunk_count(*new_limit),
+                );
+                let kind =
+                    ChangeKind::LimitChanged { old: *old_limit, new: *new_limit, same_depth };
+                changes.push(Change { path: path.clone(), kind });
+            }
+            compare_at(format!("{path}[]"), old_element, new_element, changes);
+        }
+        (Bitvector(old_length), Bitvector(new_length)) => {
+            let kind = ChangeKind::LengthChanged { old: *old_length, new: *new_length };
+            changes.push(Change { path, kind });
+        }
+        (Bitlist(old_limit), Bitlist(new_limit)) => {
+            let same_depth = same_depth(chunks_for_bits(*old_limit), chunks_for_bits(*new_limit));
+            let kind = ChangeKind::LimitChanged { old: *old_limit, new: *new_limit, same_depth };
+            changes.push(Change { path, kind });
+        }
+        (Container(old_fields), Container(new_fields)) => {
+            for i in 0..old_fields.len().max(new_fields.len()) {
+                match (old_fields.get(i), new_fields.get(i)) {
+                    (Some((old_name, old_ty)), Some((new_name, new_ty))) => {
+                        if old_name != new_name {
+                            let kind = ChangeKind::FieldRenamed {
+                                old: old_name.clone(),
+                                new: new_name.clone(),
+                            };
+                            changes.push(Change { path: path.clone(), kind });
+                        }
+                        compare_at(format!("{path}.{new_name}"), old_ty, new_ty, changes);
+                    }
+                    (Some((name, _)), None) => {
+                        let kind = ChangeKind::FieldRemoved(name.clone());
+                        changes.push(Change { path: path.clone(), kind });
+                    }
+                    (None, Some((name, _))) => {
+                        let kind = ChangeKind::FieldAdded(name.clone());
+                        changes.push(Change { path: path.clone(), kind });
+                    }
+                    (None, None) => unreachable!(),
+                }
+            }
+        }
+        (Union(old_variants), Union(new_variants)) => {
+            for i in 0..old_variants.len().max(new_variants.len()) {
+                match (old_variants.get(i), new_variants.get(i)) {
+                    (Some(Some(old_ty)), Some(Some(new_ty))) => {
+                        compare_at(format!("{path}#{i}"), old_ty, new_ty, changes);
+                    }
+                    (Some(None), Some(None)) => {}
+                    (Some(_), None) => {
+                        changes.push(Change {
+                            path: path.clone(),
+                            kind: ChangeKind::VariantRemoved(i),
+                        });
+                    }
+                    (None, Some(_)) => {
+                        changes
+                            .push(Change { path: path.clone(), kind: ChangeKind::VariantAdded(i) });
+                    }
+                    // `None` replaced a type or the other way around
+                    _ => {
+                        let kind = ChangeKind::TypeChanged { old: old.clone(), new: new.clone() };
+                        changes.push(Change { path, kind });
+                        return
+                    }
+                }
+            }
+        }
+        _ => {
+            let kind = ChangeKind::TypeChanged { old: old.clone(), new: new.clone() };
+            changes.push(Change { path, kind });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[derive(Default, Debug, SimpleSerialize, Describe)]
+    struct Checkpoint {
+        epoch: u64,
+        root: Node,
+    }
+
+    #[derive(Default, Debug, SimpleSerialize, Describe)]
+    struct State {
+        slot: u64,
+        checkpoints: List<Checkpoint, 8>,
+        bits: Bitlist<256>,
+        #[ssz(as_list(max = 4))]
+        balances: BTreeMap<u32, u64>,
+    }
+
+    #[derive(Default, Debug, SimpleSerialize, Describe)]
+    struct CheckpointV2 {
+        epoch: u32,
+        root: Node,
+    }
+
+    #[derive(Default, Debug, SimpleSerialize, Describe)]
+    struct StateV2 {
+        slot: u64,
+        checkpoints: List<CheckpointV2, 8>,
+        bits: Bitlist<128>,
+        #[ssz(as_list(max = 4))]
+        balances: BTreeMap<u32, u64>,
+        extra: Option<u8>,
+    }
+
+    #[test]
+    fn test_describe() {
+        assert_eq!(
+            Checkpoint::describe().to_string(),
+            "Container { epoch: uint64, root: Vector[uint8, 32] }"
+        );
+        assert_eq!(Option::<bool>::describe().to_string(), "Union[None, boolean]");
+        assert_eq!(Checkpoint::describe().size_hint(), 40);
+        assert!(!Checkpoint::describe().is_variable_size());
+        assert!(State::describe().is_variable_size());
+        assert_eq!(State::describe().size_hint(), 0);
+        assert_eq!(
+            State::describe(),
+            TypeDescriptor::Container(vec![
+                ("slot".into(), TypeDescriptor::Uint(64)),
+                ("checkpoints".into(), TypeDescriptor::List(Box::new(Checkpoint::describe()), 8)),
+                ("bits".into(), TypeDescriptor::Bitlist(256)),
+                ("balances".into(), map::<u32, u64, 4>()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_compare() {
+        assert!(compare(&State::describe(), &State::describe()).changes.is_empty());
+
+        let report = compare(&State::describe(), &StateV2::describe());
+        let changes = report.changes.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(
+            changes,
+            [
+                ".checkpoints[].epoch: type changed from uint64 to uint32",
+                ".bits: limit changed from 256 to 128",
+                ".: field `extra` added",
+            ]
+        );
+        assert!(!report.is_wire_compatible());
+        assert_eq!(report.breaking_changes().count(), 3);
+    }
+
+    #[test]
+    fn test_limits_and_variants() {
+        let report = compare(&List::<u64, 8>::describe(), &List::<u64, 5>::describe());
+        // both limits fit in 2 chunks
+        assert!(!report.is_wire_compatible());
+        assert!(report.roots_coincide());
+
+        let report = compare(&Bitlist::<256>::describe(), &Bitlist::<512>::describe());
+        assert!(report.is_wire_compatible());
+        assert!(!report.roots_coincide());
+
+        let old = TypeDescriptor::Union(vec![None, Some(TypeDescriptor::Boolean)]);
+        let new =
+            TypeDescriptor::Union(vec![None, Some(TypeDescriptor::Boolean), Some(u8::describe())]);
+        let report = compare(&old, &new);
+        assert_eq!(report.changes[0].kind, ChangeKind::VariantAdded(2));
+        assert!(report.is_wire_compatible() && report.roots_coincide());
+        assert_eq!(compare(&new, &old).changes[0].kind, ChangeKind::VariantRemoved(2));
+    }
+}
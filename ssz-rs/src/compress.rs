@@ -0,0 +1,411 @@
+//! A compact form of SSZ encodings for storage, which replaces the repeated parts of a value
+//! with references to their first occurrence.
+//!
+//! Generic compressors see the encoding of a beacon state as a stream of bytes and miss that
+//! most of it is made of the same few validators and balances over and over. Here the encoding
+//! is cut along the SSZ type of the value: each element of a list or vector of composite
+//! elements, and each chunk of 32 bytes of a list or vector of basic elements or of a bitfield,
+//! is looked up in a dictionary of the parts seen before. Parts smaller than a chunk are kept
+//! as they are.
+//!
+//! The compressed form is a sequence of tokens, each starting with a tag byte followed by
+//! LEB128 integers:
+//!
+//! - `0, len, bytes`: bytes kept as they are
+//! - `1, len, bytes`: bytes added to the dictionary
+//! - `2, index`: the bytes of the entry of the dictionary at `index`
+//!
+//! Decompression does not need the type of the value, and restores the encoding exactly.
+//!
+//! ```rust
+//! use ssz_rs::prelude::*;
+//! use ssz_rs::compress::{compress, decompress};
+//!
+//! #[derive(Default, Debug, Clone, PartialEq, Eq, SimpleSerialize, Describe)]
+//! struct Validator {
+//!     pubkey: Vector<u8, 48>,
+//!     effective_balance: u64,
+//!     slashed: bool,
+//! }
+//!
+//! let validators = (0..1000)
+//!     .map(|i| Validator { pubkey: Vector::from([i as u8 % 4; 48]), ..Default::default() })
+//!     .collect::<Vec<_>>();
+//! let validators = List::<Validator, 1024>::try_from(validators).unwrap();
+//! let compressed = compress(&validators).unwrap();
+//! assert!(compressed.len() < serialize(&validators).unwrap().len() / 10);
+//! assert_eq!(decompress::<List<Validator, 1024>>(&compressed).unwrap(), validators);
+//! ```
+use crate::{
+    compat::{Describe, TypeDescriptor},
+    de::DeserializeError,
+    lib::*,
+    merkleization::BYTES_PER_CHUNK,
+    ser::{SerializeError, BYTES_PER_LENGTH_OFFSET},
+    utils::serialize,
+    SimpleSerialize,
+};
+
+const TAG_RAW: u8 = 0;
+const TAG_ENTRY: u8 = 1;
+const TAG_REFERENCE: u8 = 2;
+
+/// An error restoring a value from its compressed form.
+#[derive(Debug)]
+pub enum DecompressError {
+    /// The compressed form is not valid from the byte at this position.
+    Malformed { at: usize },
+    /// The token at this position restores more than `limit` bytes in total.
+    LimitExceeded { at: usize, limit: usize },
+    /// The restored encoding is not the encoding of a value of the type.
+    Deserialize(DeserializeError),
+}
+
+impl From<DeserializeError> for DecompressError {
+    fn from(err: DeserializeError) -> Self {
+        Self::Deserialize(err)
+    }
+}
+
+impl Display for DecompressError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed { at } => write!(f, "malformed compressed encoding at byte {at}"),
+            Self::LimitExceeded { at, limit } => {
+                write!(f, "compressed encoding exceeds {limit} bytes at byte {at}")
+            }
+            Self::Deserialize(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecompressError {}
+
+/// Return the compressed form of the encoding of `value`.
+pub fn compress<T: SimpleSerialize + Describe>(value: &T) -> Result<Vec<u8>, SerializeError> {
+    let encoding = serialize(value)?;
+    Ok(compress_encoding(&encoding, &T::describe()))
+}
+
+/// Return the compressed form of `encoding`, the encoding of a value of type `schema`.
+///
+/// Parts of `encoding` which are not valid for `schema` are kept as they are, so the encoding
+/// is always restored exactly.
+pub fn compress_encoding(encoding: &[u8], schema: &TypeDescriptor) -> Vec<u8> {
+    let mut compressor =
+        Compressor { encoding, cursor: 0, dictionary: BTreeMap::new(), out: vec![] };
+    compressor.walk(schema, 0, encoding.len());
+    compressor.flush(encoding.len());
+    compressor.out
+}
+
+/// Return the value of type `T` restored from its compressed form `data`, rejecting forms
+/// which restore more bytes than the longest encoding of a `T`.
+pub fn decompress<T: SimpleSerialize>(data: &[u8]) -> Result<T, DecompressError> {
+    let encoding = decompress_encoding(data, T::max_size())?;
+    Ok(T::deserialize(&encoding)?)
+}
+
+/// Return the encoding restored from its compressed form `data`, failing once it is longer
+/// than `max_len` bytes.
+///
+/// A few bytes of references can restore an encoding of any length, so `max_len` bounds the
+/// memory spent on untrusted data.
+pub fn decompress_encoding(data: &[u8], max_len: usize) -> Result<Vec<u8>, DecompressError> {
+    let mut encoding = vec![];
+    let mut dictionary = vec![];
+    let mut cursor = 0;
+    while let Some(tag) = data.get(cursor) {
+        let at = cursor;
+        cursor += 1;
+        let malformed = || DecompressError::Malformed { at };
+        let check_len = |len: usize| match encoding.len().checked_add(len) {
+            Some(total) if total <= max_len => Ok(()),
+            _ => Err(DecompressError::LimitExceeded { at, limit: max_len }),
+        };
+        let value = read_varint(data, &mut cursor).ok_or_else(malformed)?;
+        match *tag {
+            TAG_RAW | TAG_ENTRY => {
+                let bytes = cursor
+                    .checked_add(value)
+                    .and_then(|end| data.get(cursor..end))
+                    .ok_or_else(malformed)?;
+                check_len(bytes.len())?;
+                if *tag == TAG_ENTRY {
+                    dictionary.push(encoding.len()..encoding.len() + bytes.len());
+                }
+                encoding.extend_from_slice(bytes);
+                cursor += bytes.len();
+            }
+            TAG_REFERENCE => {
+                let entry = dictionary.get(value).cloned().ok_or_else(malformed)?;
+                check_len(entry.len())?;
+                encoding.extend_from_within(entry);
+            }
+            _ => return Err(malformed()),
+        }
+    }
+    Ok(encoding)
+}
+
+fn write_varint(mut value: usize, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(data: &[u8], cursor: &mut usize) -> Option<usize> {
+    let mut value = 0usize;
+    let mut shift = 0;
+    loop {
+        let byte = *data.get(*cursor)?;
+        *cursor += 1;
+        let bits = ((byte & 0x7f) as usize).checked_shl(shift)?;
+        if bits >> shift != (byte & 0x7f) as usize {
+            return None
+        }
+        value |= bits;
+        if byte & 0x80 == 0 {
+            return Some(value)
+        }
+        shift += 7;
+    }
+}
+
+fn read_offset(encoding: &[u8], at: usize) -> Option<usize> {
+    let bytes = encoding.get(at..at.checked_add(BYTES_PER_LENGTH_OFFSET)?)?;
+    Some(u32::from_le_bytes(bytes.try_into().expect("is offset")) as usize)
+}
+
+struct Compressor<'a> {
+    encoding: &'a [u8],
+    // the start of the bytes not written to `out` yet
+    cursor: usize,
+    dictionary: BTreeMap<&'a [u8], usize>,
+    out: Vec<u8>,
+}
+
+impl<'a> Compressor<'a> {
+    // Write the bytes from the cursor to `end` as they are.
+    fn flush(&mut self, end: usize) {
+        if end > self.cursor {
+            self.out.push(TAG_RAW);
+            write_varint(end - self.cursor, &mut self.out);
+            self.out.extend_from_slice(&self.encoding[self.cursor..end]);
+            self.cursor = end;
+        }
+    }
+
+    // Write the part of the encoding from `start` to `end` as a reference to the same bytes
+    // seen before, or as a new entry of the dictionary.
+    fn part(&mut self, start: usize, end: usize) {
+        if end - start < BYTES_PER_CHUNK || start < self.cursor {
+            return
+        }
+        self.flush(start);
+        let bytes = &self.encoding[start..end];
+        match self.dictionary.get(bytes) {
+            Some(index) => {
+                self.out.push(TAG_REFERENCE);
+                write_varint(*index, &mut self.out);
+            }
+            None => {
+                self.dictionary.insert(bytes, self.dictionary.len());
+                self.out.push(TAG_ENTRY);
+                write_varint(bytes.len(), &mut self.out);
+                self.out.extend_from_slice(bytes);
+            }
+        }
+        self.cursor = end;
+    }
+
+    fn chunks(&mut self, start: usize, end: usize) {
+        let mut chunk = start;
+        while chunk + BYTES_PER_CHUNK <= end {
+            self.part(chunk, chunk + BYTES_PER_CHUNK);
+            chunk += BYTES_PER_CHUNK;
+        }
+    }
+
+    // Cut the value of type `ty` encoded from `start` to `end` into parts.
+    fn walk(&mut self, ty: &TypeDescriptor, start: usize, end: usize) {
+        match ty {
+            TypeDescriptor::Uint(..) | TypeDescriptor::Boolean => {}
+            TypeDescriptor::Bitvector(..) | TypeDescriptor::Bitlist(..) => self.chunks(start, end),
+            TypeDescriptor::Vector(element, _) | TypeDescriptor::List(element, _)
+                if element.is_basic() =>
+            {
+                self.chunks(start, end)
+            }
+            TypeDescriptor::Vector(element, _) | TypeDescriptor::List(element, _) => {
+                if element.is_variable_size() {
+                    let count =
+                        read_offset(self.encoding, start).unwrap_or(0) / BYTES_PER_LENGTH_OFFSET;
+                    self.composite((0..count).map(|_| &**element), start, end, true);
+                } else {
+                    let size = element.size_hint();
+                    let mut cursor = start;
+                    while size > 0 && cursor + size <= end {
+                        self.part(cursor, cursor + size);
+                        cursor += size;
+                    }
+                }
+            }
+            TypeDescriptor::Container(fields) => {
+                self.composite(fields.iter().map(|(_, ty)| ty), start, end, false)
+            }
+            TypeDescriptor::Union(variants) => {
+                if let Some(Some(ty)) =
+                    self.encoding.get(start).and_then(|selector| variants.get(*selector as usize))
+                {
+                    if start < end {
+                        self.walk(ty, start + 1, end);
+                    }
+                }
+            }
+        }
+    }
+
+    // Cut the parts of a composite of the types `types` encoded from `start` to `end`, where
+    // each part is kept whole in the dictionary if `elements` or walked into otherwise.
+    fn composite<'t>(
+        &mut self,
+        types: impl Iterator<Item = &'t TypeDescriptor>,
+        start: usize,
+        end: usize,
+        elements: bool,
+    ) {
+        let mut cursor = start;
+        let mut variable = vec![];
+        for ty in types {
+            if ty.is_variable_size() {
+                match read_offset(self.encoding, cursor) {
+                    Some(offset) => variable.push((ty, start.saturating_add(offset))),
+                    None => return,
+                }
+                cursor += BYTES_PER_LENGTH_OFFSET;
+            } else {
+                let size = ty.size_hint();
+                if cursor + size > end {
+                    return
+                }
+                if elements {
+                    self.part(cursor, cursor + size);
+                } else {
+                    self.walk(ty, cursor, cursor + size);
+                }
+                cursor += size;
+            }
+        }
+        let ends = variable.iter().skip(1).map(|(_, offset)| *offset).chain(iter::once(end));
+        for ((ty, part_start), part_end) in variable.iter().zip(ends.collect::<Vec<_>>()) {
+            if *part_start < cursor || part_end < *part_start || part_end > end {
+                return
+            }
+            if elements {
+                self.part(*part_start, part_end);
+            } else {
+                self.walk(ty, *part_start, part_end);
+            }
+            cursor = part_end;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prelude::*, test_utils::Validator};
+
+    #[derive(Default, Debug, Clone, PartialEq, Eq, SimpleSerialize, Describe)]
+    struct State {
+        slot: u64,
+        validators: List<Validator, 64>,
+        balances: List<u64, 64>,
+        participation: Bitlist<512>,
+        names: List<List<u8, 40>, 8>,
+        latest: Option<Validator>,
+    }
+
+    #[test]
+    fn test_compress() {
+        let validator = |i: u8| Validator {
+            pubkey: Vector::from([i; 48]),
+            effective_balance: 32,
+            slashed: i % 2 == 0,
+            ..Default::default()
+        };
+        let state = State {
+            slot: 7,
+            validators: List::try_from((0..64).map(|i| validator(i % 3)).collect::<Vec<_>>())
+                .unwrap(),
+            balances: List::try_from(vec![32_000_000_000; 64]).unwrap(),
+            participation: Bitlist::from_iter((0..500).map(|i| i % 3 == 0)),
+            names: List::try_from(vec![List::try_from(vec![1u8; 40]).unwrap(); 3]).unwrap(),
+            latest: Some(validator(2)),
+        };
+        let encoding = serialize(&state).unwrap();
+        let compressed = compress(&state).unwrap();
+        assert!(compressed.len() < encoding.len() / 5);
+        assert_eq!(decompress_encoding(&compressed, encoding.len()).unwrap(), encoding);
+        assert_eq!(decompress::<State>(&compressed).unwrap(), state);
+
+        let empty = State::default();
+        assert_eq!(decompress::<State>(&compress(&empty).unwrap()).unwrap(), empty);
+
+        // parts of an encoding which are not valid for the type are kept as they are
+        let mut corrupted = encoding.clone();
+        corrupted[8] = 0xff;
+        let compressed = compress_encoding(&corrupted, &State::describe());
+        assert_eq!(decompress_encoding(&compressed, usize::MAX).unwrap(), corrupted);
+        for len in 0..encoding.len() {
+            let compressed = compress_encoding(&encoding[..len], &State::describe());
+            assert_eq!(decompress_encoding(&compressed, len).unwrap(), &encoding[..len]);
+        }
+    }
+
+    #[test]
+    fn test_decompress_errors() {
+        assert_eq!(decompress_encoding(&[], usize::MAX).unwrap(), Vec::<u8>::new());
+        assert!(matches!(
+            decompress_encoding(&[3, 0], usize::MAX),
+            Err(DecompressError::Malformed { at: 0 })
+        ));
+        assert!(matches!(
+            decompress_encoding(&[0, 2, 1], usize::MAX),
+            Err(DecompressError::Malformed { at: 0 })
+        ));
+        assert!(matches!(
+            decompress_encoding(&[1, 1, 9, 2, 1], usize::MAX),
+            Err(DecompressError::Malformed { at: 3 })
+        ));
+        assert_eq!(decompress_encoding(&[1, 1, 9, 2, 0], usize::MAX).unwrap(), vec![9, 9]);
+        assert!(matches!(
+            decompress_encoding(&[0, 0xff], usize::MAX),
+            Err(DecompressError::Malformed { at: 0 })
+        ));
+        assert!(matches!(
+            decompress::<u64>(&[0, 1, 9]),
+            Err(DecompressError::Deserialize(DeserializeError::ExpectedFurtherInput { .. }))
+        ));
+
+        // references restoring more than the limit are rejected before they are copied
+        let bomb = [&[1, 4, 9, 9, 9, 9][..], &[2, 0].repeat(1000)].concat();
+        assert_eq!(decompress_encoding(&bomb, 4004).unwrap().len(), 4004);
+        assert!(matches!(
+            decompress_encoding(&bomb, 4003),
+            Err(DecompressError::LimitExceeded { at: 2004, limit: 4003 })
+        ));
+        assert!(matches!(
+            decompress_encoding(&[0, 2, 1, 2], 1),
+            Err(DecompressError::LimitExceeded { at: 0, limit: 1 })
+        ));
+        assert!(matches!(
+            decompress::<u64>(&bomb),
+            Err(DecompressError::LimitExceeded { at: 8, limit: 8 })
+        ));
+    }
+}
@@ -0,0 +1,62 @@
+//! Comparison of bytes in constant time, for byte types which carry secret material in some
+//! applications, e.g. a `ByteVector<32>` holding a key.
+//!
+//! `PartialEq` stops at the first difference so the time it takes reveals where two values
+//! differ; [`ConstantTimeEq::ct_eq`] reads every byte of both values instead. Types made with
+//! [`define_bytes_type!`](crate::define_bytes_type) implement it by their inner `ByteVector`.
+//!
+//! ```rust
+//! use ssz_rs::{prelude::*, ByteVector, ConstantTimeEq};
+//!
+//! let secret = ByteVector::<32>::from([0x11; 32]);
+//! let guess = ByteVector::<32>::from([0x12; 32]);
+//! assert!(bool::from(secret.ct_eq(&secret.clone())));
+//! assert!(!bool::from(secret.ct_eq(&guess)));
+//! ```
+use crate::{list::List, merkleization::Node, vector::Vector};
+pub use subtle::{Choice, ConstantTimeEq};
+
+impl<const N: usize> ConstantTimeEq for Vector<u8, N> {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        (**self).ct_eq(other)
+    }
+}
+
+// NOTE: the lengths of the lists are not secret, only their bytes
+impl<const N: usize> ConstantTimeEq for List<u8, N> {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        (**self).ct_eq(other)
+    }
+}
+
+impl ConstantTimeEq for Node {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.as_ref().ct_eq(other.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{define_bytes_type, ByteList};
+
+    define_bytes_type!(SecretKey, 32);
+
+    #[test]
+    fn test_ct_eq() {
+        let key = SecretKey::from([0x11; 32]);
+        let mut other = key.clone();
+        assert!(bool::from(key.ct_eq(&other)));
+        other.as_mut()[31] = 0;
+        assert!(!bool::from(key.ct_eq(&other)));
+
+        let list = ByteList::<8>::try_from(vec![1u8, 2, 3]).unwrap();
+        assert!(bool::from(list.ct_eq(&list.clone())));
+        assert!(!bool::from(list.ct_eq(&ByteList::<8>::try_from(vec![1u8, 2]).unwrap())));
+        assert!(!bool::from(list.ct_eq(&ByteList::<8>::try_from(vec![1u8, 2, 4]).unwrap())));
+
+        let node = Node::try_from(&[0x22; 32][..]).unwrap();
+        assert!(bool::from(node.ct_eq(&node)));
+        assert!(!bool::from(node.ct_eq(&Node::default())));
+    }
+}
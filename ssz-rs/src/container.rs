@@ -1,6 +1,326 @@
+use crate::{
+    de::{Deserialize, DeserializeError},
+    error::{Error, ErrorKind},
+    lib::*,
+    merkleization::{Context, MerkleHasher, MerkleizationError, Node},
+    ser::SerializeError,
+    SimpleSerialize,
+};
+
+/// A description of a field of a container, see [`Container::fields`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldInfo {
+    pub name: &'static str,
+    /// The name of the Rust type of the field, see `core::any::type_name`.
+    pub type_name: &'static str,
+    pub is_variable_size: bool,
+    /// The size of the encoding of a fixed-size field, or of the offset to a variable-size field.
+    pub fixed_size: usize,
+}
+
+/// An error accessing a field of an encoded container.
+#[derive(Debug)]
+pub enum FieldError {
+    /// The container has no field with this name.
+    UnknownField(String),
+    /// The field is variable-size, so its encoding can not be replaced in place.
+    VariableSize(&'static str),
+    /// The value is not of the type of the field.
+    TypeMismatch { field: &'static str, expected: &'static str, provided: &'static str },
+    /// The encoding of the container is invalid.
+    Deserialize(DeserializeError),
+    /// The new value of the field can not be encoded.
+    Serialize(SerializeError),
+    /// The root of the field can not be computed.
+    Merkleization(MerkleizationError),
+}
+
+impl FieldError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::UnknownField(..) | Self::VariableSize(..) | Self::TypeMismatch { .. } => {
+                ErrorKind::InvalidType
+            }
+            Self::Deserialize(err) => err.kind(),
+            Self::Serialize(err) => err.kind(),
+            Self::Merkleization(err) => err.kind(),
+        }
+    }
+}
+
+impl From<DeserializeError> for FieldError {
+    fn from(err: DeserializeError) -> Self {
+        Self::Deserialize(err)
+    }
+}
+
+impl From<SerializeError> for FieldError {
+    fn from(err: SerializeError) -> Self {
+        Self::Serialize(err)
+    }
+}
+
+impl From<Error> for FieldError {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::Serialize(err) => Self::Serialize(err),
+            Error::Deserialize(err) => Self::Deserialize(err),
+            Error::Merkleization(err) => Self::Merkleization(err),
+            Error::Instance(err) => Self::Deserialize(err.into()),
+            Error::Type(err) => Self::Deserialize(err.into()),
+        }
+    }
+}
+
+impl Display for FieldError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownField(name) => write!(f, "the container has no field `{name}`"),
+            Self::VariableSize(name) => {
+                write!(f, "the field `{name}` is variable-size and can not be patched")
+            }
+            Self::TypeMismatch { field, expected, provided } => write!(
+                f,
+                "the field `{field}` has type `{expected}` but a value of `{provided}` was given"
+            ),
+            Self::Deserialize(err) => write!(f, "invalid encoding of the container: {err}"),
+            Self::Serialize(err) => write!(f, "could not serialize the value: {err}"),
+            Self::Merkleization(err) => write!(f, "could not compute the root: {err}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FieldError {}
+
+/// An error building a container with the builder made by `#[ssz(builder)]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildError {
+    /// No value was given for the field with this name.
+    Missing(&'static str),
+    /// The value given for the field with this name could not be converted to the type of the
+    /// field, e.g. a `Vec` with more elements than the bound of a `List`.
+    Invalid(&'static str),
+}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing(name) => write!(f, "no value was given for the field `{name}`"),
+            Self::Invalid(name) => write!(f, "the value given for the field `{name}` is invalid"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BuildError {}
+
+/// An object-safe view of an SSZ value, e.g. a field of a container given to a [`FieldVisitor`].
+pub trait SszValue {
+    /// Return the name of the Rust type of the value, see `core::any::type_name`.
+    fn type_name(&self) -> &'static str;
+
+    /// Append the encoding of the value to `buffer`, returning the number of bytes written.
+    fn encode(&self, buffer: &mut Vec<u8>) -> Result<usize, SerializeError>;
+
+    /// Return the hash tree root of the value.
+    fn root(&self) -> Result<Node, Error>;
+
+    /// Return the approximate number of bytes allocated on the heap by the value.
+    fn heap_size(&self) -> usize;
+}
+
+impl<T: SimpleSerialize> SszValue for T {
+    fn type_name(&self) -> &'static str {
+        any::type_name::<T>()
+    }
+
+    fn encode(&self, buffer: &mut Vec<u8>) -> Result<usize, SerializeError> {
+        self.serialize(buffer)
+    }
+
+    fn root(&self) -> Result<Node, Error> {
+        // NOTE: `hash_tree_root` needs `&mut self` so hash the encoding instead
+        let mut encoding = vec![];
+        self.serialize(&mut encoding)?;
+        T::hash_tree_root_from_bytes(&encoding, Context::global())
+    }
+
+    fn heap_size(&self) -> usize {
+        self.ssz_heap_size()
+    }
+}
+
+/// A visitor of the fields of a container, see [`Container::visit_fields`].
+pub trait FieldVisitor {
+    /// Visit the field at `index` called `name`.
+    fn visit_field(&mut self, index: usize, name: &'static str, value: &dyn SszValue);
+}
+
+/// Write the range of the encoding of each field in the `encoding` of a container with
+/// `fields` to `ranges`, which has a range for each field, checking the offsets of the
+/// variable-size fields.
+pub fn field_ranges(
+    fields: &[FieldInfo],
+    encoding: &[u8],
+    ranges: &mut [Range<usize>],
+) -> Result<(), DeserializeError> {
+    debug_assert_eq!(fields.len(), ranges.len());
+    let fixed_len = fields.iter().map(|field| field.fixed_size).sum::<usize>();
+    if encoding.len() < fixed_len {
+        return Err(DeserializeError::ExpectedFurtherInput {
+            provided: encoding.len(),
+            expected: fixed_len,
+        })
+    }
+
+    // the index of the range of the last variable-size field, to be closed by the next one
+    let mut open: Option<usize> = None;
+    let mut start = 0;
+    for (index, field) in fields.iter().enumerate() {
+        let end = start + field.fixed_size;
+        if field.is_variable_size {
+            let offset = u32::deserialize(&encoding[start..end])? as usize;
+            let expected = match open {
+                Some(previous) => {
+                    let previous = &mut ranges[previous];
+                    previous.end = offset;
+                    previous.start
+                }
+                None => fixed_len,
+            };
+            if offset < expected {
+                return Err(DeserializeError::ExpectedFurtherInput { provided: offset, expected })
+            }
+            if offset > expected && open.is_none() {
+                return Err(DeserializeError::AdditionalInput { provided: offset, expected })
+            }
+            open = Some(index);
+            ranges[index] = offset..encoding.len();
+        } else {
+            ranges[index] = start..end;
+        }
+        start = end;
+    }
+
+    match open {
+        Some(last) if ranges[last].start > encoding.len() => {
+            Err(DeserializeError::ExpectedFurtherInput {
+                provided: encoding.len(),
+                expected: ranges[last].start,
+            })
+        }
+        None if encoding.len() > fixed_len => {
+            Err(DeserializeError::AdditionalInput { provided: encoding.len(), expected: fixed_len })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Access to the fields of the encoding of an SSZ container without decoding it.
+///
+/// `Container` is implemented by `#[derive(SimpleSerialize)]` for structs with named fields.
+///
+/// ```rust
+/// use ssz_rs::prelude::*;
+/// use ssz_rs::Container;
+///
+/// #[derive(Default, Debug, PartialEq, Eq, SimpleSerialize)]
+/// struct Block {
+///     slot: u64,
+///     body: List<u8, 1024>,
+///     timestamp: u64,
+/// }
+///
+/// let block = Block { slot: 1, body: List::try_from(vec![7u8; 100]).unwrap(), timestamp: 2 };
+/// let mut encoding = serialize(&block).unwrap();
+/// Block::patch_field(&mut encoding, "timestamp", &3u64).unwrap();
+/// assert_eq!(Block::deserialize(&encoding).unwrap(), Block { timestamp: 3, ..block });
+/// ```
+pub trait Container: SimpleSerialize {
+    /// Return the fields of the container in declaration order.
+    fn fields() -> Vec<FieldInfo>;
+
+    /// Return the `TypeId` of the type of the field at `index`, if there is such a field.
+    fn field_type_id(index: usize) -> Option<TypeId>
+    where
+        Self: 'static;
+
+    /// Call `visitor` with each field of `self` in declaration order.
+    fn visit_fields<V: FieldVisitor>(&self, visitor: &mut V);
+
+    /// Return the hash tree root of the field at `index` given the encoding of the field.
+    fn field_hash_tree_root_from_bytes<H: MerkleHasher>(
+        index: usize,
+        encoding: &[u8],
+        context: &Context<H>,
+    ) -> Result<Node, Error>;
+
+    /// Return the hash tree root of the field called `name` in the `encoding` of the container,
+    /// decoding and hashing only that field.
+    fn field_root_from_bytes_with<H: MerkleHasher>(
+        encoding: &[u8],
+        name: &str,
+        context: &Context<H>,
+    ) -> Result<Node, FieldError> {
+        let (index, _) = Self::field(name)?;
+        let range = Self::field_range(encoding, name)?;
+        Ok(Self::field_hash_tree_root_from_bytes(index, &encoding[range], context)?)
+    }
+
+    /// Return the index and description of the field called `name`.
+    fn field(name: &str) -> Result<(usize, FieldInfo), FieldError> {
+        Self::fields()
+            .into_iter()
+            .enumerate()
+            .find(|(_, field)| field.name == name)
+            .ok_or_else(|| FieldError::UnknownField(name.into()))
+    }
+
+    /// Return the range of `encoding` holding the encoding of the field called `name`.
+    fn field_range(encoding: &[u8], name: &str) -> Result<Range<usize>, FieldError> {
+        let (index, _) = Self::field(name)?;
+        let fields = Self::fields();
+        let mut ranges = vec![0..0; fields.len()];
+        field_ranges(&fields, encoding, &mut ranges)?;
+        Ok(ranges.swap_remove(index))
+    }
+
+    /// Replace the encoding of the fixed-size field called `name` in `encoding` with the
+    /// encoding of `value`, without decoding the rest of the container.
+    fn patch_field<F: SimpleSerialize + 'static>(
+        encoding: &mut [u8],
+        name: &str,
+        value: &F,
+    ) -> Result<(), FieldError>
+    where
+        Self: 'static,
+    {
+        let (index, field) = Self::field(name)?;
+        if field.is_variable_size {
+            return Err(FieldError::VariableSize(field.name))
+        }
+        if Self::field_type_id(index) != Some(TypeId::of::<F>()) {
+            return Err(FieldError::TypeMismatch {
+                field: field.name,
+                expected: field.type_name,
+                provided: any::type_name::<F>(),
+            })
+        }
+        let range = Self::field_range(encoding, name)?;
+        let mut buffer = Vec::with_capacity(field.fixed_size);
+        value.serialize(&mut buffer)?;
+        encoding[range].copy_from_slice(&buffer);
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::prelude::*;
+    use crate::{
+        hash_tree_root_from_bytes, lib::BTreeMap, prelude::*, BuildError, Container, FieldError,
+        FieldVisitor, MerkleizationContext, SszValue,
+    };
 
     #[derive(Default, Debug, PartialEq, Eq, SimpleSerialize)]
     struct Foo {
@@ -61,6 +381,27 @@ mod tests {
     #[derive(Default, Debug, PartialEq, Eq, SimpleSerialize)]
     struct TupleStruct(u8);
 
+    #[derive(Default, Debug, PartialEq, Eq, SimpleSerialize)]
+    #[ssz(max_depth = 2)]
+    struct Shallow {
+        a: Foo,
+        b: List<u8, 4>,
+    }
+
+    #[derive(Default, Debug, PartialEq, Eq, SimpleSerialize)]
+    struct Deep {
+        a: Bar,
+    }
+
+    #[derive(Default, Debug, PartialEq, Eq, SimpleSerialize)]
+    #[ssz(max_depth = 2, builder)]
+    struct Built<const N: usize> {
+        slot: u64,
+        flag: bool,
+        values: List<u16, N>,
+        foo: Foo,
+    }
+
     #[test]
     fn encode_container() {
         let value = Foo { a: 5u32 };
@@ -179,4 +520,374 @@ mod tests {
         let mut buffer = vec![];
         let _ = value.serialize(&mut buffer).expect("can serialize");
     }
+
+    #[test]
+    fn test_nesting_depth() {
+        assert_eq!(Foo::nesting_depth(), 1);
+        assert_eq!(Bar::nesting_depth(), 2);
+        assert_eq!(TupleStruct::nesting_depth(), 0);
+        assert_eq!(Shallow::nesting_depth(), 2);
+        assert_eq!(Deep::nesting_depth(), 3);
+        assert_eq!(List::<Option<[Bar; 2]>, 4>::nesting_depth(), 5);
+
+        assert_eq!(Foo::NESTING_DEPTH, 1);
+        assert_eq!(Bar::NESTING_DEPTH, 2);
+        assert_eq!(TupleStruct::NESTING_DEPTH, 0);
+        assert_eq!(Shallow::NESTING_DEPTH, 2);
+        assert_eq!(Deep::NESTING_DEPTH, 3);
+        assert_eq!(List::<Option<[Bar; 2]>, 4>::NESTING_DEPTH, 5);
+    }
+
+    #[test]
+    fn test_max_depth() {
+        let encoding = serialize(&Shallow::default()).unwrap();
+        assert_eq!(Shallow::deserialize(&encoding).unwrap(), Shallow::default());
+        let encoding = serialize(&Built::<4>::default()).unwrap();
+        assert_eq!(Built::<4>::deserialize(&encoding).unwrap(), Built::default());
+    }
+
+    #[test]
+    fn test_builder() {
+        let built = Built::<4>::builder()
+            .slot(7)
+            .flag(true)
+            .values(vec![1u16, 2])
+            .foo(Foo { a: 3 })
+            .build()
+            .unwrap();
+        let values = List::try_from(vec![1, 2]).unwrap();
+        assert_eq!(built, Built { slot: 7, flag: true, values, foo: Foo { a: 3 } });
+        let encoding = serialize(&built).unwrap();
+        assert_eq!(Built::<4>::deserialize(&encoding).unwrap(), built);
+
+        let builder = || Built::<4>::builder().slot(7).flag(true).foo(Foo::default());
+        assert_eq!(builder().build(), Err(BuildError::Missing("values")));
+        assert_eq!(builder().values(vec![0u16; 5]).build(), Err(BuildError::Invalid("values")));
+        let result = builder().slot(-1).values(vec![0u16; 5]).build();
+        assert_eq!(result, Err(BuildError::Invalid("slot")));
+        // the last value given to a setter wins
+        let built = builder().values(vec![0u16; 5]).values(vec![0u16; 4]).build().unwrap();
+        assert_eq!(built.values.len(), 4);
+        let result = builder().values(vec![0u16; 4]).values(vec![0u16; 5]).build();
+        assert_eq!(result, Err(BuildError::Invalid("values")));
+        assert_eq!(builder().slot(-1).slot(2).values(vec![]).build().unwrap().slot, 2);
+        assert_eq!(builder().slot(1).values(vec![]).build().unwrap().slot, 1);
+    }
+
+    #[test]
+    fn test_heap_size() {
+        assert_eq!(Foo { a: 5 }.ssz_heap_size(), 0);
+
+        let bar = Bar { a: List::try_from(vec![1u32; 10]).unwrap() };
+        let bar_size = bar.ssz_heap_size();
+        assert!(bar_size >= 10 * 4);
+        assert_eq!(bar_size, bar.a.ssz_heap_size());
+
+        let list = List::<Bar, 2>::try_from(vec![Bar::default(), bar]).unwrap();
+        assert!(list.ssz_heap_size() >= 2 * core::mem::size_of::<Bar>() + bar_size);
+        assert_eq!(Some(Foo::default()).ssz_heap_size(), 0);
+    }
+
+    #[test]
+    fn test_max_size() {
+        assert_eq!(Foo::max_size(), 4);
+        assert_eq!(Bar::max_size(), 4 + 128 * 4);
+        assert_eq!(TupleStruct::max_size(), 1);
+        assert_eq!(AnotherContainer::max_size(), 4 + 1 + (4 + 32) + 4 + 1);
+        assert_eq!(List::<Bar, 2>::max_size(), 2 * (4 + Bar::max_size()));
+        assert_eq!(Option::<Bar>::max_size(), 1 + Bar::max_size());
+        assert_eq!(Bitlist::<9>::max_size(), 2);
+        assert_eq!(List::<List<u64, { usize::MAX }>, 2>::max_size(), usize::MAX);
+
+        // the derive emits the maximum as a constant, e.g. for buffers sized at compile time
+        const BAR_MAX_SIZE: usize = Bar::MAX_SIZE;
+        assert_eq!(BAR_MAX_SIZE, Bar::max_size());
+        assert_eq!(AnotherContainer::MAX_SIZE, AnotherContainer::max_size());
+        assert_eq!(Bar::IS_VARIABLE_SIZE, Bar::is_variable_size());
+        assert_eq!(Foo::IS_VARIABLE_SIZE, Foo::is_variable_size());
+        assert_eq!(TupleStruct::IS_VARIABLE_SIZE, TupleStruct::is_variable_size());
+
+        let full = Bar { a: List::try_from(vec![u32::MAX; 128]).unwrap() };
+        let encoding = serialize(&full).unwrap();
+        assert_eq!(encoding.len(), Bar::max_size());
+        assert!(crate::validate_frame::<List<Bar, 1>>(encoding.len() + 4).is_ok());
+        assert!(matches!(
+            crate::validate_frame::<Bar>(Bar::max_size() + 1),
+            Err(DeserializeError::AdditionalInput { provided: 517, expected: 516 })
+        ));
+    }
+
+    #[test]
+    fn test_generalized_index() {
+        assert_eq!(AnotherContainer::generalized_index(&["c".into()]), Ok(10));
+        assert_eq!(AnotherContainer::generalized_index(&["c".into(), 31.into()]), Ok(20));
+        assert_eq!(
+            AnotherContainer::generalized_index(&["c".into(), crate::PathElement::Length]),
+            Ok(21)
+        );
+        assert_eq!(AnotherContainer::generalized_index(&["d".into(), 2.into()]), Ok(11));
+        assert!(AnotherContainer::generalized_index(&["e".into(), 0.into()]).is_err());
+        assert!(matches!(
+            AnotherContainer::generalized_index(&["f".into()]),
+            Err(crate::PathError::InvalidElement { type_name, .. })
+                if type_name.ends_with("AnotherContainer")
+        ));
+
+        assert_eq!(Bar::generalized_index(&["a".into(), 127.into()]), Ok(2 * 16 + 15));
+        assert_eq!(
+            List::<Bar, 4>::generalized_index(&[1.into(), "a".into(), 8.into()]),
+            Ok((2 * 4 + 1) * 2 * 16 + 1)
+        );
+        assert_eq!(
+            Bar::generalized_index(&["a".into(), 128.into()]),
+            Err(crate::PathError::IndexOutOfBounds { index: 128, bound: 128 })
+        );
+        assert_eq!(TupleStruct::generalized_index(&[]), Ok(1));
+    }
+
+    #[test]
+    fn test_patch_field() {
+        let value = VarTestStruct { a: 1, b: List::try_from(vec![2u16, 3]).unwrap(), c: 4 };
+        let mut encoding = serialize(&value).unwrap();
+        assert_eq!(VarTestStruct::fields().len(), 3);
+        assert_eq!(VarTestStruct::field_range(&encoding, "a").unwrap(), 0..2);
+        assert_eq!(VarTestStruct::field_range(&encoding, "b").unwrap(), 7..11);
+        assert_eq!(VarTestStruct::field_range(&encoding, "c").unwrap(), 6..7);
+
+        VarTestStruct::patch_field(&mut encoding, "c", &9u8).unwrap();
+        VarTestStruct::patch_field(&mut encoding, "a", &5u16).unwrap();
+        let expected = VarTestStruct { a: 5, c: 9, ..value };
+        assert_eq!(VarTestStruct::deserialize(&encoding).unwrap(), expected);
+
+        assert!(matches!(
+            VarTestStruct::patch_field(&mut encoding, "d", &9u8),
+            Err(FieldError::UnknownField(..))
+        ));
+        assert!(matches!(
+            VarTestStruct::patch_field(&mut encoding, "b", &List::<u16, 1024>::default()),
+            Err(FieldError::VariableSize("b"))
+        ));
+        assert!(matches!(
+            VarTestStruct::patch_field(&mut encoding, "a", &9u8),
+            Err(FieldError::TypeMismatch { field: "a", expected: "u16", provided: "u8" })
+        ));
+        assert!(VarTestStruct::field_range(&encoding[..5], "a").is_err());
+    }
+
+    #[derive(Default, Debug, PartialEq, Eq, SimpleSerialize)]
+    struct Tagged<T: SimpleSerialize> {
+        tag: u8,
+        value: T,
+    }
+
+    #[test]
+    fn test_patch_field_of_generic_container() {
+        let value = Tagged { tag: 1, value: 2u32 };
+        let mut encoding = serialize(&value).unwrap();
+        Tagged::<u32>::patch_field(&mut encoding, "value", &3u32).unwrap();
+        assert_eq!(Tagged::<u32>::deserialize(&encoding).unwrap(), Tagged { tag: 1, value: 3 });
+        assert!(matches!(
+            Tagged::<u32>::patch_field(&mut encoding, "value", &3u8),
+            Err(FieldError::TypeMismatch { field: "value", .. })
+        ));
+    }
+
+    #[derive(Default, Debug, PartialEq, Eq, SimpleSerialize)]
+    struct Mixed {
+        a: u16,
+        b: List<Bar, 4>,
+        c: [VarTestStruct; 2],
+        d: Option<Foo>,
+        e: Bitlist<9>,
+        #[ssz(as_list(max = 4))]
+        f: BTreeMap<u8, List<u8, 2>>,
+        g: List<bool, 40>,
+    }
+
+    #[test]
+    fn test_hash_tree_root_from_bytes() {
+        let mut value = Mixed {
+            a: 3,
+            b: List::try_from(vec![Bar { a: List::try_from(vec![1u32, 2]).unwrap() }]).unwrap(),
+            d: Some(Foo { a: 4 }),
+            e: [true, false, true].into_iter().collect(),
+            f: BTreeMap::from([(1, List::try_from(vec![5u8]).unwrap())]),
+            g: List::try_from(vec![true; 33]).unwrap(),
+            ..Default::default()
+        };
+        value.c[1].b.push(7).unwrap();
+        let encoding = serialize(&value).unwrap();
+        let context = MerkleizationContext::global();
+        let root = hash_tree_root_from_bytes::<Mixed>(&encoding, context).unwrap();
+        assert_eq!(root, value.hash_tree_root().unwrap());
+
+        // a field root is taken from its own encoding
+        let range = Mixed::field_range(&encoding, "g").unwrap();
+        let root = List::<bool, 40>::hash_tree_root_from_bytes(&encoding[range], context).unwrap();
+        assert_eq!(root, value.g.hash_tree_root().unwrap());
+
+        // invalid encodings are rejected as when decoding
+        assert!(hash_tree_root_from_bytes::<Mixed>(&encoding[1..], context).is_err());
+        let mut invalid = encoding.clone();
+        let range = Mixed::field_range(&encoding, "g").unwrap();
+        invalid[range.start] = 2;
+        assert!(Mixed::deserialize(&invalid).is_err());
+        assert!(hash_tree_root_from_bytes::<Mixed>(&invalid, context).is_err());
+        let long = [encoding.as_slice(), &[0u8; 8]].concat();
+        assert!(Mixed::deserialize(&long).is_err());
+        assert!(hash_tree_root_from_bytes::<Mixed>(&long, context).is_err());
+    }
+
+    #[test]
+    fn test_field_root_from_bytes() {
+        let value = VarTestStruct { a: 1, b: List::try_from(vec![2u16, 3]).unwrap(), c: 4 };
+        let encoding = serialize(&value).unwrap();
+        let mut b = value.b.clone();
+        let root = crate::field_root_from_bytes::<VarTestStruct>(&encoding, "b").unwrap();
+        assert_eq!(root, b.hash_tree_root().unwrap());
+        let root = crate::field_root_from_bytes::<VarTestStruct>(&encoding, "c").unwrap();
+        assert_eq!(root, 4u8.hash_tree_root().unwrap());
+        assert!(matches!(
+            crate::field_root_from_bytes::<VarTestStruct>(&encoding, "d"),
+            Err(FieldError::UnknownField(..))
+        ));
+
+        let mut map = BTreeMap::new();
+        map.insert(2u8, List::try_from(vec![1u8]).unwrap());
+        let value = Mixed { f: map.clone(), ..Default::default() };
+        let encoding = serialize(&value).unwrap();
+        let root = crate::field_root_from_bytes::<Mixed>(&encoding, "f").unwrap();
+        let expected =
+            crate::map::hash_tree_root_with::<_, _, 4, _>(&map, MerkleizationContext::global())
+                .unwrap();
+        assert_eq!(root, expected);
+    }
+
+    #[derive(Default)]
+    struct Collector(Vec<(usize, &'static str, usize, Node)>);
+
+    impl FieldVisitor for Collector {
+        fn visit_field(&mut self, index: usize, name: &'static str, value: &dyn SszValue) {
+            let mut encoding = vec![];
+            let len = value.encode(&mut encoding).unwrap();
+            self.0.push((index, name, len, value.root().unwrap()));
+        }
+    }
+
+    #[test]
+    fn test_visit_fields() {
+        let mut value = Mixed {
+            a: 3,
+            f: BTreeMap::from([(1, List::try_from(vec![5u8]).unwrap())]),
+            ..Default::default()
+        };
+        let mut collector = Collector::default();
+        value.visit_fields(&mut collector);
+        let names = collector.0.iter().map(|(_, name, ..)| *name).collect::<Vec<_>>();
+        assert_eq!(names, ["a", "b", "c", "d", "e", "f", "g"]);
+        assert_eq!(collector.0[0], (0, "a", 2, 3u16.hash_tree_root().unwrap()));
+        assert_eq!(collector.0[5].2, 4 + 5 + 1);
+
+        let chunks = collector.0.iter().flat_map(|(.., root)| root.as_ref().to_vec());
+        let root = crate::advanced::merkleize(&chunks.collect::<Vec<_>>(), None).unwrap();
+        assert_eq!(root, value.hash_tree_root().unwrap());
+    }
+
+    #[derive(Default, Debug, Clone, PartialEq, Eq, SimpleSerialize)]
+    struct Checkpoint {
+        epoch: u64,
+        root: Node,
+    }
+
+    #[derive(Default, Debug, PartialEq, Eq, SimpleSerialize)]
+    struct Sparse {
+        a: U256,
+        b: Vector<Checkpoint, 5>,
+        c: Vector<u16, 3>,
+        d: List<Checkpoint, 7>,
+        e: Bitvector<10>,
+        f: Bitlist<10>,
+        g: [u8; 4],
+        #[ssz(as_list(max = 4))]
+        h: BTreeMap<u8, u8>,
+        i: Mixed,
+    }
+
+    #[test]
+    fn test_default_roots() {
+        let context = MerkleizationContext::global();
+        let root_from_bytes = |value: &Sparse| {
+            hash_tree_root_from_bytes::<Sparse>(&serialize(value).unwrap(), context).unwrap()
+        };
+
+        let mut value = Sparse::default();
+        // only the fields whose default is known without scanning them are probed
+        assert!(value.d.is_default() && value.f.is_default() && value.h.is_empty());
+        assert!(!value.b.is_default() && !value.e.is_default() && !value.g.is_default());
+        assert!(!value.is_default());
+        assert!(Foo::default().is_default());
+        let root = root_from_bytes(&value);
+        assert_eq!(Sparse::default_hash_tree_root_with(context).unwrap(), root);
+        assert_eq!(value.hash_tree_root().unwrap(), root);
+
+        value.b[4].root = Node::try_from([1u8; 32].as_ref()).unwrap();
+        value.f.push(false).unwrap();
+        assert!(!value.f.is_default());
+        assert_eq!(value.hash_tree_root().unwrap(), root_from_bytes(&value));
+
+        // the roots of repeated composite elements for any length
+        fn check<const N: usize>(context: &MerkleizationContext) {
+            let root = Vector::<Checkpoint, N>::default().hash_tree_root().unwrap();
+            assert_eq!(
+                Vector::<Checkpoint, N>::default_hash_tree_root_with(context).unwrap(),
+                root
+            );
+        }
+        check::<1>(context);
+        check::<2>(context);
+        check::<3>(context);
+        check::<6>(context);
+        check::<8>(context);
+        check::<11>(context);
+    }
+
+    #[test]
+    fn test_default_root_memo() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        static HASHES: AtomicUsize = AtomicUsize::new(0);
+
+        // counts its hashes, and hashes in reverse so its roots differ from those of SHA-256
+        #[derive(Default)]
+        struct Counting(crate::Sha256Hasher);
+
+        impl crate::MerkleHasher for Counting {
+            fn hash_nodes(&mut self, left: &[u8], right: &[u8], out: &mut [u8]) {
+                HASHES.fetch_add(1, Ordering::Relaxed);
+                self.0.hash_nodes(right, left, out);
+            }
+        }
+
+        #[derive(Default, Debug, SimpleSerialize)]
+        struct Memoized {
+            a: Vector<Checkpoint, 5>,
+            b: List<u64, 1024>,
+            c: u8,
+        }
+
+        let context = MerkleizationContext::<Counting>::new();
+        let root = Memoized::default_hash_tree_root_with(&context).unwrap();
+        let hashes = HASHES.load(Ordering::Relaxed);
+        assert!(hashes > 0);
+        assert_eq!(Memoized::default_hash_tree_root_with(&context).unwrap(), root);
+        assert_eq!(HASHES.load(Ordering::Relaxed), hashes);
+
+        // the root memoized for one hash function is not returned for another
+        let expected = Memoized::default().hash_tree_root().unwrap();
+        assert_ne!(expected, root);
+        assert_eq!(
+            Memoized::default_hash_tree_root_with(MerkleizationContext::global()).unwrap(),
+            expected
+        );
+    }
 }
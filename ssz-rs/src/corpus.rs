@@ -0,0 +1,309 @@
+//! Corpora of malformed encodings of a type, e.g. inputs found by fuzzing, re-checked by a test
+//! suite so that a fixed decoding bug can not come back unnoticed.
+//!
+//! Checking a [`Corpus`] decodes each of its encodings and reports those that panic, those
+//! that decode to a value and those whose root is computed from the bytes although they do not
+//! decode. Corpora are kept as text, one encoding per line, so downstream crates can maintain
+//! their own next to their tests and append the inputs their fuzzers find:
+//!
+//! ```rust
+//! use ssz_rs::{corpus::Corpus, prelude::*};
+//!
+//! const CORPUS: &str = "
+//! ## the offset of the first element points past the end of the encoding
+//! offset-past-end: 0x09000000
+//! ## the second offset is before the first one
+//! offsets-decrease: 0x0800000007000000 malformed
+//! ";
+//!
+//! let corpus = Corpus::<List<List<u8, 4>, 2>>::parse(CORPUS).unwrap();
+//! assert_eq!(corpus.len(), 2);
+//! corpus.assert_rejected();
+//! ```
+use crate::{
+    de::DeserializeError, error::ErrorKind, hex, lib::*, merkleization::Context, SimpleSerialize,
+};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// A malformed encoding in a [`Corpus`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorpusEntry {
+    pub name: String,
+    pub encoding: Vec<u8>,
+    /// The kind of error decoding must fail with, if any kind is expected.
+    pub kind: Option<ErrorKind>,
+}
+
+/// The ways an encoding of a [`Corpus`] can fail to be rejected.
+#[derive(Debug)]
+pub enum Regression {
+    /// Decoding or hashing the encoding panicked with this message.
+    Panicked(String),
+    /// The encoding decoded to a value.
+    Decoded,
+    /// Decoding failed with an error of another kind than expected.
+    UnexpectedError { expected: ErrorKind, provided: DeserializeError },
+    /// A root was computed from the encoding although it does not decode.
+    RootFromBytes,
+}
+
+impl Display for Regression {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Panicked(message) => write!(f, "panicked: {message}"),
+            Self::Decoded => write!(f, "decoded to a value"),
+            Self::UnexpectedError { expected, provided } => {
+                write!(f, "expected an error of kind {expected:?} but got: {provided}")
+            }
+            Self::RootFromBytes => write!(f, "a root was computed from the encoding"),
+        }
+    }
+}
+
+/// An error parsing the text of a [`Corpus`] at the 1-based `line`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub reason: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid corpus entry at line {}: {}", self.line, self.reason)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Malformed encodings of the type `T`, which must all fail to decode.
+pub struct Corpus<T: SimpleSerialize> {
+    entries: Vec<CorpusEntry>,
+    _type: PhantomData<fn() -> T>,
+}
+
+impl<T: SimpleSerialize> Corpus<T> {
+    pub fn new() -> Self {
+        Self { entries: vec![], _type: PhantomData }
+    }
+
+    /// Parse a corpus from `text` with one entry per line, as written by the `Display` of a
+    /// corpus: `name: 0x<encoding>`, optionally followed by the kind of error expected, one of
+    /// `bounds`, `malformed`, `invalid-type` or `internal`. Empty lines and lines starting with
+    /// `#` are ignored.
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut corpus = Self::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue
+            }
+            let error = |reason: String| ParseError { line: index + 1, reason };
+            let (name, rest) = line
+                .split_once(':')
+                .ok_or_else(|| error("expected `name: 0x<encoding>`".to_string()))?;
+            let mut words = rest.split_whitespace();
+            let encoding = words.next().unwrap_or_default();
+            let encoding = hex::decode_0x(encoding).map_err(|err| error(err.to_string()))?;
+            let kind = match words.next() {
+                None => None,
+                Some("bounds") => Some(ErrorKind::Bounds),
+                Some("malformed") => Some(ErrorKind::Malformed),
+                Some("invalid-type") => Some(ErrorKind::InvalidType),
+                Some("internal") => Some(ErrorKind::Internal),
+                Some(kind) => return Err(error(format!("unknown error kind `{kind}`"))),
+            };
+            if let Some(word) = words.next() {
+                return Err(error(format!("unexpected `{word}` after the error kind")))
+            }
+            corpus.entries.push(CorpusEntry { name: name.trim().to_string(), encoding, kind });
+        }
+        Ok(corpus)
+    }
+
+    /// Add the malformed `encoding` under `name`.
+    pub fn add(&mut self, name: impl Into<String>, encoding: impl Into<Vec<u8>>) -> &mut Self {
+        self.entries.push(CorpusEntry { name: name.into(), encoding: encoding.into(), kind: None });
+        self
+    }
+
+    /// Add the malformed `encoding` under `name`, which must fail to decode with an error of
+    /// `kind`.
+    pub fn add_with_kind(
+        &mut self,
+        name: impl Into<String>,
+        encoding: impl Into<Vec<u8>>,
+        kind: ErrorKind,
+    ) -> &mut Self {
+        self.entries.push(CorpusEntry {
+            name: name.into(),
+            encoding: encoding.into(),
+            kind: Some(kind),
+        });
+        self
+    }
+
+    pub fn entries(&self) -> &[CorpusEntry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Check that every encoding is rejected, returning the entries which are not along with
+    /// how they failed.
+    pub fn check(&self) -> Vec<(&CorpusEntry, Regression)> {
+        self.entries
+            .iter()
+            .filter_map(|entry| check_entry::<T>(entry).err().map(|regression| (entry, regression)))
+            .collect()
+    }
+
+    /// Check that every encoding is rejected, panicking with the entries which are not.
+    pub fn assert_rejected(&self) {
+        let regressions = self.check();
+        if regressions.is_empty() {
+            return
+        }
+        let mut message = format!(
+            "{} of {} malformed encodings of `{}` were not rejected:",
+            regressions.len(),
+            self.len(),
+            any::type_name::<T>()
+        );
+        for (entry, regression) in regressions {
+            message += &format!("\n  {}: {regression}", entry.name);
+        }
+        panic!("{message}");
+    }
+}
+
+fn check_entry<T: SimpleSerialize>(entry: &CorpusEntry) -> Result<(), Regression> {
+    let result = catch_unwind(AssertUnwindSafe(|| {
+        let root = T::hash_tree_root_from_bytes(&entry.encoding, Context::global());
+        (T::deserialize(&entry.encoding).map(|_| ()), root.is_ok())
+    }));
+    let (decoded, hashed) = result.map_err(|payload| {
+        let message = match payload.downcast::<String>() {
+            Ok(message) => *message,
+            Err(payload) => payload.downcast_ref::<&str>().copied().unwrap_or_default().to_string(),
+        };
+        Regression::Panicked(message)
+    })?;
+    match (decoded, entry.kind) {
+        (Ok(()), _) => return Err(Regression::Decoded),
+        (Err(err), Some(expected)) if err.kind() != expected => {
+            return Err(Regression::UnexpectedError { expected, provided: err })
+        }
+        _ => {}
+    }
+    if hashed {
+        return Err(Regression::RootFromBytes)
+    }
+    Ok(())
+}
+
+impl<T: SimpleSerialize> Default for Corpus<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: SimpleSerialize> Clone for Corpus<T> {
+    fn clone(&self) -> Self {
+        Self { entries: self.entries.clone(), _type: PhantomData }
+    }
+}
+
+impl<T: SimpleSerialize> Debug for Corpus<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Corpus")
+            .field("type", &any::type_name::<T>())
+            .field("entries", &self.entries)
+            .finish()
+    }
+}
+
+impl<T: SimpleSerialize> Extend<CorpusEntry> for Corpus<T> {
+    fn extend<I: IntoIterator<Item = CorpusEntry>>(&mut self, entries: I) {
+        self.entries.extend(entries);
+    }
+}
+
+/// Write the corpus in the text format read by [`Corpus::parse`].
+impl<T: SimpleSerialize> Display for Corpus<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for entry in &self.entries {
+            write!(f, "{}: {}", entry.name, hex::encode_0x(&entry.encoding))?;
+            match entry.kind {
+                Some(ErrorKind::Bounds) => write!(f, " bounds")?,
+                Some(ErrorKind::Malformed) => write!(f, " malformed")?,
+                Some(ErrorKind::InvalidType) => write!(f, " invalid-type")?,
+                Some(ErrorKind::Internal) => write!(f, " internal")?,
+                None => {}
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    type Nested = List<List<u8, 4>, 2>;
+
+    #[test]
+    fn test_corpus_roundtrip() {
+        let mut corpus = Corpus::<Nested>::new();
+        corpus
+            .add("truncated offset", [8u8, 0, 0])
+            .add_with_kind("too many elements", [0u8; 12], ErrorKind::Malformed)
+            .add_with_kind("too many bytes", [4u8, 0, 0, 0, 1, 2, 3, 4, 5], ErrorKind::Bounds);
+        corpus.assert_rejected();
+        corpus.add_with_kind("zero length", [], ErrorKind::InvalidType).add_with_kind(
+            "internal",
+            [0u8],
+            ErrorKind::Internal,
+        );
+
+        let text = corpus.to_string();
+        assert_eq!(text.lines().count(), 5);
+        assert!(text.ends_with("internal: 0x00 internal\n"));
+        let parsed = Corpus::<Nested>::parse(&format!("# header\n\n{text}")).unwrap();
+        assert_eq!(parsed.entries(), corpus.entries());
+    }
+
+    #[test]
+    fn test_regressions() {
+        let mut corpus = Corpus::<Nested>::new();
+        corpus
+            .add("empty list", [])
+            .add_with_kind("wrong kind", [8u8, 0, 0], ErrorKind::Bounds)
+            .add("rejected", [8u8, 0, 0]);
+        let regressions = corpus.check();
+        assert_eq!(regressions.len(), 2);
+        assert_eq!(regressions[0].0.name, "empty list");
+        assert!(matches!(regressions[0].1, Regression::Decoded));
+        assert!(matches!(
+            regressions[1].1,
+            Regression::UnexpectedError { expected: ErrorKind::Bounds, .. }
+        ));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let parse = Corpus::<Nested>::parse;
+        assert_eq!(parse("ok: 0x00\nno separator").unwrap_err().line, 2);
+        assert!(parse("a: 00").is_err());
+        assert!(parse("a: 0x00 unknown").is_err());
+        assert!(parse("a: 0x00 bounds extra").is_err());
+        assert_eq!(parse("a: 0x").unwrap().entries()[0].encoding, Vec::<u8>::new());
+    }
+}
@@ -1,12 +1,14 @@
 use crate::{
-    error::{InstanceError, TypeError},
+    error::{ErrorKind, InstanceError, TypeError},
     lib::*,
     ser::BYTES_PER_LENGTH_OFFSET,
+    uint::OverflowError,
     SimpleSerialize,
 };
 
 /// Deserialization errors.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum DeserializeError {
     /// More data was expected to be in the buffer.
     ExpectedFurtherInput {
@@ -19,10 +21,37 @@ pub enum DeserializeError {
         expected: usize,
     },
     InvalidByte(u8),
+    /// Zero was decoded for a type which can not be zero, e.g. a `NonZeroU64`.
+    Zero,
     /// An invalid instance was encountered.
     InvalidInstance(InstanceError),
     /// An invalid type was encountered.
     InvalidType(TypeError),
+    /// The input of `len` bytes is longer than the maximum length `max_len` configured for
+    /// decoding it, e.g. with [`SszDecoder::with_max_len`](crate::SszDecoder::with_max_len).
+    InputTooLarge {
+        len: usize,
+        max_len: usize,
+    },
+    /// The decoded integer does not fit in the native integer type, e.g. a `usize` on a
+    /// 32-bit target.
+    Overflow(OverflowError),
+    /// The fixed-size parts of the unknown fields of a
+    /// [`ForwardCompatible`](crate::ForwardCompatible) container hold a value at `position`
+    /// which could be an offset to a variable-size part, so the end of the last known field is
+    /// not certain.
+    AmbiguousUnknownFields {
+        position: usize,
+    },
+    /// The arena a value is decoded into has `available` bytes left, fewer than the `required`
+    /// bytes of the next elements, see `ssz_rs::arena`.
+    ArenaExhausted {
+        required: usize,
+        available: usize,
+    },
+    /// The version of an envelope is not a version of the expected type, see
+    /// [`Envelope`](crate::envelope::Envelope).
+    UnknownVersion(u32),
 }
 
 impl From<InstanceError> for DeserializeError {
@@ -37,6 +66,30 @@ impl From<TypeError> for DeserializeError {
     }
 }
 
+impl From<OverflowError> for DeserializeError {
+    fn from(err: OverflowError) -> Self {
+        Self::Overflow(err)
+    }
+}
+
+impl DeserializeError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::ExpectedFurtherInput { .. } |
+            Self::AdditionalInput { .. } |
+            Self::InvalidByte(..) |
+            Self::Zero |
+            Self::AmbiguousUnknownFields { .. } |
+            Self::UnknownVersion(..) => ErrorKind::Malformed,
+            Self::ArenaExhausted { .. } => ErrorKind::Bounds,
+            Self::InvalidInstance(err) => err.kind(),
+            Self::InvalidType(err) => err.kind(),
+            Self::InputTooLarge { .. } => ErrorKind::Bounds,
+            Self::Overflow(err) => err.kind(),
+        }
+    }
+}
+
 impl Display for DeserializeError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
@@ -46,8 +99,14 @@ impl Display for DeserializeError {
                 f,
                 "invalid byte {b:x} when decoding data of the expected type"
             ),
+            DeserializeError::Zero => write!(f, "zero decoded for a type which can not be zero"),
             DeserializeError::InvalidInstance(err) => write!(f, "invalid instance: {err}"),
             DeserializeError::InvalidType(err) => write!(f, "invalid type: {err}"),
+            DeserializeError::InputTooLarge { len, max_len } => write!(f, "input of {len} bytes exceeds the maximum length {max_len}"),
+            DeserializeError::Overflow(err) => write!(f, "{err}"),
+            DeserializeError::AmbiguousUnknownFields { position } => write!(f, "the unknown fields may hold an offset at byte {position}, so the end of the last known field is ambiguous"),
+            DeserializeError::ArenaExhausted { required, available } => write!(f, "the arena has {available} bytes left but {required} bytes are required"),
+            DeserializeError::UnknownVersion(version) => write!(f, "unknown version {version}"),
         }
     }
 }
@@ -63,11 +122,62 @@ pub trait Deserialize {
         Self: Sized;
 }
 
+/// A data structure decoded from an encoding whose number of elements is known from outside
+/// the encoding, e.g. from a header of the frame carrying it, rather than from its type.
+///
+/// SSZ types bound their length with their type (`List<T, N>`), so a schema with a trailing
+/// sequence sized by the outer frame can be decoded as a `Vec<T>` of the known length instead.
+/// The SSZ collections check the known length against their bounds: `len` counts the elements
+/// of `List` and `Vector`, the bits of `Bitlist` and `Bitvector` and the bytes of `Utf8List`.
+///
+/// ```rust
+/// use ssz_rs::{prelude::*, DeserializeWithLength};
+///
+/// // a frame with the number of entries in its first byte, followed by the entries
+/// let frame = [2u8, 7, 0, 9, 0];
+/// let entries = Vec::<u16>::deserialize_with_length(&frame[1..], frame[0] as usize).unwrap();
+/// assert_eq!(entries, vec![7, 9]);
+/// assert!(Vec::<u16>::deserialize_with_length(&frame[1..], 3).is_err());
+/// ```
+pub trait DeserializeWithLength: Sized {
+    /// Decode a value of exactly `len` elements from `encoding`.
+    fn deserialize_with_length(encoding: &[u8], len: usize) -> Result<Self, DeserializeError>;
+}
+
+impl<T: SimpleSerialize> DeserializeWithLength for Vec<T> {
+    fn deserialize_with_length(encoding: &[u8], len: usize) -> Result<Self, DeserializeError> {
+        if T::is_variable_size() {
+            let ranges = variable_element_ranges(encoding)?;
+            if ranges.len() != len {
+                return Err(InstanceError::exact::<Self>(len, ranges.len()).into())
+            }
+            return ranges.into_iter().map(|range| T::deserialize(&encoding[range])).collect()
+        }
+        // NOTE: check the length of the encoding before decoding any element
+        let expected = len.saturating_mul(T::size_hint());
+        if encoding.len() < expected {
+            return Err(DeserializeError::ExpectedFurtherInput {
+                provided: encoding.len(),
+                expected,
+            })
+        }
+        if encoding.len() > expected {
+            return Err(DeserializeError::AdditionalInput { provided: encoding.len(), expected })
+        }
+        deserialize_fixed_homogeneous_composite(encoding)
+    }
+}
+
 fn deserialize_fixed_homogeneous_composite<T>(encoding: &[u8]) -> Result<Vec<T>, DeserializeError>
 where
     T: SimpleSerialize,
 {
-    let remainder = encoding.len() % T::size_hint();
+    let size = T::size_hint();
+    // NOTE: elements without any encoding, e.g. `Vector<T, 0>`, are not legal SSZ types
+    if size == 0 {
+        return Err(TypeError::InvalidBound(size).into())
+    }
+    let remainder = encoding.len() % size;
     if remainder != 0 {
         return Err(DeserializeError::AdditionalInput {
             provided: encoding.len(),
@@ -76,7 +186,7 @@ where
     }
 
     let mut elements = vec![];
-    for chunk in encoding.chunks_exact(T::size_hint()) {
+    for chunk in encoding.chunks_exact(size) {
         let element = T::deserialize(chunk)?;
         elements.push(element);
     }
@@ -89,34 +199,59 @@ fn deserialize_variable_homogeneous_composite<T>(
 where
     T: SimpleSerialize,
 {
+    variable_element_ranges(encoding)?
+        .into_iter()
+        .map(|range| T::deserialize(&encoding[range]))
+        .collect()
+}
+
+/// Return the range of the encoding of each element in the `encoding` of a homogeneous
+/// composite type with variable-size elements, checking the offsets of the elements.
+pub(crate) fn variable_element_ranges(
+    encoding: &[u8],
+) -> Result<Vec<Range<usize>>, DeserializeError> {
     if encoding.is_empty() {
         return Ok(vec![])
     }
+    let read_offset = |index: usize| {
+        let start = index * BYTES_PER_LENGTH_OFFSET;
+        let end = start + BYTES_PER_LENGTH_OFFSET;
+        encoding
+            .get(start..end)
+            .ok_or(DeserializeError::ExpectedFurtherInput {
+                provided: encoding.len(),
+                expected: end,
+            })
+            .and_then(u32::deserialize)
+            .map(|offset| offset as usize)
+    };
 
-    let data_pointer = u32::deserialize(&encoding[..BYTES_PER_LENGTH_OFFSET])?;
-    let data_pointer = data_pointer as usize;
-    if encoding.len() < data_pointer {
+    let first = read_offset(0)?;
+    // NOTE: check the first offset before allocating for the number of elements it implies
+    if first > encoding.len() {
         return Err(DeserializeError::ExpectedFurtherInput {
             provided: encoding.len(),
-            expected: data_pointer,
+            expected: first,
         })
     }
-
-    let offsets = &mut encoding[..data_pointer]
-        .chunks_exact(BYTES_PER_LENGTH_OFFSET)
-        .map(|chunk| u32::deserialize(chunk).map(|offset| offset as usize))
-        .collect::<Result<Vec<usize>, DeserializeError>>()?;
-    offsets.push(encoding.len());
-
-    let element_count = data_pointer / BYTES_PER_LENGTH_OFFSET;
-    let mut result = Vec::with_capacity(element_count);
-    for span in offsets.windows(2) {
-        let start = span[0];
-        let end = span[1];
-        let element = T::deserialize(&encoding[start..end])?;
-        result.push(element);
+    if first == 0 || first % BYTES_PER_LENGTH_OFFSET != 0 {
+        return Err(DeserializeError::AdditionalInput {
+            provided: first,
+            expected: first - first % BYTES_PER_LENGTH_OFFSET,
+        })
     }
-    Ok(result)
+    let count = first / BYTES_PER_LENGTH_OFFSET;
+    let mut ranges = Vec::with_capacity(count);
+    let mut start = first;
+    for index in 1..=count {
+        let end = if index < count { read_offset(index)? } else { encoding.len() };
+        if end < start || end > encoding.len() {
+            return Err(DeserializeError::ExpectedFurtherInput { provided: end, expected: start })
+        }
+        ranges.push(start..end);
+        start = end;
+    }
+    Ok(ranges)
 }
 
 pub fn deserialize_homogeneous_composite<T>(encoding: &[u8]) -> Result<Vec<T>, DeserializeError>
@@ -129,3 +264,109 @@ where
         deserialize_fixed_homogeneous_composite(encoding)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bitlist::Bitlist, bitvector::Bitvector, list::List, serialize, utf8_list::Utf8List,
+        vector::Vector,
+    };
+
+    #[test]
+    fn test_deserialize_with_length() {
+        let elements = vec![
+            List::<u8, 4>::try_from(vec![1u8, 2]).unwrap(),
+            List::default(),
+            List::try_from(vec![3u8]).unwrap(),
+        ];
+        let encoding = serialize(&List::<_, 3>::try_from(elements.clone()).unwrap()).unwrap();
+        let decoded = Vec::<List<u8, 4>>::deserialize_with_length(&encoding, 3).unwrap();
+        assert_eq!(decoded, elements);
+        assert!(matches!(
+            Vec::<List<u8, 4>>::deserialize_with_length(&encoding, 2),
+            Err(DeserializeError::InvalidInstance(InstanceError::Exact {
+                required: 2,
+                provided: 3,
+                ..
+            }))
+        ));
+
+        assert!(Vec::<u32>::deserialize_with_length(&[], 0).unwrap().is_empty());
+        assert!(Vec::<List<u8, 4>>::deserialize_with_length(&[], 0).unwrap().is_empty());
+        assert!(matches!(
+            Vec::<u32>::deserialize_with_length(&[0; 8], 3),
+            Err(DeserializeError::ExpectedFurtherInput { provided: 8, expected: 12 })
+        ));
+        assert!(Vec::<u32>::deserialize_with_length(&[0; 8], usize::MAX).is_err());
+
+        let list = List::<u16, 2>::deserialize_with_length(&[1, 0, 2, 0], 2).unwrap();
+        assert_eq!(list.as_ref(), [1, 2]);
+        assert!(matches!(
+            List::<u16, 1>::deserialize_with_length(&[1, 0, 2, 0], 2),
+            Err(DeserializeError::InvalidInstance(InstanceError::Bounded {
+                bound: 1,
+                provided: 2,
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn test_deserialize_collections_with_length() {
+        let vector = Vector::<u16, 2>::deserialize_with_length(&[1, 0, 2, 0], 2).unwrap();
+        assert_eq!(vector.as_ref(), [1, 2]);
+        assert!(matches!(
+            Vector::<u16, 2>::deserialize_with_length(&[1, 0, 2, 0], 1),
+            Err(DeserializeError::InvalidInstance(InstanceError::Exact {
+                required: 2,
+                provided: 1,
+                ..
+            }))
+        ));
+
+        let bits = Bitvector::<4>::deserialize_with_length(&[0b0101], 4).unwrap();
+        assert_eq!(bits.iter().by_vals().collect::<Vec<_>>(), [true, false, true, false]);
+        assert!(Bitvector::<4>::deserialize_with_length(&[0b0101], 8).is_err());
+
+        // three bits and the delimiting bit
+        let bits = Bitlist::<16>::deserialize_with_length(&[0b1101], 3).unwrap();
+        assert_eq!(bits.iter().by_vals().collect::<Vec<_>>(), [true, false, true]);
+        assert!(matches!(
+            Bitlist::<16>::deserialize_with_length(&[0b1101], 2),
+            Err(DeserializeError::InvalidInstance(InstanceError::Exact {
+                required: 2,
+                provided: 3,
+                ..
+            }))
+        ));
+        assert!(matches!(
+            Bitlist::<16>::deserialize_with_length(&[0b1101], 9),
+            Err(DeserializeError::ExpectedFurtherInput { provided: 1, expected: 2 })
+        ));
+        assert!(Bitlist::<4>::deserialize_with_length(&[0, 1], 8).is_err());
+
+        let text = Utf8List::<8>::deserialize_with_length(b"ssz", 3).unwrap();
+        assert_eq!(&*text, "ssz");
+        assert!(matches!(
+            Utf8List::<8>::deserialize_with_length(b"ssz", 2),
+            Err(DeserializeError::AdditionalInput { provided: 3, expected: 2 })
+        ));
+        assert!(matches!(
+            Utf8List::<2>::deserialize_with_length(b"ssz", 3),
+            Err(DeserializeError::InvalidInstance(InstanceError::Bounded { bound: 2, .. }))
+        ));
+    }
+
+    #[test]
+    fn test_variable_element_ranges_with_hostile_first_offset() {
+        // NOTE: a first offset past the encoding must fail before allocating for its elements
+        let encoding = u32::MAX.to_le_bytes();
+        assert!(matches!(
+            variable_element_ranges(&encoding),
+            Err(DeserializeError::ExpectedFurtherInput { provided: 4, expected }) if expected == u32::MAX as usize
+        ));
+        let encoding = [8, 0, 0, 0, 9, 0, 0, 0, 1];
+        assert_eq!(variable_element_ranges(&encoding).unwrap(), [8..9, 9..9]);
+    }
+}
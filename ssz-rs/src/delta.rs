@@ -0,0 +1,272 @@
+//! A `List<u64, N>` kept compressed in memory, for large lists of similar values like the
+//! balances of the validators in the beacon state.
+//!
+//! A [`DeltaList`] stores the difference of each element from the previous one as a variable
+//! length integer, so balances which are close to each other take one or two bytes each rather
+//! than eight. The elements are only expanded to encode the list or compute its root, which are
+//! those of the equivalent `List<u64, N>`.
+//!
+//! ```rust
+//! use ssz_rs::{delta::DeltaList, prelude::*};
+//!
+//! let balances = (0..1000u64).map(|i| 32_000_000_000 + i % 7).collect::<Vec<_>>();
+//! let mut list = List::<u64, 1024>::try_from(balances.clone()).unwrap();
+//! let mut compressed = DeltaList::<1024>::try_from(balances.as_slice()).unwrap();
+//! assert!(compressed.compressed_size() < 8 * 1000 / 4);
+//! assert_eq!(serialize(&compressed).unwrap(), serialize(&list).unwrap());
+//! assert_eq!(compressed.hash_tree_root().unwrap(), list.hash_tree_root().unwrap());
+//! assert_eq!(compressed.to_list(), list);
+//! ```
+use crate::{
+    de::{Deserialize, DeserializeError},
+    error::{Error, InstanceError},
+    lib::*,
+    list::List,
+    merkleization::{
+        chunks_to_nodes, merkleize_with, mix_in_length, pack_bytes, Context, GeneralizedIndex,
+        MerkleHasher, MerkleizationError, Merkleized, Node, PathElement, PathError,
+    },
+    ser::{Serialize, SerializeError},
+    SimpleSerialize, Sized,
+};
+
+const ELEMENT_SIZE: usize = 8;
+
+/// A `List<u64, N>` storing the zigzag-encoded differences of consecutive elements as LEB128
+/// integers.
+#[derive(Default, Clone, PartialEq, Eq)]
+pub struct DeltaList<const N: usize> {
+    deltas: Vec<u8>,
+    len: usize,
+    last: u64,
+}
+
+impl<const N: usize> fmt::Debug for DeltaList<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<const N: usize> DeltaList<N> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return the number of bytes used to store the elements.
+    pub fn compressed_size(&self) -> usize {
+        self.deltas.len()
+    }
+
+    /// Append `value` to the end of the list.
+    /// Returns the `value` back if the list is already at its limit `N`.
+    pub fn push(&mut self, value: u64) -> Result<(), (u64, Error)> {
+        if self.len >= N {
+            let provided = self.len + 1;
+            return Err((value, Error::Instance(InstanceError::bounded::<Self>(N, provided))))
+        }
+        let delta = value.wrapping_sub(self.last) as i64;
+        let mut zigzag = ((delta << 1) ^ (delta >> 63)) as u64;
+        while zigzag >= 0x80 {
+            self.deltas.push(zigzag as u8 | 0x80);
+            zigzag >>= 7;
+        }
+        self.deltas.push(zigzag as u8);
+        self.len += 1;
+        self.last = value;
+        Ok(())
+    }
+
+    /// Return the element at `index` by expanding the elements before it.
+    pub fn get(&self, index: usize) -> Option<u64> {
+        self.iter().nth(index)
+    }
+
+    /// Return an iterator expanding the elements in order.
+    pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        let mut bytes = self.deltas.iter();
+        let mut value = 0u64;
+        (0..self.len).map(move |_| {
+            let mut zigzag = 0u64;
+            let mut shift = 0;
+            for byte in bytes.by_ref() {
+                zigzag |= ((byte & 0x7f) as u64) << shift;
+                shift += 7;
+                if byte & 0x80 == 0 {
+                    break
+                }
+            }
+            let delta = (zigzag >> 1) as i64 ^ -((zigzag & 1) as i64);
+            value = value.wrapping_add(delta as u64);
+            value
+        })
+    }
+
+    /// Expand the elements into a `List<u64, N>`.
+    pub fn to_list(&self) -> List<u64, N> {
+        List::try_from(self.iter().collect::<Vec<_>>()).expect("length is within the bound")
+    }
+
+    // Append the encodings of the elements to `buffer`.
+    fn expand_into(&self, buffer: &mut Vec<u8>) {
+        buffer.reserve(self.len * ELEMENT_SIZE);
+        for value in self.iter() {
+            buffer.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+}
+
+impl<const N: usize> TryFrom<&[u64]> for DeltaList<N> {
+    type Error = Error;
+
+    fn try_from(values: &[u64]) -> Result<Self, Self::Error> {
+        let mut list = Self::new();
+        for value in values {
+            list.push(*value).map_err(|(_, err)| err)?;
+        }
+        Ok(list)
+    }
+}
+
+impl<const N: usize> From<&List<u64, N>> for DeltaList<N> {
+    fn from(list: &List<u64, N>) -> Self {
+        Self::try_from(list.as_ref()).expect("length is within the bound")
+    }
+}
+
+impl<const N: usize> Sized for DeltaList<N> {
+    fn is_variable_size() -> bool {
+        true
+    }
+
+    fn size_hint() -> usize {
+        0
+    }
+}
+
+impl<const N: usize> Serialize for DeltaList<N> {
+    fn serialize(&self, buffer: &mut Vec<u8>) -> Result<usize, SerializeError> {
+        let start = buffer.len();
+        self.expand_into(buffer);
+        Ok(buffer.len() - start)
+    }
+}
+
+impl<const N: usize> Deserialize for DeltaList<N> {
+    fn deserialize(encoding: &[u8]) -> Result<Self, DeserializeError> {
+        let remainder = encoding.len() % ELEMENT_SIZE;
+        if remainder != 0 {
+            let expected = encoding.len() - remainder;
+            return Err(DeserializeError::AdditionalInput { provided: encoding.len(), expected })
+        }
+        let len = encoding.len() / ELEMENT_SIZE;
+        if len > N {
+            return Err(InstanceError::bounded::<Self>(N, len).into())
+        }
+        let mut list = Self::new();
+        for element in encoding.chunks_exact(ELEMENT_SIZE) {
+            let value = u64::from_le_bytes(element.try_into().expect("is one element"));
+            list.push(value).expect("length is within the bound");
+        }
+        Ok(list)
+    }
+}
+
+impl<const N: usize> Merkleized for DeltaList<N> {
+    fn hash_tree_root_with<H: MerkleHasher>(
+        &mut self,
+        context: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
+        let mut chunks = vec![];
+        self.expand_into(&mut chunks);
+        pack_bytes(&mut chunks);
+        let data_root = merkleize_with(&chunks, Some(Self::chunk_count()), context)?;
+        Ok(mix_in_length(&data_root, self.len, context))
+    }
+
+    fn leaves<H: MerkleHasher>(&mut self, _: &Context<H>) -> Result<Vec<Node>, MerkleizationError> {
+        let mut chunks = vec![];
+        self.expand_into(&mut chunks);
+        pack_bytes(&mut chunks);
+        Ok(chunks_to_nodes(&chunks))
+    }
+
+    const CHUNK_COUNT: usize = List::<u64, N>::CHUNK_COUNT;
+
+    fn merkle_limit() -> Option<usize> {
+        Some(Self::chunk_count())
+    }
+}
+
+impl<const N: usize> SimpleSerialize for DeltaList<N> {
+    fn nesting_depth() -> usize {
+        List::<u64, N>::nesting_depth()
+    }
+
+    const MAX_SIZE: usize = List::<u64, N>::MAX_SIZE;
+
+    const NESTING_DEPTH: usize = List::<u64, N>::NESTING_DEPTH;
+
+    fn generalized_index_from(
+        root: GeneralizedIndex,
+        path: &[PathElement<'_>],
+    ) -> Result<GeneralizedIndex, PathError> {
+        List::<u64, N>::generalized_index_from(root, path)
+    }
+
+    fn is_default(&self) -> bool {
+        self.is_empty()
+    }
+
+    fn default_hash_tree_root_with<H: MerkleHasher>(
+        context: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
+        List::<u64, N>::default_hash_tree_root_with(context)
+    }
+
+    fn ssz_heap_size(&self) -> usize {
+        self.deltas.capacity()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn test_delta_list() {
+        let values = [5u64, 3, u64::MAX, 0, 1 << 40, 1 << 40, 7];
+        let mut compressed = DeltaList::<8>::try_from(values.as_ref()).unwrap();
+        let mut list = List::<u64, 8>::try_from(values.to_vec()).unwrap();
+        assert_eq!(compressed.iter().collect::<Vec<_>>(), values);
+        assert_eq!(compressed.get(2), Some(u64::MAX));
+        assert_eq!(compressed.get(7), None);
+
+        let encoding = serialize(&compressed).unwrap();
+        assert_eq!(encoding, serialize(&list).unwrap());
+        assert_eq!(DeltaList::<8>::deserialize(&encoding).unwrap(), compressed);
+        assert_eq!(compressed.hash_tree_root().unwrap(), list.hash_tree_root().unwrap());
+        assert_eq!(DeltaList::from(&list), compressed);
+
+        compressed.push(9).unwrap();
+        assert!(compressed.push(10).is_err());
+        assert!(DeltaList::<8>::try_from([0u64; 9].as_ref()).is_err());
+        assert!(DeltaList::<8>::deserialize(&[0u8; 9 * 8]).is_err());
+        assert!(DeltaList::<8>::deserialize(&encoding[1..]).is_err());
+
+        let mut empty = DeltaList::<8>::default();
+        assert!(empty.is_default());
+        assert_eq!(
+            empty.hash_tree_root().unwrap(),
+            List::<u64, 8>::default().hash_tree_root().unwrap()
+        );
+    }
+}
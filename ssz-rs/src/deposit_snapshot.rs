@@ -0,0 +1,108 @@
+//! Support for the finalized deposit tree snapshot format of
+//! [EIP-4881](https://eips.ethereum.org/EIPS/eip-4881).
+use crate::{lib::*, merkleization::IncrementalMerkleTree, prelude::*};
+
+/// The depth of the deposit contract's Merkle tree.
+pub const DEPOSIT_CONTRACT_DEPTH: usize = 32;
+
+/// A snapshot of the finalized portion of the deposit contract's Merkle tree.
+#[derive(Default, Debug, Clone, PartialEq, Eq, SimpleSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DepositTreeSnapshot {
+    pub finalized: List<Node, DEPOSIT_CONTRACT_DEPTH>,
+    pub deposit_root: Node,
+    pub deposit_count: u64,
+    pub execution_block_hash: Node,
+    pub execution_block_height: u64,
+}
+
+impl DepositTreeSnapshot {
+    /// Construct a snapshot of the finalized leaves of `tree` along with the
+    /// execution block they were finalized in.
+    ///
+    /// Returns `None` if `tree` is not of depth `DEPOSIT_CONTRACT_DEPTH`.
+    pub fn from_tree(
+        tree: &IncrementalMerkleTree,
+        execution_block_hash: Node,
+        execution_block_height: u64,
+    ) -> Option<Self> {
+        if tree.depth() != DEPOSIT_CONTRACT_DEPTH {
+            return None
+        }
+        let finalized_tree = IncrementalMerkleTree::from_finalized(
+            DEPOSIT_CONTRACT_DEPTH,
+            tree.finalized().to_vec(),
+            tree.finalized_count(),
+        )?;
+        Some(Self {
+            finalized: tree.finalized().to_vec().try_into().ok()?,
+            deposit_root: finalized_tree.root_with_length(),
+            deposit_count: tree.finalized_count() as u64,
+            execution_block_hash,
+            execution_block_height,
+        })
+    }
+
+    /// Reconstruct a tree from this snapshot which can be extended with further deposits
+    /// and produce proofs for them.
+    ///
+    /// Returns `None` if the snapshot is inconsistent with its `deposit_root`.
+    pub fn to_tree(&self) -> Option<IncrementalMerkleTree> {
+        let deposit_count = usize::try_from(self.deposit_count).ok()?;
+        let tree = IncrementalMerkleTree::from_finalized(
+            DEPOSIT_CONTRACT_DEPTH,
+            self.finalized.to_vec(),
+            deposit_count,
+        )?;
+        if tree.root_with_length() != self.deposit_root {
+            return None
+        }
+        Some(tree)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deposit(i: u8) -> Node {
+        Node::try_from([i; 32].as_ref()).unwrap()
+    }
+
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let mut tree = IncrementalMerkleTree::new(DEPOSIT_CONTRACT_DEPTH);
+        for i in 0..13 {
+            tree.push(deposit(i)).unwrap();
+        }
+        tree.finalize(11).unwrap();
+        let deposit_root = {
+            let mut tree = IncrementalMerkleTree::new(DEPOSIT_CONTRACT_DEPTH);
+            for i in 0..11 {
+                tree.push(deposit(i)).unwrap();
+            }
+            tree.root_with_length()
+        };
+
+        let block_hash = deposit(0xff);
+        let mut snapshot = DepositTreeSnapshot::from_tree(&tree, block_hash, 100).unwrap();
+        assert_eq!(snapshot.finalized.len(), 3);
+        assert_eq!(snapshot.deposit_root, deposit_root);
+        assert_eq!(snapshot.deposit_count, 11);
+
+        let encoding = serialize(&snapshot).expect("can serialize");
+        let recovered = DepositTreeSnapshot::deserialize(&encoding).expect("can deserialize");
+        assert_eq!(recovered, snapshot);
+
+        let mut recovered_tree = recovered.to_tree().unwrap();
+        for i in 11..13 {
+            recovered_tree.push(deposit(i)).unwrap();
+        }
+        assert_eq!(recovered_tree.root_with_length(), tree.root_with_length());
+        let root = recovered_tree.root();
+        assert!(recovered_tree.proof(12).unwrap().verify(&root));
+
+        snapshot.deposit_count = 10;
+        assert!(snapshot.to_tree().is_none());
+    }
+}
@@ -0,0 +1,157 @@
+//! Support for versioning the messages of an application with the SSZ container
+//! `Envelope[uint32, T]` of a version and a body.
+//!
+//! The version is the first field of the envelope, so it is read with [`version_of`] before the
+//! type of the body is known, and [`Versioned`] decodes a message of several versions by
+//! dispatching on it:
+//!
+//! ```rust
+//! use ssz_rs::prelude::*;
+//! use ssz_rs::envelope::{Envelope, Versioned};
+//!
+//! #[derive(Default, Debug, PartialEq, Eq, SimpleSerialize)]
+//! struct Ping {
+//!     nonce: u64,
+//! }
+//!
+//! #[derive(Default, Debug, PartialEq, Eq, SimpleSerialize)]
+//! struct PingV2 {
+//!     nonce: u64,
+//!     payload: List<u8, 64>,
+//! }
+//!
+//! #[derive(Debug, PartialEq, Eq)]
+//! enum Message {
+//!     V1(Ping),
+//!     V2(PingV2),
+//! }
+//!
+//! impl Versioned for Message {
+//!     fn deserialize_versioned(version: u32, encoding: &[u8]) -> Result<Self, DeserializeError> {
+//!         match version {
+//!             1 => Ok(Self::V1(Envelope::deserialize(encoding)?.body)),
+//!             2 => Ok(Self::V2(Envelope::deserialize(encoding)?.body)),
+//!             version => Err(DeserializeError::UnknownVersion(version)),
+//!         }
+//!     }
+//! }
+//!
+//! let encoding = serialize(&Envelope::new(1, Ping { nonce: 7 })).unwrap();
+//! assert_eq!(Message::deserialize_envelope(&encoding).unwrap(), Message::V1(Ping { nonce: 7 }));
+//!
+//! let encoding = serialize(&Envelope::new(3, Ping { nonce: 7 })).unwrap();
+//! assert!(Message::deserialize_envelope(&encoding).is_err());
+//! ```
+use crate::{
+    de::{Deserialize, DeserializeError},
+    lib::*,
+    prelude::*,
+};
+
+/// The size of the encoding of the version of an envelope.
+const VERSION_SIZE: usize = 4;
+
+/// A `body` tagged with the `version` of its type, i.e. the SSZ container
+/// `Envelope[uint32, T]`.
+#[derive(Default, Debug, Clone, PartialEq, Eq, SimpleSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Envelope<T: SimpleSerialize> {
+    pub version: u32,
+    pub body: T,
+}
+
+impl<T: SimpleSerialize> Envelope<T> {
+    pub fn new(version: u32, body: T) -> Self {
+        Self { version, body }
+    }
+
+    /// Decode an envelope of `T` from `encoding`, if it has the given `version`, so a message
+    /// of another version is not decoded as `T` by accident.
+    pub fn deserialize_version(encoding: &[u8], version: u32) -> Result<Self, DeserializeError> {
+        let found = version_of(encoding)?;
+        if found != version {
+            return Err(DeserializeError::UnknownVersion(found))
+        }
+        Self::deserialize(encoding)
+    }
+}
+
+/// Return the version of the envelope encoded as `encoding`, without decoding its body.
+pub fn version_of(encoding: &[u8]) -> Result<u32, DeserializeError> {
+    let version = encoding.get(..VERSION_SIZE).ok_or(DeserializeError::ExpectedFurtherInput {
+        provided: encoding.len(),
+        expected: VERSION_SIZE,
+    })?;
+    u32::deserialize(version)
+}
+
+/// A message with a type per version, decoded from an [`Envelope`] of the type of its version.
+pub trait Versioned: core::marker::Sized {
+    /// Decode the message from the `encoding` of an `Envelope` of the given `version`, or
+    /// return `DeserializeError::UnknownVersion` if there is no type for the `version`.
+    fn deserialize_versioned(version: u32, encoding: &[u8]) -> Result<Self, DeserializeError>;
+
+    /// Decode the message from the encoding of an `Envelope` of any of its versions.
+    fn deserialize_envelope(encoding: &[u8]) -> Result<Self, DeserializeError> {
+        Self::deserialize_versioned(version_of(encoding)?, encoding)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::ErrorKind, serialize};
+
+    #[derive(Default, Debug, PartialEq, Eq, SimpleSerialize)]
+    struct Checkpoint {
+        epoch: u64,
+    }
+
+    #[derive(Debug, PartialEq, Eq)]
+    enum Message {
+        Checkpoint(Checkpoint),
+        Roots(List<Node, 4>),
+    }
+
+    impl Versioned for Message {
+        fn deserialize_versioned(version: u32, encoding: &[u8]) -> Result<Self, DeserializeError> {
+            match version {
+                0 => Ok(Self::Checkpoint(Envelope::deserialize(encoding)?.body)),
+                1 => Ok(Self::Roots(Envelope::deserialize(encoding)?.body)),
+                version => Err(DeserializeError::UnknownVersion(version)),
+            }
+        }
+    }
+
+    #[test]
+    fn test_envelope() {
+        let mut envelope = Envelope::new(0, Checkpoint { epoch: 3 });
+        let encoding = serialize(&envelope).unwrap();
+        assert_eq!(encoding, [0, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0]);
+        let body_root = envelope.body.hash_tree_root().unwrap();
+        let expected = Node::hash_pair(&0u32.hash_tree_root().unwrap(), &body_root);
+        assert_eq!(envelope.hash_tree_root().unwrap(), expected);
+        assert_eq!(
+            Message::deserialize_envelope(&encoding).unwrap(),
+            Message::Checkpoint(Checkpoint { epoch: 3 })
+        );
+
+        let roots = List::try_from(vec![Node::default(); 2]).unwrap();
+        let encoding = serialize(&Envelope::new(1, roots.clone())).unwrap();
+        assert_eq!(version_of(&encoding).unwrap(), 1);
+        assert_eq!(encoding[4..8], [8, 0, 0, 0]);
+        assert_eq!(Message::deserialize_envelope(&encoding).unwrap(), Message::Roots(roots));
+        assert!(Envelope::<List<Node, 4>>::deserialize_version(&encoding, 1).is_ok());
+        let err = Envelope::<Checkpoint>::deserialize_version(&encoding, 0).unwrap_err();
+        assert!(matches!(err, DeserializeError::UnknownVersion(1)));
+        assert_eq!(err.kind(), ErrorKind::Malformed);
+
+        let encoding = serialize(&Envelope::new(9, Checkpoint::default())).unwrap();
+        let err = Message::deserialize_envelope(&encoding).unwrap_err();
+        assert_eq!(err.to_string(), "unknown version 9");
+        assert!(matches!(
+            Message::deserialize_envelope(&encoding[..3]),
+            Err(DeserializeError::ExpectedFurtherInput { provided: 3, expected: 4 })
+        ));
+    }
+}
@@ -1,7 +1,22 @@
 use crate::{de::DeserializeError, lib::*, merkleization::MerkleizationError, ser::SerializeError};
 
+/// The category of an error, for handling errors without matching on every variant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// A value or encoding exceeds a limit, e.g. a `List` with more elements than its bound.
+    Bounds,
+    /// An encoding is not the encoding of any value of the expected type.
+    Malformed,
+    /// A type can not be used as requested, e.g. a `Vector` of length 0.
+    InvalidType,
+    /// An operation failed on a valid value, which indicates a bug.
+    Internal,
+}
+
 /// Top-level error to wrap all other errors in this crate
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     /// A serialization error.
     Serialize(SerializeError),
@@ -15,6 +30,18 @@ pub enum Error {
     Type(TypeError),
 }
 
+impl Error {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Serialize(err) => err.kind(),
+            Self::Deserialize(err) => err.kind(),
+            Self::Merkleization(err) => err.kind(),
+            Self::Instance(err) => err.kind(),
+            Self::Type(err) => err.kind(),
+        }
+    }
+}
+
 impl From<SerializeError> for Error {
     fn from(err: SerializeError) -> Self {
         Self::Serialize(err)
@@ -33,6 +60,18 @@ impl From<MerkleizationError> for Error {
     }
 }
 
+impl From<InstanceError> for Error {
+    fn from(err: InstanceError) -> Self {
+        Self::Instance(err)
+    }
+}
+
+impl From<TypeError> for Error {
+    fn from(err: TypeError) -> Self {
+        Self::Type(err)
+    }
+}
+
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
@@ -48,11 +87,48 @@ impl Display for Error {
 #[cfg(feature = "std")]
 impl std::error::Error for Error {}
 
+/// An index past the end of a collection, returned by the `try_at` accessors of the
+/// collection types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutOfBounds {
+    /// The index that was requested.
+    pub index: usize,
+    /// The length of the collection.
+    pub len: usize,
+}
+
+impl OutOfBounds {
+    pub fn kind(&self) -> ErrorKind {
+        ErrorKind::Bounds
+    }
+}
+
+impl Display for OutOfBounds {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "index {} is out of bounds for a collection of length {}", self.index, self.len)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for OutOfBounds {}
+
 /// An invalid type.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum TypeError {
     /// A type is invalid for the given bounds.
     InvalidBound(usize),
+    /// A type nests more composite types than allowed (`depth > max_depth`).
+    MaxDepthExceeded { depth: usize, max_depth: usize },
+}
+
+impl TypeError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::InvalidBound(..) => ErrorKind::InvalidType,
+            Self::MaxDepthExceeded { .. } => ErrorKind::Bounds,
+        }
+    }
 }
 
 impl Display for TypeError {
@@ -61,6 +137,12 @@ impl Display for TypeError {
             Self::InvalidBound(size) => {
                 write!(f, "the type for this value is invalid with bound {size}")
             }
+            Self::MaxDepthExceeded { depth, max_depth } => {
+                write!(
+                    f,
+                    "the type has nesting depth {depth} which exceeds the maximum {max_depth}"
+                )
+            }
         }
     }
 }
@@ -69,28 +151,130 @@ impl Display for TypeError {
 impl std::error::Error for TypeError {}
 
 /// An invalid value.
+///
+/// The errors of lengths name the type of the collection, as given by `core::any::type_name`,
+/// so they can be acted upon without the input which caused them. They are built with
+/// [`InstanceError::exact`] and [`InstanceError::bounded`], and may gain variants and fields.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum InstanceError {
     /// The number of elements did not match (`provided != required`)
-    Exact { required: usize, provided: usize },
+    #[non_exhaustive]
+    Exact { required: usize, provided: usize, type_name: &'static str },
     /// The number of elements exceeded the maximum expected amount (`provided > bound`)
-    Bounded { bound: usize, provided: usize },
+    #[non_exhaustive]
+    Bounded { bound: usize, provided: usize, type_name: &'static str },
+    /// The elements were not in strictly increasing order, starting at `index`
+    Unordered { index: usize },
+}
+
+impl InstanceError {
+    /// The error of `provided` elements given for the type `T` of exactly `required` elements.
+    pub fn exact<T>(required: usize, provided: usize) -> Self {
+        Self::Exact { required, provided, type_name: any::type_name::<T>() }
+    }
+
+    /// The error of `provided` elements given for the type `T` of at most `bound` elements.
+    pub fn bounded<T>(bound: usize, provided: usize) -> Self {
+        Self::Bounded { bound, provided, type_name: any::type_name::<T>() }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Exact { .. } | Self::Bounded { .. } => ErrorKind::Bounds,
+            Self::Unordered { .. } => ErrorKind::Malformed,
+        }
+    }
 }
 
 impl Display for InstanceError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Exact { required, provided } => write!(
+            Self::Exact { required, provided, type_name } => write!(
                 f,
-                "required {required} elements for this type but {provided} elements given"
+                "required {required} elements for `{type_name}` but {provided} elements given"
             ),
-            Self::Bounded { bound, provided } => write!(
+            Self::Bounded { bound, provided, type_name } => write!(
                 f,
-                "{provided} elements given for a type with (inclusive) upper bound {bound}"
+                "{provided} elements given for `{type_name}` with (inclusive) upper bound {bound}"
             ),
+            Self::Unordered { index } => {
+                write!(
+                    f,
+                    "elements must be in strictly increasing order but element {index} is not"
+                )
+            }
         }
     }
 }
 
 #[cfg(feature = "std")]
 impl std::error::Error for InstanceError {}
+
+/// An error converting a value, with the path to the part which could not be converted, e.g.
+/// `header.slot` or `deposits[3]`.
+#[cfg(any(feature = "bridge", feature = "migrate"))]
+pub(crate) trait ErrorPath: core::marker::Sized {
+    fn path_mut(&mut self) -> &mut String;
+
+    /// Prefix the path of the error with the field or index `segment` of the enclosing value.
+    fn within(mut self, segment: &str) -> Self {
+        let path = self.path_mut();
+        if !path.is_empty() && !path.starts_with('[') {
+            path.insert(0, '.');
+        }
+        path.insert_str(0, segment);
+        self
+    }
+}
+
+/// Convert the elements of a collection with `convert`, with the index of an element in the
+/// path of its error.
+#[cfg(any(feature = "bridge", feature = "migrate"))]
+pub(crate) fn convert_elements<F, T, E: ErrorPath>(
+    elements: Vec<F>,
+    mut convert: impl FnMut(F) -> Result<T, E>,
+) -> Result<Vec<T>, E> {
+    elements
+        .into_iter()
+        .enumerate()
+        .map(|(i, element)| convert(element).map_err(|err| err.within(&format!("[{i}]"))))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn test_error_kind() {
+        let err = List::<u8, 2>::deserialize(&[1, 2, 3]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Bounds);
+        assert_eq!(Error::from(err).kind(), ErrorKind::Bounds);
+
+        let err = bool::deserialize(&[2]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Malformed);
+        let err = Vector::<u8, 0>::deserialize(&[]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidType);
+        assert_eq!(MerkleizationError::InputExceedsLimit(1).kind(), ErrorKind::Bounds);
+        let err = SerializeError::LimitExceeded { bound: 2, provided: 3 };
+        assert_eq!(MerkleizationError::SerializationError(err).kind(), ErrorKind::Bounds);
+    }
+
+    #[test]
+    fn test_instance_error_display() {
+        let err = List::<u16, 2>::deserialize(&[1, 0, 2, 0, 3, 0]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid instance: 3 elements given for `ssz_rs::list::List<u16, 2>` with (inclusive) \
+             upper bound 2"
+        );
+        let err = Vector::<u8, 3>::try_from(vec![1u8]).unwrap_err().1;
+        assert!(matches!(
+            err,
+            Error::Instance(InstanceError::Exact { required: 3, provided: 1, type_name })
+                if type_name == any::type_name::<Vector<u8, 3>>()
+        ));
+    }
+}
@@ -0,0 +1,423 @@
+//! A C interface to decode, encode and hash the SSZ types registered with
+//! [`export_ffi!`](crate::export_ffi), for consensus tooling in other languages to test their
+//! implementations against this one.
+//!
+//! A crate built as a `cdylib` or `staticlib` registers its types once; the types are then
+//! referred to by their position in the registration, e.g. `0` for the first one:
+//!
+//! ```c
+//! size_t ssz_type_count(void);
+//! const char *ssz_type_name(size_t type_index);
+//! int32_t ssz_decode(size_t type_index, const uint8_t *data, size_t len);
+//! int32_t ssz_encode(size_t type_index, const uint8_t *data, size_t len, SszBuffer *out);
+//! int32_t ssz_hash_tree_root(size_t type_index, const uint8_t *data, size_t len, uint8_t *out);
+//! void ssz_buffer_free(SszBuffer buffer);
+//! ```
+//!
+//! Every function returning an `int32_t` returns one of the codes of [`SszStatus`]. Encoding
+//! decodes `data` and writes the encoding of the decoded value to a buffer which must be freed
+//! with `ssz_buffer_free`, and hashing writes the 32 bytes of the root of the decoded value. A
+//! panic does not unwind into the caller but returns `SszStatus::Internal`.
+//!
+//! ```rust
+//! use ssz_rs::{export_ffi, ffi::{SszBuffer, SszStatus}, prelude::*};
+//!
+//! #[derive(Default, Debug, SimpleSerialize)]
+//! struct Checkpoint {
+//!     epoch: u64,
+//!     root: Node,
+//! }
+//!
+//! export_ffi!(Checkpoint, List<u16, 4>);
+//!
+//! let encoding = [7u8, 0, 0, 0, 0, 0, 0, 0].iter().chain(&[0xaa; 32]).copied().collect::<Vec<_>>();
+//! unsafe {
+//!     assert_eq!(ssz_type_count(), 2);
+//!     assert_eq!(ssz_decode(0, encoding.as_ptr(), encoding.len()), SszStatus::Ok);
+//!     assert_eq!(ssz_decode(1, encoding.as_ptr(), encoding.len()), SszStatus::Bounds);
+//!     assert_eq!(ssz_decode(2, encoding.as_ptr(), encoding.len()), SszStatus::UnknownType);
+//!
+//!     let mut root = [0u8; 32];
+//!     assert_eq!(ssz_hash_tree_root(0, encoding.as_ptr(), encoding.len(), root.as_mut_ptr()), SszStatus::Ok);
+//!     let mut checkpoint = Checkpoint::deserialize(&encoding).unwrap();
+//!     assert_eq!(root, checkpoint.hash_tree_root().unwrap().as_ref());
+//!
+//!     let mut buffer = SszBuffer::default();
+//!     assert_eq!(ssz_encode(0, encoding.as_ptr(), encoding.len(), &mut buffer), SszStatus::Ok);
+//!     assert_eq!(buffer.as_slice(), encoding);
+//!     ssz_rs::ffi::ssz_buffer_free(buffer);
+//! }
+//! ```
+use crate::{
+    error::{Error, ErrorKind},
+    lib::*,
+    merkleization::BYTES_PER_CHUNK,
+    utils::serialize,
+    SimpleSerialize,
+};
+use std::{os::raw::c_char, panic, ptr};
+
+/// The result of a call through the C interface.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SszStatus {
+    Ok = 0,
+    /// A pointer argument is null.
+    NullPointer = 1,
+    /// No type is registered at the given index.
+    UnknownType = 2,
+    /// A value or encoding exceeds a limit of its type.
+    Bounds = 3,
+    /// The data is not the encoding of any value of the type.
+    Malformed = 4,
+    /// The type can not be used, e.g. a `Vector` of length 0.
+    InvalidType = 5,
+    /// An operation failed on a valid value.
+    Internal = 6,
+}
+
+impl From<&Error> for SszStatus {
+    fn from(err: &Error) -> Self {
+        match err.kind() {
+            ErrorKind::Bounds => Self::Bounds,
+            ErrorKind::Malformed => Self::Malformed,
+            ErrorKind::InvalidType => Self::InvalidType,
+            _ => Self::Internal,
+        }
+    }
+}
+
+/// Bytes owned by this library and lent to the caller, which must be freed with
+/// [`ssz_buffer_free`].
+#[repr(C)]
+#[derive(Debug)]
+pub struct SszBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+    pub capacity: usize,
+}
+
+impl Default for SszBuffer {
+    fn default() -> Self {
+        Self { data: ptr::null_mut(), len: 0, capacity: 0 }
+    }
+}
+
+impl From<Vec<u8>> for SszBuffer {
+    fn from(bytes: Vec<u8>) -> Self {
+        let mut bytes = mem::ManuallyDrop::new(bytes);
+        Self { data: bytes.as_mut_ptr(), len: bytes.len(), capacity: bytes.capacity() }
+    }
+}
+
+impl SszBuffer {
+    /// Return the bytes of the buffer.
+    ///
+    /// # Safety
+    ///
+    /// The buffer must be empty or have been filled by this library and not freed.
+    pub unsafe fn as_slice(&self) -> &[u8] {
+        if self.data.is_null() {
+            return &[]
+        }
+        slice::from_raw_parts(self.data, self.len)
+    }
+}
+
+/// Free the bytes of `buffer`.
+///
+/// # Safety
+///
+/// The buffer must be empty or have been filled by this library and not freed before.
+#[no_mangle]
+pub unsafe extern "C" fn ssz_buffer_free(buffer: SszBuffer) {
+    if !buffer.data.is_null() {
+        drop(Vec::from_raw_parts(buffer.data, buffer.len, buffer.capacity));
+    }
+}
+
+/// The operations of the C interface on the encodings of a registered type.
+#[derive(Clone, Copy)]
+pub struct TypeOperations {
+    /// The name of the type, terminated by a NUL byte for C.
+    pub name: &'static str,
+    pub validate: fn(&[u8]) -> Result<(), Error>,
+    pub encode: fn(&[u8]) -> Result<Vec<u8>, Error>,
+    pub hash_tree_root: fn(&[u8]) -> Result<[u8; BYTES_PER_CHUNK], Error>,
+}
+
+impl TypeOperations {
+    /// Return the operations on the type `T` named `name`, which must end with a NUL byte.
+    pub const fn of<T: SimpleSerialize>(name: &'static str) -> Self {
+        let bytes = name.as_bytes();
+        assert!(
+            !bytes.is_empty() && bytes[bytes.len() - 1] == 0,
+            "the name of a type must end with a NUL byte"
+        );
+        Self {
+            name,
+            validate: validate::<T>,
+            encode: encode::<T>,
+            hash_tree_root: hash_tree_root::<T>,
+        }
+    }
+}
+
+fn validate<T: SimpleSerialize>(data: &[u8]) -> Result<(), Error> {
+    T::deserialize(data)?;
+    Ok(())
+}
+
+fn encode<T: SimpleSerialize>(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let value = T::deserialize(data)?;
+    serialize(&value).map_err(Error::from)
+}
+
+fn hash_tree_root<T: SimpleSerialize>(data: &[u8]) -> Result<[u8; BYTES_PER_CHUNK], Error> {
+    let mut value = T::deserialize(data)?;
+    let root = value.hash_tree_root().map_err(Error::from)?;
+    let mut bytes = [0u8; BYTES_PER_CHUNK];
+    bytes.copy_from_slice(root.as_ref());
+    Ok(bytes)
+}
+
+// Run `f`, returning `SszStatus::Internal` if it panics, as unwinding into C is undefined.
+fn catch_panic(f: impl FnOnce() -> SszStatus) -> SszStatus {
+    panic::catch_unwind(panic::AssertUnwindSafe(f)).unwrap_or(SszStatus::Internal)
+}
+
+// Return the data at `data` as a slice, or `None` if the pointer is null for some bytes.
+unsafe fn input<'a>(data: *const u8, len: usize) -> Option<&'a [u8]> {
+    match (data.is_null(), len) {
+        (true, 0) => Some(&[]),
+        (true, _) => None,
+        (false, len) => Some(slice::from_raw_parts(data, len)),
+    }
+}
+
+/// Return the NUL-terminated name of the type at `type_index`, or null if there is none.
+pub fn type_name(types: &[TypeOperations], type_index: usize) -> *const c_char {
+    types.get(type_index).map_or(ptr::null(), |ty| ty.name.as_ptr().cast())
+}
+
+/// Check that `data` is the encoding of a value of the type at `type_index`, returning
+/// `SszStatus::Internal` if decoding panics.
+///
+/// # Safety
+///
+/// `data` must be valid for reads of `len` bytes.
+pub unsafe fn decode(
+    types: &[TypeOperations],
+    type_index: usize,
+    data: *const u8,
+    len: usize,
+) -> SszStatus {
+    let ty = match types.get(type_index) {
+        Some(ty) => ty,
+        None => return SszStatus::UnknownType,
+    };
+    catch_panic(|| match input(data, len).map(ty.validate) {
+        Some(Ok(())) => SszStatus::Ok,
+        Some(Err(err)) => SszStatus::from(&err),
+        None => SszStatus::NullPointer,
+    })
+}
+
+/// Decode `data` as a value of the type at `type_index` and write its encoding to `out`,
+/// returning `SszStatus::Internal` if either panics.
+///
+/// # Safety
+///
+/// `data` must be valid for reads of `len` bytes and `out` must be valid for writes.
+pub unsafe fn encode_into(
+    types: &[TypeOperations],
+    type_index: usize,
+    data: *const u8,
+    len: usize,
+    out: *mut SszBuffer,
+) -> SszStatus {
+    let ty = match types.get(type_index) {
+        Some(ty) => ty,
+        None => return SszStatus::UnknownType,
+    };
+    if out.is_null() {
+        return SszStatus::NullPointer
+    }
+    catch_panic(|| match input(data, len).map(ty.encode) {
+        Some(Ok(encoding)) => {
+            out.write(SszBuffer::from(encoding));
+            SszStatus::Ok
+        }
+        Some(Err(err)) => SszStatus::from(&err),
+        None => SszStatus::NullPointer,
+    })
+}
+
+/// Decode `data` as a value of the type at `type_index` and write its root to `out`,
+/// returning `SszStatus::Internal` if either panics.
+///
+/// # Safety
+///
+/// `data` must be valid for reads of `len` bytes and `out` must be valid for writes of 32
+/// bytes.
+pub unsafe fn hash_tree_root_into(
+    types: &[TypeOperations],
+    type_index: usize,
+    data: *const u8,
+    len: usize,
+    out: *mut u8,
+) -> SszStatus {
+    let ty = match types.get(type_index) {
+        Some(ty) => ty,
+        None => return SszStatus::UnknownType,
+    };
+    if out.is_null() {
+        return SszStatus::NullPointer
+    }
+    catch_panic(|| match input(data, len).map(ty.hash_tree_root) {
+        Some(Ok(root)) => {
+            ptr::copy_nonoverlapping(root.as_ptr(), out, BYTES_PER_CHUNK);
+            SszStatus::Ok
+        }
+        Some(Err(err)) => SszStatus::from(&err),
+        None => SszStatus::NullPointer,
+    })
+}
+
+/// Register the given SSZ types with the C interface of [`ssz_rs::ffi`](crate::ffi), defining
+/// its `extern "C"` functions in the calling crate. The types are numbered in the given order.
+///
+/// The macro must be invoked at most once in a library, as the functions have fixed names.
+#[macro_export]
+macro_rules! export_ffi {
+    ($($ty:ty),* $(,)?) => {
+        static __SSZ_FFI_TYPES: &[$crate::ffi::TypeOperations] =
+            &[$($crate::ffi::TypeOperations::of::<$ty>(concat!(stringify!($ty), "\0"))),*];
+
+        /// Return the number of registered SSZ types.
+        #[no_mangle]
+        pub extern "C" fn ssz_type_count() -> usize {
+            __SSZ_FFI_TYPES.len()
+        }
+
+        /// Return the NUL-terminated name of the SSZ type at `type_index`, or null.
+        #[no_mangle]
+        pub extern "C" fn ssz_type_name(type_index: usize) -> *const ::std::os::raw::c_char {
+            $crate::ffi::type_name(__SSZ_FFI_TYPES, type_index)
+        }
+
+        /// Check that `data` is the encoding of a value of the SSZ type at `type_index`.
+        ///
+        /// # Safety
+        ///
+        /// `data` must be valid for reads of `len` bytes.
+        #[no_mangle]
+        pub unsafe extern "C" fn ssz_decode(
+            type_index: usize,
+            data: *const u8,
+            len: usize,
+        ) -> $crate::ffi::SszStatus {
+            $crate::ffi::decode(__SSZ_FFI_TYPES, type_index, data, len)
+        }
+
+        /// Write the encoding of the value of the SSZ type at `type_index` decoded from `data`.
+        ///
+        /// # Safety
+        ///
+        /// `data` must be valid for reads of `len` bytes and `out` must be valid for writes.
+        #[no_mangle]
+        pub unsafe extern "C" fn ssz_encode(
+            type_index: usize,
+            data: *const u8,
+            len: usize,
+            out: *mut $crate::ffi::SszBuffer,
+        ) -> $crate::ffi::SszStatus {
+            $crate::ffi::encode_into(__SSZ_FFI_TYPES, type_index, data, len, out)
+        }
+
+        /// Write the root of the value of the SSZ type at `type_index` decoded from `data`.
+        ///
+        /// # Safety
+        ///
+        /// `data` must be valid for reads of `len` bytes and `out` must be valid for writes of
+        /// 32 bytes.
+        #[no_mangle]
+        pub unsafe extern "C" fn ssz_hash_tree_root(
+            type_index: usize,
+            data: *const u8,
+            len: usize,
+            out: *mut u8,
+        ) -> $crate::ffi::SszStatus {
+            $crate::ffi::hash_tree_root_into(__SSZ_FFI_TYPES, type_index, data, len, out)
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+    use std::ffi::CStr;
+
+    #[derive(Default, Debug, SimpleSerialize)]
+    struct Pair {
+        a: u16,
+        b: List<u8, 2>,
+    }
+
+    fn types() -> Vec<TypeOperations> {
+        vec![TypeOperations::of::<Pair>("Pair\0"), TypeOperations::of::<bool>("bool\0")]
+    }
+
+    #[test]
+    fn test_ffi() {
+        let types = types();
+        let encoding = [1u8, 0, 6, 0, 0, 0, 9];
+        let name = unsafe { CStr::from_ptr(type_name(&types, 1)) };
+        assert_eq!(name.to_str().unwrap(), "bool");
+        assert!(type_name(&types, 2).is_null());
+
+        unsafe {
+            assert_eq!(decode(&types, 0, encoding.as_ptr(), encoding.len()), SszStatus::Ok);
+            assert_eq!(decode(&types, 0, encoding.as_ptr(), 5), SszStatus::Malformed);
+            assert_eq!(decode(&types, 0, ptr::null(), 6), SszStatus::NullPointer);
+            assert_eq!(decode(&types, 1, ptr::null(), 0), SszStatus::Malformed);
+            assert_eq!(decode(&types, 1, [2u8].as_ptr(), 1), SszStatus::Malformed);
+
+            let mut buffer = SszBuffer::default();
+            let status = encode_into(&types, 0, encoding.as_ptr(), encoding.len(), &mut buffer);
+            assert_eq!(status, SszStatus::Ok);
+            assert_eq!(buffer.as_slice(), encoding);
+            ssz_buffer_free(buffer);
+            let status = encode_into(&types, 0, encoding.as_ptr(), 7, ptr::null_mut());
+            assert_eq!(status, SszStatus::NullPointer);
+
+            let mut root = [0u8; 32];
+            let status = hash_tree_root_into(&types, 1, [1u8].as_ptr(), 1, root.as_mut_ptr());
+            assert_eq!(status, SszStatus::Ok);
+            assert_eq!(root[0], 1);
+            let status = hash_tree_root_into(&types, 2, [1u8].as_ptr(), 1, root.as_mut_ptr());
+            assert_eq!(status, SszStatus::UnknownType);
+        }
+    }
+
+    #[test]
+    fn test_ffi_panic() {
+        let types = [TypeOperations {
+            name: "Panic\0",
+            validate: |_| panic!("failed to decode"),
+            encode: |_| panic!("failed to encode"),
+            hash_tree_root: |_| panic!("failed to hash"),
+        }];
+        unsafe {
+            assert_eq!(decode(&types, 0, [1u8].as_ptr(), 1), SszStatus::Internal);
+            let mut buffer = SszBuffer::default();
+            let status = encode_into(&types, 0, [1u8].as_ptr(), 1, &mut buffer);
+            assert_eq!(status, SszStatus::Internal);
+            assert!(buffer.data.is_null());
+            let mut root = [0u8; 32];
+            let status = hash_tree_root_into(&types, 0, [1u8].as_ptr(), 1, root.as_mut_ptr());
+            assert_eq!(status, SszStatus::Internal);
+        }
+    }
+}
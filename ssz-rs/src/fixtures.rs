@@ -0,0 +1,109 @@
+//! Large values shaped like the ones in the beacon state, to compare performance on identical
+//! workloads across changes to this crate and in downstream applications.
+//!
+//! The values are the same on every run so measurements of different versions are comparable,
+//! e.g. with `ssz_rs::bench::measure` when the `bench` feature is also enabled.
+//!
+//! ```rust
+//! use ssz_rs::prelude::*;
+//! use ssz_rs::fixtures::{validator_registry, Validator};
+//!
+//! let mut registry = validator_registry(1000);
+//! assert_eq!(serialize(&registry).unwrap().len(), 1000 * Validator::size_hint());
+//! let root = registry.hash_tree_root().unwrap();
+//! assert_eq!(validator_registry(1000).hash_tree_root().unwrap(), root);
+//! ```
+use crate::{lib::*, prelude::*};
+
+/// The bound of the registry of validators in the beacon state, `2**40`, or the largest bound
+/// on targets where it does not fit in a `usize`.
+pub const VALIDATOR_REGISTRY_LIMIT: usize =
+    if usize::BITS >= 64 { (1u64 << 40) as usize } else { usize::MAX };
+
+/// The number of validators of a large registry, about a million like on mainnet.
+pub const LARGE_REGISTRY_COUNT: usize = 1 << 20;
+
+/// The epoch of events which have not happened yet.
+pub const FAR_FUTURE_EPOCH: u64 = u64::MAX;
+
+/// A validator record of the beacon state, a fixed-size container of 121 bytes.
+#[derive(Default, Debug, Clone, PartialEq, Eq, SimpleSerialize)]
+pub struct Validator {
+    pub pubkey: Vector<u8, 48>,
+    pub withdrawal_credentials: Node,
+    pub effective_balance: u64,
+    pub slashed: bool,
+    pub activation_eligibility_epoch: u64,
+    pub activation_epoch: u64,
+    pub exit_epoch: u64,
+    pub withdrawable_epoch: u64,
+}
+
+pub type ValidatorRegistry = List<Validator, VALIDATOR_REGISTRY_LIMIT>;
+
+pub type Balances = List<u64, VALIDATOR_REGISTRY_LIMIT>;
+
+impl Validator {
+    /// Return the validator at `index` of the fixtures, an active validator with a balance of
+    /// 32 ETH unless a few of them which have been slashed and have exited.
+    pub fn fixture(index: usize) -> Self {
+        let index = index as u64;
+        let mut pubkey = vec![0xa0; 48];
+        pubkey[..8].copy_from_slice(&index.to_le_bytes());
+        let mut withdrawal_credentials = [0u8; 32];
+        withdrawal_credentials[0] = 0x01;
+        withdrawal_credentials[24..].copy_from_slice(&index.to_be_bytes());
+        let slashed = index % 97 == 0;
+        Self {
+            pubkey: Vector::try_from(pubkey).expect("is 48 bytes"),
+            withdrawal_credentials: Node::try_from(withdrawal_credentials.as_ref())
+                .expect("is 32 bytes"),
+            effective_balance: 32_000_000_000,
+            slashed,
+            activation_eligibility_epoch: index / 8,
+            activation_epoch: index / 8 + 4,
+            exit_epoch: if slashed { index / 8 + 256 } else { FAR_FUTURE_EPOCH },
+            withdrawable_epoch: if slashed { index / 8 + 8448 } else { FAR_FUTURE_EPOCH },
+        }
+    }
+}
+
+/// Return a registry of the first `count` validators of the fixtures, e.g.
+/// [`LARGE_REGISTRY_COUNT`] of them.
+pub fn validator_registry(count: usize) -> ValidatorRegistry {
+    let validators = (0..count).map(Validator::fixture).collect::<Vec<_>>();
+    ValidatorRegistry::try_from(validators).expect("count is within the registry limit")
+}
+
+/// Return the balances of the first `count` validators of the fixtures, slightly above or below
+/// their effective balance.
+pub fn balances(count: usize) -> Balances {
+    let balances = (0..count as u64)
+        .map(|index| 32_000_000_000 + (index * 7919) % 1_000_000_000 - 500_000_000)
+        .collect::<Vec<_>>();
+    Balances::try_from(balances).expect("count is within the registry limit")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixtures() {
+        assert_eq!(Validator::size_hint(), 121);
+        assert!(!Validator::is_variable_size());
+
+        let mut registry = validator_registry(100);
+        assert_eq!(registry[3], Validator::fixture(3));
+        assert_eq!(registry.iter().filter(|validator| validator.slashed).count(), 2);
+        assert_eq!(serialize(&registry).unwrap().len(), 100 * 121);
+        let root = registry.hash_tree_root().unwrap();
+        registry[99].effective_balance -= 1;
+        assert_ne!(registry.hash_tree_root().unwrap(), root);
+
+        let mut balances = balances(100);
+        assert_eq!(balances.len(), 100);
+        assert_eq!(serialize(&balances).unwrap().len(), 800);
+        assert_ne!(balances.hash_tree_root().unwrap(), Node::default());
+    }
+}
@@ -0,0 +1,342 @@
+use crate::{
+    container::{Container, FieldInfo},
+    de::{Deserialize, DeserializeError},
+    error::InstanceError,
+    lib::*,
+    ser::{Serialize, SerializeError, BYTES_PER_LENGTH_OFFSET, MAXIMUM_LENGTH},
+};
+
+/// A container decoded from the encoding of a newer version of its type with fields appended
+/// to the end, keeping the encoding of the unknown fields to emit them again when encoded.
+///
+/// Decoding a container directly rejects any trailing fields, while `ForwardCompatible` lets a
+/// service pass on values it can only partially model. The unknown fields are kept as
+/// `unknown_fields`, without their order or types.
+///
+/// The unknown fields are only decoded if their boundary with the known fields is certain.
+/// If the container has no variable-size fields, `unknown_fields` is the rest of the encoding
+/// after the known fields. Otherwise, `unknown_fields` holds the fixed-size parts of the unknown
+/// fields, which sit between those of the known fields and the variable-size parts, and the
+/// variable-size parts of any unknown fields must be empty. The types of the unknown fields are
+/// not known, so an offset to a variable-size part among them can not be told apart from the
+/// value of a fixed-size field: if any 4 bytes of the unknown fields hold a value pointing
+/// between the start of the last known variable-size part and the end of the encoding, the end
+/// of the last known field is ambiguous and decoding fails with
+/// [`DeserializeError::AmbiguousUnknownFields`].
+///
+/// The offsets of the known fields are adjusted on both ends, but the offsets among the unknown
+/// fields are not, so these are only emitted if the encodings of the known variable-size fields
+/// have kept their total length.
+///
+/// ```rust
+/// use ssz_rs::prelude::*;
+/// use ssz_rs::ForwardCompatible;
+///
+/// #[derive(Default, Debug, PartialEq, Eq, SimpleSerialize)]
+/// struct Checkpoint {
+///     epoch: u64,
+/// }
+///
+/// #[derive(Default, Debug, PartialEq, Eq, SimpleSerialize)]
+/// struct CheckpointV2 {
+///     epoch: u64,
+///     label: List<u8, 32>,
+/// }
+///
+/// let newer = CheckpointV2 { epoch: 3, label: List::try_from(b"final".to_vec()).unwrap() };
+/// let encoding = serialize(&newer).unwrap();
+/// assert!(Checkpoint::deserialize(&encoding).is_err());
+///
+/// let mut checkpoint = ForwardCompatible::<Checkpoint>::deserialize(&encoding).unwrap();
+/// assert_eq!(checkpoint.value.epoch, 3);
+/// checkpoint.value.epoch = 4;
+/// let mut encoding = vec![];
+/// checkpoint.serialize(&mut encoding).unwrap();
+/// assert_eq!(CheckpointV2::deserialize(&encoding).unwrap(), CheckpointV2 { epoch: 4, ..newer });
+/// ```
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct ForwardCompatible<T: Container> {
+    pub value: T,
+    pub unknown_fields: Vec<u8>,
+    // the total length of the encodings of the known variable-size fields when decoded
+    variable_len: usize,
+}
+
+impl<T: Container> ForwardCompatible<T> {
+    /// Wrap `value` without any unknown fields.
+    pub fn new(value: T) -> Self {
+        Self { value, unknown_fields: vec![], variable_len: 0 }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+// Return the length of the fixed-size part of the known fields and the positions of the
+// offsets to the known variable-size fields.
+fn layout(fields: &[FieldInfo]) -> (usize, Vec<usize>) {
+    let mut fixed_len = 0;
+    let mut offsets = vec![];
+    for field in fields {
+        if field.is_variable_size {
+            offsets.push(fixed_len);
+        }
+        fixed_len += field.fixed_size;
+    }
+    (fixed_len, offsets)
+}
+
+fn read_offset(encoding: &[u8], position: usize) -> Result<usize, DeserializeError> {
+    let bytes = &encoding[position..position + BYTES_PER_LENGTH_OFFSET];
+    u32::deserialize(bytes).map(|offset| offset as usize)
+}
+
+// Invariant: `offset` is less than the maximum length of an encoding.
+fn write_offset(encoding: &mut [u8], position: usize, offset: usize) {
+    let bytes = u32::try_from(offset).expect("offset fits in 32 bits").to_le_bytes();
+    encoding[position..position + BYTES_PER_LENGTH_OFFSET].copy_from_slice(&bytes);
+}
+
+impl<T: Container> Deserialize for ForwardCompatible<T> {
+    fn deserialize(encoding: &[u8]) -> Result<Self, DeserializeError> {
+        let (fixed_len, offsets) = layout(&T::fields());
+        if encoding.len() < fixed_len {
+            return Err(DeserializeError::ExpectedFurtherInput {
+                provided: encoding.len(),
+                expected: fixed_len,
+            })
+        }
+
+        // the fixed-size parts of the unknown fields end where the first variable-size part
+        // starts, if the container has any
+        let fixed_end = match offsets.first() {
+            Some(&position) => {
+                let first = read_offset(encoding, position)?;
+                if first < fixed_len {
+                    return Err(DeserializeError::ExpectedFurtherInput {
+                        provided: first,
+                        expected: fixed_len,
+                    })
+                }
+                if first > encoding.len() {
+                    return Err(DeserializeError::ExpectedFurtherInput {
+                        provided: encoding.len(),
+                        expected: first,
+                    })
+                }
+                first
+            }
+            None => encoding.len(),
+        };
+        let unknown_len = fixed_end - fixed_len;
+
+        // the variable-size parts of any unknown fields would follow those of the known fields,
+        // starting where the first offset among the unknown fields points, but without their
+        // types any value in the unknown fields pointing there could be that offset
+        if let Some(&position) = offsets.last() {
+            let last = read_offset(encoding, position)?.max(fixed_end);
+            let ambiguous = (fixed_len..fixed_end.saturating_sub(BYTES_PER_LENGTH_OFFSET - 1))
+                .find(|&position| {
+                    matches!(
+                        read_offset(encoding, position),
+                        Ok(offset) if (last..encoding.len()).contains(&offset)
+                    )
+                });
+            if let Some(position) = ambiguous {
+                return Err(DeserializeError::AmbiguousUnknownFields { position })
+            }
+        }
+
+        // rebuild the encoding of the known fields alone
+        let mut known = Vec::with_capacity(encoding.len() - unknown_len);
+        known.extend_from_slice(&encoding[..fixed_len]);
+        known.extend_from_slice(&encoding[fixed_end..]);
+        for &position in &offsets {
+            let offset = read_offset(encoding, position)?;
+            let offset =
+                offset.checked_sub(unknown_len).ok_or(DeserializeError::ExpectedFurtherInput {
+                    provided: offset,
+                    expected: fixed_end,
+                })?;
+            write_offset(&mut known, position, offset);
+        }
+
+        let value = T::deserialize(&known)?;
+        let unknown_fields = encoding[fixed_len..fixed_end].to_vec();
+        Ok(Self { value, unknown_fields, variable_len: known.len() - fixed_len })
+    }
+}
+
+impl<T: Container> Serialize for ForwardCompatible<T> {
+    fn serialize(&self, buffer: &mut Vec<u8>) -> Result<usize, SerializeError> {
+        let mut known = vec![];
+        self.value.serialize(&mut known)?;
+        if self.unknown_fields.is_empty() {
+            buffer.extend_from_slice(&known);
+            return Ok(known.len())
+        }
+
+        let (fixed_len, offsets) = layout(&T::fields());
+        let variable_len = known.len() - fixed_len;
+        if !offsets.is_empty() && variable_len != self.variable_len {
+            return Err(InstanceError::exact::<Self>(self.variable_len, variable_len).into())
+        }
+        // the unknown fields precede the variable-size parts of the known fields
+        let unknown_len = self.unknown_fields.len();
+        let total_len = known.len() as u64 + unknown_len as u64;
+        if total_len >= MAXIMUM_LENGTH {
+            return Err(SerializeError::MaximumEncodedLengthExceeded(
+                usize::try_from(total_len).unwrap_or(usize::MAX),
+            ))
+        }
+        for &position in &offsets {
+            let offset = read_offset(&known, position).expect("offset was just serialized");
+            write_offset(&mut known, position, offset + unknown_len);
+        }
+
+        let start = buffer.len();
+        buffer.extend_from_slice(&known[..fixed_len]);
+        buffer.extend_from_slice(&self.unknown_fields);
+        buffer.extend_from_slice(&known[fixed_len..]);
+        Ok(buffer.len() - start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[derive(Default, Debug, PartialEq, Eq, SimpleSerialize)]
+    struct Header {
+        slot: u64,
+        body: List<u8, 16>,
+    }
+
+    #[derive(Default, Debug, PartialEq, Eq, SimpleSerialize)]
+    struct HeaderV2 {
+        slot: u64,
+        body: List<u8, 16>,
+        proposer: u32,
+        flag: bool,
+    }
+
+    #[derive(Default, Debug, PartialEq, Eq, SimpleSerialize)]
+    struct HeaderV3 {
+        slot: u64,
+        body: List<u8, 16>,
+        proposer: u32,
+        extra: List<u16, 4>,
+    }
+
+    fn encode(value: &ForwardCompatible<Header>) -> Result<Vec<u8>, SerializeError> {
+        let mut buffer = vec![];
+        value.serialize(&mut buffer)?;
+        Ok(buffer)
+    }
+
+    #[test]
+    fn test_unknown_fixed_size_fields() {
+        let newer = HeaderV2 {
+            slot: 7,
+            body: List::try_from(vec![1u8, 2, 3]).unwrap(),
+            proposer: 9,
+            flag: true,
+        };
+        let encoding = serialize(&newer).unwrap();
+        assert!(Header::deserialize(&encoding).is_err());
+
+        let mut header = ForwardCompatible::<Header>::deserialize(&encoding).unwrap();
+        assert_eq!(header.value, Header { slot: 7, body: newer.body.clone() });
+        assert_eq!(header.unknown_fields, vec![9, 0, 0, 0, 1]);
+        assert_eq!(encode(&header).unwrap(), encoding);
+
+        header.value.slot = 8;
+        header.value.body[0] = 4;
+        let updated = HeaderV2::deserialize(&encode(&header).unwrap()).unwrap();
+        assert_eq!(updated.slot, 8);
+        assert_eq!(updated.body[0], 4);
+        assert_eq!((updated.proposer, updated.flag), (9, true));
+
+        header.value.body.push(5).unwrap();
+        assert!(encode(&header).is_err());
+        header.unknown_fields.clear();
+        assert_eq!(Header::deserialize(&encode(&header).unwrap()).unwrap(), header.value);
+    }
+
+    #[test]
+    fn test_without_unknown_fields() {
+        let header = Header { slot: 1, body: List::try_from(vec![6u8]).unwrap() };
+        let encoding = serialize(&header).unwrap();
+        let decoded = ForwardCompatible::<Header>::deserialize(&encoding).unwrap();
+        assert!(decoded.unknown_fields.is_empty());
+        assert_eq!(decoded.into_inner(), header);
+        assert_eq!(encode(&ForwardCompatible::new(header)).unwrap(), encoding);
+
+        assert!(ForwardCompatible::<Header>::deserialize(&encoding[..10]).is_err());
+        // the first offset points past the end of the encoding
+        let mut invalid = encoding;
+        invalid[8] = 64;
+        assert!(ForwardCompatible::<Header>::deserialize(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_unknown_variable_size_fields() {
+        let mut newer = HeaderV3 {
+            slot: 7,
+            body: List::try_from(vec![1u8, 2, 3]).unwrap(),
+            proposer: 9,
+            extra: List::default(),
+        };
+        let encoding = serialize(&newer).unwrap();
+
+        // the variable-size parts of the unknown fields are empty
+        let mut header = ForwardCompatible::<Header>::deserialize(&encoding).unwrap();
+        assert_eq!(header.value, Header { slot: 7, body: newer.body.clone() });
+        assert_eq!(header.unknown_fields, vec![9, 0, 0, 0, 23, 0, 0, 0]);
+        assert_eq!(encode(&header).unwrap(), encoding);
+
+        header.value.slot = 8;
+        header.value.body[2] = 6;
+        let updated = HeaderV3::deserialize(&encode(&header).unwrap()).unwrap();
+        assert_eq!(updated.slot, 8);
+        assert_eq!(updated.body.as_ref(), [1, 2, 6]);
+        assert_eq!((updated.proposer, updated.extra), (9, newer.extra.clone()));
+
+        header.value.body.pop();
+        assert!(encode(&header).is_err());
+
+        // the offset to the variable-size part of `extra` could also be the value of a
+        // fixed-size field, with `body` running to the end of the encoding
+        newer.extra = List::try_from(vec![4u16, 5]).unwrap();
+        let encoding = serialize(&newer).unwrap();
+        assert!(matches!(
+            ForwardCompatible::<Header>::deserialize(&encoding),
+            Err(DeserializeError::AmbiguousUnknownFields { position: 16 })
+        ));
+    }
+
+    #[test]
+    fn test_ambiguous_unknown_fields() {
+        // `proposer` happens to point into the encoding of `body`
+        let newer = HeaderV2 {
+            slot: 7,
+            body: List::try_from(vec![1u8, 2, 3]).unwrap(),
+            proposer: 18,
+            flag: false,
+        };
+        let encoding = serialize(&newer).unwrap();
+        assert!(matches!(
+            ForwardCompatible::<Header>::deserialize(&encoding),
+            Err(DeserializeError::AmbiguousUnknownFields { position: 12 })
+        ));
+
+        // pointing to the end of the encoding leaves `body` the same either way
+        let newer = HeaderV2 { proposer: 20, ..newer };
+        let encoding = serialize(&newer).unwrap();
+        let header = ForwardCompatible::<Header>::deserialize(&encoding).unwrap();
+        assert_eq!(header.value.body, newer.body);
+        assert_eq!(encode(&header).unwrap(), encoding);
+    }
+}
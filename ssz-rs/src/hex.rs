@@ -0,0 +1,192 @@
+//! Hex encoding of bytes, shared by the `LowerHex`, serde and text representations of SSZ
+//! values so they all write bytes the same way: lowercase digits, optionally prefixed with `0x`.
+//!
+//! ```rust
+//! use ssz_rs::hex;
+//!
+//! assert_eq!(hex::encode_0x([0xab, 0x01]), "0xab01");
+//! assert_eq!(hex::decode_0x("0xAB01").unwrap(), vec![0xab, 0x01]);
+//! assert!(hex::decode_0x("ab01").is_err());
+//!
+//! const DOMAIN: [u8; 4] = hex::decode_const("0x07000000");
+//! assert_eq!(DOMAIN, [7, 0, 0, 0]);
+//! ```
+use crate::lib::*;
+
+/// The prefix of hex encodings written by this crate.
+pub const PREFIX: &str = "0x";
+
+/// An error decoding hex digits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HexError {
+    /// The data does not start with [`PREFIX`].
+    MissingPrefix,
+    /// The number of digits is odd.
+    OddLength,
+    /// The character at `index` of the digits is not a hex digit.
+    InvalidDigit { index: usize },
+    /// The digits do not encode the expected number of bytes.
+    InvalidLength { expected: usize, provided: usize },
+}
+
+impl Display for HexError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingPrefix => write!(f, "missing prefix `{PREFIX}` of hex data"),
+            Self::OddLength => write!(f, "odd number of hex digits"),
+            Self::InvalidDigit { index } => write!(f, "invalid hex digit at index {index}"),
+            Self::InvalidLength { expected, provided } => {
+                write!(f, "expected {expected} bytes of hex data but {provided} bytes given")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for HexError {}
+
+const DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+const fn digit_value(digit: u8) -> Option<u8> {
+    match digit {
+        b'0'..=b'9' => Some(digit - b'0'),
+        b'a'..=b'f' => Some(digit - b'a' + 10),
+        b'A'..=b'F' => Some(digit - b'A' + 10),
+        _ => None,
+    }
+}
+
+// Decode pairs of `digits` into `output`, which holds half as many bytes.
+fn decode_digits(digits: &[u8], output: &mut [u8]) -> Result<(), HexError> {
+    for (i, (byte, pair)) in output.iter_mut().zip(digits.chunks_exact(2)).enumerate() {
+        let high = digit_value(pair[0]).ok_or(HexError::InvalidDigit { index: 2 * i })?;
+        let low = digit_value(pair[1]).ok_or(HexError::InvalidDigit { index: 2 * i + 1 })?;
+        *byte = high << 4 | low;
+    }
+    Ok(())
+}
+
+/// Return the lowercase hex digits of `data` without a prefix.
+pub fn encode<T: AsRef<[u8]>>(data: T) -> String {
+    let data = data.as_ref();
+    let mut result = String::with_capacity(2 * data.len());
+    for byte in data {
+        result.push(DIGITS[(byte >> 4) as usize] as char);
+        result.push(DIGITS[(byte & 0xf) as usize] as char);
+    }
+    result
+}
+
+/// Return the lowercase hex digits of `data` prefixed with `0x`.
+pub fn encode_0x<T: AsRef<[u8]>>(data: T) -> String {
+    let mut result = String::from(PREFIX);
+    result.push_str(&encode(data));
+    result
+}
+
+/// Decode hex `digits` without a prefix, in either case.
+pub fn decode<T: AsRef<[u8]>>(digits: T) -> Result<Vec<u8>, HexError> {
+    let digits = digits.as_ref();
+    if digits.len() % 2 != 0 {
+        return Err(HexError::OddLength)
+    }
+    let mut result = vec![0u8; digits.len() / 2];
+    decode_digits(digits, &mut result)?;
+    Ok(result)
+}
+
+/// Decode hex digits prefixed with `0x`.
+pub fn decode_0x(data: &str) -> Result<Vec<u8>, HexError> {
+    decode(data.strip_prefix(PREFIX).ok_or(HexError::MissingPrefix)?)
+}
+
+/// Decode hex digits, optionally prefixed with `0x`, encoding exactly `N` bytes.
+pub fn decode_to_array<const N: usize>(data: &str) -> Result<[u8; N], HexError> {
+    let digits = data.strip_prefix(PREFIX).unwrap_or(data).as_bytes();
+    if digits.len() % 2 != 0 {
+        return Err(HexError::OddLength)
+    }
+    if digits.len() != 2 * N {
+        return Err(HexError::InvalidLength { expected: N, provided: digits.len() / 2 })
+    }
+    let mut result = [0u8; N];
+    decode_digits(digits, &mut result)?;
+    Ok(result)
+}
+
+/// Decode hex digits, optionally prefixed with `0x`, encoding exactly `N` bytes in a
+/// `const` context, e.g. to define constant roots.
+///
+/// Panics if `data` is not valid, which fails the build when evaluated in a `const` item.
+pub const fn decode_const<const N: usize>(data: &str) -> [u8; N] {
+    let data = data.as_bytes();
+    let start = if data.len() >= 2 && data[0] == b'0' && data[1] == b'x' { 2 } else { 0 };
+    if data.len() - start != 2 * N {
+        panic!("hex data does not encode the expected number of bytes");
+    }
+    let mut result = [0u8; N];
+    let mut i = 0;
+    while i < N {
+        let (high, low) =
+            match (digit_value(data[start + 2 * i]), digit_value(data[start + 2 * i + 1])) {
+                (Some(high), Some(low)) => (high, low),
+                _ => panic!("invalid hex digit"),
+            };
+        result[i] = high << 4 | low;
+        i += 1;
+    }
+    result
+}
+
+// Write the lowercase hex digits of `data`, prefixed with `0x` in the alternate form (`{:#x}`).
+pub(crate) fn write_lower_hex<T: AsRef<[u8]>>(f: &mut Formatter<'_>, data: T) -> fmt::Result {
+    if f.alternate() {
+        write!(f, "{PREFIX}")?;
+    }
+    for byte in data.as_ref() {
+        write!(f, "{byte:02x}")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let data = (0..=255u8).collect::<Vec<_>>();
+        let digits = encode(&data);
+        assert_eq!(&digits[..6], "000102");
+        assert_eq!(&digits[digits.len() - 4..], "feff");
+        assert_eq!(decode(&digits).unwrap(), data);
+        assert_eq!(decode(digits.to_uppercase()).unwrap(), data);
+        assert_eq!(decode_0x(&encode_0x(&data)).unwrap(), data);
+        assert_eq!(encode_0x([]), "0x");
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(decode_0x("00"), Err(HexError::MissingPrefix));
+        assert_eq!(decode("abc"), Err(HexError::OddLength));
+        assert_eq!(decode("0g"), Err(HexError::InvalidDigit { index: 1 }));
+        assert_eq!(
+            decode_to_array::<2>("0x0102ff"),
+            Err(HexError::InvalidLength { expected: 2, provided: 3 })
+        );
+        assert_eq!(decode_to_array::<2>("0102"), Ok([1, 2]));
+    }
+
+    #[test]
+    fn test_decode_const() {
+        const DATA: [u8; 3] = decode_const("0x0aFf10");
+        assert_eq!(DATA, [0x0a, 0xff, 0x10]);
+        assert_eq!(decode_const::<1>("7f"), [0x7f]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_decode_const_invalid() {
+        decode_const::<2>("0x01");
+    }
+}
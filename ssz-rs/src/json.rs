@@ -0,0 +1,540 @@
+//! Transcode between SSZ encodings and the JSON of the beacon API, given the SSZ type of the
+//! value as a [`TypeDescriptor`] rather than a Rust type.
+//!
+//! The JSON of a value writes integers as decimal strings, bytes and bitfields as `0x`-prefixed
+//! hex, containers as objects keyed by field name and unions as an object with the `selector`
+//! and the `value` of the variant. Integers are also read from JSON numbers.
+//!
+//! ```rust
+//! use ssz_rs::prelude::*;
+//! use ssz_rs::json::{transcode_json_to_ssz, transcode_ssz_to_json};
+//!
+//! #[derive(Default, Debug, SimpleSerialize, Describe)]
+//! struct Checkpoint {
+//!     epoch: u64,
+//!     root: Node,
+//! }
+//!
+//! let json = serde_json::json!({
+//!     "epoch": "7",
+//!     "root": format!("0x{}", "11".repeat(32)),
+//! });
+//! let encoding = transcode_json_to_ssz(&Checkpoint::describe(), &json).unwrap();
+//! let mut checkpoint = Checkpoint::deserialize(&encoding).unwrap();
+//! assert_eq!(checkpoint.epoch, 7);
+//! assert_eq!(transcode_ssz_to_json(&Checkpoint::describe(), &encoding).unwrap(), json);
+//! ```
+use crate::{
+    compat::TypeDescriptor,
+    hex,
+    lib::*,
+    ser::{BYTES_PER_LENGTH_OFFSET, MAXIMUM_LENGTH},
+};
+use num_bigint::BigUint;
+use serde_json::{Map, Value};
+
+/// An error transcoding a value, either because the JSON or the encoding is not valid for the
+/// SSZ type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranscodeError {
+    /// The path to the part of the value which is not valid, e.g. `$.balances[3]`.
+    pub path: String,
+    /// A description of what the part was expected to be.
+    pub expected: &'static str,
+}
+
+impl Display for TranscodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "expected {} at `{}`", self.expected, self.path)
+    }
+}
+
+impl std::error::Error for TranscodeError {}
+
+fn error<T>(path: &str, expected: &'static str) -> Result<T, TranscodeError> {
+    Err(TranscodeError { path: path.to_string(), expected })
+}
+
+fn is_byte(ty: &TypeDescriptor) -> bool {
+    *ty == TypeDescriptor::Uint(8)
+}
+
+/// Return the SSZ encoding of the value of type `schema` written as `json`.
+pub fn transcode_json_to_ssz(
+    schema: &TypeDescriptor,
+    json: &Value,
+) -> Result<Vec<u8>, TranscodeError> {
+    let mut buffer = vec![];
+    encode(schema, json, "$", &mut buffer)?;
+    Ok(buffer)
+}
+
+/// Return the JSON of the value of type `schema` with the SSZ encoding `encoding`.
+pub fn transcode_ssz_to_json(
+    schema: &TypeDescriptor,
+    encoding: &[u8],
+) -> Result<Value, TranscodeError> {
+    decode(schema, encoding, "$")
+}
+
+fn encode(
+    ty: &TypeDescriptor,
+    json: &Value,
+    path: &str,
+    buffer: &mut Vec<u8>,
+) -> Result<(), TranscodeError> {
+    match ty {
+        TypeDescriptor::Uint(bits) => encode_uint(*bits / 8, json, path, buffer),
+        TypeDescriptor::Boolean => match json {
+            Value::Bool(value) => {
+                buffer.push(*value as u8);
+                Ok(())
+            }
+            _ => error(path, "a boolean"),
+        },
+        TypeDescriptor::Vector(element, length) if is_byte(element) => {
+            let bytes = encode_bytes(json, path)?;
+            if bytes.len() != *length {
+                return error(path, "as many bytes as the length of the vector")
+            }
+            buffer.extend_from_slice(&bytes);
+            Ok(())
+        }
+        TypeDescriptor::List(element, limit) if is_byte(element) => {
+            let bytes = encode_bytes(json, path)?;
+            if bytes.len() > *limit {
+                return error(path, "at most as many bytes as the limit of the list")
+            }
+            buffer.extend_from_slice(&bytes);
+            Ok(())
+        }
+        TypeDescriptor::Vector(element, length) => match json {
+            Value::Array(elements) if elements.len() == *length => {
+                encode_elements(element, elements, path, buffer)
+            }
+            _ => error(path, "an array of the length of the vector"),
+        },
+        TypeDescriptor::List(element, limit) => match json {
+            Value::Array(elements) if elements.len() <= *limit => {
+                encode_elements(element, elements, path, buffer)
+            }
+            _ => error(path, "an array of at most the limit of the list"),
+        },
+        TypeDescriptor::Bitvector(length) => {
+            let bytes = encode_bytes(json, path)?;
+            check_bitvector(&bytes, *length, path)?;
+            buffer.extend_from_slice(&bytes);
+            Ok(())
+        }
+        TypeDescriptor::Bitlist(limit) => {
+            let bytes = encode_bytes(json, path)?;
+            check_bitlist(&bytes, *limit, path)?;
+            buffer.extend_from_slice(&bytes);
+            Ok(())
+        }
+        TypeDescriptor::Container(fields) => {
+            let object = match json {
+                Value::Object(object) => object,
+                _ => return error(path, "an object"),
+            };
+            if let Some(name) =
+                object.keys().find(|key| fields.iter().all(|(name, _)| name != *key))
+            {
+                return error(&format!("{path}.{name}"), "a field of the container")
+            }
+            let mut parts = Vec::with_capacity(fields.len());
+            for (name, ty) in fields {
+                let path = format!("{path}.{name}");
+                match object.get(name) {
+                    Some(value) => parts.push((ty, value, path)),
+                    None => return error(&path, "a value for the field"),
+                }
+            }
+            encode_composite(parts, buffer)
+        }
+        TypeDescriptor::Union(variants) => {
+            let object = match json {
+                Value::Object(object) if object.len() == 2 => object,
+                _ => return error(path, "an object with a `selector` and a `value`"),
+            };
+            let selector_path = format!("{path}.selector");
+            let selector = match object.get("selector").map(parse_u64) {
+                Some(Some(selector)) if selector < variants.len() as u64 => selector as usize,
+                _ => return error(&selector_path, "the selector of a variant"),
+            };
+            let value_path = format!("{path}.value");
+            buffer.push(selector as u8);
+            match (&variants[selector], object.get("value")) {
+                (None, Some(Value::Null)) => Ok(()),
+                (Some(ty), Some(value)) => encode(ty, value, &value_path, buffer),
+                (None, _) => error(&value_path, "null for the `None` variant"),
+                (Some(_), None) => error(&value_path, "the value of the variant"),
+            }
+        }
+    }
+}
+
+fn parse_u64(json: &Value) -> Option<u64> {
+    match json {
+        Value::String(digits) => digits.parse().ok(),
+        _ => json.as_u64(),
+    }
+}
+
+fn encode_uint(
+    size: usize,
+    json: &Value,
+    path: &str,
+    buffer: &mut Vec<u8>,
+) -> Result<(), TranscodeError> {
+    let value = match json {
+        Value::String(digits) if !digits.starts_with('+') => digits.parse::<BigUint>().ok(),
+        Value::Number(number) => number.as_u64().map(BigUint::from),
+        _ => None,
+    };
+    let mut bytes = match value {
+        Some(value) => value.to_bytes_le(),
+        None => return error(path, "an unsigned integer as a decimal string"),
+    };
+    if bytes.len() > size {
+        return error(path, "an integer within the range of the type")
+    }
+    bytes.resize(size, 0);
+    buffer.extend_from_slice(&bytes);
+    Ok(())
+}
+
+fn encode_bytes(json: &Value, path: &str) -> Result<Vec<u8>, TranscodeError> {
+    match json.as_str().map(hex::decode_0x) {
+        Some(Ok(bytes)) => Ok(bytes),
+        _ => error(path, "`0x`-prefixed hex"),
+    }
+}
+
+fn encode_elements(
+    element: &TypeDescriptor,
+    elements: &[Value],
+    path: &str,
+    buffer: &mut Vec<u8>,
+) -> Result<(), TranscodeError> {
+    let parts = elements
+        .iter()
+        .enumerate()
+        .map(|(i, value)| (element, value, format!("{path}[{i}]")))
+        .collect::<Vec<_>>();
+    encode_composite(parts, buffer)
+}
+
+// Encode the parts of a composite value, with the variable-size parts after the offsets.
+fn encode_composite(
+    parts: Vec<(&TypeDescriptor, &Value, String)>,
+    buffer: &mut Vec<u8>,
+) -> Result<(), TranscodeError> {
+    let start = buffer.len();
+    let mut variable = vec![];
+    for (ty, value, path) in &parts {
+        if ty.is_variable_size() {
+            let mut encoding = vec![];
+            encode(ty, value, path, &mut encoding)?;
+            buffer.extend_from_slice(&[0; BYTES_PER_LENGTH_OFFSET]);
+            variable.push((buffer.len() - BYTES_PER_LENGTH_OFFSET, encoding, path));
+        } else {
+            encode(ty, value, path, buffer)?;
+        }
+    }
+    for (at, encoding, path) in variable {
+        let offset = buffer.len() - start;
+        if offset as u64 >= MAXIMUM_LENGTH {
+            return error(path, "a value small enough to be encoded")
+        }
+        buffer[at..at + BYTES_PER_LENGTH_OFFSET].copy_from_slice(&(offset as u32).to_le_bytes());
+        buffer.extend_from_slice(&encoding);
+    }
+    Ok(())
+}
+
+fn check_bitvector(bytes: &[u8], length: usize, path: &str) -> Result<(), TranscodeError> {
+    if bytes.len() != (length + 7) / 8 {
+        return error(path, "as many bytes as needed for the bits of the bitvector")
+    }
+    let padding = bytes.last().map(|last| last >> (length % 8)).unwrap_or(0);
+    if length % 8 != 0 && padding != 0 {
+        return error(path, "zero bits after the bits of the bitvector")
+    }
+    Ok(())
+}
+
+fn check_bitlist(bytes: &[u8], limit: usize, path: &str) -> Result<(), TranscodeError> {
+    let last = match bytes.last() {
+        Some(last) if *last != 0 => last,
+        _ => return error(path, "a bitlist ending with its delimiting bit"),
+    };
+    let length = 8 * (bytes.len() - 1) + 7 - last.leading_zeros() as usize;
+    if length > limit {
+        return error(path, "at most as many bits as the limit of the bitlist")
+    }
+    Ok(())
+}
+
+fn decode(ty: &TypeDescriptor, bytes: &[u8], path: &str) -> Result<Value, TranscodeError> {
+    match ty {
+        TypeDescriptor::Uint(bits) => {
+            if bytes.len() != bits / 8 {
+                return error(path, "as many bytes as the size of the integer")
+            }
+            Ok(Value::String(BigUint::from_bytes_le(bytes).to_string()))
+        }
+        TypeDescriptor::Boolean => match bytes {
+            [0] => Ok(Value::Bool(false)),
+            [1] => Ok(Value::Bool(true)),
+            _ => error(path, "a byte of `0` or `1`"),
+        },
+        TypeDescriptor::Vector(element, length) if is_byte(element) => {
+            if bytes.len() != *length {
+                return error(path, "as many bytes as the length of the vector")
+            }
+            Ok(Value::String(hex::encode_0x(bytes)))
+        }
+        TypeDescriptor::List(element, limit) if is_byte(element) => {
+            if bytes.len() > *limit {
+                return error(path, "at most as many bytes as the limit of the list")
+            }
+            Ok(Value::String(hex::encode_0x(bytes)))
+        }
+        TypeDescriptor::Vector(element, length) => {
+            decode_elements(element, Some(*length), bytes, path).map(Value::Array)
+        }
+        TypeDescriptor::List(element, limit) => {
+            let elements = decode_elements(element, None, bytes, path)?;
+            if elements.len() > *limit {
+                return error(path, "at most as many elements as the limit of the list")
+            }
+            Ok(Value::Array(elements))
+        }
+        TypeDescriptor::Bitvector(length) => {
+            check_bitvector(bytes, *length, path)?;
+            Ok(Value::String(hex::encode_0x(bytes)))
+        }
+        TypeDescriptor::Bitlist(limit) => {
+            check_bitlist(bytes, *limit, path)?;
+            Ok(Value::String(hex::encode_0x(bytes)))
+        }
+        TypeDescriptor::Container(fields) => {
+            let parts = fields.iter().map(|(name, ty)| (ty, format!("{path}.{name}"))).collect();
+            let values = decode_composite(parts, bytes, path)?;
+            let object = fields.iter().map(|(name, _)| name.to_string()).zip(values);
+            Ok(Value::Object(object.collect::<Map<_, _>>()))
+        }
+        TypeDescriptor::Union(variants) => {
+            let (selector, value) = match bytes.split_first() {
+                Some(split) => split,
+                None => return error(path, "the selector of a variant"),
+            };
+            let value_path = format!("{path}.value");
+            let value = match variants.get(*selector as usize) {
+                Some(None) if value.is_empty() => Value::Null,
+                Some(None) => return error(&value_path, "no value for the `None` variant"),
+                Some(Some(ty)) => decode(ty, value, &value_path)?,
+                None => return error(&format!("{path}.selector"), "the selector of a variant"),
+            };
+            let mut object = Map::new();
+            object.insert("selector".to_string(), Value::String(selector.to_string()));
+            object.insert("value".to_string(), value);
+            Ok(Value::Object(object))
+        }
+    }
+}
+
+// Decode the elements of a vector of `length` elements or of a list.
+fn decode_elements(
+    element: &TypeDescriptor,
+    length: Option<usize>,
+    bytes: &[u8],
+    path: &str,
+) -> Result<Vec<Value>, TranscodeError> {
+    let count = if element.is_variable_size() {
+        match (length, bytes.get(..BYTES_PER_LENGTH_OFFSET)) {
+            (Some(length), _) => length,
+            (None, None) if bytes.is_empty() => 0,
+            (None, Some(first)) => {
+                let first = u32::from_le_bytes(first.try_into().expect("is offset")) as usize;
+                if first == 0 || first % BYTES_PER_LENGTH_OFFSET != 0 {
+                    return error(path, "a first offset after the offsets of the elements")
+                }
+                first / BYTES_PER_LENGTH_OFFSET
+            }
+            (None, None) => return error(path, "the offset of the first element"),
+        }
+    } else {
+        let size = element.size_hint();
+        if size == 0 {
+            return error(path, "elements of a non-zero size")
+        }
+        let count = bytes.len() / size;
+        if bytes.len() % size != 0 || matches!(length, Some(length) if length != count) {
+            return error(path, "a whole number of elements")
+        }
+        count
+    };
+    if element.is_variable_size() && count.saturating_mul(BYTES_PER_LENGTH_OFFSET) > bytes.len() {
+        return error(path, "the offsets of the elements")
+    }
+    let parts = (0..count).map(|i| (element, format!("{path}[{i}]"))).collect();
+    decode_composite(parts, bytes, path)
+}
+
+// Decode the parts of a composite value, with the variable-size parts after the offsets.
+fn decode_composite(
+    parts: Vec<(&TypeDescriptor, String)>,
+    bytes: &[u8],
+    path: &str,
+) -> Result<Vec<Value>, TranscodeError> {
+    let mut values = vec![Value::Null; parts.len()];
+    let mut cursor = 0;
+    let mut offsets = vec![];
+    for (i, (ty, part_path)) in parts.iter().enumerate() {
+        let size = if ty.is_variable_size() { BYTES_PER_LENGTH_OFFSET } else { ty.size_hint() };
+        let part = match bytes.get(cursor..cursor + size) {
+            Some(part) => part,
+            None => return error(part_path, "more bytes of the encoding"),
+        };
+        if ty.is_variable_size() {
+            let offset = u32::from_le_bytes(part.try_into().expect("is offset")) as usize;
+            offsets.push((i, offset));
+        } else {
+            values[i] = decode(ty, part, part_path)?;
+        }
+        cursor += size;
+    }
+    if offsets.is_empty() && cursor != bytes.len() {
+        return error(path, "no bytes after the encoding")
+    }
+    for (j, (i, start)) in offsets.iter().enumerate() {
+        let end = offsets.get(j + 1).map_or(bytes.len(), |(_, next)| *next);
+        let (ty, part_path) = &parts[*i];
+        if (j == 0 && *start != cursor) || start > &end || end > bytes.len() {
+            return error(part_path, "an offset within the encoding after the previous one")
+        }
+        values[*i] = decode(ty, &bytes[*start..end], part_path)?;
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{compat::Describe, prelude::*};
+    use serde_json::json;
+
+    #[derive(Default, Debug, PartialEq, Eq, SimpleSerialize, Describe)]
+    struct Attestation {
+        aggregation_bits: Bitlist<16>,
+        slot: u64,
+        signature: Vector<u8, 4>,
+    }
+
+    #[derive(Default, Debug, PartialEq, Eq, SimpleSerialize, Describe)]
+    struct Block {
+        flag: bool,
+        attestations: List<Attestation, 4>,
+        extra_data: List<u8, 8>,
+        balance: U256,
+        bits: Bitvector<3>,
+        parent: Option<u16>,
+        roots: Vector<List<u16, 2>, 2>,
+    }
+
+    fn block() -> Block {
+        let attestation = |slot: u64, bits: &[bool]| Attestation {
+            aggregation_bits: bits.iter().copied().collect(),
+            slot,
+            signature: Vector::try_from(vec![slot as u8; 4]).unwrap(),
+        };
+        let mut balance = [0u8; 32];
+        balance[8] = 1;
+        Block {
+            flag: true,
+            attestations: List::try_from(vec![
+                attestation(1, &[true, false, true]),
+                attestation(2, &[]),
+            ])
+            .unwrap(),
+            extra_data: List::try_from(vec![0xab, 0xcd]).unwrap(),
+            balance: U256::from_bytes_le(balance),
+            bits: Bitvector::from_raw_bytes(&[0b101], 3).unwrap(),
+            parent: Some(9),
+            roots: Vector::try_from(vec![List::default(), List::try_from(vec![1, 2]).unwrap()])
+                .unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_transcode() {
+        let schema = Block::describe();
+        let encoding = serialize(&block()).unwrap();
+        let json = transcode_ssz_to_json(&schema, &encoding).unwrap();
+        assert_eq!(
+            json,
+            json!({
+                "flag": true,
+                "attestations": [
+                    { "aggregation_bits": "0x0d", "slot": "1", "signature": "0x01010101" },
+                    { "aggregation_bits": "0x01", "slot": "2", "signature": "0x02020202" },
+                ],
+                "extra_data": "0xabcd",
+                "balance": "18446744073709551616",
+                "bits": "0x05",
+                "parent": { "selector": "1", "value": "9" },
+                "roots": [[], ["1", "2"]],
+            })
+        );
+        assert_eq!(transcode_json_to_ssz(&schema, &json).unwrap(), encoding);
+
+        let mut json = json;
+        json["attestations"][1]["slot"] = json!(2);
+        json["parent"] = json!({ "selector": 0, "value": null });
+        let encoding = transcode_json_to_ssz(&schema, &json).unwrap();
+        assert_eq!(Block::deserialize(&encoding).unwrap(), Block { parent: None, ..block() });
+    }
+
+    #[test]
+    fn test_transcode_errors() {
+        let schema = Block::describe();
+        let mut json = transcode_ssz_to_json(&schema, &serialize(&block()).unwrap()).unwrap();
+        let check = |json: &Value, path: &str| {
+            assert_eq!(transcode_json_to_ssz(&schema, json).unwrap_err().path, path);
+        };
+
+        let mut invalid = json.clone();
+        invalid["attestations"][1]["slot"] = json!("-1");
+        check(&invalid, "$.attestations[1].slot");
+        invalid = json.clone();
+        invalid["attestations"][0]["signature"] = json!("0x01");
+        check(&invalid, "$.attestations[0].signature");
+        invalid = json.clone();
+        invalid["extra_data"] = json!(format!("0x{}", "00".repeat(9)));
+        check(&invalid, "$.extra_data");
+        invalid = json.clone();
+        invalid["bits"] = json!("0x0f");
+        check(&invalid, "$.bits");
+        invalid = json.clone();
+        invalid["parent"]["selector"] = json!("2");
+        check(&invalid, "$.parent.selector");
+        invalid = json.clone();
+        invalid["unknown"] = json!(1);
+        check(&invalid, "$.unknown");
+        json.as_object_mut().unwrap().remove("flag");
+        check(&json, "$.flag");
+
+        let encoding = serialize(&block()).unwrap();
+        for end in 0..encoding.len() {
+            let truncated = &encoding[..end];
+            let json = transcode_ssz_to_json(&schema, truncated);
+            assert_eq!(json.is_ok(), Block::deserialize(truncated).is_ok());
+        }
+        let mut invalid = encoding.clone();
+        invalid[0] = 2;
+        let error = transcode_ssz_to_json(&schema, &invalid).unwrap_err();
+        assert_eq!(error.path, "$.flag");
+    }
+}
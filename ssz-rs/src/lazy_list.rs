@@ -0,0 +1,251 @@
+use crate::{
+    de::{Deserialize, DeserializeError},
+    error::InstanceError,
+    lib::*,
+    list::List,
+    ser::BYTES_PER_LENGTH_OFFSET,
+    SimpleSerialize,
+};
+
+/// A view of the encoding of a `List<T, N>` that decodes elements only when they are accessed.
+///
+/// Creating a `LazyList` checks the length of the list and, for variable-size elements, the
+/// offsets of the elements, but not the elements themselves, so an invalid element is only
+/// reported when it is accessed. Elements accessed with [`LazyList::get`] are decoded once and
+/// kept for later accesses while [`LazyList::decode`] decodes the element on every call.
+///
+/// ```rust
+/// use ssz_rs::prelude::*;
+/// use ssz_rs::LazyList;
+///
+/// let list = List::<u64, 1024>::try_from((0..1000u64).collect::<Vec<_>>()).unwrap();
+/// let mut lazy = LazyList::<u64, 1024>::new(serialize(&list).unwrap()).unwrap();
+/// assert_eq!(lazy.len(), 1000);
+/// assert_eq!(*lazy.get(999).unwrap().unwrap(), 999);
+/// assert!(lazy.get(1000).is_none());
+/// assert_eq!(lazy.to_list().unwrap(), list);
+/// ```
+#[derive(Clone)]
+pub struct LazyList<T: SimpleSerialize, const N: usize> {
+    encoding: Vec<u8>,
+    len: usize,
+    decoded: BTreeMap<usize, T>,
+}
+
+impl<T: SimpleSerialize, const N: usize> fmt::Debug for LazyList<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "LazyList<{}, {N}>(len={}, decoded={})",
+            any::type_name::<T>(),
+            self.len,
+            self.decoded.len()
+        )
+    }
+}
+
+impl<T: SimpleSerialize, const N: usize> LazyList<T, N> {
+    /// Create a view of `encoding`, the encoding of a `List<T, N>`.
+    pub fn new(encoding: Vec<u8>) -> Result<Self, DeserializeError> {
+        let len = if T::is_variable_size() {
+            Self::check_offsets(&encoding)?
+        } else {
+            let size = T::size_hint();
+            let remainder = encoding.len() % size;
+            if remainder != 0 {
+                return Err(DeserializeError::AdditionalInput {
+                    provided: encoding.len(),
+                    expected: encoding.len() - remainder,
+                })
+            }
+            encoding.len() / size
+        };
+        if len > N {
+            return Err(InstanceError::bounded::<Self>(N, len).into())
+        }
+        Ok(Self { encoding, len, decoded: BTreeMap::new() })
+    }
+
+    // Check the offsets of variable-size elements and return the number of elements.
+    fn check_offsets(encoding: &[u8]) -> Result<usize, DeserializeError> {
+        if encoding.is_empty() {
+            return Ok(0)
+        }
+        let first = Self::read_offset(encoding, 0)?;
+        if first == 0 {
+            return Err(DeserializeError::ExpectedFurtherInput {
+                provided: first,
+                expected: BYTES_PER_LENGTH_OFFSET,
+            })
+        }
+        let remainder = first % BYTES_PER_LENGTH_OFFSET;
+        if remainder != 0 {
+            return Err(DeserializeError::AdditionalInput {
+                provided: first,
+                expected: first - remainder,
+            })
+        }
+        let len = first / BYTES_PER_LENGTH_OFFSET;
+        let mut previous = first;
+        for index in 1..len {
+            let offset = Self::read_offset(encoding, index)?;
+            if offset < previous {
+                return Err(DeserializeError::ExpectedFurtherInput {
+                    provided: offset,
+                    expected: previous,
+                })
+            }
+            previous = offset;
+        }
+        if previous > encoding.len() {
+            return Err(DeserializeError::ExpectedFurtherInput {
+                provided: encoding.len(),
+                expected: previous,
+            })
+        }
+        Ok(len)
+    }
+
+    fn read_offset(encoding: &[u8], index: usize) -> Result<usize, DeserializeError> {
+        let start = index * BYTES_PER_LENGTH_OFFSET;
+        let end = start + BYTES_PER_LENGTH_OFFSET;
+        let bytes = encoding.get(start..end).ok_or(DeserializeError::ExpectedFurtherInput {
+            provided: encoding.len(),
+            expected: end,
+        })?;
+        u32::deserialize(bytes).map(|offset| offset as usize)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return the encoding of the whole list.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.encoding
+    }
+
+    /// Return the encoding of the element at `index`.
+    pub fn element_bytes(&self, index: usize) -> Option<&[u8]> {
+        Self::element_bytes_in(&self.encoding, self.len, index)
+    }
+
+    // Return the encoding of the element at `index` of the `len` elements encoded in `encoding`.
+    fn element_bytes_in(encoding: &[u8], len: usize, index: usize) -> Option<&[u8]> {
+        if index >= len {
+            return None
+        }
+        let range = if T::is_variable_size() {
+            // NOTE: offsets were checked in `new`
+            let start = Self::read_offset(encoding, index).ok()?;
+            let end = if index + 1 < len {
+                Self::read_offset(encoding, index + 1).ok()?
+            } else {
+                encoding.len()
+            };
+            start..end
+        } else {
+            let size = T::size_hint();
+            index * size..(index + 1) * size
+        };
+        encoding.get(range)
+    }
+
+    /// Decode the element at `index` without keeping it.
+    pub fn decode(&self, index: usize) -> Option<Result<T, DeserializeError>> {
+        self.element_bytes(index).map(T::deserialize)
+    }
+
+    /// Return the element at `index`, decoding it on the first access.
+    pub fn get(&mut self, index: usize) -> Option<Result<&T, DeserializeError>> {
+        match self.decoded.entry(index) {
+            btree_map::Entry::Occupied(entry) => Some(Ok(entry.into_mut())),
+            btree_map::Entry::Vacant(entry) => {
+                let bytes = Self::element_bytes_in(&self.encoding, self.len, index)?;
+                Some(T::deserialize(bytes).map(|element| &*entry.insert(element)))
+            }
+        }
+    }
+
+    /// Return the number of elements decoded and kept by [`LazyList::get`].
+    pub fn decoded_len(&self) -> usize {
+        self.decoded.len()
+    }
+
+    /// Drop the elements kept by [`LazyList::get`].
+    pub fn clear_decoded(&mut self) {
+        self.decoded.clear();
+    }
+
+    /// Decode every element into a `List`.
+    pub fn to_list(&self) -> Result<List<T, N>, DeserializeError> {
+        List::deserialize(&self.encoding)
+    }
+
+    /// Return the encoding, dropping any decoded elements.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.encoding
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serialize;
+
+    #[test]
+    fn test_lazy_fixed_size() {
+        let list = List::<u16, 8>::try_from(vec![1u16, 2, 3]).unwrap();
+        let mut lazy = LazyList::<u16, 8>::new(serialize(&list).unwrap()).unwrap();
+        assert_eq!(lazy.len(), 3);
+        assert_eq!(lazy.element_bytes(1), Some([2u8, 0].as_ref()));
+        assert_eq!(lazy.decode(2).unwrap().unwrap(), 3);
+        assert_eq!(lazy.decoded_len(), 0);
+        assert_eq!(*lazy.get(0).unwrap().unwrap(), 1);
+        assert_eq!(lazy.decoded_len(), 1);
+        assert!(lazy.get(3).is_none());
+        assert_eq!(lazy.to_list().unwrap(), list);
+
+        assert!(LazyList::<u16, 8>::new(vec![1, 2, 3]).is_err());
+        assert!(matches!(
+            LazyList::<u16, 2>::new(serialize(&list).unwrap()),
+            Err(DeserializeError::InvalidInstance(InstanceError::Bounded {
+                bound: 2,
+                provided: 3,
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn test_lazy_variable_size() {
+        let elements = vec![
+            List::<u8, 4>::try_from(vec![1u8]).unwrap(),
+            List::<u8, 4>::default(),
+            List::<u8, 4>::try_from(vec![2u8, 3]).unwrap(),
+        ];
+        let list = List::<List<u8, 4>, 4>::try_from(elements.clone()).unwrap();
+        let mut lazy = LazyList::<List<u8, 4>, 4>::new(serialize(&list).unwrap()).unwrap();
+        assert_eq!(lazy.len(), 3);
+        for (i, element) in elements.iter().enumerate() {
+            assert_eq!(lazy.get(i).unwrap().unwrap(), element);
+        }
+        assert_eq!(lazy.to_list().unwrap(), list);
+        assert!(LazyList::<List<u8, 4>, 4>::new(vec![]).unwrap().is_empty());
+
+        // an element longer than its limit is only reported when accessed
+        let long = [12u8, 0, 0, 0, 12, 0, 0, 0, 12, 0, 0, 0, 1, 2, 3, 4, 5];
+        let lazy = LazyList::<List<u8, 4>, 4>::new(long.to_vec()).unwrap();
+        assert!(lazy.decode(0).unwrap().is_ok());
+        assert!(lazy.decode(2).unwrap().is_err());
+
+        // offsets must not decrease or point past the end
+        assert!(LazyList::<List<u8, 4>, 4>::new(vec![8, 0, 0, 0, 4, 0, 0, 0]).is_err());
+        assert!(LazyList::<List<u8, 4>, 4>::new(vec![8, 0, 0, 0, 9, 0, 0, 0]).is_err());
+        assert!(LazyList::<List<u8, 4>, 4>::new(vec![3, 0, 0, 0]).is_err());
+    }
+}
@@ -29,42 +29,249 @@
 //! );
 //! ```
 //!
+//! Domain types (e.g. a `Duration`-like count of seconds) can reuse the encoding of an existing
+//! type by deriving on a "newtype" struct with a single unnamed field:
+//!
+//! ```
+//! # use ssz_rs::prelude::*;
+//! #[derive(Debug, Default, Eq, PartialEq, SimpleSerialize)]
+//! struct Seconds(u64);
+//!
+//! let mut buf = Vec::new();
+//! Seconds(12).serialize(&mut buf);
+//! assert_eq!(buf, ssz_rs::serialize(&12u64).unwrap());
+//! assert_eq!(Seconds::deserialize(&buf).unwrap(), Seconds(12));
+//! ```
+//!
+//! The `NonZero` integers, e.g. `NonZeroU64`, are encoded as the underlying integer and zero is
+//! rejected when decoding them. They have no `Default`, so they do not implement
+//! `SimpleSerialize` and can not be the field of a derived type. A newtype which picks a default
+//! implements the traits by delegating to the integer instead:
+//!
+//! ```
+//! # use ssz_rs::prelude::*;
+//! use ssz_rs::{MerkleHasher, MerkleizationContext};
+//! use std::num::NonZeroU64;
+//!
+//! #[derive(Debug, Clone, Copy, Eq, PartialEq)]
+//! struct Index(NonZeroU64);
+//!
+//! impl Default for Index {
+//!     fn default() -> Self {
+//!         Self(NonZeroU64::new(1).unwrap())
+//!     }
+//! }
+//!
+//! impl ssz_rs::Sized for Index {
+//!     fn is_variable_size() -> bool {
+//!         false
+//!     }
+//!
+//!     fn size_hint() -> usize {
+//!         NonZeroU64::size_hint()
+//!     }
+//! }
+//!
+//! impl Serialize for Index {
+//!     fn serialize(&self, buffer: &mut Vec<u8>) -> Result<usize, SerializeError> {
+//!         self.0.serialize(buffer)
+//!     }
+//! }
+//!
+//! impl Deserialize for Index {
+//!     fn deserialize(encoding: &[u8]) -> Result<Self, DeserializeError> {
+//!         NonZeroU64::deserialize(encoding).map(Self)
+//!     }
+//! }
+//!
+//! impl Merkleized for Index {
+//!     fn hash_tree_root_with<H: MerkleHasher>(
+//!         &mut self,
+//!         context: &MerkleizationContext<H>,
+//!     ) -> Result<Node, MerkleizationError> {
+//!         self.0.hash_tree_root_with(context)
+//!     }
+//! }
+//!
+//! impl SimpleSerialize for Index {
+//!     const PACKED_PER_CHUNK: usize = 4;
+//!
+//!     fn is_composite_type() -> bool {
+//!         false
+//!     }
+//! }
+//!
+//! #[derive(Debug, Default, Eq, PartialEq, SimpleSerialize)]
+//! struct Entry {
+//!     index: Index,
+//!     value: u64,
+//! }
+//!
+//! let entry = Entry { index: Index(NonZeroU64::new(3).unwrap()), value: 4 };
+//! let buf = ssz_rs::serialize(&entry).unwrap();
+//! assert_eq!(buf, [3u64.to_le_bytes(), 4u64.to_le_bytes()].concat());
+//! assert_eq!(Entry::deserialize(&buf).unwrap(), entry);
+//! assert!(matches!(Entry::deserialize(&[0; 16]), Err(DeserializeError::Zero)));
+//! ```
+//!
+//! Unions are derived from enums whose variants hold one type each, except for an optional first
+//! variant `None`. Any other shape is rejected when compiling, e.g. a second `None`:
+//!
+//! ```compile_fail
+//! # use ssz_rs::prelude::*;
+//! #[derive(Debug, Eq, PartialEq, SimpleSerialize)]
+//! enum Data {
+//!     None,
+//!     Some(u64),
+//!     Empty,
+//! }
+//! # impl Default for Data {
+//! #     fn default() -> Self {
+//! #         Self::None
+//! #     }
+//! # }
+//! ```
+//!
+//! A container can limit the number of composite types nested in it with
+//! `#[ssz(max_depth = N)]`, so a deeper container is rejected when compiling:
+//!
+//! ```compile_fail
+//! # use ssz_rs::prelude::*;
+//! #[derive(Debug, Default, SimpleSerialize)]
+//! #[ssz(max_depth = 2)]
+//! struct Nested {
+//!     rows: List<List<u8, 4>, 4>,
+//! }
+//! ```
+//!
 //! [ssz]: https://github.com/ethereum/consensus-specs/blob/dev/ssz/simple-serialize.md
 #![cfg_attr(not(feature = "std"), no_std)]
 
 #[cfg(not(feature = "std"))]
 extern crate alloc;
 
+#[cfg(feature = "arena")]
+pub mod arena;
 mod array;
+#[cfg(feature = "bench")]
+pub mod bench;
 mod bitlist;
 mod bitvector;
 mod boolean;
+mod borrowed;
+#[cfg(feature = "bridge")]
+pub mod bridge;
+mod bytes;
+mod codec;
+#[cfg(feature = "compat")]
+pub mod compat;
+#[cfg(feature = "compress")]
+pub mod compress;
+#[cfg(feature = "subtle")]
+mod constant_time;
 mod container;
+#[cfg(feature = "corpus")]
+pub mod corpus;
 mod de;
+#[cfg(feature = "delta")]
+pub mod delta;
+mod deposit_snapshot;
+pub mod envelope;
 mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "fixtures")]
+pub mod fixtures;
+mod forward_compatible;
+pub mod hex;
+#[cfg(feature = "json")]
+pub mod json;
+mod lazy_list;
 mod list;
+pub mod map;
 mod merkleization;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(not(feature = "metrics"))]
+mod metrics;
+#[cfg(feature = "migrate")]
+pub mod migrate;
+#[cfg(feature = "mutate")]
+pub mod mutate;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "query")]
+pub mod query;
+mod raw_ssz;
+#[cfg(feature = "roots")]
+pub mod roots;
 mod ser;
 #[cfg(feature = "serde")]
 mod serde;
+mod spans;
+#[cfg(test)]
+mod test_utils;
+#[cfg(feature = "test-values")]
+pub mod test_value;
+#[cfg(feature = "text")]
+pub mod text;
+#[cfg(feature = "tree")]
+pub mod tree;
+#[cfg(feature = "tuples")]
+mod tuple;
 mod uint;
 mod union;
+mod utf8_list;
 mod utils;
 mod vector;
 
 pub use crate::{
     bitlist::Bitlist,
     bitvector::Bitvector,
-    de::{Deserialize, DeserializeError},
-    error::{Error as SimpleSerializeError, InstanceError, TypeError},
+    borrowed::{ByteListRef, ByteVectorRef, DeserializeBorrowed},
+    bytes::{ByteList, ByteVector},
+    codec::{SszDecoder, SszEncoder},
+    container::{BuildError, Container, FieldError, FieldInfo, FieldVisitor, SszValue},
+    de::{Deserialize, DeserializeError, DeserializeWithLength},
+    deposit_snapshot::{DepositTreeSnapshot, DEPOSIT_CONTRACT_DEPTH},
+    error::{
+        Error, Error as SimpleSerializeError, ErrorKind, InstanceError, OutOfBounds, TypeError,
+    },
+    forward_compatible::ForwardCompatible,
+    lazy_list::LazyList,
     list::List,
-    merkleization::{is_valid_merkle_branch, MerkleizationError, Merkleized, Node},
-    ser::{Serialize, SerializeError},
-    uint::U256,
-    utils::{deserialize, serialize},
+    merkleization::{
+        is_valid_merkle_branch, is_valid_merkle_branch_with, mix_in_length, mix_in_selector,
+        roots_equal, verify_merkle_branches_batch, Cached, Context as MerkleizationContext,
+        DefaultRoot, GeneralizedIndex, HasherCheckpoint, IncrementalMerkleTree, MerkleHasher,
+        MerkleizationError, Merkleized, Node, PathElement, PathError, Proof, Root, RootAccumulator,
+        RootCache, RootEq, Sha256Hasher, StreamingHasher,
+    },
+    raw_ssz::RawSsz,
+    ser::{serialize_iter, Serialize, SerializeError},
+    uint::{FromU256, IntoU256, OverflowError, SszUint, U256},
+    union::Union,
+    utf8_list::Utf8List,
+    utils::{
+        deserialize, field_root_from_bytes, from_bytes, hash_tree_root_from_bytes, root, serialize,
+        to_bytes, validate_frame,
+    },
     vector::Vector,
 };
 
+#[cfg(feature = "compat")]
+pub use crate::compat::Describe;
+#[cfg(feature = "subtle")]
+pub use crate::constant_time::{Choice, ConstantTimeEq};
+#[cfg(feature = "reference")]
+pub use crate::merkleization::reference;
+#[cfg(feature = "std")]
+pub use crate::merkleization::Memoized;
+#[cfg(feature = "test-values")]
+pub use crate::test_value::TestValue;
+#[cfg(feature = "text")]
+pub use crate::text::CanonicalText;
+
 mod lib {
     mod core {
         #[cfg(not(feature = "std"))]
@@ -73,24 +280,40 @@ mod lib {
         pub use std::*;
     }
 
-    pub use self::core::{any, cmp, fmt, iter, slice};
+    pub use self::core::{any, fmt, iter, mem, num, slice, str};
 
     pub use self::{
-        cmp::Ordering,
         core::{
+            any::TypeId,
             array::TryFromSliceError,
             fmt::{Debug, Display, Formatter},
-            ops::{Deref, DerefMut, Index, IndexMut},
-            slice::{IterMut, SliceIndex},
+            marker::PhantomData,
+            ops::{Bound, Deref, DerefMut, Index, IndexMut, Range, RangeBounds},
+            slice::SliceIndex,
         },
         iter::Enumerate,
     };
 
+    #[cfg(all(not(feature = "std"), feature = "compat"))]
+    pub use alloc::boxed::Box;
+    #[cfg(all(not(feature = "std"), feature = "tree"))]
+    pub use alloc::sync::Arc;
     #[cfg(not(feature = "std"))]
-    pub use alloc::{format, string::String, vec, vec::Vec};
+    pub use alloc::{
+        collections::{btree_map, BTreeMap},
+        format,
+        string::String,
+        vec,
+        vec::{Drain, Vec},
+    };
+    #[cfg(all(feature = "std", feature = "tree"))]
+    pub use std::sync::Arc;
 
     #[cfg(feature = "std")]
-    pub use std::vec::Vec;
+    pub use std::{
+        collections::{btree_map, BTreeMap},
+        vec::{Drain, Vec},
+    };
 }
 
 /// `Sized` is a trait for types that can
@@ -108,6 +331,122 @@ pub trait SimpleSerialize: Serialize + Deserialize + Sized + Merkleized + Defaul
     fn is_composite_type() -> bool {
         true
     }
+
+    /// The number of values of `Self` packed into a chunk, the constant counterpart of
+    /// [`Sized::size_hint`] for basic types to compute the `CHUNK_COUNT` of collections of them.
+    ///
+    /// Composite types take a chunk each, which is the default, so basic types must set it.
+    const PACKED_PER_CHUNK: usize = 1;
+
+    /// Return the number of composite types nested in `Self`, including `Self`.
+    ///
+    /// Basic types have a nesting depth of 0.
+    fn nesting_depth() -> usize {
+        Self::is_composite_type() as usize
+    }
+
+    /// Return the approximate number of bytes allocated on the heap by `self`,
+    /// excluding `self` itself, e.g. the elements of a `List` and their heap allocations.
+    fn ssz_heap_size(&self) -> usize {
+        0
+    }
+
+    /// Return the hash tree root of the value encoded as `encoding`, checking the encoding.
+    ///
+    /// Types override this to hash parts of the encoding directly instead of decoding the value
+    /// first, e.g. a `List` of basic types packs its encoding into chunks as it is.
+    fn hash_tree_root_from_bytes<H: MerkleHasher>(
+        encoding: &[u8],
+        context: &MerkleizationContext<H>,
+    ) -> Result<Node, SimpleSerializeError> {
+        Ok(Self::deserialize(encoding)?.hash_tree_root_with(context)?)
+    }
+
+    /// Return `true` if `self` is known to equal `Self::default()`, e.g. an empty `List`, so a
+    /// container can take its root from [`SimpleSerialize::default_hash_tree_root_with`].
+    ///
+    /// Returning `false` is always correct. Containers check each of their fields before hashing
+    /// it, so types only override this when the check takes constant time, e.g. the length of a
+    /// `List`, and not when it scans the value, e.g. the elements of a `Vector`.
+    fn is_default(&self) -> bool {
+        false
+    }
+
+    /// Return the hash tree root of `Self::default()`.
+    ///
+    /// Types override this to compute the root from the zero hashes of the `context` instead of
+    /// hashing every chunk, e.g. a `Vector` of basic types whose default root is a zero hash.
+    fn default_hash_tree_root_with<H: MerkleHasher>(
+        context: &MerkleizationContext<H>,
+    ) -> Result<Node, MerkleizationError> {
+        Self::default().hash_tree_root_with(context)
+    }
+
+    /// The maximum length of the encoding of any value of this type, saturating at
+    /// `usize::MAX`, e.g. to reject oversized frames with [`validate_frame`] before decoding.
+    ///
+    /// `#[derive(SimpleSerialize)]` computes it from the fields of containers and the variants
+    /// of unions. Types which do not set it have no maximum.
+    const MAX_SIZE: usize = usize::MAX;
+
+    /// Whether this type is variable-size, the constant counterpart of
+    /// [`Sized::is_variable_size`] to compute the `MAX_SIZE` of composite types.
+    ///
+    /// Types which do not set it are taken to be variable-size, which at worst overestimates the
+    /// `MAX_SIZE` of composite types of them.
+    const IS_VARIABLE_SIZE: bool = true;
+
+    /// The number of composite types nested in `Self`, the constant counterpart of
+    /// [`SimpleSerialize::nesting_depth`] to check the `max_depth` of containers when compiling.
+    ///
+    /// Types which do not set it are taken to be nested without bound, so a container with a
+    /// `max_depth` does not compile with fields of them.
+    const NESTING_DEPTH: usize = usize::MAX;
+
+    /// Return the maximum length of the encoding of any value of this type, which is the
+    /// length of the encoding of fixed-size types and [`MAX_SIZE`](Self::MAX_SIZE) otherwise.
+    fn max_size() -> usize {
+        if Self::is_variable_size() {
+            Self::MAX_SIZE
+        } else {
+            Self::size_hint()
+        }
+    }
+
+    /// Return the generalized index of the node at `path` in the Merkle tree of `Self`,
+    /// e.g. the path `["validators".into(), 3.into(), "withdrawal_credentials".into()]`.
+    fn generalized_index(path: &[PathElement<'_>]) -> Result<GeneralizedIndex, PathError> {
+        Self::generalized_index_from(1, path)
+    }
+
+    /// Return the generalized index of the node at `path` in the Merkle tree of `Self` rooted
+    /// at the generalized index `root` of an enclosing tree.
+    ///
+    /// Composite types override this to resolve the first element of `path` and the rest of
+    /// it in the type of the selected field or element, so only an empty path is valid here.
+    fn generalized_index_from(
+        root: GeneralizedIndex,
+        path: &[PathElement<'_>],
+    ) -> Result<GeneralizedIndex, PathError> {
+        match path.first() {
+            None => Ok(root),
+            Some(element) => Err(merkleization::invalid_path_element::<Self>(element)),
+        }
+    }
+
+    /// Append the encodings of `elements` to `buffer`, e.g. to pack them into chunks.
+    ///
+    /// Basic types override this to copy their little-endian bytes directly instead of
+    /// serializing each element on its own.
+    fn serialize_elements(
+        elements: &[Self],
+        buffer: &mut lib::Vec<u8>,
+    ) -> Result<(), SerializeError> {
+        for element in elements {
+            element.serialize(buffer)?;
+        }
+        Ok(())
+    }
 }
 
 /// The `prelude` contains common traits and types a user of this library
@@ -119,9 +458,12 @@ pub mod prelude {
         de::{Deserialize, DeserializeError},
         error::{Error as SimpleSerializeError, InstanceError, TypeError},
         list::List,
-        merkleization::{is_valid_merkle_branch, MerkleizationError, Merkleized, Node},
+        merkleization::{
+            is_valid_merkle_branch, DefaultRoot, MerkleizationError, Merkleized, Node,
+        },
         ser::{Serialize, SerializeError},
         uint::U256,
+        utf8_list::Utf8List,
         utils::{deserialize, serialize},
         vector::Vector,
         SimpleSerialize, Sized,
@@ -129,7 +471,45 @@ pub mod prelude {
     // expose this so the derive macro has everything in scope
     // with a simple `prelude` import
     pub use crate as ssz_rs;
-    pub use ssz_rs_derive::SimpleSerialize;
+    #[cfg(feature = "subtle")]
+    pub use crate::constant_time::ConstantTimeEq;
+    #[cfg(feature = "bridge")]
+    pub use ssz_rs_derive::SszBridge;
+    pub use ssz_rs_derive::{SimpleSerialize, SszEnum};
+    #[cfg(feature = "compat")]
+    pub use {crate::compat::Describe, ssz_rs_derive::Describe};
+    #[cfg(feature = "migrate")]
+    pub use {crate::migrate::Migrate, ssz_rs_derive::Migrate};
+    #[cfg(feature = "test-values")]
+    pub use {crate::test_value::TestValue, ssz_rs_derive::TestValue};
+    #[cfg(feature = "text")]
+    pub use {crate::text::CanonicalText, ssz_rs_derive::CanonicalText};
+}
+
+/// `advanced` contains the Merkle tree machinery underlying the "hash tree root" of SSZ types:
+/// hash functions, proofs, caches and the merkleization helpers for implementing `Merkleized`
+/// by hand. Unlike the `prelude`, this surface may change as the machinery evolves.
+pub mod advanced {
+    #[cfg(feature = "reference")]
+    pub use crate::merkleization::reference;
+    #[cfg(feature = "rayon")]
+    pub use crate::merkleization::verify_merkle_branches_batch_par;
+    #[cfg(feature = "layers")]
+    pub use crate::merkleization::{
+        compute_root_from_leaves, compute_root_from_leaves_with, hash_layer, hash_layer_with,
+    };
+    pub use crate::{
+        merkleization::{
+            chunks_for_bits, chunks_for_list, depth_for_chunks, is_valid_merkle_branch,
+            is_valid_merkle_branch_with, leaf_count_for_chunks, merkleize, merkleize_bulk,
+            merkleize_with, mix_in_length, mix_in_selector, pack, pack_bytes,
+            verify_merkle_branches_batch, BulkHashError, BulkHasher,
+            Context as MerkleizationContext, HasherCheckpoint, IncrementalMerkleTree, MerkleCache,
+            MerkleHasher, Proof, Root, RootAccumulator, RootCache, Sha256Hasher, StreamingHasher,
+            BYTES_PER_CHUNK, MAX_LAYER_DEPTH,
+        },
+        ser::BYTES_PER_LENGTH_OFFSET,
+    };
 }
 
 #[doc(hidden)]
@@ -137,7 +517,18 @@ pub mod prelude {
 pub mod __internal {
     // exported for derive macro to avoid code duplication...
     pub use crate::{
-        merkleization::{merkleize, mix_in_selector},
-        ser::serialize_composite_from_components,
+        container::field_ranges,
+        merkleization::{
+            child_generalized_index, invalid_path_element, merkleize, merkleize_roots,
+            merkleize_with, mix_in_selector, union_generalized_index, RootMemo,
+        },
+        ser::{composite_nesting_depth, field_max_size, CompositeParts},
     };
+    // exported for `impl_ssz_uint!` and `define_bytes_type!`
+    pub use crate::lib::Vec;
+    // exported for `define_bytes_type!`
+    #[cfg(feature = "subtle")]
+    pub use ::subtle;
+    #[cfg(feature = "serde")]
+    pub use {crate::serde::as_hex, ::serde};
 }
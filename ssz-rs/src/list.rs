@@ -1,20 +1,26 @@
 use crate::{
-    de::{deserialize_homogeneous_composite, Deserialize, DeserializeError},
-    error::{Error, InstanceError},
+    de::{deserialize_homogeneous_composite, Deserialize, DeserializeError, DeserializeWithLength},
+    error::{Error, InstanceError, OutOfBounds},
     lib::*,
     merkleization::{
-        merkleize, mix_in_length, pack, MerkleCache, MerkleizationError, Merkleized, Node,
+        chunks_to_nodes, element_leaf, elements_chunk_count, elements_chunk_count_const,
+        elements_chunks, elements_generalized_index, elements_root_from_bytes, layer_at_depth_with,
+        merkleize_with, mix_in_length, Context, GeneralizedIndex, MerkleCache, MerkleHasher,
+        MerkleizationError, Merkleized, Node, PathElement, PathError, Sha256Hasher,
         BYTES_PER_CHUNK,
     },
-    ser::{serialize_composite, Serialize, SerializeError},
+    ser::{elements_max_size, serialize_composite, Serialize, SerializeError},
     SimpleSerialize, Sized,
 };
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 #[cfg(feature = "serde")]
 use serde::ser::SerializeSeq;
 #[cfg(feature = "serde")]
 use std::marker::PhantomData;
 
 /// A homogenous collection of a variable number of values.
+/// NOTE: a `List` with a bound of `0` is legal and always empty.
 #[derive(Clone)]
 pub struct List<T: SimpleSerialize, const N: usize> {
     data: Vec<T>,
@@ -121,7 +127,7 @@ where
     fn try_from(data: Vec<T>) -> Result<Self, Self::Error> {
         if data.len() > N {
             let len = data.len();
-            Err((data, Error::Instance(InstanceError::Bounded { bound: N, provided: len })))
+            Err((data, Error::Instance(InstanceError::bounded::<Self>(N, len))))
         } else {
             let leaf_count = Self::get_leaf_count(data.len());
             Ok(Self { data, cache: MerkleCache::with_leaves(leaf_count) })
@@ -129,11 +135,17 @@ where
     }
 }
 
+impl<T: SimpleSerialize, const N: usize> From<List<T, N>> for Vec<T> {
+    fn from(list: List<T, N>) -> Self {
+        list.into_inner()
+    }
+}
+
 impl<T, const N: usize> Deref for List<T, N>
 where
     T: SimpleSerialize,
 {
-    type Target = Vec<T>;
+    type Target = [T];
 
     fn deref(&self) -> &Self::Target {
         &self.data
@@ -142,7 +154,8 @@ where
 
 // NOTE: implement `IndexMut` rather than `DerefMut` to ensure
 // the `List`'s inner `Vec` is not mutated, but its elements
-// can change.
+// can change. Every change to the length goes through a method
+// on `List` so the limit `N` can be enforced.
 impl<T, Idx: SliceIndex<[T]>, const N: usize> Index<Idx> for List<T, N>
 where
     T: SimpleSerialize,
@@ -188,7 +201,7 @@ where
 {
     fn serialize(&self, buffer: &mut Vec<u8>) -> Result<usize, SerializeError> {
         if self.len() > N {
-            return Err(InstanceError::Bounded { bound: N, provided: self.len() }.into())
+            return Err(SerializeError::LimitExceeded { bound: N, provided: self.len() })
         }
         serialize_composite(&self.data, buffer)
     }
@@ -201,7 +214,7 @@ where
     fn deserialize(encoding: &[u8]) -> Result<Self, DeserializeError> {
         let result = deserialize_homogeneous_composite(encoding)?;
         if result.len() > N {
-            return Err(InstanceError::Bounded { bound: N, provided: result.len() }.into())
+            return Err(InstanceError::bounded::<Self>(N, result.len()).into())
         }
         let result = result.try_into().map_err(|(_, err)| match err {
             Error::Instance(err) => DeserializeError::InvalidInstance(err),
@@ -211,10 +224,27 @@ where
     }
 }
 
+impl<T, const N: usize> DeserializeWithLength for List<T, N>
+where
+    T: SimpleSerialize,
+{
+    fn deserialize_with_length(encoding: &[u8], len: usize) -> Result<Self, DeserializeError> {
+        if len > N {
+            return Err(InstanceError::bounded::<Self>(N, len).into())
+        }
+        let data = Vec::<T>::deserialize_with_length(encoding, len)?;
+        let leaf_count = Self::get_leaf_count(data.len());
+        Ok(Self { data, cache: MerkleCache::with_leaves(leaf_count) })
+    }
+}
+
 impl<T, const N: usize> List<T, N>
 where
     T: SimpleSerialize,
 {
+    /// The maximum number of elements in this `List`.
+    pub const MAX_LENGTH: usize = N;
+
     // the number of leafs in the Merkle tree of this `Vector`
     fn get_leaf_count(element_count: usize) -> usize {
         if T::is_composite_type() {
@@ -225,41 +255,69 @@ where
         }
     }
 
+    // the index of the leaf holding the element at `index`, i.e. the chunk it is packed into
     fn get_leaf_index(index: usize) -> usize {
         if T::is_composite_type() {
             index
         } else {
-            // TODO: compute correct leaf index
-            index + 1
+            index * T::size_hint() / BYTES_PER_CHUNK
         }
     }
 
-    fn compute_hash_tree_root(&mut self) -> Result<Node, MerkleizationError> {
-        if T::is_composite_type() {
-            let mut chunks = vec![0u8; self.len() * BYTES_PER_CHUNK];
-            for (i, elem) in self.data.iter_mut().enumerate() {
-                let chunk = elem.hash_tree_root()?;
-                let range = i * BYTES_PER_CHUNK..(i + 1) * BYTES_PER_CHUNK;
-                chunks[range].copy_from_slice(chunk.as_ref());
-            }
-            let data_root = merkleize(&chunks, Some(N))?;
-            Ok(mix_in_length(&data_root, self.len()))
-        } else {
-            let chunks = pack(self)?;
-            let chunk_count = (N * T::size_hint() + 31) / 32;
-            let data_root = merkleize(&chunks, Some(chunk_count))?;
-            Ok(mix_in_length(&data_root, self.len()))
-        }
+    fn compute_hash_tree_root<H: MerkleHasher>(
+        &mut self,
+        context: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
+        let chunks = elements_chunks(&mut self.data, context)?;
+        let data_root = merkleize_with(&chunks, Some(elements_chunk_count::<T>(N)), context)?;
+        Ok(mix_in_length(&data_root, self.len(), context))
+    }
+
+    /// Return the roots of the `2**depth` subtrees at `depth` of the Merkle tree of the
+    /// elements, before the length is mixed in, e.g. the roots of the halves of the `List` at
+    /// depth 1, or the root of the elements at depth 0.
+    ///
+    /// The `2**depth` roots are allocated at once. A `depth` greater than `Self::tree_depth()`
+    /// or than [`MAX_LAYER_DEPTH`](crate::advanced::MAX_LAYER_DEPTH) is an error.
+    pub fn hash_tree_root_at_depth<H: MerkleHasher>(
+        &mut self,
+        depth: u32,
+        context: &Context<H>,
+    ) -> Result<Vec<Node>, MerkleizationError> {
+        let chunks = elements_chunks(&mut self.data, context)?;
+        layer_at_depth_with(&chunks, elements_chunk_count::<T>(N), depth, context)
+    }
+
+    /// Return an iterator over the roots of the elements with the hash function of `context`,
+    /// e.g. to index or deduplicate the elements by root.
+    ///
+    /// The root of a basic element is its encoding padded to a chunk, not the chunk it is
+    /// packed into in the Merkle tree of the `List`. An element which can not be merkleized,
+    /// e.g. a `RawSsz` holding bytes which do not encode its type, yields an error.
+    pub fn element_roots<'a, H: MerkleHasher>(
+        &'a mut self,
+        context: &'a Context<H>,
+    ) -> impl Iterator<Item = Result<Node, MerkleizationError>> + 'a {
+        self.data.iter_mut().map(move |element| element.hash_tree_root_with(context))
     }
 
-    pub fn push(&mut self, element: T) {
+    /// Append `element` to the end of the `List`.
+    /// Returns the `element` back if the `List` is already at its limit `N`.
+    pub fn push(&mut self, element: T) -> Result<(), (T, Error)> {
+        if self.len() >= N {
+            let provided = self.len() + 1;
+            return Err((element, Error::Instance(InstanceError::bounded::<Self>(N, provided))))
+        }
         self.data.push(element);
-        self.cache.resize(self.len());
+        self.cache.resize(Self::get_leaf_count(self.len()));
+        self.invalidate_from(self.len() - 1);
+        Ok(())
     }
 
     pub fn pop(&mut self) -> Option<T> {
+        self.invalidate_from(self.len().saturating_sub(1));
         let element = self.data.pop();
-        self.cache.resize(self.len());
+        self.cache.resize(Self::get_leaf_count(self.len()));
         element
     }
 
@@ -268,9 +326,196 @@ where
         self.cache.resize(0);
     }
 
+    /// Insert `element` at `index`, shifting all elements after it to the right.
+    /// Returns the `element` back if the `List` is already at its limit `N`.
+    ///
+    /// Panics if `index > len`.
+    pub fn insert(&mut self, index: usize, element: T) -> Result<(), (T, Error)> {
+        if self.len() >= N {
+            let provided = self.len() + 1;
+            return Err((element, Error::Instance(InstanceError::bounded::<Self>(N, provided))))
+        }
+        self.data.insert(index, element);
+        self.cache.resize(Self::get_leaf_count(self.len()));
+        self.invalidate_from(index);
+        Ok(())
+    }
+
+    /// Remove and return the element at `index`, shifting all elements after it to the left.
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn remove(&mut self, index: usize) -> T {
+        self.invalidate_from(index);
+        let element = self.data.remove(index);
+        self.cache.resize(Self::get_leaf_count(self.len()));
+        element
+    }
+
+    /// Remove the elements in `range` from the `List`, returning them as an iterator.
+    ///
+    /// Panics under the same conditions as `Vec::drain`.
+    pub fn drain<R>(&mut self, range: R) -> Drain<'_, T>
+    where
+        R: RangeBounds<usize>,
+    {
+        let start = match range.start_bound() {
+            Bound::Included(&index) => index,
+            Bound::Excluded(&index) => index.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        self.invalidate_from(start);
+        let len = self.len();
+        let drain = self.data.drain(range);
+        self.cache.resize(Self::get_leaf_count(len - drain.len()));
+        drain
+    }
+
+    // invalidate every leaf holding an element at or after `index`
+    fn invalidate_from(&mut self, index: usize) {
+        for i in index..self.len() {
+            self.cache.invalidate(Self::get_leaf_index(i));
+        }
+    }
+
+    /// Return the elements as a mutable slice.
+    ///
+    /// The elements can be changed but the length of the `List` can not.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        self.cache.invalidate_all();
+        &mut self.data
+    }
+
     pub fn iter_mut(&mut self) -> IterMut<'_, T, N> {
         IterMut { inner: self.data.iter_mut().enumerate(), cache: &mut self.cache }
     }
+
+    /// Return a guard to read and change the element at `index`, or `None` if out of bounds.
+    ///
+    /// Unlike `get_mut`, only a change through the guard invalidates the cached leaf of the
+    /// element, once the guard is dropped, so reading the element through it keeps the cache.
+    pub fn entry(&mut self, index: usize) -> Option<Entry<'_, T, N>> {
+        if index >= self.len() {
+            return None
+        }
+        Some(Entry { list: self, index, changed: false })
+    }
+
+    /// Return a mutable reference to the element at `index`, or `None` if out of bounds.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.len() {
+            return None
+        }
+        Some(&mut self[index])
+    }
+
+    /// Return the element at `index`, or an error with the length if out of bounds.
+    pub fn try_at(&self, index: usize) -> Result<&T, OutOfBounds> {
+        self.data.get(index).ok_or(OutOfBounds { index, len: self.len() })
+    }
+
+    /// Return a mutable reference to the element at `index`, or an error with the length if
+    /// out of bounds.
+    pub fn try_at_mut(&mut self, index: usize) -> Result<&mut T, OutOfBounds> {
+        let len = self.len();
+        self.get_mut(index).ok_or(OutOfBounds { index, len })
+    }
+
+    /// Return the elements, moving them out without copying.
+    pub fn into_inner(self) -> Vec<T> {
+        self.data
+    }
+
+    /// Return the cache of the root of the `List`, e.g. to persist it with
+    /// [`MerkleCache::to_bytes`].
+    pub fn merkle_cache(&self) -> &MerkleCache {
+        &self.cache
+    }
+
+    /// Construct a `List` from its elements and a cache of its root, e.g. one persisted with
+    /// [`MerkleCache::to_bytes`] and restored with [`MerkleCache::from_bytes`], so hashing the
+    /// `List` again returns the kept root.
+    ///
+    /// The cache is trusted to belong to `data`. Returns `data` back if there are more than `N`
+    /// elements or the cache has a different number of leaves.
+    ///
+    /// ```rust
+    /// use ssz_rs::{advanced::MerkleCache, prelude::*};
+    ///
+    /// let mut list = List::<u64, 1024>::try_from((0..1000u64).collect::<Vec<_>>()).unwrap();
+    /// let root = list.hash_tree_root().unwrap();
+    /// let persisted = list.merkle_cache().to_bytes();
+    ///
+    /// let cache = MerkleCache::from_bytes(&persisted).unwrap();
+    /// let mut restored = List::<u64, 1024>::from_parts(list.into_inner(), cache).unwrap();
+    /// assert_eq!(restored.hash_tree_root().unwrap(), root);
+    /// ```
+    pub fn from_parts(data: Vec<T>, cache: MerkleCache) -> Result<Self, (Vec<T>, Error)> {
+        if data.len() > N {
+            let len = data.len();
+            return Err((data, Error::Instance(InstanceError::bounded::<Self>(N, len))))
+        }
+        let leaf_count = Self::get_leaf_count(data.len());
+        if cache.leaf_count() != leaf_count {
+            let provided = cache.leaf_count();
+            let err = InstanceError::exact::<MerkleCache>(leaf_count, provided);
+            return Err((data, Error::Instance(err)))
+        }
+        Ok(Self { data, cache })
+    }
+}
+
+/// A guard on an element of a [`List`] returned by [`List::entry`], which invalidates the leaf
+/// of the element in the cache of the `List` when it is dropped after a change.
+pub struct Entry<'a, T, const N: usize>
+where
+    T: SimpleSerialize,
+{
+    list: &'a mut List<T, N>,
+    index: usize,
+    changed: bool,
+}
+
+impl<'a, T, const N: usize> Entry<'a, T, N>
+where
+    T: SimpleSerialize,
+{
+    /// The index of the element in the `List`.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
+
+impl<'a, T, const N: usize> Deref for Entry<'a, T, N>
+where
+    T: SimpleSerialize,
+{
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.list.data[self.index]
+    }
+}
+
+impl<'a, T, const N: usize> DerefMut for Entry<'a, T, N>
+where
+    T: SimpleSerialize,
+{
+    fn deref_mut(&mut self) -> &mut T {
+        self.changed = true;
+        &mut self.list.data[self.index]
+    }
+}
+
+impl<'a, T, const N: usize> Drop for Entry<'a, T, N>
+where
+    T: SimpleSerialize,
+{
+    fn drop(&mut self) {
+        if self.changed {
+            let leaf_index = List::<T, N>::get_leaf_index(self.index);
+            self.list.cache.invalidate(leaf_index);
+        }
+    }
 }
 
 pub struct IterMut<'a, T, const N: usize>
@@ -298,21 +543,134 @@ where
     }
 }
 
+#[cfg(feature = "rayon")]
+impl<T, const N: usize> rayon::iter::IntoParallelIterator for List<T, N>
+where
+    T: SimpleSerialize + Send,
+{
+    type Iter = rayon::vec::IntoIter<T>;
+    type Item = T;
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.data.into_par_iter()
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, T, const N: usize> rayon::iter::IntoParallelIterator for &'a List<T, N>
+where
+    T: SimpleSerialize + Sync,
+{
+    type Iter = rayon::slice::Iter<'a, T>;
+    type Item = &'a T;
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.data.par_iter()
+    }
+}
+
+// NOTE: the elements may be mutated in any order across threads
+// so conservatively invalidate every leaf of the cache up front.
+#[cfg(feature = "rayon")]
+impl<'a, T, const N: usize> rayon::iter::IntoParallelIterator for &'a mut List<T, N>
+where
+    T: SimpleSerialize + Send,
+{
+    type Iter = rayon::slice::IterMut<'a, T>;
+    type Item = &'a mut T;
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.cache.invalidate_all();
+        self.data.par_iter_mut()
+    }
+}
+
 impl<T, const N: usize> Merkleized for List<T, N>
 where
     T: SimpleSerialize,
 {
-    fn hash_tree_root(&mut self) -> Result<Node, MerkleizationError> {
-        self.compute_hash_tree_root()
+    fn hash_tree_root_with<H: MerkleHasher>(
+        &mut self,
+        context: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
+        // NOTE: the cache only holds roots computed with the default hash function
+        if TypeId::of::<H>() != TypeId::of::<Sha256Hasher>() {
+            return self.compute_hash_tree_root(context)
+        }
+        if !self.cache.valid() {
+            let Self { data, cache } = self;
+            let depth = Self::tree_depth() as usize;
+            let data_root = cache.rehash(depth, context, |i| element_leaf(data, i, context))?;
+            let root = mix_in_length(&data_root, data.len(), context);
+            self.cache.update(root);
+            self.cache.clean();
+        }
+        Ok(self.cache.root())
+    }
+
+    fn leaves<H: MerkleHasher>(
+        &mut self,
+        context: &Context<H>,
+    ) -> Result<Vec<Node>, MerkleizationError> {
+        Ok(chunks_to_nodes(&elements_chunks(&mut self.data, context)?))
+    }
+
+    const CHUNK_COUNT: usize = elements_chunk_count_const::<T>(N);
+
+    fn merkle_limit() -> Option<usize> {
+        Some(Self::chunk_count())
     }
 }
 
-impl<T, const N: usize> SimpleSerialize for List<T, N> where T: SimpleSerialize {}
+impl<T, const N: usize> SimpleSerialize for List<T, N>
+where
+    T: SimpleSerialize,
+{
+    fn nesting_depth() -> usize {
+        1 + T::nesting_depth()
+    }
+
+    const MAX_SIZE: usize = elements_max_size::<T>(N);
+
+    const NESTING_DEPTH: usize = T::NESTING_DEPTH.saturating_add(1);
+
+    fn generalized_index_from(
+        root: GeneralizedIndex,
+        path: &[PathElement<'_>],
+    ) -> Result<GeneralizedIndex, PathError> {
+        elements_generalized_index::<Self, T>(root, path, N, Self::tree_depth(), true)
+    }
+
+    fn hash_tree_root_from_bytes<H: MerkleHasher>(
+        encoding: &[u8],
+        context: &Context<H>,
+    ) -> Result<Node, Error> {
+        let (data_root, len) = elements_root_from_bytes::<Self, T, H>(encoding, N, context)?;
+        Ok(mix_in_length(&data_root, len, context))
+    }
+
+    fn is_default(&self) -> bool {
+        self.is_empty()
+    }
+
+    fn default_hash_tree_root_with<H: MerkleHasher>(
+        context: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
+        let data_root = merkleize_with(&[], Some(elements_chunk_count::<T>(N)), context)?;
+        Ok(mix_in_length(&data_root, 0, context))
+    }
+
+    fn ssz_heap_size(&self) -> usize {
+        self.data.capacity() * mem::size_of::<T>() +
+            self.data.iter().map(T::ssz_heap_size).sum::<usize>() +
+            self.cache.heap_size()
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::serialize;
+    use crate::{merkleization::pack_elements, serialize};
 
     const COUNT: usize = 32;
 
@@ -320,7 +678,7 @@ mod tests {
     fn encode_list() {
         let mut value: List<u16, COUNT> = List::default();
         for _ in 0..COUNT {
-            value.push(33u16);
+            value.push(33u16).unwrap();
         }
         let encoding = serialize(&value).expect("can encode");
         let expected = [
@@ -368,13 +726,118 @@ mod tests {
         assert_eq!(input, recovered);
     }
 
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_par_iter() {
+        let mut value = List::<u16, COUNT>::try_from(vec![3u16; 10]).unwrap();
+        assert_eq!(value.par_iter().map(|&element| u32::from(element)).sum::<u32>(), 30);
+
+        value.par_iter_mut().for_each(|element| *element += 1);
+        let evens = value.into_par_iter().filter(|element| element % 2 == 0).count();
+        assert_eq!(evens, 10);
+    }
+
+    #[test]
+    fn test_insert_remove_drain() {
+        let mut value = List::<u8, 4>::try_from(vec![1u8, 2u8, 3u8]).unwrap();
+        value.insert(1, 9u8).expect("within limit");
+        assert_eq!(value.as_ref(), [1u8, 9u8, 2u8, 3u8]);
+
+        let (element, err) = value.insert(0, 7u8).unwrap_err();
+        assert_eq!(element, 7u8);
+        assert!(matches!(
+            err,
+            Error::Instance(InstanceError::Bounded { bound: 4, provided: 5, .. })
+        ));
+
+        assert_eq!(value.remove(0), 1u8);
+        assert_eq!(value.as_ref(), [9u8, 2u8, 3u8]);
+
+        let drained = value.drain(1..).collect::<Vec<_>>();
+        assert_eq!(drained, [2u8, 3u8]);
+        assert_eq!(value.as_ref(), [9u8]);
+
+        let mut expected = List::<u8, 4>::try_from(vec![9u8]).unwrap();
+        assert_eq!(value.hash_tree_root().unwrap(), expected.hash_tree_root().unwrap());
+    }
+
+    #[test]
+    fn test_into_inner_moves_elements() {
+        let data = vec![1u32, 2, 3];
+        let ptr = data.as_ptr();
+        let list = List::<u32, 4>::try_from(data).unwrap();
+        let data = list.into_inner();
+        assert_eq!(data.as_ptr(), ptr);
+
+        let list = List::<u32, 4>::try_from(data).unwrap();
+        let data = Vec::from(list);
+        assert_eq!(data, [1, 2, 3]);
+        assert_eq!(data.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn test_push_over_limit() {
+        let mut value = List::<u8, 2>::try_from(vec![1u8]).unwrap();
+        value.push(2).unwrap();
+        let (element, err) = value.push(3).unwrap_err();
+        assert_eq!(element, 3);
+        assert!(matches!(
+            err,
+            Error::Instance(InstanceError::Bounded { bound: 2, provided: 3, .. })
+        ));
+        assert_eq!(serialize(&value).unwrap(), [1u8, 2]);
+
+        value.as_mut_slice()[1] = 3;
+        assert_eq!(value[..], [1u8, 3]);
+    }
+
+    #[test]
+    fn test_list_with_zero_bound() {
+        let mut value = List::<u64, 0>::default();
+        assert_eq!(serialize(&value).unwrap(), [0u8; 0]);
+        assert_eq!(List::<u64, 0>::deserialize(&[]).unwrap(), value);
+        assert!(matches!(
+            List::<u64, 0>::deserialize(&[0u8; 8]),
+            Err(DeserializeError::InvalidInstance(InstanceError::Bounded {
+                bound: 0,
+                provided: 1,
+                ..
+            }))
+        ));
+        assert!(value.push(1).is_err());
+
+        // the length is mixed into a single zero chunk, as for a limit of `0` chunks
+        let context: &Context = Context::global();
+        let expected = mix_in_length(&Node::default(), 0, context);
+        assert_eq!(value.hash_tree_root().unwrap(), expected);
+        assert_eq!(List::<List<u8, 4>, 0>::default().hash_tree_root().unwrap(), expected);
+        assert_eq!(List::<u64, 0>::hash_tree_root_from_bytes(&[], context).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_capacity_consts() {
+        assert_eq!(List::<u8, 7>::MAX_LENGTH, 7);
+        assert_eq!(crate::Vector::<u8, 7>::LENGTH, 7);
+        assert_eq!(crate::Utf8List::<33>::CHUNK_COUNT, 2);
+    }
+
+    #[test]
+    fn test_serialize_over_limit() {
+        let mut value = List::<u8, 2>::try_from(vec![1u8, 2]).unwrap();
+        assert!(value.push(3).is_err());
+        // NOTE: a value over the limit can no longer be built through the public API
+        value.data.push(3);
+        let result = serialize(&value);
+        assert!(matches!(result, Err(SerializeError::LimitExceeded { bound: 2, provided: 3 })));
+    }
+
     #[test]
     fn test_ssz_of_nested_list() {
         use crate::prelude::*;
         type Foo = List<List<u8, 16>, 32>;
 
         let mut value = Foo::default();
-        value.push(Default::default());
+        value.push(Default::default()).unwrap();
         let encoding = ssz_rs::serialize(&value).unwrap();
 
         let mut recovered: Foo = ssz_rs::deserialize(&encoding).unwrap();
@@ -382,4 +845,195 @@ mod tests {
 
         let _ = recovered.hash_tree_root().unwrap();
     }
+
+    #[test]
+    fn test_fallible_access() {
+        let mut list = List::<u64, 8>::try_from(vec![1, 2, 3]).unwrap();
+        let root = list.hash_tree_root().unwrap();
+        assert_eq!(list.get(2), Some(&3));
+        assert_eq!(list.try_at(1), Ok(&2));
+        assert_eq!(list.try_at(3), Err(OutOfBounds { index: 3, len: 3 }));
+        assert!(list.get_mut(5).is_none());
+
+        *list.get_mut(0).unwrap() = 4;
+        *list.try_at_mut(1).unwrap() = 5;
+        assert_eq!(list.as_ref(), [4, 5, 3]);
+        assert_ne!(list.hash_tree_root().unwrap(), root);
+        assert_eq!(
+            list.hash_tree_root().unwrap(),
+            List::<u64, 8>::try_from(vec![4, 5, 3]).unwrap().hash_tree_root().unwrap()
+        );
+        assert_eq!(list.try_at_mut(3).unwrap_err().kind(), crate::ErrorKind::Bounds);
+    }
+
+    #[test]
+    fn test_element_roots() {
+        let context = Context::global();
+        let mut list = List::<List<u8, 4>, 8>::try_from(vec![
+            List::try_from(vec![1u8]).unwrap(),
+            List::default(),
+            List::try_from(vec![1u8]).unwrap(),
+        ])
+        .unwrap();
+        let roots = list.element_roots(context).collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(roots.len(), 3);
+        assert_eq!(roots[0], roots[2]);
+        assert_eq!(roots[1], List::<u8, 4>::default().hash_tree_root().unwrap());
+
+        let chunks = roots.iter().flat_map(|root| root.as_ref().to_vec()).collect::<Vec<_>>();
+        let data_root = merkleize_with(&chunks, Some(8), context).unwrap();
+        assert_eq!(mix_in_length(&data_root, 3, context), list.hash_tree_root().unwrap());
+
+        let mut basic = List::<u16, 8>::try_from(vec![1, 2]).unwrap();
+        let roots = basic.element_roots(context).collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(roots, [1u16.hash_tree_root().unwrap(), 2u16.hash_tree_root().unwrap()]);
+
+        // an element which does not decode yields an error in its place
+        let mut raw = List::<crate::RawSsz<List<u8, 2>>, 4>::try_from(vec![
+            crate::RawSsz::new(vec![1, 2]),
+            crate::RawSsz::new(vec![1, 2, 3]),
+            crate::RawSsz::default(),
+        ])
+        .unwrap();
+        let roots = raw.element_roots(context).collect::<Vec<_>>();
+        assert_eq!(roots.len(), 3);
+        assert!(roots[0].is_ok());
+        assert!(roots[1].is_err());
+        assert_eq!(roots[2].as_ref().unwrap(), &List::<u8, 2>::default().hash_tree_root().unwrap());
+    }
+
+    #[test]
+    fn test_hash_tree_root_at_depth() {
+        let context = Context::global();
+        let mut list = List::<u64, 16>::try_from((1..=5).collect::<Vec<_>>()).unwrap();
+        let chunks = pack_elements(&list).unwrap();
+
+        let halves = list.hash_tree_root_at_depth(1, context).unwrap();
+        assert_eq!(halves.len(), 2);
+        assert_eq!(halves[0], merkleize_with(&chunks, Some(2), context).unwrap());
+        assert_eq!(halves[1], Node::zero_at_depth(1, context));
+
+        let leaves = list.hash_tree_root_at_depth(2, context).unwrap();
+        assert_eq!(leaves[1], Node::try_from(&chunks[32..]).unwrap());
+        assert_eq!(leaves[2], Node::default());
+
+        let data_root = list.hash_tree_root_at_depth(0, context).unwrap();
+        assert_eq!(mix_in_length(&data_root[0], 5, context), list.hash_tree_root().unwrap());
+
+        let err = list.hash_tree_root_at_depth(3, context).unwrap_err();
+        assert!(matches!(err, MerkleizationError::InvalidDepth { depth: 3, max_depth: 2 }));
+        // the layers of large trees are bounded regardless of their depth
+        let mut list = List::<u64, { 1 << 40 }>::default();
+        let err = list.hash_tree_root_at_depth(64, context).unwrap_err();
+        assert!(matches!(err, MerkleizationError::InvalidDepth { depth: 64, max_depth: 24 }));
+    }
+    #[test]
+    fn test_entry() {
+        let mut list = List::<u16, 64>::try_from((0..40).collect::<Vec<_>>()).unwrap();
+        assert_eq!(list.cache.dirty_leaves().collect::<Vec<_>>(), [0, 1, 2]);
+        list.cache.clean();
+
+        assert_eq!(*list.entry(17).unwrap(), 17);
+        assert_eq!(list.cache.dirty_leaves().count(), 0);
+        let mut entry = list.entry(17).unwrap();
+        *entry += 100;
+        assert_eq!(entry.index(), 17);
+        drop(entry);
+        assert_eq!(list[17], 117);
+        assert_eq!(list.cache.dirty_leaves().collect::<Vec<_>>(), [1]);
+        assert!(list.entry(40).is_none());
+
+        list.cache.clean();
+        list.push(40).unwrap();
+        assert_eq!(list.cache.dirty_leaves().collect::<Vec<_>>(), [2]);
+        list.cache.clean();
+        list.drain(32..);
+        assert_eq!(list.cache.dirty_leaves().count(), 0);
+        list.pop();
+        assert_eq!(list.cache.dirty_leaves().collect::<Vec<_>>(), [1]);
+
+        let mut list = List::<List<u8, 4>, 8>::try_from(vec![List::default(); 3]).unwrap();
+        list.cache.clean();
+        list.entry(2).unwrap().push(1).unwrap();
+        assert_eq!(list.cache.dirty_leaves().collect::<Vec<_>>(), [2]);
+        let mut expected = List::<List<u8, 4>, 8>::try_from(vec![List::default(); 3]).unwrap();
+        expected[2].push(1).unwrap();
+        assert_eq!(list.hash_tree_root().unwrap(), expected.hash_tree_root().unwrap());
+    }
+
+    #[test]
+    fn test_rehash_dirty_leaves() {
+        // a change the cache does not see shows that only the dirty leaves are hashed again
+        let mut list = List::<List<u8, 4>, 64>::try_from(vec![List::default(); 20]).unwrap();
+        list.hash_tree_root().unwrap();
+        list.data[0].push(1).unwrap();
+        list.entry(7).unwrap().push(2).unwrap();
+        let mut expected = List::<List<u8, 4>, 64>::try_from(vec![List::default(); 20]).unwrap();
+        expected[7].push(2).unwrap();
+        assert_eq!(list.hash_tree_root().unwrap(), expected.hash_tree_root().unwrap());
+
+        // the root follows every change of the elements and of the length
+        fn check(list: &mut List<u16, 300>) {
+            let mut expected = List::<u16, 300>::try_from(list.to_vec()).unwrap();
+            assert_eq!(list.hash_tree_root().unwrap(), expected.hash_tree_root().unwrap());
+        }
+        let mut list = List::<u16, 300>::default();
+        check(&mut list);
+        for i in 0..100 {
+            list.push(i).unwrap();
+            check(&mut list);
+        }
+        for i in (0..100).step_by(7) {
+            *list.entry(i).unwrap() += 1;
+            list[i / 2] = 9;
+            check(&mut list);
+        }
+        for len in [99, 96, 80, 65, 64, 63, 33, 17, 16, 1] {
+            list.drain(len..);
+            check(&mut list);
+            list.pop();
+            check(&mut list);
+            list.push(7).unwrap();
+            list.push(8).unwrap();
+            check(&mut list);
+        }
+        list.remove(1);
+        check(&mut list);
+        list.insert(0, 3).unwrap();
+        check(&mut list);
+        list.clear();
+        check(&mut list);
+        list.push(1).unwrap();
+        check(&mut list);
+    }
+
+    #[test]
+    fn test_restored_cache() {
+        let mut list = List::<u64, 64>::try_from((0..40u64).collect::<Vec<_>>()).unwrap();
+        let root = list.hash_tree_root().unwrap();
+        assert!(list.merkle_cache().valid());
+        let cache = MerkleCache::from_bytes(&list.merkle_cache().to_bytes()).unwrap();
+        let mut restored = List::<u64, 64>::from_parts(list.to_vec(), cache).unwrap();
+        assert_eq!(restored.hash_tree_root().unwrap(), root);
+
+        // the restored root is returned without hashing the elements, so a planted root shows
+        let mut planted = MerkleCache::with_leaves(10);
+        planted.update(Node::try_from([7u8; 32].as_ref()).unwrap());
+        planted.clean();
+        let mut restored = List::<u64, 64>::from_parts(list.to_vec(), planted).unwrap();
+        assert_eq!(restored.hash_tree_root().unwrap(), Node::try_from([7u8; 32].as_ref()).unwrap());
+        restored[3] = 3;
+        assert_eq!(restored.hash_tree_root().unwrap(), root);
+        restored.push(40).unwrap();
+        assert_ne!(restored.hash_tree_root().unwrap(), root);
+
+        assert!(List::<u64, 64>::from_parts(list.to_vec(), MerkleCache::with_leaves(9)).is_err());
+        assert!(List::<u64, 8>::from_parts(list.to_vec(), MerkleCache::with_leaves(10)).is_err());
+        let mut empty = List::<u64, 64>::default();
+        assert_eq!(
+            empty.hash_tree_root().unwrap(),
+            List::<u64, 64>::default().hash_tree_root().unwrap()
+        );
+        assert_ne!(empty.hash_tree_root().unwrap(), Node::default());
+    }
 }
@@ -0,0 +1,254 @@
+//! Support for encoding a `BTreeMap<K, V>` as the SSZ type `List[MapEntry[K, V], N]`.
+//!
+//! SSZ has no native map type so a map is represented by the list of its entries
+//! in strictly increasing order of the keys. This gives every map exactly one
+//! encoding (and so one hash tree root) and decoding rejects any other ordering.
+//!
+//! A `BTreeMap` field of a container can use this representation with the
+//! `#[ssz(as_list(max = N))]` attribute:
+//!
+//! ```rust
+//! use ssz_rs::prelude::*;
+//! use std::collections::BTreeMap;
+//!
+//! #[derive(Default, Debug, PartialEq, Eq, SimpleSerialize)]
+//! struct Registry {
+//!     version: u8,
+//!     #[ssz(as_list(max = 16))]
+//!     balances: BTreeMap<u32, u64>,
+//! }
+//!
+//! // the same SSZ type, spelled out
+//! #[derive(Default, Debug, SimpleSerialize)]
+//! struct RegistryAsList {
+//!     version: u8,
+//!     balances: List<ssz_rs::map::MapEntry<u32, u64>, 16>,
+//! }
+//!
+//! let mut registry = Registry { version: 1, balances: BTreeMap::from([(3, 30), (1, 10)]) };
+//! let mut equivalent = RegistryAsList::default();
+//! equivalent.version = 1;
+//! equivalent.balances.push(ssz_rs::map::MapEntry { key: 1, value: 10 }).unwrap();
+//! equivalent.balances.push(ssz_rs::map::MapEntry { key: 3, value: 30 }).unwrap();
+//!
+//! let encoding = serialize(&registry).unwrap();
+//! assert_eq!(encoding, serialize(&equivalent).unwrap());
+//! assert_eq!(Registry::deserialize(&encoding).unwrap(), registry);
+//! assert_eq!(registry.hash_tree_root().unwrap(), equivalent.hash_tree_root().unwrap());
+//! ```
+use crate::{
+    container::SszValue,
+    de::{Deserialize, DeserializeError},
+    error::{Error, InstanceError},
+    lib::*,
+    list::List,
+    merkleization::{
+        Context, GeneralizedIndex, MerkleHasher, MerkleizationError, Merkleized, Node, PathElement,
+        PathError,
+    },
+    prelude::*,
+    ser::SerializeError,
+};
+
+/// An entry of a map, i.e. the SSZ container `MapEntry[K, V]`.
+#[derive(Default, Debug, Clone, PartialEq, Eq, SimpleSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MapEntry<K: SimpleSerialize, V: SimpleSerialize> {
+    pub key: K,
+    pub value: V,
+}
+
+fn to_list<K, V, const N: usize>(
+    map: &BTreeMap<K, V>,
+) -> Result<List<MapEntry<K, V>, N>, InstanceError>
+where
+    K: SimpleSerialize + Clone,
+    V: SimpleSerialize + Clone,
+{
+    let entries = map
+        .iter()
+        .map(|(key, value)| MapEntry { key: key.clone(), value: value.clone() })
+        .collect::<Vec<_>>();
+    List::try_from(entries)
+        .map_err(|_| InstanceError::bounded::<List<MapEntry<K, V>, N>>(N, map.len()))
+}
+
+/// Serialize `map` as a `List[MapEntry[K, V], N]` into `buffer`.
+pub fn serialize<K, V, const N: usize>(
+    map: &BTreeMap<K, V>,
+    buffer: &mut Vec<u8>,
+) -> Result<usize, SerializeError>
+where
+    K: SimpleSerialize + Clone,
+    V: SimpleSerialize + Clone,
+{
+    to_list::<K, V, N>(map)
+        .map_err(|_| SerializeError::LimitExceeded { bound: N, provided: map.len() })?
+        .serialize(buffer)
+}
+
+/// A view of a map as the SSZ type `List[MapEntry[K, V], N]`, e.g. to visit a map field.
+pub struct MapAsList<'a, K, V, const N: usize>(pub &'a BTreeMap<K, V>);
+
+impl<'a, K, V, const N: usize> SszValue for MapAsList<'a, K, V, N>
+where
+    K: SimpleSerialize + Clone,
+    V: SimpleSerialize + Clone,
+{
+    fn type_name(&self) -> &'static str {
+        any::type_name::<BTreeMap<K, V>>()
+    }
+
+    fn encode(&self, buffer: &mut Vec<u8>) -> Result<usize, SerializeError> {
+        serialize::<K, V, N>(self.0, buffer)
+    }
+
+    fn root(&self) -> Result<Node, Error> {
+        Ok(hash_tree_root::<K, V, N>(self.0)?)
+    }
+
+    fn heap_size(&self) -> usize {
+        heap_size(self.0)
+    }
+}
+
+/// Deserialize a map from the encoding of a `List[MapEntry[K, V], N]`.
+///
+/// The keys must be in strictly increasing order.
+pub fn deserialize<K, V, const N: usize>(
+    encoding: &[u8],
+) -> Result<BTreeMap<K, V>, DeserializeError>
+where
+    K: SimpleSerialize + Ord,
+    V: SimpleSerialize,
+{
+    let mut entries = List::<MapEntry<K, V>, N>::deserialize(encoding)?;
+    if let Some(index) = entries.windows(2).position(|pair| pair[0].key >= pair[1].key) {
+        return Err(InstanceError::Unordered { index: index + 1 }.into())
+    }
+    Ok(entries.drain(..).map(|entry| (entry.key, entry.value)).collect())
+}
+
+/// Compute the hash tree root of `map` as a `List[MapEntry[K, V], N]`.
+pub fn hash_tree_root<K, V, const N: usize>(
+    map: &BTreeMap<K, V>,
+) -> Result<Node, MerkleizationError>
+where
+    K: SimpleSerialize + Clone,
+    V: SimpleSerialize + Clone,
+{
+    hash_tree_root_with::<K, V, N, _>(map, Context::global())
+}
+
+/// Compute the hash tree root of `map` as a `List[MapEntry[K, V], N]`
+/// with the hash function of the given `context`.
+pub fn hash_tree_root_with<K, V, const N: usize, H>(
+    map: &BTreeMap<K, V>,
+    context: &Context<H>,
+) -> Result<Node, MerkleizationError>
+where
+    K: SimpleSerialize + Clone,
+    V: SimpleSerialize + Clone,
+    H: MerkleHasher,
+{
+    let mut entries =
+        to_list::<K, V, N>(map).map_err(|_| MerkleizationError::InputExceedsLimit(N))?;
+    entries.hash_tree_root_with(context)
+}
+
+/// Compute the hash tree root of the map encoded as `encoding`, checking the order of the keys.
+pub fn hash_tree_root_from_bytes<K, V, const N: usize, H>(
+    encoding: &[u8],
+    context: &Context<H>,
+) -> Result<Node, Error>
+where
+    K: SimpleSerialize + Ord + Clone,
+    V: SimpleSerialize + Clone,
+    H: MerkleHasher,
+{
+    let map = deserialize::<K, V, N>(encoding)?;
+    Ok(hash_tree_root_with::<K, V, N, H>(&map, context)?)
+}
+
+/// Return the nesting depth of a map as a `List[MapEntry[K, V], N]`.
+pub const fn nesting_depth<K, V>() -> usize
+where
+    K: SimpleSerialize,
+    V: SimpleSerialize,
+{
+    List::<MapEntry<K, V>, 1>::NESTING_DEPTH
+}
+
+/// Return the maximum length of the encoding of a map as a `List[MapEntry[K, V], N]`.
+pub const fn max_size<K, V, const N: usize>() -> usize
+where
+    K: SimpleSerialize,
+    V: SimpleSerialize,
+{
+    List::<MapEntry<K, V>, N>::MAX_SIZE
+}
+
+/// Return the generalized index of the node at `path` in the Merkle tree of a map as a
+/// `List[MapEntry[K, V], N]` rooted at `root`.
+pub fn generalized_index_from<K, V, const N: usize>(
+    root: GeneralizedIndex,
+    path: &[PathElement<'_>],
+) -> Result<GeneralizedIndex, PathError>
+where
+    K: SimpleSerialize,
+    V: SimpleSerialize,
+{
+    List::<MapEntry<K, V>, N>::generalized_index_from(root, path)
+}
+
+/// Return the approximate number of bytes allocated on the heap by `map`.
+pub fn heap_size<K, V>(map: &BTreeMap<K, V>) -> usize
+where
+    K: SimpleSerialize,
+    V: SimpleSerialize,
+{
+    map.iter()
+        .map(|(key, value)| mem::size_of::<(K, V)>() + key.ssz_heap_size() + value.ssz_heap_size())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_as_list() {
+        let map = BTreeMap::from([(5u16, true), (2, false), (9, true)]);
+        let mut buffer = vec![];
+        serialize::<_, _, 4>(&map, &mut buffer).unwrap();
+
+        let mut entries = List::<MapEntry<u16, bool>, 4>::default();
+        for (key, value) in [(2u16, false), (5, true), (9, true)] {
+            entries.push(MapEntry { key, value }).unwrap();
+        }
+        assert_eq!(buffer, crate::serialize(&entries).unwrap());
+        assert_eq!(deserialize::<u16, bool, 4>(&buffer).unwrap(), map);
+        assert_eq!(hash_tree_root::<_, _, 4>(&map).unwrap(), entries.hash_tree_root().unwrap());
+
+        assert!(serialize::<_, _, 2>(&map, &mut vec![]).is_err());
+        assert!(hash_tree_root::<_, _, 2>(&map).is_err());
+    }
+
+    #[test]
+    fn test_map_rejects_non_canonical_order() {
+        let mut entries = List::<MapEntry<u16, bool>, 4>::default();
+        for (key, value) in [(2u16, false), (9, true), (5, true)] {
+            entries.push(MapEntry { key, value }).unwrap();
+        }
+        let encoding = crate::serialize(&entries).unwrap();
+        let result = deserialize::<u16, bool, 4>(&encoding);
+        assert!(matches!(
+            result,
+            Err(DeserializeError::InvalidInstance(InstanceError::Unordered { index: 2 }))
+        ));
+
+        entries[2].key = 9;
+        let encoding = crate::serialize(&entries).unwrap();
+        assert!(deserialize::<u16, bool, 4>(&encoding).is_err());
+    }
+}
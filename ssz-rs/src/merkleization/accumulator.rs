@@ -0,0 +1,109 @@
+use crate::{
+    lib::*,
+    list::List,
+    merkleization::{incremental::Frontier, mix_in_length, Context, MerkleizationError, Node},
+};
+
+/// A `RootAccumulator` maintains a `List<Node, N>` of roots, e.g. the historical roots of
+/// a beacon state, along with the frontier of the Merkle tree over them so that appending a
+/// root hashes each subtree it completes once and the root costs `O(log N)` hashes rather than
+/// re-hashing the entire list from scratch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RootAccumulator<const N: usize> {
+    roots: List<Node, N>,
+    frontier: Frontier,
+}
+
+impl<const N: usize> Default for RootAccumulator<N> {
+    fn default() -> Self {
+        let depth = N.next_power_of_two().trailing_zeros() as usize;
+        Self { roots: List::default(), frontier: Frontier::new(depth, Context::global()) }
+    }
+}
+
+impl<const N: usize> RootAccumulator<N> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append `root` to the accumulated roots.
+    pub fn push(&mut self, root: Node) -> Result<(), MerkleizationError> {
+        let index = self.roots.len();
+        self.roots.push(root).map_err(|_| MerkleizationError::InputExceedsLimit(N))?;
+        self.frontier.push(index, root, |_, _| {});
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.roots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.roots.is_empty()
+    }
+
+    /// Return the accumulated roots.
+    pub fn roots(&self) -> &List<Node, N> {
+        &self.roots
+    }
+
+    /// Return the accumulated roots, consuming the accumulator.
+    pub fn into_roots(self) -> List<Node, N> {
+        self.roots
+    }
+
+    /// Compute the hash tree root of the accumulated roots, i.e. the
+    /// hash tree root of `roots()`.
+    pub fn root(&self) -> Node {
+        mix_in_length(&self.frontier.root(self.len()), self.len(), Context::global())
+    }
+}
+
+impl<const N: usize> From<List<Node, N>> for RootAccumulator<N> {
+    fn from(roots: List<Node, N>) -> Self {
+        let mut accumulator = Self::default();
+        for root in roots.iter() {
+            accumulator.push(*root).expect("list is within bounds");
+        }
+        accumulator
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkleization::Merkleized;
+
+    #[test]
+    fn test_root_accumulator() {
+        let mut accumulator = RootAccumulator::<5>::new();
+        let mut list = List::<Node, 5>::default();
+        assert_eq!(accumulator.root(), list.hash_tree_root().unwrap());
+
+        for i in 0..5u8 {
+            let root = Node::try_from([i; 32].as_ref()).unwrap();
+            accumulator.push(root).unwrap();
+            list.push(root).unwrap();
+            assert_eq!(accumulator.root(), list.hash_tree_root().unwrap());
+        }
+        assert!(accumulator.push(Node::default()).is_err());
+
+        let recovered = RootAccumulator::from(list.clone());
+        assert_eq!(recovered, accumulator);
+        assert_eq!(accumulator.into_roots(), list);
+    }
+
+    #[test]
+    fn test_root_accumulator_when_full() {
+        let mut accumulator = RootAccumulator::<4>::new();
+        let mut list = List::<Node, 4>::default();
+        for i in 0..4u8 {
+            let root = Node::try_from([i; 32].as_ref()).unwrap();
+            accumulator.push(root).unwrap();
+            list.push(root).unwrap();
+            assert_eq!(accumulator.root(), list.hash_tree_root().unwrap());
+        }
+        assert!(accumulator.push(Node::default()).is_err());
+        assert_eq!(accumulator.root(), list.hash_tree_root().unwrap());
+    }
+}
@@ -0,0 +1,278 @@
+//! Merkleization with backends hashing whole layers of a Merkle tree at once, e.g. on a GPU or
+//! an FPGA, which are driven asynchronously.
+use crate::{
+    lib::*,
+    merkleization::{
+        padded_leaf_count, Context, MerkleHasher, MerkleizationError, Node, BYTES_PER_CHUNK,
+    },
+    metrics,
+};
+use core::{
+    convert::Infallible,
+    future::{ready, Future, Ready},
+};
+
+/// A `BulkHasher` hashes many pairs of nodes in one request, e.g. to a GPU or an FPGA, so the
+/// cost of a request is amortized over a whole layer of a Merkle tree.
+///
+/// The request returns a future, so backends can hash asynchronously. Every [`MerkleHasher`] is
+/// a `BulkHasher` hashing the pairs with [`MerkleHasher::hash_pairs`] in a future which is
+/// immediately ready, which is also how [`Merkleized`](crate::Merkleized) values hash each layer
+/// of their trees, so a synchronous backend overriding it serves every hash tree root.
+///
+/// ```rust
+/// use core::future::{ready, Ready};
+/// use ssz_rs::advanced::{BulkHasher, MerkleHasher, Sha256Hasher};
+///
+/// // a backend which would submit the pairs to a device in one batch
+/// #[derive(Default)]
+/// struct Device {
+///     batches: usize,
+/// }
+///
+/// impl BulkHasher for Device {
+///     type Error = core::convert::Infallible;
+///     type Future<'a> = Ready<Result<(), Self::Error>>;
+///
+///     fn hash_pairs<'a>(&'a mut self, pairs: &'a [u8], out: &'a mut [u8]) -> Self::Future<'a> {
+///         self.batches += 1;
+///         let mut hasher = Sha256Hasher::default();
+///         for (pair, parent) in pairs.chunks_exact(64).zip(out.chunks_exact_mut(32)) {
+///             hasher.hash_nodes(&pair[..32], &pair[32..], parent);
+///         }
+///         ready(Ok(()))
+///     }
+/// }
+/// ```
+pub trait BulkHasher {
+    /// The error of a failed request, e.g. a lost device.
+    type Error;
+
+    /// The future of a request, resolving once every parent was written.
+    type Future<'a>: Future<Output = Result<(), Self::Error>> + 'a
+    where
+        Self: 'a;
+
+    /// Hash each pair of nodes in `pairs`, 64 bytes each, into the chunk of `out` at the same
+    /// index, so `out` is half as long as `pairs`.
+    fn hash_pairs<'a>(&'a mut self, pairs: &'a [u8], out: &'a mut [u8]) -> Self::Future<'a>;
+}
+
+impl<H: MerkleHasher> BulkHasher for H {
+    type Error = Infallible;
+    type Future<'a> = Ready<Result<(), Infallible>>;
+
+    fn hash_pairs<'a>(&'a mut self, pairs: &'a [u8], out: &'a mut [u8]) -> Self::Future<'a> {
+        MerkleHasher::hash_pairs(self, pairs, out);
+        ready(Ok(()))
+    }
+}
+
+/// An error merkleizing with a [`BulkHasher`].
+#[derive(Debug)]
+pub enum BulkHashError<E> {
+    /// The chunks can not be merkleized, e.g. there are more chunks than the limit.
+    Merkleization(MerkleizationError),
+    /// The backend failed a request.
+    Backend(E),
+}
+
+impl<E> From<MerkleizationError> for BulkHashError<E> {
+    fn from(err: MerkleizationError) -> Self {
+        Self::Merkleization(err)
+    }
+}
+
+impl<E: Display> Display for BulkHashError<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Merkleization(err) => write!(f, "{err}"),
+            Self::Backend(err) => write!(f, "the hashing backend failed: {err}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: Debug + Display> std::error::Error for BulkHashError<E> {}
+
+/// Return the root of the Merkle tree of `chunks` padded to `limit` chunks, as
+/// [`merkleize_with`](crate::advanced::merkleize_with), hashing each layer of the tree in one
+/// request to `hasher`.
+///
+/// The zero subtrees padding the layers are taken from the `context`, whose hash function must
+/// be the one of `hasher`.
+pub async fn merkleize_bulk<B: BulkHasher, H: MerkleHasher>(
+    hasher: &mut B,
+    chunks: &[u8],
+    limit: Option<usize>,
+    context: &Context<H>,
+) -> Result<Node, BulkHashError<B::Error>> {
+    debug_assert!(chunks.len() % BYTES_PER_CHUNK == 0);
+    let chunk_count = chunks.len() / BYTES_PER_CHUNK;
+    metrics::record_chunks(chunk_count);
+    let depth = padded_leaf_count(chunk_count, limit)?.trailing_zeros() as usize;
+    if chunk_count == 0 {
+        return Ok(Node::try_from(&context[depth]).expect("is one chunk"))
+    }
+
+    let mut layer = chunks.to_vec();
+    for height in 0..depth {
+        // NOTE: an odd last node is paired with the root of a zero subtree
+        if layer.len() % (2 * BYTES_PER_CHUNK) != 0 {
+            layer.extend_from_slice(&context[height]);
+        }
+        let mut parents = vec![0u8; layer.len() / 2];
+        metrics::record_hashes(parents.len() / BYTES_PER_CHUNK);
+        hasher.hash_pairs(&layer, &mut parents).await.map_err(BulkHashError::Backend)?;
+        layer = parents;
+    }
+    Ok(Node::try_from(layer.as_ref()).expect("is one chunk"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        merkleization::{merkleize, Sha256Hasher},
+        prelude::*,
+    };
+    use core::{
+        pin::Pin,
+        task::{Context as TaskContext, Poll, RawWaker, RawWakerVTable, Waker},
+    };
+
+    // a backend answering every other poll, recording the size of each request
+    #[derive(Default)]
+    struct Device {
+        requests: Vec<usize>,
+        fail: bool,
+    }
+
+    struct Request<'a> {
+        pairs: &'a [u8],
+        out: &'a mut [u8],
+        fail: bool,
+        polled: bool,
+    }
+
+    impl<'a> Future for Request<'a> {
+        type Output = Result<(), &'static str>;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Self::Output> {
+            if !self.polled {
+                self.polled = true;
+                cx.waker().wake_by_ref();
+                return Poll::Pending
+            }
+            if self.fail {
+                return Poll::Ready(Err("device lost"))
+            }
+            let Request { pairs, out, .. } = &mut *self;
+            let mut hasher = Sha256Hasher::default();
+            for (pair, parent) in pairs.chunks_exact(64).zip(out.chunks_exact_mut(32)) {
+                hasher.hash_nodes(&pair[..32], &pair[32..], parent);
+            }
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    impl BulkHasher for Device {
+        type Error = &'static str;
+        type Future<'a> = Request<'a>;
+
+        fn hash_pairs<'a>(&'a mut self, pairs: &'a [u8], out: &'a mut [u8]) -> Self::Future<'a> {
+            self.requests.push(pairs.len() / (2 * BYTES_PER_CHUNK));
+            Request { pairs, out, fail: self.fail, polled: false }
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        fn raw_waker() -> RawWaker {
+            fn clone(_: *const ()) -> RawWaker {
+                raw_waker()
+            }
+            fn noop(_: *const ()) {}
+            static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+            RawWaker::new(core::ptr::null(), &VTABLE)
+        }
+        let waker = unsafe { Waker::from_raw(raw_waker()) };
+        let mut cx = TaskContext::from_waker(&waker);
+        let mut future = Box::pin(future);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output
+            }
+        }
+    }
+
+    #[test]
+    fn test_merkleize_bulk() {
+        let context = Context::global();
+        let chunks = (0..5 * BYTES_PER_CHUNK).map(|i| i as u8).collect::<Vec<_>>();
+        for limit in [None, Some(5), Some(8), Some(1 << 20)] {
+            let mut device = Device::default();
+            let root = block_on(merkleize_bulk(&mut device, &chunks, limit, context)).unwrap();
+            assert_eq!(root, merkleize(&chunks, limit).unwrap());
+            assert_eq!(device.requests[..3], [3, 2, 1]);
+        }
+
+        let mut device = Device::default();
+        let root = block_on(merkleize_bulk(&mut device, &[], Some(4), context)).unwrap();
+        assert_eq!(root, Node::zero_at_depth(2, context));
+        assert!(device.requests.is_empty());
+
+        let mut hasher = Sha256Hasher::default();
+        let root = block_on(merkleize_bulk(&mut hasher, &chunks[..32], None, context)).unwrap();
+        assert_eq!(root.as_ref(), &chunks[..32]);
+        assert!(matches!(
+            block_on(merkleize_bulk(&mut hasher, &chunks, Some(4), context)),
+            Err(BulkHashError::Merkleization(MerkleizationError::InputExceedsLimit(4)))
+        ));
+
+        let mut device = Device { fail: true, ..Default::default() };
+        let err = block_on(merkleize_bulk(&mut device, &chunks, None, context)).unwrap_err();
+        assert!(matches!(err, BulkHashError::Backend("device lost")));
+        assert_eq!(err.to_string(), "the hashing backend failed: device lost");
+    }
+
+    #[test]
+    fn test_merkleized_walk_hashes_layers() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+
+        static BATCHES: AtomicUsize = AtomicUsize::new(0);
+        static PAIRS: AtomicUsize = AtomicUsize::new(0);
+
+        // counts the layers it is asked to hash at once
+        #[derive(Default)]
+        struct Batching(Sha256Hasher);
+
+        impl MerkleHasher for Batching {
+            fn hash_nodes(&mut self, left: &[u8], right: &[u8], out: &mut [u8]) {
+                self.0.hash_nodes(left, right, out);
+            }
+
+            fn hash_pairs(&mut self, pairs: &[u8], out: &mut [u8]) {
+                BATCHES.fetch_add(1, Ordering::Relaxed);
+                PAIRS.fetch_add(out.len() / BYTES_PER_CHUNK, Ordering::Relaxed);
+                MerkleHasher::hash_pairs(&mut self.0, pairs, out);
+            }
+        }
+
+        let mut value =
+            List::<Vector<u64, 8>, 16>::try_from(vec![Vector::try_from(vec![7u64; 8]).unwrap(); 5])
+                .unwrap();
+        let context = Context::<Batching>::new();
+        let root = value.hash_tree_root_with(&context).unwrap();
+        assert_eq!(root, value.hash_tree_root().unwrap());
+        // one layer of each element and the four layers of the list
+        assert_eq!(BATCHES.load(Ordering::Relaxed), 5 + 4);
+        assert_eq!(PAIRS.load(Ordering::Relaxed), 5 + 3 + 2 + 1 + 1);
+
+        // the blanket `BulkHasher` hashes with the override as well
+        let chunks = [1u8; 4 * BYTES_PER_CHUNK];
+        let mut hasher = Batching::default();
+        let root = block_on(merkleize_bulk(&mut hasher, &chunks, None, &context)).unwrap();
+        assert_eq!(root, merkleize(&chunks, None).unwrap());
+        assert_eq!(BATCHES.load(Ordering::Relaxed), 5 + 4 + 2);
+    }
+}
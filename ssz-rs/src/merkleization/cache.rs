@@ -1,22 +1,56 @@
-use crate::merkleization::Node;
+use crate::{
+    de::{Deserialize, DeserializeError},
+    lib::*,
+    merkleization::{hash_nodes, Context, MerkleHasher, MerkleizationError, Node, BYTES_PER_CHUNK},
+    metrics,
+    ser::Serialize,
+};
 use bitvec::prelude::{bitvec, BitVec, Lsb0};
 
+// the leaf count, whether there is a root, the root and the number of dirty leaves precede the
+// packed dirty leaves
+const HEADER_LENGTH: usize = 8 + 1 + BYTES_PER_CHUNK + 8;
+
 #[derive(Default, Debug, Clone)]
 pub struct Cache {
     leaf_count: usize,
     dirty_leaves: BitVec,
-    root: Node,
+    root: Option<Node>,
+    // the nodes of the Merkle tree from the leaves up, without the zero nodes padding each layer
+    layers: Vec<Vec<Node>>,
 }
 
+// NOTE: the nodes of the tree are not persisted, so they are not part of the state of the cache
+impl PartialEq for Cache {
+    fn eq(&self, other: &Self) -> bool {
+        self.leaf_count == other.leaf_count &&
+            self.dirty_leaves == other.dirty_leaves &&
+            self.root == other.root
+    }
+}
+
+impl Eq for Cache {}
+
 impl Cache {
     pub fn with_leaves(leaf_count: usize) -> Self {
         Self { leaf_count, dirty_leaves: bitvec![usize, Lsb0; 1; leaf_count], ..Default::default() }
     }
 
+    /// Return the number of bytes allocated on the heap by the cache.
+    pub fn heap_size(&self) -> usize {
+        let nodes = self.layers.iter().map(Vec::capacity).sum::<usize>();
+        self.dirty_leaves.capacity() / 8 + nodes * BYTES_PER_CHUNK
+    }
+
+    /// Return the number of leaves of the value the cache belongs to.
+    pub fn leaf_count(&self) -> usize {
+        self.dirty_leaves.len()
+    }
+
     pub fn valid(&self) -> bool {
         let has_dirty_leaves = self.dirty_leaves.any();
         let did_resize = self.leaf_count != self.dirty_leaves.len();
-        !(has_dirty_leaves || did_resize)
+        self.root.is_some() && !(has_dirty_leaves || did_resize)
     }
 
     pub fn invalidate(&mut self, leaf_index: usize) {
@@ -26,15 +60,192 @@ impl Cache {
         }
     }
 
+    /// Return the indices of the leaves changed since the cache was last cleaned.
+    pub fn dirty_leaves(&self) -> impl Iterator<Item = usize> + '_ {
+        self.dirty_leaves.iter_ones()
+    }
+
+    /// Mark every leaf clean, e.g. once the dirty leaves were hashed again.
+    pub fn clean(&mut self) {
+        self.leaf_count = self.dirty_leaves.len();
+        self.dirty_leaves.fill(false);
+    }
+
+    pub fn invalidate_all(&mut self) {
+        self.dirty_leaves.fill(true);
+    }
+
     pub fn resize(&mut self, bound: usize) {
         self.dirty_leaves.resize(bound, true);
     }
 
     pub fn update(&mut self, root: Node) {
-        self.root = root;
+        self.root = Some(root);
     }
 
+    /// Return the root of the Merkle tree of the leaves padded to `2**depth` leaves, hashing
+    /// again only the dirty leaves, as given by `leaf`, and the nodes above them.
+    ///
+    /// The nodes of the tree are kept for the next call. A cache without them, e.g. one
+    /// restored with [`Cache::from_bytes`], hashes every leaf the first time. The leaves are
+    /// left dirty, to be cleaned with [`Cache::clean`] once the root is updated.
+    pub(crate) fn rehash<H: MerkleHasher>(
+        &mut self,
+        depth: usize,
+        context: &Context<H>,
+        mut leaf: impl FnMut(usize) -> Result<Node, MerkleizationError>,
+    ) -> Result<Node, MerkleizationError> {
+        if self.layers.len() != depth + 1 {
+            self.layers = vec![vec![]; depth + 1];
+            self.dirty_leaves.fill(true);
+        }
+        let leaves = self
+            .dirty_leaves
+            .iter_ones()
+            .map(|i| Ok((i, leaf(i)?)))
+            .collect::<Result<Vec<_>, MerkleizationError>>()?;
+        metrics::record_chunks(leaves.len());
+
+        let mut dirty = leaves.iter().map(|(i, _)| *i).collect::<Vec<_>>();
+        let mut hasher = H::default();
+        for k in 0..=depth {
+            let (lower, upper) = self.layers.split_at_mut(k);
+            let layer = &mut upper[0];
+            let len = match lower.last() {
+                Some(children) => (children.len() + 1) / 2,
+                None => self.dirty_leaves.len(),
+            };
+            // the last node of a shrinking layer may have lost its sibling
+            if len < layer.len() && len % 2 == 1 {
+                dirty.push(len - 1);
+                dirty.dedup();
+            }
+            layer.resize(len, Node::default());
+            match lower.last() {
+                Some(children) => {
+                    for &i in &dirty {
+                        let right = match children.get(2 * i + 1) {
+                            Some(right) => *right,
+                            None => Node::zero_at_depth(k - 1, context),
+                        };
+                        let (left, out) = (children[2 * i].as_ref(), layer[i].as_mut());
+                        hash_nodes(&mut hasher, left, right.as_ref(), out);
+                    }
+                }
+                None => {
+                    for (i, node) in &leaves {
+                        layer[*i] = *node;
+                    }
+                }
+            }
+            dirty = dirty.iter().map(|i| i / 2).collect();
+            dirty.dedup();
+        }
+        Ok(self.layers[depth]
+            .first()
+            .copied()
+            .unwrap_or_else(|| Node::zero_at_depth(depth, context)))
+    }
+
+    /// Return the last root given to [`Cache::update`], or the zero node if there is none.
     pub fn root(&self) -> Node {
-        self.root
+        self.root.unwrap_or_default()
+    }
+
+    /// Encode the cache to persist it, e.g. across restarts of a service, to be restored with
+    /// [`Cache::from_bytes`].
+    ///
+    /// The encoding holds the leaf count, whether there is a root and the root (or the zero
+    /// node) as an SSZ integer, boolean and node, then the number of dirty leaves and one bit per
+    /// leaf, least significant bit first.
+    ///
+    /// A `List` or `Vector` is given a restored cache with `from_parts`, e.g.
+    /// [`List::from_parts`](crate::List::from_parts), to return its root without hashing it.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buffer = Vec::with_capacity(HEADER_LENGTH + (self.dirty_leaves.len() + 7) / 8);
+        let _ = self.leaf_count.serialize(&mut buffer).expect("can encode integer");
+        let _ = self.root.is_some().serialize(&mut buffer).expect("can encode boolean");
+        let _ = self.root().serialize(&mut buffer).expect("can encode node");
+        let _ = self.dirty_leaves.len().serialize(&mut buffer).expect("can encode integer");
+        let mut byte = 0u8;
+        for (i, dirty) in self.dirty_leaves.iter().enumerate() {
+            byte |= (*dirty as u8) << (i % 8);
+            if i % 8 == 7 {
+                buffer.push(byte);
+                byte = 0;
+            }
+        }
+        if self.dirty_leaves.len() % 8 != 0 {
+            buffer.push(byte);
+        }
+        buffer
+    }
+
+    /// Decode a cache encoded with [`Cache::to_bytes`].
+    pub fn from_bytes(encoding: &[u8]) -> Result<Self, DeserializeError> {
+        if encoding.len() < HEADER_LENGTH {
+            return Err(DeserializeError::ExpectedFurtherInput {
+                provided: encoding.len(),
+                expected: HEADER_LENGTH,
+            })
+        }
+        let (header, bits) = encoding.split_at(HEADER_LENGTH);
+        let leaf_count = usize::deserialize(&header[..8])?;
+        let has_root = bool::deserialize(&header[8..9])?;
+        let root = Node::deserialize(&header[9..9 + BYTES_PER_CHUNK])?;
+        // NOTE: the root must be zero without a root so the encoding is canonical
+        if !has_root && root != Node::default() {
+            return Err(DeserializeError::InvalidByte(header[8]))
+        }
+        let root = has_root.then_some(root);
+        let dirty_count = usize::deserialize(&header[9 + BYTES_PER_CHUNK..])?;
+
+        let expected = dirty_count / 8 + usize::from(dirty_count % 8 != 0);
+        if bits.len() < expected {
+            return Err(DeserializeError::ExpectedFurtherInput { provided: bits.len(), expected })
+        }
+        if bits.len() > expected {
+            return Err(DeserializeError::AdditionalInput { provided: bits.len(), expected })
+        }
+        if let Some(last) = bits.last() {
+            // NOTE: the bits past the last leaf must be zero so the encoding is canonical
+            if dirty_count % 8 != 0 && last >> (dirty_count % 8) != 0 {
+                return Err(DeserializeError::InvalidByte(*last))
+            }
+        }
+        let dirty_leaves = (0..dirty_count).map(|i| bits[i / 8] >> (i % 8) & 1 == 1).collect();
+        Ok(Self { leaf_count, dirty_leaves, root, layers: vec![] })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_persistence() {
+        let mut cache = Cache::with_leaves(11);
+        cache.update(Node::try_from([7u8; 32].as_ref()).unwrap());
+        cache.dirty_leaves.fill(false);
+        cache.invalidate(3);
+        cache.invalidate(10);
+        cache.resize(9);
+        let encoding = cache.to_bytes();
+        assert_eq!(encoding.len(), HEADER_LENGTH + 2);
+        assert_eq!(encoding[HEADER_LENGTH..], [0b1000, 0]);
+        let restored = Cache::from_bytes(&encoding).unwrap();
+        assert_eq!(restored, cache);
+        assert!(!restored.valid());
+
+        assert_eq!(Cache::from_bytes(&Cache::default().to_bytes()).unwrap(), Cache::default());
+        assert!(!Cache::with_leaves(0).valid());
+        let mut rootless = Cache::default().to_bytes();
+        rootless[9] = 1;
+        assert!(Cache::from_bytes(&rootless).is_err());
+        assert!(Cache::from_bytes(&encoding[..HEADER_LENGTH + 1]).is_err());
+        assert!(Cache::from_bytes(&[encoding.as_slice(), &[0]].concat()).is_err());
+        let mut stale = encoding;
+        stale[HEADER_LENGTH + 1] = 0b10;
+        assert!(Cache::from_bytes(&stale).is_err());
     }
 }
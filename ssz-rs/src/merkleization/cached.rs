@@ -0,0 +1,289 @@
+use crate::{
+    de::{Deserialize, DeserializeError},
+    error::Error,
+    lib::*,
+    merkleization::{
+        mix_in_selector, Context, GeneralizedIndex, MerkleHasher, MerkleizationError, Merkleized,
+        Node, PathElement, PathError,
+    },
+    ser::{Serialize, SerializeError},
+    union::Union,
+    SimpleSerialize, Sized,
+};
+
+/// A union of type `U` along with the root of the value of its selected variant once computed.
+///
+/// `Cached<U>` has the same SSZ representation and root as `U` but hashing it again only mixes
+/// the selector into the kept root of the variant until the union is accessed mutably, e.g. for a
+/// union with a large variant. The root is kept along with the selector and the hash function it
+/// was computed for, and only returned for the same selector and hash function.
+///
+/// ```rust
+/// use ssz_rs::{prelude::*, Cached};
+///
+/// #[derive(Debug, Default, PartialEq, Eq, SimpleSerialize)]
+/// enum Body {
+///     #[default]
+///     None,
+///     Payload(List<u64, 1024>),
+/// }
+///
+/// let payload = List::try_from((0..1000u64).collect::<Vec<_>>()).unwrap();
+/// let mut body = Cached::new(Body::Payload(payload));
+/// let root = body.hash_tree_root().unwrap();
+/// // only the selector is hashed again
+/// assert!(body.is_cached());
+/// assert_eq!(body.hash_tree_root().unwrap(), root);
+///
+/// if let Body::Payload(payload) = &mut *body {
+///     payload.push(7).unwrap();
+/// }
+/// assert!(!body.is_cached());
+/// assert_ne!(body.hash_tree_root().unwrap(), root);
+/// ```
+pub struct Cached<U: Union> {
+    value: U,
+    // the hash function, selector and root of the variant the root was computed for
+    root: Option<(TypeId, u8, Node)>,
+}
+
+impl<U: Union> Cached<U> {
+    pub fn new(value: U) -> Self {
+        Self { value, root: None }
+    }
+
+    pub fn into_inner(self) -> U {
+        self.value
+    }
+
+    /// Return `true` if the root of the value of the selected variant is kept.
+    pub fn is_cached(&self) -> bool {
+        matches!(self.root, Some((_, selector, _)) if selector == self.value.selector())
+    }
+
+    /// Drop the kept root, e.g. after changing the value through interior mutability.
+    pub fn invalidate(&mut self) {
+        self.root = None;
+    }
+
+    fn variant_root<H: MerkleHasher>(
+        &mut self,
+        context: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
+        let hasher = TypeId::of::<H>();
+        let selector = self.value.selector();
+        if let Some((cached_hasher, cached_selector, root)) = self.root {
+            if cached_hasher == hasher && cached_selector == selector {
+                return Ok(root)
+            }
+        }
+        let root = self.value.variant_hash_tree_root_with(context)?;
+        self.root = Some((hasher, selector, root));
+        Ok(root)
+    }
+}
+
+impl<U: Union> From<U> for Cached<U> {
+    fn from(value: U) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<U: Union> Deref for Cached<U> {
+    type Target = U;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<U: Union> DerefMut for Cached<U> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.invalidate();
+        &mut self.value
+    }
+}
+
+impl<U: Union + fmt::Debug> fmt::Debug for Cached<U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Cached").field("value", &self.value).field("root", &self.root).finish()
+    }
+}
+
+impl<U: Union + Clone> Clone for Cached<U> {
+    fn clone(&self) -> Self {
+        Self { value: self.value.clone(), root: self.root }
+    }
+}
+
+impl<U: Union + PartialEq> PartialEq for Cached<U> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<U: Union + Eq> Eq for Cached<U> {}
+
+impl<U: Union> Default for Cached<U> {
+    fn default() -> Self {
+        Self::new(U::default())
+    }
+}
+
+impl<U: Union> Sized for Cached<U> {
+    fn is_variable_size() -> bool {
+        U::is_variable_size()
+    }
+
+    fn size_hint() -> usize {
+        U::size_hint()
+    }
+}
+
+impl<U: Union> Serialize for Cached<U> {
+    fn serialize(&self, buffer: &mut Vec<u8>) -> Result<usize, SerializeError> {
+        self.value.serialize(buffer)
+    }
+}
+
+impl<U: Union> Deserialize for Cached<U> {
+    fn deserialize(encoding: &[u8]) -> Result<Self, DeserializeError> {
+        U::deserialize(encoding).map(Self::new)
+    }
+}
+
+impl<U: Union> Merkleized for Cached<U> {
+    fn hash_tree_root_with<H: MerkleHasher>(
+        &mut self,
+        context: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
+        let root = self.variant_root(context)?;
+        Ok(mix_in_selector(&root, self.value.selector() as usize, context))
+    }
+
+    fn leaves<H: MerkleHasher>(
+        &mut self,
+        context: &Context<H>,
+    ) -> Result<Vec<Node>, MerkleizationError> {
+        self.value.leaves(context)
+    }
+
+    const CHUNK_COUNT: usize = U::CHUNK_COUNT;
+
+    fn tree_depth() -> u32 {
+        U::tree_depth()
+    }
+
+    fn merkle_limit() -> Option<usize> {
+        U::merkle_limit()
+    }
+}
+
+impl<U: Union> SimpleSerialize for Cached<U> {
+    fn is_composite_type() -> bool {
+        U::is_composite_type()
+    }
+
+    fn nesting_depth() -> usize {
+        U::nesting_depth()
+    }
+
+    fn ssz_heap_size(&self) -> usize {
+        self.value.ssz_heap_size()
+    }
+
+    fn hash_tree_root_from_bytes<H: MerkleHasher>(
+        encoding: &[u8],
+        context: &Context<H>,
+    ) -> Result<Node, Error> {
+        U::hash_tree_root_from_bytes(encoding, context)
+    }
+
+    const MAX_SIZE: usize = U::MAX_SIZE;
+
+    const IS_VARIABLE_SIZE: bool = U::IS_VARIABLE_SIZE;
+
+    const NESTING_DEPTH: usize = U::NESTING_DEPTH;
+
+    fn generalized_index_from(
+        root: GeneralizedIndex,
+        path: &[PathElement<'_>],
+    ) -> Result<GeneralizedIndex, PathError> {
+        U::generalized_index_from(root, path)
+    }
+}
+
+impl<U: Union> Union for Cached<U> {
+    const VARIANT_COUNT: usize = U::VARIANT_COUNT;
+
+    fn selector(&self) -> u8 {
+        self.value.selector()
+    }
+
+    fn variant_name(selector: u8) -> Option<&'static str> {
+        U::variant_name(selector)
+    }
+
+    fn variant_hash_tree_root_with<H: MerkleHasher>(
+        &mut self,
+        context: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
+        self.variant_root(context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{list::List, serialize};
+
+    #[test]
+    fn test_cached_root() {
+        let list = List::<u16, 64>::try_from(vec![1u16, 2, 3]).unwrap();
+        let mut cached = Cached::new(Some(list.clone()));
+        assert_eq!(serialize(&cached).unwrap(), serialize(&Some(list.clone())).unwrap());
+        assert!(!cached.is_cached());
+
+        let root = cached.hash_tree_root().unwrap();
+        assert!(cached.is_cached());
+        assert_eq!(root, Some(list.clone()).hash_tree_root().unwrap());
+        assert_eq!(
+            cached.variant_hash_tree_root_with(Context::global()).unwrap(),
+            list.clone().hash_tree_root().unwrap()
+        );
+        assert_eq!(cached.as_ref().map(|list| list.len()), Some(3));
+        assert!(cached.is_cached());
+        // the root is kept for the selected variant only
+        assert!(matches!(cached.root, Some((_, 1, _))));
+
+        cached.as_mut().unwrap().push(4).unwrap();
+        assert!(!cached.is_cached());
+        let mut expected = cached.clone().into_inner();
+        assert_eq!(cached.hash_tree_root().unwrap(), expected.hash_tree_root().unwrap());
+
+        *cached = None;
+        assert!(!cached.is_cached());
+        assert_eq!(
+            cached.hash_tree_root().unwrap(),
+            None::<List<u16, 64>>.hash_tree_root().unwrap()
+        );
+        assert!(matches!(cached.root, Some((_, 0, _))));
+        *cached = expected.clone();
+
+        // a root kept for one hash function is not returned for another
+        #[derive(Default)]
+        struct Reversed(crate::Sha256Hasher);
+
+        impl MerkleHasher for Reversed {
+            fn hash_nodes(&mut self, left: &[u8], right: &[u8], out: &mut [u8]) {
+                self.0.hash_nodes(right, left, out);
+            }
+        }
+
+        let context = Context::<Reversed>::new();
+        let reversed = expected.hash_tree_root_with(&context).unwrap();
+        assert_ne!(reversed, expected.hash_tree_root().unwrap());
+        assert_eq!(cached.hash_tree_root().unwrap(), expected.hash_tree_root().unwrap());
+        assert_eq!(cached.hash_tree_root_with(&context).unwrap(), reversed);
+    }
+}
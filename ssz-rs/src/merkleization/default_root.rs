@@ -0,0 +1,418 @@
+//! The roots of default values computed at compile time, with an implementation of SHA-256 in
+//! `const fn`s.
+use crate::{
+    bitlist::Bitlist,
+    bitvector::Bitvector,
+    lib::*,
+    list::List,
+    merkleization::{
+        chunks_for_bits, chunks_for_list, depth_for_chunks, MerkleHasher, MerkleizationError, Node,
+        BYTES_PER_CHUNK,
+    },
+    uint::U256,
+    utf8_list::Utf8List,
+    vector::Vector,
+    SimpleSerialize,
+};
+use core::{
+    cell::UnsafeCell,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+/// The hash tree root of the default value of a type as a constant, hashing with SHA-256.
+///
+/// Unlike [`SimpleSerialize::default_hash_tree_root_with`], this costs nothing at runtime, e.g.
+/// comparing a root with `DEFAULT_ROOT` tells if a subtree is still freshly initialized.
+/// `DefaultRoot` is implemented for containers by `#[derive(SimpleSerialize)]` with
+/// `#[ssz(default_root)]`.
+///
+/// ```rust
+/// use ssz_rs::prelude::*;
+///
+/// #[derive(Default, Debug, SimpleSerialize)]
+/// #[ssz(default_root)]
+/// struct Checkpoint {
+///     epoch: u64,
+///     root: Node,
+/// }
+///
+/// const EMPTY_CHECKPOINT: Node = Checkpoint::DEFAULT_ROOT;
+/// assert_eq!(EMPTY_CHECKPOINT, Checkpoint::default().hash_tree_root().unwrap());
+/// ```
+pub trait DefaultRoot {
+    const DEFAULT_ROOT: Node;
+
+    /// The size of the encoding of a basic type, which is packed into chunks with the other
+    /// elements of a collection, or `0` for a composite type.
+    const PACKED_SIZE: usize = 0;
+}
+
+const ROUND_CONSTANTS: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+const INITIAL_STATE: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+// Apply the SHA-256 compression function to `state` and the 64 bytes of `block`.
+const fn compress(state: [u32; 8], block: &[u8; 64]) -> [u32; 8] {
+    let mut schedule = [0u32; 64];
+    let mut i = 0;
+    while i < 16 {
+        let j = 4 * i;
+        schedule[i] = u32::from_be_bytes([block[j], block[j + 1], block[j + 2], block[j + 3]]);
+        i += 1;
+    }
+    while i < 64 {
+        let w15 = schedule[i - 15];
+        let w2 = schedule[i - 2];
+        let s0 = w15.rotate_right(7) ^ w15.rotate_right(18) ^ (w15 >> 3);
+        let s1 = w2.rotate_right(17) ^ w2.rotate_right(19) ^ (w2 >> 10);
+        schedule[i] =
+            schedule[i - 16].wrapping_add(s0).wrapping_add(schedule[i - 7]).wrapping_add(s1);
+        i += 1;
+    }
+
+    let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+    let mut i = 0;
+    while i < 64 {
+        let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+        let choice = (e & f) ^ (!e & g);
+        let t1 = h
+            .wrapping_add(s1)
+            .wrapping_add(choice)
+            .wrapping_add(ROUND_CONSTANTS[i])
+            .wrapping_add(schedule[i]);
+        let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+        let majority = (a & b) ^ (a & c) ^ (b & c);
+        let t2 = s0.wrapping_add(majority);
+        h = g;
+        g = f;
+        f = e;
+        e = d.wrapping_add(t1);
+        d = c;
+        c = b;
+        b = a;
+        a = t1.wrapping_add(t2);
+        i += 1;
+    }
+    [
+        state[0].wrapping_add(a),
+        state[1].wrapping_add(b),
+        state[2].wrapping_add(c),
+        state[3].wrapping_add(d),
+        state[4].wrapping_add(e),
+        state[5].wrapping_add(f),
+        state[6].wrapping_add(g),
+        state[7].wrapping_add(h),
+    ]
+}
+
+/// Return the SHA-256 hash of `left` followed by `right`, i.e. their parent in a Merkle tree.
+const fn hash_pair(left: &Node, right: &Node) -> Node {
+    let mut block = [0u8; 64];
+    let mut i = 0;
+    while i < BYTES_PER_CHUNK {
+        block[i] = left.0[i];
+        block[BYTES_PER_CHUNK + i] = right.0[i];
+        i += 1;
+    }
+    let state = compress(INITIAL_STATE, &block);
+    // NOTE: the padding of a message of 64 bytes, ending with its length of 512 bits
+    let mut padding = [0u8; 64];
+    padding[0] = 0x80;
+    padding[62] = 0x02;
+    let state = compress(state, &padding);
+
+    let mut digest = [0u8; BYTES_PER_CHUNK];
+    let mut i = 0;
+    while i < 8 {
+        let word = state[i].to_be_bytes();
+        digest[4 * i] = word[0];
+        digest[4 * i + 1] = word[1];
+        digest[4 * i + 2] = word[2];
+        digest[4 * i + 3] = word[3];
+        i += 1;
+    }
+    Node(digest)
+}
+
+/// Return the root of a Merkle tree of `depth` with only zero leaves.
+const fn zero_root(depth: u32) -> Node {
+    let mut root = Node([0u8; BYTES_PER_CHUNK]);
+    let mut i = 0;
+    while i < depth {
+        root = hash_pair(&root, &root);
+        i += 1;
+    }
+    root
+}
+
+/// Return the root of a Merkle tree of `depth` whose first `count` leaves are `leaf` and
+/// whose other leaves are zero.
+const fn repeated_root(leaf: Node, count: usize, depth: u32) -> Node {
+    if count == 0 {
+        return zero_root(depth)
+    }
+    // all the nodes of a layer are `full` but the last one, which is `last`
+    let mut full = leaf;
+    let mut last = leaf;
+    let mut count = count;
+    let mut zero = Node([0u8; BYTES_PER_CHUNK]);
+    let mut i = 0;
+    while i < depth {
+        last = if count % 2 == 1 { hash_pair(&last, &zero) } else { hash_pair(&full, &last) };
+        full = hash_pair(&full, &full);
+        count = count / 2 + count % 2;
+        zero = hash_pair(&zero, &zero);
+        i += 1;
+    }
+    last
+}
+
+/// Return the root of a Merkle tree of the `leaves` padded with zero leaves to a power of two.
+pub const fn merkleize_roots<const K: usize>(leaves: [Node; K]) -> Node {
+    let mut layer = leaves;
+    let mut count = K;
+    let mut zero = Node([0u8; BYTES_PER_CHUNK]);
+    if count == 0 {
+        return zero
+    }
+    while count > 1 {
+        let mut i = 0;
+        while i < count {
+            let right = if i + 1 < count { layer[i + 1] } else { zero };
+            layer[i / 2] = hash_pair(&layer[i], &right);
+            i += 2;
+        }
+        count = count / 2 + count % 2;
+        zero = hash_pair(&zero, &zero);
+    }
+    layer[0]
+}
+
+// Return the root of an empty collection whose elements take `chunks` chunks at most.
+const fn empty_collection_root(chunks: usize) -> Node {
+    hash_pair(&zero_root(depth_for_chunks(chunks)), &Node([0u8; BYTES_PER_CHUNK]))
+}
+
+macro_rules! impl_default_root_for_basic {
+    ($($ty:ty),*) => {
+        $(
+            impl DefaultRoot for $ty {
+                const DEFAULT_ROOT: Node = Node([0u8; BYTES_PER_CHUNK]);
+                const PACKED_SIZE: usize = mem::size_of::<$ty>();
+            }
+        )*
+    };
+}
+
+impl_default_root_for_basic!(bool, u8, u16, u32, u64, u128);
+
+impl DefaultRoot for usize {
+    const DEFAULT_ROOT: Node = Node([0u8; BYTES_PER_CHUNK]);
+    // NOTE: a `usize` is encoded as a `u64` on every target
+    const PACKED_SIZE: usize = 8;
+}
+
+impl DefaultRoot for U256 {
+    const DEFAULT_ROOT: Node = Node([0u8; BYTES_PER_CHUNK]);
+    const PACKED_SIZE: usize = 32;
+}
+
+impl DefaultRoot for Node {
+    const DEFAULT_ROOT: Node = Node([0u8; BYTES_PER_CHUNK]);
+}
+
+// Return the root of a vector of `length` default elements of type `T`.
+const fn vector_root<T: DefaultRoot>(length: usize) -> Node {
+    if T::PACKED_SIZE > 0 {
+        zero_root(depth_for_chunks(chunks_for_list(length, T::PACKED_SIZE)))
+    } else {
+        repeated_root(T::DEFAULT_ROOT, length, depth_for_chunks(length))
+    }
+}
+
+impl<T: SimpleSerialize + DefaultRoot, const N: usize> DefaultRoot for Vector<T, N> {
+    const DEFAULT_ROOT: Node = vector_root::<T>(N);
+}
+
+impl<T: DefaultRoot, const N: usize> DefaultRoot for [T; N] {
+    const DEFAULT_ROOT: Node = vector_root::<T>(N);
+}
+
+impl<T: SimpleSerialize + DefaultRoot, const N: usize> DefaultRoot for List<T, N> {
+    const DEFAULT_ROOT: Node = if T::PACKED_SIZE > 0 {
+        empty_collection_root(chunks_for_list(N, T::PACKED_SIZE))
+    } else {
+        empty_collection_root(N)
+    };
+}
+
+impl<const N: usize> DefaultRoot for Bitvector<N> {
+    const DEFAULT_ROOT: Node = zero_root(depth_for_chunks(chunks_for_bits(N)));
+}
+
+impl<const N: usize> DefaultRoot for Bitlist<N> {
+    const DEFAULT_ROOT: Node = empty_collection_root(chunks_for_bits(N));
+}
+
+impl<const N: usize> DefaultRoot for Utf8List<N> {
+    const DEFAULT_ROOT: Node = empty_collection_root(chunks_for_list(N, 1));
+}
+
+impl<T: SimpleSerialize> DefaultRoot for Option<T> {
+    // the root of the `None` variant mixed with its selector 0
+    const DEFAULT_ROOT: Node = zero_root(1);
+}
+
+const EMPTY: u8 = 0;
+const WRITING: u8 = 1;
+const READY: u8 = 2;
+
+/// The default root of a type, computed on first use at runtime and kept for the hash function
+/// it was first computed with.
+///
+/// `#[derive(SimpleSerialize)]` keeps one in a `static` in the `default_hash_tree_root_with` of
+/// each non-generic container, so the roots of its default fields are only hashed once.
+pub struct RootMemo {
+    state: AtomicU8,
+    entry: UnsafeCell<mem::MaybeUninit<(TypeId, Node)>>,
+}
+
+// SAFETY: `entry` is written once, by the thread moving `state` from `EMPTY` to `WRITING`, and
+// only read after `state` is `READY`
+unsafe impl Sync for RootMemo {}
+
+impl RootMemo {
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self { state: AtomicU8::new(EMPTY), entry: UnsafeCell::new(mem::MaybeUninit::uninit()) }
+    }
+
+    /// Return the memoized root for the hasher `H`, or compute it with `compute`, keeping it
+    /// if no root is memoized yet.
+    pub fn get_or_try_init<H: MerkleHasher>(
+        &self,
+        compute: impl FnOnce() -> Result<Node, MerkleizationError>,
+    ) -> Result<Node, MerkleizationError> {
+        let hasher = TypeId::of::<H>();
+        if self.state.load(Ordering::Acquire) == READY {
+            // SAFETY: the entry was written before `state` became `READY`
+            let (memo_hasher, root) = unsafe { (*self.entry.get()).assume_init() };
+            if memo_hasher == hasher {
+                return Ok(root)
+            }
+            return compute()
+        }
+        let root = compute()?;
+        if self.state.compare_exchange(EMPTY, WRITING, Ordering::Acquire, Ordering::Relaxed).is_ok()
+        {
+            // SAFETY: only this thread moved `state` to `WRITING` and nothing reads the entry
+            // before it is `READY`
+            unsafe { (*self.entry.get()).write((hasher, root)) };
+            self.state.store(READY, Ordering::Release);
+        }
+        Ok(root)
+    }
+}
+
+impl fmt::Debug for RootMemo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RootMemo")
+            .field("ready", &(self.state.load(Ordering::Acquire) == READY))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        merkleization::{merkleize, Context},
+        prelude::*,
+        test_utils::Validator,
+        MerkleHasher, Sha256Hasher,
+    };
+
+    #[derive(Default, Debug, SimpleSerialize)]
+    #[ssz(default_root, max_depth = 4)]
+    struct State<const N: usize> {
+        slot: u64,
+        validators: List<Validator, N>,
+        committee: Vector<Validator, 3>,
+        balances: List<u64, 1024>,
+        bits: Bitvector<300>,
+        participation: Bitlist<2048>,
+        graffiti: Utf8List<32>,
+        option: Option<u32>,
+        big: U256,
+        roots: Vector<Node, 5>,
+    }
+
+    #[test]
+    fn test_hash_pair() {
+        let left = Node::try_from(&[0x11; 32][..]).unwrap();
+        let right = Node::try_from(&(0..32).collect::<Vec<u8>>()[..]).unwrap();
+        let mut expected = [0u8; 32];
+        Sha256Hasher::default().hash_nodes(left.as_ref(), right.as_ref(), &mut expected);
+        assert_eq!(hash_pair(&left, &right).as_ref(), expected);
+
+        for depth in 0..8 {
+            assert_eq!(zero_root(depth), Node::zero_at_depth(depth as usize, Context::global()));
+        }
+    }
+
+    #[test]
+    fn test_default_root() {
+        fn check<T: SimpleSerialize + DefaultRoot>() {
+            assert_eq!(T::DEFAULT_ROOT, T::default().hash_tree_root().unwrap());
+            assert_eq!(T::DEFAULT_ROOT, T::default_hash_tree_root_with(Context::global()).unwrap());
+        }
+        check::<u8>();
+        check::<u128>();
+        check::<bool>();
+        check::<U256>();
+        check::<Node>();
+        check::<Vector<u16, 33>>();
+        check::<Vector<Node, 3>>();
+        check::<Vector<Vector<u8, 3>, 7>>();
+        check::<[u32; 9]>();
+        check::<[Vector<u8, 33>; 3]>();
+        check::<List<u8, 1>>();
+        check::<List<usize, 9>>();
+        check::<List<List<u8, 3>, 9>>();
+        assert_eq!(usize::PACKED_SIZE, usize::size_hint());
+        check::<Bitvector<1>>();
+        check::<Bitlist<513>>();
+        check::<Option<Node>>();
+        check::<Validator>();
+        check::<State<0>>();
+        check::<State<5>>();
+        check::<State<1099511627776>>();
+
+        for count in 0..20 {
+            let leaf = hash_pair(&Node::default(), &Node::default());
+            let leaves = vec![leaf; count];
+            let mut chunks =
+                leaves.iter().flat_map(|leaf| leaf.as_ref().to_vec()).collect::<Vec<_>>();
+            if chunks.is_empty() {
+                chunks = vec![0; 32];
+            }
+            let expected = merkleize(&chunks, Some(32)).unwrap();
+            assert_eq!(repeated_root(leaf, count, 5), expected);
+        }
+        assert_eq!(merkleize_roots([]), Node::default());
+        let leaves = [Validator::DEFAULT_ROOT, Node::default(), zero_root(3)];
+        let chunks = leaves.iter().flat_map(|leaf| leaf.as_ref().to_vec()).collect::<Vec<_>>();
+        assert_eq!(merkleize_roots(leaves), merkleize(&chunks, None).unwrap());
+    }
+}
@@ -0,0 +1,270 @@
+//! Generalized indices locate a node in the Merkle tree of a value: the root has generalized
+//! index 1 and the children of the node at `i` are at `2 * i` and `2 * i + 1`, see the
+//! [SSZ spec](https://github.com/ethereum/consensus-specs/blob/dev/ssz/merkle-proofs.md).
+//!
+//! The generalized index of a node is resolved from a path of field names, element indices and
+//! the lengths of lists, composed through the nested types of the path, e.g.
+//! `validators[3].withdrawal_credentials`:
+//!
+//! ```rust
+//! use ssz_rs::{prelude::*, PathElement};
+//!
+//! #[derive(Default, Debug, SimpleSerialize)]
+//! struct Validator {
+//!     pubkey: Vector<u8, 48>,
+//!     withdrawal_credentials: Node,
+//!     effective_balance: u64,
+//!     slashed: bool,
+//! }
+//!
+//! #[derive(Default, Debug, SimpleSerialize)]
+//! struct State {
+//!     slot: u64,
+//!     validators: List<Validator, 1024>,
+//!     balances: List<u64, 1024>,
+//! }
+//!
+//! let path = ["validators".into(), 3.into(), "withdrawal_credentials".into()];
+//! // the field `validators` is at 4 + 1, its elements at 5 * 2 * 1024 + i and the field
+//! // `withdrawal_credentials` of an element at 4 * (5 * 2 * 1024 + 3) + 1
+//! assert_eq!(State::generalized_index(&path), Ok(4 * (5 * 2 * 1024 + 3) + 1));
+//! // four balances are packed into each chunk
+//! assert_eq!(State::generalized_index(&["balances".into(), 5.into()]), Ok(6 * 2 * 256 + 1));
+//! assert_eq!(State::generalized_index(&["balances".into(), PathElement::Length]), Ok(6 * 2 + 1));
+//! assert!(State::generalized_index(&["slot".into(), 0.into()]).is_err());
+//! ```
+use crate::{
+    error::ErrorKind,
+    lib::*,
+    merkleization::{layout::BITS_PER_CHUNK, BYTES_PER_CHUNK},
+    SimpleSerialize,
+};
+
+/// The index of a node in a Merkle tree, where the root is at 1 and the children of the node at
+/// `i` are at `2 * i` and `2 * i + 1`.
+pub type GeneralizedIndex = usize;
+
+/// An element of a path to a node in the Merkle tree of a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathElement<'a> {
+    /// The field of a container with this name.
+    Field(&'a str),
+    /// The element of a collection, or the bit of a bitfield, at this index, or the value of the
+    /// variant of a union with this selector.
+    Index(usize),
+    /// The length mixed into the root of a list.
+    Length,
+    /// The selector mixed into the root of a union.
+    Selector,
+}
+
+impl<'a> From<&'a str> for PathElement<'a> {
+    fn from(name: &'a str) -> Self {
+        Self::Field(name)
+    }
+}
+
+impl From<usize> for PathElement<'_> {
+    fn from(index: usize) -> Self {
+        Self::Index(index)
+    }
+}
+
+impl Display for PathElement<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Field(name) => write!(f, ".{name}"),
+            Self::Index(index) => write!(f, "[{index}]"),
+            Self::Length => write!(f, ".len()"),
+            Self::Selector => write!(f, ".selector()"),
+        }
+    }
+}
+
+/// An error resolving a path to a node in the Merkle tree of a value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathError {
+    /// The element of the path does not exist in the type, e.g. an unknown field or an index
+    /// into a basic type.
+    InvalidElement { element: String, type_name: &'static str },
+    /// The index is past the bound of the collection (`index >= bound`).
+    IndexOutOfBounds { index: usize, bound: usize },
+    /// The generalized index does not fit in a `usize`.
+    Overflow,
+}
+
+impl PathError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::InvalidElement { .. } => ErrorKind::InvalidType,
+            Self::IndexOutOfBounds { .. } | Self::Overflow => ErrorKind::Bounds,
+        }
+    }
+}
+
+impl Display for PathError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidElement { element, type_name } => {
+                write!(f, "the path element `{element}` does not exist in `{type_name}`")
+            }
+            Self::IndexOutOfBounds { index, bound } => {
+                write!(f, "index {index} is out of bounds for a collection with bound {bound}")
+            }
+            Self::Overflow => write!(f, "the generalized index does not fit in a `usize`"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PathError {}
+
+/// Return the error for the path `element` which does not exist in the type `T`.
+pub fn invalid_path_element<T>(element: &PathElement<'_>) -> PathError {
+    PathError::InvalidElement { element: format!("{element}"), type_name: any::type_name::<T>() }
+}
+
+/// Return the generalized index of the node at `position` among the nodes `depth` levels below
+/// the node at `root`.
+pub fn child_generalized_index(
+    root: GeneralizedIndex,
+    depth: u32,
+    position: usize,
+) -> Result<GeneralizedIndex, PathError> {
+    1usize
+        .checked_shl(depth)
+        .and_then(|width| root.checked_mul(width))
+        .and_then(|index| index.checked_add(position))
+        .ok_or(PathError::Overflow)
+}
+
+// Resolve `path` in the collection `C` of at most `bound` elements of type `T` whose tree of
+// `depth` is rooted at `root`, with the length mixed in for lists.
+pub(crate) fn elements_generalized_index<C, T: SimpleSerialize>(
+    root: GeneralizedIndex,
+    path: &[PathElement<'_>],
+    bound: usize,
+    depth: u32,
+    is_list: bool,
+) -> Result<GeneralizedIndex, PathError> {
+    let (element, rest) = match path.split_first() {
+        Some(split) => split,
+        None => return Ok(root),
+    };
+    match element {
+        PathElement::Index(index) => {
+            if *index >= bound {
+                return Err(PathError::IndexOutOfBounds { index: *index, bound })
+            }
+            let root = if is_list { child_generalized_index(root, 1, 0)? } else { root };
+            let position = if T::is_composite_type() {
+                *index
+            } else {
+                // NOTE: basic elements are packed into chunks
+                index.checked_mul(T::size_hint()).ok_or(PathError::Overflow)? / BYTES_PER_CHUNK
+            };
+            T::generalized_index_from(child_generalized_index(root, depth, position)?, rest)
+        }
+        PathElement::Length if is_list => {
+            u64::generalized_index_from(child_generalized_index(root, 1, 1)?, rest)
+        }
+        element => Err(invalid_path_element::<C>(element)),
+    }
+}
+
+// Resolve `path` in the bitfield `C` of at most `bound` bits whose tree of `depth` is rooted at
+// `root`, with the length mixed in for bitlists.
+pub(crate) fn bits_generalized_index<C>(
+    root: GeneralizedIndex,
+    path: &[PathElement<'_>],
+    bound: usize,
+    depth: u32,
+    is_list: bool,
+) -> Result<GeneralizedIndex, PathError> {
+    let (element, rest) = match path.split_first() {
+        Some(split) => split,
+        None => return Ok(root),
+    };
+    let index = match element {
+        PathElement::Index(index) => {
+            if *index >= bound {
+                return Err(PathError::IndexOutOfBounds { index: *index, bound })
+            }
+            let root = if is_list { child_generalized_index(root, 1, 0)? } else { root };
+            child_generalized_index(root, depth, index / BITS_PER_CHUNK)?
+        }
+        PathElement::Length if is_list => child_generalized_index(root, 1, 1)?,
+        element => return Err(invalid_path_element::<C>(element)),
+    };
+    bool::generalized_index_from(index, rest)
+}
+
+/// Resolve `path` in the union `T` rooted at `root`, resolving the rest of the path after an
+/// index with `value` given the selector and the generalized index of the value.
+pub fn union_generalized_index<T>(
+    root: GeneralizedIndex,
+    path: &[PathElement<'_>],
+    value: impl FnOnce(
+        usize,
+        GeneralizedIndex,
+        &[PathElement<'_>],
+    ) -> Result<GeneralizedIndex, PathError>,
+) -> Result<GeneralizedIndex, PathError> {
+    match path.split_first() {
+        None => Ok(root),
+        Some((PathElement::Index(selector), rest)) => {
+            value(*selector, child_generalized_index(root, 1, 0)?, rest)
+        }
+        Some((PathElement::Selector, rest)) => {
+            u8::generalized_index_from(child_generalized_index(root, 1, 1)?, rest)
+        }
+        Some((element, _)) => Err(invalid_path_element::<T>(element)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn test_generalized_index_of_collections() {
+        type Bytes = List<u8, 64>;
+        assert_eq!(Bytes::generalized_index(&[]), Ok(1));
+        assert_eq!(Bytes::generalized_index(&[PathElement::Length]), Ok(3));
+        assert_eq!(Bytes::generalized_index(&[0.into()]), Ok(4));
+        assert_eq!(Bytes::generalized_index(&[33.into()]), Ok(5));
+        assert_eq!(
+            Bytes::generalized_index(&[64.into()]),
+            Err(PathError::IndexOutOfBounds { index: 64, bound: 64 })
+        );
+        assert!(Bytes::generalized_index(&[0.into(), 0.into()]).is_err());
+        assert!(Bytes::generalized_index(&["len".into()]).is_err());
+
+        type Nested = Vector<List<u16, 32>, 4>;
+        assert_eq!(Nested::generalized_index(&[2.into()]), Ok(6));
+        assert_eq!(Nested::generalized_index(&[2.into(), 17.into()]), Ok(6 * 2 * 2 + 1));
+        assert_eq!(Nested::generalized_index(&[3.into(), PathElement::Length]), Ok(7 * 2 + 1));
+        assert!(Nested::generalized_index(&[3.into(), PathElement::Selector]).is_err());
+        assert_eq!(<[u64; 8]>::generalized_index(&[7.into()]), Ok(3));
+
+        assert_eq!(Bitlist::<512>::generalized_index(&[300.into()]), Ok(2 * 2 + 1));
+        assert_eq!(Bitlist::<512>::generalized_index(&[PathElement::Length]), Ok(3));
+        assert_eq!(Bitvector::<512>::generalized_index(&[300.into()]), Ok(3));
+        assert!(Bitvector::<512>::generalized_index(&[PathElement::Length]).is_err());
+
+        assert_eq!(Option::<u64>::generalized_index(&[PathElement::Selector]), Ok(3));
+        assert_eq!(Option::<u64>::generalized_index(&[1.into()]), Ok(2));
+        assert_eq!(
+            Option::<List<u64, 8>>::generalized_index(&[1.into(), 5.into()]),
+            Ok(2 * 2 * 2 + 1)
+        );
+    }
+
+    #[test]
+    fn test_generalized_index_overflow() {
+        type Deep = List<List<List<u8, { 1 << 30 }>, { 1 << 30 }>, { 1 << 30 }>;
+        let path = [1.into(), 1.into(), 1.into()];
+        assert_eq!(Deep::generalized_index(&path), Err(PathError::Overflow));
+    }
+}
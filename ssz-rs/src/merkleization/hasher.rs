@@ -0,0 +1,64 @@
+use crate::{merkleization::BYTES_PER_CHUNK, metrics};
+use sha2::{Digest, Sha256};
+
+/// A `MerkleHasher` is the hash function used to merkleize SSZ data.
+///
+/// The SSZ spec uses SHA-256 (provided here as [`Sha256Hasher`]) but deployments outside
+/// of Ethereum may commit to SSZ data with a different 32-byte hash function,
+/// e.g. Keccak-256 or Blake2s. Implement this trait for the hash function and merkleize
+/// with a [`Context`](crate::MerkleizationContext) built for it.
+pub trait MerkleHasher: Default + 'static {
+    /// Write the hash of the concatenation of `left` and `right` into `out`.
+    ///
+    /// `left` and `right` are each one chunk (32 bytes) and `out` is one chunk long.
+    fn hash_nodes(&mut self, left: &[u8], right: &[u8], out: &mut [u8]);
+
+    /// Write the hash of each pair of nodes in `pairs`, 64 bytes each, into the chunk of `out`
+    /// at the same index, so `out` is half as long as `pairs`.
+    ///
+    /// Merkleization hashes each layer of a Merkle tree in one call, so hash functions with a
+    /// faster path for many inputs, e.g. SIMD or a device, should override it. The default
+    /// hashes the pairs one by one with [`MerkleHasher::hash_nodes`].
+    fn hash_pairs(&mut self, pairs: &[u8], out: &mut [u8]) {
+        let parents = out.chunks_exact_mut(BYTES_PER_CHUNK);
+        for (pair, parent) in pairs.chunks_exact(2 * BYTES_PER_CHUNK).zip(parents) {
+            self.hash_nodes(&pair[..BYTES_PER_CHUNK], &pair[BYTES_PER_CHUNK..], parent);
+        }
+    }
+}
+
+/// The SHA-256 hash function, as specified by SSZ.
+#[derive(Default, Debug, Clone)]
+pub struct Sha256Hasher(Sha256);
+
+impl MerkleHasher for Sha256Hasher {
+    fn hash_nodes(&mut self, left: &[u8], right: &[u8], out: &mut [u8]) {
+        self.0.update(left);
+        self.0.update(right);
+        out.copy_from_slice(&self.0.finalize_reset());
+    }
+}
+
+/// Write the hash of `left` and `right` into `out` with `hasher`, counting it in the
+/// [`metrics`](crate::metrics).
+///
+/// Every hash of two nodes in this crate goes through here, so the counters see all of them.
+#[inline]
+pub(crate) fn hash_nodes<H: MerkleHasher>(
+    hasher: &mut H,
+    left: &[u8],
+    right: &[u8],
+    out: &mut [u8],
+) {
+    metrics::record_hashes(1);
+    hasher.hash_nodes(left, right, out);
+}
+
+/// Write the hashes of the pairs of nodes in `pairs` into `out` with `hasher`, counting them in
+/// the [`metrics`](crate::metrics).
+#[inline]
+pub(crate) fn hash_pairs<H: MerkleHasher>(hasher: &mut H, pairs: &[u8], out: &mut [u8]) {
+    debug_assert!(pairs.len() == 2 * out.len() && out.len() % BYTES_PER_CHUNK == 0);
+    metrics::record_hashes(out.len() / BYTES_PER_CHUNK);
+    hasher.hash_pairs(pairs, out);
+}
@@ -0,0 +1,470 @@
+use crate::{
+    lib::*,
+    merkleization::{
+        hash_nodes, Context, MerkleHasher, MerkleizationError, Node, Proof, Sha256Hasher,
+    },
+};
+
+/// An append-only Merkle tree of a fixed `depth`, e.g. the tree maintained by the
+/// Ethereum deposit contract.
+///
+/// As in the deposit contract, the tree keeps the branch of the last complete subtree at each
+/// height so appending a leaf hashes each new complete subtree once and the root is computed
+/// in `O(depth)` hashes. The complete subtrees are kept as well so a proof costs `O(depth)`.
+///
+/// A prefix of the leaves can be "finalized" which drops them from memory and keeps
+/// only the roots of the complete subtrees covering them. Proofs can only be produced
+/// for leaves that have not been finalized.
+pub struct IncrementalMerkleTree<H: MerkleHasher = Sha256Hasher> {
+    depth: usize,
+    // roots of the complete subtrees covering the finalized leaves, largest first
+    finalized: Vec<Node>,
+    finalized_count: usize,
+    // roots of the complete subtrees at each height, from the first one which is not
+    // finalized, i.e. the leaves at height 0
+    layers: Vec<Vec<Node>>,
+    frontier: Frontier<H>,
+}
+
+impl IncrementalMerkleTree {
+    /// Construct an empty tree with capacity for `2**depth` leaves.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `depth` is not less than `usize::BITS`, as the capacity would overflow `usize`.
+    pub fn new(depth: usize) -> Self {
+        Self::new_with(depth, Context::global())
+    }
+
+    /// Construct a tree from the roots of the complete subtrees covering the first
+    /// `finalized_count` leaves, ordered from left to right.
+    ///
+    /// Returns `None` if the number of roots does not match `finalized_count` or if `depth` is
+    /// not less than `usize::BITS`.
+    pub fn from_finalized(
+        depth: usize,
+        finalized: Vec<Node>,
+        finalized_count: usize,
+    ) -> Option<Self> {
+        Self::from_finalized_with(depth, finalized, finalized_count, Context::global())
+    }
+}
+
+impl<H: MerkleHasher> IncrementalMerkleTree<H> {
+    /// Construct an empty tree with capacity for `2**depth` leaves, hashing with the hash
+    /// function of `context`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `depth` is not less than `usize::BITS`, as the capacity would overflow `usize`.
+    pub fn new_with(depth: usize, context: &Context<H>) -> Self {
+        assert!(depth < usize::BITS as usize, "tree depth must be less than the pointer width");
+        Self {
+            depth,
+            finalized: vec![],
+            finalized_count: 0,
+            layers: vec![vec![]; depth + 1],
+            frontier: Frontier::new(depth, context),
+        }
+    }
+
+    /// Construct a tree as [`IncrementalMerkleTree::from_finalized`], hashing with the hash
+    /// function of `context`.
+    pub fn from_finalized_with(
+        depth: usize,
+        finalized: Vec<Node>,
+        finalized_count: usize,
+        context: &Context<H>,
+    ) -> Option<Self> {
+        if depth >= usize::BITS as usize {
+            return None
+        }
+        let mut tree = Self::new_with(depth, context);
+        if finalized_count > tree.capacity() ||
+            finalized.len() != finalized_count.count_ones() as usize
+        {
+            return None
+        }
+        for (root, (height, _)) in finalized.iter().zip(finalized_subtrees(finalized_count, depth))
+        {
+            tree.frontier.branch[height] = *root;
+        }
+        tree.finalized = finalized;
+        tree.finalized_count = finalized_count;
+        Some(tree)
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Return the total number of leaves, including finalized leaves.
+    pub fn len(&self) -> usize {
+        self.finalized_count + self.layers[0].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        1 << self.depth
+    }
+
+    /// Return the number of finalized leaves.
+    pub fn finalized_count(&self) -> usize {
+        self.finalized_count
+    }
+
+    /// Return the roots of the complete subtrees covering the finalized leaves,
+    /// ordered from left to right.
+    pub fn finalized(&self) -> &[Node] {
+        &self.finalized
+    }
+
+    /// Append `leaf` to the tree.
+    pub fn push(&mut self, leaf: Node) -> Result<(), MerkleizationError> {
+        if self.len() == self.capacity() {
+            return Err(MerkleizationError::InputExceedsLimit(self.capacity()))
+        }
+        let layers = &mut self.layers;
+        self.frontier.push(self.finalized_count + layers[0].len(), leaf, |height, root| {
+            layers[height].push(root)
+        });
+        Ok(())
+    }
+
+    /// Finalize the first `count` leaves of the tree.
+    ///
+    /// Finalizing fewer leaves than are already finalized is a no-op.
+    pub fn finalize(&mut self, count: usize) -> Result<(), MerkleizationError> {
+        if count > self.len() {
+            return Err(MerkleizationError::InputExceedsLimit(self.len()))
+        }
+        if count <= self.finalized_count {
+            return Ok(())
+        }
+        let finalized = finalized_subtrees(count, self.depth)
+            .map(|(height, start)| self.complete_subtree(height, start >> height))
+            .collect();
+        for (height, layer) in self.layers.iter_mut().enumerate() {
+            layer.drain(..(count >> height) - (self.finalized_count >> height));
+        }
+        self.finalized = finalized;
+        self.finalized_count = count;
+        Ok(())
+    }
+
+    /// Compute the root of the tree.
+    pub fn root(&self) -> Node {
+        self.frontier.root(self.len())
+    }
+
+    /// Compute the root of the tree with the number of leaves mixed in,
+    /// as done by the deposit contract.
+    pub fn root_with_length(&self) -> Node {
+        let mut length = Node::default();
+        length.as_mut()[..8].copy_from_slice(&(self.len() as u64).to_le_bytes());
+        hash::<H>(&self.root(), &length)
+    }
+
+    /// Return a proof of the leaf at `index` against the `root` of the tree,
+    /// or `None` if the leaf is missing or finalized.
+    pub fn proof(&self, index: usize) -> Option<Proof<H>> {
+        if index < self.finalized_count || index >= self.len() {
+            return None
+        }
+        let len = self.len();
+        let edge = self.frontier.edge(len);
+        let leaf = self.layers[0][index - self.finalized_count];
+        let branch = (0..self.depth)
+            .map(|height| {
+                let sibling = (index >> height) ^ 1;
+                if (sibling + 1) << height <= len {
+                    self.complete_subtree(height, sibling)
+                } else if sibling << height >= len {
+                    self.frontier.zeros[height]
+                } else {
+                    edge[height]
+                }
+            })
+            .collect();
+        Some(Proof::new(leaf, branch, index))
+    }
+
+    // Return the root of the complete subtree of height `height` at `index` in its layer.
+    fn complete_subtree(&self, height: usize, index: usize) -> Node {
+        let start = self.finalized_count >> height;
+        if index >= start {
+            return self.layers[height][index - start]
+        }
+        // NOTE: a complete subtree covered by finalized leaves whose parent is not is exactly
+        // one of the finalized subtrees
+        let position = finalized_subtrees(self.finalized_count, self.depth)
+            .position(|subtree| subtree == (height, index << height))
+            .expect("finalized subtrees cover the finalized leaves");
+        self.finalized[position]
+    }
+}
+
+impl<H: MerkleHasher> Clone for IncrementalMerkleTree<H> {
+    fn clone(&self) -> Self {
+        Self {
+            depth: self.depth,
+            finalized: self.finalized.clone(),
+            finalized_count: self.finalized_count,
+            layers: self.layers.clone(),
+            frontier: self.frontier.clone(),
+        }
+    }
+}
+
+impl<H: MerkleHasher> PartialEq for IncrementalMerkleTree<H> {
+    fn eq(&self, other: &Self) -> bool {
+        // NOTE: the frontier and the layers above the leaves are derived from the other fields
+        self.depth == other.depth &&
+            self.finalized == other.finalized &&
+            self.finalized_count == other.finalized_count &&
+            self.layers[0] == other.layers[0]
+    }
+}
+
+impl<H: MerkleHasher> Eq for IncrementalMerkleTree<H> {}
+
+impl<H: MerkleHasher> Debug for IncrementalMerkleTree<H> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IncrementalMerkleTree")
+            .field("hasher", &any::type_name::<H>())
+            .field("depth", &self.depth)
+            .field("finalized", &self.finalized)
+            .field("finalized_count", &self.finalized_count)
+            .field("leaves", &self.layers[0])
+            .finish()
+    }
+}
+
+/// The last complete left subtree at each height of an append-only Merkle tree, as kept by
+/// the deposit contract, from which the root of the tree is computed in `O(depth)` hashes.
+pub(crate) struct Frontier<H: MerkleHasher = Sha256Hasher> {
+    // only meaningful at the heights of the set bits of the number of leaves, and at the
+    // height of the root once the tree is full
+    branch: Vec<Node>,
+    // roots of the subtrees of zero leaves at each height
+    zeros: Vec<Node>,
+    _hasher: PhantomData<fn() -> H>,
+}
+
+impl<H: MerkleHasher> Frontier<H> {
+    pub(crate) fn new(depth: usize, context: &Context<H>) -> Self {
+        Self {
+            branch: vec![Node::default(); depth + 1],
+            zeros: (0..=depth).map(|height| Node::zero_at_depth(height, context)).collect(),
+            _hasher: PhantomData,
+        }
+    }
+
+    fn depth(&self) -> usize {
+        self.zeros.len() - 1
+    }
+
+    /// Append `leaf` at `index`, calling `complete` with the height and root of each subtree
+    /// it completes, starting with the leaf itself.
+    pub(crate) fn push(&mut self, index: usize, leaf: Node, mut complete: impl FnMut(usize, Node)) {
+        let mut size = index + 1;
+        let mut node = leaf;
+        complete(0, leaf);
+        for height in 0..self.depth() {
+            if size & 1 == 1 {
+                self.branch[height] = node;
+                return
+            }
+            node = hash::<H>(&self.branch[height], &node);
+            complete(height + 1, node);
+            size >>= 1;
+        }
+        let depth = self.depth();
+        self.branch[depth] = node;
+    }
+
+    /// Return the root of the tree of `len` leaves.
+    pub(crate) fn root(&self, len: usize) -> Node {
+        let depth = self.depth();
+        if len >> depth == 1 {
+            return self.branch[depth]
+        }
+        self.edge(len).pop().expect("has the root")
+    }
+
+    /// Return the root of the subtree at each height containing the first missing leaf of a
+    /// tree of `len` leaves, from the leaf to the root of the tree.
+    pub(crate) fn edge(&self, len: usize) -> Vec<Node> {
+        let depth = self.depth();
+        let mut edge = Vec::with_capacity(depth + 1);
+        edge.push(self.zeros[0]);
+        for height in 0..depth {
+            let node = edge[height];
+            let parent = if (len >> height) & 1 == 1 {
+                hash::<H>(&self.branch[height], &node)
+            } else if len & ((1 << height) - 1) == 0 {
+                // NOTE: the subtree has no leaves
+                self.zeros[height + 1]
+            } else {
+                hash::<H>(&node, &self.zeros[height])
+            };
+            edge.push(parent);
+        }
+        edge
+    }
+}
+
+impl<H: MerkleHasher> Clone for Frontier<H> {
+    fn clone(&self) -> Self {
+        Self { branch: self.branch.clone(), zeros: self.zeros.clone(), _hasher: PhantomData }
+    }
+}
+
+impl<H: MerkleHasher> PartialEq for Frontier<H> {
+    fn eq(&self, other: &Self) -> bool {
+        self.branch == other.branch && self.zeros == other.zeros
+    }
+}
+
+impl<H: MerkleHasher> Eq for Frontier<H> {}
+
+impl<H: MerkleHasher> Debug for Frontier<H> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Frontier").field("branch", &self.branch).finish()
+    }
+}
+
+fn hash<H: MerkleHasher>(left: &Node, right: &Node) -> Node {
+    let mut parent = Node::default();
+    hash_nodes(&mut H::default(), left.as_ref(), right.as_ref(), parent.as_mut());
+    parent
+}
+
+// Return the `(depth, start)` of each complete subtree covering the first `count` leaves
+// of a tree of height `depth`, ordered from left to right.
+fn finalized_subtrees(count: usize, depth: usize) -> impl Iterator<Item = (usize, usize)> {
+    let mut start = 0;
+    (0..=depth).rev().filter(move |i| count & (1 << i) != 0).map(move |i| {
+        let subtree = (i, start);
+        start += 1 << i;
+        subtree
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkleization::{merkleize, merkleize_with, mix_in_length, BYTES_PER_CHUNK};
+
+    fn leaf(i: u8) -> Node {
+        Node::try_from([i; BYTES_PER_CHUNK].as_ref()).unwrap()
+    }
+
+    #[test]
+    fn test_incremental_tree_root_and_proofs() {
+        let mut tree = IncrementalMerkleTree::new(4);
+        let mut chunks = vec![];
+        assert_eq!(tree.root(), merkleize(&chunks, Some(16)).unwrap());
+
+        for i in 0..11 {
+            tree.push(leaf(i)).unwrap();
+            chunks.extend_from_slice(leaf(i).as_ref());
+            assert_eq!(tree.root(), merkleize(&chunks, Some(16)).unwrap());
+        }
+
+        let root = tree.root();
+        for i in 0..11 {
+            assert!(tree.proof(i).unwrap().verify(&root));
+        }
+        assert!(tree.proof(11).is_none());
+
+        tree.finalize(7).unwrap();
+        assert_eq!(tree.finalized().len(), 3);
+        assert_eq!(tree.root(), root);
+        assert!(tree.proof(6).is_none());
+        for i in 7..11 {
+            assert!(tree.proof(i).unwrap().verify(&root));
+        }
+
+        for i in 11..16 {
+            tree.push(leaf(i)).unwrap();
+            chunks.extend_from_slice(leaf(i).as_ref());
+        }
+        assert!(tree.push(leaf(16)).is_err());
+        assert_eq!(tree.root(), merkleize(&chunks, Some(16)).unwrap());
+
+        tree.finalize(16).unwrap();
+        assert_eq!(tree.finalized(), &[merkleize(&chunks, Some(16)).unwrap()]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_incremental_tree_too_deep() {
+        IncrementalMerkleTree::new(usize::BITS as usize);
+    }
+
+    #[test]
+    fn test_incremental_tree_from_finalized_too_deep() {
+        let depth = usize::BITS as usize;
+        assert!(IncrementalMerkleTree::from_finalized(depth, vec![], 0).is_none());
+        assert!(IncrementalMerkleTree::from_finalized(depth - 1, vec![], 0).is_some());
+    }
+
+    #[test]
+    fn test_incremental_tree_finalized_prefixes() {
+        let chunks = (0..16).flat_map(|i| leaf(i).0).collect::<Vec<_>>();
+        for finalized in 0..=16 {
+            let mut tree = IncrementalMerkleTree::new(4);
+            for i in 0..finalized {
+                tree.push(leaf(i as u8)).unwrap();
+            }
+            tree.finalize(finalized).unwrap();
+            let mut resumed =
+                IncrementalMerkleTree::from_finalized(4, tree.finalized().to_vec(), finalized)
+                    .unwrap();
+            for len in finalized..=16 {
+                let root = merkleize(&chunks[..len * BYTES_PER_CHUNK], Some(16)).unwrap();
+                assert_eq!(tree.root(), root);
+                assert_eq!(resumed.root(), root);
+                assert_eq!(tree.root_with_length(), mix_in_length(&root, len, Context::global()));
+                for index in finalized..len {
+                    assert!(tree.proof(index).unwrap().verify(&root));
+                    assert!(resumed.proof(index).unwrap().verify(&root));
+                }
+                if len < 16 {
+                    tree.push(leaf(len as u8)).unwrap();
+                    resumed.push(leaf(len as u8)).unwrap();
+                }
+            }
+            assert_eq!(tree, resumed);
+        }
+    }
+
+    #[test]
+    fn test_incremental_tree_with_hasher() {
+        #[derive(Default)]
+        struct Reversed(Sha256Hasher);
+
+        impl MerkleHasher for Reversed {
+            fn hash_nodes(&mut self, left: &[u8], right: &[u8], out: &mut [u8]) {
+                self.0.hash_nodes(right, left, out)
+            }
+        }
+
+        let context = Context::<Reversed>::new();
+        let mut tree = IncrementalMerkleTree::new_with(3, &context);
+        let mut chunks = vec![];
+        for i in 0..5 {
+            tree.push(leaf(i)).unwrap();
+            chunks.extend_from_slice(leaf(i).as_ref());
+        }
+        let root = merkleize_with(&chunks, Some(8), &context).unwrap();
+        assert_eq!(tree.root(), root);
+        assert_ne!(tree.root(), merkleize(&chunks, Some(8)).unwrap());
+        assert_eq!(tree.root_with_length(), mix_in_length(&root, 5, &context));
+        assert!(tree.proof(4).unwrap().verify(&root));
+    }
+}
@@ -0,0 +1,122 @@
+//! Hashing of whole layers of nodes, for Merkle trees built outside of SSZ types, e.g. airdrop
+//! allowlists or rollup commitments, which follow the SSZ padding with zero subtrees.
+use crate::{
+    lib::*,
+    merkleization::{
+        hash_nodes, merkleize_with, Context, MerkleHasher, MerkleizationError, Node,
+        BYTES_PER_CHUNK,
+    },
+};
+
+/// Return the parents of `nodes`, hashing each pair of adjacent nodes with SHA-256.
+///
+/// An odd last node is paired with the zero node.
+pub fn hash_layer(nodes: &[Node]) -> Vec<Node> {
+    hash_layer_with(nodes, 0, Context::global())
+}
+
+/// Return the parents of `nodes` at `height` in a Merkle tree, where leaves are at height 0,
+/// using the hash function of the given `context`.
+///
+/// An odd last node is paired with the root of a zero subtree of `height`.
+pub fn hash_layer_with<H: MerkleHasher>(
+    nodes: &[Node],
+    height: usize,
+    context: &Context<H>,
+) -> Vec<Node> {
+    let mut hasher = H::default();
+    nodes
+        .chunks(2)
+        .map(|pair| {
+            let right = match pair {
+                [_, right] => right.as_ref(),
+                _ => &context[height],
+            };
+            let mut parent = Node::default();
+            hash_nodes(&mut hasher, pair[0].as_ref(), right, parent.as_mut());
+            parent
+        })
+        .collect()
+}
+
+/// Return the root of a Merkle tree of `depth` with `leaves` on the left, padded with zero
+/// leaves, hashing with SHA-256.
+///
+/// The zero subtrees are not hashed, so this is cheap for deep trees with few leaves.
+///
+/// ```rust
+/// use ssz_rs::{
+///     advanced::{compute_root_from_leaves, hash_layer},
+///     prelude::*,
+/// };
+///
+/// let leaves = [Node::try_from([1u8; 32].as_ref()).unwrap(); 3];
+/// let root = compute_root_from_leaves(&leaves, 2).unwrap();
+/// assert_eq!(root, hash_layer(&hash_layer(&leaves))[0]);
+/// assert!(compute_root_from_leaves(&leaves, 1).is_err());
+/// ```
+pub fn compute_root_from_leaves(leaves: &[Node], depth: usize) -> Result<Node, MerkleizationError> {
+    compute_root_from_leaves_with(leaves, depth, Context::global())
+}
+
+/// Return the root of a Merkle tree of `depth` with `leaves` on the left, padded with zero
+/// leaves, using the hash function of the given `context`.
+pub fn compute_root_from_leaves_with<H: MerkleHasher>(
+    leaves: &[Node],
+    depth: usize,
+    context: &Context<H>,
+) -> Result<Node, MerkleizationError> {
+    let leaf_count = u32::try_from(depth)
+        .ok()
+        .and_then(|depth| 1usize.checked_shl(depth))
+        .ok_or(MerkleizationError::InputExceedsLimit(usize::MAX))?;
+    let mut chunks = Vec::with_capacity(leaves.len() * BYTES_PER_CHUNK);
+    for leaf in leaves {
+        chunks.extend_from_slice(leaf.as_ref());
+    }
+    merkleize_with(&chunks, Some(leaf_count), context)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{merkleization::mix_in_length, prelude::*};
+
+    fn leaf(byte: u8) -> Node {
+        Node::try_from([byte; 32].as_ref()).unwrap()
+    }
+
+    #[test]
+    fn test_hash_layer() {
+        assert!(hash_layer(&[]).is_empty());
+        assert_eq!(hash_layer(&[leaf(1), leaf(2)]), vec![Node::hash_pair(&leaf(1), &leaf(2))]);
+        assert_eq!(
+            hash_layer(&[leaf(1), leaf(2), leaf(3)]),
+            vec![Node::hash_pair(&leaf(1), &leaf(2)), Node::hash_pair(&leaf(3), &Node::default())]
+        );
+
+        let zero = Node::hash_pair(&Node::default(), &Node::default());
+        let parents = hash_layer_with(&[leaf(4)], 1, Context::global());
+        assert_eq!(parents, vec![Node::hash_pair(&leaf(4), &zero)]);
+    }
+
+    #[test]
+    fn test_compute_root_from_leaves() {
+        let leaves = (1..=5).map(leaf).collect::<Vec<_>>();
+        let mut layer = leaves.clone();
+        for height in 0..4 {
+            layer = hash_layer_with(&layer, height, Context::global());
+        }
+        assert_eq!(layer.len(), 1);
+        assert_eq!(compute_root_from_leaves(&leaves, 4).unwrap(), layer[0]);
+
+        let mut list = List::<Node, 16>::try_from(leaves.clone()).unwrap();
+        let root = compute_root_from_leaves(&leaves, 4).unwrap();
+        assert_eq!(mix_in_length(&root, 5, Context::global()), list.hash_tree_root().unwrap());
+
+        assert_eq!(compute_root_from_leaves(&[], 0).unwrap(), Node::default());
+        assert_eq!(compute_root_from_leaves(&leaves[..1], 0).unwrap(), leaves[0]);
+        assert!(compute_root_from_leaves(&leaves, 2).is_err());
+        assert!(compute_root_from_leaves(&leaves, 64).is_err());
+    }
+}
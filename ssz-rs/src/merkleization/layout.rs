@@ -0,0 +1,100 @@
+//! `const` functions for the layout of the Merkle trees of SSZ types: the number of chunks
+//! of a collection and the number of leaves and depth of the padded tree.
+//!
+//! These follow the rules of the SSZ spec used when merkleizing the types of this crate,
+//! so custom types can size their trees in `const` items.
+//!
+//! ```rust
+//! use ssz_rs::advanced::{
+//!     chunks_for_bits, chunks_for_list, depth_for_chunks, leaf_count_for_chunks, BYTES_PER_CHUNK,
+//! };
+//!
+//! // a `List<u64, 1000>` and a `List<Validator, 1000>` with composite elements
+//! const BALANCES_CHUNKS: usize = chunks_for_list(1000, 8);
+//! const VALIDATORS_CHUNKS: usize = chunks_for_list(1000, BYTES_PER_CHUNK);
+//! assert_eq!(BALANCES_CHUNKS, 250);
+//! assert_eq!(leaf_count_for_chunks(VALIDATORS_CHUNKS), 1024);
+//! assert_eq!(depth_for_chunks(VALIDATORS_CHUNKS), 10);
+//! assert_eq!(chunks_for_bits(2048), 8);
+//! ```
+use crate::merkleization::BYTES_PER_CHUNK;
+
+pub(crate) const BITS_PER_CHUNK: usize = 8 * BYTES_PER_CHUNK;
+
+/// Return the number of chunks of a vector or list of (at most) `limit` elements, each
+/// encoded in `element_size` bytes, saturating at `usize::MAX`.
+///
+/// Basic elements are packed into chunks, while composite elements take one chunk each,
+/// i.e. pass `BYTES_PER_CHUNK` as the `element_size` of composite elements.
+pub const fn chunks_for_list(limit: usize, element_size: usize) -> usize {
+    let len = limit.saturating_mul(element_size);
+    len / BYTES_PER_CHUNK + (len % BYTES_PER_CHUNK != 0) as usize
+}
+
+/// Return the number of chunks of a bitvector or bitlist of (at most) `bits` bits.
+pub const fn chunks_for_bits(bits: usize) -> usize {
+    bits / BITS_PER_CHUNK + (bits % BITS_PER_CHUNK != 0) as usize
+}
+
+/// Return the number of leaves of a Merkle tree of `chunks` chunks padded with zero chunks
+/// to a power of two, where an empty tree has one leaf.
+///
+/// Panics if the number of leaves does not fit in a `usize`.
+pub const fn leaf_count_for_chunks(chunks: usize) -> usize {
+    if chunks == 0 {
+        1
+    } else {
+        chunks.next_power_of_two()
+    }
+}
+
+/// Return the depth of a Merkle tree of `chunks` chunks padded to a power of two.
+pub const fn depth_for_chunks(chunks: usize) -> u32 {
+    leaf_count_for_chunks(chunks).trailing_zeros()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prelude::*, Cached};
+
+    #[test]
+    fn test_layout_of_types() {
+        assert_eq!(chunks_for_list(1024, 2), List::<u16, 1024>::chunk_count());
+        assert_eq!(chunks_for_list(3, 8), Vector::<u64, 3>::chunk_count());
+        assert_eq!(chunks_for_list(5, BYTES_PER_CHUNK), List::<List<u8, 1>, 5>::chunk_count());
+        assert_eq!(chunks_for_list(0, 8), 0);
+        assert_eq!(chunks_for_list(usize::MAX, 2), usize::MAX / BYTES_PER_CHUNK + 1);
+        assert_eq!(chunks_for_bits(257), Bitlist::<257>::chunk_count());
+        assert_eq!(chunks_for_bits(0), 0);
+
+        assert_eq!(leaf_count_for_chunks(0), 1);
+        assert_eq!(leaf_count_for_chunks(5), 8);
+        assert_eq!(depth_for_chunks(1), 0);
+        assert_eq!(depth_for_chunks(5), List::<List<u8, 1>, 5>::tree_depth());
+    }
+
+    #[derive(Default, Debug, SimpleSerialize)]
+    struct Header {
+        slot: u64,
+        parent_root: Node,
+        bits: Bitlist<1024>,
+    }
+
+    #[test]
+    fn test_padded_leaf_count_and_limit() {
+        assert_eq!(List::<u16, 1000>::padded_leaf_count(), 64);
+        assert_eq!(List::<u16, 1000>::merkle_limit(), Some(63));
+        assert_eq!(Vector::<u64, 5>::padded_leaf_count(), 2);
+        assert_eq!(Vector::<u64, 5>::merkle_limit(), None);
+        assert_eq!(Bitlist::<1024>::merkle_limit(), Some(4));
+        assert_eq!(Header::padded_leaf_count(), 4);
+        assert_eq!(Header::merkle_limit(), None);
+        assert_eq!(u64::padded_leaf_count(), 1);
+        assert_eq!(Option::<Header>::padded_leaf_count(), 1);
+        assert_eq!(
+            Cached::<Option<List<u8, 100>>>::merkle_limit(),
+            Option::<List<u8, 100>>::merkle_limit()
+        );
+    }
+}
@@ -1,28 +1,150 @@
+mod accumulator;
+mod bulk;
 mod cache;
+mod cached;
+mod default_root;
+mod generalized_index;
+mod hasher;
+mod incremental;
+#[cfg(feature = "layers")]
+mod layers;
+mod layout;
 mod node;
 mod proofs;
+#[cfg(any(test, feature = "reference"))]
+pub mod reference;
+mod root;
+mod root_cache;
+mod root_eq;
+mod streaming;
 
 use crate::{
+    de::{variable_element_ranges, DeserializeError},
+    error::{Error, ErrorKind, InstanceError},
     lib::*,
+    metrics,
     ser::{Serialize, SerializeError},
+    spans::Span,
+    SimpleSerialize,
 };
-use sha2::{Digest, Sha256};
 
+pub use accumulator::RootAccumulator;
+pub use bulk::{merkleize_bulk, BulkHashError, BulkHasher};
 pub use cache::Cache as MerkleCache;
+pub use cached::Cached;
+pub use default_root::{merkleize_roots, DefaultRoot, RootMemo};
+pub(crate) use generalized_index::{bits_generalized_index, elements_generalized_index};
+pub use generalized_index::{
+    child_generalized_index, invalid_path_element, union_generalized_index, GeneralizedIndex,
+    PathElement, PathError,
+};
+pub(crate) use hasher::{hash_nodes, hash_pairs};
+pub use hasher::{MerkleHasher, Sha256Hasher};
+pub use incremental::IncrementalMerkleTree;
+#[cfg(feature = "layers")]
+pub use layers::{
+    compute_root_from_leaves, compute_root_from_leaves_with, hash_layer, hash_layer_with,
+};
+pub use layout::{chunks_for_bits, chunks_for_list, depth_for_chunks, leaf_count_for_chunks};
 pub use node::Node;
-pub use proofs::is_valid_merkle_branch;
+#[cfg(feature = "rayon")]
+pub use proofs::verify_merkle_branches_batch_par;
+pub use proofs::{
+    is_valid_merkle_branch, is_valid_merkle_branch_with, verify_merkle_branches_batch, Proof,
+};
+pub use root::Root;
+#[cfg(feature = "std")]
+pub use root_cache::Memoized;
+pub use root_cache::RootCache;
+pub use root_eq::{roots_equal, RootEq};
+pub use streaming::{HasherCheckpoint, StreamingHasher};
+
+pub const BYTES_PER_CHUNK: usize = 32;
 
-pub(crate) const BYTES_PER_CHUNK: usize = 32;
+/// The maximum depth of a layer of a Merkle tree computed at once, e.g. by
+/// [`List::hash_tree_root_at_depth`](crate::List::hash_tree_root_at_depth), which bounds the
+/// layer to `2**24` nodes, i.e. 512 MiB.
+pub const MAX_LAYER_DEPTH: u32 = 24;
 
 pub trait Merkleized {
     /// Compute the "hash tree root" of `Self`.
-    fn hash_tree_root(&mut self) -> Result<Node, MerkleizationError>;
+    fn hash_tree_root(&mut self) -> Result<Node, MerkleizationError> {
+        Span::new::<Self>("hash_tree_root").in_scope(|| self.hash_tree_root_with(Context::global()))
+    }
+
+    /// Compute the "hash tree root" of `Self` with the hash function of the given `context`.
+    fn hash_tree_root_with<H: MerkleHasher>(
+        &mut self,
+        context: &Context<H>,
+    ) -> Result<Node, MerkleizationError>;
+
+    /// Return the leaves of the Merkle tree of `Self` before padding, i.e. the chunks of packed
+    /// basic values or the roots of elements and fields which are merkleized for the root,
+    /// before any length or selector is mixed in.
+    ///
+    /// The default is the root itself, the only leaf of basic types. Composite types must
+    /// override it, as the default fails for a type of more than one chunk.
+    fn leaves<H: MerkleHasher>(
+        &mut self,
+        context: &Context<H>,
+    ) -> Result<Vec<Node>, MerkleizationError> {
+        if Self::chunk_count() > 1 {
+            return Err(MerkleizationError::LeavesNotImplemented(any::type_name::<Self>()))
+        }
+        Ok(vec![self.hash_tree_root_with(context)?])
+    }
+
+    /// The number of chunks (leaves) of the Merkle tree of `Self` before padding, the constant
+    /// counterpart of [`Merkleized::chunk_count`] to size trees when compiling.
+    ///
+    /// For types with a limit (e.g. `List`) this is the number of chunks at the limit. Types which
+    /// do not set it are taken to have one chunk, as basic types do, so composite types must set
+    /// it.
+    const CHUNK_COUNT: usize = 1;
+
+    /// Return the number of chunks (leaves) of the Merkle tree of `Self` before padding, which is
+    /// [`CHUNK_COUNT`](Self::CHUNK_COUNT).
+    fn chunk_count() -> usize {
+        Self::CHUNK_COUNT
+    }
+
+    /// Return the depth of the Merkle tree of `Self`, excluding any mixed in length or selector.
+    fn tree_depth() -> u32 {
+        depth_for_chunks(Self::chunk_count())
+    }
+
+    /// Return the number of leaves of the Merkle tree of `Self` once padded to a power of two,
+    /// i.e. `2**tree_depth()`.
+    fn padded_leaf_count() -> usize {
+        leaf_count_for_chunks(Self::chunk_count())
+    }
+
+    /// Return the limit in chunks the leaves of `Self` are merkleized with, which is the
+    /// chunk count of types with a length mixed in, e.g. a `List`, and `None` for types whose
+    /// tree is sized by their leaves, e.g. a `Vector` or a container.
+    fn merkle_limit() -> Option<usize> {
+        None
+    }
 }
 
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum MerkleizationError {
     SerializationError(SerializeError),
     InputExceedsLimit(usize),
+    /// An encoding hashed without decoding it first, e.g. the bytes of a `RawSsz`, is invalid.
+    InvalidEncoding(DeserializeError),
+    /// The type with this name has more than one chunk but does not implement
+    /// [`Merkleized::leaves`].
+    LeavesNotImplemented(&'static str),
+    /// The input of this many bytes is not a whole number of chunks.
+    IncompleteChunk(usize),
+    /// The layer at `depth` of a Merkle tree was requested but layers are only computed down to
+    /// `max_depth`, the depth of the tree or [`MAX_LAYER_DEPTH`], whichever is smaller.
+    InvalidDepth {
+        depth: u32,
+        max_depth: u32,
+    },
 }
 
 impl From<SerializeError> for MerkleizationError {
@@ -31,6 +153,19 @@ impl From<SerializeError> for MerkleizationError {
     }
 }
 
+impl MerkleizationError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::SerializationError(err) => err.kind(),
+            Self::InputExceedsLimit(..) => ErrorKind::Bounds,
+            Self::InvalidEncoding(err) => err.kind(),
+            Self::LeavesNotImplemented(..) => ErrorKind::InvalidType,
+            Self::IncompleteChunk(..) => ErrorKind::Malformed,
+            Self::InvalidDepth { .. } => ErrorKind::Bounds,
+        }
+    }
+}
+
 impl Display for MerkleizationError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
@@ -38,6 +173,16 @@ impl Display for MerkleizationError {
                 write!(f, "failed to serialize value: {err}")
             }
             Self::InputExceedsLimit(size) => write!(f, "data exceeds the declared limit {size}"),
+            Self::InvalidEncoding(err) => write!(f, "invalid encoding: {err}"),
+            Self::LeavesNotImplemented(type_name) => {
+                write!(f, "the leaves of `{type_name}` are not implemented")
+            }
+            Self::IncompleteChunk(len) => {
+                write!(f, "input of {len} bytes is not a whole number of chunks")
+            }
+            Self::InvalidDepth { depth, max_depth } => {
+                write!(f, "depth {depth} exceeds the maximum depth {max_depth}")
+            }
         }
     }
 }
@@ -54,6 +199,127 @@ pub fn pack_bytes(buffer: &mut Vec<u8>) {
     }
 }
 
+// Return the number of chunks needed to merkleize `count` elements of type `T`.
+pub(crate) fn elements_chunk_count<T: SimpleSerialize>(count: usize) -> usize {
+    if T::is_composite_type() {
+        count
+    } else {
+        chunks_for_list(count, T::size_hint())
+    }
+}
+
+// Return the number of chunks needed to merkleize `count` elements of type `T` when compiling.
+pub(crate) const fn elements_chunk_count_const<T: SimpleSerialize>(count: usize) -> usize {
+    count / T::PACKED_PER_CHUNK + (count % T::PACKED_PER_CHUNK != 0) as usize
+}
+
+/// Return the root of the Merkle tree of the elements of a homogeneous composite type
+/// `C` with at most `limit` elements given its `encoding`, and the number of elements.
+///
+/// Basic elements are checked and packed from the encoding directly, composite elements
+/// are hashed from their encodings with `SimpleSerialize::hash_tree_root_from_bytes`.
+pub(crate) fn elements_root_from_bytes<C, T, H>(
+    encoding: &[u8],
+    limit: usize,
+    context: &Context<H>,
+) -> Result<(Node, usize), Error>
+where
+    T: SimpleSerialize,
+    H: MerkleHasher,
+{
+    let chunk_count = elements_chunk_count::<T>(limit);
+    let check_len = |len: usize| {
+        if len > limit {
+            Err(DeserializeError::from(InstanceError::bounded::<C>(limit, len)))
+        } else {
+            Ok(len)
+        }
+    };
+    if !T::is_composite_type() {
+        let size = T::size_hint();
+        let remainder = encoding.len() % size;
+        if remainder != 0 {
+            let expected = encoding.len() - remainder;
+            return Err(
+                DeserializeError::AdditionalInput { provided: encoding.len(), expected }.into()
+            )
+        }
+        let len = check_len(encoding.len() / size)?;
+        for element in encoding.chunks_exact(size) {
+            T::deserialize(element)?;
+        }
+        let mut chunks = encoding.to_vec();
+        pack_bytes(&mut chunks);
+        return Ok((merkleize_with(&chunks, Some(chunk_count), context)?, len))
+    }
+
+    let ranges = if T::is_variable_size() {
+        variable_element_ranges(encoding)?
+    } else {
+        let size = T::size_hint();
+        let remainder = encoding.len() % size;
+        if remainder != 0 {
+            let expected = encoding.len() - remainder;
+            return Err(
+                DeserializeError::AdditionalInput { provided: encoding.len(), expected }.into()
+            )
+        }
+        (0..encoding.len() / size).map(|i| i * size..(i + 1) * size).collect()
+    };
+    let len = check_len(ranges.len())?;
+    let mut chunks = vec![0u8; len * BYTES_PER_CHUNK];
+    for (i, range) in ranges.into_iter().enumerate() {
+        let root = T::hash_tree_root_from_bytes(&encoding[range], context)?;
+        chunks[i * BYTES_PER_CHUNK..(i + 1) * BYTES_PER_CHUNK].copy_from_slice(root.as_ref());
+    }
+    Ok((merkleize_with(&chunks, Some(chunk_count), context)?, len))
+}
+
+// Return the chunks of the Merkle tree of the homogeneous `elements`, before padding: the
+// packed elements if they are basic, otherwise their roots.
+pub(crate) fn elements_chunks<T: SimpleSerialize, H: MerkleHasher>(
+    elements: &mut [T],
+    context: &Context<H>,
+) -> Result<Vec<u8>, MerkleizationError> {
+    if T::is_composite_type() {
+        let mut chunks = vec![0u8; elements.len() * BYTES_PER_CHUNK];
+        for (i, elem) in elements.iter_mut().enumerate() {
+            let chunk = elem.hash_tree_root_with(context)?;
+            let range = i * BYTES_PER_CHUNK..(i + 1) * BYTES_PER_CHUNK;
+            chunks[range].copy_from_slice(chunk.as_ref());
+        }
+        Ok(chunks)
+    } else {
+        pack_elements(elements)
+    }
+}
+
+// Return the leaf at `leaf_index` of the Merkle tree of `elements`, i.e. the root of a composite
+// element or the chunk basic elements are packed into.
+pub(crate) fn element_leaf<T: SimpleSerialize, H: MerkleHasher>(
+    elements: &mut [T],
+    leaf_index: usize,
+    context: &Context<H>,
+) -> Result<Node, MerkleizationError> {
+    if T::is_composite_type() {
+        return elements[leaf_index].hash_tree_root_with(context)
+    }
+    let per_chunk = BYTES_PER_CHUNK / T::size_hint();
+    let start = leaf_index * per_chunk;
+    let end = (start + per_chunk).min(elements.len());
+    let chunk = pack_elements(&elements[start..end])?;
+    Ok(Node::try_from(chunk.as_slice()).expect("is one chunk"))
+}
+
+// Split `chunks` into nodes.
+// Invariant: `chunks.len() % BYTES_PER_CHUNK == 0`
+pub(crate) fn chunks_to_nodes(chunks: &[u8]) -> Vec<Node> {
+    chunks
+        .chunks_exact(BYTES_PER_CHUNK)
+        .map(|chunk| Node::try_from(chunk).expect("is one chunk"))
+        .collect()
+}
+
 // Packs serializations of `values` into the return buffer with the
 // guarantee that `buffer.len() % BYTES_PER_CHUNK == 0`
 pub fn pack<T>(values: &[T]) -> Result<Vec<u8>, MerkleizationError>
@@ -68,20 +334,83 @@ where
     Ok(buffer)
 }
 
-fn hash_nodes(hasher: &mut Sha256, a: &[u8], b: &[u8], out: &mut [u8]) {
-    hasher.update(a);
-    hasher.update(b);
-    out.copy_from_slice(&hasher.finalize_reset());
+// Pack the encodings of the basic `elements` into chunks, see
+// `SimpleSerialize::serialize_elements`.
+pub(crate) fn pack_elements<T: SimpleSerialize>(
+    elements: &[T],
+) -> Result<Vec<u8>, MerkleizationError> {
+    let mut buffer =
+        Vec::with_capacity(elements_chunk_count::<T>(elements.len()) * BYTES_PER_CHUNK);
+    T::serialize_elements(elements, &mut buffer)?;
+    pack_bytes(&mut buffer);
+    Ok(buffer)
 }
 
 const MAX_MERKLE_TREE_DEPTH: usize = 64;
 
-#[derive(Debug)]
-struct Context {
+/// A `Context` holds the precomputed roots of "zero" subtrees used to virtually pad the
+/// Merkle trees formed during merkleization with the hash function `H`.
+///
+/// A `Context` is immutable once constructed so it is `Send + Sync` and can be freely shared
+/// across threads, e.g. by reference to the process-wide instance returned by
+/// [`Context::global`] or behind an `Arc`.
+pub struct Context<H: MerkleHasher = Sha256Hasher> {
     zero_hashes: [u8; MAX_MERKLE_TREE_DEPTH * BYTES_PER_CHUNK],
+    // NOTE: use `fn() -> H` so the `Context` is `Send + Sync` regardless of `H`
+    _hasher: PhantomData<fn() -> H>,
+}
+
+// `Context` must remain shareable across threads; fail the build if this ever changes.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Context>();
+};
+
+impl Context {
+    /// Return the process-wide `Context` for the SSZ hash function, SHA-256.
+    ///
+    /// The zero hashes are computed at build time so this is free to call.
+    pub fn global() -> &'static Self {
+        &CONTEXT
+    }
+}
+
+impl<H: MerkleHasher> Context<H> {
+    /// Construct a `Context` by computing the zero hashes with the hash function `H`.
+    pub fn new() -> Self {
+        let mut hasher = H::default();
+        let mut zero_hashes = [0u8; MAX_MERKLE_TREE_DEPTH * BYTES_PER_CHUNK];
+        for i in 0..MAX_MERKLE_TREE_DEPTH - 1 {
+            let focus = &mut zero_hashes[i * BYTES_PER_CHUNK..(i + 2) * BYTES_PER_CHUNK];
+            let (source, target) = focus.split_at_mut(BYTES_PER_CHUNK);
+            hash_nodes(&mut hasher, source, source, target);
+        }
+        Self { zero_hashes, _hasher: PhantomData }
+    }
 }
 
-impl Index<usize> for Context {
+impl<H: MerkleHasher> Default for Context<H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<H: MerkleHasher> Clone for Context<H> {
+    fn clone(&self) -> Self {
+        Self { zero_hashes: self.zero_hashes, _hasher: PhantomData }
+    }
+}
+
+impl<H: MerkleHasher> Debug for Context<H> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Context")
+            .field("hasher", &any::type_name::<H>())
+            .field("zero_hashes", &self.zero_hashes)
+            .finish()
+    }
+}
+
+impl<H: MerkleHasher> Index<usize> for Context<H> {
     type Output = [u8];
 
     fn index(&self, index: usize) -> &Self::Output {
@@ -97,19 +426,20 @@ include!(concat!(env!("OUT_DIR"), "/context.rs"));
 /// `chunks` forms the bottom layer of a binary tree that is Merkleized.
 ///
 /// This implementation is memory efficient by relying on pre-computed subtrees of all
-/// "zero" leaves stored in the `CONTEXT`. SSZ specifies that `chunks` is padded to the next power
+/// "zero" leaves stored in the `context`. SSZ specifies that `chunks` is padded to the next power
 /// of two and this can be quite large for some types. "Zero" subtrees are virtualized to avoid the
 /// memory and computation cost of large trees with partially empty leaves.
 ///
 /// Invariant: `chunks.len() % BYTES_PER_CHUNK == 0`
 /// Invariant: `leaf_count.next_power_of_two() == leaf_count`
-fn merkleize_chunks_with_virtual_padding(
+fn merkleize_chunks_with_virtual_padding<H: MerkleHasher>(
     chunks: &[u8],
     leaf_count: usize,
+    context: &Context<H>,
 ) -> Result<Node, MerkleizationError> {
     let chunk_count = chunks.len() / BYTES_PER_CHUNK;
 
-    let mut hasher = Sha256::new();
+    let mut hasher = H::default();
     debug_assert!(chunks.len() % BYTES_PER_CHUNK == 0);
     debug_assert!(leaf_count.next_power_of_two() == leaf_count);
 
@@ -117,53 +447,20 @@ fn merkleize_chunks_with_virtual_padding(
 
     if chunk_count == 0 {
         let depth = height - 1;
-        return Ok(CONTEXT[depth as usize].try_into().expect("can produce a single root chunk"))
+        return Ok(context[depth as usize].try_into().expect("can produce a single root chunk"))
     }
 
     let mut layer = chunks.to_vec();
-    let mut last_index = chunk_count - 1;
-    for k in (1..height).rev() {
-        for i in (0..2usize.pow(k)).step_by(2) {
-            let parent_index = i / 2;
-            match i.cmp(&last_index) {
-                Ordering::Less => {
-                    let focus =
-                        &mut layer[parent_index * BYTES_PER_CHUNK..(i + 2) * BYTES_PER_CHUNK];
-                    let children_index = focus.len() - 2 * BYTES_PER_CHUNK;
-                    let (parent, children) = focus.split_at_mut(children_index);
-                    let (left, right) = children.split_at_mut(BYTES_PER_CHUNK);
-                    if parent.is_empty() {
-                        // NOTE: have to specially handle the situation where the children nodes and
-                        // parent node share memory
-                        hasher.update(&left);
-                        hasher.update(right);
-                        left.copy_from_slice(&hasher.finalize_reset());
-                    } else {
-                        hash_nodes(&mut hasher, left, right, &mut parent[..BYTES_PER_CHUNK]);
-                    }
-                }
-                Ordering::Equal => {
-                    let focus =
-                        &mut layer[parent_index * BYTES_PER_CHUNK..(i + 1) * BYTES_PER_CHUNK];
-                    let children_index = focus.len() - BYTES_PER_CHUNK;
-                    let (parent, children) = focus.split_at_mut(children_index);
-                    let (left, _) = children.split_at_mut(BYTES_PER_CHUNK);
-                    let depth = height - k - 1;
-                    let right = &CONTEXT[depth as usize];
-                    if parent.is_empty() {
-                        // NOTE: have to specially handle the situation where the children nodes and
-                        // parent node share memory
-                        hasher.update(&left);
-                        hasher.update(right);
-                        left.copy_from_slice(&hasher.finalize_reset());
-                    } else {
-                        hash_nodes(&mut hasher, left, right, &mut parent[..BYTES_PER_CHUNK]);
-                    }
-                }
-                _ => break,
-            };
+    let mut parents = Vec::with_capacity(layer.len() / 2 + BYTES_PER_CHUNK);
+    for depth in 0..height as usize - 1 {
+        // NOTE: an odd last node is paired with the root of a zero subtree
+        if layer.len() % (2 * BYTES_PER_CHUNK) != 0 {
+            layer.extend_from_slice(&context[depth]);
         }
-        last_index /= 2;
+        parents.clear();
+        parents.resize(layer.len() / 2, 0);
+        hash_pairs(&mut hasher, &layer, &mut parents);
+        mem::swap(&mut layer, &mut parents);
     }
 
     Ok(layer[..BYTES_PER_CHUNK].try_into().expect("can produce a single root chunk"))
@@ -172,33 +469,162 @@ fn merkleize_chunks_with_virtual_padding(
 // Return the root of the Merklization of a binary tree formed from `chunks`.
 // Invariant: `chunks.len() % BYTES_PER_CHUNK == 0`
 pub fn merkleize(chunks: &[u8], limit: Option<usize>) -> Result<Node, MerkleizationError> {
+    merkleize_with(chunks, limit, Context::global())
+}
+
+// Return the root of the Merklization of a binary tree formed from `chunks`
+// using the hash function of the given `context`.
+// Invariant: `chunks.len() % BYTES_PER_CHUNK == 0`
+pub fn merkleize_with<H: MerkleHasher>(
+    chunks: &[u8],
+    limit: Option<usize>,
+    context: &Context<H>,
+) -> Result<Node, MerkleizationError> {
     debug_assert!(chunks.len() % BYTES_PER_CHUNK == 0);
     let chunk_count = chunks.len() / BYTES_PER_CHUNK;
-    let mut leaf_count = chunk_count.next_power_of_two();
-    if let Some(limit) = limit {
-        if limit < chunk_count {
-            return Err(MerkleizationError::InputExceedsLimit(limit))
+    metrics::record_chunks(chunk_count);
+    let leaf_count = padded_leaf_count(chunk_count, limit)?;
+    merkleize_chunks_with_virtual_padding(chunks, leaf_count, context)
+}
+
+// Return the number of leaves of the Merkle tree of `chunk_count` chunks padded to `limit`
+// chunks, or to the next power of two without a `limit`.
+fn padded_leaf_count(
+    chunk_count: usize,
+    limit: Option<usize>,
+) -> Result<usize, MerkleizationError> {
+    match limit {
+        Some(limit) if limit < chunk_count => Err(MerkleizationError::InputExceedsLimit(limit)),
+        // NOTE: limits above `usize::MAX / 2` can not be padded on 32-bit targets
+        Some(limit) => {
+            limit.checked_next_power_of_two().ok_or(MerkleizationError::InputExceedsLimit(limit))
         }
-        leaf_count = limit.next_power_of_two();
+        None => Ok(chunk_count.next_power_of_two()),
+    }
+}
+
+// Return the `2**depth` nodes at `depth` of the Merkle tree formed from `chunks` padded to
+// `limit` chunks, as in `merkleize_with`, where the root is at depth 0.
+// Invariant: `chunks.len() % BYTES_PER_CHUNK == 0`
+pub(crate) fn layer_at_depth_with<H: MerkleHasher>(
+    chunks: &[u8],
+    limit: usize,
+    depth: u32,
+    context: &Context<H>,
+) -> Result<Vec<Node>, MerkleizationError> {
+    let leaf_count =
+        limit.checked_next_power_of_two().ok_or(MerkleizationError::InputExceedsLimit(limit))?;
+    let tree_depth = leaf_count.trailing_zeros();
+    // NOTE: the layer is allocated at once, so its size is bounded along with the depth
+    let max_depth = tree_depth.min(MAX_LAYER_DEPTH);
+    if depth > max_depth {
+        return Err(MerkleizationError::InvalidDepth { depth, max_depth })
     }
-    merkleize_chunks_with_virtual_padding(chunks, leaf_count)
+    let subtree_leaf_count = leaf_count >> depth;
+    let subtree_size = subtree_leaf_count * BYTES_PER_CHUNK;
+    (0..1usize << depth)
+        .map(|i| match chunks.get(i * subtree_size..) {
+            Some(rest) if !rest.is_empty() => {
+                let subtree = &rest[..subtree_size.min(rest.len())];
+                merkleize_with(subtree, Some(subtree_leaf_count), context)
+            }
+            _ => Ok(Node::zero_at_depth((tree_depth - depth) as usize, context)),
+        })
+        .collect()
 }
 
-fn mix_in_decoration(root: &Node, mut decoration: usize) -> Node {
-    let decoration_data = decoration.hash_tree_root().expect("can merkleize usize");
+// Return the root of the Merklization of `count` copies of `leaf` padded to `leaf_count`
+// leaves, hashing each distinct pair of nodes once, e.g. for the default value of a `Vector` of
+// composite types.
+// Invariant: `count <= leaf_count` and `leaf_count.next_power_of_two() == leaf_count`
+pub(crate) fn merkleize_repeated_with<H: MerkleHasher>(
+    leaf: &Node,
+    count: usize,
+    leaf_count: usize,
+    context: &Context<H>,
+) -> Node {
+    debug_assert!(count <= leaf_count);
+    debug_assert!(leaf_count.next_power_of_two() == leaf_count);
 
-    let mut hasher = Sha256::new();
-    let mut output = vec![0u8; BYTES_PER_CHUNK];
-    hash_nodes(&mut hasher, root.as_ref(), decoration_data.as_ref(), &mut output);
-    output.as_slice().try_into().expect("can extract root")
+    let mut hasher = H::default();
+    let mut hash = |left: &[u8], right: &[u8]| {
+        let mut output = Node::default();
+        hash_nodes(&mut hasher, left, right, output.as_mut());
+        output
+    };
+    // each layer is `count` copies of `node` followed by the `last` node, if any
+    let (mut node, mut count, mut last) = (*leaf, count, None);
+    for depth in 0..leaf_count.trailing_zeros() as usize {
+        let zero = &context[depth];
+        let next = if count % 2 == 1 {
+            let right = last.as_ref().map_or(zero, |last: &Node| last.as_ref());
+            Some(hash(node.as_ref(), right))
+        } else {
+            last.map(|last| hash(last.as_ref(), zero))
+        };
+        if count > 1 {
+            node = hash(node.as_ref(), node.as_ref());
+        }
+        count /= 2;
+        last = next;
+    }
+    match (count, last) {
+        (1, _) => node,
+        (_, Some(last)) => last,
+        _ => Node::zero_at_depth(leaf_count.trailing_zeros() as usize, context),
+    }
 }
 
-pub(crate) fn mix_in_length(root: &Node, length: usize) -> Node {
-    mix_in_decoration(root, length)
+fn mix_in_decoration<H: MerkleHasher>(
+    root: &Node,
+    mut decoration: usize,
+    context: &Context<H>,
+) -> Node {
+    let decoration_data = decoration.hash_tree_root_with(context).expect("can merkleize usize");
+
+    let mut hasher = H::default();
+    let mut output = Node::default();
+    hash_nodes(&mut hasher, root.as_ref(), decoration_data.as_ref(), output.as_mut());
+    output
 }
 
-pub fn mix_in_selector(root: &Node, selector: usize) -> Node {
-    mix_in_decoration(root, selector)
+/// Mix the `length` of a variable-length collection into the `root` of its contents.
+///
+/// This is the final step of merkleizing the list-like SSZ types (`List`, `Bitlist`) where
+/// the root of the elements, merkleized up to the limit of the type, is combined with the
+/// number of elements. Types implementing `Merkleized` for a custom list-like type should
+/// use this to match the root of the equivalent SSZ list.
+///
+/// ```rust
+/// use ssz_rs::{
+///     advanced::{mix_in_length, MerkleizationContext},
+///     prelude::*,
+/// };
+///
+/// // the root of a `List<Node, 4>` with a single element, where the contents are
+/// // merkleized as if padded with zero nodes up to the limit
+/// let element = Node::try_from([1u8; 32].as_ref()).unwrap();
+/// let mut padded = Vector::<Node, 4>::try_from(vec![element, Node::default(), Node::default(), Node::default()]).unwrap();
+/// let contents = padded.hash_tree_root().unwrap();
+/// let root = mix_in_length(&contents, 1, MerkleizationContext::global());
+///
+/// let mut list = List::<Node, 4>::try_from(vec![element]).unwrap();
+/// assert_eq!(root, list.hash_tree_root().unwrap());
+/// ```
+pub fn mix_in_length<H: MerkleHasher>(root: &Node, length: usize, context: &Context<H>) -> Node {
+    mix_in_decoration(root, length, context)
+}
+
+/// Mix the `selector` of a union into the `root` of the selected value.
+///
+/// This is the final step of merkleizing an SSZ union, where the `root` is that of the
+/// selected value, or the zero node if the `None` variant (with selector 0) is selected.
+pub fn mix_in_selector<H: MerkleHasher>(
+    root: &Node,
+    selector: usize,
+    context: &Context<H>,
+) -> Node {
+    mix_in_decoration(root, selector, context)
 }
 
 #[cfg(test)]
@@ -242,6 +668,22 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn test_packing_wide_uints() {
+        let values = [1u128, u128::MAX, 1 << 100];
+        assert_eq!(pack_elements(&values).unwrap(), pack(&values).unwrap());
+        assert_eq!(pack_elements(&values).unwrap().len(), 2 * BYTES_PER_CHUNK);
+
+        let values =
+            [U256::zero(), U256::from(7u64), U256::from_bytes_le([u8::MAX; 32]), U256::from(1u64)];
+        let chunks = pack_elements(&values).unwrap();
+        assert_eq!(chunks, pack(&values).unwrap());
+        assert_eq!(chunks[..32], [0u8; 32]);
+        assert_eq!(chunks[32], 7);
+        assert_eq!(chunks[64..96], [u8::MAX; 32]);
+        assert!(pack_elements::<u16>(&[]).unwrap().is_empty());
+    }
+
     #[test]
     fn test_merkleize_basic() {
         let input = &[];
@@ -257,79 +699,48 @@ mod tests {
         assert_eq!(result, expected);
     }
 
-    // Return the root of the Merklization of a binary tree formed from `chunks`.
-    // Invariant: `chunks.len() % BYTES_PER_CHUNK == 0`
-    // Invariant: `leaf_count.next_power_of_two() == leaf_count`
-    // NOTE: naive implementation, can make much more efficient
-    fn merkleize_chunks(chunks: &[u8], leaf_count: usize) -> Result<Node, MerkleizationError> {
-        debug_assert!(chunks.len() % BYTES_PER_CHUNK == 0);
-        debug_assert!(leaf_count.next_power_of_two() == leaf_count);
-
-        let node_count = 2 * leaf_count - 1;
-        let interior_count = node_count - leaf_count;
-        let leaf_start = interior_count * BYTES_PER_CHUNK;
-
-        let mut hasher = Sha256::new();
-        let mut buffer = vec![0u8; node_count * BYTES_PER_CHUNK];
-        buffer[leaf_start..leaf_start + chunks.len()].copy_from_slice(chunks);
-        let zero_chunk = [0u8; 32];
-        for i in chunks.len()..leaf_count {
-            let start = leaf_start + (i * BYTES_PER_CHUNK);
-            let end = leaf_start + (i + 1) * BYTES_PER_CHUNK;
-            buffer[start..end].copy_from_slice(&zero_chunk);
-        }
-
-        for i in (1..node_count).rev().step_by(2) {
-            let parent_index = (i - 1) / 2;
-            let focus = &mut buffer[parent_index * BYTES_PER_CHUNK..(i + 1) * BYTES_PER_CHUNK];
-            let children_index = focus.len() - 2 * BYTES_PER_CHUNK;
-            let (parent, children) = focus.split_at_mut(children_index);
-            let left = &children[0..BYTES_PER_CHUNK];
-            let right = &children[BYTES_PER_CHUNK..2 * BYTES_PER_CHUNK];
-            hash_nodes(&mut hasher, left, right, &mut parent[..BYTES_PER_CHUNK]);
-        }
-        Ok(buffer[0..BYTES_PER_CHUNK].try_into().expect("can produce a single root chunk"))
-    }
-
     #[test]
     fn test_naive_merkleize_chunks() {
         let chunks = vec![0u8; 2 * BYTES_PER_CHUNK];
-        let root = merkleize_chunks(&chunks, 2).expect("can merkleize");
+        let root = reference::merkleize_chunks(&chunks, 2).expect("can merkleize");
         assert_eq!(root, hex!("f5a5fd42d16a20302798ef6ed309979b43003d2320d9f0e8ea9831a92759fb4b"));
 
         let chunks = vec![1u8; 2 * BYTES_PER_CHUNK];
-        let root = merkleize_chunks(&chunks, 2).expect("can merkleize");
+        let root = reference::merkleize_chunks(&chunks, 2).expect("can merkleize");
         assert_eq!(root, hex!("7c8975e1e60a5c8337f28edf8c33c3b180360b7279644a9bc1af3c51e6220bf5"));
 
         let chunks = vec![0u8; BYTES_PER_CHUNK];
-        let root = merkleize_chunks(&chunks, 4).expect("can merkleize");
+        let root = reference::merkleize_chunks(&chunks, 4).expect("can merkleize");
         assert_eq!(root, hex!("db56114e00fdd4c1f85c892bf35ac9a89289aaecb1ebd0a96cde606a748b5d71"));
 
         let chunks = vec![1u8; BYTES_PER_CHUNK];
-        let root = merkleize_chunks(&chunks, 4).expect("can merkleize");
+        let root = reference::merkleize_chunks(&chunks, 4).expect("can merkleize");
         assert_eq!(root, hex!("29797eded0e83376b70f2bf034cc0811ae7f1414653b1d720dfd18f74cf13309"));
 
         let chunks = vec![2u8; BYTES_PER_CHUNK];
-        let root = merkleize_chunks(&chunks, 8).expect("can merkleize");
+        let root = reference::merkleize_chunks(&chunks, 8).expect("can merkleize");
         assert_eq!(root, hex!("fa4cf775712aa8a2fe5dcb5a517d19b2e9effcf58ff311b9fd8e4a7d308e6d00"));
 
         let chunks = vec![1u8; 5 * BYTES_PER_CHUNK];
-        let root = merkleize_chunks(&chunks, 8).expect("can merkleize");
+        let root = reference::merkleize_chunks(&chunks, 8).expect("can merkleize");
         assert_eq!(root, hex!("0ae67e34cba4ad2bbfea5dc39e6679b444021522d861fab00f05063c54341289"));
     }
 
     #[test]
     fn test_merkleize_chunks() {
         let chunks = vec![1u8; 3 * BYTES_PER_CHUNK];
-        let root = merkleize_chunks_with_virtual_padding(&chunks, 4).expect("can merkleize");
+        let root = merkleize_chunks_with_virtual_padding(&chunks, 4, Context::global())
+            .expect("can merkleize");
         assert_eq!(root, hex!("65aa94f2b59e517abd400cab655f42821374e433e41b8fe599f6bb15484adcec"));
 
         let chunks = vec![1u8; 5 * BYTES_PER_CHUNK];
-        let root = merkleize_chunks_with_virtual_padding(&chunks, 8).expect("can merkleize");
+        let root = merkleize_chunks_with_virtual_padding(&chunks, 8, Context::global())
+            .expect("can merkleize");
         assert_eq!(root, hex!("0ae67e34cba4ad2bbfea5dc39e6679b444021522d861fab00f05063c54341289"));
 
         let chunks = vec![1u8; 6 * BYTES_PER_CHUNK];
-        let root = merkleize_chunks_with_virtual_padding(&chunks, 8).expect("can merkleize");
+        let root = merkleize_chunks_with_virtual_padding(&chunks, 8, Context::global())
+            .expect("can merkleize");
         assert_eq!(root, hex!("0ef7df63c204ef203d76145627b8083c49aa7c55ebdee2967556f55a4f65a238"));
     }
 
@@ -337,12 +748,14 @@ mod tests {
     fn test_merkleize_chunks_with_many_virtual_nodes() {
         let chunks = vec![1u8; 5 * BYTES_PER_CHUNK];
         let root =
-            merkleize_chunks_with_virtual_padding(&chunks, 2usize.pow(10)).expect("can merkleize");
+            merkleize_chunks_with_virtual_padding(&chunks, 2usize.pow(10), Context::global())
+                .expect("can merkleize");
         assert_eq!(root, hex!("2647cb9e26bd83eeb0982814b2ac4d6cc4a65d0d98637f1a73a4c06d3db0e6ce"));
 
         let chunks = vec![1u8; 70 * BYTES_PER_CHUNK];
         let root =
-            merkleize_chunks_with_virtual_padding(&chunks, 2usize.pow(63)).expect("can merkleize");
+            merkleize_chunks_with_virtual_padding(&chunks, 2usize.pow(63), Context::global())
+                .expect("can merkleize");
         assert_eq!(root, hex!("9317695d95b5a3b46e976b5a9cbfcfccb600accaddeda9ac867cc9669b862979"));
     }
 
@@ -441,7 +854,18 @@ mod tests {
         assert_eq!(root, hex!("7078155bf8f0dc42d8afccec8d9b5aeb54f0a2e8e58fcef3e723f6a867232ce7"));
 
         let root = foo.hash_tree_root().expect("can make root");
-        assert_eq!(root, hex!("0063bfcfabbca567483a2ee859fcfafb958329489eb328ac7f07790c7df1b231"));
+        assert_eq!(root, hex!("44880ccc671318dcbde4a2450259ac5c253629cf00634de93a83549e526b4283"));
+
+        // NOTE: the bit removed by `pop` is not part of `d`, so `foo` has the root of the same
+        // values built from scratch
+        let mut rebuilt_foo = Foo {
+            b: Vector::try_from(vec![3u32, 2u32, 44u32, 10u32]).unwrap(),
+            d: original_foo.d.iter().take(26).map(|bit| *bit).collect(),
+            e: Bar::A(33),
+            ..original_foo.clone()
+        };
+        assert_eq!(rebuilt_foo, foo);
+        assert_eq!(rebuilt_foo.hash_tree_root().expect("can make root"), root);
 
         let encoding = serialize(&original_foo).expect("can serialize");
 
@@ -455,7 +879,128 @@ mod tests {
         restored_foo.e = Bar::A(33);
 
         let root = foo.hash_tree_root().expect("can make root");
-        assert_eq!(root, hex!("0063bfcfabbca567483a2ee859fcfafb958329489eb328ac7f07790c7df1b231"));
+        assert_eq!(root, hex!("44880ccc671318dcbde4a2450259ac5c253629cf00634de93a83549e526b4283"));
+    }
+
+    #[test]
+    fn test_chunk_count_and_tree_depth() {
+        #[derive(Default, Debug, SimpleSerialize)]
+        struct Foo {
+            a: u8,
+            b: List<u64, 5>,
+            c: Bitvector<300>,
+        }
+
+        assert_eq!(u64::chunk_count(), 1);
+        assert_eq!(u64::tree_depth(), 0);
+        assert_eq!(<[u16; 17]>::chunk_count(), 2);
+        assert_eq!(Vector::<Foo, 3>::chunk_count(), 3);
+        assert_eq!(Vector::<Foo, 3>::tree_depth(), 2);
+        assert_eq!(List::<u64, 5>::chunk_count(), 2);
+        assert_eq!(List::<u64, 5>::tree_depth(), 1);
+        assert_eq!(List::<Foo, 0>::tree_depth(), 0);
+        assert_eq!(Bitvector::<300>::chunk_count(), 2);
+        assert_eq!(Foo::chunk_count(), 3);
+        assert_eq!(Foo::tree_depth(), 2);
+
+        // a basic type implemented without the chunk count takes the default of one chunk
+        struct Flag(bool);
+
+        impl Merkleized for Flag {
+            fn hash_tree_root_with<H: MerkleHasher>(
+                &mut self,
+                context: &Context<H>,
+            ) -> Result<Node, MerkleizationError> {
+                self.0.hash_tree_root_with(context)
+            }
+        }
+
+        assert_eq!(Flag::chunk_count(), 1);
+        assert_eq!(Flag::tree_depth(), 0);
+        assert_eq!(Flag(true).leaves(Context::global()).unwrap(), [true.hash_tree_root().unwrap()]);
+    }
+
+    #[test]
+    fn test_chunk_count_const() {
+        #[derive(Default, Debug, SimpleSerialize)]
+        struct Foo {
+            a: u8,
+            b: List<u64, 5>,
+            c: Bitvector<300>,
+        }
+
+        // the chunk counts are constants, e.g. to size arrays of leaves
+        const FOO_CHUNKS: usize = <Vector<Foo, 3> as Merkleized>::CHUNK_COUNT;
+        let leaves = [Node::default(); FOO_CHUNKS];
+        assert_eq!(leaves.len(), 3);
+        assert_eq!(<[u16; 17]>::CHUNK_COUNT, 2);
+        assert_eq!(List::<u64, 5>::CHUNK_COUNT, 2);
+        assert_eq!(List::<bool, 33>::CHUNK_COUNT, 2);
+        assert_eq!(Bitlist::<257>::CHUNK_COUNT, 2);
+        assert_eq!(Foo::CHUNK_COUNT, 3);
+    }
+
+    #[test]
+    fn test_context_is_shared_across_threads() {
+        let context = Context::global();
+        assert!(core::ptr::eq(context, Context::global()));
+
+        let shared = std::sync::Arc::new(context.clone());
+        let handles = (0..4)
+            .map(|depth| {
+                let shared = shared.clone();
+                std::thread::spawn(move || shared[depth].to_vec())
+            })
+            .collect::<Vec<_>>();
+        for (depth, handle) in handles.into_iter().enumerate() {
+            assert_eq!(handle.join().unwrap(), &context[depth]);
+        }
+    }
+
+    // A hash function distinct from SHA-256 for testing purposes.
+    #[derive(Default)]
+    struct PrefixedSha256Hasher(Sha256Hasher);
+
+    impl MerkleHasher for PrefixedSha256Hasher {
+        fn hash_nodes(&mut self, left: &[u8], right: &[u8], out: &mut [u8]) {
+            let mut prefixed = [0u8; BYTES_PER_CHUNK];
+            self.0.hash_nodes(b"not-sha256-not-sha256-not-sha256", left, &mut prefixed);
+            self.0.hash_nodes(&prefixed, right, out);
+        }
+    }
+
+    #[test]
+    fn test_context_for_hasher() {
+        let context = Context::<Sha256Hasher>::new();
+        assert_eq!(context.zero_hashes, Context::global().zero_hashes);
+
+        let context = Context::<PrefixedSha256Hasher>::new();
+        let mut expected = [0u8; BYTES_PER_CHUNK];
+        PrefixedSha256Hasher::default().hash_nodes(&context[0], &context[0], &mut expected);
+        assert_eq!(&context[1], expected);
+        assert_ne!(&context[1], &Context::global()[1]);
+    }
+
+    #[test]
+    fn test_hash_tree_root_with_hasher() {
+        let context = Context::<PrefixedSha256Hasher>::new();
+
+        let mut value = [1u64, 2, 3, 4, 5, 6, 7, 8];
+        let chunks = pack(&value).expect("can pack");
+        let mut expected = Node::default();
+        PrefixedSha256Hasher::default().hash_nodes(
+            &chunks[..BYTES_PER_CHUNK],
+            &chunks[BYTES_PER_CHUNK..],
+            expected.as_mut(),
+        );
+        let root = value.hash_tree_root_with(&context).expect("can compute root");
+        assert_eq!(root, expected);
+        assert_ne!(root, value.hash_tree_root().expect("can compute root"));
+
+        let mut value = List::<u64, 64>::try_from(vec![1u64, 2, 3]).unwrap();
+        let data_root = merkleize_with(&pack(&value).unwrap(), Some(16), &context).unwrap();
+        let root = value.hash_tree_root_with(&context).expect("can compute root");
+        assert_eq!(root, mix_in_length(&data_root, 3, &context));
     }
 
     #[test]
@@ -472,4 +1017,64 @@ mod tests {
         let hash_tree_root = root.hash_tree_root().expect("can find root");
         assert_eq!(hash_tree_root, Node::default());
     }
+
+    #[derive(Default, Debug, PartialEq, Eq, SimpleSerialize)]
+    struct Leaves {
+        a: u8,
+        b: List<u16, 40>,
+        c: Option<bool>,
+    }
+
+    // check that the root of `value` is merkleized from its leaves, with `length` mixed in
+    fn check_leaves<T: SimpleSerialize>(value: &mut T, length: Option<usize>) {
+        let context = Context::global();
+        let leaves = value.leaves(context).unwrap();
+        let chunks = leaves.iter().flat_map(|leaf| leaf.as_ref().to_vec()).collect::<Vec<_>>();
+        let mut root = merkleize_with(&chunks, Some(T::chunk_count()), context).unwrap();
+        if let Some(length) = length {
+            root = mix_in_length(&root, length, context);
+        }
+        assert_eq!(root, value.hash_tree_root().unwrap(), "{}", any::type_name::<T>());
+    }
+
+    #[test]
+    fn test_leaves() {
+        let context = Context::global();
+        check_leaves(&mut 7u64, None);
+        check_leaves(&mut [1u32, 2, 3], None);
+        let mut list = List::<u16, 40>::try_from(vec![1; 17]).unwrap();
+        assert_eq!(list.leaves(context).unwrap().len(), 2);
+        check_leaves(&mut list, Some(17));
+        let mut vector = Vector::<List<u8, 4>, 3>::default();
+        assert_eq!(vector.leaves(context).unwrap().len(), 3);
+        check_leaves(&mut vector, None);
+        check_leaves(&mut Bitvector::<9>::default(), None);
+        check_leaves(&mut (0..260).map(|i| i % 3 == 0).collect::<Bitlist<300>>(), Some(260));
+
+        let mut value = Leaves { a: 1, b: list, c: Some(true) };
+        let leaves = value.leaves(context).unwrap();
+        assert_eq!(leaves[1], value.b.hash_tree_root().unwrap());
+        check_leaves(&mut value, None);
+
+        let mut union = Some(3u8);
+        assert_eq!(union.leaves(context).unwrap(), [3u8.hash_tree_root().unwrap()]);
+
+        // a composite type without its own leaves has no single leaf to fall back to
+        struct Pair;
+
+        impl Merkleized for Pair {
+            fn hash_tree_root_with<H: MerkleHasher>(
+                &mut self,
+                context: &Context<H>,
+            ) -> Result<Node, MerkleizationError> {
+                merkleize_with(&[0u8; 2 * BYTES_PER_CHUNK], None, context)
+            }
+
+            const CHUNK_COUNT: usize = 2;
+        }
+
+        let err = Pair.leaves(context).unwrap_err();
+        assert!(matches!(err, MerkleizationError::LeavesNotImplemented(..)));
+        assert_eq!(err.kind(), ErrorKind::InvalidType);
+    }
 }
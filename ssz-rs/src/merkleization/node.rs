@@ -1,13 +1,57 @@
-use crate::{lib::*, prelude::*, utils::write_bytes_to_lower_hex};
+use crate::{
+    hex,
+    lib::*,
+    merkleization::{hash_nodes, Context, MerkleHasher, Sha256Hasher, BYTES_PER_CHUNK},
+    prelude::*,
+};
 
 /// A node in a merkle tree.
 #[derive(Default, Clone, Copy, Eq, SimpleSerialize)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-pub struct Node(#[cfg_attr(feature = "serde", serde(with = "crate::serde::as_hex"))] [u8; 32]);
+#[repr(transparent)]
+pub struct Node(
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde::as_hex"))] pub(crate) [u8; 32],
+);
+
+impl Node {
+    /// Parse a `Node` from 64 hex digits, optionally prefixed with `0x`.
+    pub fn from_hex(s: &str) -> Option<Self> {
+        hex::decode_to_array::<BYTES_PER_CHUNK>(s).ok().map(Self)
+    }
+
+    /// Return the parent of `left` and `right` in a Merkle tree, hashing with SHA-256.
+    pub fn hash_pair(left: &Self, right: &Self) -> Self {
+        let mut parent = Self::default();
+        hash_nodes(&mut Sha256Hasher::default(), left.as_ref(), right.as_ref(), parent.as_mut());
+        parent
+    }
+
+    /// Return the root of a Merkle tree of `depth` with only zero leaves,
+    /// i.e. `Node::default()` at depth 0.
+    ///
+    /// Panics if `depth` exceeds the maximum depth of a Merkle tree (63).
+    pub fn zero_at_depth<H: MerkleHasher>(depth: usize, context: &Context<H>) -> Self {
+        Self::try_from(&context[depth]).expect("is one chunk")
+    }
+
+    /// View the bytes of `chunk` as a `Node`, without copying them.
+    #[cfg(feature = "roots")]
+    pub(crate) fn from_chunk(chunk: &[u8; BYTES_PER_CHUNK]) -> &Self {
+        // SAFETY: `Node` is a transparent wrapper of `[u8; 32]`
+        unsafe { &*(chunk as *const [u8; BYTES_PER_CHUNK]).cast::<Self>() }
+    }
+
+    /// View the bytes of `chunk` as a mutable `Node`, without copying them.
+    #[cfg(feature = "roots")]
+    pub(crate) fn from_chunk_mut(chunk: &mut [u8; BYTES_PER_CHUNK]) -> &mut Self {
+        // SAFETY: `Node` is a transparent wrapper of `[u8; 32]`
+        unsafe { &mut *(chunk as *mut [u8; BYTES_PER_CHUNK]).cast::<Self>() }
+    }
+}
 
 impl fmt::LowerHex for Node {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write_bytes_to_lower_hex(f, self)
+        hex::write_lower_hex(f, self)
     }
 }
 
@@ -65,4 +109,22 @@ mod tests {
         let recovered_node: Node = serde_json::from_str(&node_repr).unwrap();
         assert_eq!(node, recovered_node);
     }
+
+    #[test]
+    fn test_node_helpers() {
+        let zero = Node::default();
+        assert_eq!(Node::zero_at_depth(0, Context::global()), zero);
+
+        let expected =
+            Node::from_hex("0xf5a5fd42d16a20302798ef6ed309979b43003d2320d9f0e8ea9831a92759fb4b")
+                .unwrap();
+        assert_eq!(Node::hash_pair(&zero, &zero), expected);
+        assert_eq!(Node::zero_at_depth(1, Context::global()), expected);
+        let parent = Node::hash_pair(&expected, &expected);
+        assert_eq!(Node::zero_at_depth(2, Context::global()), parent);
+        assert_eq!(Node::from_hex(&format!("{parent:x}")), Some(parent));
+
+        assert!(Node::from_hex("0x00").is_none());
+        assert!(Node::from_hex(&"zz".repeat(32)).is_none());
+    }
 }
@@ -1,9 +1,26 @@
-use crate::merkleization::Node;
-use sha2::{Digest, Sha256};
+use crate::{
+    lib::*,
+    merkleization::{hash_nodes, MerkleHasher, Node, Sha256Hasher},
+    union::selector_node,
+};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 
 /// `is_valid_merkle_branch` verifies the Merkle proof
 /// against the `root` given the other metadata.
 pub fn is_valid_merkle_branch<'a>(
+    leaf: &Node,
+    branch: impl Iterator<Item = &'a Node>,
+    depth: usize,
+    index: usize,
+    root: &Node,
+) -> bool {
+    is_valid_merkle_branch_with::<Sha256Hasher>(leaf, branch, depth, index, root)
+}
+
+/// `is_valid_merkle_branch_with` verifies the Merkle proof
+/// against the `root` given the other metadata, hashing with `H`.
+pub fn is_valid_merkle_branch_with<'a, H: MerkleHasher>(
     leaf: &Node,
     mut branch: impl Iterator<Item = &'a Node>,
     depth: usize,
@@ -11,31 +28,193 @@ pub fn is_valid_merkle_branch<'a>(
     root: &Node,
 ) -> bool {
     let mut value = *leaf;
+    let mut output = Node::default();
 
-    let mut hasher = Sha256::new();
+    let mut hasher = H::default();
     for i in 0..depth {
         let next_node = match branch.next() {
             Some(node) => node,
             None => return false,
         };
         if (index / 2usize.pow(i as u32)) % 2 != 0 {
-            hasher.update(next_node.as_ref());
-            hasher.update(value.as_ref());
+            hash_nodes(&mut hasher, next_node.as_ref(), value.as_ref(), output.as_mut());
         } else {
-            hasher.update(value.as_ref());
-            hasher.update(next_node.as_ref());
+            hash_nodes(&mut hasher, value.as_ref(), next_node.as_ref(), output.as_mut());
         }
-        value.as_mut().copy_from_slice(&hasher.finalize_reset());
+        value = output;
     }
     value == *root
 }
 
+/// `verify_merkle_branches_batch` verifies each of the `proofs` against the root at the same
+/// position in `roots`, returning `false` as soon as one is invalid or if the lengths differ.
+///
+/// The nodes proven by each proof are kept so that later proofs against an equal root stop
+/// hashing at the first node already proven and only compare the rest of their branch with
+/// the proven nodes, e.g. proofs of neighbouring leaves only hash up to their common ancestor.
+pub fn verify_merkle_branches_batch<H: MerkleHasher>(proofs: &[Proof<H>], roots: &[Node]) -> bool {
+    if proofs.len() != roots.len() {
+        return false
+    }
+    // nodes proven so far, keyed by the first position of their root, depth and position
+    let mut proven = BTreeMap::new();
+    let mut hasher = H::default();
+    for (i, (proof, root)) in proofs.iter().zip(roots).enumerate() {
+        let root_id = roots[..i].iter().position(|other| other == root).unwrap_or(i);
+        proven.entry((root_id, 0, 0)).or_insert(*root);
+
+        let depth = proof.depth();
+        let position = |level: usize| {
+            let position = proof.index.checked_shr(level as u32).unwrap_or(0);
+            // NOTE: bits of `index` above `depth` are ignored, as in `is_valid_merkle_branch`
+            match 1usize.checked_shl((depth - level) as u32) {
+                Some(width) => position & (width - 1),
+                None => position,
+            }
+        };
+        let mut path = vec![];
+        let mut value = proof.leaf;
+        let mut level = 0;
+        // NOTE: the root is proven so the walk ends at the latest at the top of the tree
+        loop {
+            let key = (root_id, depth - level, position(level));
+            if let Some(node) = proven.get(&key) {
+                if *node != value {
+                    return false
+                }
+                break
+            }
+            let sibling = proof.branch[level];
+            path.push((key, value));
+            path.push(((root_id, depth - level, position(level) ^ 1), sibling));
+            let mut output = Node::default();
+            if position(level) % 2 != 0 {
+                hash_nodes(&mut hasher, sibling.as_ref(), value.as_ref(), output.as_mut());
+            } else {
+                hash_nodes(&mut hasher, value.as_ref(), sibling.as_ref(), output.as_mut());
+            }
+            value = output;
+            level += 1;
+        }
+        // NOTE: the siblings of a proven node and of its ancestors are proven as well
+        for level in level..depth {
+            let key = (root_id, depth - level, position(level) ^ 1);
+            if proven.get(&key) != Some(&proof.branch[level]) {
+                return false
+            }
+        }
+        for (key, node) in path {
+            proven.entry(key).or_insert(node);
+        }
+    }
+    true
+}
+
+/// `verify_merkle_branches_batch_par` verifies the `proofs` against the `roots` like
+/// [`verify_merkle_branches_batch`], splitting them into one batch per thread.
+#[cfg(feature = "rayon")]
+pub fn verify_merkle_branches_batch_par<H: MerkleHasher>(
+    proofs: &[Proof<H>],
+    roots: &[Node],
+) -> bool {
+    if proofs.len() != roots.len() {
+        return false
+    }
+    let threads = rayon::current_num_threads();
+    let batch_size = ((proofs.len() + threads - 1) / threads).max(1);
+    proofs
+        .par_chunks(batch_size)
+        .zip(roots.par_chunks(batch_size))
+        .all(|(proofs, roots)| verify_merkle_branches_batch(proofs, roots))
+}
+
+/// A Merkle proof of the `leaf` at `index` in a tree merkleized with the hash function `H`.
+///
+/// Every supported hash function produces a 32-byte digest so the nodes of the proof are
+/// plain [`Node`]s; the hash function is instead tracked in the type of the proof so that
+/// a proof can only be verified under the hash it was produced with.
+pub struct Proof<H: MerkleHasher = Sha256Hasher> {
+    pub leaf: Node,
+    pub branch: Vec<Node>,
+    pub index: usize,
+    _hasher: PhantomData<fn() -> H>,
+}
+
+impl<H: MerkleHasher> Proof<H> {
+    /// Construct a proof of `leaf` at `index` where `branch` holds the sibling
+    /// nodes from the bottom of the tree to the top.
+    pub fn new(leaf: Node, branch: Vec<Node>, index: usize) -> Self {
+        Self { leaf, branch, index, _hasher: PhantomData }
+    }
+
+    /// Return the depth of the tree this proof is against.
+    pub fn depth(&self) -> usize {
+        self.branch.len()
+    }
+
+    /// Verify this proof against the `root` with the hash function `H`.
+    pub fn verify(&self, root: &Node) -> bool {
+        is_valid_merkle_branch_with::<H>(
+            &self.leaf,
+            self.branch.iter(),
+            self.depth(),
+            self.index,
+            root,
+        )
+    }
+}
+
+impl<H: MerkleHasher> Proof<H> {
+    /// Extend a proof against the root of the value of the variant `selector` of a union
+    /// to a proof against the root of the union.
+    pub fn mix_in_selector(mut self, selector: u8) -> Self {
+        // NOTE: the value is the left child of the root of the union so `index` is unchanged
+        self.branch.push(selector_node(selector));
+        self
+    }
+
+    /// Verify that this proof, e.g. from `Union::prove_selector`, proves that the union with
+    /// `root` has the variant `selector` selected.
+    pub fn verify_selector(&self, selector: u8, root: &Node) -> bool {
+        self.depth() == 1 &&
+            self.index == 1 &&
+            self.leaf == selector_node(selector) &&
+            self.verify(root)
+    }
+}
+
+impl<H: MerkleHasher> Clone for Proof<H> {
+    fn clone(&self) -> Self {
+        Self::new(self.leaf, self.branch.clone(), self.index)
+    }
+}
+
+impl<H: MerkleHasher> PartialEq for Proof<H> {
+    fn eq(&self, other: &Self) -> bool {
+        self.leaf == other.leaf && self.branch == other.branch && self.index == other.index
+    }
+}
+
+impl<H: MerkleHasher> Eq for Proof<H> {}
+
+impl<H: MerkleHasher> Debug for Proof<H> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Proof")
+            .field("hasher", &any::type_name::<H>())
+            .field("leaf", &self.leaf)
+            .field("branch", &self.branch)
+            .field("index", &self.index)
+            .finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::merkleization::IncrementalMerkleTree;
 
     fn decode_node_from_hex(hex: &str) -> Node {
-        let bytes = hex::decode(hex).expect("is hex");
+        let bytes = crate::hex::decode(hex).expect("is hex");
         Node::try_from(bytes.as_ref()).expect("is right size")
     }
 
@@ -61,4 +240,73 @@ mod tests {
 
         assert!(is_valid_merkle_branch(&leaf, branch.iter(), depth, index, &root))
     }
+
+    #[test]
+    fn test_batch_verification() {
+        let mut trees = [IncrementalMerkleTree::new(3), IncrementalMerkleTree::new(4)];
+        for i in 0..6 {
+            trees[0].push(Node::try_from([i; 32].as_ref()).unwrap()).unwrap();
+            trees[1].push(Node::try_from([i + 6; 32].as_ref()).unwrap()).unwrap();
+        }
+        let mut proofs = vec![];
+        let mut roots = vec![];
+        for i in [0, 1, 5, 4, 0] {
+            for tree in &trees {
+                proofs.push(tree.proof(i).unwrap());
+                roots.push(tree.root());
+            }
+        }
+        assert!(verify_merkle_branches_batch(&proofs, &roots));
+        assert!(verify_merkle_branches_batch::<Sha256Hasher>(&[], &[]));
+        assert!(!verify_merkle_branches_batch(&proofs, &roots[1..]));
+
+        // a proof that only differs from a proven one in a node it shares is rejected
+        for i in 0..proofs.len() {
+            let mut tampered = proofs.clone();
+            tampered[i].branch[1] = Node::try_from([0xff; 32].as_ref()).unwrap();
+            assert!(!verify_merkle_branches_batch(&tampered, &roots));
+            let mut tampered = proofs.clone();
+            tampered[i].leaf = Node::try_from([0xff; 32].as_ref()).unwrap();
+            assert!(!verify_merkle_branches_batch(&tampered, &roots));
+        }
+        let mut swapped = roots.clone();
+        swapped.swap(0, 1);
+        assert!(!verify_merkle_branches_batch(&proofs, &swapped));
+
+        #[cfg(feature = "rayon")]
+        {
+            assert!(verify_merkle_branches_batch_par(&proofs, &roots));
+            assert!(!verify_merkle_branches_batch_par(&proofs, &swapped));
+        }
+    }
+
+    #[derive(Default)]
+    struct ReversedSha256Hasher(Sha256Hasher);
+
+    impl MerkleHasher for ReversedSha256Hasher {
+        fn hash_nodes(&mut self, left: &[u8], right: &[u8], out: &mut [u8]) {
+            self.0.hash_nodes(right, left, out);
+        }
+    }
+
+    #[test]
+    fn test_proof_with_hasher() {
+        let leaves = [
+            Node::try_from([1u8; 32].as_ref()).unwrap(),
+            Node::try_from([2u8; 32].as_ref()).unwrap(),
+        ];
+        let mut root = Node::default();
+        ReversedSha256Hasher::default().hash_nodes(
+            leaves[0].as_ref(),
+            leaves[1].as_ref(),
+            root.as_mut(),
+        );
+
+        let proof = Proof::<ReversedSha256Hasher>::new(leaves[1], vec![leaves[0]], 1);
+        assert!(proof.verify(&root));
+        assert!(!is_valid_merkle_branch(&proof.leaf, proof.branch.iter(), 1, 1, &root));
+
+        let proof = Proof::<Sha256Hasher>::new(leaves[1], vec![leaves[0]], 1);
+        assert!(!proof.verify(&root));
+    }
 }
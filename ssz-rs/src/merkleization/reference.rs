@@ -0,0 +1,95 @@
+//! A naive merkleizer that materializes the entire Merkle tree in memory.
+//!
+//! This is far slower than the merkleization used to compute hash tree roots, which only
+//! hashes the non-zero parts of the tree, but is simple enough to be obviously correct.
+//! It is useful to cross-check the optimized implementation, e.g. in differential tests
+//! over your own data.
+use crate::{
+    lib::*,
+    merkleization::{
+        hash_nodes, MerkleHasher, MerkleizationError, Node, Sha256Hasher, BYTES_PER_CHUNK,
+    },
+};
+
+/// Return the root of the Merkle tree with `leaf_count` leaves formed from `chunks`
+/// and padded with zero chunks, hashing with SHA-256.
+///
+/// `chunks.len()` must be a multiple of `BYTES_PER_CHUNK` and `leaf_count` is rounded up
+/// to the next power of two. Every node of the tree is allocated so `leaf_count` should
+/// be kept modest, and a `leaf_count` whose tree does not fit in memory is an error.
+pub fn merkleize_chunks(chunks: &[u8], leaf_count: usize) -> Result<Node, MerkleizationError> {
+    merkleize_chunks_with::<Sha256Hasher>(chunks, leaf_count)
+}
+
+/// Return the root of the Merkle tree with `leaf_count` leaves formed from `chunks`
+/// and padded with zero chunks, hashing with `H`.
+///
+/// See [`merkleize_chunks`] for the requirements on the inputs.
+pub fn merkleize_chunks_with<H: MerkleHasher>(
+    chunks: &[u8],
+    leaf_count: usize,
+) -> Result<Node, MerkleizationError> {
+    if chunks.len() % BYTES_PER_CHUNK != 0 {
+        return Err(MerkleizationError::IncompleteChunk(chunks.len()))
+    }
+    let leaf_count = leaf_count
+        .checked_next_power_of_two()
+        .ok_or(MerkleizationError::InputExceedsLimit(leaf_count))?;
+    if chunks.len() / BYTES_PER_CHUNK > leaf_count {
+        return Err(MerkleizationError::InputExceedsLimit(leaf_count))
+    }
+
+    let node_count = leaf_count
+        .checked_mul(2)
+        .map(|count| count - 1)
+        .ok_or(MerkleizationError::InputExceedsLimit(leaf_count))?;
+    let buffer_len = node_count
+        .checked_mul(BYTES_PER_CHUNK)
+        .ok_or(MerkleizationError::InputExceedsLimit(leaf_count))?;
+    let interior_count = node_count - leaf_count;
+    let leaf_start = interior_count * BYTES_PER_CHUNK;
+
+    let mut hasher = H::default();
+    // NOTE: the buffer starts zeroed so the leaves past `chunks` are already padding
+    let mut buffer: Vec<u8> = vec![0u8; buffer_len];
+    buffer[leaf_start..leaf_start + chunks.len()].copy_from_slice(chunks);
+
+    for i in (1..node_count).rev().step_by(2) {
+        let parent_index = (i - 1) / 2;
+        let focus = &mut buffer[parent_index * BYTES_PER_CHUNK..(i + 1) * BYTES_PER_CHUNK];
+        let children_index = focus.len() - 2 * BYTES_PER_CHUNK;
+        let (parent, children) = focus.split_at_mut(children_index);
+        let left = &children[0..BYTES_PER_CHUNK];
+        let right = &children[BYTES_PER_CHUNK..2 * BYTES_PER_CHUNK];
+        hash_nodes(&mut hasher, left, right, &mut parent[..BYTES_PER_CHUNK]);
+    }
+    Ok(buffer[0..BYTES_PER_CHUNK].try_into().expect("can produce a single root chunk"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkleization::merkleize;
+
+    #[test]
+    fn test_reference_matches_merkleize() {
+        for chunk_count in 0..=17 {
+            let chunks = (0..chunk_count * BYTES_PER_CHUNK).map(|i| i as u8).collect::<Vec<_>>();
+            for limit in chunk_count.max(1)..=33 {
+                let expected = merkleize(&chunks, Some(limit)).unwrap();
+                assert_eq!(merkleize_chunks(&chunks, limit).unwrap(), expected);
+            }
+        }
+        assert!(merkleize_chunks(&[0u8; 3 * BYTES_PER_CHUNK], 2).is_err());
+    }
+
+    #[test]
+    fn test_reference_rejects_invalid_input() {
+        let err = merkleize_chunks(&[0u8; BYTES_PER_CHUNK + 1], 2).unwrap_err();
+        assert!(matches!(err, MerkleizationError::IncompleteChunk(33)));
+        for leaf_count in [usize::MAX, usize::MAX / 2 + 2, usize::MAX / 32] {
+            let err = merkleize_chunks(&[], leaf_count).unwrap_err();
+            assert!(matches!(err, MerkleizationError::InputExceedsLimit(..)));
+        }
+    }
+}
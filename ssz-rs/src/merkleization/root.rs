@@ -0,0 +1,246 @@
+use crate::{
+    de::{Deserialize, DeserializeError},
+    lib::*,
+    merkleization::{
+        Context, MerkleHasher, MerkleizationError, Merkleized, Node, Proof, Sha256Hasher,
+    },
+    ser::{Serialize, SerializeError},
+    SimpleSerialize, Sized,
+};
+
+/// The hash tree root of a value of type `T`.
+///
+/// A `Root<T>` is the "summary" of a `T`: it has the same SSZ representation as a
+/// [`Node`] and, because the hash tree root of a `Node` is itself, the same hash tree
+/// root as the `T` it summarizes. This means a field of type `T` in a container
+/// can be replaced with a `Root<T>` without changing the root of the container.
+/// Unlike a bare `Node`, the summarized type is tracked by the type system.
+///
+/// So is the hash function `H` the root is computed with, SHA-256 by default, so a [`Proof`]
+/// can only be verified against a root of the hash function it was produced with:
+///
+/// ```compile_fail
+/// # use ssz_rs::{prelude::*, MerkleHasher, MerkleizationContext, Proof, Root};
+/// #[derive(Default)]
+/// struct Reversed(ssz_rs::Sha256Hasher);
+///
+/// impl MerkleHasher for Reversed {
+///     fn hash_nodes(&mut self, left: &[u8], right: &[u8], out: &mut [u8]) {
+///         self.0.hash_nodes(right, left, out);
+///     }
+/// }
+///
+/// let mut value = Vector::<u64, 8>::default();
+/// let root = Root::summarize_with(&mut value, &MerkleizationContext::<Reversed>::new()).unwrap();
+/// let proof = Proof::<ssz_rs::Sha256Hasher>::new(Node::default(), vec![], 0);
+/// root.verify_proof(&proof);
+/// ```
+pub struct Root<T, H: MerkleHasher = Sha256Hasher> {
+    node: Node,
+    _summarized: PhantomData<fn() -> (T, H)>,
+}
+
+impl<T: Merkleized> Root<T> {
+    /// Summarize `value` by computing its hash tree root.
+    pub fn summarize(value: &mut T) -> Result<Self, MerkleizationError> {
+        Self::summarize_with(value, Context::global())
+    }
+
+    /// Return `true` if `value` is an expansion of this summary,
+    /// i.e. if `value` has this hash tree root.
+    pub fn is_expanded_by(&self, value: &mut T) -> Result<bool, MerkleizationError> {
+        self.is_expanded_by_with(value, Context::global())
+    }
+}
+
+impl<T: Merkleized, H: MerkleHasher> Root<T, H> {
+    /// Summarize `value` by computing its hash tree root with the hash function of `context`.
+    pub fn summarize_with(value: &mut T, context: &Context<H>) -> Result<Self, MerkleizationError> {
+        Ok(Self::from(value.hash_tree_root_with(context)?))
+    }
+
+    /// Return `true` if `value` is an expansion of this summary, i.e. if `value` has this hash
+    /// tree root with the hash function of `context`.
+    pub fn is_expanded_by_with(
+        &self,
+        value: &mut T,
+        context: &Context<H>,
+    ) -> Result<bool, MerkleizationError> {
+        Ok(value.hash_tree_root_with(context)? == self.node)
+    }
+}
+
+impl<T, H: MerkleHasher> Root<T, H> {
+    pub fn as_node(&self) -> &Node {
+        &self.node
+    }
+
+    /// Return `true` if `proof` proves a leaf of the `T` summarized by this root, hashing with
+    /// the hash function `H` of both.
+    pub fn verify_proof(&self, proof: &Proof<H>) -> bool {
+        proof.verify(&self.node)
+    }
+}
+
+impl<T, H: MerkleHasher> From<Node> for Root<T, H> {
+    fn from(node: Node) -> Self {
+        Self { node, _summarized: PhantomData }
+    }
+}
+
+impl<T, H: MerkleHasher> From<Root<T, H>> for Node {
+    fn from(root: Root<T, H>) -> Self {
+        root.node
+    }
+}
+
+impl<T, H: MerkleHasher> AsRef<[u8]> for Root<T, H> {
+    fn as_ref(&self) -> &[u8] {
+        self.node.as_ref()
+    }
+}
+
+impl<T, H: MerkleHasher> Default for Root<T, H> {
+    fn default() -> Self {
+        Self::from(Node::default())
+    }
+}
+
+impl<T, H: MerkleHasher> Clone for Root<T, H> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, H: MerkleHasher> Copy for Root<T, H> {}
+
+impl<T, H: MerkleHasher> PartialEq for Root<T, H> {
+    fn eq(&self, other: &Self) -> bool {
+        self.node == other.node
+    }
+}
+
+impl<T, H: MerkleHasher> Eq for Root<T, H> {}
+
+impl<T, H: MerkleHasher> Debug for Root<T, H> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Root<{}>({:#x})", any::type_name::<T>(), self.node)
+    }
+}
+
+impl<T, H: MerkleHasher> Display for Root<T, H> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.node)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T, H: MerkleHasher> serde::Serialize for Root<T, H> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serde::Serialize::serialize(&self.node, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T, H: MerkleHasher> serde::Deserialize<'de> for Root<T, H> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        <Node as serde::Deserialize>::deserialize(deserializer).map(Self::from)
+    }
+}
+
+impl<T, H: MerkleHasher> Sized for Root<T, H> {
+    fn is_variable_size() -> bool {
+        false
+    }
+
+    fn size_hint() -> usize {
+        Node::size_hint()
+    }
+}
+
+impl<T, H: MerkleHasher> Serialize for Root<T, H> {
+    fn serialize(&self, buffer: &mut Vec<u8>) -> Result<usize, SerializeError> {
+        self.node.serialize(buffer)
+    }
+}
+
+impl<T, H: MerkleHasher> Deserialize for Root<T, H> {
+    fn deserialize(encoding: &[u8]) -> Result<Self, DeserializeError> {
+        Node::deserialize(encoding).map(Self::from)
+    }
+}
+
+impl<T, S: MerkleHasher> Merkleized for Root<T, S> {
+    fn hash_tree_root_with<H: MerkleHasher>(
+        &mut self,
+        _: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
+        Ok(self.node)
+    }
+
+    const CHUNK_COUNT: usize = 1;
+}
+
+impl<T, H: MerkleHasher> SimpleSerialize for Root<T, H> {
+    const MAX_SIZE: usize = 32;
+
+    const IS_VARIABLE_SIZE: bool = false;
+
+    const NESTING_DEPTH: usize = 1;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{list::List, serialize, vector::Vector};
+
+    #[test]
+    fn test_root_summarizes_value() {
+        let mut value = List::<u64, 16>::try_from(vec![1u64, 2, 3]).unwrap();
+        let mut root = Root::summarize(&mut value).unwrap();
+        assert!(root.is_expanded_by(&mut value).unwrap());
+        assert_eq!(root.hash_tree_root().unwrap(), value.hash_tree_root().unwrap());
+        assert_eq!(serialize(&root).unwrap(), root.as_node().as_ref());
+
+        value.push(4).unwrap();
+        assert!(!root.is_expanded_by(&mut value).unwrap());
+
+        let recovered = Root::<List<u64, 16>>::deserialize(root.as_ref()).unwrap();
+        assert_eq!(recovered, root);
+    }
+
+    #[derive(Default)]
+    struct ReversedSha256Hasher(Sha256Hasher);
+
+    impl MerkleHasher for ReversedSha256Hasher {
+        fn hash_nodes(&mut self, left: &[u8], right: &[u8], out: &mut [u8]) {
+            self.0.hash_nodes(right, left, out);
+        }
+    }
+
+    #[test]
+    fn test_root_with_hasher() {
+        let leaves = [Node::try_from([1u8; 32].as_ref()).unwrap(), Node::default()];
+        let mut value = Vector::<Node, 2>::try_from(leaves.to_vec()).unwrap();
+
+        let context = Context::<ReversedSha256Hasher>::new();
+        let root = Root::summarize_with(&mut value, &context).unwrap();
+        assert!(root.is_expanded_by_with(&mut value, &context).unwrap());
+        assert!(root.verify_proof(&Proof::new(leaves[0], vec![leaves[1]], 0)));
+
+        let root = Root::summarize(&mut value).unwrap();
+        assert_ne!(
+            root.as_node(),
+            Root::<_, ReversedSha256Hasher>::summarize_with(&mut value, &context)
+                .unwrap()
+                .as_node()
+        );
+        assert!(root.verify_proof(&Proof::new(leaves[0], vec![leaves[1]], 0)));
+    }
+}
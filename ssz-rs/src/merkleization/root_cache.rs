@@ -0,0 +1,378 @@
+#[cfg(feature = "std")]
+use crate::{
+    de::{Deserialize, DeserializeError},
+    error::Error,
+    merkleization::{
+        Context, GeneralizedIndex, MerkleHasher, Merkleized, PathElement, PathError, Sha256Hasher,
+    },
+    ser::{Serialize, SerializeError},
+    Sized,
+};
+use crate::{
+    lib::*,
+    merkleization::{MerkleizationError, Node},
+    SimpleSerialize,
+};
+use sha2::{Digest, Sha256};
+#[cfg(feature = "std")]
+use std::sync::{Mutex, PoisonError};
+
+// the type of a value and the SHA-256 digest of its serialization
+type Key = (TypeId, [u8; 32]);
+
+/// A `RootCache` memoizes hash tree roots keyed by the type and a digest of the serialization
+/// of a value.
+///
+/// This is useful when the same (typically small, fixed-size) value is hashed repeatedly,
+/// e.g. validator records that rarely change between states, as the serialization is
+/// usually much cheaper to compute than the hash tree root. The cache holds at most
+/// `capacity` roots and evicts the least recently used root once full.
+///
+/// Only roots computed with SHA-256, i.e. with
+/// [`Sha256Hasher`](crate::merkleization::Sha256Hasher), are cached, as the key does not name
+/// the hash function. Wrap a type in [`Memoized`] to look up its roots in the process-wide
+/// cache returned by [`RootCache::global`] whenever it is merkleized, e.g. as the elements of a
+/// `List`.
+#[derive(Debug, Clone, Default)]
+pub struct RootCache {
+    capacity: usize,
+    clock: u64,
+    roots: BTreeMap<Key, (Node, u64)>,
+    recency: BTreeMap<u64, Key>,
+}
+
+#[cfg(feature = "std")]
+static GLOBAL: Mutex<RootCache> = Mutex::new(RootCache::new(0));
+
+impl RootCache {
+    /// Create an empty cache holding at most `capacity` roots.
+    pub const fn new(capacity: usize) -> Self {
+        Self { capacity, clock: 0, roots: BTreeMap::new(), recency: BTreeMap::new() }
+    }
+
+    /// Return the process-wide cache consulted by [`Memoized`] values.
+    ///
+    /// It starts with a capacity of 0, i.e. caching nothing, until a capacity is set with
+    /// [`RootCache::set_capacity`].
+    #[cfg(feature = "std")]
+    pub fn global() -> &'static Mutex<RootCache> {
+        &GLOBAL
+    }
+
+    /// Return the maximum number of cached roots.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Return the number of cached roots.
+    pub fn len(&self) -> usize {
+        self.roots.len()
+    }
+
+    /// Return whether no root is cached.
+    pub fn is_empty(&self) -> bool {
+        self.roots.is_empty()
+    }
+
+    /// Set the maximum number of cached roots, evicting the least recently used
+    /// roots if there are more than `capacity`.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.roots.len() > self.capacity {
+            self.evict();
+        }
+    }
+
+    /// Remove every cached root, keeping the capacity.
+    pub fn clear(&mut self) {
+        self.roots.clear();
+        self.recency.clear();
+    }
+
+    /// Return the SHA-256 hash tree root of `value`, computing and caching it if there
+    /// is no root for the serialization of `value` in the cache.
+    pub fn hash_tree_root<T>(&mut self, value: &mut T) -> Result<Node, MerkleizationError>
+    where
+        T: SimpleSerialize + 'static,
+    {
+        let key = key_of(value)?;
+        if let Some(root) = self.get(&key) {
+            return Ok(root)
+        }
+        let root = value.hash_tree_root()?;
+        self.insert(key, root);
+        Ok(root)
+    }
+
+    fn get(&mut self, key: &Key) -> Option<Node> {
+        self.clock += 1;
+        let (root, last_used) = self.roots.get_mut(key)?;
+        let key = self.recency.remove(last_used).expect("cached root has a recency entry");
+        *last_used = self.clock;
+        self.recency.insert(self.clock, key);
+        Some(*root)
+    }
+
+    fn insert(&mut self, key: Key, root: Node) {
+        if self.capacity == 0 || self.roots.contains_key(&key) {
+            return
+        }
+        if self.roots.len() == self.capacity {
+            self.evict();
+        }
+        self.clock += 1;
+        self.recency.insert(self.clock, key);
+        self.roots.insert(key, (root, self.clock));
+    }
+
+    fn evict(&mut self) {
+        if let Some((_, key)) = self.recency.pop_first() {
+            self.roots.remove(&key);
+        }
+    }
+}
+
+fn key_of<T: SimpleSerialize + 'static>(value: &T) -> Result<Key, MerkleizationError> {
+    let mut encoding = vec![];
+    value.serialize(&mut encoding)?;
+    Ok((TypeId::of::<T>(), Sha256::digest(&encoding).into()))
+}
+
+/// A value of type `T` whose SHA-256 hash tree root is looked up in the global [`RootCache`].
+///
+/// `Memoized<T>` has the same SSZ representation and root as `T` and dereferences to it. Hashing
+/// it consults [`RootCache::global`] by the serialization of the value and only computes the root
+/// of a value not in the cache, e.g. for records repeated across many states. The lock of the
+/// cache is not held while computing a root, so memoized values may be nested.
+///
+/// ```rust
+/// use ssz_rs::{prelude::*, Memoized, RootCache};
+///
+/// #[derive(Debug, Default, Clone, PartialEq, Eq, SimpleSerialize)]
+/// struct Validator {
+///     pubkey: Vector<u8, 48>,
+///     effective_balance: u64,
+/// }
+///
+/// RootCache::global().lock().unwrap().set_capacity(1024);
+///
+/// let validator = Validator { effective_balance: 32, ..Default::default() };
+/// let mut validators =
+///     List::<Memoized<Validator>, 16>::try_from(vec![Memoized::new(validator); 4]).unwrap();
+/// validators.hash_tree_root().unwrap();
+/// // the root of the validator is computed once and then looked up for the same validator
+/// assert_eq!(RootCache::global().lock().unwrap().len(), 1);
+/// assert_eq!(validators[0].effective_balance, 32);
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Memoized<T>(T);
+
+#[cfg(feature = "std")]
+impl<T> Memoized<T> {
+    pub fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> Deref for Memoized<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T> DerefMut for Memoized<T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: SimpleSerialize> Sized for Memoized<T> {
+    fn is_variable_size() -> bool {
+        T::is_variable_size()
+    }
+
+    fn size_hint() -> usize {
+        T::size_hint()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: SimpleSerialize> Serialize for Memoized<T> {
+    fn serialize(&self, buffer: &mut Vec<u8>) -> Result<usize, SerializeError> {
+        self.0.serialize(buffer)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: SimpleSerialize> Deserialize for Memoized<T> {
+    fn deserialize(encoding: &[u8]) -> Result<Self, DeserializeError> {
+        T::deserialize(encoding).map(Self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: SimpleSerialize + 'static> Merkleized for Memoized<T> {
+    fn hash_tree_root_with<H: MerkleHasher>(
+        &mut self,
+        context: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
+        // NOTE: the cache only holds roots computed with the default hash function
+        if TypeId::of::<H>() != TypeId::of::<Sha256Hasher>() {
+            return self.0.hash_tree_root_with(context)
+        }
+        let key = key_of(&self.0)?;
+        let cached = GLOBAL.lock().unwrap_or_else(PoisonError::into_inner).get(&key);
+        if let Some(root) = cached {
+            return Ok(root)
+        }
+        let root = self.0.hash_tree_root_with(context)?;
+        GLOBAL.lock().unwrap_or_else(PoisonError::into_inner).insert(key, root);
+        Ok(root)
+    }
+
+    fn leaves<H: MerkleHasher>(
+        &mut self,
+        context: &Context<H>,
+    ) -> Result<Vec<Node>, MerkleizationError> {
+        self.0.leaves(context)
+    }
+
+    const CHUNK_COUNT: usize = T::CHUNK_COUNT;
+
+    fn merkle_limit() -> Option<usize> {
+        T::merkle_limit()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: SimpleSerialize + 'static> SimpleSerialize for Memoized<T> {
+    fn is_composite_type() -> bool {
+        T::is_composite_type()
+    }
+
+    const PACKED_PER_CHUNK: usize = T::PACKED_PER_CHUNK;
+
+    fn nesting_depth() -> usize {
+        T::nesting_depth()
+    }
+
+    fn ssz_heap_size(&self) -> usize {
+        self.0.ssz_heap_size()
+    }
+
+    fn hash_tree_root_from_bytes<H: MerkleHasher>(
+        encoding: &[u8],
+        context: &Context<H>,
+    ) -> Result<Node, Error> {
+        T::hash_tree_root_from_bytes(encoding, context)
+    }
+
+    fn is_default(&self) -> bool {
+        self.0.is_default()
+    }
+
+    fn default_hash_tree_root_with<H: MerkleHasher>(
+        context: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
+        T::default_hash_tree_root_with(context)
+    }
+
+    const MAX_SIZE: usize = T::MAX_SIZE;
+
+    const IS_VARIABLE_SIZE: bool = T::IS_VARIABLE_SIZE;
+
+    const NESTING_DEPTH: usize = T::NESTING_DEPTH;
+
+    fn generalized_index_from(
+        root: GeneralizedIndex,
+        path: &[PathElement<'_>],
+    ) -> Result<GeneralizedIndex, PathError> {
+        T::generalized_index_from(root, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::Vector;
+
+    #[test]
+    fn test_root_cache() {
+        let mut cache = RootCache::new(2);
+        let mut a = Vector::<u8, 8>::try_from(vec![1u8; 8]).unwrap();
+        let mut b = 2u64;
+        let mut c = 3u64;
+
+        assert_eq!(cache.hash_tree_root(&mut a).unwrap(), a.hash_tree_root().unwrap());
+        assert_eq!(cache.hash_tree_root(&mut b).unwrap(), b.hash_tree_root().unwrap());
+        assert_eq!(cache.len(), 2);
+
+        // same bytes as `a` but a different type
+        let mut d = u64::from_le_bytes([1u8; 8]);
+        assert_eq!(cache.hash_tree_root(&mut a).unwrap(), a.hash_tree_root().unwrap());
+        assert_eq!(cache.hash_tree_root(&mut d).unwrap(), d.hash_tree_root().unwrap());
+        // `b` was the least recently used root
+        assert_eq!(cache.len(), 2);
+        assert!(!cache.roots.contains_key(&key_of(&b).unwrap()));
+
+        assert_eq!(cache.hash_tree_root(&mut c).unwrap(), c.hash_tree_root().unwrap());
+        cache.set_capacity(1);
+        assert_eq!(cache.len(), 1);
+        assert!(cache.roots.contains_key(&key_of(&c).unwrap()));
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_memoized() {
+        use crate::list::List;
+
+        // a type only hashed by this test, so other tests sharing the global cache do not
+        // interfere with its entries
+        type Record = Vector<u32, 9>;
+
+        RootCache::global().lock().unwrap().set_capacity(usize::MAX);
+        let record = Record::try_from((1..10).collect::<Vec<_>>()).unwrap();
+        let mut memoized = Memoized::new(record.clone());
+        let key = key_of(&record).unwrap();
+        assert!(!RootCache::global().lock().unwrap().roots.contains_key(&key));
+
+        let expected = record.clone().hash_tree_root().unwrap();
+        assert_eq!(memoized.hash_tree_root().unwrap(), expected);
+        assert!(RootCache::global().lock().unwrap().roots.contains_key(&key));
+        assert_eq!(memoized.hash_tree_root().unwrap(), expected);
+
+        // the elements of a list look up their roots in the cache
+        let mut list = List::<Memoized<Record>, 4>::try_from(vec![memoized.clone(); 3]).unwrap();
+        let mut plain = List::<Record, 4>::try_from(vec![record; 3]).unwrap();
+        assert_eq!(list.hash_tree_root().unwrap(), plain.hash_tree_root().unwrap());
+        assert_eq!(crate::serialize(&list).unwrap(), crate::serialize(&plain).unwrap());
+
+        // other hash functions bypass the cache
+        let context = Context::<ReversedHasher>::new();
+        assert_ne!(memoized.hash_tree_root_with(&context).unwrap(), expected);
+    }
+
+    #[cfg(feature = "std")]
+    #[derive(Default)]
+    struct ReversedHasher(Sha256Hasher);
+
+    #[cfg(feature = "std")]
+    impl MerkleHasher for ReversedHasher {
+        fn hash_nodes(&mut self, left: &[u8], right: &[u8], out: &mut [u8]) {
+            self.0.hash_nodes(right, left, out);
+        }
+    }
+}
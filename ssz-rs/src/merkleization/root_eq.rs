@@ -0,0 +1,142 @@
+use crate::{
+    lib::*,
+    merkleization::{Context, MerkleHasher, MerkleizationError, Merkleized, Node},
+};
+
+/// Return `true` if `a` and `b` have the same hash tree root with the hash function of the given
+/// `context`, or the error of the first of them which cannot be merkleized.
+///
+/// For types keeping their roots, e.g. a [`Cached`](crate::Cached) union hashed before, this is
+/// much cheaper than comparing the values field by field.
+pub fn roots_equal<T: Merkleized, H: MerkleHasher>(
+    a: &mut T,
+    b: &mut T,
+    context: &Context<H>,
+) -> Result<bool, MerkleizationError> {
+    Ok(a.hash_tree_root_with(context)? == b.hash_tree_root_with(context)?)
+}
+
+/// A value of type `T` compared with other values by its hash tree root.
+///
+/// The root is computed once when the `RootEq` is made, so the value can only be accessed
+/// immutably afterwards; [`RootEq::update`] changes the value and computes the root again.
+///
+/// ```rust
+/// use ssz_rs::{prelude::*, RootEq};
+///
+/// let values = (0..1000u64).collect::<Vec<_>>();
+/// let a = RootEq::new(List::<u64, 1024>::try_from(values.clone()).unwrap()).unwrap();
+/// let mut b = RootEq::new(List::<u64, 1024>::try_from(values).unwrap()).unwrap();
+/// assert_eq!(a, b);
+///
+/// b.update(|list| list.push(7).unwrap()).unwrap();
+/// assert_ne!(a, b);
+/// ```
+pub struct RootEq<T> {
+    value: T,
+    root: Node,
+}
+
+impl<T: Merkleized> RootEq<T> {
+    pub fn new(value: T) -> Result<Self, MerkleizationError> {
+        Self::new_with(value, Context::global())
+    }
+
+    pub fn new_with<H: MerkleHasher>(
+        mut value: T,
+        context: &Context<H>,
+    ) -> Result<Self, MerkleizationError> {
+        let root = value.hash_tree_root_with(context)?;
+        Ok(Self { value, root })
+    }
+
+    /// Change the value with `f` and compute its root again.
+    pub fn update<F: FnOnce(&mut T)>(&mut self, f: F) -> Result<(), MerkleizationError> {
+        self.update_with(f, Context::global())
+    }
+
+    /// Change the value with `f` and compute its root again with the hash function of the given
+    /// `context`, which should be the one the `RootEq` was made with.
+    pub fn update_with<F: FnOnce(&mut T), H: MerkleHasher>(
+        &mut self,
+        f: F,
+        context: &Context<H>,
+    ) -> Result<(), MerkleizationError> {
+        f(&mut self.value);
+        self.root = self.value.hash_tree_root_with(context)?;
+        Ok(())
+    }
+}
+
+impl<T> RootEq<T> {
+    /// Return the hash tree root the value is compared by.
+    pub fn root(&self) -> Node {
+        self.root
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for RootEq<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for RootEq<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RootEq").field("value", &self.value).field("root", &self.root).finish()
+    }
+}
+
+impl<T: Clone> Clone for RootEq<T> {
+    fn clone(&self) -> Self {
+        Self { value: self.value.clone(), root: self.root }
+    }
+}
+
+impl<T> PartialEq for RootEq<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.root == other.root
+    }
+}
+
+impl<T> Eq for RootEq<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prelude::*, Cached};
+
+    #[test]
+    fn test_roots_equal() {
+        let values = (0..100u64).collect::<Vec<_>>();
+        let mut a = Cached::new(Some(List::<u64, 128>::try_from(values.clone()).unwrap()));
+        let mut b = Cached::new(Some(List::<u64, 128>::try_from(values).unwrap()));
+        assert!(roots_equal(&mut a, &mut b, Context::global()).unwrap());
+        assert!(a.is_cached() && b.is_cached());
+        b.as_mut().unwrap().push(100).unwrap();
+        assert!(!roots_equal(&mut a, &mut b, Context::global()).unwrap());
+
+        // a value which cannot be merkleized is an error rather than unequal
+        let mut raw = crate::RawSsz::<List<u8, 2>>::new(vec![1, 2, 3]);
+        let mut other = crate::RawSsz::<List<u8, 2>>::default();
+        assert!(roots_equal(&mut raw, &mut other, Context::global()).is_err());
+        assert!(roots_equal(&mut other, &mut raw, Context::global()).is_err());
+
+        let a = RootEq::new(a.into_inner().unwrap()).unwrap();
+        let mut b = RootEq::new(b.into_inner().unwrap()).unwrap();
+        assert_ne!(a, b);
+        assert_eq!(b.len(), 101);
+        b.update(|list| {
+            list.pop();
+        })
+        .unwrap();
+        assert_eq!(a, b);
+        assert_eq!(b.root(), a.clone().into_inner().hash_tree_root().unwrap());
+    }
+}
@@ -0,0 +1,194 @@
+use crate::{
+    lib::*,
+    merkleization::{
+        hash_nodes, Context, MerkleHasher, MerkleizationError, Node, Sha256Hasher,
+        MAX_MERKLE_TREE_DEPTH,
+    },
+    prelude::*,
+};
+
+/// A hasher computing the root of a Merkle tree of a fixed `depth` from a stream of chunks,
+/// e.g. the chunks of a structure too large to hold in memory.
+///
+/// Only the roots of the complete subtrees covering the chunks pushed so far are kept, so the
+/// state is at most `depth + 1` nodes. It can be saved as a [`HasherCheckpoint`], e.g. encoded
+/// with SSZ, and resumed with [`StreamingHasher::from_checkpoint`] in another process.
+///
+/// ```rust
+/// use ssz_rs::prelude::*;
+/// use ssz_rs::{advanced::merkleize, StreamingHasher};
+///
+/// let chunks = (0..5u8).map(|i| Node::try_from([i; 32].as_ref()).unwrap()).collect::<Vec<_>>();
+///
+/// let mut hasher: StreamingHasher = StreamingHasher::new(3);
+/// for chunk in &chunks[..3] {
+///     hasher.push(*chunk).unwrap();
+/// }
+/// let checkpoint = serialize(&hasher.checkpoint()).unwrap();
+///
+/// let checkpoint = deserialize(&checkpoint).unwrap();
+/// let mut resumed: StreamingHasher = StreamingHasher::from_checkpoint(&checkpoint).unwrap();
+/// for chunk in &chunks[3..] {
+///     resumed.push(*chunk).unwrap();
+/// }
+/// let bytes = chunks.iter().flat_map(|chunk| chunk.as_ref().to_vec()).collect::<Vec<_>>();
+/// assert_eq!(resumed.root(), merkleize(&bytes, Some(8)).unwrap());
+/// ```
+#[derive(Debug, Clone)]
+pub struct StreamingHasher<H: MerkleHasher = Sha256Hasher> {
+    depth: usize,
+    count: u64,
+    // roots of the complete subtrees covering the chunks pushed so far, largest first
+    partials: Vec<Node>,
+    hasher: H,
+}
+
+/// The state of a [`StreamingHasher`], which can be encoded to resume hashing later.
+///
+/// The hash function is not recorded so a checkpoint must be resumed with the same one.
+#[derive(Default, Debug, Clone, PartialEq, Eq, SimpleSerialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HasherCheckpoint {
+    pub depth: u8,
+    pub count: u64,
+    pub partials: List<Node, MAX_MERKLE_TREE_DEPTH>,
+}
+
+impl StreamingHasher {
+    /// Compute the root of the tree with the chunks pushed so far, padded with zero chunks.
+    pub fn root(&self) -> Node {
+        self.root_with(Context::global())
+    }
+}
+
+impl<H: MerkleHasher> StreamingHasher<H> {
+    /// Construct a hasher for a tree with capacity for `2**depth` chunks.
+    pub fn new(depth: usize) -> Self {
+        assert!(depth < MAX_MERKLE_TREE_DEPTH, "tree depth must be less than 64");
+        Self { depth, count: 0, partials: vec![], hasher: H::default() }
+    }
+
+    /// Resume hashing from `checkpoint`.
+    ///
+    /// Returns `None` if the checkpoint is inconsistent, e.g. has more chunks than the tree.
+    pub fn from_checkpoint(checkpoint: &HasherCheckpoint) -> Option<Self> {
+        let depth = checkpoint.depth as usize;
+        if depth >= MAX_MERKLE_TREE_DEPTH ||
+            checkpoint.count > 1 << depth ||
+            checkpoint.partials.len() != checkpoint.count.count_ones() as usize
+        {
+            return None
+        }
+        let mut hasher = Self::new(depth);
+        hasher.count = checkpoint.count;
+        hasher.partials = checkpoint.partials.to_vec();
+        Some(hasher)
+    }
+
+    /// Return the state of the hasher, to resume hashing later.
+    pub fn checkpoint(&self) -> HasherCheckpoint {
+        HasherCheckpoint {
+            depth: self.depth as u8,
+            count: self.count,
+            partials: self.partials.clone().try_into().expect("at most one root per level"),
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Return the number of chunks pushed so far.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Append `chunk` to the chunks of the tree.
+    pub fn push(&mut self, chunk: Node) -> Result<(), MerkleizationError> {
+        if self.count == 1 << self.depth {
+            let capacity = usize::try_from(self.count).unwrap_or(usize::MAX);
+            return Err(MerkleizationError::InputExceedsLimit(capacity))
+        }
+        // NOTE: each trailing one of `count` is a subtree completed by the new chunk
+        let mut node = chunk;
+        for _ in 0..self.count.trailing_ones() {
+            let left = self.partials.pop().expect("one root per completed subtree");
+            node = hash(&mut self.hasher, &left, &node);
+        }
+        self.partials.push(node);
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Compute the root of the tree with the chunks pushed so far, padded with the zero hashes
+    /// of `context`.
+    pub fn root_with(&self, context: &Context<H>) -> Node {
+        if self.count == 1 << self.depth {
+            return self.partials[0]
+        }
+        let mut hasher = H::default();
+        let mut partials = self.partials.iter().rev();
+        let mut root: Option<Node> = None;
+        for height in 0..self.depth {
+            let zero = Node::try_from(&context[height]).expect("is valid node");
+            root = match (self.count & (1 << height) != 0, root) {
+                (true, root) => {
+                    let left = partials.next().expect("one root per set bit");
+                    Some(hash(&mut hasher, left, &root.unwrap_or(zero)))
+                }
+                (false, Some(root)) => Some(hash(&mut hasher, &root, &zero)),
+                (false, None) => None,
+            };
+        }
+        root.unwrap_or_else(|| Node::try_from(&context[self.depth]).expect("is valid node"))
+    }
+}
+
+fn hash<H: MerkleHasher>(hasher: &mut H, left: &Node, right: &Node) -> Node {
+    let mut root = Node::default();
+    hash_nodes(hasher, left.as_ref(), right.as_ref(), root.as_mut());
+    root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkleization::{merkleize, BYTES_PER_CHUNK};
+
+    fn chunk(i: u8) -> Node {
+        Node::try_from([i; BYTES_PER_CHUNK].as_ref()).unwrap()
+    }
+
+    #[test]
+    fn test_streaming_hasher() {
+        for depth in [0, 1, 4] {
+            let mut hasher = StreamingHasher::new(depth);
+            let mut chunks = vec![];
+            assert_eq!(hasher.root(), merkleize(&chunks, Some(1 << depth)).unwrap());
+            for i in 0..1u8 << depth {
+                hasher.push(chunk(i)).unwrap();
+                chunks.extend_from_slice(chunk(i).as_ref());
+                assert_eq!(hasher.root(), merkleize(&chunks, Some(1 << depth)).unwrap());
+
+                let checkpoint = deserialize(&serialize(&hasher.checkpoint()).unwrap()).unwrap();
+                hasher = StreamingHasher::from_checkpoint(&checkpoint).unwrap();
+                assert_eq!(hasher.count(), i as u64 + 1);
+            }
+            assert!(hasher.push(chunk(0)).is_err());
+        }
+    }
+
+    #[test]
+    fn test_inconsistent_checkpoint() {
+        let mut hasher = StreamingHasher::<Sha256Hasher>::new(2);
+        for i in 0..3 {
+            hasher.push(chunk(i)).unwrap();
+        }
+        let checkpoint = hasher.checkpoint();
+        assert_eq!(checkpoint.partials.len(), 2);
+        let resume = StreamingHasher::<Sha256Hasher>::from_checkpoint;
+        assert!(resume(&HasherCheckpoint { count: 4, ..checkpoint.clone() }).is_none());
+        assert!(resume(&HasherCheckpoint { count: 5, ..checkpoint.clone() }).is_none());
+        assert!(resume(&HasherCheckpoint { depth: 64, ..checkpoint }).is_none());
+    }
+}
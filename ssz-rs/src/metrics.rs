@@ -0,0 +1,102 @@
+//! Counters of the work done by this crate, e.g. to watch SSZ-related load in a node.
+//!
+//! The counters are process-wide and only updated with the `metrics` feature enabled,
+//! otherwise recording compiles to nothing. They count:
+//! - `hashes`: invocations of the hash function on a pair of nodes, wherever they happen
+//! - `chunks`: chunks merkleized, before any padding
+//! - `decoded_bytes`: bytes decoded with [`deserialize`](crate::deserialize) or an
+//!   [`SszDecoder`](crate::SszDecoder)
+#[cfg(feature = "metrics")]
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(feature = "metrics")]
+static HASHES: AtomicUsize = AtomicUsize::new(0);
+#[cfg(feature = "metrics")]
+static CHUNKS: AtomicUsize = AtomicUsize::new(0);
+#[cfg(feature = "metrics")]
+static DECODED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+/// A snapshot of the counters.
+#[cfg(feature = "metrics")]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Counters {
+    pub hashes: usize,
+    pub chunks: usize,
+    pub decoded_bytes: usize,
+}
+
+/// Return the current value of the counters.
+#[cfg(feature = "metrics")]
+pub fn snapshot() -> Counters {
+    Counters {
+        hashes: HASHES.load(Ordering::Relaxed),
+        chunks: CHUNKS.load(Ordering::Relaxed),
+        decoded_bytes: DECODED_BYTES.load(Ordering::Relaxed),
+    }
+}
+
+/// Reset the counters to zero, returning their value before the reset.
+#[cfg(feature = "metrics")]
+pub fn reset() -> Counters {
+    Counters {
+        hashes: HASHES.swap(0, Ordering::Relaxed),
+        chunks: CHUNKS.swap(0, Ordering::Relaxed),
+        decoded_bytes: DECODED_BYTES.swap(0, Ordering::Relaxed),
+    }
+}
+
+#[inline]
+pub(crate) fn record_hashes(_count: usize) {
+    #[cfg(feature = "metrics")]
+    HASHES.fetch_add(_count, Ordering::Relaxed);
+}
+
+#[inline]
+pub(crate) fn record_chunks(_count: usize) {
+    #[cfg(feature = "metrics")]
+    CHUNKS.fetch_add(_count, Ordering::Relaxed);
+}
+
+#[inline]
+pub(crate) fn record_decoded_bytes(_count: usize) {
+    #[cfg(feature = "metrics")]
+    DECODED_BYTES.fetch_add(_count, Ordering::Relaxed);
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod tests {
+    use super::*;
+    use crate::{
+        deserialize,
+        list::List,
+        merkleization::{Merkleized, Node, Sha256Hasher, StreamingHasher},
+        serialize,
+    };
+
+    #[test]
+    fn test_counters() {
+        let mut value = List::<u64, 16>::try_from(vec![1u64, 2, 3, 4, 5]).unwrap();
+        let encoding = serialize(&value).unwrap();
+
+        // NOTE: other tests may run concurrently so only check the counters grow
+        let before = snapshot();
+        let _ = value.hash_tree_root().unwrap();
+        let _: List<u64, 16> = deserialize(&encoding).unwrap();
+        let after = snapshot();
+        assert!(after.hashes >= before.hashes + 3);
+        assert!(after.chunks >= before.chunks + 2);
+        assert!(after.decoded_bytes >= before.decoded_bytes + encoding.len());
+    }
+
+    #[test]
+    fn test_hashes_outside_of_merkleize() {
+        let before = snapshot();
+        let _ = Node::hash_pair(&Node::default(), &Node::default());
+        let mut hasher = StreamingHasher::<Sha256Hasher>::new(2);
+        for i in 0..4u8 {
+            hasher.push(Node::try_from([i; 32].as_ref()).unwrap()).unwrap();
+        }
+        let after = snapshot();
+        assert!(after.hashes >= before.hashes + 4);
+    }
+}
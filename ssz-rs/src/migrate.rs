@@ -0,0 +1,331 @@
+//! Conversions of values between the versions of an SSZ type across forks, e.g. to upgrade the
+//! objects of a database when the containers gain or lose fields.
+//!
+//! [`Migrate`] builds a value from the value of an older (or newer) version of its type. It is
+//! implemented for unchanged basic types, for collections whose elements migrate, checking the
+//! bounds of the new type, and for an `Option` of a type which migrates.
+//!
+//! `#[derive(Migrate)]` implements it for a container from each container named by a
+//! `#[migrate(from = Type)]` attribute, migrating the fields of the same names, and from itself
+//! as is, for the containers which did not change. The fields of the new container which are
+//! missing from the old one need a hook:
+//!
+//! - `#[migrate(default)]` takes the default value of the field;
+//! - `#[migrate(with = path)]` calls `path(&old)`, returning a `Result<Field, MigrateError>`, which
+//!   can also compute the field from fields which were removed or changed.
+//!
+//! The fields of the old container missing from the new one are dropped, after a hook given as
+//! `#[migrate(from = Type, removed = path)]` calls `path(&old)`, returning a
+//! `Result<(), MigrateError>`, e.g. to refuse values whose removed fields still hold data.
+//!
+//! ```rust
+//! use ssz_rs::prelude::*;
+//! use ssz_rs::migrate::MigrateError;
+//!
+//! #[derive(Default, Debug, SimpleSerialize, Migrate)]
+//! struct Header {
+//!     slot: u64,
+//!     parent_root: Node,
+//! }
+//!
+//! #[derive(Default, Debug, SimpleSerialize)]
+//! struct BlockA {
+//!     header: Header,
+//!     deposits: List<u64, 16>,
+//!     legacy_flags: u8,
+//! }
+//!
+//! #[derive(Default, Debug, SimpleSerialize, Migrate)]
+//! #[migrate(from = BlockA, removed = no_legacy_flags)]
+//! struct BlockB {
+//!     header: Header,
+//!     deposits: List<u64, 32>,
+//!     #[migrate(with = deposit_count)]
+//!     deposit_count: u64,
+//!     #[migrate(default)]
+//!     withdrawals: List<u64, 16>,
+//! }
+//!
+//! fn no_legacy_flags(block: &BlockA) -> Result<(), MigrateError> {
+//!     if block.legacy_flags != 0 {
+//!         return Err(MigrateError::rejected("legacy flags are set"))
+//!     }
+//!     Ok(())
+//! }
+//!
+//! fn deposit_count(block: &BlockA) -> Result<u64, MigrateError> {
+//!     Ok(block.deposits.len() as u64)
+//! }
+//!
+//! let mut block = BlockA::default();
+//! block.deposits.push(32).unwrap();
+//! let upgraded = BlockB::migrate(block).unwrap();
+//! assert_eq!(upgraded.deposit_count, 1);
+//!
+//! let block = BlockA { legacy_flags: 1, ..Default::default() };
+//! assert!(BlockB::migrate(block).is_err());
+//! ```
+use crate::{
+    bitlist::Bitlist,
+    bitvector::Bitvector,
+    error::{convert_elements, Error, ErrorPath, InstanceError},
+    lib::*,
+    list::List,
+    merkleization::Node,
+    uint::U256,
+    utf8_list::Utf8List,
+    vector::Vector,
+    SimpleSerialize,
+};
+
+/// The reason a value could not be migrated.
+#[derive(Debug)]
+pub enum MigrateErrorKind {
+    /// The value does not fit the new type, e.g. a list beyond its new bound.
+    Invalid(Error),
+    /// A hook refused the value, for the given reason.
+    Rejected(String),
+}
+
+/// An error migrating a value, with the path to the part which could not be migrated, e.g.
+/// `header.slot`.
+#[derive(Debug)]
+pub struct MigrateError {
+    pub path: String,
+    pub kind: MigrateErrorKind,
+}
+
+impl MigrateError {
+    pub fn new(kind: MigrateErrorKind) -> Self {
+        Self { path: String::new(), kind }
+    }
+
+    /// An error of a hook refusing the value for the given `reason`.
+    pub fn rejected(reason: impl Into<String>) -> Self {
+        Self::new(MigrateErrorKind::Rejected(reason.into()))
+    }
+
+    /// Prefix the path of the error with the field or index `segment` of the enclosing value.
+    pub fn within(self, segment: &str) -> Self {
+        ErrorPath::within(self, segment)
+    }
+}
+
+impl ErrorPath for MigrateError {
+    fn path_mut(&mut self) -> &mut String {
+        &mut self.path
+    }
+}
+
+impl From<Error> for MigrateError {
+    fn from(err: Error) -> Self {
+        Self::new(MigrateErrorKind::Invalid(err))
+    }
+}
+
+impl Display for MigrateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() { "the value" } else { &self.path };
+        match &self.kind {
+            MigrateErrorKind::Invalid(err) => write!(f, "invalid value at {path}: {err}"),
+            MigrateErrorKind::Rejected(reason) => write!(f, "rejected value at {path}: {reason}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MigrateError {}
+
+/// A type which can be built from the value of another version `F` of the type.
+pub trait Migrate<F>: core::marker::Sized {
+    fn migrate(from: F) -> Result<Self, MigrateError>;
+}
+
+macro_rules! define_migrate_as_is {
+    ($(<$(const $n:ident: usize),*> $ty:ty),*) => {
+        $(
+            impl<$(const $n: usize),*> Migrate<$ty> for $ty {
+                fn migrate(from: $ty) -> Result<Self, MigrateError> {
+                    Ok(from)
+                }
+            }
+        )*
+    };
+}
+
+define_migrate_as_is!(
+    <> u8, <> u16, <> u32, <> u64, <> u128, <> usize, <> bool, <> U256, <> Node,
+    <const N: usize> Bitvector<N>
+);
+
+impl<T, F, const N: usize, const M: usize> Migrate<List<F, M>> for List<T, N>
+where
+    T: SimpleSerialize + Migrate<F>,
+    F: SimpleSerialize,
+{
+    fn migrate(from: List<F, M>) -> Result<Self, MigrateError> {
+        if from.len() > N {
+            let err = InstanceError::bounded::<Self>(N, from.len());
+            return Err(Error::Instance(err).into())
+        }
+        let elements = convert_elements(from.into_inner(), T::migrate)?;
+        Ok(Self::try_from(elements).map_err(|(_, err)| err)?)
+    }
+}
+
+impl<T, F, const N: usize, const M: usize> Migrate<Vector<F, M>> for Vector<T, N>
+where
+    T: SimpleSerialize + Migrate<F>,
+    F: SimpleSerialize,
+{
+    fn migrate(from: Vector<F, M>) -> Result<Self, MigrateError> {
+        if M != N {
+            let err = InstanceError::exact::<Self>(N, M);
+            return Err(Error::Instance(err).into())
+        }
+        let elements = convert_elements(from.into_inner(), T::migrate)?;
+        Ok(Self::try_from(elements).map_err(|(_, err)| err)?)
+    }
+}
+
+impl<const N: usize, const M: usize> Migrate<Bitlist<M>> for Bitlist<N> {
+    fn migrate(from: Bitlist<M>) -> Result<Self, MigrateError> {
+        if from.len() > N {
+            let err = InstanceError::bounded::<Self>(N, from.len());
+            return Err(Error::Instance(err).into())
+        }
+        Ok(Self::from_raw_bytes(from.as_raw_bytes(), from.len()).expect("bits are within bound"))
+    }
+}
+
+impl<const N: usize, const M: usize> Migrate<Utf8List<M>> for Utf8List<N> {
+    fn migrate(from: Utf8List<M>) -> Result<Self, MigrateError> {
+        Ok(Self::try_from(from.as_str())?)
+    }
+}
+
+impl<T: Migrate<F>, F> Migrate<Option<F>> for Option<T> {
+    fn migrate(from: Option<F>) -> Result<Self, MigrateError> {
+        from.map(T::migrate).transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[derive(Default, Debug, PartialEq, Eq, SimpleSerialize)]
+    struct CheckpointA {
+        epoch: u64,
+        root: Node,
+    }
+
+    #[derive(Default, Debug, PartialEq, Eq, SimpleSerialize)]
+    struct LegacyCheckpoint {
+        epoch: u64,
+        root: Node,
+        slot: u64,
+    }
+
+    #[derive(Default, Debug, PartialEq, Eq, SimpleSerialize, Migrate)]
+    #[migrate(from = CheckpointA)]
+    #[migrate(from = LegacyCheckpoint, removed = check_slot)]
+    struct CheckpointB {
+        root: Node,
+        epoch: u64,
+    }
+
+    #[derive(Default, Debug, PartialEq, Eq, SimpleSerialize)]
+    struct StateA {
+        slot: u64,
+        checkpoints: List<CheckpointA, 4>,
+        justified: Option<CheckpointA>,
+        bits: Bitlist<8>,
+        removed: u32,
+    }
+
+    #[derive(Default, Debug, PartialEq, Eq, SimpleSerialize, Migrate)]
+    #[migrate(from = StateA, removed = check_removed)]
+    struct StateB {
+        #[migrate(with = next_slot)]
+        slot: u64,
+        checkpoints: List<CheckpointB, 2>,
+        justified: Option<CheckpointB>,
+        bits: Bitlist<16>,
+        #[migrate(default)]
+        added: Vector<u8, 2>,
+    }
+
+    fn check_slot(from: &LegacyCheckpoint) -> Result<(), MigrateError> {
+        if from.slot / 32 != from.epoch {
+            return Err(MigrateError::rejected("slot is not in the epoch").within("slot"))
+        }
+        Ok(())
+    }
+
+    fn next_slot(from: &StateA) -> Result<u64, MigrateError> {
+        Ok(from.slot + 1)
+    }
+
+    fn check_removed(from: &StateA) -> Result<(), MigrateError> {
+        if from.removed != 0 {
+            return Err(MigrateError::rejected("removed field is set").within("removed"))
+        }
+        Ok(())
+    }
+
+    fn checkpoint(epoch: u64) -> CheckpointA {
+        CheckpointA { epoch, root: Node::try_from(&[epoch as u8; 32][..]).unwrap() }
+    }
+
+    #[test]
+    fn test_migrate() {
+        let mut state = StateA {
+            slot: 9,
+            checkpoints: List::try_from(vec![checkpoint(1), checkpoint(2)]).unwrap(),
+            justified: Some(checkpoint(3)),
+            ..Default::default()
+        };
+        state.bits.push(true).unwrap();
+        let upgraded = StateB::migrate(state).unwrap();
+        assert_eq!(upgraded.slot, 10);
+        assert_eq!(upgraded.checkpoints.len(), 2);
+        assert_eq!(upgraded.checkpoints[1], CheckpointB { epoch: 2, root: checkpoint(2).root });
+        assert_eq!(upgraded.justified.unwrap().epoch, 3);
+        assert_eq!(upgraded.bits.len(), 1);
+        assert_eq!(upgraded.added, Vector::default());
+
+        let legacy = LegacyCheckpoint { epoch: 2, root: checkpoint(2).root, slot: 64 };
+        let upgraded = CheckpointB::migrate(legacy).unwrap();
+        assert_eq!(upgraded, CheckpointB::migrate(checkpoint(2)).unwrap());
+        let legacy = LegacyCheckpoint { epoch: 2, root: checkpoint(2).root, slot: 1 };
+        assert_eq!(CheckpointB::migrate(legacy).unwrap_err().path, "slot");
+
+        let state = StateA { removed: 1, ..Default::default() };
+        let err = StateB::migrate(state).unwrap_err();
+        assert_eq!(err.path, "removed");
+        assert!(matches!(err.kind, MigrateErrorKind::Rejected(..)));
+
+        let checkpoints = List::<_, 4>::try_from((0..3).map(checkpoint).collect::<Vec<_>>());
+        let state = StateA { checkpoints: checkpoints.unwrap(), ..Default::default() };
+        let err = StateB::migrate(state).unwrap_err();
+        assert_eq!(err.path, "checkpoints");
+        assert!(matches!(
+            err.kind,
+            MigrateErrorKind::Invalid(Error::Instance(InstanceError::Bounded {
+                bound: 2,
+                provided: 3,
+                ..
+            }))
+        ));
+        assert!(err.to_string().starts_with("invalid value at checkpoints: "));
+
+        let err = Vector::<u8, 3>::migrate(Vector::<u8, 2>::default()).unwrap_err();
+        assert!(matches!(err.kind, MigrateErrorKind::Invalid(..)));
+        let mut bits = Bitlist::<8>::default();
+        bits.resize(5, true).unwrap();
+        assert!(Bitlist::<4>::migrate(bits.clone()).is_err());
+        assert_eq!(Bitlist::<5>::migrate(bits).unwrap().count_ones(), 5);
+    }
+}
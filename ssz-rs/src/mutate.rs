@@ -0,0 +1,335 @@
+//! Corruptions of valid encodings guided by their SSZ type, to feed negative tests of decoders.
+//!
+//! Unlike random bit flips, which mostly hit the bytes of integers, the mutations target the
+//! structure of the encoding: the offsets of variable-size parts, the lengths of lists, the
+//! selectors of unions, the bytes of booleans and the padding and delimiting bits of bitfields.
+//!
+//! ```rust
+//! use ssz_rs::prelude::*;
+//! use ssz_rs::mutate::{mutate, mutations};
+//!
+//! #[derive(Default, Debug, SimpleSerialize, Describe)]
+//! struct Message {
+//!     flag: bool,
+//!     data: List<u16, 8>,
+//!     bits: Bitlist<16>,
+//! }
+//!
+//! let message = Message {
+//!     flag: true,
+//!     data: List::try_from(vec![1, 2, 3]).unwrap(),
+//!     bits: Bitlist::from_iter([true, false]),
+//! };
+//! let encoding = serialize(&message).unwrap();
+//! assert!(!mutations(&encoding, &Message::describe()).is_empty());
+//!
+//! for seed in 0..100 {
+//!     let corrupted = mutate(&encoding, &Message::describe(), seed);
+//!     assert_ne!(corrupted, encoding);
+//!     // a decoder must not panic on the corrupted encoding, which may still be valid
+//!     let _ = Message::deserialize(&corrupted);
+//! }
+//! ```
+use crate::{compat::TypeDescriptor, lib::*, ser::BYTES_PER_LENGTH_OFFSET, test_value::mix};
+
+/// Where the end of a variable-size part of an encoding is set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum End {
+    /// The part ends with the encoding.
+    Encoding,
+    /// The part ends where the part with the offset at this position starts.
+    Offset(usize),
+}
+
+/// A corruption of an encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mutation {
+    /// Replace the offset at `at` with `value`.
+    Offset { at: usize, value: u32 },
+    /// Replace the selector of a union at `at` with `value`.
+    Selector { at: usize, value: u8 },
+    /// Replace the byte at `at` with `value`, e.g. a boolean or the last byte of a bitfield.
+    Byte { at: usize, value: u8 },
+    /// Move the `end` of a part by `delta` bytes, e.g. to add or remove an element of a list.
+    Resize { end: End, delta: isize },
+}
+
+impl Mutation {
+    /// Return `encoding` with this mutation applied.
+    pub fn apply(&self, encoding: &[u8]) -> Vec<u8> {
+        let mut result = encoding.to_vec();
+        match *self {
+            Self::Offset { at, value } => write_offset(&mut result, at, value),
+            Self::Selector { at, value } | Self::Byte { at, value } => {
+                if let Some(byte) = result.get_mut(at) {
+                    *byte = value;
+                }
+            }
+            Self::Resize { end: End::Encoding, delta } => {
+                let len = result.len().saturating_add_signed(delta);
+                result.resize(len, 0);
+            }
+            Self::Resize { end: End::Offset(at), delta } => {
+                if let Some(offset) = read_offset(&result, at) {
+                    write_offset(&mut result, at, offset.wrapping_add(delta as u32));
+                }
+            }
+        }
+        result
+    }
+}
+
+impl Display for Mutation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Offset { at, value } => write!(f, "offset at {at} set to {value}"),
+            Self::Selector { at, value } => write!(f, "selector at {at} set to {value}"),
+            Self::Byte { at, value } => write!(f, "byte at {at} set to {value:#04x}"),
+            Self::Resize { end: End::Encoding, delta } => {
+                write!(f, "end of the encoding moved by {delta}")
+            }
+            Self::Resize { end: End::Offset(at), delta } => {
+                write!(f, "offset at {at} moved by {delta}")
+            }
+        }
+    }
+}
+
+/// Return the structural mutations of `encoding`, a valid encoding of a value of `schema`.
+///
+/// The parts of the encoding which are not valid for `schema` are skipped.
+pub fn mutations(encoding: &[u8], schema: &TypeDescriptor) -> Vec<Mutation> {
+    let mut result = vec![];
+    walk(schema, 0, encoding, End::Encoding, &mut result);
+    result
+}
+
+/// Return `encoding` corrupted by one of its [`mutations`] chosen by `seed`, or with a bit
+/// flipped if it has none. The same seed always selects the same corruption.
+pub fn mutate(encoding: &[u8], schema: &TypeDescriptor, seed: u64) -> Vec<u8> {
+    let candidates = mutations(encoding, schema);
+    let choice = mix(seed, 0) as usize;
+    let mutation = match candidates.get(choice % candidates.len().max(1)) {
+        Some(mutation) => *mutation,
+        None if encoding.is_empty() => Mutation::Resize { end: End::Encoding, delta: 1 },
+        None => {
+            let at = choice % encoding.len();
+            let bit = mix(seed, 1) % 8;
+            Mutation::Byte { at, value: encoding[at] ^ (1 << bit) }
+        }
+    };
+    mutation.apply(encoding)
+}
+
+fn read_offset(encoding: &[u8], at: usize) -> Option<u32> {
+    let bytes = encoding.get(at..at.checked_add(BYTES_PER_LENGTH_OFFSET)?)?;
+    Some(u32::from_le_bytes(bytes.try_into().expect("is offset")))
+}
+
+fn write_offset(encoding: &mut [u8], at: usize, value: u32) {
+    if let Some(bytes) = encoding.get_mut(at..at.saturating_add(BYTES_PER_LENGTH_OFFSET)) {
+        bytes.copy_from_slice(&value.to_le_bytes());
+    }
+}
+
+// Collect the mutations of `bytes`, the part of the encoding at `base` holding a value of `ty`.
+fn walk(ty: &TypeDescriptor, base: usize, bytes: &[u8], end: End, out: &mut Vec<Mutation>) {
+    match ty {
+        TypeDescriptor::Uint(..) => {}
+        TypeDescriptor::Boolean => {
+            if bytes.len() == 1 {
+                out.push(Mutation::Byte { at: base, value: 2 });
+            }
+        }
+        TypeDescriptor::Bitvector(length) => {
+            if let Some(last) = bytes.last().filter(|_| length % 8 != 0) {
+                // NOTE: the highest bit of the last byte is padding
+                out.push(Mutation::Byte { at: base + bytes.len() - 1, value: last | 0x80 });
+            }
+        }
+        TypeDescriptor::Bitlist(..) => {
+            if let Some(last) = bytes.last() {
+                let at = base + bytes.len() - 1;
+                // remove the delimiting bit, or move it past the bits
+                out.push(Mutation::Byte { at, value: 0 });
+                if last & 0x80 == 0 {
+                    out.push(Mutation::Byte { at, value: last | 0x80 });
+                }
+            }
+            out.push(Mutation::Resize { end, delta: 1 });
+        }
+        TypeDescriptor::Vector(element, length) => {
+            walk_elements(element, Some(*length), base, bytes, end, out)
+        }
+        TypeDescriptor::List(element, _) => walk_elements(element, None, base, bytes, end, out),
+        TypeDescriptor::Container(fields) => {
+            walk_composite(fields.iter().map(|(_, ty)| ty), base, bytes, end, out)
+        }
+        TypeDescriptor::Union(variants) => {
+            let (selector, value) = match bytes.split_first() {
+                Some(split) => split,
+                None => return,
+            };
+            out.push(Mutation::Selector { at: base, value: variants.len() as u8 });
+            if (*selector as usize) + 1 < variants.len() {
+                out.push(Mutation::Selector { at: base, value: selector + 1 });
+            }
+            if *selector > 0 {
+                out.push(Mutation::Selector { at: base, value: selector - 1 });
+            }
+            if let Some(Some(ty)) = variants.get(*selector as usize) {
+                walk(ty, base + 1, value, end, out);
+            }
+        }
+    }
+}
+
+// Collect the mutations of the elements of type `element` of a vector of `length` elements
+// or of a list.
+fn walk_elements(
+    element: &TypeDescriptor,
+    length: Option<usize>,
+    base: usize,
+    bytes: &[u8],
+    end: End,
+    out: &mut Vec<Mutation>,
+) {
+    if element.is_variable_size() {
+        let count = match (length, read_offset(bytes, 0)) {
+            (Some(length), _) => length,
+            (None, Some(first)) if first != 0 && first as usize % BYTES_PER_LENGTH_OFFSET == 0 => {
+                // one element more or less, as given by the first offset
+                let offset = BYTES_PER_LENGTH_OFFSET as u32;
+                out.push(Mutation::Offset { at: base, value: first + offset });
+                out.push(Mutation::Offset { at: base, value: first - offset });
+                first as usize / BYTES_PER_LENGTH_OFFSET
+            }
+            (None, _) => {
+                out.push(Mutation::Resize { end, delta: BYTES_PER_LENGTH_OFFSET as isize });
+                return
+            }
+        };
+        walk_composite((0..count).map(|_| element), base, bytes, end, out);
+        return
+    }
+
+    let size = element.size_hint();
+    if size == 0 {
+        return
+    }
+    if length.is_none() {
+        // one element more or less, and a length which is not a multiple of the element size
+        out.push(Mutation::Resize { end, delta: size as isize });
+        if !bytes.is_empty() {
+            out.push(Mutation::Resize { end, delta: -(size as isize) });
+        }
+        if size > 1 {
+            out.push(Mutation::Resize { end, delta: 1 });
+        }
+    }
+    let count = length.unwrap_or(usize::MAX);
+    for (i, chunk) in bytes.chunks_exact(size).take(count).enumerate() {
+        walk(element, base + i * size, chunk, end, out);
+    }
+}
+
+// Collect the mutations of the parts of a composite of the types `types`.
+fn walk_composite<'a>(
+    types: impl Iterator<Item = &'a TypeDescriptor>,
+    base: usize,
+    bytes: &[u8],
+    end: End,
+    out: &mut Vec<Mutation>,
+) {
+    let mut cursor = 0;
+    let mut variable = vec![];
+    for ty in types {
+        if ty.is_variable_size() {
+            if cursor + BYTES_PER_LENGTH_OFFSET > bytes.len() {
+                return
+            }
+            variable.push((ty, cursor));
+            cursor += BYTES_PER_LENGTH_OFFSET;
+        } else {
+            let size = ty.size_hint();
+            let part = match bytes.get(cursor..cursor + size) {
+                Some(part) => part,
+                None => return,
+            };
+            walk(ty, base + cursor, part, end, out);
+            cursor += size;
+        }
+    }
+
+    for (i, (ty, at)) in variable.iter().enumerate() {
+        let offset = read_offset(bytes, *at).expect("offset is in bounds");
+        let past_end = (bytes.len() as u32).wrapping_add(1);
+        for value in [0, offset.wrapping_sub(1), offset.wrapping_add(1), past_end, u32::MAX] {
+            out.push(Mutation::Offset { at: base + at, value });
+        }
+        let start = offset as usize;
+        let (stop, part_end) = match variable.get(i + 1) {
+            Some((_, next)) => {
+                let stop = read_offset(bytes, *next).expect("offset is in bounds") as usize;
+                (stop, End::Offset(base + next))
+            }
+            None => (bytes.len(), end),
+        };
+        if start < cursor || start > stop || stop > bytes.len() {
+            continue
+        }
+        walk(ty, base + start, &bytes[start..stop], part_end, out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        compat::Describe,
+        prelude::*,
+        test_utils::{outer, Outer},
+    };
+
+    #[test]
+    fn test_mutations() {
+        let encoding = serialize(&outer()).unwrap();
+        let candidates = mutations(&encoding, &Outer::describe());
+        // the delimiting bit of `inner.b`, the first variable-size part
+        assert!(candidates.contains(&Mutation::Byte { at: 69, value: 0 }));
+        assert!(candidates.contains(&Mutation::Byte { at: 69, value: 0x8d }));
+        // the selector of `union`, one past the last variant and the neighbouring variant
+        let union = u32::from_le_bytes(encoding[52..56].try_into().unwrap()) as usize;
+        for value in [3, 1] {
+            assert!(candidates.contains(&Mutation::Selector { at: union, value }));
+        }
+        // the length of the `b` of the first element of `list` moves the start of the second
+        assert!(candidates.contains(&Mutation::Resize { end: End::Offset(74), delta: 1 }));
+        // the value of the entry of `map`, the last part
+        assert!(candidates.contains(&Mutation::Byte { at: encoding.len() - 1, value: 2 }));
+
+        for mutation in candidates {
+            let corrupted = mutation.apply(&encoding);
+            assert_ne!(corrupted, encoding, "{mutation}");
+            // NOTE: some mutations still give valid encodings, e.g. the length of a list
+            if let Ok(value) = Outer::deserialize(&corrupted) {
+                assert_ne!(value, outer(), "{mutation}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_mutate() {
+        let encoding = serialize(&outer()).unwrap();
+        let schema = Outer::describe();
+        assert_eq!(mutate(&encoding, &schema, 3), mutate(&encoding, &schema, 3));
+        for seed in 0..64 {
+            assert_ne!(mutate(&encoding, &schema, seed), encoding);
+        }
+        // a bit is flipped in encodings without any structure
+        let corrupted = mutate(&[7, 0, 0, 0], &TypeDescriptor::Uint(32), 1);
+        assert_eq!(corrupted.len(), 4);
+        assert_ne!(corrupted, [7, 0, 0, 0]);
+        assert_eq!(mutate(&[], &TypeDescriptor::Uint(32), 1), [0]);
+    }
+}
@@ -0,0 +1,455 @@
+//! Python bindings to encode, decode and hash SSZ values, for research code to check its
+//! results against this implementation.
+//!
+//! Every type is exposed as an [`SszType`] with the methods `serialize`, `deserialize` and
+//! `hash_tree_root`. Values are passed as the Python objects of their JSON in the beacon API
+//! (see [`crate::json`]), where `bytes` are also accepted in place of `0x`-prefixed hex.
+//!
+//! A Rust type is registered with [`add_type`], so values are checked by its own implementation
+//! of [`SimpleSerialize`]. Types can also be described at runtime by passing a schema to the
+//! `SszType` constructor:
+//!
+//! - `"boolean"` or `"uintN"` for `N` in `8, 16, 32, 64, 128, 256`
+//! - `["Vector", element, length]` and `["List", element, limit]`
+//! - `["Bitvector", length]` and `["Bitlist", limit]`
+//! - `["Union", variant, ...]` with `None` for the `None` variant
+//! - a `dict` of the fields of a container to their schemas, in order
+//!
+//! ```python
+//! checkpoint = consensus.SszType({"epoch": "uint64", "root": ["Vector", "uint8", 32]})
+//! encoding = checkpoint.serialize({"epoch": 7, "root": bytes(32)})
+//! assert checkpoint.hash_tree_root(encoding) == consensus.Checkpoint.hash_tree_root(encoding)
+//! ```
+//!
+//! The module above is built as a `cdylib` from:
+//!
+//! ```rust
+//! use ssz_rs::{prelude::*, python::{self, pyo3::prelude::*}};
+//!
+//! #[derive(Default, Debug, SimpleSerialize, Describe)]
+//! struct Checkpoint {
+//!     epoch: u64,
+//!     root: Node,
+//! }
+//!
+//! #[pymodule]
+//! #[pyo3(crate = "ssz_rs::python::pyo3")]
+//! fn consensus(_py: Python<'_>, module: &PyModule) -> PyResult<()> {
+//!     python::add_class(module)?;
+//!     python::add_type::<Checkpoint>(module, "Checkpoint")
+//! }
+//! ```
+// NOTE: `pymethods` defines impls within the functions it generates
+#![allow(non_local_definitions)]
+use crate::{
+    compat::{Describe, TypeDescriptor},
+    error::Error,
+    hex,
+    json::{transcode_json_to_ssz, transcode_ssz_to_json},
+    lib::*,
+    merkleization::{
+        merkleize, mix_in_length, mix_in_selector, pack_bytes, Context, Node, BYTES_PER_CHUNK,
+    },
+    utils::serialize,
+    SimpleSerialize,
+};
+pub use pyo3;
+use pyo3::{
+    exceptions::PyValueError,
+    prelude::*,
+    types::{PyBool, PyBytes, PyDict, PyFloat, PyList, PyLong, PyString, PyTuple},
+};
+use serde_json::{Map, Number, Value};
+
+/// The operations on the encodings of a registered Rust type.
+#[derive(Clone, Copy)]
+struct TypeOperations {
+    encode: fn(&[u8]) -> Result<Vec<u8>, Error>,
+    hash_tree_root: fn(&[u8]) -> Result<Node, Error>,
+}
+
+fn encode<T: SimpleSerialize>(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let value = T::deserialize(data)?;
+    serialize(&value).map_err(Error::from)
+}
+
+fn hash_tree_root<T: SimpleSerialize>(data: &[u8]) -> Result<Node, Error> {
+    let mut value = T::deserialize(data)?;
+    value.hash_tree_root().map_err(Error::from)
+}
+
+/// An SSZ type exposed to Python, either registered from Rust or described at runtime.
+#[pyclass(module = "ssz_rs")]
+pub struct SszType {
+    name: String,
+    schema: TypeDescriptor,
+    operations: Option<TypeOperations>,
+}
+
+impl SszType {
+    /// Return the type exposing the Rust type `T` as `name`.
+    pub fn of<T: SimpleSerialize + Describe>(name: &str) -> Self {
+        let operations =
+            TypeOperations { encode: encode::<T>, hash_tree_root: hash_tree_root::<T> };
+        Self { name: name.to_string(), schema: T::describe(), operations: Some(operations) }
+    }
+
+    pub fn schema(&self) -> &TypeDescriptor {
+        &self.schema
+    }
+}
+
+#[pymethods]
+impl SszType {
+    /// Return the type described by `schema`.
+    #[new]
+    fn new(schema: &PyAny) -> PyResult<Self> {
+        let schema = parse_schema(schema)?;
+        Ok(Self { name: schema.to_string(), schema, operations: None })
+    }
+
+    #[getter]
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn __repr__(&self) -> String {
+        format!("SszType({})", self.name)
+    }
+
+    /// Return the encoding of `value`.
+    fn serialize<'py>(&self, py: Python<'py>, value: &PyAny) -> PyResult<&'py PyBytes> {
+        let json = to_json(value)?;
+        let mut encoding = transcode_json_to_ssz(&self.schema, &json).map_err(value_error)?;
+        if let Some(operations) = self.operations {
+            encoding = (operations.encode)(&encoding).map_err(value_error)?;
+        }
+        Ok(PyBytes::new(py, &encoding))
+    }
+
+    /// Return the value with the encoding `data`.
+    fn deserialize(&self, py: Python<'_>, data: &[u8]) -> PyResult<PyObject> {
+        if let Some(operations) = self.operations {
+            (operations.encode)(data).map_err(value_error)?;
+        }
+        let json = transcode_ssz_to_json(&self.schema, data).map_err(value_error)?;
+        Ok(from_json(py, &json))
+    }
+
+    /// Return the hash tree root of the value with the encoding `data`.
+    fn hash_tree_root<'py>(&self, py: Python<'py>, data: &[u8]) -> PyResult<&'py PyBytes> {
+        let root = match self.operations {
+            Some(operations) => (operations.hash_tree_root)(data).map_err(value_error)?,
+            None => {
+                let json = transcode_ssz_to_json(&self.schema, data).map_err(value_error)?;
+                root_of(&self.schema, &json)?
+            }
+        };
+        Ok(PyBytes::new(py, root.as_ref()))
+    }
+}
+
+/// Add the [`SszType`] class to `module`, to describe types at runtime.
+pub fn add_class(module: &PyModule) -> PyResult<()> {
+    module.add_class::<SszType>()
+}
+
+/// Add the Rust type `T` to `module` as the [`SszType`] named `name`.
+pub fn add_type<T: SimpleSerialize + Describe>(module: &PyModule, name: &str) -> PyResult<()> {
+    module.add(name, Py::new(module.py(), SszType::of::<T>(name))?)
+}
+
+fn value_error<E: Display>(err: E) -> PyErr {
+    PyValueError::new_err(err.to_string())
+}
+
+fn parse_length(value: Option<&PyAny>) -> PyResult<usize> {
+    match value {
+        Some(value) => value.extract(),
+        None => Err(PyValueError::new_err("expected a length in the schema")),
+    }
+}
+
+fn parse_schema(schema: &PyAny) -> PyResult<TypeDescriptor> {
+    if let Ok(name) = schema.downcast::<PyString>() {
+        return match name.to_str()? {
+            "boolean" => Ok(TypeDescriptor::Boolean),
+            "uint8" => Ok(TypeDescriptor::Uint(8)),
+            "uint16" => Ok(TypeDescriptor::Uint(16)),
+            "uint32" => Ok(TypeDescriptor::Uint(32)),
+            "uint64" => Ok(TypeDescriptor::Uint(64)),
+            "uint128" => Ok(TypeDescriptor::Uint(128)),
+            "uint256" => Ok(TypeDescriptor::Uint(256)),
+            name => Err(PyValueError::new_err(format!("unknown type `{name}` in the schema"))),
+        }
+    }
+    if let Ok(fields) = schema.downcast::<PyDict>() {
+        let fields = fields
+            .iter()
+            .map(|(name, schema)| Ok((name.extract::<String>()?, parse_schema(schema)?)))
+            .collect::<PyResult<Vec<_>>>()?;
+        return Ok(TypeDescriptor::Container(fields))
+    }
+    let parts = match (schema.downcast::<PyList>(), schema.downcast::<PyTuple>()) {
+        (Ok(parts), _) => parts.iter().collect::<Vec<_>>(),
+        (_, Ok(parts)) => parts.iter().collect::<Vec<_>>(),
+        _ => return Err(PyValueError::new_err("expected a string, list or dict in the schema")),
+    };
+    let kind = match parts.first() {
+        Some(kind) => kind.extract::<&str>()?,
+        None => return Err(PyValueError::new_err("expected the kind of a composite type")),
+    };
+    let element = || match parts.get(1) {
+        Some(element) => parse_schema(element).map(Box::new),
+        None => Err(PyValueError::new_err("expected the type of the elements in the schema")),
+    };
+    match kind {
+        "Vector" => Ok(TypeDescriptor::Vector(element()?, parse_length(parts.get(2).copied())?)),
+        "List" => Ok(TypeDescriptor::List(element()?, parse_length(parts.get(2).copied())?)),
+        "Bitvector" => Ok(TypeDescriptor::Bitvector(parse_length(parts.get(1).copied())?)),
+        "Bitlist" => Ok(TypeDescriptor::Bitlist(parse_length(parts.get(1).copied())?)),
+        "Union" => {
+            let variants = parts[1..]
+                .iter()
+                .map(
+                    |variant| {
+                        if variant.is_none() {
+                            Ok(None)
+                        } else {
+                            parse_schema(variant).map(Some)
+                        }
+                    },
+                )
+                .collect::<PyResult<Vec<_>>>()?;
+            Ok(TypeDescriptor::Union(variants))
+        }
+        kind => Err(PyValueError::new_err(format!("unknown type `{kind}` in the schema"))),
+    }
+}
+
+fn to_json(value: &PyAny) -> PyResult<Value> {
+    if value.is_none() {
+        return Ok(Value::Null)
+    }
+    // NOTE: `bool` is a subclass of `int` in Python so it is checked first
+    if let Ok(value) = value.downcast::<PyBool>() {
+        return Ok(Value::Bool(value.is_true()))
+    }
+    if let Ok(value) = value.downcast::<PyLong>() {
+        return match value.extract::<u64>() {
+            Ok(value) => Ok(Value::from(value)),
+            // NOTE: larger integers are written as decimal strings like in the JSON of the API
+            Err(_) => Ok(Value::String(value.str()?.to_str()?.to_string())),
+        }
+    }
+    if let Ok(value) = value.downcast::<PyFloat>() {
+        return Ok(Number::from_f64(value.value()).map_or(Value::Null, Value::Number))
+    }
+    if let Ok(value) = value.downcast::<PyString>() {
+        return Ok(Value::String(value.to_str()?.to_string()))
+    }
+    if let Ok(value) = value.downcast::<PyBytes>() {
+        return Ok(Value::String(hex::encode_0x(value.as_bytes())))
+    }
+    if let Ok(value) = value.downcast::<PyDict>() {
+        let mut object = Map::new();
+        for (key, value) in value {
+            object.insert(key.extract()?, to_json(value)?);
+        }
+        return Ok(Value::Object(object))
+    }
+    if let Ok(value) = value.downcast::<PyList>() {
+        return value.iter().map(to_json).collect::<PyResult<_>>().map(Value::Array)
+    }
+    if let Ok(value) = value.downcast::<PyTuple>() {
+        return value.iter().map(to_json).collect::<PyResult<_>>().map(Value::Array)
+    }
+    Err(PyValueError::new_err(format!("can not convert a `{}` to a value", value.get_type())))
+}
+
+fn from_json(py: Python<'_>, json: &Value) -> PyObject {
+    match json {
+        Value::Null => py.None(),
+        Value::Bool(value) => value.into_py(py),
+        Value::Number(number) => match (number.as_u64(), number.as_i64()) {
+            (Some(value), _) => value.into_py(py),
+            (_, Some(value)) => value.into_py(py),
+            _ => number.as_f64().into_py(py),
+        },
+        Value::String(value) => value.into_py(py),
+        Value::Array(elements) => {
+            PyList::new(py, elements.iter().map(|element| from_json(py, element))).into_py(py)
+        }
+        Value::Object(object) => {
+            let dict = PyDict::new(py);
+            for (key, value) in object {
+                dict.set_item(key, from_json(py, value)).expect("can set an item of a new dict");
+            }
+            dict.into_py(py)
+        }
+    }
+}
+
+// Return the root of the chunks of `bytes` padded to `limit` chunks.
+fn merkleize_bytes(mut bytes: Vec<u8>, limit: Option<usize>) -> PyResult<Node> {
+    pack_bytes(&mut bytes);
+    merkleize(&bytes, limit).map_err(value_error)
+}
+
+// Return the hash tree root of the value of type `ty` written as `json`, which must be valid
+// for the type.
+fn root_of(ty: &TypeDescriptor, json: &Value) -> PyResult<Node> {
+    let context = Context::global();
+    match ty {
+        TypeDescriptor::Uint(..) | TypeDescriptor::Boolean => {
+            merkleize_bytes(transcode_json_to_ssz(ty, json).map_err(value_error)?, None)
+        }
+        TypeDescriptor::Bitvector(length) => {
+            let bytes = transcode_json_to_ssz(ty, json).map_err(value_error)?;
+            merkleize_bytes(bytes, Some((length + 255) / 256))
+        }
+        TypeDescriptor::Bitlist(limit) => {
+            let mut bytes = transcode_json_to_ssz(ty, json).map_err(value_error)?;
+            let last = bytes.pop().expect("a valid bitlist has a delimiting bit");
+            let delimiter = 7 - last.leading_zeros() as usize;
+            let length = 8 * bytes.len() + delimiter;
+            bytes.push(last ^ (1 << delimiter));
+            let root = merkleize_bytes(bytes, Some((limit + 255) / 256))?;
+            Ok(mix_in_length(&root, length, context))
+        }
+        TypeDescriptor::Vector(element, length) if element.is_basic() => {
+            let bytes = transcode_json_to_ssz(ty, json).map_err(value_error)?;
+            let size = element.size_hint();
+            merkleize_bytes(bytes, Some((length * size + BYTES_PER_CHUNK - 1) / BYTES_PER_CHUNK))
+        }
+        TypeDescriptor::List(element, limit) if element.is_basic() => {
+            let bytes = transcode_json_to_ssz(ty, json).map_err(value_error)?;
+            let size = element.size_hint();
+            let length = bytes.len() / size;
+            let root = merkleize_bytes(
+                bytes,
+                Some((limit * size + BYTES_PER_CHUNK - 1) / BYTES_PER_CHUNK),
+            )?;
+            Ok(mix_in_length(&root, length, context))
+        }
+        TypeDescriptor::Vector(element, length) => {
+            let elements = json.as_array().expect("a valid vector is an array");
+            let root = merkleize_bytes(roots_of(element, elements)?, Some(*length))?;
+            Ok(root)
+        }
+        TypeDescriptor::List(element, limit) => {
+            let elements = json.as_array().expect("a valid list is an array");
+            let root = merkleize_bytes(roots_of(element, elements)?, Some(*limit))?;
+            Ok(mix_in_length(&root, elements.len(), context))
+        }
+        TypeDescriptor::Container(fields) => {
+            let mut chunks = Vec::with_capacity(fields.len() * BYTES_PER_CHUNK);
+            for (name, ty) in fields {
+                chunks.extend_from_slice(root_of(ty, &json[name.as_str()])?.as_ref());
+            }
+            merkleize_bytes(chunks, None)
+        }
+        TypeDescriptor::Union(variants) => {
+            let selector = match &json["selector"] {
+                Value::String(digits) => digits.parse().ok(),
+                selector => selector.as_u64().map(|selector| selector as usize),
+            }
+            .expect("a valid union has a selector");
+            let root = match &variants[selector] {
+                Some(ty) => root_of(ty, &json["value"])?,
+                None => Node::default(),
+            };
+            Ok(mix_in_selector(&root, selector, context))
+        }
+    }
+}
+
+fn roots_of(element: &TypeDescriptor, elements: &[Value]) -> PyResult<Vec<u8>> {
+    let mut chunks = Vec::with_capacity(elements.len() * BYTES_PER_CHUNK);
+    for element_json in elements {
+        chunks.extend_from_slice(root_of(element, element_json)?.as_ref());
+    }
+    Ok(chunks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[derive(Default, Debug, SimpleSerialize, Describe)]
+    struct Attestation {
+        aggregation_bits: Bitlist<16>,
+        data: Checkpoint,
+        committees: Vector<u16, 3>,
+        signature: Vector<u8, 4>,
+        proofs: List<Checkpoint, 4>,
+        option: Option<u32>,
+    }
+
+    #[derive(Default, Debug, Clone, SimpleSerialize, Describe)]
+    struct Checkpoint {
+        epoch: u64,
+        root: Node,
+    }
+
+    #[test]
+    fn test_python() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let module = PyModule::new(py, "consensus").unwrap();
+            add_class(module).unwrap();
+            add_type::<Attestation>(module, "Attestation").unwrap();
+            let locals = PyDict::new(py);
+            locals.set_item("consensus", module).unwrap();
+            py.run(
+                r#"
+schema = {
+    "aggregation_bits": ["Bitlist", 16],
+    "data": {"epoch": "uint64", "root": ["Vector", "uint8", 32]},
+    "committees": ["Vector", "uint16", 3],
+    "signature": ["Vector", "uint8", 4],
+    "proofs": ["List", {"epoch": "uint64", "root": ["Vector", "uint8", 32]}, 4],
+    "option": ["Union", None, "uint32"],
+}
+dynamic = consensus.SszType(schema)
+value = {
+    "aggregation_bits": "0x0b",
+    "data": {"epoch": 2**40, "root": bytes(range(32))},
+    "committees": [1, "2", 3],
+    "signature": "0xdeadbeef",
+    "proofs": [{"epoch": 1, "root": bytes(32)}],
+    "option": {"selector": 1, "value": 7},
+}
+encoding = dynamic.serialize(value)
+assert encoding == consensus.Attestation.serialize(value)
+assert dynamic.deserialize(encoding) == consensus.Attestation.deserialize(encoding)
+assert dynamic.deserialize(encoding)["data"]["epoch"] == str(2**40)
+root = consensus.Attestation.hash_tree_root(encoding)
+assert dynamic.hash_tree_root(encoding) == root
+assert consensus.Attestation.name == "Attestation"
+"#,
+                None,
+                Some(locals),
+            )
+            .unwrap();
+
+            let encoding = locals.get_item("encoding").unwrap().unwrap();
+            let encoding = encoding.extract::<&[u8]>().unwrap();
+            let mut attestation = Attestation::deserialize(encoding).unwrap();
+            assert_eq!(attestation.data.epoch, 1 << 40);
+            let root = locals.get_item("root").unwrap().unwrap();
+            let expected = attestation.hash_tree_root().unwrap();
+            assert_eq!(root.extract::<&[u8]>().unwrap(), expected.as_ref());
+
+            let dynamic = locals.get_item("dynamic").unwrap().unwrap();
+            let mut truncated = encoding.to_vec();
+            truncated.pop();
+            let err = dynamic.call_method1("deserialize", (PyBytes::new(py, &truncated),));
+            assert!(err.unwrap_err().is_instance_of::<PyValueError>(py));
+            let err =
+                module.getattr("SszType").unwrap().call1((("Vector", "uint7", 2),)).unwrap_err();
+            assert!(err.is_instance_of::<PyValueError>(py));
+        });
+    }
+}
@@ -0,0 +1,389 @@
+//! Read a part of an encoded value by its path, e.g. `/validators/1234/effective_balance`,
+//! without decoding the rest of the value.
+//!
+//! A path is a sequence of segments each starting with `/` like a JSON pointer: the name of a
+//! field of a container, the index of an element of a list or vector, the index of a bit of a
+//! bitfield, or the selector of the variant of a union, which must be the selected one. Only the
+//! offsets on the way to the part are read, so a query is cheap even on a large beacon state.
+//!
+//! ```rust
+//! use ssz_rs::prelude::*;
+//! use ssz_rs::query::{query, Scalar};
+//!
+//! #[derive(Default, Debug, SimpleSerialize, Describe)]
+//! struct State {
+//!     slot: u64,
+//!     balances: List<u64, 1024>,
+//!     slashings: Bitlist<1024>,
+//! }
+//!
+//! let state = State {
+//!     slot: 7,
+//!     balances: List::try_from((0..100).collect::<Vec<_>>()).unwrap(),
+//!     slashings: Bitlist::from_iter((0..100).map(|i| i == 13)),
+//! };
+//! let encoding = serialize(&state).unwrap();
+//!
+//! let balance = query::<State>(&encoding, "/balances/42").unwrap();
+//! assert_eq!(balance.as_u64(), Some(42));
+//! assert_eq!(query::<State>(&encoding, "/slashings/13").unwrap(), Scalar::Bool(true));
+//! assert!(query::<State>(&encoding, "/balances/100").is_err());
+//! ```
+use crate::{
+    compat::{Describe, TypeDescriptor},
+    de::{Deserialize, DeserializeError},
+    lib::*,
+    ser::BYTES_PER_LENGTH_OFFSET,
+    uint::U256,
+};
+
+/// A basic value or bytes read from an encoding by [`query`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Scalar {
+    Bool(bool),
+    /// An unsigned integer of any size.
+    Uint(U256),
+    /// The bytes of a `Vector<u8, N>` or a `List<u8, N>`.
+    Bytes(Vec<u8>),
+}
+
+impl Scalar {
+    /// Return the integer, or `None` if this is not an integer or it does not fit in a `u64`.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Self::Uint(value) => {
+                let bytes = value.to_bytes_le();
+                if bytes.iter().skip(8).any(|byte| *byte != 0) {
+                    return None
+                }
+                let mut le_bytes = [0u8; 8];
+                let len = bytes.len().min(8);
+                le_bytes[..len].copy_from_slice(&bytes[..len]);
+                Some(u64::from_le_bytes(le_bytes))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The reason a path could not be read from an encoding.
+#[derive(Debug)]
+pub enum QueryErrorKind {
+    /// A segment does not start with `/`.
+    InvalidPath,
+    /// The container has no field with this name.
+    UnknownField(String),
+    /// The segment is not an index within the length of the collection, or the selector of the
+    /// selected variant of a union.
+    OutOfRange,
+    /// The path leads to a composite value which is not bytes, see [`resolve`].
+    NotScalar,
+    /// The encoding is not valid on the way to the part.
+    Deserialize(DeserializeError),
+}
+
+impl From<DeserializeError> for QueryErrorKind {
+    fn from(err: DeserializeError) -> Self {
+        Self::Deserialize(err)
+    }
+}
+
+/// An error reading a path from an encoding, with the prefix of the path up to and including the
+/// segment which could not be read.
+#[derive(Debug)]
+pub struct QueryError {
+    pub path: String,
+    pub kind: QueryErrorKind,
+}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let path = &self.path;
+        match &self.kind {
+            QueryErrorKind::InvalidPath => write!(f, "invalid path `{path}`"),
+            QueryErrorKind::UnknownField(name) => write!(f, "no field `{name}` at `{path}`"),
+            QueryErrorKind::OutOfRange => write!(f, "index out of range at `{path}`"),
+            QueryErrorKind::NotScalar => write!(f, "the value at `{path}` is not a scalar"),
+            QueryErrorKind::Deserialize(err) => write!(f, "invalid encoding at `{path}`: {err}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for QueryError {}
+
+/// A part of an encoding resolved by [`resolve`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Part<'a> {
+    /// The encoding of a value of this type.
+    Value(&'a TypeDescriptor, &'a [u8]),
+    /// A bit of a bitfield.
+    Bit(bool),
+}
+
+/// Return the scalar at `path` in the `encoding` of a value of type `T`.
+pub fn query<T: Describe>(encoding: &[u8], path: &str) -> Result<Scalar, QueryError> {
+    let schema = T::describe();
+    let error = |kind| QueryError { path: path.into(), kind };
+    let (ty, bytes) = match resolve(&schema, encoding, path)? {
+        Part::Value(ty, bytes) => (ty, bytes),
+        Part::Bit(bit) => return Ok(Scalar::Bool(bit)),
+    };
+    match ty {
+        TypeDescriptor::Boolean => {
+            bool::deserialize(bytes).map(Scalar::Bool).map_err(|err| error(err.into()))
+        }
+        TypeDescriptor::Uint(bits) => {
+            let size = bits / 8;
+            let (provided, expected) = (bytes.len(), size);
+            if provided < expected {
+                let err = DeserializeError::ExpectedFurtherInput { provided, expected };
+                return Err(error(err.into()))
+            }
+            if provided > expected {
+                let err = DeserializeError::AdditionalInput { provided, expected };
+                return Err(error(err.into()))
+            }
+            let mut le_bytes = [0u8; 32];
+            le_bytes[..size].copy_from_slice(bytes);
+            Ok(Scalar::Uint(U256::from_bytes_le(le_bytes)))
+        }
+        TypeDescriptor::Vector(element, _) | TypeDescriptor::List(element, _)
+            if **element == TypeDescriptor::Uint(8) =>
+        {
+            Ok(Scalar::Bytes(bytes.to_vec()))
+        }
+        _ => Err(error(QueryErrorKind::NotScalar)),
+    }
+}
+
+fn read_offset(bytes: &[u8], at: usize) -> Result<usize, DeserializeError> {
+    match bytes.get(at..at + BYTES_PER_LENGTH_OFFSET) {
+        Some(offset) => Ok(u32::deserialize(offset)? as usize),
+        None => Err(DeserializeError::ExpectedFurtherInput {
+            provided: bytes.len(),
+            expected: at + BYTES_PER_LENGTH_OFFSET,
+        }),
+    }
+}
+
+// Return the range of the part at `index` of a composite of the types `types` encoded in `bytes`.
+fn part_range<'t>(
+    types: impl Iterator<Item = &'t TypeDescriptor>,
+    index: usize,
+    bytes: &[u8],
+) -> Result<Range<usize>, DeserializeError> {
+    let mut cursor = 0;
+    let mut target = None;
+    for (i, ty) in types.enumerate() {
+        let size = if ty.is_variable_size() { BYTES_PER_LENGTH_OFFSET } else { ty.size_hint() };
+        match target {
+            Some(Range { ref mut end, .. }) if ty.is_variable_size() => {
+                // the part ends where the next variable-size part starts
+                *end = read_offset(bytes, cursor)?;
+                break
+            }
+            None if i == index && ty.is_variable_size() => {
+                target = Some(read_offset(bytes, cursor)?..bytes.len());
+            }
+            None if i == index => return check_range(cursor..cursor + size, bytes),
+            _ => {}
+        }
+        cursor += size;
+    }
+    let range = target.unwrap_or(0..0);
+    if range.start > range.end || range.end > bytes.len() {
+        return Err(DeserializeError::ExpectedFurtherInput {
+            provided: bytes.len(),
+            expected: range.start.max(range.end),
+        })
+    }
+    Ok(range)
+}
+
+fn check_range(range: Range<usize>, bytes: &[u8]) -> Result<Range<usize>, DeserializeError> {
+    if range.end > bytes.len() {
+        return Err(DeserializeError::ExpectedFurtherInput {
+            provided: bytes.len(),
+            expected: range.end,
+        })
+    }
+    Ok(range)
+}
+
+/// Return the part at `path` in the `encoding` of a value of type `schema`, e.g. to decode a
+/// composite value with its own type.
+pub fn resolve<'a>(
+    schema: &'a TypeDescriptor,
+    encoding: &'a [u8],
+    path: &str,
+) -> Result<Part<'a>, QueryError> {
+    let mut ty = schema;
+    let mut bytes = encoding;
+    let mut rest = path;
+    while !rest.is_empty() {
+        let (segment, next) = match rest.strip_prefix('/') {
+            Some(rest) => rest
+                .split_once('/')
+                .map_or((rest, ""), |(segment, _)| (segment, &rest[segment.len()..])),
+            None => return Err(QueryError { path: path.into(), kind: QueryErrorKind::InvalidPath }),
+        };
+        let prefix = &path[..path.len() - next.len()];
+        let error = |kind| QueryError { path: prefix.into(), kind };
+        let segment = segment.replace("~1", "/").replace("~0", "~");
+        let index = || segment.parse::<usize>().map_err(|_| error(QueryErrorKind::OutOfRange));
+        let range = match ty {
+            TypeDescriptor::Container(fields) => {
+                let index = fields
+                    .iter()
+                    .position(|(name, _)| *name == segment)
+                    .ok_or_else(|| error(QueryErrorKind::UnknownField(segment.clone())))?;
+                let range = part_range(fields.iter().map(|(_, ty)| ty), index, bytes)
+                    .map_err(|err| error(err.into()))?;
+                ty = &fields[index].1;
+                range
+            }
+            TypeDescriptor::Vector(element, _) | TypeDescriptor::List(element, _) => {
+                let index = index()?;
+                let range = if element.is_variable_size() {
+                    let count = match bytes.len() {
+                        0 => 0,
+                        _ => {
+                            read_offset(bytes, 0).map_err(|err| error(err.into()))? /
+                                BYTES_PER_LENGTH_OFFSET
+                        }
+                    };
+                    if index >= count {
+                        return Err(error(QueryErrorKind::OutOfRange))
+                    }
+                    part_range((0..count).map(|_| &**element), index, bytes)
+                } else {
+                    let size = element.size_hint();
+                    if size == 0 || index >= bytes.len() / size {
+                        return Err(error(QueryErrorKind::OutOfRange))
+                    }
+                    check_range(index * size..(index + 1) * size, bytes)
+                };
+                ty = element;
+                range.map_err(|err| error(err.into()))?
+            }
+            TypeDescriptor::Bitvector(length) | TypeDescriptor::Bitlist(length) => {
+                let index = index()?;
+                let length = match ty {
+                    TypeDescriptor::Bitlist(..) => match bytes.last() {
+                        Some(last) if *last != 0 => {
+                            8 * (bytes.len() - 1) + 7 - last.leading_zeros() as usize
+                        }
+                        _ => return Err(error(DeserializeError::InvalidByte(0).into())),
+                    },
+                    _ => *length,
+                };
+                if index >= length || !next.is_empty() {
+                    return Err(error(QueryErrorKind::OutOfRange))
+                }
+                return match bytes.get(index / 8) {
+                    Some(byte) => Ok(Part::Bit(byte >> (index % 8) & 1 == 1)),
+                    None => Err(error(QueryErrorKind::OutOfRange)),
+                }
+            }
+            TypeDescriptor::Union(variants) => {
+                let index = index()?;
+                match (bytes.first(), variants.get(index)) {
+                    (Some(selector), Some(Some(variant))) if *selector as usize == index => {
+                        ty = variant;
+                        1..bytes.len()
+                    }
+                    _ => return Err(error(QueryErrorKind::OutOfRange)),
+                }
+            }
+            TypeDescriptor::Uint(..) | TypeDescriptor::Boolean => {
+                return Err(error(QueryErrorKind::OutOfRange))
+            }
+        };
+        bytes = &bytes[range];
+        rest = next;
+    }
+    Ok(Part::Value(ty, bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[derive(Default, Debug, SimpleSerialize, Describe)]
+    struct Attestation {
+        bits: Bitlist<16>,
+        slot: u64,
+        indices: List<u16, 8>,
+    }
+
+    #[derive(Default, Debug, SimpleSerialize, Describe)]
+    struct Block {
+        slot: u64,
+        attestations: List<Attestation, 4>,
+        graffiti: Vector<u8, 4>,
+        extra: List<u8, 8>,
+        flags: Bitvector<12>,
+        root: U256,
+        option: Option<u32>,
+    }
+
+    #[test]
+    fn test_query() {
+        let attestation = |slot| Attestation {
+            bits: Bitlist::from_iter([true, false, true]),
+            slot,
+            indices: List::try_from(vec![slot as u16; slot as usize]).unwrap(),
+        };
+        let block = Block {
+            slot: 9,
+            attestations: List::try_from(vec![attestation(1), attestation(2), attestation(3)])
+                .unwrap(),
+            graffiti: Vector::from([1, 2, 3, 4]),
+            extra: List::try_from(vec![5u8, 6]).unwrap(),
+            flags: Bitvector::from_iter((0..12).map(|i| i == 11)),
+            root: U256::from(u64::MAX),
+            option: Some(77),
+        };
+        let encoding = serialize(&block).unwrap();
+        let read = |path| query::<Block>(&encoding, path);
+
+        assert_eq!(read("/slot").unwrap().as_u64(), Some(9));
+        assert_eq!(read("/attestations/2/slot").unwrap().as_u64(), Some(3));
+        assert_eq!(read("/attestations/1/indices/1").unwrap().as_u64(), Some(2));
+        assert_eq!(read("/attestations/0/bits/2").unwrap(), Scalar::Bool(true));
+        assert_eq!(read("/attestations/0/bits/1").unwrap(), Scalar::Bool(false));
+        assert_eq!(read("/graffiti").unwrap(), Scalar::Bytes(vec![1, 2, 3, 4]));
+        assert_eq!(read("/extra").unwrap(), Scalar::Bytes(vec![5, 6]));
+        assert_eq!(read("/extra/1").unwrap().as_u64(), Some(6));
+        assert_eq!(read("/flags/11").unwrap(), Scalar::Bool(true));
+        assert_eq!(read("/root").unwrap(), Scalar::Uint(U256::from(u64::MAX)));
+        assert_eq!(read("/option/1").unwrap().as_u64(), Some(77));
+
+        let schema = Block::describe();
+        let part = resolve(&schema, &encoding, "/attestations/1").unwrap();
+        match part {
+            Part::Value(ty, bytes) => {
+                assert_eq!(*ty, Attestation::describe());
+                assert_eq!(Attestation::deserialize(bytes).unwrap().slot, 2);
+            }
+            Part::Bit(..) => unreachable!(),
+        }
+
+        let kind = |path| read(path).unwrap_err().kind;
+        assert!(matches!(kind("/attestations/3/slot"), QueryErrorKind::OutOfRange));
+        assert!(matches!(kind("/attestations/0/bits/3"), QueryErrorKind::OutOfRange));
+        assert!(matches!(kind("/attestations/0/bits/0/1"), QueryErrorKind::OutOfRange));
+        assert!(matches!(kind("/option/0"), QueryErrorKind::OutOfRange));
+        assert!(matches!(kind("/slot/0"), QueryErrorKind::OutOfRange));
+        assert!(matches!(kind("/attestations"), QueryErrorKind::NotScalar));
+        assert!(matches!(kind("slot"), QueryErrorKind::InvalidPath));
+        assert!(matches!(kind("/state"), QueryErrorKind::UnknownField(name) if name == "state"));
+        assert_eq!(read("/attestations/7/slot").unwrap_err().path, "/attestations/7");
+
+        let truncated = &encoding[..encoding.len() - 10];
+        let err = query::<Block>(truncated, "/attestations/2/indices/0").unwrap_err();
+        assert!(matches!(err.kind, QueryErrorKind::Deserialize(..)));
+    }
+}
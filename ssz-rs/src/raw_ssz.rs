@@ -0,0 +1,231 @@
+use crate::{
+    de::{Deserialize, DeserializeError},
+    error::Error,
+    lib::*,
+    merkleization::{
+        Context as MerkleizationContext, GeneralizedIndex, MerkleHasher, MerkleizationError,
+        Merkleized, Node, PathElement, PathError,
+    },
+    ser::{Serialize, SerializeError},
+    SimpleSerialize, Sized,
+};
+
+/// The encoding of a value of type `T`, kept as it is rather than decoded.
+///
+/// As a field of a container, `RawSsz<T>` has the same encoding as `T` but decoding it only
+/// checks the length of the field, so the bytes are re-encoded verbatim without interpreting
+/// them, e.g. to route a payload by the other fields of a message. The bytes are decoded
+/// as a `T` only when requested with [`RawSsz::decode`] or when computing the hash tree root,
+/// which is the root of the `T` they encode and fails if they do not encode a `T`.
+///
+/// ```rust
+/// use ssz_rs::{prelude::*, RawSsz};
+///
+/// #[derive(Default, Debug, SimpleSerialize)]
+/// struct Envelope {
+///     topic: u8,
+///     payload: RawSsz<List<u64, 8>>,
+/// }
+///
+/// let payload = List::<u64, 8>::try_from(vec![1, 2]).unwrap();
+/// let mut envelope = Envelope { topic: 3, payload: RawSsz::from_value(&payload).unwrap() };
+/// let encoding = serialize(&envelope).unwrap();
+///
+/// let mut routed = Envelope::deserialize(&encoding).unwrap();
+/// assert_eq!(serialize(&routed).unwrap(), encoding);
+/// assert_eq!(routed.payload.decode().unwrap(), payload);
+/// assert_eq!(routed.hash_tree_root().unwrap(), envelope.hash_tree_root().unwrap());
+/// ```
+pub struct RawSsz<T: SimpleSerialize> {
+    bytes: Vec<u8>,
+    _marker: PhantomData<T>,
+}
+
+impl<T: SimpleSerialize> RawSsz<T> {
+    /// Keep `bytes` as the encoding of a `T` without checking them.
+    pub fn new(bytes: Vec<u8>) -> Self {
+        Self { bytes, _marker: PhantomData }
+    }
+
+    /// Keep the encoding of `value`.
+    pub fn from_value(value: &T) -> Result<Self, SerializeError> {
+        let mut bytes = vec![];
+        value.serialize(&mut bytes)?;
+        Ok(Self::new(bytes))
+    }
+
+    /// Decode the bytes as a `T`.
+    pub fn decode(&self) -> Result<T, DeserializeError> {
+        T::deserialize(&self.bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+impl<T: SimpleSerialize> fmt::Debug for RawSsz<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RawSsz<{}>(len={})", any::type_name::<T>(), self.bytes.len())
+    }
+}
+
+impl<T: SimpleSerialize> Clone for RawSsz<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.bytes.clone())
+    }
+}
+
+impl<T: SimpleSerialize> PartialEq for RawSsz<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.bytes == other.bytes
+    }
+}
+
+impl<T: SimpleSerialize> Eq for RawSsz<T> {}
+
+impl<T: SimpleSerialize> Default for RawSsz<T> {
+    fn default() -> Self {
+        match Self::from_value(&T::default()) {
+            Ok(result) => result,
+            Err(err) => panic!("{err}"),
+        }
+    }
+}
+
+impl<T: SimpleSerialize> Sized for RawSsz<T> {
+    fn is_variable_size() -> bool {
+        T::is_variable_size()
+    }
+
+    fn size_hint() -> usize {
+        T::size_hint()
+    }
+}
+
+impl<T: SimpleSerialize> Serialize for RawSsz<T> {
+    fn serialize(&self, buffer: &mut Vec<u8>) -> Result<usize, SerializeError> {
+        buffer.extend_from_slice(&self.bytes);
+        Ok(self.bytes.len())
+    }
+}
+
+impl<T: SimpleSerialize> Deserialize for RawSsz<T> {
+    fn deserialize(encoding: &[u8]) -> Result<Self, DeserializeError> {
+        let expected = T::max_size();
+        if encoding.len() > expected {
+            return Err(DeserializeError::AdditionalInput { provided: encoding.len(), expected })
+        }
+        if !T::is_variable_size() && encoding.len() < expected {
+            return Err(DeserializeError::ExpectedFurtherInput {
+                provided: encoding.len(),
+                expected,
+            })
+        }
+        Ok(Self::new(encoding.to_vec()))
+    }
+}
+
+impl<T: SimpleSerialize> Merkleized for RawSsz<T> {
+    fn hash_tree_root_with<H: MerkleHasher>(
+        &mut self,
+        context: &MerkleizationContext<H>,
+    ) -> Result<Node, MerkleizationError> {
+        T::hash_tree_root_from_bytes(&self.bytes, context).map_err(|err| match err {
+            Error::Merkleization(err) => err,
+            Error::Serialize(err) => MerkleizationError::SerializationError(err),
+            Error::Deserialize(err) => MerkleizationError::InvalidEncoding(err),
+            Error::Instance(err) => MerkleizationError::InvalidEncoding(err.into()),
+            Error::Type(err) => MerkleizationError::InvalidEncoding(err.into()),
+        })
+    }
+
+    fn leaves<H: MerkleHasher>(
+        &mut self,
+        context: &MerkleizationContext<H>,
+    ) -> Result<Vec<Node>, MerkleizationError> {
+        let mut value = T::deserialize(&self.bytes).map_err(MerkleizationError::InvalidEncoding)?;
+        value.leaves(context)
+    }
+
+    const CHUNK_COUNT: usize = T::CHUNK_COUNT;
+
+    fn tree_depth() -> u32 {
+        T::tree_depth()
+    }
+
+    fn merkle_limit() -> Option<usize> {
+        T::merkle_limit()
+    }
+}
+
+impl<T: SimpleSerialize> SimpleSerialize for RawSsz<T> {
+    fn is_composite_type() -> bool {
+        T::is_composite_type()
+    }
+
+    const PACKED_PER_CHUNK: usize = T::PACKED_PER_CHUNK;
+
+    fn nesting_depth() -> usize {
+        T::nesting_depth()
+    }
+
+    fn ssz_heap_size(&self) -> usize {
+        self.bytes.capacity()
+    }
+
+    fn hash_tree_root_from_bytes<H: MerkleHasher>(
+        encoding: &[u8],
+        context: &MerkleizationContext<H>,
+    ) -> Result<Node, Error> {
+        T::hash_tree_root_from_bytes(encoding, context)
+    }
+
+    const MAX_SIZE: usize = T::MAX_SIZE;
+
+    const IS_VARIABLE_SIZE: bool = T::IS_VARIABLE_SIZE;
+
+    const NESTING_DEPTH: usize = T::NESTING_DEPTH;
+
+    fn generalized_index_from(
+        root: GeneralizedIndex,
+        path: &[PathElement<'_>],
+    ) -> Result<GeneralizedIndex, PathError> {
+        T::generalized_index_from(root, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{list::List, serialize, vector::Vector};
+
+    #[test]
+    fn test_raw_ssz() {
+        let mut list = List::<u16, 4>::try_from(vec![1u16, 2, 3]).unwrap();
+        let mut raw = RawSsz::<List<u16, 4>>::from_value(&list).unwrap();
+        assert_eq!(raw.as_bytes(), [1, 0, 2, 0, 3, 0]);
+        assert_eq!(raw.decode().unwrap(), list);
+        assert_eq!(raw.hash_tree_root().unwrap(), list.hash_tree_root().unwrap());
+        assert_eq!(
+            RawSsz::<List<u16, 4>>::default().hash_tree_root().unwrap(),
+            List::<u16, 4>::default().hash_tree_root().unwrap()
+        );
+
+        // bytes are kept as they are and only checked when decoded or hashed
+        let mut raw = RawSsz::<List<u16, 4>>::deserialize(&[1, 0, 2]).unwrap();
+        assert_eq!(serialize(&raw).unwrap(), [1, 0, 2]);
+        assert!(raw.decode().is_err());
+        assert!(matches!(raw.hash_tree_root(), Err(MerkleizationError::InvalidEncoding(..))));
+        assert!(RawSsz::<List<u16, 4>>::deserialize(&[0; 10]).is_err());
+
+        assert!(!RawSsz::<Vector<u16, 2>>::is_variable_size());
+        assert_eq!(RawSsz::<Vector<u16, 2>>::size_hint(), 4);
+        assert!(RawSsz::<Vector<u16, 2>>::deserialize(&[1, 0, 2]).is_err());
+        assert!(RawSsz::<Vector<u16, 2>>::deserialize(&[1, 0, 2, 0]).is_ok());
+    }
+}
@@ -0,0 +1,531 @@
+//! A `Vector<Node, N>` and a `List<Node, N>` kept as one contiguous buffer of chunks, for large
+//! collections of roots like the block and state roots of the beacon state.
+//!
+//! A [`RootsVector`] or [`RootsList`] stores its roots back to back, exactly as they are
+//! encoded, so decoding is a single copy of the encoding and the chunks of its Merkle tree are
+//! the buffer itself, hashed in a straight pass without a cache of the tree. The elements are
+//! viewed as `Node`s in place, and the encoding and root are those of the equivalent
+//! `Vector<Node, N>` or `List<Node, N>`.
+//!
+//! ```rust
+//! use ssz_rs::{prelude::*, roots::RootsList};
+//!
+//! let nodes = (0..100u8).map(|i| Node::try_from(&[i; 32][..]).unwrap()).collect::<Vec<_>>();
+//! let mut list = List::<Node, 8192>::try_from(nodes).unwrap();
+//! let encoding = serialize(&list).unwrap();
+//! let mut roots = RootsList::<8192>::deserialize(&encoding).unwrap();
+//! assert_eq!(roots[7], list[7]);
+//! assert_eq!(roots.as_bytes(), &encoding[..]);
+//! assert_eq!(roots.hash_tree_root().unwrap(), list.hash_tree_root().unwrap());
+//! assert_eq!(roots.to_list(), list);
+//! ```
+use crate::{
+    de::{Deserialize, DeserializeError},
+    error::{Error, InstanceError, TypeError},
+    lib::*,
+    list::List,
+    merkleization::{
+        chunks_to_nodes, merkleize_with, mix_in_length, Context, GeneralizedIndex, MerkleHasher,
+        MerkleizationError, Merkleized, Node, PathElement, PathError, BYTES_PER_CHUNK,
+    },
+    ser::{Serialize, SerializeError},
+    vector::Vector,
+    SimpleSerialize, Sized,
+};
+use core::ops::{Index, IndexMut};
+
+// View the chunk of `chunks` at `index` as a `Node`.
+fn node_at(chunks: &[u8], index: usize) -> Option<&Node> {
+    let chunk = chunks.get(index * BYTES_PER_CHUNK..(index + 1) * BYTES_PER_CHUNK)?;
+    Some(Node::from_chunk(chunk.try_into().expect("is one chunk")))
+}
+
+fn node_at_mut(chunks: &mut [u8], index: usize) -> Option<&mut Node> {
+    let chunk = chunks.get_mut(index * BYTES_PER_CHUNK..(index + 1) * BYTES_PER_CHUNK)?;
+    Some(Node::from_chunk_mut(chunk.try_into().expect("is one chunk")))
+}
+
+fn nodes(chunks: &[u8]) -> impl Iterator<Item = &Node> {
+    chunks
+        .chunks_exact(BYTES_PER_CHUNK)
+        .map(|chunk| Node::from_chunk(chunk.try_into().expect("is one chunk")))
+}
+
+// Copy `nodes` into one buffer of chunks.
+fn chunks_from_nodes(nodes: &[Node]) -> Vec<u8> {
+    let mut chunks = Vec::with_capacity(nodes.len() * BYTES_PER_CHUNK);
+    for node in nodes {
+        chunks.extend_from_slice(node.as_ref());
+    }
+    chunks
+}
+
+// Check that `encoding` is a whole number of chunks and return how many.
+fn chunk_count_of(encoding: &[u8]) -> Result<usize, DeserializeError> {
+    let remainder = encoding.len() % BYTES_PER_CHUNK;
+    if remainder != 0 {
+        let expected = encoding.len() - remainder;
+        return Err(DeserializeError::AdditionalInput { provided: encoding.len(), expected })
+    }
+    Ok(encoding.len() / BYTES_PER_CHUNK)
+}
+
+/// A `Vector<Node, N>` storing its roots in one contiguous buffer.
+#[derive(Clone, PartialEq, Eq)]
+pub struct RootsVector<const N: usize> {
+    chunks: Vec<u8>,
+}
+
+impl<const N: usize> Default for RootsVector<N> {
+    fn default() -> Self {
+        Self { chunks: vec![0u8; N * BYTES_PER_CHUNK] }
+    }
+}
+
+impl<const N: usize> fmt::Debug for RootsVector<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<const N: usize> RootsVector<N> {
+    pub fn len(&self) -> usize {
+        N
+    }
+
+    pub fn is_empty(&self) -> bool {
+        N == 0
+    }
+
+    /// Return the root at `index`, viewed in place.
+    pub fn get(&self, index: usize) -> Option<&Node> {
+        node_at(&self.chunks, index)
+    }
+
+    /// Return the root at `index`, to change it in place.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Node> {
+        node_at_mut(&mut self.chunks, index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Node> {
+        nodes(&self.chunks)
+    }
+
+    /// Return the roots as one buffer, which is also their encoding.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.chunks
+    }
+
+    /// Copy the roots into a `Vector<Node, N>`.
+    pub fn to_vector(&self) -> Vector<Node, N> {
+        Vector::try_from(self.iter().copied().collect::<Vec<_>>()).expect("length is exactly N")
+    }
+}
+
+impl<const N: usize> Index<usize> for RootsVector<N> {
+    type Output = Node;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get(index).expect("index is within the length")
+    }
+}
+
+impl<const N: usize> IndexMut<usize> for RootsVector<N> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        self.get_mut(index).expect("index is within the length")
+    }
+}
+
+impl<const N: usize> TryFrom<&[Node]> for RootsVector<N> {
+    type Error = Error;
+
+    fn try_from(nodes: &[Node]) -> Result<Self, Self::Error> {
+        if nodes.len() != N {
+            return Err(Error::Instance(InstanceError::exact::<Self>(N, nodes.len())))
+        }
+        Ok(Self { chunks: chunks_from_nodes(nodes) })
+    }
+}
+
+impl<const N: usize> From<&Vector<Node, N>> for RootsVector<N> {
+    fn from(vector: &Vector<Node, N>) -> Self {
+        Self { chunks: chunks_from_nodes(vector) }
+    }
+}
+
+impl<const N: usize> Sized for RootsVector<N> {
+    fn is_variable_size() -> bool {
+        false
+    }
+
+    fn size_hint() -> usize {
+        N * BYTES_PER_CHUNK
+    }
+}
+
+impl<const N: usize> Serialize for RootsVector<N> {
+    fn serialize(&self, buffer: &mut Vec<u8>) -> Result<usize, SerializeError> {
+        if N == 0 {
+            return Err(TypeError::InvalidBound(N).into())
+        }
+        buffer.extend_from_slice(&self.chunks);
+        Ok(self.chunks.len())
+    }
+}
+
+impl<const N: usize> Deserialize for RootsVector<N> {
+    fn deserialize(encoding: &[u8]) -> Result<Self, DeserializeError> {
+        if N == 0 {
+            return Err(TypeError::InvalidBound(N).into())
+        }
+        let expected = N * BYTES_PER_CHUNK;
+        if encoding.len() < expected {
+            return Err(DeserializeError::ExpectedFurtherInput {
+                provided: encoding.len(),
+                expected,
+            })
+        }
+        if encoding.len() > expected {
+            return Err(DeserializeError::AdditionalInput { provided: encoding.len(), expected })
+        }
+        Ok(Self { chunks: encoding.to_vec() })
+    }
+}
+
+impl<const N: usize> Merkleized for RootsVector<N> {
+    fn hash_tree_root_with<H: MerkleHasher>(
+        &mut self,
+        context: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
+        merkleize_with(&self.chunks, Some(N), context)
+    }
+
+    fn leaves<H: MerkleHasher>(&mut self, _: &Context<H>) -> Result<Vec<Node>, MerkleizationError> {
+        Ok(chunks_to_nodes(&self.chunks))
+    }
+
+    const CHUNK_COUNT: usize = N;
+}
+
+impl<const N: usize> SimpleSerialize for RootsVector<N> {
+    fn nesting_depth() -> usize {
+        Vector::<Node, N>::nesting_depth()
+    }
+
+    const MAX_SIZE: usize = Vector::<Node, N>::MAX_SIZE;
+
+    const IS_VARIABLE_SIZE: bool = false;
+
+    const NESTING_DEPTH: usize = Vector::<Node, N>::NESTING_DEPTH;
+
+    fn generalized_index_from(
+        root: GeneralizedIndex,
+        path: &[PathElement<'_>],
+    ) -> Result<GeneralizedIndex, PathError> {
+        Vector::<Node, N>::generalized_index_from(root, path)
+    }
+
+    fn hash_tree_root_from_bytes<H: MerkleHasher>(
+        encoding: &[u8],
+        context: &Context<H>,
+    ) -> Result<Node, Error> {
+        if N == 0 {
+            return Err(Error::Type(TypeError::InvalidBound(N)))
+        }
+        let len = chunk_count_of(encoding)?;
+        if len != N {
+            return Err(Error::Instance(InstanceError::exact::<Self>(N, len)))
+        }
+        Ok(merkleize_with(encoding, Some(N), context)?)
+    }
+
+    fn default_hash_tree_root_with<H: MerkleHasher>(
+        context: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
+        Vector::<Node, N>::default_hash_tree_root_with(context)
+    }
+
+    fn ssz_heap_size(&self) -> usize {
+        self.chunks.capacity()
+    }
+}
+
+/// A `List<Node, N>` storing its roots in one contiguous buffer.
+#[derive(Default, Clone, PartialEq, Eq)]
+pub struct RootsList<const N: usize> {
+    chunks: Vec<u8>,
+}
+
+impl<const N: usize> fmt::Debug for RootsList<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<const N: usize> RootsList<N> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.chunks.len() / BYTES_PER_CHUNK
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Append `node` to the end of the list.
+    /// Returns the `node` back if the list is already at its limit `N`.
+    pub fn push(&mut self, node: Node) -> Result<(), (Node, Error)> {
+        if self.len() >= N {
+            let provided = self.len() + 1;
+            return Err((node, Error::Instance(InstanceError::bounded::<Self>(N, provided))))
+        }
+        self.chunks.extend_from_slice(node.as_ref());
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Option<Node> {
+        let start = self.chunks.len().checked_sub(BYTES_PER_CHUNK)?;
+        let node = Node::try_from(&self.chunks[start..]).expect("is one chunk");
+        self.chunks.truncate(start);
+        Some(node)
+    }
+
+    /// Return the root at `index`, viewed in place.
+    pub fn get(&self, index: usize) -> Option<&Node> {
+        node_at(&self.chunks, index)
+    }
+
+    /// Return the root at `index`, to change it in place.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Node> {
+        node_at_mut(&mut self.chunks, index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Node> {
+        nodes(&self.chunks)
+    }
+
+    /// Return the roots as one buffer, which is also their encoding.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.chunks
+    }
+
+    /// Copy the roots into a `List<Node, N>`.
+    pub fn to_list(&self) -> List<Node, N> {
+        List::try_from(self.iter().copied().collect::<Vec<_>>())
+            .expect("length is within the bound")
+    }
+}
+
+impl<const N: usize> Index<usize> for RootsList<N> {
+    type Output = Node;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get(index).expect("index is within the length")
+    }
+}
+
+impl<const N: usize> IndexMut<usize> for RootsList<N> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        self.get_mut(index).expect("index is within the length")
+    }
+}
+
+impl<const N: usize> TryFrom<&[Node]> for RootsList<N> {
+    type Error = Error;
+
+    fn try_from(nodes: &[Node]) -> Result<Self, Self::Error> {
+        if nodes.len() > N {
+            return Err(Error::Instance(InstanceError::bounded::<Self>(N, nodes.len())))
+        }
+        Ok(Self { chunks: chunks_from_nodes(nodes) })
+    }
+}
+
+impl<const N: usize> From<&List<Node, N>> for RootsList<N> {
+    fn from(list: &List<Node, N>) -> Self {
+        Self { chunks: chunks_from_nodes(list) }
+    }
+}
+
+impl<const N: usize> Sized for RootsList<N> {
+    fn is_variable_size() -> bool {
+        true
+    }
+
+    fn size_hint() -> usize {
+        0
+    }
+}
+
+impl<const N: usize> Serialize for RootsList<N> {
+    fn serialize(&self, buffer: &mut Vec<u8>) -> Result<usize, SerializeError> {
+        buffer.extend_from_slice(&self.chunks);
+        Ok(self.chunks.len())
+    }
+}
+
+impl<const N: usize> Deserialize for RootsList<N> {
+    fn deserialize(encoding: &[u8]) -> Result<Self, DeserializeError> {
+        let len = chunk_count_of(encoding)?;
+        if len > N {
+            return Err(InstanceError::bounded::<Self>(N, len).into())
+        }
+        Ok(Self { chunks: encoding.to_vec() })
+    }
+}
+
+impl<const N: usize> Merkleized for RootsList<N> {
+    fn hash_tree_root_with<H: MerkleHasher>(
+        &mut self,
+        context: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
+        let data_root = merkleize_with(&self.chunks, Some(N), context)?;
+        Ok(mix_in_length(&data_root, self.len(), context))
+    }
+
+    fn leaves<H: MerkleHasher>(&mut self, _: &Context<H>) -> Result<Vec<Node>, MerkleizationError> {
+        Ok(chunks_to_nodes(&self.chunks))
+    }
+
+    const CHUNK_COUNT: usize = N;
+
+    fn merkle_limit() -> Option<usize> {
+        Some(N)
+    }
+}
+
+impl<const N: usize> SimpleSerialize for RootsList<N> {
+    fn nesting_depth() -> usize {
+        List::<Node, N>::nesting_depth()
+    }
+
+    const MAX_SIZE: usize = List::<Node, N>::MAX_SIZE;
+
+    const NESTING_DEPTH: usize = List::<Node, N>::NESTING_DEPTH;
+
+    fn generalized_index_from(
+        root: GeneralizedIndex,
+        path: &[PathElement<'_>],
+    ) -> Result<GeneralizedIndex, PathError> {
+        List::<Node, N>::generalized_index_from(root, path)
+    }
+
+    fn hash_tree_root_from_bytes<H: MerkleHasher>(
+        encoding: &[u8],
+        context: &Context<H>,
+    ) -> Result<Node, Error> {
+        let len = chunk_count_of(encoding)?;
+        if len > N {
+            return Err(Error::Instance(InstanceError::bounded::<Self>(N, len)))
+        }
+        let data_root = merkleize_with(encoding, Some(N), context)?;
+        Ok(mix_in_length(&data_root, len, context))
+    }
+
+    fn is_default(&self) -> bool {
+        self.is_empty()
+    }
+
+    fn default_hash_tree_root_with<H: MerkleHasher>(
+        context: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
+        List::<Node, N>::default_hash_tree_root_with(context)
+    }
+
+    fn ssz_heap_size(&self) -> usize {
+        self.chunks.capacity()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    fn node(byte: u8) -> Node {
+        Node::try_from(&[byte; 32][..]).unwrap()
+    }
+
+    #[test]
+    fn test_roots_vector() {
+        let nodes = (1..=5).map(node).collect::<Vec<_>>();
+        let mut roots = RootsVector::<5>::try_from(nodes.as_slice()).unwrap();
+        let mut vector = Vector::<Node, 5>::try_from(nodes.clone()).unwrap();
+        assert_eq!(roots.iter().copied().collect::<Vec<_>>(), nodes);
+        assert_eq!(roots[2], node(3));
+        assert_eq!(roots.get(5), None);
+
+        let encoding = serialize(&roots).unwrap();
+        assert_eq!(encoding, serialize(&vector).unwrap());
+        assert_eq!(RootsVector::<5>::deserialize(&encoding).unwrap(), roots);
+        assert_eq!(roots.hash_tree_root().unwrap(), vector.hash_tree_root().unwrap());
+        assert_eq!(
+            RootsVector::<5>::hash_tree_root_from_bytes(&encoding, Context::global()).unwrap(),
+            vector.hash_tree_root().unwrap()
+        );
+        assert_eq!(RootsVector::from(&vector), roots);
+        assert_eq!(roots.to_vector(), vector);
+
+        roots[4] = node(9);
+        vector[4] = node(9);
+        assert_eq!(roots.hash_tree_root().unwrap(), vector.hash_tree_root().unwrap());
+
+        assert!(RootsVector::<5>::try_from(&nodes[..4]).is_err());
+        assert!(RootsVector::<5>::deserialize(&encoding[1..]).is_err());
+        assert!(RootsVector::<4>::deserialize(&encoding).is_err());
+        assert!(RootsVector::<5>::hash_tree_root_from_bytes(&encoding[32..], Context::global())
+            .is_err());
+
+        let mut empty = RootsVector::<5>::default();
+        assert_eq!(
+            empty.hash_tree_root().unwrap(),
+            Vector::<Node, 5>::default_hash_tree_root_with(Context::global()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_roots_list() {
+        let nodes = (1..=5).map(node).collect::<Vec<_>>();
+        let mut roots = RootsList::<8>::try_from(nodes.as_slice()).unwrap();
+        let mut list = List::<Node, 8>::try_from(nodes.clone()).unwrap();
+        assert_eq!(roots.len(), 5);
+        assert_eq!(roots[0], node(1));
+
+        let encoding = serialize(&roots).unwrap();
+        assert_eq!(encoding, serialize(&list).unwrap());
+        assert_eq!(RootsList::<8>::deserialize(&encoding).unwrap(), roots);
+        assert_eq!(roots.hash_tree_root().unwrap(), list.hash_tree_root().unwrap());
+        assert_eq!(
+            RootsList::<8>::hash_tree_root_from_bytes(&encoding, Context::global()).unwrap(),
+            list.hash_tree_root().unwrap()
+        );
+        assert_eq!(RootsList::from(&list), roots);
+        assert_eq!(roots.to_list(), list);
+
+        roots.push(node(6)).unwrap();
+        roots.push(node(7)).unwrap();
+        roots.push(node(8)).unwrap();
+        assert!(roots.push(node(9)).is_err());
+        assert_eq!(roots.pop(), Some(node(8)));
+        *roots.get_mut(0).unwrap() = node(0);
+        list.push(node(6)).unwrap();
+        list.push(node(7)).unwrap();
+        list[0] = node(0);
+        assert_eq!(roots.hash_tree_root().unwrap(), list.hash_tree_root().unwrap());
+
+        assert!(RootsList::<4>::try_from(nodes.as_slice()).is_err());
+        assert!(RootsList::<4>::deserialize(&encoding).is_err());
+        assert!(RootsList::<8>::deserialize(&encoding[1..]).is_err());
+
+        let mut empty = RootsList::<8>::new();
+        assert!(empty.is_default());
+        assert_eq!(empty.pop(), None);
+        assert_eq!(
+            empty.hash_tree_root().unwrap(),
+            List::<Node, 8>::default().hash_tree_root().unwrap()
+        );
+    }
+}
@@ -1,18 +1,21 @@
 use crate::{
-    error::{InstanceError, TypeError},
+    error::{ErrorKind, InstanceError, TypeError},
     lib::*,
     SimpleSerialize,
 };
 
 // NOTE: if this is changed, go change in `ssz_derive` as well!
 pub const BYTES_PER_LENGTH_OFFSET: usize = 4;
-const MAXIMUM_LENGTH: u64 = 2u64.pow((8 * BYTES_PER_LENGTH_OFFSET) as u32);
+pub(crate) const MAXIMUM_LENGTH: u64 = 2u64.pow((8 * BYTES_PER_LENGTH_OFFSET) as u32);
 
 /// Serialization errors.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum SerializeError {
     /// The encoded length exceeds the maximum.
     MaximumEncodedLengthExceeded(usize),
+    /// The number of elements exceeds the declared limit of the type (`provided > bound`).
+    LimitExceeded { bound: usize, provided: usize },
     /// An invalid instance was encountered.
     InvalidInstance(InstanceError),
     /// An invalid type was encountered.
@@ -31,6 +34,18 @@ impl From<TypeError> for SerializeError {
     }
 }
 
+impl SerializeError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::MaximumEncodedLengthExceeded(..) | Self::LimitExceeded { .. } => {
+                ErrorKind::Bounds
+            }
+            Self::InvalidInstance(err) => err.kind(),
+            Self::InvalidType(err) => err.kind(),
+        }
+    }
+}
+
 impl Display for SerializeError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
@@ -38,6 +53,9 @@ impl Display for SerializeError {
                 f,
                 "the encoded length is {size} which exceeds the maximum length {MAXIMUM_LENGTH}",
             ),
+            SerializeError::LimitExceeded { bound, provided } => {
+                write!(f, "{provided} elements exceed the declared limit {bound} of this type")
+            }
             SerializeError::InvalidInstance(err) => write!(f, "invalid instance: {err}"),
             SerializeError::InvalidType(err) => write!(f, "invalid type: {err}"),
         }
@@ -62,8 +80,11 @@ pub fn serialize_composite_from_components(
     fixed_lengths_sum: usize,
     buffer: &mut Vec<u8>,
 ) -> Result<usize, SerializeError> {
-    let total_size = fixed_lengths_sum + variable_lengths.iter().sum::<usize>();
-    if total_size as u64 >= MAXIMUM_LENGTH {
+    // NOTE: sum in `u64` so the check can not be defeated by overflow on 32-bit targets
+    let total_size =
+        variable_lengths.iter().fold(fixed_lengths_sum as u64, |sum, &len| sum + len as u64);
+    if total_size >= MAXIMUM_LENGTH {
+        let total_size = usize::try_from(total_size).unwrap_or(usize::MAX);
         return Err(SerializeError::MaximumEncodedLengthExceeded(total_size))
     }
 
@@ -75,7 +96,9 @@ pub fn serialize_composite_from_components(
             buffer.append(part);
         } else {
             let variable_lengths_sum = variable_lengths[0..i].iter().sum::<usize>();
-            let length = (fixed_lengths_sum + variable_lengths_sum) as u32;
+            // the total size was checked to fit in an offset
+            let length = u32::try_from(fixed_lengths_sum + variable_lengths_sum)
+                .expect("offset is less than the total size");
             let mut offset_buffer = Vec::with_capacity(4);
             let _ = length.serialize(&mut offset_buffer)?;
             buffer.append(&mut offset_buffer);
@@ -91,37 +114,180 @@ pub fn serialize_composite_from_components(
     Ok(total_bytes_written)
 }
 
-pub fn serialize_composite<T: SimpleSerialize>(
-    elements: &[T],
+// Return the maximum length of the encoding of `count` elements of type `T`.
+pub(crate) const fn elements_max_size<T: SimpleSerialize>(count: usize) -> usize {
+    count.saturating_mul(field_max_size::<T>())
+}
+
+// Return the maximum length of a field of type `T` in the encoding of a composite type,
+// including the offset to a variable-size field.
+pub const fn field_max_size<T: SimpleSerialize>() -> usize {
+    if T::IS_VARIABLE_SIZE {
+        BYTES_PER_LENGTH_OFFSET.saturating_add(T::MAX_SIZE)
+    } else {
+        T::MAX_SIZE
+    }
+}
+
+// Return the nesting depth of a composite type whose fields or elements have the nesting depths
+// `depths`, saturating at `usize::MAX`.
+pub const fn composite_nesting_depth(depths: &[usize]) -> usize {
+    let mut depth = 0;
+    let mut i = 0;
+    while i < depths.len() {
+        if depths[i] > depth {
+            depth = depths[i];
+        }
+        i += 1;
+    }
+    depth.saturating_add(1)
+}
+
+/// The encodings of the fields or elements of a composite type, in order, to be appended as one
+/// encoding with the offsets to its variable-size parts.
+#[derive(Debug, Default)]
+pub struct CompositeParts {
+    fixed: Vec<Option<Vec<u8>>>,
+    variable: Vec<Vec<u8>>,
+    variable_lengths: Vec<usize>,
+    fixed_lengths_sum: usize,
+}
+
+impl CompositeParts {
+    /// Add the `encoding` of the next part, which is variable-size if `is_variable_size`.
+    pub fn push(&mut self, is_variable_size: bool, encoding: Vec<u8>) {
+        let len = encoding.len();
+        if is_variable_size {
+            self.fixed.push(None);
+            self.fixed_lengths_sum += BYTES_PER_LENGTH_OFFSET;
+            self.variable.push(encoding);
+            self.variable_lengths.push(len);
+        } else {
+            self.fixed.push(Some(encoding));
+            self.fixed_lengths_sum += len;
+            self.variable_lengths.push(0);
+        }
+    }
+
+    /// Append the encoding of the composite to `buffer`, returning the number of bytes written.
+    pub fn serialize(self, buffer: &mut Vec<u8>) -> Result<usize, SerializeError> {
+        serialize_composite_from_components(
+            self.fixed,
+            self.variable,
+            self.variable_lengths,
+            self.fixed_lengths_sum,
+            buffer,
+        )
+    }
+}
+
+pub fn serialize_composite<'a, T: SimpleSerialize + 'a>(
+    elements: impl IntoIterator<Item = &'a T>,
     buffer: &mut Vec<u8>,
 ) -> Result<usize, SerializeError> {
-    let mut fixed = vec![];
-    let mut variable = vec![];
-    let mut variable_lengths = vec![];
-    let mut fixed_lengths_sum = 0;
-
+    let mut parts = CompositeParts::default();
     for element in elements {
-        let mut buffer = Vec::with_capacity(T::size_hint());
-        element.serialize(&mut buffer)?;
+        let mut encoding = Vec::with_capacity(T::size_hint());
+        element.serialize(&mut encoding)?;
+        parts.push(T::is_variable_size(), encoding);
+    }
+    parts.serialize(buffer)
+}
+
+/// Append the encoding of a list of the elements of `iter` to `buffer`, without collecting them
+/// first, e.g. elements read from a database cursor. The encoding is the one of a
+/// `List<T, limit>` of these elements.
+///
+/// Returns the number of bytes written. On error, e.g. once the iterator yields more than
+/// `limit` elements, `buffer` is left as it was.
+///
+/// ```rust
+/// use ssz_rs::{prelude::*, serialize_iter};
+///
+/// let mut buffer = vec![];
+/// let written = serialize_iter((1..=3u16).map(|i| i * 2), 8, &mut buffer).unwrap();
+/// assert_eq!(written, 6);
+/// let list = List::<u16, 8>::try_from(vec![2, 4, 6]).unwrap();
+/// assert_eq!(buffer, serialize(&list).unwrap());
+///
+/// assert!(serialize_iter(0..9u16, 8, &mut buffer).is_err());
+/// assert_eq!(buffer.len(), 6);
+/// ```
+pub fn serialize_iter<T: SimpleSerialize>(
+    iter: impl IntoIterator<Item = T>,
+    limit: usize,
+    buffer: &mut Vec<u8>,
+) -> Result<usize, SerializeError> {
+    let start = buffer.len();
+    let result = serialize_elements_from(iter, limit, buffer, start);
+    if result.is_err() {
+        buffer.truncate(start);
+    }
+    result
+}
 
-        let buffer_len = buffer.len();
+fn serialize_elements_from<T: SimpleSerialize>(
+    iter: impl IntoIterator<Item = T>,
+    limit: usize,
+    buffer: &mut Vec<u8>,
+    start: usize,
+) -> Result<usize, SerializeError> {
+    // the position of each variable-size element relative to the first one
+    let mut positions = vec![];
+    let mut count = 0;
+    for element in iter {
+        count += 1;
+        if count > limit {
+            return Err(SerializeError::LimitExceeded { bound: limit, provided: count })
+        }
         if T::is_variable_size() {
-            fixed.push(None);
-            fixed_lengths_sum += BYTES_PER_LENGTH_OFFSET;
-            variable.push(buffer);
-            variable_lengths.push(buffer_len);
-        } else {
-            fixed.push(Some(buffer));
-            fixed_lengths_sum += buffer_len;
-            variable_lengths.push(0)
+            positions.push(buffer.len() - start);
+        }
+        element.serialize(buffer)?;
+    }
+
+    let offsets_len = positions.len() * BYTES_PER_LENGTH_OFFSET;
+    let total_size = (buffer.len() - start) as u64 + offsets_len as u64;
+    if total_size >= MAXIMUM_LENGTH {
+        let total_size = usize::try_from(total_size).unwrap_or(usize::MAX);
+        return Err(SerializeError::MaximumEncodedLengthExceeded(total_size))
+    }
+    if T::is_variable_size() {
+        // NOTE: the offsets are appended after the elements and rotated in front of them
+        for position in positions {
+            // the total size was checked to fit in an offset
+            let offset = (offsets_len + position) as u32;
+            buffer.extend_from_slice(&offset.to_le_bytes());
         }
+        buffer[start..].rotate_right(offsets_len);
     }
+    Ok(buffer.len() - start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{list::List, serialize};
+
+    #[test]
+    fn test_serialize_iter_of_variable_size_elements() {
+        let elements = vec![
+            List::<u8, 4>::try_from(vec![1u8, 2]).unwrap(),
+            List::default(),
+            List::try_from(vec![3u8, 4, 5, 6]).unwrap(),
+        ];
+        let expected = serialize(&List::<_, 3>::try_from(elements.clone()).unwrap()).unwrap();
 
-    serialize_composite_from_components(
-        fixed,
-        variable,
-        variable_lengths,
-        fixed_lengths_sum,
-        buffer,
-    )
+        let mut buffer = vec![0xff];
+        let written = serialize_iter(elements.clone(), 3, &mut buffer).unwrap();
+        assert_eq!(written, expected.len());
+        assert_eq!(buffer[0], 0xff);
+        assert_eq!(buffer[1..], expected);
+
+        let err = serialize_iter(elements, 2, &mut buffer).unwrap_err();
+        assert!(matches!(err, SerializeError::LimitExceeded { bound: 2, provided: 3 }));
+        assert_eq!(buffer.len(), expected.len() + 1);
+
+        assert_eq!(serialize_iter(Vec::<List<u8, 4>>::new(), 0, &mut buffer).unwrap(), 0);
+    }
 }
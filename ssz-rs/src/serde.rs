@@ -1,21 +1,4 @@
-use hex::FromHexError;
-use thiserror::Error;
-
-const HEX_ENCODING_PREFIX: &str = "0x";
-
-#[derive(Debug, Error)]
-pub enum HexError {
-    #[error("{0}")]
-    Hex(#[from] FromHexError),
-    #[error("missing prefix `{HEX_ENCODING_PREFIX}` when deserializing hex data")]
-    MissingPrefix,
-}
-
-fn try_bytes_from_hex_str(s: &str) -> Result<Vec<u8>, HexError> {
-    let target = s.strip_prefix(HEX_ENCODING_PREFIX).ok_or(HexError::MissingPrefix)?;
-    let data = hex::decode(target)?;
-    Ok(data)
-}
+use crate::hex;
 
 pub mod as_hex {
     use super::*;
@@ -25,9 +8,7 @@ pub mod as_hex {
     where
         S: serde::Serializer,
     {
-        let encoding = hex::encode(data.as_ref());
-        let output = format!("{HEX_ENCODING_PREFIX}{encoding}");
-        serializer.collect_str(&output)
+        serializer.collect_str(&hex::encode_0x(data))
     }
 
     pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
@@ -37,7 +18,7 @@ pub mod as_hex {
     {
         let s = <String>::deserialize(deserializer)?;
 
-        let data = try_bytes_from_hex_str(&s).map_err(serde::de::Error::custom)?;
+        let data = hex::decode_0x(&s).map_err(serde::de::Error::custom)?;
 
         let inner = T::try_from(&data)
             .map_err(|_| serde::de::Error::custom("could not parse instance from byte data"))?;
@@ -0,0 +1,121 @@
+//! Spans around encoding, decoding and hashing of values with the `tracing` feature, so
+//! profiles can attribute time and payload sizes to SSZ types. Each span is named `ssz` and
+//! carries the `operation`, the `type_name` of the value and, where known, its size in `bytes`.
+//!
+//! Without the feature, spans compile to nothing.
+
+pub(crate) struct Span {
+    #[cfg(feature = "tracing")]
+    inner: ::tracing::Span,
+}
+
+impl Span {
+    #[cfg(feature = "tracing")]
+    #[inline]
+    pub(crate) fn new<T: ?Sized>(operation: &'static str) -> Self {
+        Self {
+            inner: ::tracing::info_span!(
+                "ssz",
+                operation,
+                type_name = core::any::type_name::<T>(),
+                bytes = ::tracing::field::Empty,
+            ),
+        }
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    #[inline]
+    #[allow(clippy::extra_unused_type_parameters)]
+    pub(crate) fn new<T: ?Sized>(_operation: &'static str) -> Self {
+        Self {}
+    }
+
+    #[inline]
+    pub(crate) fn record_bytes(&self, bytes: usize) {
+        #[cfg(feature = "tracing")]
+        self.inner.record("bytes", bytes as u64);
+        #[cfg(not(feature = "tracing"))]
+        let _ = bytes;
+    }
+
+    #[inline]
+    pub(crate) fn in_scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        #[cfg(feature = "tracing")]
+        return self.inner.in_scope(f);
+        #[cfg(not(feature = "tracing"))]
+        f()
+    }
+}
+
+#[cfg(all(test, feature = "tracing", feature = "std"))]
+mod tests {
+    use crate::prelude::*;
+    use std::{
+        fmt,
+        sync::{Arc, Mutex},
+    };
+    use tracing::{
+        field::{Field, Visit},
+        span, Event, Metadata, Subscriber,
+    };
+
+    // Collects the fields of every span as `name=value` pairs.
+    #[derive(Default, Clone)]
+    struct Recorder(Arc<Mutex<Vec<Vec<String>>>>);
+
+    struct Fields<'a>(&'a mut Vec<String>);
+
+    impl Visit for Fields<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0.push(format!("{}={:?}", field.name(), value));
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, attributes: &span::Attributes<'_>) -> span::Id {
+            let mut spans = self.0.lock().unwrap();
+            let mut fields = vec![];
+            attributes.record(&mut Fields(&mut fields));
+            spans.push(fields);
+            span::Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, id: &span::Id, values: &span::Record<'_>) {
+            let mut spans = self.0.lock().unwrap();
+            values.record(&mut Fields(&mut spans[id.into_u64() as usize - 1]));
+        }
+
+        fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+        fn event(&self, _: &Event<'_>) {}
+
+        fn enter(&self, _: &span::Id) {}
+
+        fn exit(&self, _: &span::Id) {}
+    }
+
+    #[test]
+    fn test_spans() {
+        let recorder = Recorder::default();
+        tracing::subscriber::with_default(recorder.clone(), || {
+            let mut value = List::<u16, 8>::try_from(vec![1, 2, 3]).unwrap();
+            let encoding = serialize(&value).unwrap();
+            let _ = deserialize::<List<u16, 8>>(&encoding).unwrap();
+            let _ = value.hash_tree_root().unwrap();
+        });
+
+        let type_name = core::any::type_name::<List<u16, 8>>();
+        let spans = recorder.0.lock().unwrap();
+        let expected = [("serialize", Some(6)), ("deserialize", Some(6)), ("hash_tree_root", None)];
+        assert_eq!(spans.len(), expected.len());
+        for (fields, (operation, bytes)) in spans.iter().zip(expected) {
+            assert_eq!(fields[0], format!("operation={operation:?}"));
+            assert_eq!(fields[1], format!("type_name={type_name:?}"));
+            assert_eq!(fields.get(2), bytes.map(|bytes| format!("bytes={bytes}")).as_ref());
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! Fixtures shared by the unit tests of several modules.
+#[cfg(any(feature = "mutate", feature = "test-values", feature = "text"))]
+use crate::lib::*;
+use crate::prelude::*;
+
+/// A validator record, trimmed down from the one of the beacon state.
+#[derive(Default, Debug, Clone, PartialEq, Eq, SimpleSerialize)]
+#[cfg_attr(feature = "compat", derive(Describe))]
+#[ssz(default_root)]
+pub(crate) struct Validator {
+    pub(crate) pubkey: Vector<u8, 48>,
+    pub(crate) effective_balance: u64,
+    pub(crate) slashed: bool,
+    pub(crate) root: Node,
+}
+
+#[cfg(any(feature = "bridge", feature = "mutate", feature = "test-values", feature = "text"))]
+#[derive(Default, Debug, PartialEq, Eq, SimpleSerialize)]
+#[cfg_attr(feature = "bridge", derive(SszBridge), bridge(proto::Inner))]
+#[cfg_attr(feature = "compat", derive(Describe))]
+#[cfg_attr(feature = "test-values", derive(TestValue))]
+#[cfg_attr(feature = "text", derive(CanonicalText))]
+pub(crate) struct Inner {
+    pub(crate) a: u8,
+    pub(crate) b: Bitlist<5>,
+}
+
+#[cfg(any(feature = "mutate", feature = "test-values", feature = "text"))]
+#[derive(Default, Debug, PartialEq, Eq, SimpleSerialize)]
+#[cfg_attr(feature = "compat", derive(Describe))]
+#[cfg_attr(feature = "test-values", derive(TestValue))]
+#[cfg_attr(feature = "text", derive(CanonicalText))]
+pub(crate) enum Union {
+    #[default]
+    None,
+    A(u32),
+    B(Inner),
+}
+
+#[cfg(any(feature = "mutate", feature = "test-values", feature = "text"))]
+#[derive(Default, Debug, PartialEq, Eq, SimpleSerialize)]
+#[cfg_attr(feature = "compat", derive(Describe))]
+#[cfg_attr(feature = "test-values", derive(TestValue))]
+#[cfg_attr(feature = "text", derive(CanonicalText))]
+pub(crate) struct Outer {
+    pub(crate) inner: Inner,
+    pub(crate) list: List<Inner, 3>,
+    pub(crate) vector: Vector<u16, 4>,
+    pub(crate) root: Node,
+    pub(crate) large: List<u8, 1099511627776>,
+    pub(crate) union: Union,
+    pub(crate) name: Utf8List<16>,
+    #[ssz(as_list(max = 2))]
+    pub(crate) map: BTreeMap<u8, bool>,
+}
+
+/// A container with a counterpart in `proto` for each kind of conversion of `ssz_rs::bridge`.
+#[cfg(feature = "bridge")]
+#[derive(Default, Debug, PartialEq, Eq, SimpleSerialize, SszBridge)]
+#[bridge(proto::Message)]
+pub(crate) struct Message {
+    pub(crate) index: u8,
+    pub(crate) inners: List<Inner, 2>,
+    pub(crate) parent: Option<Inner>,
+    pub(crate) data: Vector<u8, 4>,
+    pub(crate) balance: U256,
+}
+
+/// The structs `prost` would generate for the protobuf messages of `Inner` and `Message`.
+#[cfg(feature = "bridge")]
+pub(crate) mod proto {
+    #[derive(Default, Debug, Clone, PartialEq, Eq)]
+    pub(crate) struct Inner {
+        pub(crate) a: u32,
+        pub(crate) b: Vec<u8>,
+    }
+
+    #[derive(Default, Debug, Clone, PartialEq, Eq)]
+    pub(crate) struct Message {
+        pub(crate) index: u32,
+        pub(crate) inners: Vec<Inner>,
+        pub(crate) parent: Option<Inner>,
+        pub(crate) data: Vec<u8>,
+        pub(crate) balance: Vec<u8>,
+    }
+}
+
+/// An `Outer` with a value in each field and special characters in `name`.
+#[cfg(any(feature = "mutate", feature = "text"))]
+pub(crate) fn outer() -> Outer {
+    let inner = |a, bits: &[bool]| Inner { a, b: bits.iter().copied().collect() };
+    Outer {
+        inner: inner(1, &[true, false, true]),
+        list: List::try_from(vec![inner(2, &[]), inner(3, &[true])]).unwrap(),
+        vector: Vector::try_from(vec![1u16, 2, 3, 65535]).unwrap(),
+        root: Node::default(),
+        large: List::try_from(vec![5, 6]).unwrap(),
+        union: Union::B(inner(4, &[])),
+        name: Utf8List::try_from("a\"\\\né".to_string()).unwrap(),
+        map: BTreeMap::from([(7, true)]),
+    }
+}
@@ -0,0 +1,180 @@
+//! Deterministic values of SSZ types for benchmarks and golden tests.
+//!
+//! Unlike values generated from fuzzer input, a value generated from a `seed` is the same
+//! on every run and platform, so it can be checked into test fixtures. Generated values
+//! always respect the limits of their type, although collections with a large limit are
+//! kept short (at most [`MAX_TEST_LENGTH`] elements).
+//!
+//! ```rust
+//! use ssz_rs::prelude::*;
+//!
+//! #[derive(Default, Debug, PartialEq, Eq, SimpleSerialize, TestValue)]
+//! struct Foo {
+//!     a: u64,
+//!     b: List<u16, 4>,
+//! }
+//!
+//! let value = Foo::test_value(7);
+//! assert_eq!(value, Foo::test_value(7));
+//! assert!(value.b.len() <= 4);
+//! ```
+use crate::{
+    bitlist::Bitlist, bitvector::Bitvector, lib::*, list::List, merkleization::Node, uint::U256,
+    utf8_list::Utf8List, vector::Vector, SimpleSerialize,
+};
+
+/// The maximum number of elements of a generated collection with a variable length.
+pub const MAX_TEST_LENGTH: usize = 16;
+
+/// A type with deterministic values generated from a seed.
+pub trait TestValue: core::marker::Sized {
+    /// Return the value of `Self` generated from `seed`.
+    fn test_value(seed: u64) -> Self;
+}
+
+/// Derive the seed for the part of a value at `index` from the `seed` of the value.
+pub fn mix(seed: u64, index: u64) -> u64 {
+    // NOTE: the finalizer of `SplitMix64`
+    let mut z = seed ^ index.wrapping_add(1).wrapping_mul(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+// Return the length of a generated collection with at most `limit` elements.
+fn length(seed: u64, limit: usize) -> usize {
+    let bound = limit.min(MAX_TEST_LENGTH) as u64;
+    (mix(seed, u64::MAX) % (bound + 1)) as usize
+}
+
+/// Return the map generated from `seed` with at most `N` entries.
+pub fn map<K, V, const N: usize>(seed: u64) -> BTreeMap<K, V>
+where
+    K: TestValue + Ord,
+    V: TestValue,
+{
+    (0..length(seed, N) as u64)
+        .map(|i| (K::test_value(mix(seed, 2 * i)), V::test_value(mix(seed, 2 * i + 1))))
+        .collect()
+}
+
+macro_rules! define_test_value_for_uint {
+    ($($uint:ty),*) => {
+        $(
+            impl TestValue for $uint {
+                fn test_value(seed: u64) -> Self {
+                    mix(seed, 0) as $uint
+                }
+            }
+        )*
+    };
+}
+
+define_test_value_for_uint!(u8, u16, u32, u64, usize);
+
+impl TestValue for u128 {
+    fn test_value(seed: u64) -> Self {
+        (mix(seed, 1) as u128) << 64 | mix(seed, 0) as u128
+    }
+}
+
+impl TestValue for bool {
+    fn test_value(seed: u64) -> Self {
+        mix(seed, 0) % 2 == 1
+    }
+}
+
+impl TestValue for U256 {
+    fn test_value(seed: u64) -> Self {
+        Self::from_bytes_le(<[u8; 32]>::test_value(seed))
+    }
+}
+
+impl TestValue for Node {
+    fn test_value(seed: u64) -> Self {
+        Self::try_from(<[u8; 32]>::test_value(seed).as_ref()).expect("is one chunk")
+    }
+}
+
+impl<T: TestValue, const N: usize> TestValue for [T; N] {
+    fn test_value(seed: u64) -> Self {
+        core::array::from_fn(|i| T::test_value(mix(seed, i as u64)))
+    }
+}
+
+impl<T: SimpleSerialize + TestValue, const N: usize> TestValue for Vector<T, N> {
+    fn test_value(seed: u64) -> Self {
+        Self::try_from_fn(|i| Ok::<_, ()>(T::test_value(mix(seed, i as u64))))
+            .expect("is infallible")
+    }
+}
+
+impl<T: SimpleSerialize + TestValue, const N: usize> TestValue for List<T, N> {
+    fn test_value(seed: u64) -> Self {
+        let elements =
+            (0..length(seed, N) as u64).map(|i| T::test_value(mix(seed, i))).collect::<Vec<_>>();
+        match Self::try_from(elements) {
+            Ok(result) => result,
+            Err(_) => unreachable!("length is within limit"),
+        }
+    }
+}
+
+impl<const N: usize> TestValue for Bitvector<N> {
+    fn test_value(seed: u64) -> Self {
+        let mut result = Self::default();
+        for i in 0..N {
+            result.set(i, bool::test_value(mix(seed, i as u64)));
+        }
+        result
+    }
+}
+
+impl<const N: usize> TestValue for Bitlist<N> {
+    fn test_value(seed: u64) -> Self {
+        (0..length(seed, N) as u64).map(|i| bool::test_value(mix(seed, i))).collect()
+    }
+}
+
+impl<const N: usize> TestValue for Utf8List<N> {
+    fn test_value(seed: u64) -> Self {
+        // NOTE: use ASCII letters so each character is one byte
+        let data = (0..length(seed, N) as u64)
+            .map(|i| char::from(b'a' + (mix(seed, i) % 26) as u8))
+            .collect::<String>();
+        Self::try_from(data).expect("is within limit")
+    }
+}
+
+impl<T: TestValue> TestValue for Option<T> {
+    fn test_value(seed: u64) -> Self {
+        bool::test_value(seed).then(|| T::test_value(mix(seed, 1)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        prelude::*,
+        test_utils::{Outer, Union},
+    };
+
+    #[test]
+    fn test_values_are_deterministic_and_valid() {
+        for seed in 0..32 {
+            let value = Outer::test_value(seed);
+            assert_eq!(value, Outer::test_value(seed));
+            assert!(value.list.len() <= 3);
+            assert!(value.large.len() <= MAX_TEST_LENGTH);
+            assert!(value.name.len() <= 16);
+            assert!(value.map.len() <= 2);
+
+            let encoding = serialize(&value).unwrap();
+            assert_eq!(Outer::deserialize(&encoding).unwrap(), value);
+        }
+        assert_ne!(Outer::test_value(0), Outer::test_value(1));
+        assert!((0..32).any(|seed| matches!(Union::test_value(seed), Union::None)));
+        assert!((0..32).any(|seed| matches!(Union::test_value(seed), Union::B(..))));
+    }
+}
@@ -0,0 +1,744 @@
+//! A canonical textual encoding of SSZ values for snapshot tests.
+//!
+//! The text of a value lists the fields of a container in declaration order, one per line,
+//! writes bytes and bits as digits rather than lists of numbers and states the length of
+//! every collection, so a change to a value shows up in a diff as the lines of the fields
+//! that changed. The text of a value is unique and [`from_text`] recovers the value from it.
+//!
+//! ```rust
+//! use ssz_rs::prelude::*;
+//!
+//! #[derive(Default, Debug, PartialEq, Eq, SimpleSerialize, CanonicalText)]
+//! struct Foo {
+//!     a: u64,
+//!     b: List<u8, 4>,
+//!     c: Bitvector<3>,
+//! }
+//!
+//! let foo = Foo { a: 7, b: List::try_from(vec![1u8, 255]).unwrap(), c: Bitvector::default() };
+//! let text = ssz_rs::text::to_text(&foo);
+//! assert_eq!(text, "{\n  a: 7,\n  b: list(2) 0x01ff,\n  c: bitvector(3) 0b000,\n}");
+//! assert_eq!(ssz_rs::text::from_text::<Foo>(&text).unwrap(), foo);
+//! ```
+use crate::{
+    bitlist::Bitlist, bitvector::Bitvector, hex, lib::*, list::List, merkleization::Node,
+    uint::U256, utf8_list::Utf8List, vector::Vector, SimpleSerialize,
+};
+
+/// An error parsing the text of a value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextError {
+    /// The offset in bytes into the text where parsing failed.
+    pub position: usize,
+    /// A description of what the parser expected at `position`.
+    pub expected: &'static str,
+}
+
+impl Display for TextError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "expected {} at byte {}", self.expected, self.position)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TextError {}
+
+/// A type with a canonical textual encoding.
+pub trait CanonicalText: core::marker::Sized {
+    /// Write the text of `self` to `writer`.
+    fn write_text(&self, writer: &mut TextWriter);
+
+    /// Parse a value from the text at the position of `parser`.
+    fn parse_text(parser: &mut TextParser<'_>) -> Result<Self, TextError>;
+
+    /// Return `true` if the text of a value never spans multiple lines.
+    fn is_inline() -> bool {
+        false
+    }
+
+    /// Write the text of a sequence of values, without its length.
+    fn write_slice_text(values: &[Self], writer: &mut TextWriter) {
+        if values.is_empty() {
+            writer.write("[]");
+        } else if Self::is_inline() {
+            writer.write("[");
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    writer.write(", ");
+                }
+                value.write_text(writer);
+            }
+            writer.write("]");
+        } else {
+            writer.begin("[");
+            for value in values {
+                writer.item();
+                value.write_text(writer);
+                writer.write(",");
+            }
+            writer.end("]");
+        }
+    }
+
+    /// Parse a sequence of values written by `write_slice_text`.
+    fn parse_slice_text(parser: &mut TextParser<'_>) -> Result<Vec<Self>, TextError> {
+        parser.expect("[")?;
+        let mut values = vec![];
+        while !parser.eat("]") {
+            values.push(Self::parse_text(parser)?);
+            if !parser.eat(",") {
+                parser.expect("]")?;
+                break
+            }
+        }
+        Ok(values)
+    }
+}
+
+/// Return the text of `value`.
+pub fn to_text<T: CanonicalText>(value: &T) -> String {
+    let mut writer = TextWriter::default();
+    value.write_text(&mut writer);
+    writer.finish()
+}
+
+/// Parse a value of type `T` from `text`, which must contain nothing else.
+pub fn from_text<T: CanonicalText>(text: &str) -> Result<T, TextError> {
+    let mut parser = TextParser::new(text);
+    let value = T::parse_text(&mut parser)?;
+    parser.finish()?;
+    Ok(value)
+}
+
+/// Accumulates the text of a value, tracking the indentation of nested values.
+#[derive(Debug, Default)]
+pub struct TextWriter {
+    output: String,
+    indent: usize,
+}
+
+impl TextWriter {
+    pub fn write(&mut self, text: &str) {
+        self.output.push_str(text);
+    }
+
+    /// Write `open` and indent the items until the matching `end`.
+    pub fn begin(&mut self, open: &str) {
+        self.write(open);
+        self.indent += 1;
+    }
+
+    /// Start a new line for the next item.
+    pub fn item(&mut self) {
+        self.output.push('\n');
+        for _ in 0..self.indent {
+            self.output.push_str("  ");
+        }
+    }
+
+    /// Write `close` on a new line at the indentation of the matching `begin`.
+    pub fn end(&mut self, close: &str) {
+        self.indent -= 1;
+        self.item();
+        self.write(close);
+    }
+
+    /// Write the name of a field of a container as a new item.
+    pub fn field(&mut self, name: &str) {
+        self.item();
+        self.write(name);
+        self.write(": ");
+    }
+
+    pub fn finish(self) -> String {
+        self.output
+    }
+}
+
+/// Parses values from their text, skipping whitespace between tokens.
+#[derive(Debug)]
+pub struct TextParser<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl<'a> TextParser<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self { input, position: 0 }
+    }
+
+    /// Return an error at the current position.
+    pub fn error(&self, expected: &'static str) -> TextError {
+        TextError { position: self.position, expected }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.position..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    /// Consume `token` if the text continues with it.
+    pub fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        let found = self.rest().starts_with(token);
+        if found {
+            self.position += token.len();
+        }
+        found
+    }
+
+    /// Consume `token` or return an error if the text does not continue with it.
+    pub fn expect(&mut self, token: &'static str) -> Result<(), TextError> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(token))
+        }
+    }
+
+    /// Consume the next word, i.e. a run of ASCII letters, digits and underscores.
+    pub fn word(&mut self) -> Result<&'a str, TextError> {
+        self.skip_whitespace();
+        let rest = self.rest();
+        let len =
+            rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error("a word"))
+        }
+        self.position += len;
+        Ok(&rest[..len])
+    }
+
+    /// Consume the name of a field followed by a colon.
+    pub fn field(&mut self, name: &'static str) -> Result<(), TextError> {
+        self.skip_whitespace();
+        let start = self.position;
+        if self.word().ok() != Some(name) {
+            return Err(TextError { position: start, expected: name })
+        }
+        self.expect(":")
+    }
+
+    /// Consume a decimal number.
+    pub fn number<T: str::FromStr>(&mut self) -> Result<T, TextError> {
+        self.skip_whitespace();
+        let start = self.position;
+        self.word()?.parse().map_err(|_| TextError { position: start, expected: "a number" })
+    }
+
+    /// Consume a collection header, e.g. `list(3)`, and return the stated length.
+    pub fn header(&mut self, kind: &'static str) -> Result<usize, TextError> {
+        self.expect(kind)?;
+        self.expect("(")?;
+        let len = self.number()?;
+        self.expect(")")?;
+        Ok(len)
+    }
+
+    /// Consume a word starting with `prefix` and return the digits following it.
+    pub fn digits(&mut self, prefix: &'static str) -> Result<&'a str, TextError> {
+        self.skip_whitespace();
+        let start = self.position;
+        self.word()?.strip_prefix(prefix).ok_or(TextError { position: start, expected: prefix })
+    }
+
+    /// Consume bytes written as `0x` and a pair of hex digits per byte.
+    pub fn bytes(&mut self) -> Result<Vec<u8>, TextError> {
+        self.skip_whitespace();
+        let start = self.position;
+        let digits = self.digits(hex::PREFIX)?;
+        hex::decode(digits)
+            .map_err(|_| TextError { position: start, expected: "pairs of hex digits" })
+    }
+
+    /// Consume a quoted string, resolving the escapes written by `write_string`.
+    pub fn string(&mut self) -> Result<String, TextError> {
+        self.expect("\"")?;
+        let mut result = String::new();
+        let mut chars = self.rest().char_indices();
+        let start = self.position;
+        let error = move |offset| TextError { position: start + offset, expected: "a string" };
+        loop {
+            match chars.next() {
+                Some((offset, '"')) => {
+                    self.position += offset + 1;
+                    return Ok(result)
+                }
+                Some((offset, '\\')) => match chars.next() {
+                    Some((_, c @ ('"' | '\\'))) => result.push(c),
+                    Some((_, 'u')) => {
+                        let code = chars
+                            .by_ref()
+                            .skip(1)
+                            .take_while(|(_, c)| *c != '}')
+                            .map(|(_, c)| c)
+                            .collect::<String>();
+                        let c = u32::from_str_radix(&code, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or(error(offset))?;
+                        result.push(c);
+                    }
+                    _ => return Err(error(offset)),
+                },
+                Some((_, c)) => result.push(c),
+                None => return Err(error(self.input.len() - start)),
+            }
+        }
+    }
+
+    /// Return an error unless only whitespace remains.
+    pub fn finish(mut self) -> Result<(), TextError> {
+        self.skip_whitespace();
+        if self.rest().is_empty() {
+            Ok(())
+        } else {
+            Err(self.error("the end of the text"))
+        }
+    }
+}
+
+/// Write `value` as a quoted string, escaping quotes, backslashes and control characters.
+pub fn write_string(value: &str, writer: &mut TextWriter) {
+    writer.write("\"");
+    for c in value.chars() {
+        match c {
+            '"' => writer.write("\\\""),
+            '\\' => writer.write("\\\\"),
+            c if c.is_control() => writer.write(&format!("\\u{{{:x}}}", c as u32)),
+            c => writer.output.push(c),
+        }
+    }
+    writer.write("\"");
+}
+
+fn write_bytes(bytes: &[u8], writer: &mut TextWriter) {
+    writer.write(&hex::encode_0x(bytes));
+}
+
+fn write_bits<'a>(bits: impl Iterator<Item = &'a bool>, writer: &mut TextWriter) {
+    writer.write("0b");
+    for bit in bits {
+        writer.write(if *bit { "1" } else { "0" });
+    }
+}
+
+fn parse_bits(parser: &mut TextParser<'_>, len: usize) -> Result<Vec<bool>, TextError> {
+    parser.skip_whitespace();
+    let start = parser.position;
+    let bits = parser
+        .digits("0b")?
+        .chars()
+        .map(|c| match c {
+            '0' => Some(false),
+            '1' => Some(true),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()
+        .filter(|bits| bits.len() == len)
+        .ok_or(TextError { position: start, expected: "one binary digit per bit" })?;
+    Ok(bits)
+}
+
+fn check_len(start: usize, len: usize, expected: usize) -> Result<(), TextError> {
+    if len == expected {
+        Ok(())
+    } else {
+        Err(TextError { position: start, expected: "as many elements as the stated length" })
+    }
+}
+
+macro_rules! define_text_for_uint {
+    ($($uint:ty),*) => {
+        $(
+            impl CanonicalText for $uint {
+                fn write_text(&self, writer: &mut TextWriter) {
+                    writer.write(&format!("{self}"));
+                }
+
+                fn parse_text(parser: &mut TextParser<'_>) -> Result<Self, TextError> {
+                    parser.number()
+                }
+
+                fn is_inline() -> bool {
+                    true
+                }
+            }
+        )*
+    };
+}
+
+define_text_for_uint!(u16, u32, u64, u128, usize);
+
+impl CanonicalText for u8 {
+    fn write_text(&self, writer: &mut TextWriter) {
+        writer.write(&format!("{self}"));
+    }
+
+    fn parse_text(parser: &mut TextParser<'_>) -> Result<Self, TextError> {
+        parser.number()
+    }
+
+    fn is_inline() -> bool {
+        true
+    }
+
+    fn write_slice_text(values: &[Self], writer: &mut TextWriter) {
+        write_bytes(values, writer);
+    }
+
+    fn parse_slice_text(parser: &mut TextParser<'_>) -> Result<Vec<Self>, TextError> {
+        parser.bytes()
+    }
+}
+
+impl CanonicalText for bool {
+    fn write_text(&self, writer: &mut TextWriter) {
+        writer.write(if *self { "true" } else { "false" });
+    }
+
+    fn parse_text(parser: &mut TextParser<'_>) -> Result<Self, TextError> {
+        if parser.eat("true") {
+            Ok(true)
+        } else if parser.eat("false") {
+            Ok(false)
+        } else {
+            Err(parser.error("a boolean"))
+        }
+    }
+
+    fn is_inline() -> bool {
+        true
+    }
+}
+
+impl CanonicalText for U256 {
+    fn write_text(&self, writer: &mut TextWriter) {
+        let mut bytes = self.to_bytes_le();
+        bytes.reverse();
+        write_bytes(&bytes, writer);
+    }
+
+    fn parse_text(parser: &mut TextParser<'_>) -> Result<Self, TextError> {
+        parser.skip_whitespace();
+        let start = parser.position;
+        let bytes = parser.bytes()?;
+        let mut bytes = <[u8; 32]>::try_from(bytes)
+            .map_err(|_| TextError { position: start, expected: "32 bytes" })?;
+        bytes.reverse();
+        Ok(Self::from_bytes_le(bytes))
+    }
+
+    fn is_inline() -> bool {
+        true
+    }
+}
+
+impl CanonicalText for Node {
+    fn write_text(&self, writer: &mut TextWriter) {
+        write_bytes(self.as_ref(), writer);
+    }
+
+    fn parse_text(parser: &mut TextParser<'_>) -> Result<Self, TextError> {
+        parser.skip_whitespace();
+        let start = parser.position;
+        let bytes = parser.bytes()?;
+        Self::try_from(bytes.as_ref())
+            .map_err(|_| TextError { position: start, expected: "32 bytes" })
+    }
+
+    fn is_inline() -> bool {
+        true
+    }
+}
+
+impl<T: CanonicalText, const N: usize> CanonicalText for [T; N] {
+    fn write_text(&self, writer: &mut TextWriter) {
+        writer.write(&format!("vector({N}) "));
+        T::write_slice_text(self, writer);
+    }
+
+    fn parse_text(parser: &mut TextParser<'_>) -> Result<Self, TextError> {
+        let len = parser.header("vector")?;
+        parser.skip_whitespace();
+        let start = parser.position;
+        let elements = T::parse_slice_text(parser)?;
+        check_len(start, len, N)?;
+        check_len(start, elements.len(), len)?;
+        match Self::try_from(elements) {
+            Ok(result) => Ok(result),
+            Err(_) => unreachable!("length was checked"),
+        }
+    }
+}
+
+impl<T: SimpleSerialize + CanonicalText, const N: usize> CanonicalText for Vector<T, N> {
+    fn write_text(&self, writer: &mut TextWriter) {
+        writer.write(&format!("vector({N}) "));
+        T::write_slice_text(self, writer);
+    }
+
+    fn parse_text(parser: &mut TextParser<'_>) -> Result<Self, TextError> {
+        let len = parser.header("vector")?;
+        parser.skip_whitespace();
+        let start = parser.position;
+        let elements = T::parse_slice_text(parser)?;
+        check_len(start, len, N)?;
+        check_len(start, elements.len(), len)?;
+        Self::try_from(elements)
+            .map_err(|_| TextError { position: start, expected: "a vector of non-zero length" })
+    }
+}
+
+impl<T: SimpleSerialize + CanonicalText, const N: usize> CanonicalText for List<T, N> {
+    fn write_text(&self, writer: &mut TextWriter) {
+        writer.write(&format!("list({}) ", self.len()));
+        T::write_slice_text(self, writer);
+    }
+
+    fn parse_text(parser: &mut TextParser<'_>) -> Result<Self, TextError> {
+        let len = parser.header("list")?;
+        parser.skip_whitespace();
+        let start = parser.position;
+        let elements = T::parse_slice_text(parser)?;
+        check_len(start, elements.len(), len)?;
+        Self::try_from(elements)
+            .map_err(|_| TextError { position: start, expected: "at most the maximum length" })
+    }
+}
+
+impl<const N: usize> CanonicalText for Bitvector<N> {
+    fn write_text(&self, writer: &mut TextWriter) {
+        writer.write(&format!("bitvector({N}) "));
+        write_bits(self.iter().by_refs(), writer);
+    }
+
+    fn parse_text(parser: &mut TextParser<'_>) -> Result<Self, TextError> {
+        let start = parser.position;
+        let len = parser.header("bitvector")?;
+        if len != N {
+            return Err(TextError { position: start, expected: "the length of the bitvector" })
+        }
+        let mut result = Self::default();
+        for (i, bit) in parse_bits(parser, len)?.into_iter().enumerate() {
+            result.set(i, bit);
+        }
+        Ok(result)
+    }
+
+    fn is_inline() -> bool {
+        true
+    }
+}
+
+impl<const N: usize> CanonicalText for Bitlist<N> {
+    fn write_text(&self, writer: &mut TextWriter) {
+        writer.write(&format!("bitlist({}) ", self.len()));
+        write_bits(self.iter().by_refs(), writer);
+    }
+
+    fn parse_text(parser: &mut TextParser<'_>) -> Result<Self, TextError> {
+        let start = parser.position;
+        let len = parser.header("bitlist")?;
+        if len > N {
+            return Err(TextError { position: start, expected: "at most the maximum length" })
+        }
+        Ok(parse_bits(parser, len)?.into_iter().collect())
+    }
+
+    fn is_inline() -> bool {
+        true
+    }
+}
+
+impl<const N: usize> CanonicalText for Utf8List<N> {
+    fn write_text(&self, writer: &mut TextWriter) {
+        writer.write(&format!("utf8({}) ", self.len()));
+        write_string(self, writer);
+    }
+
+    fn parse_text(parser: &mut TextParser<'_>) -> Result<Self, TextError> {
+        let len = parser.header("utf8")?;
+        parser.skip_whitespace();
+        let start = parser.position;
+        let data = parser.string()?;
+        check_len(start, data.len(), len)?;
+        Self::try_from(data)
+            .map_err(|_| TextError { position: start, expected: "at most the maximum length" })
+    }
+
+    fn is_inline() -> bool {
+        true
+    }
+}
+
+impl<T: CanonicalText> CanonicalText for Option<T> {
+    fn write_text(&self, writer: &mut TextWriter) {
+        match self {
+            Some(value) => {
+                writer.write("Some(");
+                value.write_text(writer);
+                writer.write(")");
+            }
+            None => writer.write("None"),
+        }
+    }
+
+    fn parse_text(parser: &mut TextParser<'_>) -> Result<Self, TextError> {
+        if parser.eat("None") {
+            return Ok(None)
+        }
+        parser.expect("Some")?;
+        parser.expect("(")?;
+        let value = T::parse_text(parser)?;
+        parser.expect(")")?;
+        Ok(Some(value))
+    }
+
+    fn is_inline() -> bool {
+        T::is_inline()
+    }
+}
+
+/// Write the text of a map encoded as the list of its entries, see `ssz_rs::map`.
+pub fn write_map<K, V>(map: &BTreeMap<K, V>, writer: &mut TextWriter)
+where
+    K: CanonicalText,
+    V: CanonicalText,
+{
+    writer.write(&format!("list({}) ", map.len()));
+    if map.is_empty() {
+        writer.write("[]");
+        return
+    }
+    writer.begin("[");
+    for (key, value) in map {
+        writer.item();
+        writer.begin("{");
+        writer.field("key");
+        key.write_text(writer);
+        writer.write(",");
+        writer.field("value");
+        value.write_text(writer);
+        writer.write(",");
+        writer.end("},");
+    }
+    writer.end("]");
+}
+
+/// Parse a map with at most `N` entries written by `write_map`.
+pub fn parse_map<K, V, const N: usize>(
+    parser: &mut TextParser<'_>,
+) -> Result<BTreeMap<K, V>, TextError>
+where
+    K: CanonicalText + Ord,
+    V: CanonicalText,
+{
+    let len = parser.header("list")?;
+    parser.skip_whitespace();
+    let start = parser.position;
+    if len > N {
+        return Err(TextError { position: start, expected: "at most the maximum length" })
+    }
+    parser.expect("[")?;
+    let mut map = BTreeMap::new();
+    while !parser.eat("]") {
+        parser.expect("{")?;
+        parser.field("key")?;
+        let key_position = parser.position;
+        let key = K::parse_text(parser)?;
+        parser.expect(",")?;
+        parser.field("value")?;
+        let value = V::parse_text(parser)?;
+        parser.eat(",");
+        parser.expect("}")?;
+        if matches!(map.last_key_value(), Some((last, _)) if *last >= key) {
+            return Err(TextError { position: key_position, expected: "keys in increasing order" })
+        }
+        map.insert(key, value);
+        if !parser.eat(",") {
+            parser.expect("]")?;
+            break
+        }
+    }
+    check_len(start, map.len(), len)?;
+    Ok(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{outer, Inner, Outer, Union};
+
+    const OUTER: &str = r#"{
+  inner: {
+    a: 1,
+    b: bitlist(3) 0b101,
+  },
+  list: list(2) [
+    {
+      a: 2,
+      b: bitlist(0) 0b,
+    },
+    {
+      a: 3,
+      b: bitlist(1) 0b1,
+    },
+  ],
+  vector: vector(4) [1, 2, 3, 65535],
+  root: 0x0000000000000000000000000000000000000000000000000000000000000000,
+  large: list(2) 0x0506,
+  union: B({
+    a: 4,
+    b: bitlist(0) 0b,
+  }),
+  name: utf8(6) "a\"\\\u{a}é",
+  map: list(1) [
+    {
+      key: 7,
+      value: true,
+    },
+  ],
+}"#;
+
+    #[test]
+    fn test_round_trip() {
+        let value = outer();
+        assert_eq!(to_text(&value), OUTER);
+        assert_eq!(from_text::<Outer>(OUTER).unwrap(), value);
+
+        let compact = OUTER.split_whitespace().collect::<Vec<_>>().join(" ");
+        assert_eq!(from_text::<Outer>(&compact).unwrap(), value);
+
+        let value = Outer { union: Union::A(9), ..Default::default() };
+        assert_eq!(from_text::<Outer>(&to_text(&value)).unwrap(), value);
+        assert_eq!(to_text(&U256::from(1)), format!("0x{}01", "00".repeat(31)));
+        assert_eq!(from_text::<U256>(&to_text(&U256::from(1))).unwrap(), U256::from(1));
+        assert_eq!(to_text(&Some(List::<u32, 2>::default())), "Some(list(0) [])");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            from_text::<List<u8, 4>>("list(3) 0x0102"),
+            Err(TextError { position: 8, expected: "as many elements as the stated length" })
+        );
+        assert!(from_text::<List<u8, 1>>("list(2) 0x0102").is_err());
+        assert!(from_text::<Bitvector<3>>("bitvector(3) 0b01").is_err());
+        assert!(from_text::<Bitlist<3>>("bitlist(4) 0b0101").is_err());
+        assert!(from_text::<bool>("true false").is_err());
+        assert!(from_text::<Union>("C(1)").is_err());
+        assert!(from_text::<Inner>("{ b: bitlist(0) 0b, a: 1, }").is_err());
+
+        let unordered = "list(2) [{ key: 2, value: true }, { key: 1, value: false }]";
+        let mut parser = TextParser::new(unordered);
+        assert_eq!(
+            parse_map::<u8, bool, 2>(&mut parser).unwrap_err().expected,
+            "keys in increasing order"
+        );
+    }
+}
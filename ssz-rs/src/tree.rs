@@ -0,0 +1,812 @@
+//! A `Vector<T, N>` and a `List<T, N>` kept in a persistent Merkle tree, for large collections
+//! which are copied often, like the validators and balances of snapshots of the beacon state.
+//!
+//! A [`TreeVector`] or [`TreeList`] stores its elements in the leaves of its Merkle tree, one
+//! chunk per leaf, behind nodes shared through an `Arc`. Cloning one only clones the `Arc` of
+//! its root, and writing to an element copies the nodes on the path to its leaf if they are
+//! shared, so a clone and the original share every node but the ones written to since.
+//!
+//! Each node keeps its root once computed, so hashing again after a write only hashes the path
+//! to the written leaf. A node shared with a clone keeps a root computed before it was shared,
+//! so hash the tree before taking snapshots of it. The encoding and root are those of the
+//! equivalent `Vector<T, N>` or `List<T, N>`.
+//!
+//! ```rust
+//! use ssz_rs::{prelude::*, tree::TreeList};
+//!
+//! let mut balances = TreeList::<u64, 1024>::try_from((0..1000u64).collect::<Vec<_>>()).unwrap();
+//! let root = balances.hash_tree_root().unwrap();
+//! let mut snapshot = balances.clone();
+//! *balances.get_mut(7).unwrap() += 1;
+//! assert_eq!(snapshot[7], 7);
+//! assert_eq!(snapshot.hash_tree_root().unwrap(), root);
+//!
+//! let mut list = List::<u64, 1024>::try_from(balances.iter().copied().collect::<Vec<_>>()).unwrap();
+//! assert_eq!(balances.hash_tree_root().unwrap(), list.hash_tree_root().unwrap());
+//! assert_eq!(balances.to_list(), list);
+//! ```
+use crate::{
+    de::{Deserialize, DeserializeError},
+    error::{Error, InstanceError},
+    lib::*,
+    list::List,
+    merkleization::{
+        chunks_to_nodes, depth_for_chunks, element_leaf, elements_chunk_count,
+        elements_chunk_count_const, elements_chunks, hash_nodes, mix_in_length, Context,
+        GeneralizedIndex, MerkleHasher, MerkleizationError, Merkleized, Node, PathElement,
+        PathError, BYTES_PER_CHUNK,
+    },
+    ser::{serialize_composite, Serialize, SerializeError},
+    vector::Vector,
+    SimpleSerialize, Sized,
+};
+
+// A node of the Merkle tree of the elements.
+#[derive(Clone)]
+enum Kind<T> {
+    // the subtrees on the left and right, where a missing subtree is a zero subtree
+    Branch([Option<Arc<TreeNode<T>>>; 2]),
+    // the elements of one chunk, i.e. the basic elements packed into it or a composite element
+    Leaf(Vec<T>),
+}
+
+#[derive(Clone)]
+struct TreeNode<T> {
+    kind: Kind<T>,
+    // the root of the node and the hash function it was computed with, dropped on writes
+    root: Option<(TypeId, Node)>,
+}
+
+impl<T> TreeNode<T> {
+    // Return an empty node at `height` above the leaves.
+    fn empty(height: u32) -> Self {
+        let kind = if height == 0 { Kind::Leaf(vec![]) } else { Kind::Branch([None, None]) };
+        Self { kind, root: None }
+    }
+
+    fn kept_root<H: MerkleHasher>(&self) -> Option<Node> {
+        match self.root {
+            Some((hasher, root)) if hasher == TypeId::of::<H>() => Some(root),
+            _ => None,
+        }
+    }
+
+    fn heap_size(&self) -> usize
+    where
+        T: SimpleSerialize,
+    {
+        let size = match &self.kind {
+            Kind::Branch(children) => {
+                children.iter().flatten().map(|child| child.heap_size()).sum()
+            }
+            Kind::Leaf(elements) => {
+                elements.capacity() * mem::size_of::<T>() +
+                    elements.iter().map(T::ssz_heap_size).sum::<usize>()
+            }
+        };
+        mem::size_of::<Self>() + size
+    }
+}
+
+// Return the depth of the Merkle tree of `count` elements of type `T`.
+fn tree_depth<T: SimpleSerialize>(count: usize) -> u32 {
+    depth_for_chunks(elements_chunk_count::<T>(count))
+}
+
+// Return the number of elements in a leaf of the tree of elements of type `T`.
+fn elements_per_leaf<T: SimpleSerialize>() -> usize {
+    if T::is_composite_type() {
+        1
+    } else {
+        BYTES_PER_CHUNK / T::size_hint()
+    }
+}
+
+// Return the node of `slot` at `height` to write to, inserting an empty node if it is missing
+// and copying it if it is shared.
+fn make_mut<T: Clone>(slot: &mut Option<Arc<TreeNode<T>>>, height: u32) -> &mut TreeNode<T> {
+    let node = Arc::make_mut(slot.get_or_insert_with(|| Arc::new(TreeNode::empty(height))));
+    node.root = None;
+    node
+}
+
+// Return the root of the subtree of `slot` at `height`, keeping the roots of the nodes which are
+// not shared.
+fn subtree_root<T, H>(
+    slot: &mut Option<Arc<TreeNode<T>>>,
+    height: u32,
+    context: &Context<H>,
+) -> Result<Node, MerkleizationError>
+where
+    T: SimpleSerialize + Clone,
+    H: MerkleHasher,
+{
+    let Some(node) = slot else { return Ok(Node::zero_at_depth(height as usize, context)) };
+    if let Some(root) = node.kept_root::<H>() {
+        return Ok(root)
+    }
+    // NOTE: writing the root to a shared node would copy it
+    let Some(node) = Arc::get_mut(node) else { return shared_root(node, height, context) };
+    let root = match &mut node.kind {
+        Kind::Branch([left, right]) => {
+            let left = subtree_root(left, height - 1, context)?;
+            let right = subtree_root(right, height - 1, context)?;
+            hash_pair::<H>(&left, &right)
+        }
+        Kind::Leaf(elements) => element_leaf(elements, 0, context)?,
+    };
+    node.root = Some((TypeId::of::<H>(), root));
+    Ok(root)
+}
+
+// Return the root of the shared `node` at `height` without keeping it.
+fn shared_root<T, H>(
+    node: &TreeNode<T>,
+    height: u32,
+    context: &Context<H>,
+) -> Result<Node, MerkleizationError>
+where
+    T: SimpleSerialize + Clone,
+    H: MerkleHasher,
+{
+    if let Some(root) = node.kept_root::<H>() {
+        return Ok(root)
+    }
+    match &node.kind {
+        Kind::Branch(children) => {
+            let mut roots = [Node::default(); 2];
+            for (root, child) in roots.iter_mut().zip(children) {
+                *root = match child {
+                    Some(child) => shared_root(child, height - 1, context)?,
+                    None => Node::zero_at_depth(height as usize - 1, context),
+                };
+            }
+            Ok(hash_pair::<H>(&roots[0], &roots[1]))
+        }
+        // NOTE: hash a copy of the elements as hashing an element may update its own caches
+        Kind::Leaf(elements) => element_leaf(&mut elements.clone(), 0, context),
+    }
+}
+
+fn hash_pair<H: MerkleHasher>(left: &Node, right: &Node) -> Node {
+    let mut root = Node::default();
+    hash_nodes(&mut H::default(), left.as_ref(), right.as_ref(), root.as_mut());
+    root
+}
+
+// The Merkle tree of `depth` over the elements, missing the subtrees past the last element.
+struct Tree<T> {
+    root: Option<Arc<TreeNode<T>>>,
+    depth: u32,
+}
+
+impl<T> Clone for Tree<T> {
+    fn clone(&self) -> Self {
+        Self { root: self.root.clone(), depth: self.depth }
+    }
+}
+
+impl<T: SimpleSerialize> Tree<T> {
+    // Build the tree of `depth` over `elements` from its leaves up.
+    fn new(elements: Vec<T>, depth: u32) -> Self {
+        let per_leaf = elements_per_leaf::<T>();
+        let mut elements = elements.into_iter().peekable();
+        let mut layer = vec![];
+        while elements.peek().is_some() {
+            let leaf = Kind::Leaf(elements.by_ref().take(per_leaf).collect());
+            layer.push(Arc::new(TreeNode { kind: leaf, root: None }));
+        }
+        for _ in 0..depth {
+            let mut nodes = layer.into_iter();
+            layer = vec![];
+            while let Some(left) = nodes.next() {
+                let branch = Kind::Branch([Some(left), nodes.next()]);
+                layer.push(Arc::new(TreeNode { kind: branch, root: None }));
+            }
+        }
+        Self { root: layer.pop(), depth }
+    }
+
+    fn get(&self, index: usize) -> Option<&T> {
+        let per_leaf = elements_per_leaf::<T>();
+        let leaf_index = index / per_leaf;
+        let mut node = self.root.as_deref()?;
+        for height in (0..self.depth).rev() {
+            let Kind::Branch(children) = &node.kind else { unreachable!("leaves are at height 0") };
+            node = children[(leaf_index >> height) & 1].as_deref()?;
+        }
+        match &node.kind {
+            Kind::Leaf(elements) => elements.get(index % per_leaf),
+            Kind::Branch(..) => unreachable!("branches are above height 0"),
+        }
+    }
+
+    // Return the elements of the leaf at `leaf_index` to write to, copying the shared nodes on
+    // the path to it and inserting the missing ones.
+    fn leaf_mut(&mut self, leaf_index: usize) -> &mut Vec<T>
+    where
+        T: Clone,
+    {
+        let mut node = make_mut(&mut self.root, self.depth);
+        for height in (0..self.depth).rev() {
+            let Kind::Branch(children) = &mut node.kind else {
+                unreachable!("leaves are at height 0")
+            };
+            node = make_mut(&mut children[(leaf_index >> height) & 1], height);
+        }
+        match &mut node.kind {
+            Kind::Leaf(elements) => elements,
+            Kind::Branch(..) => unreachable!("branches are above height 0"),
+        }
+    }
+
+    fn get_mut(&mut self, index: usize) -> &mut T
+    where
+        T: Clone,
+    {
+        let per_leaf = elements_per_leaf::<T>();
+        &mut self.leaf_mut(index / per_leaf)[index % per_leaf]
+    }
+
+    fn hash_tree_root_with<H: MerkleHasher>(
+        &mut self,
+        context: &Context<H>,
+    ) -> Result<Node, MerkleizationError>
+    where
+        T: Clone,
+    {
+        subtree_root(&mut self.root, self.depth, context)
+    }
+
+    fn iter(&self) -> Iter<'_, T> {
+        Iter { nodes: self.root.as_deref().into_iter().collect(), elements: [].iter() }
+    }
+
+    fn heap_size(&self) -> usize {
+        self.root.as_ref().map_or(0, |root| root.heap_size())
+    }
+}
+
+// Remove the leaf at `leaf_index` from the subtree of `slot` at `height`, along with the
+// branches it leaves empty.
+fn remove_leaf<T: Clone>(slot: &mut Option<Arc<TreeNode<T>>>, leaf_index: usize, height: u32) {
+    if height == 0 {
+        *slot = None;
+        return
+    }
+    let node = make_mut(slot, height);
+    let Kind::Branch(children) = &mut node.kind else { unreachable!("leaves are at height 0") };
+    remove_leaf(&mut children[(leaf_index >> (height - 1)) & 1], leaf_index, height - 1);
+    if children.iter().all(Option::is_none) {
+        *slot = None;
+    }
+}
+
+/// An iterator over the elements of a [`TreeVector`] or [`TreeList`] in order.
+pub struct Iter<'a, T> {
+    // the nodes left to visit, the next one last
+    nodes: Vec<&'a TreeNode<T>>,
+    elements: slice::Iter<'a, T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(element) = self.elements.next() {
+                return Some(element)
+            }
+            match &self.nodes.pop()?.kind {
+                Kind::Branch(children) => {
+                    self.nodes.extend(children.iter().rev().flatten().map(|child| &**child))
+                }
+                Kind::Leaf(elements) => self.elements = elements.iter(),
+            }
+        }
+    }
+}
+
+/// A `Vector<T, N>` storing its elements in a persistent Merkle tree.
+pub struct TreeVector<T: SimpleSerialize, const N: usize> {
+    tree: Tree<T>,
+}
+
+impl<T: SimpleSerialize, const N: usize> Clone for TreeVector<T, N> {
+    fn clone(&self) -> Self {
+        Self { tree: self.tree.clone() }
+    }
+}
+
+impl<T: SimpleSerialize + PartialEq, const N: usize> PartialEq for TreeVector<T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<T: SimpleSerialize + Eq, const N: usize> Eq for TreeVector<T, N> {}
+
+impl<T: SimpleSerialize + Default + Clone, const N: usize> Default for TreeVector<T, N> {
+    fn default() -> Self {
+        Self::from(Vector::default())
+    }
+}
+
+impl<T: SimpleSerialize + fmt::Debug, const N: usize> fmt::Debug for TreeVector<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: SimpleSerialize, const N: usize> TreeVector<T, N> {
+    pub fn len(&self) -> usize {
+        N
+    }
+
+    pub fn is_empty(&self) -> bool {
+        N == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.tree.get(index)
+    }
+
+    /// Return the element at `index` to change it, copying the nodes on the path to it if they
+    /// are shared with a clone.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T>
+    where
+        T: Clone,
+    {
+        if index >= N {
+            return None
+        }
+        Some(self.tree.get_mut(index))
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.tree.iter()
+    }
+
+    /// Copy the elements into a `Vector<T, N>`.
+    pub fn to_vector(&self) -> Vector<T, N>
+    where
+        T: Clone,
+    {
+        Vector::try_from(self.iter().cloned().collect::<Vec<_>>())
+            .map_err(|(_, err)| err)
+            .expect("length is exactly N")
+    }
+}
+
+impl<T: SimpleSerialize, const N: usize> Index<usize> for TreeVector<T, N> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get(index).expect("index is within the length")
+    }
+}
+
+impl<T: SimpleSerialize, const N: usize> TryFrom<Vec<T>> for TreeVector<T, N> {
+    type Error = (Vec<T>, Error);
+
+    fn try_from(data: Vec<T>) -> Result<Self, Self::Error> {
+        Vector::<T, N>::try_from(data).map(Self::from)
+    }
+}
+
+impl<T: SimpleSerialize, const N: usize> From<Vector<T, N>> for TreeVector<T, N> {
+    fn from(vector: Vector<T, N>) -> Self {
+        Self { tree: Tree::new(vector.into_inner(), tree_depth::<T>(N)) }
+    }
+}
+
+impl<T: SimpleSerialize, const N: usize> Sized for TreeVector<T, N> {
+    fn is_variable_size() -> bool {
+        Vector::<T, N>::is_variable_size()
+    }
+
+    fn size_hint() -> usize {
+        Vector::<T, N>::size_hint()
+    }
+}
+
+impl<T: SimpleSerialize, const N: usize> Serialize for TreeVector<T, N> {
+    fn serialize(&self, buffer: &mut Vec<u8>) -> Result<usize, SerializeError> {
+        serialize_composite(self.iter(), buffer)
+    }
+}
+
+impl<T: SimpleSerialize, const N: usize> Deserialize for TreeVector<T, N> {
+    fn deserialize(encoding: &[u8]) -> Result<Self, DeserializeError> {
+        Vector::<T, N>::deserialize(encoding).map(Self::from)
+    }
+}
+
+impl<T: SimpleSerialize + Clone, const N: usize> Merkleized for TreeVector<T, N> {
+    fn hash_tree_root_with<H: MerkleHasher>(
+        &mut self,
+        context: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
+        self.tree.hash_tree_root_with(context)
+    }
+
+    fn leaves<H: MerkleHasher>(
+        &mut self,
+        context: &Context<H>,
+    ) -> Result<Vec<Node>, MerkleizationError> {
+        let mut elements = self.iter().cloned().collect::<Vec<_>>();
+        Ok(chunks_to_nodes(&elements_chunks(&mut elements, context)?))
+    }
+
+    const CHUNK_COUNT: usize = elements_chunk_count_const::<T>(N);
+}
+
+impl<T: SimpleSerialize + Clone, const N: usize> SimpleSerialize for TreeVector<T, N> {
+    fn nesting_depth() -> usize {
+        Vector::<T, N>::nesting_depth()
+    }
+
+    const MAX_SIZE: usize = Vector::<T, N>::MAX_SIZE;
+
+    const IS_VARIABLE_SIZE: bool = Vector::<T, N>::IS_VARIABLE_SIZE;
+
+    const NESTING_DEPTH: usize = Vector::<T, N>::NESTING_DEPTH;
+
+    fn generalized_index_from(
+        root: GeneralizedIndex,
+        path: &[PathElement<'_>],
+    ) -> Result<GeneralizedIndex, PathError> {
+        Vector::<T, N>::generalized_index_from(root, path)
+    }
+
+    fn hash_tree_root_from_bytes<H: MerkleHasher>(
+        encoding: &[u8],
+        context: &Context<H>,
+    ) -> Result<Node, Error> {
+        Vector::<T, N>::hash_tree_root_from_bytes(encoding, context)
+    }
+
+    fn default_hash_tree_root_with<H: MerkleHasher>(
+        context: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
+        Vector::<T, N>::default_hash_tree_root_with(context)
+    }
+
+    fn ssz_heap_size(&self) -> usize {
+        self.tree.heap_size()
+    }
+}
+
+/// A `List<T, N>` storing its elements in a persistent Merkle tree.
+pub struct TreeList<T: SimpleSerialize, const N: usize> {
+    tree: Tree<T>,
+    len: usize,
+}
+
+impl<T: SimpleSerialize, const N: usize> Clone for TreeList<T, N> {
+    fn clone(&self) -> Self {
+        Self { tree: self.tree.clone(), len: self.len }
+    }
+}
+
+impl<T: SimpleSerialize, const N: usize> Default for TreeList<T, N> {
+    fn default() -> Self {
+        Self { tree: Tree { root: None, depth: tree_depth::<T>(N) }, len: 0 }
+    }
+}
+
+impl<T: SimpleSerialize + PartialEq, const N: usize> PartialEq for TreeList<T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: SimpleSerialize + Eq, const N: usize> Eq for TreeList<T, N> {}
+
+impl<T: SimpleSerialize + fmt::Debug, const N: usize> fmt::Debug for TreeList<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: SimpleSerialize, const N: usize> TreeList<T, N> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len {
+            return None
+        }
+        self.tree.get(index)
+    }
+
+    /// Return the element at `index` to change it, copying the nodes on the path to it if they
+    /// are shared with a clone.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T>
+    where
+        T: Clone,
+    {
+        if index >= self.len {
+            return None
+        }
+        Some(self.tree.get_mut(index))
+    }
+
+    /// Append `element` to the end of the list.
+    /// Returns the `element` back if the list is already at its limit `N`.
+    pub fn push(&mut self, element: T) -> Result<(), (T, Error)>
+    where
+        T: Clone,
+    {
+        if self.len >= N {
+            let provided = self.len + 1;
+            return Err((element, Error::Instance(InstanceError::bounded::<Self>(N, provided))))
+        }
+        let leaf_index = self.len / elements_per_leaf::<T>();
+        self.tree.leaf_mut(leaf_index).push(element);
+        self.len += 1;
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.len = self.len.checked_sub(1)?;
+        let leaf_index = self.len / elements_per_leaf::<T>();
+        let leaf = self.tree.leaf_mut(leaf_index);
+        let element = leaf.pop();
+        if leaf.is_empty() {
+            remove_leaf(&mut self.tree.root, leaf_index, self.tree.depth);
+        }
+        element
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.tree.iter()
+    }
+
+    /// Copy the elements into a `List<T, N>`.
+    pub fn to_list(&self) -> List<T, N>
+    where
+        T: Clone,
+    {
+        List::try_from(self.iter().cloned().collect::<Vec<_>>())
+            .map_err(|(_, err)| err)
+            .expect("length is within the bound")
+    }
+}
+
+impl<T: SimpleSerialize, const N: usize> Index<usize> for TreeList<T, N> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get(index).expect("index is within the length")
+    }
+}
+
+impl<T: SimpleSerialize, const N: usize> TryFrom<Vec<T>> for TreeList<T, N> {
+    type Error = (Vec<T>, Error);
+
+    fn try_from(data: Vec<T>) -> Result<Self, Self::Error> {
+        List::<T, N>::try_from(data).map(Self::from)
+    }
+}
+
+impl<T: SimpleSerialize, const N: usize> From<List<T, N>> for TreeList<T, N> {
+    fn from(list: List<T, N>) -> Self {
+        let len = list.len();
+        Self { tree: Tree::new(list.into_inner(), tree_depth::<T>(N)), len }
+    }
+}
+
+impl<T: SimpleSerialize, const N: usize> Sized for TreeList<T, N> {
+    fn is_variable_size() -> bool {
+        true
+    }
+
+    fn size_hint() -> usize {
+        0
+    }
+}
+
+impl<T: SimpleSerialize, const N: usize> Serialize for TreeList<T, N> {
+    fn serialize(&self, buffer: &mut Vec<u8>) -> Result<usize, SerializeError> {
+        serialize_composite(self.iter(), buffer)
+    }
+}
+
+impl<T: SimpleSerialize, const N: usize> Deserialize for TreeList<T, N> {
+    fn deserialize(encoding: &[u8]) -> Result<Self, DeserializeError> {
+        List::<T, N>::deserialize(encoding).map(Self::from)
+    }
+}
+
+impl<T: SimpleSerialize + Clone, const N: usize> Merkleized for TreeList<T, N> {
+    fn hash_tree_root_with<H: MerkleHasher>(
+        &mut self,
+        context: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
+        let data_root = self.tree.hash_tree_root_with(context)?;
+        Ok(mix_in_length(&data_root, self.len, context))
+    }
+
+    fn leaves<H: MerkleHasher>(
+        &mut self,
+        context: &Context<H>,
+    ) -> Result<Vec<Node>, MerkleizationError> {
+        let mut elements = self.iter().cloned().collect::<Vec<_>>();
+        Ok(chunks_to_nodes(&elements_chunks(&mut elements, context)?))
+    }
+
+    const CHUNK_COUNT: usize = elements_chunk_count_const::<T>(N);
+
+    fn merkle_limit() -> Option<usize> {
+        Some(Self::chunk_count())
+    }
+}
+
+impl<T: SimpleSerialize + Clone, const N: usize> SimpleSerialize for TreeList<T, N> {
+    fn nesting_depth() -> usize {
+        List::<T, N>::nesting_depth()
+    }
+
+    const MAX_SIZE: usize = List::<T, N>::MAX_SIZE;
+
+    const NESTING_DEPTH: usize = List::<T, N>::NESTING_DEPTH;
+
+    fn generalized_index_from(
+        root: GeneralizedIndex,
+        path: &[PathElement<'_>],
+    ) -> Result<GeneralizedIndex, PathError> {
+        List::<T, N>::generalized_index_from(root, path)
+    }
+
+    fn hash_tree_root_from_bytes<H: MerkleHasher>(
+        encoding: &[u8],
+        context: &Context<H>,
+    ) -> Result<Node, Error> {
+        List::<T, N>::hash_tree_root_from_bytes(encoding, context)
+    }
+
+    fn is_default(&self) -> bool {
+        self.is_empty()
+    }
+
+    fn default_hash_tree_root_with<H: MerkleHasher>(
+        context: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
+        List::<T, N>::default_hash_tree_root_with(context)
+    }
+
+    fn ssz_heap_size(&self) -> usize {
+        self.tree.heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn test_tree_vector() {
+        let elements = (0..100u16).collect::<Vec<_>>();
+        let mut tree = TreeVector::<u16, 100>::try_from(elements.clone()).unwrap();
+        let mut vector = Vector::<u16, 100>::try_from(elements.clone()).unwrap();
+        assert_eq!(tree.iter().copied().collect::<Vec<_>>(), elements);
+        assert_eq!(tree[42], 42);
+        assert_eq!(tree.get(100), None);
+
+        let encoding = serialize(&tree).unwrap();
+        assert_eq!(encoding, serialize(&vector).unwrap());
+        assert_eq!(TreeVector::<u16, 100>::deserialize(&encoding).unwrap(), tree);
+        assert_eq!(tree.hash_tree_root().unwrap(), vector.hash_tree_root().unwrap());
+        assert_eq!(tree.to_vector(), vector);
+
+        let mut snapshot = tree.clone();
+        *tree.get_mut(17).unwrap() = 1700;
+        vector[17] = 1700;
+        assert_eq!(snapshot[17], 17);
+        assert_eq!(tree.hash_tree_root().unwrap(), vector.hash_tree_root().unwrap());
+        assert_eq!(
+            snapshot.hash_tree_root().unwrap(),
+            Vector::<u16, 100>::try_from(elements).unwrap().hash_tree_root().unwrap()
+        );
+        assert!(tree.get_mut(100).is_none());
+
+        let mut default = TreeVector::<u16, 100>::default();
+        assert_eq!(
+            default.hash_tree_root().unwrap(),
+            Vector::<u16, 100>::default().hash_tree_root().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_tree_list() {
+        let elements = (0..5).map(|i| List::<u8, 4>::try_from(vec![i; i as usize % 4]).unwrap());
+        let elements = elements.collect::<Vec<_>>();
+        let mut tree = TreeList::<List<u8, 4>, 8>::try_from(elements.clone()).unwrap();
+        let mut list = List::<List<u8, 4>, 8>::try_from(elements).unwrap();
+        assert_eq!(tree.len(), 5);
+        assert_eq!(tree[3], list[3]);
+        assert_eq!(tree.get(5), None);
+
+        let encoding = serialize(&tree).unwrap();
+        assert_eq!(encoding, serialize(&list).unwrap());
+        assert_eq!(TreeList::<List<u8, 4>, 8>::deserialize(&encoding).unwrap(), tree);
+        assert_eq!(tree.hash_tree_root().unwrap(), list.hash_tree_root().unwrap());
+        assert_eq!(tree.to_list(), list);
+
+        let snapshot = tree.clone();
+        for i in 5..8 {
+            let element = List::try_from(vec![i]).unwrap();
+            tree.push(element.clone()).unwrap();
+            list.push(element).unwrap();
+        }
+        assert!(tree.push(List::default()).is_err());
+        tree.get_mut(1).unwrap().push(9).unwrap();
+        list[1].push(9).unwrap();
+        assert_eq!(tree.hash_tree_root().unwrap(), list.hash_tree_root().unwrap());
+        assert_eq!(snapshot.len(), 5);
+        assert_eq!(snapshot.to_list()[1].len(), 1);
+
+        while let Some(element) = tree.pop() {
+            assert_eq!(list.pop(), Some(element));
+            assert_eq!(tree.hash_tree_root().unwrap(), list.hash_tree_root().unwrap());
+        }
+        assert!(tree.is_default());
+        assert_eq!(tree, TreeList::new());
+    }
+
+    #[test]
+    fn test_tree_snapshots_share_nodes() {
+        let mut balances =
+            TreeList::<u64, 4096>::try_from((0..4096u64).collect::<Vec<_>>()).unwrap();
+        balances.hash_tree_root().unwrap();
+        let mut snapshots = vec![];
+        for i in 0..2000 {
+            snapshots.push(balances.clone());
+            *balances.get_mut(i).unwrap() += 1;
+            balances.hash_tree_root().unwrap();
+        }
+        // NOTE: the writes to the first 2000 elements only copy nodes of the left half of the tree
+        let root = balances.tree.root.as_ref().unwrap();
+        let Kind::Branch([_, right]) = &root.kind else { unreachable!() };
+        for snapshot in &snapshots {
+            let Kind::Branch([_, other]) = &snapshot.tree.root.as_ref().unwrap().kind else {
+                unreachable!()
+            };
+            assert!(Arc::ptr_eq(right.as_ref().unwrap(), other.as_ref().unwrap()));
+        }
+        for (i, snapshot) in snapshots.iter().enumerate().skip(1).step_by(97) {
+            assert_eq!(snapshot[i - 1], i as u64);
+            assert_eq!(snapshot[i], i as u64);
+        }
+    }
+
+    #[test]
+    fn test_tree_list_of_basic_types() {
+        let mut tree = TreeList::<u64, 32>::new();
+        let mut list = List::<u64, 32>::default();
+        for i in 0..20 {
+            tree.push(i).unwrap();
+            list.push(i).unwrap();
+            assert_eq!(tree.hash_tree_root().unwrap(), list.hash_tree_root().unwrap());
+        }
+        for _ in 0..7 {
+            assert_eq!(tree.pop(), list.pop());
+            assert_eq!(tree.hash_tree_root().unwrap(), list.hash_tree_root().unwrap());
+        }
+        assert_eq!(tree.iter().copied().collect::<Vec<_>>(), list.to_vec());
+    }
+}
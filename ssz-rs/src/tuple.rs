@@ -0,0 +1,191 @@
+//! `SimpleSerialize` for tuples as anonymous SSZ containers, i.e. `(A, B)` has the same
+//! SSZ representation as a container with the fields `A` and `B` in order.
+use crate::{
+    container::{field_ranges, FieldInfo},
+    de::{Deserialize, DeserializeError},
+    lib::*,
+    merkleization::{
+        child_generalized_index, invalid_path_element, merkleize_with, Context, GeneralizedIndex,
+        MerkleHasher, MerkleizationError, Merkleized, Node, PathElement, PathError,
+    },
+    ser::{
+        composite_nesting_depth, field_max_size, CompositeParts, Serialize, SerializeError,
+        BYTES_PER_LENGTH_OFFSET,
+    },
+    SimpleSerialize, Sized,
+};
+
+// Return the description of a field of type `T` called `name`.
+fn field_info<T: SimpleSerialize>(name: &'static str) -> FieldInfo {
+    let is_variable_size = T::is_variable_size();
+    FieldInfo {
+        name,
+        type_name: any::type_name::<T>(),
+        is_variable_size,
+        fixed_size: if is_variable_size { BYTES_PER_LENGTH_OFFSET } else { T::size_hint() },
+    }
+}
+
+macro_rules! define_ssz_for_tuple {
+    ($count:literal; $($field:ident: $index:tt),+) => {
+        impl<$($field: SimpleSerialize),+> Sized for ($($field,)+) {
+            fn is_variable_size() -> bool {
+                $($field::is_variable_size())||+
+            }
+
+            fn size_hint() -> usize {
+                if Self::is_variable_size() {
+                    0
+                } else {
+                    0 $(+ $field::size_hint())+
+                }
+            }
+        }
+
+        impl<$($field: SimpleSerialize),+> Serialize for ($($field,)+) {
+            fn serialize(&self, buffer: &mut Vec<u8>) -> Result<usize, SerializeError> {
+                let mut parts = CompositeParts::default();
+                $(
+                    let mut encoding = Vec::with_capacity($field::size_hint());
+                    self.$index.serialize(&mut encoding)?;
+                    parts.push($field::is_variable_size(), encoding);
+                )+
+                parts.serialize(buffer)
+            }
+        }
+
+        impl<$($field: SimpleSerialize),+> Deserialize for ($($field,)+) {
+            fn deserialize(encoding: &[u8]) -> Result<Self, DeserializeError> {
+                let fields = [$(field_info::<$field>(stringify!($index))),+];
+                let mut ranges = [(); $count].map(|_| 0..0);
+                field_ranges(&fields, encoding, &mut ranges)?;
+                Ok(($($field::deserialize(&encoding[ranges[$index].clone()])?,)+))
+            }
+        }
+
+        impl<$($field: SimpleSerialize),+> Merkleized for ($($field,)+) {
+            fn hash_tree_root_with<H: MerkleHasher>(
+                &mut self,
+                context: &Context<H>,
+            ) -> Result<Node, MerkleizationError> {
+                let mut chunks = vec![];
+                $(
+                    chunks.extend_from_slice(self.$index.hash_tree_root_with(context)?.as_ref());
+                )+
+                merkleize_with(&chunks, None, context)
+            }
+
+            fn leaves<H: MerkleHasher>(
+                &mut self,
+                context: &Context<H>,
+            ) -> Result<Vec<Node>, MerkleizationError> {
+                Ok(vec![$(self.$index.hash_tree_root_with(context)?),+])
+            }
+
+            const CHUNK_COUNT: usize = $count;
+        }
+
+        impl<$($field: SimpleSerialize),+> SimpleSerialize for ($($field,)+) {
+            fn nesting_depth() -> usize {
+                1 $(.max(1 + $field::nesting_depth()))+
+            }
+
+            const MAX_SIZE: usize = 0usize $(.saturating_add(field_max_size::<$field>()))+;
+
+            const IS_VARIABLE_SIZE: bool = false $(|| $field::IS_VARIABLE_SIZE)+;
+
+            const NESTING_DEPTH: usize = composite_nesting_depth(&[$($field::NESTING_DEPTH),+]);
+
+            fn generalized_index_from(
+                root: GeneralizedIndex,
+                path: &[PathElement<'_>],
+            ) -> Result<GeneralizedIndex, PathError> {
+                let depth = Self::tree_depth();
+                match path.split_first() {
+                    None => Ok(root),
+                    Some((PathElement::Index(index), rest)) => match index {
+                        $(
+                            $index => $field::generalized_index_from(
+                                child_generalized_index(root, depth, $index)?,
+                                rest,
+                            ),
+                        )+
+                        _ => Err(PathError::IndexOutOfBounds { index: *index, bound: $count }),
+                    },
+                    Some((element, _)) => Err(invalid_path_element::<Self>(element)),
+                }
+            }
+
+            fn ssz_heap_size(&self) -> usize {
+                0 $(+ self.$index.ssz_heap_size())+
+            }
+        }
+    };
+}
+
+define_ssz_for_tuple!(2; A: 0, B: 1);
+define_ssz_for_tuple!(3; A: 0, B: 1, C: 2);
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[derive(Default, Debug, PartialEq, Eq, SimpleSerialize)]
+    struct Pair {
+        a: u16,
+        b: List<u8, 8>,
+    }
+
+    #[derive(Default, Debug, PartialEq, Eq, SimpleSerialize)]
+    struct Triple {
+        a: List<u8, 8>,
+        b: bool,
+        c: List<u32, 4>,
+    }
+
+    #[test]
+    fn test_tuple_as_container() {
+        let b = List::<u8, 8>::try_from(vec![1u8, 2, 3]).unwrap();
+        let mut value = (7u16, b.clone());
+        let mut container = Pair { a: 7, b };
+        let encoding = serialize(&value).unwrap();
+        assert_eq!(encoding, serialize(&container).unwrap());
+        assert_eq!(<(u16, List<u8, 8>)>::deserialize(&encoding).unwrap(), value);
+        assert_eq!(value.hash_tree_root().unwrap(), container.hash_tree_root().unwrap());
+        assert!(<(u16, List<u8, 8>)>::is_variable_size());
+        assert_eq!(<(u16, List<u8, 8>)>::MAX_SIZE, Pair::MAX_SIZE);
+
+        let a = List::<u8, 8>::try_from(vec![4u8]).unwrap();
+        let c = List::<u32, 4>::try_from(vec![5u32, 6]).unwrap();
+        let mut value = (a.clone(), true, c.clone());
+        let mut container = Triple { a, b: true, c };
+        let encoding = serialize(&value).unwrap();
+        assert_eq!(encoding, serialize(&container).unwrap());
+        assert_eq!(<(List<u8, 8>, bool, List<u32, 4>)>::deserialize(&encoding).unwrap(), value);
+        assert_eq!(value.hash_tree_root().unwrap(), container.hash_tree_root().unwrap());
+    }
+
+    #[test]
+    fn test_fixed_tuple() {
+        let mut value = (1u8, 2u32, true);
+        let encoding = serialize(&value).unwrap();
+        assert_eq!(encoding, [1u8, 2, 0, 0, 0, 1]);
+        assert_eq!(<(u8, u32, bool)>::size_hint(), 6);
+        assert_eq!(<(u8, u32, bool)>::MAX_SIZE, 6);
+        assert_eq!(<(u8, u32, bool)>::IS_VARIABLE_SIZE, <(u8, u32, bool)>::is_variable_size());
+        assert_eq!(<(u8, u32, bool)>::deserialize(&encoding).unwrap(), value);
+        assert!(<(u8, u32, bool)>::deserialize(&encoding[..5]).is_err());
+        assert!(<(u8, u32, bool)>::deserialize(&[encoding.as_slice(), &[0]].concat()).is_err());
+        assert_ne!(value.hash_tree_root().unwrap(), Node::default());
+    }
+
+    #[test]
+    fn test_tuple_rejects_bad_offsets() {
+        // offset points past the end of the fixed part
+        assert!(<(u16, List<u8, 8>)>::deserialize(&[7, 0, 7, 0, 0, 0, 0, 1]).is_err());
+        // offset points into the fixed part
+        assert!(<(u16, List<u8, 8>)>::deserialize(&[7, 0, 2, 0, 0, 0, 1]).is_err());
+        // offset points past the end of the encoding
+        assert!(<(u16, List<u8, 8>)>::deserialize(&[7, 0, 9, 0, 0, 0]).is_err());
+    }
+}
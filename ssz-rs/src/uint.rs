@@ -1,7 +1,13 @@
 use crate::{
     de::{Deserialize, DeserializeError},
-    lib::*,
-    merkleization::{pack_bytes, MerkleizationError, Merkleized, Node},
+    error::ErrorKind,
+    lib::{
+        num::{NonZeroU128, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8},
+        *,
+    },
+    merkleization::{
+        pack_bytes, Context, MerkleHasher, MerkleizationError, Merkleized, Node, BYTES_PER_CHUNK,
+    },
     ser::{Serialize, SerializeError},
     SimpleSerialize, Sized,
 };
@@ -19,6 +25,8 @@ macro_rules! define_uint {
             }
         }
 
+        // NOTE: every conversion to and from bytes names the byte order explicitly, so encodings
+        // and roots are the same on big-endian targets (e.g. s390x)
         impl Serialize for $uint {
             fn serialize(&self, buffer: &mut Vec<u8>) -> Result<usize, SerializeError> {
                 buffer.extend_from_slice(&self.to_le_bytes());
@@ -48,18 +56,54 @@ macro_rules! define_uint {
         }
 
         impl Merkleized for $uint {
-            fn hash_tree_root(&mut self) -> Result<Node, MerkleizationError> {
+            fn hash_tree_root_with<H: MerkleHasher>(
+                &mut self,
+                _: &Context<H>,
+            ) -> Result<Node, MerkleizationError> {
                 let mut root = vec![];
                 let _ = self.serialize(&mut root)?;
                 pack_bytes(&mut root);
                 Ok(root.as_slice().try_into().expect("is valid root"))
             }
+
+            const CHUNK_COUNT: usize = 1;
         }
 
+        impl SszUint for $uint {}
+
         impl SimpleSerialize for $uint {
+            const MAX_SIZE: usize = (<$uint>::BITS / 8) as usize;
+
+            const IS_VARIABLE_SIZE: bool = false;
+
+            const NESTING_DEPTH: usize = 0;
+
+            const PACKED_PER_CHUNK: usize = BYTES_PER_CHUNK / (<$uint>::BITS / 8) as usize;
+
             fn is_composite_type() -> bool {
                 false
             }
+
+            fn is_default(&self) -> bool {
+                *self == 0
+            }
+
+            fn default_hash_tree_root_with<H: MerkleHasher>(
+                _: &Context<H>,
+            ) -> Result<Node, MerkleizationError> {
+                Ok(Node::default())
+            }
+
+            fn serialize_elements(
+                elements: &[Self],
+                buffer: &mut Vec<u8>,
+            ) -> Result<(), SerializeError> {
+                buffer.reserve(elements.len() * (<$uint>::BITS / 8) as usize);
+                for element in elements {
+                    buffer.extend_from_slice(&element.to_le_bytes());
+                }
+                Ok(())
+            }
         }
     };
 }
@@ -69,7 +113,226 @@ define_uint!(u16);
 define_uint!(u32);
 define_uint!(u64);
 define_uint!(u128);
-define_uint!(usize);
+
+/// An unsigned integer type of SSZ, to implement SSZ for a newtype over it with
+/// [`impl_ssz_uint`](crate::impl_ssz_uint).
+pub trait SszUint: SimpleSerialize {}
+
+/// Implement the SSZ traits for a newtype `$name($uint)` over an unsigned integer type of SSZ,
+/// e.g. `Slot(u64)`, so it has the encoding and root of `$uint` and is packed like `$uint` in
+/// collections. The newtype must implement `Default`.
+///
+/// ```rust
+/// use ssz_rs::{impl_ssz_uint, prelude::*};
+///
+/// #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+/// struct Slot(u64);
+///
+/// impl_ssz_uint!(Slot, u64);
+///
+/// let mut slots = List::<Slot, 8>::try_from(vec![Slot(1), Slot(2)]).unwrap();
+/// let mut values = List::<u64, 8>::try_from(vec![1, 2]).unwrap();
+/// assert_eq!(serialize(&slots).unwrap(), serialize(&values).unwrap());
+/// assert_eq!(slots.hash_tree_root().unwrap(), values.hash_tree_root().unwrap());
+/// ```
+#[macro_export]
+macro_rules! impl_ssz_uint {
+    ($name:ty, $uint:ty) => {
+        const _: () = {
+            fn assert_ssz_uint<T: $crate::SszUint>() {}
+            let _ = assert_ssz_uint::<$uint>;
+        };
+
+        impl $crate::Sized for $name {
+            fn is_variable_size() -> bool {
+                false
+            }
+
+            fn size_hint() -> usize {
+                <$uint as $crate::Sized>::size_hint()
+            }
+        }
+
+        impl $crate::Serialize for $name {
+            fn serialize(
+                &self,
+                buffer: &mut $crate::__internal::Vec<u8>,
+            ) -> ::core::result::Result<usize, $crate::SerializeError> {
+                $crate::Serialize::serialize(&self.0, buffer)
+            }
+        }
+
+        impl $crate::Deserialize for $name {
+            fn deserialize(
+                encoding: &[u8],
+            ) -> ::core::result::Result<Self, $crate::DeserializeError> {
+                <$uint as $crate::Deserialize>::deserialize(encoding).map(Self)
+            }
+        }
+
+        impl $crate::Merkleized for $name {
+            fn hash_tree_root_with<H: $crate::MerkleHasher>(
+                &mut self,
+                context: &$crate::MerkleizationContext<H>,
+            ) -> ::core::result::Result<$crate::Node, $crate::MerkleizationError> {
+                $crate::Merkleized::hash_tree_root_with(&mut self.0, context)
+            }
+
+            const CHUNK_COUNT: usize = 1;
+        }
+
+        impl $crate::SimpleSerialize for $name {
+            const MAX_SIZE: usize = <$uint as $crate::SimpleSerialize>::MAX_SIZE;
+
+            const IS_VARIABLE_SIZE: bool = false;
+
+            const NESTING_DEPTH: usize = 0;
+
+            const PACKED_PER_CHUNK: usize = <$uint as $crate::SimpleSerialize>::PACKED_PER_CHUNK;
+
+            fn is_composite_type() -> bool {
+                false
+            }
+
+            fn is_default(&self) -> bool {
+                $crate::SimpleSerialize::is_default(&self.0)
+            }
+
+            fn default_hash_tree_root_with<H: $crate::MerkleHasher>(
+                context: &$crate::MerkleizationContext<H>,
+            ) -> ::core::result::Result<$crate::Node, $crate::MerkleizationError> {
+                <$uint as $crate::SimpleSerialize>::default_hash_tree_root_with(context)
+            }
+
+            fn ssz_heap_size(&self) -> usize {
+                $crate::SimpleSerialize::ssz_heap_size(&self.0)
+            }
+        }
+    };
+}
+
+// NOTE: `usize` is encoded as a `uint64` on every target so the encoding does not depend on the
+// pointer width, and decoding fails on 32-bit targets rather than truncating larger values
+impl Sized for usize {
+    fn is_variable_size() -> bool {
+        false
+    }
+
+    fn size_hint() -> usize {
+        u64::size_hint()
+    }
+}
+
+impl Serialize for usize {
+    fn serialize(&self, buffer: &mut Vec<u8>) -> Result<usize, SerializeError> {
+        (*self as u64).serialize(buffer)
+    }
+}
+
+impl Deserialize for usize {
+    fn deserialize(encoding: &[u8]) -> Result<Self, DeserializeError> {
+        let value = u64::deserialize(encoding)?;
+        usize::try_from(value).map_err(|_| {
+            DeserializeError::from(OverflowError {
+                bits: (u64::BITS - value.leading_zeros()) as u64,
+                max_bits: usize::BITS,
+            })
+        })
+    }
+}
+
+impl Merkleized for usize {
+    fn hash_tree_root_with<H: MerkleHasher>(
+        &mut self,
+        context: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
+        (*self as u64).hash_tree_root_with(context)
+    }
+
+    const CHUNK_COUNT: usize = 1;
+}
+
+impl SszUint for usize {}
+
+impl SimpleSerialize for usize {
+    const MAX_SIZE: usize = 8;
+
+    const IS_VARIABLE_SIZE: bool = false;
+
+    const NESTING_DEPTH: usize = 0;
+
+    const PACKED_PER_CHUNK: usize = 4;
+
+    fn is_composite_type() -> bool {
+        false
+    }
+
+    fn is_default(&self) -> bool {
+        *self == 0
+    }
+
+    fn default_hash_tree_root_with<H: MerkleHasher>(
+        _: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
+        Ok(Node::default())
+    }
+
+    fn serialize_elements(elements: &[Self], buffer: &mut Vec<u8>) -> Result<(), SerializeError> {
+        buffer.reserve(elements.len() * Self::size_hint());
+        for element in elements {
+            buffer.extend_from_slice(&(*element as u64).to_le_bytes());
+        }
+        Ok(())
+    }
+}
+
+// NOTE: the `NonZero` types do not implement `Default` so they can not implement
+// `SimpleSerialize` but they can still participate in the individual SSZ traits, e.g. through a
+// newtype as in the crate documentation. `NonZeroUsize` is left out, as `usize` is not an SSZ
+// type.
+macro_rules! define_non_zero_uint {
+    ($non_zero:ty, $uint:ty) => {
+        impl Sized for $non_zero {
+            fn is_variable_size() -> bool {
+                false
+            }
+
+            fn size_hint() -> usize {
+                <$uint>::size_hint()
+            }
+        }
+
+        impl Serialize for $non_zero {
+            fn serialize(&self, buffer: &mut Vec<u8>) -> Result<usize, SerializeError> {
+                self.get().serialize(buffer)
+            }
+        }
+
+        impl Deserialize for $non_zero {
+            fn deserialize(encoding: &[u8]) -> Result<Self, DeserializeError> {
+                let value = <$uint>::deserialize(encoding)?;
+                <$non_zero>::new(value).ok_or(DeserializeError::Zero)
+            }
+        }
+
+        impl Merkleized for $non_zero {
+            fn hash_tree_root_with<H: MerkleHasher>(
+                &mut self,
+                context: &Context<H>,
+            ) -> Result<Node, MerkleizationError> {
+                self.get().hash_tree_root_with(context)
+            }
+
+            const CHUNK_COUNT: usize = <$uint as $crate::Merkleized>::CHUNK_COUNT;
+        }
+    };
+}
+
+define_non_zero_uint!(NonZeroU8, u8);
+define_non_zero_uint!(NonZeroU16, u16);
+define_non_zero_uint!(NonZeroU32, u32);
+define_non_zero_uint!(NonZeroU64, u64);
+define_non_zero_uint!(NonZeroU128, u128);
 
 #[derive(Default, Debug, PartialEq, Eq, Clone, PartialOrd, Ord, Hash)]
 pub struct U256(BigUint);
@@ -92,23 +355,112 @@ impl U256 {
     }
 
     pub fn to_bytes_le(&self) -> Vec<u8> {
-        let mut bytes = self.0.to_bytes_le();
-        bytes.resize(32, 0u8);
+        let mut bytes = Vec::with_capacity(32);
+        self.write_bytes_le(&mut bytes);
         bytes
     }
 
+    // Append the 32 little-endian bytes of `self` to `buffer` without an intermediate allocation.
+    fn write_bytes_le(&self, buffer: &mut Vec<u8>) {
+        let end = buffer.len() + 32;
+        buffer.extend(self.0.iter_u64_digits().flat_map(u64::to_le_bytes));
+        buffer.resize(end, 0u8);
+    }
+
     pub fn from_hex(data: &str) -> Option<Self> {
         let data = data.strip_prefix("0x").unwrap_or(data);
         BigUint::parse_bytes(data.as_bytes(), 16).map(Self)
     }
 }
 
+macro_rules! define_u256_conversions {
+    ($($uint:ty),*) => {
+        $(
+            impl FromU256 for $uint {
+                fn from_ssz_uint256(value: &U256) -> Result<Self, OverflowError> {
+                    let max_bits = <$uint>::BITS;
+                    let bits = value.0.bits();
+                    if bits > max_bits as u64 {
+                        return Err(OverflowError { bits, max_bits })
+                    }
+                    Ok(<$uint>::try_from(&value.0).expect("fits in the integer"))
+                }
+            }
+
+            impl IntoU256 for $uint {
+                fn into_ssz_uint256(self) -> U256 {
+                    U256(self.into())
+                }
+            }
+        )*
+    };
+}
+
+define_u256_conversions!(u8, u16, u32, u64, u128, usize);
+
 impl From<u64> for U256 {
     fn from(x: u64) -> Self {
         Self(x.into())
     }
 }
 
+/// Checked narrowing of a `U256` to a smaller unsigned integer.
+///
+/// ```rust
+/// use ssz_rs::{prelude::*, FromU256};
+///
+/// let amount = U256::from(32_000_000_000u64);
+/// assert_eq!(u64::from_ssz_uint256(&amount).unwrap(), 32_000_000_000);
+/// assert!(u32::from_ssz_uint256(&amount).is_err());
+/// ```
+pub trait FromU256: core::marker::Sized {
+    /// Return `value` as `Self` or an error if it does not fit.
+    fn from_ssz_uint256(value: &U256) -> Result<Self, OverflowError>;
+}
+
+/// Widening of an unsigned integer to a `U256`, which never overflows.
+///
+/// ```rust
+/// use ssz_rs::{prelude::*, IntoU256};
+///
+/// let amount = u128::MAX.into_ssz_uint256();
+/// assert_eq!(amount.to_bytes_le()[..16], [u8::MAX; 16]);
+/// assert_eq!(7u8.into_ssz_uint256(), U256::from(7));
+/// ```
+pub trait IntoU256 {
+    /// Return `self` as a `U256`.
+    fn into_ssz_uint256(self) -> U256;
+}
+
+/// A value too large for the unsigned integer type it was narrowed to, e.g. a `U256` narrowed
+/// to a `u64` or a `uint64` decoded as a `usize` on a 32-bit target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OverflowError {
+    /// The number of significant bits of the value.
+    pub bits: u64,
+    /// The number of bits of the integer type.
+    pub max_bits: u32,
+}
+
+impl OverflowError {
+    pub fn kind(&self) -> ErrorKind {
+        ErrorKind::Bounds
+    }
+}
+
+impl Display for OverflowError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "a value of {} bits does not fit in an integer of {} bits",
+            self.bits, self.max_bits
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for OverflowError {}
+
 #[cfg(feature = "serde")]
 impl serde::Serialize for U256 {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -144,7 +496,7 @@ impl Sized for U256 {
 
 impl Serialize for U256 {
     fn serialize(&self, buffer: &mut Vec<u8>) -> Result<usize, SerializeError> {
-        buffer.extend_from_slice(&self.to_bytes_le());
+        self.write_bytes_le(buffer);
         Ok(32)
     }
 }
@@ -171,23 +523,64 @@ impl Deserialize for U256 {
 }
 
 impl Merkleized for U256 {
-    fn hash_tree_root(&mut self) -> Result<Node, MerkleizationError> {
+    fn hash_tree_root_with<H: MerkleHasher>(
+        &mut self,
+        _: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
         let data = self.to_bytes_le();
         let node = Node::try_from(data.as_ref()).expect("is right size");
         Ok(node)
     }
+
+    const CHUNK_COUNT: usize = 1;
 }
 
+impl SszUint for U256 {}
+
 impl SimpleSerialize for U256 {
+    const MAX_SIZE: usize = 32;
+
+    const IS_VARIABLE_SIZE: bool = false;
+
+    const NESTING_DEPTH: usize = 0;
+
+    const PACKED_PER_CHUNK: usize = 1;
+
     fn is_composite_type() -> bool {
         false
     }
+
+    fn is_default(&self) -> bool {
+        self.0.bits() == 0
+    }
+
+    fn default_hash_tree_root_with<H: MerkleHasher>(
+        _: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
+        Ok(Node::default())
+    }
+
+    fn ssz_heap_size(&self) -> usize {
+        // NOTE: `BigUint` stores its digits as `u64`s
+        ((self.0.bits() as usize + 63) / 64) * mem::size_of::<u64>()
+    }
+
+    fn serialize_elements(elements: &[Self], buffer: &mut Vec<u8>) -> Result<(), SerializeError> {
+        buffer.reserve(elements.len() * 32);
+        for element in elements {
+            element.write_bytes_le(buffer);
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::serialize;
+    use crate::{
+        prelude::{ssz_rs, SszEnum},
+        serialize,
+    };
 
     #[test]
     fn encode_uints() {
@@ -245,6 +638,41 @@ mod tests {
         }
     }
 
+    #[test]
+    fn roundtrip_non_zero_uints() {
+        let value = NonZeroU16::new(1337).unwrap();
+        let encoding = serialize_non_zero(&value);
+        assert_eq!(encoding, [57u8, 5u8]);
+        assert_eq!(NonZeroU16::deserialize(&encoding).unwrap(), value);
+
+        let mut value = NonZeroU64::new(u64::MAX).unwrap();
+        let encoding = serialize_non_zero(&value);
+        assert_eq!(encoding, [u8::MAX; 8]);
+        assert_eq!(NonZeroU64::deserialize(&encoding).unwrap(), value);
+        let mut inner = value.get();
+        assert_eq!(value.hash_tree_root().unwrap(), inner.hash_tree_root().unwrap());
+    }
+
+    #[test]
+    fn decode_non_zero_uints_rejects_zero() {
+        let result = NonZeroU8::deserialize(&[0u8]);
+        assert!(matches!(result, Err(DeserializeError::Zero)));
+
+        let result = NonZeroU32::deserialize(&[0u8; 4]);
+        let err = result.unwrap_err();
+        assert!(matches!(err, DeserializeError::Zero));
+        assert_eq!(err.to_string(), "zero decoded for a type which can not be zero");
+
+        let result = NonZeroU32::deserialize(&[0u8; 3]);
+        assert!(matches!(result, Err(DeserializeError::ExpectedFurtherInput { .. })));
+    }
+
+    fn serialize_non_zero<T: Serialize>(value: &T) -> Vec<u8> {
+        let mut buffer = vec![];
+        let _ = value.serialize(&mut buffer).expect("can encode");
+        buffer
+    }
+
     #[test]
     fn decode_uints() {
         let tests = vec![(u8::default(), [0u8]), (2u8, [2u8]), (u8::MAX, [u8::MAX])];
@@ -300,4 +728,151 @@ mod tests {
             assert_eq!(result, expected);
         }
     }
+
+    #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, SszEnum)]
+    #[repr(u8)]
+    enum Kind {
+        #[default]
+        Deposit,
+        Withdrawal = 4,
+        Exit,
+    }
+
+    #[test]
+    fn test_ssz_enum() {
+        for (mut kind, discriminant) in
+            [(Kind::Deposit, 0u8), (Kind::Withdrawal, 4), (Kind::Exit, 5)]
+        {
+            let encoding = serialize(&kind).expect("can encode");
+            assert_eq!(encoding, [discriminant]);
+            assert_eq!(Kind::deserialize(&encoding).expect("can decode"), kind);
+            assert_eq!(u8::from(kind), discriminant);
+            assert_eq!(Kind::try_from(discriminant).unwrap(), kind);
+            let mut discriminant = discriminant;
+            assert_eq!(kind.hash_tree_root().unwrap(), discriminant.hash_tree_root().unwrap());
+        }
+        assert!(Kind::size_hint() == 1 && !Kind::is_variable_size());
+        assert!(matches!(Kind::deserialize(&[1u8]), Err(DeserializeError::InvalidByte(1))));
+        assert!(Kind::deserialize(&[0u8, 0u8]).is_err());
+    }
+
+    #[test]
+    fn test_u256_narrowing() {
+        let value = U256::from(u32::MAX as u64 + 1);
+        assert_eq!(u64::from_ssz_uint256(&value).unwrap(), u32::MAX as u64 + 1);
+        assert_eq!(u128::from_ssz_uint256(&value).unwrap(), u32::MAX as u128 + 1);
+        assert_eq!(u32::from_ssz_uint256(&value), Err(OverflowError { bits: 33, max_bits: 32 }));
+        assert_eq!(u32::from_ssz_uint256(&U256::from(u32::MAX as u64)).unwrap(), u32::MAX);
+        assert_eq!(u8::from_ssz_uint256(&U256::zero()).unwrap(), 0);
+
+        let max = U256::from_bytes_le([u8::MAX; 32]);
+        assert_eq!(u128::from_ssz_uint256(&max).unwrap_err().kind(), crate::ErrorKind::Bounds);
+    }
+
+    #[test]
+    fn test_u256_widening() {
+        assert_eq!(
+            u128::MAX.into_ssz_uint256(),
+            U256::from_bytes_le([[u8::MAX; 16], [0; 16]].concat().try_into().unwrap())
+        );
+        assert_eq!(u8::MAX.into_ssz_uint256(), U256::from(255));
+        assert_eq!(usize::MAX.into_ssz_uint256(), U256::from(usize::MAX as u64));
+        for value in [0u64, 1, u32::MAX as u64 + 1, u64::MAX] {
+            assert_eq!(u64::from_ssz_uint256(&value.into_ssz_uint256()), Ok(value));
+        }
+    }
+
+    #[test]
+    fn test_little_endian_on_any_target() {
+        let value = 0x0102_0304_0506_0708u64;
+        let encoding = serialize(&value).unwrap();
+        assert_eq!(encoding, [8, 7, 6, 5, 4, 3, 2, 1]);
+        #[cfg(target_endian = "little")]
+        assert_eq!(encoding, value.to_ne_bytes());
+        #[cfg(target_endian = "big")]
+        assert_ne!(encoding, value.to_ne_bytes());
+        assert_eq!(u64::deserialize(&encoding).unwrap(), value);
+
+        let mut packed = crate::Vector::<u16, 16>::try_from(vec![0x0102; 16]).unwrap();
+        let root = packed.hash_tree_root().unwrap();
+        assert_eq!(root.as_ref(), [2u8, 1].repeat(16));
+
+        let mut value = U256::from(0x0102u64);
+        let mut expected = [0u8; 32];
+        expected[..2].copy_from_slice(&[2, 1]);
+        assert_eq!(serialize(&value).unwrap(), expected);
+        assert_eq!(value.hash_tree_root().unwrap().as_ref(), expected);
+        assert_eq!(U256::from_hex("0x0102").unwrap(), value);
+
+        let mut bits = crate::Bitvector::<12>::default();
+        bits.set(0, true);
+        bits.set(9, true);
+        assert_eq!(serialize(&bits).unwrap(), [0b1, 0b10]);
+    }
+
+    #[test]
+    fn test_usize_is_uint64() {
+        let mut value = 1337usize;
+        let encoding = serialize(&value).unwrap();
+        assert_eq!(encoding, serialize(&1337u64).unwrap());
+        assert_eq!(usize::size_hint(), 8);
+        assert_eq!(usize::deserialize(&encoding).unwrap(), value);
+        assert_eq!(value.hash_tree_root().unwrap(), 1337u64.hash_tree_root().unwrap());
+        assert!(usize::deserialize(&encoding[..4]).is_err());
+
+        let large = serialize(&(u32::MAX as u64 + 1)).unwrap();
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(usize::deserialize(&large).unwrap(), u32::MAX as usize + 1);
+        #[cfg(target_pointer_width = "32")]
+        assert!(matches!(
+            usize::deserialize(&large),
+            Err(DeserializeError::Overflow(OverflowError { bits: 33, max_bits: 32 }))
+        ));
+    }
+
+    #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+    struct Epoch(u64);
+
+    crate::impl_ssz_uint!(Epoch, u64);
+
+    #[derive(Default, Debug, Clone, PartialEq, Eq)]
+    struct Wei(U256);
+
+    crate::impl_ssz_uint!(Wei, U256);
+
+    // the macro names every item by its path, so it works where `Vec` or `Result` name other
+    // items, e.g. in `no_std` crates
+    mod shadowed {
+        #![allow(dead_code)]
+        struct Vec;
+        type Result = ();
+
+        #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+        pub(super) struct Height(pub(super) u32);
+
+        crate::impl_ssz_uint!(Height, u32);
+    }
+
+    #[test]
+    fn test_uint_newtypes() {
+        use crate::list::List;
+
+        let mut epoch = Epoch(7);
+        assert_eq!(serialize(&epoch).unwrap(), serialize(&7u64).unwrap());
+        assert_eq!(Epoch::deserialize(&7u64.to_le_bytes()).unwrap(), epoch);
+        assert_eq!(epoch.hash_tree_root().unwrap(), 7u64.hash_tree_root().unwrap());
+        assert!(!Epoch::is_composite_type());
+        assert_eq!(serialize(&shadowed::Height(7)).unwrap(), serialize(&7u32).unwrap());
+
+        // the newtypes are packed into chunks like the integers
+        let mut epochs = List::<Epoch, 16>::try_from(vec![Epoch(1), Epoch(2), Epoch(3)]).unwrap();
+        let mut values = List::<u64, 16>::try_from(vec![1, 2, 3]).unwrap();
+        assert_eq!(epochs.hash_tree_root().unwrap(), values.hash_tree_root().unwrap());
+        assert_eq!(List::<Epoch, 16>::chunk_count(), 4);
+
+        let mut amounts = List::<Wei, 4>::try_from(vec![Wei(U256::from(5u64))]).unwrap();
+        let mut values = List::<U256, 4>::try_from(vec![U256::from(5u64)]).unwrap();
+        assert_eq!(serialize(&amounts).unwrap(), serialize(&values).unwrap());
+        assert_eq!(amounts.hash_tree_root().unwrap(), values.hash_tree_root().unwrap());
+    }
 }
@@ -1,11 +1,105 @@
 use crate::{
     de::{Deserialize, DeserializeError},
     lib::*,
-    merkleization::{mix_in_selector, MerkleizationError, Merkleized, Node},
+    merkleization::{
+        invalid_path_element, mix_in_selector, union_generalized_index, Context, GeneralizedIndex,
+        MerkleHasher, MerkleizationError, Merkleized, Node, PathElement, PathError, Proof,
+    },
     ser::{Serialize, SerializeError},
     SimpleSerialize, Sized,
 };
 
+/// Access to the selected variant of an SSZ union, e.g. to prove it against the root.
+///
+/// The root of a union is the hash of the root of the value of the selected variant
+/// (the zero node for `None`) and the selector, so both have a proof of depth 1 and
+/// a proof against the root of the value extends to the root of the union with
+/// [`Proof::mix_in_selector`].
+///
+/// `Union` is implemented by `#[derive(SimpleSerialize)]` for enums, where the selector of a
+/// variant is its position in the declaration of the enum, starting at 0 for `None`. The root of
+/// a large variant can be kept across hashes of an unchanged union, keyed by its selector, by
+/// wrapping the union in a [`Cached`](crate::Cached).
+///
+/// ```rust
+/// use ssz_rs::prelude::*;
+/// use ssz_rs::Union;
+///
+/// let mut value = Some(7u64);
+/// let root = value.hash_tree_root().unwrap();
+/// let proof = value.prove_selector().unwrap();
+/// assert!(proof.verify_selector(1, &root));
+/// assert!(!proof.verify_selector(0, &root));
+///
+/// let proof = value.prove_variant().unwrap();
+/// assert_eq!(proof.leaf, 7u64.hash_tree_root().unwrap());
+/// assert!(proof.verify(&root));
+/// assert_eq!(value.variant(), Some("Some"));
+/// assert_eq!(Option::<u64>::variant_name(0), Some("None"));
+/// ```
+pub trait Union: SimpleSerialize {
+    /// The number of variants, including `None`, whose selectors are `0..VARIANT_COUNT`.
+    ///
+    /// Defaults to the most variants a union can have, so implementations should override it.
+    const VARIANT_COUNT: usize = 127;
+
+    /// Return the selector of the selected variant.
+    fn selector(&self) -> u8;
+
+    /// Return the name of the variant with `selector`, e.g. to label it in logs and metrics,
+    /// or `None` if there is no such variant or its name is not known.
+    fn variant_name(_selector: u8) -> Option<&'static str> {
+        None
+    }
+
+    /// Return the name of the selected variant, if known.
+    fn variant(&self) -> Option<&'static str> {
+        Self::variant_name(self.selector())
+    }
+
+    /// Return the root of the value of the selected variant, or the zero node for `None`.
+    fn variant_hash_tree_root_with<H: MerkleHasher>(
+        &mut self,
+        context: &Context<H>,
+    ) -> Result<Node, MerkleizationError>;
+
+    /// Prove the selector against the root of `self`.
+    fn prove_selector(&mut self) -> Result<Proof, MerkleizationError> {
+        self.prove_selector_with(Context::global())
+    }
+
+    /// Prove the selector against the root of `self`, hashing with `H`.
+    fn prove_selector_with<H: MerkleHasher>(
+        &mut self,
+        context: &Context<H>,
+    ) -> Result<Proof<H>, MerkleizationError> {
+        let variant_root = self.variant_hash_tree_root_with(context)?;
+        Ok(Proof::new(selector_node(self.selector()), vec![variant_root], 1))
+    }
+
+    /// Prove the root of the value of the selected variant against the root of `self`.
+    fn prove_variant(&mut self) -> Result<Proof, MerkleizationError> {
+        self.prove_variant_with(Context::global())
+    }
+
+    /// Prove the root of the value of the selected variant against the root of `self`,
+    /// hashing with `H`.
+    fn prove_variant_with<H: MerkleHasher>(
+        &mut self,
+        context: &Context<H>,
+    ) -> Result<Proof<H>, MerkleizationError> {
+        let variant_root = self.variant_hash_tree_root_with(context)?;
+        Ok(Proof::new(variant_root, vec![selector_node(self.selector())], 0))
+    }
+}
+
+/// Return the chunk of `selector` mixed into the root of a union.
+pub(crate) fn selector_node(selector: u8) -> Node {
+    let mut node = Node::default();
+    node.as_mut()[0] = selector;
+    node
+}
+
 /// `SimpleSerialize` is implemented for `Option` as a convenience
 /// when the schema is equivalent to one described by:
 /// enum Option<T: SimpleSerialize> {
@@ -48,7 +142,10 @@ where
         }
 
         match encoding[0] {
-            0 => Ok(None),
+            0 => match encoding.len() {
+                1 => Ok(None),
+                provided => Err(DeserializeError::AdditionalInput { provided, expected: 1 }),
+            },
             1 => {
                 let inner = T::deserialize(&encoding[1..])?;
                 Ok(Some(inner))
@@ -62,19 +159,100 @@ impl<T> Merkleized for Option<T>
 where
     T: SimpleSerialize,
 {
-    fn hash_tree_root(&mut self) -> Result<Node, MerkleizationError> {
+    fn hash_tree_root_with<H: MerkleHasher>(
+        &mut self,
+        context: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
+        match self {
+            Some(value) => Ok(mix_in_selector(&value.hash_tree_root_with(context)?, 1, context)),
+            None => Ok(mix_in_selector(&Node::default(), 0, context)),
+        }
+    }
+
+    fn leaves<H: MerkleHasher>(
+        &mut self,
+        context: &Context<H>,
+    ) -> Result<Vec<Node>, MerkleizationError> {
+        Ok(vec![self.variant_hash_tree_root_with(context)?])
+    }
+
+    const CHUNK_COUNT: usize = 1;
+}
+
+impl<T> Union for Option<T>
+where
+    T: SimpleSerialize,
+{
+    const VARIANT_COUNT: usize = 2;
+
+    fn selector(&self) -> u8 {
+        self.is_some() as u8
+    }
+
+    fn variant_name(selector: u8) -> Option<&'static str> {
+        match selector {
+            0 => Some("None"),
+            1 => Some("Some"),
+            _ => None,
+        }
+    }
+
+    fn variant_hash_tree_root_with<H: MerkleHasher>(
+        &mut self,
+        context: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
         match self {
-            Some(value) => Ok(mix_in_selector(&value.hash_tree_root()?, 1)),
-            None => Ok(mix_in_selector(&Node::default(), 0)),
+            Some(value) => value.hash_tree_root_with(context),
+            None => Ok(Node::default()),
         }
     }
 }
 
-impl<T> SimpleSerialize for Option<T> where T: SimpleSerialize {}
+impl<T> SimpleSerialize for Option<T>
+where
+    T: SimpleSerialize,
+{
+    fn nesting_depth() -> usize {
+        1 + T::nesting_depth()
+    }
+
+    const MAX_SIZE: usize = T::MAX_SIZE.saturating_add(1);
+
+    const NESTING_DEPTH: usize = T::NESTING_DEPTH.saturating_add(1);
+
+    fn generalized_index_from(
+        root: GeneralizedIndex,
+        path: &[PathElement<'_>],
+    ) -> Result<GeneralizedIndex, PathError> {
+        union_generalized_index::<Self>(root, path, |selector, root, path| match selector {
+            0 => match path.first() {
+                None => Ok(root),
+                Some(element) => Err(invalid_path_element::<Self>(element)),
+            },
+            1 => T::generalized_index_from(root, path),
+            index => Err(PathError::IndexOutOfBounds { index, bound: 2 }),
+        })
+    }
+
+    fn ssz_heap_size(&self) -> usize {
+        self.as_ref().map_or(0, T::ssz_heap_size)
+    }
+
+    fn is_default(&self) -> bool {
+        self.is_none()
+    }
+
+    fn default_hash_tree_root_with<H: MerkleHasher>(
+        context: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
+        Ok(mix_in_selector(&Node::default(), 0, context))
+    }
+}
 
 #[cfg(test)]
 mod tests {
-    use crate::prelude::*;
+    use crate::{prelude::*, MerkleizationContext};
+    use hex_literal::hex;
 
     #[derive(Debug, PartialEq, Eq, SimpleSerialize)]
     enum AnotherOption {
@@ -191,6 +369,33 @@ mod tests {
         assert_eq!(x, recovered);
     }
 
+    #[test]
+    fn test_none_selector() {
+        // selector 0 with an empty body
+        let mut x = AnotherOption::None;
+        let encoding = serialize(&x).expect("can encode");
+        assert_eq!(encoding, [0u8]);
+        assert_eq!(AnotherOption::deserialize(&encoding).expect("can decode"), x);
+        let mut y = Option::<u8>::None;
+        assert_eq!(serialize(&y).expect("can encode"), encoding);
+
+        // mix_in_selector(zero_root, 0)
+        let expected = Node::try_from(
+            hex!("f5a5fd42d16a20302798ef6ed309979b43003d2320d9f0e8ea9831a92759fb4b").as_ref(),
+        )
+        .unwrap();
+        assert_eq!(x.hash_tree_root().expect("can merkleize"), expected);
+        assert_eq!(y.hash_tree_root().expect("can merkleize"), expected);
+
+        // the `None` variant must not have a body
+        let result = AnotherOption::deserialize(&[0u8, 0u8]);
+        assert!(matches!(
+            result,
+            Err(DeserializeError::AdditionalInput { provided: 2, expected: 1 })
+        ));
+        assert!(Option::<u8>::deserialize(&[0u8, 12u8]).is_err());
+    }
+
     #[test]
     fn encode_union() {
         let value = Foo::A(12u32);
@@ -332,4 +537,109 @@ mod tests {
         let recovered = Boo::deserialize(&buffer).expect("can decode");
         assert_eq!(value, recovered);
     }
+
+    #[test]
+    fn test_union_hash_tree_root_from_bytes() {
+        let context = MerkleizationContext::global();
+        let mut values = [
+            Boo::A(7),
+            Boo::B(Inner { data: List::try_from(vec![1u8, 2]).unwrap() }),
+            Boo::C(List::default()),
+            Boo::D(Vector::try_from(vec![3u8, 4]).unwrap()),
+        ];
+        for value in values.iter_mut() {
+            let encoding = serialize(value).unwrap();
+            let root = Boo::hash_tree_root_from_bytes(&encoding, context).unwrap();
+            assert_eq!(root, value.hash_tree_root().unwrap());
+        }
+        let root = AnotherOption::hash_tree_root_from_bytes(&[0], context).unwrap();
+        assert_eq!(root, AnotherOption::None.hash_tree_root().unwrap());
+        assert!(AnotherOption::hash_tree_root_from_bytes(&[0, 1], context).is_err());
+        assert!(Boo::hash_tree_root_from_bytes(&[4, 1], context).is_err());
+        assert!(Boo::hash_tree_root_from_bytes(&[], context).is_err());
+    }
+
+    #[derive(Debug, Default, PartialEq, Eq, SimpleSerialize)]
+    struct Pair {
+        left: u64,
+        right: u64,
+    }
+
+    #[derive(Debug, Default, PartialEq, Eq, SimpleSerialize)]
+    enum PairOrNone {
+        #[default]
+        None,
+        Pair(Pair),
+    }
+
+    #[test]
+    fn test_union_proofs() {
+        use crate::{Proof, Sha256Hasher, Union};
+
+        let mut value = PairOrNone::Pair(Pair { left: 3, right: 4 });
+        let root = value.hash_tree_root().unwrap();
+        assert_eq!(value.selector(), 1);
+        assert!(value.prove_selector().unwrap().verify_selector(1, &root));
+        assert!(value.prove_variant().unwrap().verify(&root));
+
+        // prove a field of the selected variant against the root of the union
+        let left = 3u64.hash_tree_root().unwrap();
+        let right = 4u64.hash_tree_root().unwrap();
+        let proof = Proof::<Sha256Hasher>::new(left, vec![right], 0).mix_in_selector(1);
+        assert_eq!(proof.depth(), 2);
+        assert!(proof.verify(&root));
+        assert!(!Proof::<Sha256Hasher>::new(left, vec![right], 0).mix_in_selector(0).verify(&root));
+
+        let mut none = PairOrNone::None;
+        let root = none.hash_tree_root().unwrap();
+        assert_eq!(none.selector(), 0);
+        let proof = none.prove_selector().unwrap();
+        assert!(proof.verify_selector(0, &root));
+        assert_eq!(proof.branch, [Node::default()]);
+        // a proof of the value is not a proof of the selector
+        assert!(!none.prove_variant().unwrap().verify_selector(0, &root));
+    }
+
+    #[test]
+    fn test_generalized_index() {
+        use crate::{PathElement, PathError};
+
+        assert_eq!(Baz::generalized_index(&[PathElement::Selector]), Ok(3));
+        assert_eq!(Baz::generalized_index(&[1.into(), "data".into()]), Ok(2));
+        assert_eq!(Baz::generalized_index(&[1.into(), "data".into(), 3.into()]), Ok(4));
+        assert_eq!(Baz::generalized_index(&[2.into(), PathElement::Length]), Ok(5));
+        assert_eq!(
+            Baz::generalized_index(&[3.into()]),
+            Err(PathError::IndexOutOfBounds { index: 3, bound: 3 })
+        );
+        assert_eq!(PairOrNone::generalized_index(&[0.into()]), Ok(2));
+        assert!(PairOrNone::generalized_index(&[0.into(), "left".into()]).is_err());
+        assert_eq!(PairOrNone::generalized_index(&[1.into(), "right".into()]), Ok(5));
+    }
+
+    #[test]
+    fn test_variant_names() {
+        use crate::Union;
+
+        assert_eq!(Boo::VARIANT_COUNT, 4);
+        let names = (0..Boo::VARIANT_COUNT as u8).map(Boo::variant_name).collect::<Vec<_>>();
+        assert_eq!(names, [Some("A"), Some("B"), Some("C"), Some("D")]);
+        assert_eq!(Boo::variant_name(4), None);
+        assert_eq!(Boo::D(Default::default()).variant(), Some("D"));
+
+        assert_eq!(AnotherOption::VARIANT_COUNT, 3);
+        assert_eq!(AnotherOption::None.variant(), Some("None"));
+        assert_eq!(AnotherOption::B(1).variant(), Some("B"));
+        assert_eq!(Option::<u8>::VARIANT_COUNT, 2);
+        assert_eq!(Some(1u8).variant(), Some("Some"));
+    }
+
+    #[test]
+    fn test_max_size() {
+        const BAZ_MAX_SIZE: usize = Baz::MAX_SIZE;
+        assert_eq!(BAZ_MAX_SIZE, 1 + (4 + 8));
+        assert_eq!(Bar::MAX_SIZE, 1 + 4);
+        assert_eq!(Boo::max_size(), Baz::MAX_SIZE);
+        assert_eq!(Foo::IS_VARIABLE_SIZE, Foo::is_variable_size());
+    }
 }
@@ -0,0 +1,235 @@
+use crate::{
+    de::{Deserialize, DeserializeError, DeserializeWithLength},
+    error::{Error, InstanceError},
+    lib::*,
+    merkleization::{
+        chunks_for_list, chunks_to_nodes, elements_generalized_index, merkleize_with,
+        mix_in_length, pack_bytes, Context, GeneralizedIndex, MerkleHasher, MerkleizationError,
+        Merkleized, Node, PathElement, PathError,
+    },
+    ser::{Serialize, SerializeError},
+    SimpleSerialize, Sized,
+};
+
+/// A UTF-8 string of at most `N` bytes.
+///
+/// The SSZ representation is equivalent to `List<u8, N>` but the contents are validated
+/// as UTF-8 when decoding.
+#[derive(Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Utf8List<const N: usize>(String);
+
+#[cfg(feature = "serde")]
+impl<const N: usize> serde::Serialize for Utf8List<N> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, const N: usize> serde::Deserialize<'de> for Utf8List<N> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let data = <String>::deserialize(deserializer)?;
+        Self::try_from(data).map_err(|(_, err)| serde::de::Error::custom(err))
+    }
+}
+
+impl<const N: usize> fmt::Debug for Utf8List<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Utf8List<{N}>(len={}){:?}", self.0.len(), self.0)
+    }
+}
+
+impl<const N: usize> fmt::Display for Utf8List<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl<const N: usize> Utf8List<N> {
+    /// The maximum number of bytes in this `Utf8List`.
+    pub const MAX_LENGTH: usize = N;
+
+    /// Return the contents as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Return the inner `String`.
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl<const N: usize> Deref for Utf8List<N> {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<const N: usize> AsRef<str> for Utf8List<N> {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<const N: usize> AsRef<[u8]> for Utf8List<N> {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_bytes()
+    }
+}
+
+impl<const N: usize> TryFrom<String> for Utf8List<N> {
+    type Error = (String, Error);
+
+    fn try_from(data: String) -> Result<Self, Self::Error> {
+        if data.len() > N {
+            let len = data.len();
+            Err((data, Error::Instance(InstanceError::bounded::<Self>(N, len))))
+        } else {
+            Ok(Self(data))
+        }
+    }
+}
+
+impl<const N: usize> TryFrom<&str> for Utf8List<N> {
+    type Error = Error;
+
+    fn try_from(data: &str) -> Result<Self, Self::Error> {
+        Self::try_from(String::from(data)).map_err(|(_, err)| err)
+    }
+}
+
+impl<const N: usize> Sized for Utf8List<N> {
+    fn is_variable_size() -> bool {
+        true
+    }
+
+    fn size_hint() -> usize {
+        0
+    }
+}
+
+impl<const N: usize> Serialize for Utf8List<N> {
+    fn serialize(&self, buffer: &mut Vec<u8>) -> Result<usize, SerializeError> {
+        if self.0.len() > N {
+            return Err(SerializeError::LimitExceeded { bound: N, provided: self.0.len() })
+        }
+        buffer.extend_from_slice(self.0.as_bytes());
+        Ok(self.0.len())
+    }
+}
+
+impl<const N: usize> Deserialize for Utf8List<N> {
+    fn deserialize(encoding: &[u8]) -> Result<Self, DeserializeError> {
+        if encoding.len() > N {
+            return Err(InstanceError::bounded::<Self>(N, encoding.len()).into())
+        }
+        match str::from_utf8(encoding) {
+            Ok(data) => Ok(Self(String::from(data))),
+            Err(err) => Err(DeserializeError::InvalidByte(encoding[err.valid_up_to()])),
+        }
+    }
+}
+
+impl<const N: usize> DeserializeWithLength for Utf8List<N> {
+    fn deserialize_with_length(encoding: &[u8], len: usize) -> Result<Self, DeserializeError> {
+        if len > N {
+            return Err(InstanceError::bounded::<Self>(N, len).into())
+        }
+        if encoding.len() < len {
+            return Err(DeserializeError::ExpectedFurtherInput {
+                provided: encoding.len(),
+                expected: len,
+            })
+        }
+        if encoding.len() > len {
+            return Err(DeserializeError::AdditionalInput {
+                provided: encoding.len(),
+                expected: len,
+            })
+        }
+        Self::deserialize(encoding)
+    }
+}
+
+impl<const N: usize> Merkleized for Utf8List<N> {
+    fn hash_tree_root_with<H: MerkleHasher>(
+        &mut self,
+        context: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
+        let mut chunks = self.0.as_bytes().to_vec();
+        pack_bytes(&mut chunks);
+        let data_root = merkleize_with(&chunks, Some(Self::CHUNK_COUNT), context)?;
+        Ok(mix_in_length(&data_root, self.0.len(), context))
+    }
+
+    fn leaves<H: MerkleHasher>(&mut self, _: &Context<H>) -> Result<Vec<Node>, MerkleizationError> {
+        let mut chunks = self.0.as_bytes().to_vec();
+        pack_bytes(&mut chunks);
+        Ok(chunks_to_nodes(&chunks))
+    }
+
+    const CHUNK_COUNT: usize = chunks_for_list(N, 1);
+
+    fn merkle_limit() -> Option<usize> {
+        Some(Self::chunk_count())
+    }
+}
+
+impl<const N: usize> SimpleSerialize for Utf8List<N> {
+    const MAX_SIZE: usize = N;
+
+    const NESTING_DEPTH: usize = 1;
+
+    fn generalized_index_from(
+        root: GeneralizedIndex,
+        path: &[PathElement<'_>],
+    ) -> Result<GeneralizedIndex, PathError> {
+        elements_generalized_index::<Self, u8>(root, path, N, Self::tree_depth(), true)
+    }
+
+    fn ssz_heap_size(&self) -> usize {
+        self.0.capacity()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{list::List, serialize};
+
+    #[test]
+    fn roundtrip_utf8_list() {
+        let value = Utf8List::<32>::try_from("hello, 世界").unwrap();
+        let encoding = serialize(&value).expect("can encode");
+        assert_eq!(encoding, "hello, 世界".as_bytes());
+        let recovered = Utf8List::<32>::deserialize(&encoding).expect("can decode");
+        assert_eq!(recovered.as_str(), "hello, 世界");
+    }
+
+    #[test]
+    fn decode_utf8_list_rejects_invalid_input() {
+        let result = Utf8List::<32>::deserialize(&[104u8, 105u8, 0xffu8]);
+        assert!(matches!(result, Err(DeserializeError::InvalidByte(0xff))));
+
+        let result = Utf8List::<2>::deserialize(b"abc");
+        assert!(matches!(result, Err(DeserializeError::InvalidInstance(_))));
+
+        assert!(Utf8List::<2>::try_from("abc").is_err());
+    }
+
+    #[test]
+    fn utf8_list_matches_byte_list_root() {
+        let mut value = Utf8List::<64>::try_from("some string data").unwrap();
+        let mut bytes = List::<u8, 64>::try_from(b"some string data".to_vec()).unwrap();
+        assert_eq!(value.hash_tree_root().unwrap(), bytes.hash_tree_root().unwrap());
+    }
+}
@@ -1,4 +1,14 @@
-use crate::{de::DeserializeError, lib::*, ser::SerializeError, SimpleSerialize};
+use crate::{
+    container::{Container, FieldError},
+    de::DeserializeError,
+    error::Error,
+    lib::*,
+    merkleization::{Context, MerkleHasher, MerkleizationError, Node},
+    metrics,
+    ser::SerializeError,
+    spans::Span,
+    SimpleSerialize,
+};
 
 /// `serialize` is a convenience function for taking a value that
 /// implements `SimpleSerialize` and attempting to encode it to
@@ -7,8 +17,10 @@ pub fn serialize<T>(value: &T) -> Result<Vec<u8>, SerializeError>
 where
     T: SimpleSerialize,
 {
+    let span = Span::new::<T>("serialize");
     let mut result = vec![];
-    value.serialize(&mut result)?;
+    span.in_scope(|| value.serialize(&mut result))?;
+    span.record_bytes(result.len());
     Ok(result)
 }
 
@@ -19,18 +31,115 @@ pub fn deserialize<T>(encoding: &[u8]) -> Result<T, DeserializeError>
 where
     T: SimpleSerialize,
 {
-    T::deserialize(encoding)
+    metrics::record_decoded_bytes(encoding.len());
+    let span = Span::new::<T>("deserialize");
+    span.record_bytes(encoding.len());
+    span.in_scope(|| T::deserialize(encoding))
 }
 
-pub(crate) fn write_bytes_to_lower_hex<T: AsRef<[u8]>>(
-    f: &mut fmt::Formatter<'_>,
-    data: T,
-) -> fmt::Result {
-    if f.alternate() {
-        write!(f, "0x")?;
-    }
-    for i in data.as_ref() {
-        write!(f, "{i:02x}")?;
+/// `to_bytes` encodes `value`, like [`serialize`]. Together with [`from_bytes`] and [`root`],
+/// it covers simple uses without importing any traits.
+///
+/// ```rust
+/// use ssz_rs::{List, Node};
+///
+/// let value = List::<u16, 8>::try_from(vec![1, 2, 3]).unwrap();
+/// let encoding = ssz_rs::to_bytes(&value).unwrap();
+/// assert_eq!(encoding, [1, 0, 2, 0, 3, 0]);
+///
+/// let mut decoded = ssz_rs::from_bytes::<List<u16, 8>>(&encoding).unwrap();
+/// assert_eq!(decoded, value);
+/// assert_ne!(ssz_rs::root(&mut decoded).unwrap(), Node::default());
+/// ```
+pub fn to_bytes<T>(value: &T) -> Result<Vec<u8>, SerializeError>
+where
+    T: SimpleSerialize,
+{
+    serialize(value)
+}
+
+/// `from_bytes` decodes a value of type `T` from `encoding`, like [`deserialize`].
+pub fn from_bytes<T>(encoding: &[u8]) -> Result<T, DeserializeError>
+where
+    T: SimpleSerialize,
+{
+    deserialize(encoding)
+}
+
+/// `root` computes the hash tree root of `value` with the default hasher.
+pub fn root<T>(value: &mut T) -> Result<Node, MerkleizationError>
+where
+    T: SimpleSerialize,
+{
+    value.hash_tree_root()
+}
+
+/// `validate_frame` checks that a frame of `len` bytes is not longer than the encoding of any
+/// value of type `T`, so oversized frames can be rejected before reading or decoding them.
+///
+/// ```rust
+/// use ssz_rs::prelude::*;
+///
+/// #[derive(Default, Debug, SimpleSerialize)]
+/// struct Message {
+///     id: u64,
+///     payload: List<u8, 1024>,
+/// }
+///
+/// const MAX_MESSAGE_SIZE: usize = Message::MAX_SIZE;
+/// assert_eq!(MAX_MESSAGE_SIZE, 8 + 4 + 1024);
+/// assert!(ssz_rs::validate_frame::<Message>(1036).is_ok());
+/// assert!(ssz_rs::validate_frame::<Message>(1 << 20).is_err());
+/// ```
+pub fn validate_frame<T>(len: usize) -> Result<(), DeserializeError>
+where
+    T: SimpleSerialize,
+{
+    let max_size = T::max_size();
+    if len > max_size {
+        return Err(DeserializeError::AdditionalInput { provided: len, expected: max_size })
     }
     Ok(())
 }
+
+/// `hash_tree_root_from_bytes` computes the hash tree root of the value of type `T`
+/// encoded as `encoding`, hashing the encoding directly where the type allows
+/// rather than deserializing the value first.
+pub fn hash_tree_root_from_bytes<T>(
+    encoding: &[u8],
+    context: &Context<impl MerkleHasher>,
+) -> Result<Node, Error>
+where
+    T: SimpleSerialize,
+{
+    metrics::record_decoded_bytes(encoding.len());
+    let span = Span::new::<T>("hash_tree_root_from_bytes");
+    span.record_bytes(encoding.len());
+    span.in_scope(|| T::hash_tree_root_from_bytes(encoding, context))
+}
+
+/// `field_root_from_bytes` computes the hash tree root of the field called `name` in the
+/// encoding of the container `T`, decoding and hashing only that field.
+///
+/// ```rust
+/// use ssz_rs::prelude::*;
+///
+/// #[derive(Default, Debug, SimpleSerialize)]
+/// struct Header {
+///     slot: u64,
+///     state_root: Node,
+///     extra: List<u8, 32>,
+/// }
+///
+/// let mut header = Header { slot: 1, ..Default::default() };
+/// let encoding = serialize(&header).unwrap();
+/// let root = ssz_rs::field_root_from_bytes::<Header>(&encoding, "state_root").unwrap();
+/// assert_eq!(root, header.state_root.hash_tree_root().unwrap());
+/// ```
+pub fn field_root_from_bytes<T>(encoding: &[u8], name: &str) -> Result<Node, FieldError>
+where
+    T: Container,
+{
+    metrics::record_decoded_bytes(encoding.len());
+    T::field_root_from_bytes_with(encoding, name, Context::global())
+}
@@ -1,13 +1,19 @@
 use crate::{
-    de::{deserialize_homogeneous_composite, Deserialize, DeserializeError},
-    error::{Error, InstanceError, TypeError},
+    de::{deserialize_homogeneous_composite, Deserialize, DeserializeError, DeserializeWithLength},
+    error::{Error, InstanceError, OutOfBounds, TypeError},
     lib::*,
     merkleization::{
-        merkleize, pack, MerkleCache, MerkleizationError, Merkleized, Node, BYTES_PER_CHUNK,
+        chunks_to_nodes, element_leaf, elements_chunk_count, elements_chunk_count_const,
+        elements_chunks, elements_generalized_index, elements_root_from_bytes, layer_at_depth_with,
+        merkleize_repeated_with, merkleize_with, Context, GeneralizedIndex, MerkleCache,
+        MerkleHasher, MerkleizationError, Merkleized, Node, PathElement, PathError, Sha256Hasher,
+        BYTES_PER_CHUNK,
     },
-    ser::{serialize_composite, Serialize, SerializeError},
+    ser::{elements_max_size, serialize_composite, Serialize, SerializeError},
     SimpleSerialize, Sized,
 };
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 #[cfg(feature = "serde")]
 use serde::ser::SerializeSeq;
 #[cfg(feature = "serde")]
@@ -92,7 +98,7 @@ impl<T: SimpleSerialize, const N: usize> TryFrom<Vec<T>> for Vector<T, N> {
         }
         if data.len() != N {
             let len = data.len();
-            Err((data, Error::Instance(InstanceError::Exact { required: N, provided: len })))
+            Err((data, Error::Instance(InstanceError::exact::<Self>(N, len))))
         } else {
             let leaf_count = Self::get_leaf_count();
             Ok(Self { data, cache: MerkleCache::with_leaves(leaf_count) })
@@ -100,6 +106,31 @@ impl<T: SimpleSerialize, const N: usize> TryFrom<Vec<T>> for Vector<T, N> {
     }
 }
 
+// NOTE: a `Vector` of length `0` is illegal and will fail to serialize.
+impl<T: SimpleSerialize, const N: usize> From<[T; N]> for Vector<T, N> {
+    fn from(data: [T; N]) -> Self {
+        let leaf_count = Self::get_leaf_count();
+        Self { data: Vec::from(data), cache: MerkleCache::with_leaves(leaf_count) }
+    }
+}
+
+impl<T: SimpleSerialize, const N: usize> TryFrom<Vector<T, N>> for [T; N] {
+    type Error = Error;
+
+    fn try_from(vector: Vector<T, N>) -> Result<Self, Self::Error> {
+        vector
+            .data
+            .try_into()
+            .map_err(|data: Vec<T>| Error::Instance(InstanceError::exact::<Self>(N, data.len())))
+    }
+}
+
+impl<T: SimpleSerialize, const N: usize> From<Vector<T, N>> for Vec<T> {
+    fn from(vector: Vector<T, N>) -> Self {
+        vector.into_inner()
+    }
+}
+
 impl<T, const N: usize> fmt::Debug for Vector<T, N>
 where
     T: SimpleSerialize + fmt::Debug,
@@ -225,10 +256,25 @@ where
     }
 }
 
+impl<T, const N: usize> DeserializeWithLength for Vector<T, N>
+where
+    T: SimpleSerialize,
+{
+    fn deserialize_with_length(encoding: &[u8], len: usize) -> Result<Self, DeserializeError> {
+        if len != N {
+            return Err(InstanceError::exact::<Self>(N, len).into())
+        }
+        Self::deserialize(encoding)
+    }
+}
+
 impl<T, const N: usize> Vector<T, N>
 where
     T: SimpleSerialize,
 {
+    /// The number of elements in this `Vector`.
+    pub const LENGTH: usize = N;
+
     // the number of leafs in the Merkle tree of this `Vector`
     fn get_leaf_count() -> usize {
         if T::is_composite_type() {
@@ -243,24 +289,160 @@ where
         if T::is_composite_type() {
             index
         } else {
-            // TODO: compute correct leaf index
-            index + 1
+            // the chunk the element is packed into
+            index * T::size_hint() / BYTES_PER_CHUNK
         }
     }
 
-    fn compute_hash_tree_root(&mut self) -> Result<Node, MerkleizationError> {
-        if T::is_composite_type() {
-            let mut chunks = vec![0u8; self.len() * BYTES_PER_CHUNK];
-            for (i, elem) in self.data.iter_mut().enumerate() {
-                let chunk = elem.hash_tree_root()?;
-                let range = i * BYTES_PER_CHUNK..(i + 1) * BYTES_PER_CHUNK;
-                chunks[range].copy_from_slice(chunk.as_ref());
-            }
-            merkleize(&chunks, None)
-        } else {
-            let chunks = pack(&self.data)?;
-            merkleize(&chunks, None)
+    /// Construct a `Vector` by calling `f` with the index of each element in order,
+    /// returning the first error from `f`.
+    pub fn try_from_fn<E, F>(f: F) -> Result<Self, E>
+    where
+        F: FnMut(usize) -> Result<T, E>,
+    {
+        let data = (0..N).map(f).collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { data, cache: MerkleCache::with_leaves(Self::get_leaf_count()) })
+    }
+
+    /// Return a `Vector` of the same length with `f` applied to each element.
+    pub fn map<U, F>(self, f: F) -> Vector<U, N>
+    where
+        U: SimpleSerialize,
+        F: FnMut(T) -> U,
+    {
+        let data = self.data.into_iter().map(f).collect();
+        Vector { data, cache: MerkleCache::with_leaves(Vector::<U, N>::get_leaf_count()) }
+    }
+
+    /// Return a mutable reference to the element at `index`, or `None` if out of bounds.
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index >= self.len() {
+            return None
+        }
+        Some(&mut self[index])
+    }
+
+    /// Return the element at `index`, or an error with the length if out of bounds.
+    pub fn try_at(&self, index: usize) -> Result<&T, OutOfBounds> {
+        self.data.get(index).ok_or(OutOfBounds { index, len: self.len() })
+    }
+
+    /// Return a mutable reference to the element at `index`, or an error with the length if
+    /// out of bounds.
+    pub fn try_at_mut(&mut self, index: usize) -> Result<&mut T, OutOfBounds> {
+        let len = self.len();
+        self.get_mut(index).ok_or(OutOfBounds { index, len })
+    }
+
+    /// Return the elements, moving them out without copying.
+    pub fn into_inner(self) -> Vec<T> {
+        self.data
+    }
+
+    /// Return the cache of the root of the `Vector`, e.g. to persist it with
+    /// [`MerkleCache::to_bytes`].
+    pub fn merkle_cache(&self) -> &MerkleCache {
+        &self.cache
+    }
+
+    /// Construct a `Vector` from its elements and a cache of its root, e.g. one persisted with
+    /// [`MerkleCache::to_bytes`] and restored with [`MerkleCache::from_bytes`], so hashing the
+    /// `Vector` again returns the kept root.
+    ///
+    /// The cache is trusted to belong to `data`. Returns `data` back if there are not exactly
+    /// `N` elements or the cache has a different number of leaves.
+    pub fn from_parts(data: Vec<T>, cache: MerkleCache) -> Result<Self, (Vec<T>, Error)> {
+        if N == 0 {
+            return Err((data, Error::Type(TypeError::InvalidBound(N))))
+        }
+        if data.len() != N {
+            let len = data.len();
+            return Err((data, Error::Instance(InstanceError::exact::<Self>(N, len))))
         }
+        let leaf_count = Self::get_leaf_count();
+        if cache.leaf_count() != leaf_count {
+            let provided = cache.leaf_count();
+            let err = InstanceError::exact::<MerkleCache>(leaf_count, provided);
+            return Err((data, Error::Instance(err)))
+        }
+        Ok(Self { data, cache })
+    }
+
+    /// Return the elements as a slice.
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    /// Return the elements as a mutable slice.
+    ///
+    /// The number of elements can not change through the slice.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        self.cache.invalidate_all();
+        &mut self.data
+    }
+
+    fn compute_hash_tree_root<H: MerkleHasher>(
+        &mut self,
+        context: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
+        let chunks = elements_chunks(&mut self.data, context)?;
+        merkleize_with(&chunks, None, context)
+    }
+
+    /// Return the roots of the `2**depth` subtrees at `depth` of the Merkle tree of the
+    /// `Vector`, e.g. the roots of its halves at depth 1, or its root at depth 0.
+    ///
+    /// The `2**depth` roots are allocated at once. A `depth` greater than `Self::tree_depth()`
+    /// or than [`MAX_LAYER_DEPTH`](crate::advanced::MAX_LAYER_DEPTH) is an error.
+    pub fn hash_tree_root_at_depth<H: MerkleHasher>(
+        &mut self,
+        depth: u32,
+        context: &Context<H>,
+    ) -> Result<Vec<Node>, MerkleizationError> {
+        let chunks = elements_chunks(&mut self.data, context)?;
+        layer_at_depth_with(&chunks, elements_chunk_count::<T>(N), depth, context)
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T, const N: usize> rayon::iter::IntoParallelIterator for Vector<T, N>
+where
+    T: SimpleSerialize + Send,
+{
+    type Iter = rayon::vec::IntoIter<T>;
+    type Item = T;
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.data.into_par_iter()
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, T, const N: usize> rayon::iter::IntoParallelIterator for &'a Vector<T, N>
+where
+    T: SimpleSerialize + Sync,
+{
+    type Iter = rayon::slice::Iter<'a, T>;
+    type Item = &'a T;
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.data.par_iter()
+    }
+}
+
+// NOTE: the elements may be mutated in any order across threads
+// so conservatively invalidate every leaf of the cache up front.
+#[cfg(feature = "rayon")]
+impl<'a, T, const N: usize> rayon::iter::IntoParallelIterator for &'a mut Vector<T, N>
+where
+    T: SimpleSerialize + Send,
+{
+    type Iter = rayon::slice::IterMut<'a, T>;
+    type Item = &'a mut T;
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.cache.invalidate_all();
+        self.data.par_iter_mut()
     }
 }
 
@@ -268,19 +450,89 @@ impl<T, const N: usize> Merkleized for Vector<T, N>
 where
     T: SimpleSerialize,
 {
-    fn hash_tree_root(&mut self) -> Result<Node, MerkleizationError> {
+    fn hash_tree_root_with<H: MerkleHasher>(
+        &mut self,
+        context: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
+        // NOTE: the cache only holds roots computed with the default hash function
+        if TypeId::of::<H>() != TypeId::of::<Sha256Hasher>() {
+            return self.compute_hash_tree_root(context)
+        }
         if !self.cache.valid() {
-            // which leaves are dirty
-            // figure out which elements are needed and recompute leaves
-            // update cache w/ new leaves
-            let root = self.compute_hash_tree_root()?;
+            let Self { data, cache } = self;
+            let depth = Self::tree_depth() as usize;
+            let root = cache.rehash(depth, context, |i| element_leaf(data, i, context))?;
             self.cache.update(root);
+            self.cache.clean();
         }
         Ok(self.cache.root())
     }
+
+    fn leaves<H: MerkleHasher>(
+        &mut self,
+        context: &Context<H>,
+    ) -> Result<Vec<Node>, MerkleizationError> {
+        Ok(chunks_to_nodes(&elements_chunks(&mut self.data, context)?))
+    }
+
+    const CHUNK_COUNT: usize = elements_chunk_count_const::<T>(N);
 }
 
-impl<T, const N: usize> SimpleSerialize for Vector<T, N> where T: SimpleSerialize + Clone {}
+impl<T, const N: usize> SimpleSerialize for Vector<T, N>
+where
+    T: SimpleSerialize + Clone,
+{
+    fn nesting_depth() -> usize {
+        1 + T::nesting_depth()
+    }
+
+    const MAX_SIZE: usize = elements_max_size::<T>(N);
+
+    const IS_VARIABLE_SIZE: bool = T::IS_VARIABLE_SIZE;
+
+    const NESTING_DEPTH: usize = T::NESTING_DEPTH.saturating_add(1);
+
+    fn generalized_index_from(
+        root: GeneralizedIndex,
+        path: &[PathElement<'_>],
+    ) -> Result<GeneralizedIndex, PathError> {
+        elements_generalized_index::<Self, T>(root, path, N, Self::tree_depth(), false)
+    }
+
+    fn hash_tree_root_from_bytes<H: MerkleHasher>(
+        encoding: &[u8],
+        context: &Context<H>,
+    ) -> Result<Node, Error> {
+        if N == 0 {
+            return Err(Error::Type(TypeError::InvalidBound(N)))
+        }
+        let (root, len) = elements_root_from_bytes::<Self, T, H>(encoding, N, context)?;
+        if len != N {
+            return Err(Error::Instance(InstanceError::exact::<Self>(N, len)))
+        }
+        Ok(root)
+    }
+
+    fn default_hash_tree_root_with<H: MerkleHasher>(
+        context: &Context<H>,
+    ) -> Result<Node, MerkleizationError> {
+        let element_root = T::default_hash_tree_root_with(context)?;
+        if T::is_composite_type() {
+            Ok(merkleize_repeated_with(&element_root, N, N.next_power_of_two(), context))
+        } else if element_root == Node::default() {
+            // NOTE: the default elements pack into zero chunks
+            merkleize_with(&[], Some(elements_chunk_count::<T>(N)), context)
+        } else {
+            Self::default().hash_tree_root_with(context)
+        }
+    }
+
+    fn ssz_heap_size(&self) -> usize {
+        self.data.capacity() * mem::size_of::<T>() +
+            self.data.iter().map(T::ssz_heap_size).sum::<usize>() +
+            self.cache.heap_size()
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -307,6 +559,26 @@ mod tests {
         assert_eq!(vector[0], 2u8);
     }
 
+    #[test]
+    fn test_array_conversions() {
+        let mut vector = Vector::<u8, 4>::from([1u8, 2u8, 3u8, 4u8]);
+        let root = vector.hash_tree_root().expect("can compute root");
+        assert_eq!(vector.as_slice(), [1u8, 2u8, 3u8, 4u8]);
+
+        vector.as_mut_slice().copy_from_slice(&[4u8, 3u8, 2u8, 1u8]);
+        assert_ne!(vector.hash_tree_root().expect("can compute root"), root);
+
+        let array = <[u8; 4]>::try_from(vector).unwrap();
+        assert_eq!(array, [4u8, 3u8, 2u8, 1u8]);
+
+        let data = vec![5u8; 4];
+        let ptr = data.as_ptr();
+        let vector = Vector::<u8, 4>::try_from(data).unwrap();
+        let data = Vec::from(vector);
+        assert_eq!(data.as_ptr(), ptr);
+        assert_eq!(Vector::<u8, 4>::try_from(data).unwrap().into_inner(), [5u8; 4]);
+    }
+
     #[test]
     fn encode_vector() {
         let data = vec![33u16; COUNT];
@@ -363,6 +635,37 @@ mod tests {
         assert_eq!(input, recovered);
     }
 
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_par_iter_mut_invalidates_cache() {
+        let mut value = Vector::<u64, COUNT>::try_from(vec![1u64; COUNT]).unwrap();
+        let root = value.hash_tree_root().expect("can compute root");
+
+        value.par_iter_mut().for_each(|element| *element *= 2);
+        assert_eq!(value.par_iter().sum::<u64>(), 2 * COUNT as u64);
+
+        let mut expected = Vector::<u64, COUNT>::try_from(vec![2u64; COUNT]).unwrap();
+        let new_root = value.hash_tree_root().expect("can compute root");
+        assert_ne!(root, new_root);
+        assert_eq!(new_root, expected.hash_tree_root().expect("can compute root"));
+    }
+
+    #[test]
+    fn test_try_from_fn_and_map() {
+        let value = Vector::<u16, 4>::try_from_fn(|i| Ok::<_, ()>(i as u16 * 2)).unwrap();
+        assert_eq!(value.as_slice(), [0, 2, 4, 6]);
+        let result = Vector::<u16, 4>::try_from_fn(|i| if i < 2 { Ok(0) } else { Err(i) });
+        assert_eq!(result.unwrap_err(), 2);
+
+        let mut mapped = value.map(|element| List::<u16, 2>::try_from(vec![element]).unwrap());
+        let mut expected = Vector::<List<u16, 2>, 4>::try_from(
+            (0..4).map(|i| List::try_from(vec![i * 2]).unwrap()).collect::<Vec<_>>(),
+        )
+        .unwrap();
+        assert_eq!(mapped, expected);
+        assert_eq!(mapped.hash_tree_root().unwrap(), expected.hash_tree_root().unwrap());
+    }
+
     #[test]
     fn roundtrip_variable_vector() {
         const COUNT: usize = 4;
@@ -376,4 +679,71 @@ mod tests {
         let recovered = Vector::<List<u8, 1>, COUNT>::deserialize(&buffer).expect("can decode");
         assert_eq!(input, recovered);
     }
+
+    #[test]
+    fn test_hash_tree_root_at_depth() {
+        let context = Context::global();
+        let mut vector = Vector::<List<u8, 4>, 3>::try_from(vec![
+            List::try_from(vec![1u8]).unwrap(),
+            List::try_from(vec![2u8]).unwrap(),
+            List::default(),
+        ])
+        .unwrap();
+        let roots = vector
+            .as_mut_slice()
+            .iter_mut()
+            .map(|list| list.hash_tree_root().unwrap())
+            .collect::<Vec<_>>();
+
+        let leaves = vector.hash_tree_root_at_depth(2, context).unwrap();
+        assert_eq!(leaves, [roots[0], roots[1], roots[2], Node::default()]);
+        let halves = vector.hash_tree_root_at_depth(1, context).unwrap();
+        assert_eq!(
+            halves,
+            [Node::hash_pair(&roots[0], &roots[1]), Node::hash_pair(&roots[2], &Node::default())]
+        );
+        let root = vector.hash_tree_root_at_depth(0, context).unwrap();
+        assert_eq!(root, [vector.hash_tree_root().unwrap()]);
+    }
+
+    #[test]
+    fn test_rehash_dirty_leaves() {
+        // a change the cache does not see shows that only the dirty leaves are hashed again
+        let mut vector = Vector::<List<u8, 4>, 5>::default();
+        vector.hash_tree_root().unwrap();
+        vector.data[0].push(1).unwrap();
+        vector[3].push(2).unwrap();
+        let mut expected = Vector::<List<u8, 4>, 5>::default();
+        expected[3].push(2).unwrap();
+        assert_eq!(vector.hash_tree_root().unwrap(), expected.hash_tree_root().unwrap());
+
+        let mut vector = Vector::<u32, 70>::default();
+        for i in (0..70).step_by(3) {
+            vector[i] = i as u32;
+            vector[69 - i / 2] += 1;
+            let mut expected = Vector::<u32, 70>::try_from(vector.to_vec()).unwrap();
+            assert_eq!(vector.hash_tree_root().unwrap(), expected.hash_tree_root().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_restored_cache() {
+        let mut vector = Vector::<u64, 8>::try_from((0..8u64).collect::<Vec<_>>()).unwrap();
+        let root = vector.hash_tree_root().unwrap();
+        let cache = MerkleCache::from_bytes(&vector.merkle_cache().to_bytes()).unwrap();
+        let mut restored = Vector::<u64, 8>::from_parts(vector.to_vec(), cache.clone()).unwrap();
+        assert_eq!(restored.hash_tree_root().unwrap(), root);
+
+        // the restored root is returned without hashing the elements, so a planted root shows
+        let mut planted = MerkleCache::with_leaves(2);
+        planted.update(Node::try_from([7u8; 32].as_ref()).unwrap());
+        planted.clean();
+        let mut restored = Vector::<u64, 8>::from_parts(vector.to_vec(), planted).unwrap();
+        assert_eq!(restored.hash_tree_root().unwrap(), Node::try_from([7u8; 32].as_ref()).unwrap());
+        restored[0] = 0;
+        assert_eq!(restored.hash_tree_root().unwrap(), root);
+
+        assert!(Vector::<u64, 8>::from_parts(vec![0; 7], cache.clone()).is_err());
+        assert!(Vector::<u64, 4>::from_parts(vec![0; 4], cache).is_err());
+    }
 }
@@ -0,0 +1,104 @@
+#![cfg(feature = "arena")]
+use ssz_rs::{
+    arena::{Arena, ArenaList, ArenaVector, DeserializeIn},
+    prelude::*,
+};
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, SimpleSerialize)]
+struct Validator {
+    pubkey: Vector<u8, 48>,
+    balances: List<u64, 16>,
+}
+
+// `Validator` decoded in an arena
+#[derive(Debug, Clone, Copy)]
+struct ArenaValidator<'a> {
+    pubkey: ArenaVector<'a, u8, 48>,
+    balances: ArenaList<'a, u64, 16>,
+}
+
+impl ssz_rs::Sized for ArenaValidator<'_> {
+    fn is_variable_size() -> bool {
+        true
+    }
+
+    fn size_hint() -> usize {
+        0
+    }
+}
+
+impl<'a> DeserializeIn<'a> for ArenaValidator<'a> {
+    fn deserialize_in(encoding: &[u8], arena: &'a Arena) -> Result<Self, DeserializeError> {
+        // the fixed-size `pubkey` and the offset of `balances` precede the encoding of `balances`
+        if encoding.len() < 52 {
+            return Err(DeserializeError::ExpectedFurtherInput {
+                provided: encoding.len(),
+                expected: 52,
+            })
+        }
+        let offset = u32::deserialize(&encoding[48..52])? as usize;
+        if offset != 52 {
+            return Err(DeserializeError::AdditionalInput { provided: offset, expected: 52 })
+        }
+        Ok(Self {
+            pubkey: arena.deserialize(&encoding[..48])?,
+            balances: arena.deserialize(&encoding[52..])?,
+        })
+    }
+}
+
+impl ArenaValidator<'_> {
+    fn to_validator(self) -> Validator {
+        Validator { pubkey: self.pubkey.to_vector(), balances: self.balances.to_list() }
+    }
+}
+
+fn validators(count: usize) -> List<Validator, 64> {
+    let validator = |i: usize| Validator {
+        pubkey: Vector::try_from(vec![i as u8; 48]).unwrap(),
+        balances: List::try_from(vec![i as u64; 4]).unwrap(),
+    };
+    List::try_from((0..count).map(validator).collect::<Vec<_>>()).unwrap()
+}
+
+#[test]
+fn test_arena() {
+    let values = validators(32);
+    let encoding = serialize(&values).unwrap();
+
+    let mut arena = Arena::with_capacity(1 << 16).unwrap();
+    let decoded: ArenaList<ArenaValidator, 64> = arena.deserialize(&encoding).unwrap();
+    assert_eq!(decoded.len(), 32);
+    let decoded = decoded.iter().map(|validator| validator.to_validator()).collect::<Vec<_>>();
+    assert_eq!(decoded, values.to_vec());
+    // the elements of all lists are in the arena
+    assert!(arena.used() >= 32 * (48 + 4 * 8));
+    assert!(arena.used() <= arena.capacity());
+
+    // invalid encodings and bounds are rejected as without an arena
+    assert!(arena.deserialize::<ArenaList<ArenaValidator, 64>>(&encoding[..7]).is_err());
+    assert!(matches!(
+        arena.deserialize::<ArenaList<ArenaValidator, 16>>(&encoding),
+        Err(DeserializeError::InvalidInstance(InstanceError::Bounded {
+            bound: 16,
+            provided: 32,
+            ..
+        }))
+    ));
+    assert!(arena.deserialize::<ArenaVector<u8, 48>>(&[0; 47]).is_err());
+
+    // the region is reused once every decoded value is freed
+    arena.reset();
+    assert_eq!(arena.used(), 0);
+    let decoded: ArenaList<u64, 16> =
+        arena.deserialize(&serialize(&values[1].balances).unwrap()).unwrap();
+    assert_eq!(decoded.as_slice(), [1; 4]);
+
+    // decoding fails once the region is used up
+    let arena = Arena::with_capacity(256).unwrap();
+    assert!(matches!(
+        arena.deserialize::<ArenaList<ArenaValidator, 64>>(&encoding),
+        Err(DeserializeError::ArenaExhausted { .. })
+    ));
+    assert!(arena.used() <= 256);
+}
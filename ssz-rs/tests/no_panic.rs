@@ -0,0 +1,194 @@
+//! Decoding and hashing must return errors rather than panic on any input, as encodings are
+//! read from untrusted peers. Each type is exercised with mutations of a valid encoding and
+//! with pseudo-random bytes, failing with the inputs that panicked.
+use ssz_rs::{
+    prelude::*, Bitlist, Bitvector, Container, ForwardCompatible, LazyList, MerkleizationContext,
+    Utf8List,
+};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, SimpleSerialize)]
+struct Fixed {
+    a: u8,
+    b: u64,
+    c: Vector<u16, 3>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, SimpleSerialize)]
+struct Variable {
+    a: u16,
+    b: List<u16, 8>,
+    c: u8,
+    d: List<List<u8, 4>, 3>,
+    e: Fixed,
+    f: Bitlist<12>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, SimpleSerialize)]
+enum Choice {
+    #[default]
+    None,
+    Number(u32),
+    Data(List<List<u8, 4>, 3>),
+}
+
+// A small deterministic generator of pseudo-random bytes (xorshift64).
+struct Bytes(u64);
+
+impl Bytes {
+    fn next(&mut self) -> u8 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 as u8
+    }
+
+    fn take(&mut self, len: usize) -> Vec<u8> {
+        (0..len).map(|_| self.next()).collect()
+    }
+}
+
+// Return truncations, extensions and single-byte mutations of `encoding`, and random inputs.
+fn inputs(encoding: &[u8]) -> Vec<Vec<u8>> {
+    let mut inputs = vec![];
+    for len in 0..encoding.len() {
+        inputs.push(encoding[..len].to_vec());
+    }
+    for extra in [&[0u8][..], &[1], &[0xff; 5]] {
+        inputs.push([encoding, extra].concat());
+    }
+    for i in 0..encoding.len() {
+        for byte in [0, 1, 0x7f, 0x80, 0xff, encoding[i] ^ 1, encoding[i].wrapping_add(4)] {
+            let mut input = encoding.to_vec();
+            input[i] = byte;
+            inputs.push(input);
+        }
+    }
+    let mut bytes = Bytes(0x9e37_79b9_7f4a_7c15 ^ encoding.len() as u64);
+    for len in 0..128 {
+        inputs.push(bytes.take(len % 64));
+    }
+    inputs
+}
+
+fn assert_no_panic<T: SimpleSerialize>(value: T) {
+    let encoding = serialize(&value).expect("can encode");
+    let mut panicked = vec![];
+    for input in inputs(&encoding) {
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            let _ = ssz_rs::hash_tree_root_from_bytes::<T>(&input, MerkleizationContext::global());
+            if let Ok(mut value) = T::deserialize(&input) {
+                value.hash_tree_root().expect("a decoded value can be hashed");
+                let encoding = serialize(&value).expect("a decoded value can be encoded");
+                assert_eq!(encoding, input, "decoding is canonical");
+            }
+        }));
+        if result.is_err() {
+            panicked.push(input);
+        }
+    }
+    assert!(panicked.is_empty(), "{} panicked on inputs {panicked:?}", std::any::type_name::<T>());
+}
+
+fn assert_fields_no_panic<T: Container>(value: T) {
+    let encoding = serialize(&value).expect("can encode");
+    let mut panicked = vec![];
+    for input in inputs(&encoding) {
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            for field in T::fields() {
+                let _ = T::field_root_from_bytes_with(
+                    &input,
+                    field.name,
+                    MerkleizationContext::global(),
+                );
+            }
+            let _ = ForwardCompatible::<T>::deserialize(&input);
+        }));
+        if result.is_err() {
+            panicked.push(input);
+        }
+    }
+    assert!(panicked.is_empty(), "{} panicked on inputs {panicked:?}", std::any::type_name::<T>());
+}
+
+fn assert_lazy_no_panic<T: SimpleSerialize, const N: usize>(list: List<T, N>) {
+    let encoding = serialize(&list).expect("can encode");
+    let mut panicked = vec![];
+    for input in inputs(&encoding) {
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            if let Ok(lazy) = LazyList::<T, N>::new(input.clone()) {
+                for index in 0..lazy.len() {
+                    let _ = lazy.decode(index);
+                }
+                let _ = lazy.to_list();
+            }
+        }));
+        if result.is_err() {
+            panicked.push(input);
+        }
+    }
+    assert!(panicked.is_empty(), "{} panicked on inputs {panicked:?}", std::any::type_name::<T>());
+}
+
+fn variable() -> Variable {
+    Variable {
+        a: 3,
+        b: List::try_from(vec![1, 2, 3]).unwrap(),
+        c: 7,
+        d: List::try_from(vec![
+            List::try_from(vec![1u8]).unwrap(),
+            List::default(),
+            List::try_from(vec![2u8, 3]).unwrap(),
+        ])
+        .unwrap(),
+        e: Fixed { a: 1, b: 2, c: Vector::try_from(vec![3, 4, 5]).unwrap() },
+        f: Bitlist::from_iter([true, false, true]),
+    }
+}
+
+#[test]
+fn test_basic_types() {
+    assert_no_panic(true);
+    assert_no_panic(0x1234u16);
+    assert_no_panic(u64::MAX);
+    assert_no_panic(7usize);
+    assert_no_panic(U256::from(5u64));
+}
+
+#[test]
+fn test_bitfields() {
+    let mut bits = Bitvector::<10>::default();
+    bits.set(9, true);
+    assert_no_panic(bits);
+    assert_no_panic(Bitlist::<20>::from_iter([true; 9]));
+    assert_no_panic(Bitlist::<20>::default());
+}
+
+#[test]
+fn test_collections() {
+    assert_no_panic(Vector::<u16, 3>::try_from(vec![1, 2, 3]).unwrap());
+    assert_no_panic(List::<u64, 8>::try_from(vec![1, 2]).unwrap());
+    assert_no_panic(variable().d);
+    assert_no_panic(
+        Vector::<List<u8, 4>, 2>::try_from(vec![List::default(), variable().d[2].clone()]).unwrap(),
+    );
+    assert_no_panic([variable().b, List::default()]);
+    assert_no_panic(Utf8List::<8>::try_from("héllo").unwrap());
+    assert_no_panic(Some(variable().b));
+}
+
+#[test]
+fn test_containers_and_unions() {
+    assert_no_panic(variable().e);
+    assert_no_panic(variable());
+    assert_no_panic(Choice::Number(9));
+    assert_no_panic(Choice::Data(variable().d));
+    assert_fields_no_panic(variable().e);
+    assert_fields_no_panic(variable());
+}
+
+#[test]
+fn test_lazy_lists() {
+    assert_lazy_no_panic(variable().b);
+    assert_lazy_no_panic(variable().d);
+}
@@ -2,7 +2,11 @@ use ssz_rs::prelude::*;
 use std::{convert::TryInto, fs::File, io::Read, path::PathBuf};
 
 pub fn root_from_hex(hex_str: &str) -> Node {
-    hex::decode(hex_str).expect("can read hex").as_slice().try_into().expect("can extract root")
+    ssz_rs::hex::decode(hex_str)
+        .expect("can read hex")
+        .as_slice()
+        .try_into()
+        .expect("can extract root")
 }
 
 pub fn serialize<T: SimpleSerialize>(value: &T) -> Vec<u8> {